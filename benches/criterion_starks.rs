@@ -7,8 +7,10 @@ use lambdaworks_stark::{
     air::{
         cairo_air::air::{CairoAIR, PublicInputs},
         context::ProofOptions,
+        example::poseidon::{poseidon_chain_trace, PoseidonAIR, PoseidonPublicInput},
     },
     cairo_run::{cairo_layout::CairoLayout, run::run_program},
+    fri::FieldElement,
     prover::prove,
 };
 
@@ -46,9 +48,10 @@ fn run_cairo_bench(group: &mut BenchmarkGroup<'_, WallTime>, benchname: &str, pr
         blowup_factor: 4,
         fri_number_of_queries: 5,
         coset_offset: 3,
+        validate_trace: false,
     };
 
-    let mut pub_inputs = PublicInputs::from_regs_and_mem(&register_states, &memory, program_size);
+    let pub_inputs = PublicInputs::from_regs_and_mem(&register_states, &memory, program_size);
 
     // This should be auto calculated
     // First pad is needed by the prover to validate the program bytecode
@@ -56,7 +59,7 @@ fn run_cairo_bench(group: &mut BenchmarkGroup<'_, WallTime>, benchname: &str, pr
     // Then we need to pad it to next power of two
     let padded_trace_length = (register_states.steps() + first_pad).next_power_of_two();
 
-    let cairo_air = CairoAIR::new(proof_options, padded_trace_length, register_states.steps());
+    let cairo_air = CairoAIR::new(proof_options, padded_trace_length);
 
     group.bench_function(benchname, |bench| {
         bench.iter(|| {
@@ -65,7 +68,7 @@ fn run_cairo_bench(group: &mut BenchmarkGroup<'_, WallTime>, benchname: &str, pr
                 prove(
                     &(register_states.clone(), memory.clone()),
                     &cairo_air,
-                    &mut pub_inputs,
+                    &pub_inputs,
                 )
                 .unwrap(),
             )
@@ -73,5 +76,38 @@ fn run_cairo_bench(group: &mut BenchmarkGroup<'_, WallTime>, benchname: &str, pr
     });
 }
 
-criterion_group!(benches, cairo_benches);
+fn poseidon_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("POSEIDON");
+    group.sample_size(10);
+    for permutations in [8, 32] {
+        run_poseidon_bench(&mut group, permutations);
+    }
+}
+
+fn run_poseidon_bench(group: &mut BenchmarkGroup<'_, WallTime>, permutations: usize) {
+    let proof_options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 5,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let trace_length = permutations * 128;
+    let air = PoseidonAIR::new(proof_options, trace_length).unwrap();
+    let input = [
+        FieldElement::from(1),
+        FieldElement::from(2),
+        FieldElement::from(3),
+    ];
+    let trace = poseidon_chain_trace(&input, trace_length, air.round_constants());
+    let public_input = PoseidonPublicInput {
+        input: input.clone(),
+        output: input,
+    };
+
+    group.bench_function(format!("chain/{permutations}"), |bench| {
+        bench.iter(|| black_box(prove(&trace, &air, &public_input).unwrap()));
+    });
+}
+
+criterion_group!(benches, cairo_benches, poseidon_benches);
 criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! The rounds of the prover, exposed for those who want to instrument, replace
+//! or skip some of them. `prover::prove` runs them in this order:
+//!
+//! 1. `round_1_commit_main_trace`, which starts with the preprocessed columns
+//!    of `commit_preprocessed_columns`, then `round_1_randomized_air_with_preprocessing`.
+//! 2. `batch_sample_challenges` for the boundary and transition coefficients,
+//!    `round_2_compute_composition_polynomial`, and the commitments [Hᵢ] to the
+//!    parts of the composition polynomial are appended to the transcript.
+//! 3. `sample_z_ood`, `round_3_evaluate_polynomials_in_out_of_domain_element`,
+//!    and Hᵢ(zᴺ) and the trace frame evaluations are appended to the
+//!    transcript.
+//! 4. `round_4_compute_and_run_fri_on_the_deep_composition_polynomial`.
+//!
+//! The verifier replays this exact sequence of transcript operations, so rounds
+//! run in a different way only produce verifying proofs if they keep it.
+
+pub use crate::prover::{
+    commit_preprocessed_columns, round_1_commit_main_trace,
+    round_1_randomized_air_with_preprocessing, round_2_compute_composition_polynomial,
+    round_3_evaluate_polynomials_in_out_of_domain_element,
+    round_4_compute_and_run_fri_on_the_deep_composition_polynomial, MainTraceCommitment,
+    ProverWorkspace, Round1, Round2, Round3, Round4,
+};
+pub use crate::{batch_sample_challenges, sample_z_ood, Domain};
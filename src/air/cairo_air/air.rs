@@ -1,15 +1,28 @@
+use std::collections::BTreeSet;
+
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
-use lambdaworks_math::field::{
-    element::FieldElement,
-    fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
-    traits::{IsFFTField, IsPrimeField},
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+        traits::{IsFFTField, IsPrimeField},
+    },
+    traits::ByteConversion,
 };
 
 use crate::{
     air::{
+        cairo_air::{
+            ec_op::{self, EC_OP_ROWS},
+            keccak::{self, KECCAK_ROWS},
+            layout::{self, Builtin, Layout},
+            pedersen::{self, PEDERSEN_ROWS},
+            poseidon::{self, POSEIDON_ROWS},
+        },
         constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
         context::{AirContext, ProofOptions},
         frame::Frame,
+        gadgets::public_memory::{public_cells, public_product, MemoryPage},
         trace::TraceTable,
         traits::AIR,
     },
@@ -18,7 +31,7 @@ use crate::{
         execution_trace::build_cairo_execution_trace,
     },
     prover::ProvingError,
-    transcript_to_field, FE,
+    transcript_to_field, IsChallengeField, FE,
 };
 
 /// Main constraint identifiers
@@ -43,24 +56,134 @@ const MEMORY_INCREASING_0: usize = 31;
 const MEMORY_INCREASING_1: usize = 32;
 const MEMORY_INCREASING_2: usize = 33;
 const MEMORY_INCREASING_3: usize = 34;
-
-const MEMORY_CONSISTENCY_0: usize = 35;
-const MEMORY_CONSISTENCY_1: usize = 36;
-const MEMORY_CONSISTENCY_2: usize = 37;
-const MEMORY_CONSISTENCY_3: usize = 38;
-
-const PERMUTATION_ARGUMENT_0: usize = 39;
-const PERMUTATION_ARGUMENT_1: usize = 40;
-const PERMUTATION_ARGUMENT_2: usize = 41;
-const PERMUTATION_ARGUMENT_3: usize = 42;
-
-const RANGE_CHECK_INCREASING_0: usize = 43;
-const RANGE_CHECK_INCREASING_1: usize = 44;
-const RANGE_CHECK_INCREASING_2: usize = 45;
-
-const RANGE_CHECK_0: usize = 46;
-const RANGE_CHECK_1: usize = 47;
-const RANGE_CHECK_2: usize = 48;
+const MEMORY_INCREASING_4: usize = 35;
+const MEMORY_INCREASING_5: usize = 36;
+const MEMORY_INCREASING_6: usize = 37;
+const MEMORY_INCREASING_7: usize = 38;
+const MEMORY_INCREASING_8: usize = 39;
+
+const MEMORY_CONSISTENCY_0: usize = 40;
+const MEMORY_CONSISTENCY_1: usize = 41;
+const MEMORY_CONSISTENCY_2: usize = 42;
+const MEMORY_CONSISTENCY_3: usize = 43;
+const MEMORY_CONSISTENCY_4: usize = 44;
+const MEMORY_CONSISTENCY_5: usize = 45;
+const MEMORY_CONSISTENCY_6: usize = 46;
+const MEMORY_CONSISTENCY_7: usize = 47;
+const MEMORY_CONSISTENCY_8: usize = 48;
+
+const PERMUTATION_ARGUMENT_0: usize = 49;
+const PERMUTATION_ARGUMENT_1: usize = 50;
+const PERMUTATION_ARGUMENT_2: usize = 51;
+const PERMUTATION_ARGUMENT_3: usize = 52;
+const PERMUTATION_ARGUMENT_4: usize = 53;
+const PERMUTATION_ARGUMENT_5: usize = 54;
+const PERMUTATION_ARGUMENT_6: usize = 55;
+const PERMUTATION_ARGUMENT_7: usize = 56;
+const PERMUTATION_ARGUMENT_8: usize = 57;
+
+// One constraint per column of the range check pool, from these indices.
+const RANGE_CHECK_INCREASING: usize = 58;
+const RANGE_CHECK: usize = 69;
+
+const SELECTOR_BIT: usize = 80;
+const SELECTOR_DECREASING: usize = 81;
+
+const RC_BUILTIN_SELECTOR_BIT: usize = 82;
+const RC_BUILTIN_SELECTOR_DECREASING: usize = 83;
+const RC_BUILTIN_ADDR_INCREASING: usize = 84;
+const RC_BUILTIN_DECOMPOSITION: usize = 85;
+const RC_BUILTIN_UNUSED_ADDR: usize = 86;
+const RC_BUILTIN_UNUSED_VALUE: usize = 87;
+
+const PEDERSEN_SUFFIX_SHIFT: usize = 88;
+const PEDERSEN_SUFFIX_END: usize = 89;
+const PEDERSEN_BIT_BINARY: usize = 90;
+const PEDERSEN_BIT_END: usize = 91;
+const PEDERSEN_UNPACKING_251: usize = 92;
+const PEDERSEN_UNPACKING_196: usize = 93;
+const PEDERSEN_UNPACKING_192: usize = 94;
+// One constraint per Pedersen unpacking column, from this index.
+const PEDERSEN_UNPACKING_COPY: usize = 95;
+const PEDERSEN_ZEROES_197: usize = 98;
+const PEDERSEN_ZEROES_193: usize = 99;
+const PEDERSEN_MAX_INPUT: usize = 100;
+const PEDERSEN_SLOPE_DEFINITION: usize = 101;
+const PEDERSEN_ADD_X: usize = 102;
+const PEDERSEN_ADD_Y: usize = 103;
+const PEDERSEN_COPY_X: usize = 104;
+const PEDERSEN_COPY_Y: usize = 105;
+const PEDERSEN_START_X: usize = 106;
+const PEDERSEN_START_Y: usize = 107;
+const PEDERSEN_SELECTOR_BIT: usize = 108;
+const PEDERSEN_SELECTOR_CONSTANT: usize = 109;
+const PEDERSEN_SELECTOR_DECREASING: usize = 110;
+const PEDERSEN_BASE_INCREASING: usize = 111;
+const PEDERSEN_ADDR_DEFINITION: usize = 112;
+const PEDERSEN_VALUE_DEFINITION: usize = 113;
+
+// One constraint per element of the state, from these indices.
+const POSEIDON_ROUND: usize = 114;
+const POSEIDON_CUBE: usize = 117;
+const POSEIDON_CARRY_SECOND: usize = 120;
+const POSEIDON_CARRY_THIRD: usize = 121;
+const POSEIDON_CARRY_THIRD_AGAIN: usize = 122;
+const POSEIDON_SELECTOR_BIT: usize = 123;
+const POSEIDON_SELECTOR_CONSTANT: usize = 124;
+const POSEIDON_SELECTOR_DECREASING: usize = 125;
+const POSEIDON_BASE_INCREASING: usize = 126;
+const POSEIDON_ADDR_DEFINITION: usize = 127;
+const POSEIDON_VALUE_DEFINITION: usize = 128;
+
+const KECCAK_SELECTOR_BIT: usize = 129;
+const KECCAK_SELECTOR_CONSTANT: usize = 130;
+const KECCAK_SELECTOR_DECREASING: usize = 131;
+const KECCAK_BASE_INCREASING: usize = 132;
+const KECCAK_ADDR_DEFINITION: usize = 133;
+const KECCAK_VALUE_DEFINITION: usize = 134;
+// One constraint per Keccak cell but the last one, from this index.
+const KECCAK_SHIFT: usize = 135;
+
+const EC_OP_SUFFIX_SHIFT: usize = 142;
+const EC_OP_SUFFIX_END: usize = 143;
+const EC_OP_BIT_BINARY: usize = 144;
+const EC_OP_BIT_END: usize = 145;
+const EC_OP_UNPACKING_251: usize = 146;
+const EC_OP_UNPACKING_196: usize = 147;
+const EC_OP_UNPACKING_192: usize = 148;
+// One constraint per EC op unpacking column, from this index.
+const EC_OP_UNPACKING_COPY: usize = 149;
+const EC_OP_ZEROES_197: usize = 152;
+const EC_OP_ZEROES_193: usize = 153;
+const EC_OP_MAX_INPUT: usize = 154;
+const EC_OP_DOUBLING_SLOPE_DEFINITION: usize = 155;
+const EC_OP_DOUBLING_X: usize = 156;
+const EC_OP_DOUBLING_Y: usize = 157;
+const EC_OP_SLOPE_DEFINITION: usize = 158;
+const EC_OP_X_DIFF_INVERSE: usize = 159;
+const EC_OP_ADD_X: usize = 160;
+const EC_OP_ADD_Y: usize = 161;
+const EC_OP_COPY_X: usize = 162;
+const EC_OP_COPY_Y: usize = 163;
+const EC_OP_SELECTOR_BIT: usize = 164;
+const EC_OP_SELECTOR_CONSTANT: usize = 165;
+const EC_OP_SELECTOR_DECREASING: usize = 166;
+const EC_OP_BASE_INCREASING: usize = 167;
+const EC_OP_ADDR_DEFINITION: usize = 168;
+const EC_OP_VALUE_DEFINITION: usize = 169;
+// One constraint per EC op input cell, from this index, and one per cell but
+// the last one from the next.
+const EC_OP_INPUT: usize = 170;
+const EC_OP_SHIFT: usize = 175;
+
+const RC96_BUILTIN_SELECTOR_BIT: usize = 179;
+const RC96_BUILTIN_SELECTOR_INCREASING: usize = 180;
+// One constraint per part of the range check builtin above 96 bits, from
+// this index.
+const RC96_BUILTIN_TOP_PARTS: usize = 181;
+/// Constraints of every layout. The ones of the bits of the Keccak builtin
+/// follow them in the layouts with it.
+const LAYOUT_CONSTRAINTS: usize = 183;
 
 // Frame row identifiers
 //  - Flags
@@ -101,30 +224,126 @@ pub const FRAME_T1: usize = 31;
 pub const FRAME_MUL: usize = 32;
 pub const FRAME_SELECTOR: usize = 33;
 
-// Auxiliary range check columns
-pub const RANGE_CHECK_COL_1: usize = 34;
-pub const RANGE_CHECK_COL_2: usize = 35;
-pub const RANGE_CHECK_COL_3: usize = 36;
+// Range check builtin columns
+pub const RC_BUILTIN_SELECTOR: usize = 34;
+pub const RC_BUILTIN_ADDR: usize = 35;
+pub const RC_BUILTIN_VALUE: usize = 36;
+/// 16 bit parts of the value, from the least significant one.
+pub const RC_BUILTIN_PARTS: [usize; 8] = [37, 38, 39, 40, 41, 42, 43, 44];
+
+/// Cells checked to be in the range `[range_check_min, range_check_max]`.
+pub const RANGE_CHECK_POOL: [usize; 11] =
+    [OFF_DST, OFF_OP0, OFF_OP1, 37, 38, 39, 40, 41, 42, 43, 44];
+
+// Auxiliary range check columns, with the cells of the pool sorted
+// Pedersen builtin columns
+pub const PEDERSEN_SUFFIX: usize = 45;
+pub const PEDERSEN_BIT: usize = 46;
+/// Products of the bits 251, 196 and 192 of the input of each half.
+pub const PEDERSEN_UNPACKING: [usize; 3] = [47, 48, 49];
+pub const PEDERSEN_X: usize = 50;
+pub const PEDERSEN_Y: usize = 51;
+pub const PEDERSEN_SLOPE: usize = 52;
+pub const PEDERSEN_SELECTOR: usize = 53;
+/// Address of the first cell of the instance.
+pub const PEDERSEN_BASE: usize = 54;
+pub const PEDERSEN_ADDR: usize = 55;
+pub const PEDERSEN_VALUE: usize = 56;
+
+// Poseidon builtin columns
+pub const POSEIDON_STATE: [usize; 3] = [57, 58, 59];
+/// Cubes of the elements of the state plus the constants of the round.
+pub const POSEIDON_CUBES: [usize; 3] = [60, 61, 62];
+/// The second and third element of the input and the output, carried to the
+/// rows where they're in the memory.
+pub const POSEIDON_CARRIES: [usize; 2] = [63, 64];
+pub const POSEIDON_SELECTOR: usize = 65;
+/// Address of the first cell of the instance.
+pub const POSEIDON_BASE: usize = 66;
+pub const POSEIDON_ADDR: usize = 67;
+pub const POSEIDON_VALUE: usize = 68;
+
+// Keccak builtin columns
+/// Cells of the instance, shifted down to the rows where the first one is in
+/// the memory. They hold the input on the first row and the output on the row
+/// after the last round.
+pub const KECCAK_CELLS: [usize; 8] = [69, 70, 71, 72, 73, 74, 75, 76];
+pub const KECCAK_SELECTOR: usize = 77;
+/// Address of the first cell of the instance.
+pub const KECCAK_BASE: usize = 78;
+pub const KECCAK_ADDR: usize = 79;
+pub const KECCAK_VALUE: usize = 80;
+
+// EC op builtin columns
+pub const EC_OP_SUFFIX: usize = 81;
+pub const EC_OP_BIT: usize = 82;
+/// Products of the bits 251, 196 and 192 of `m`.
+pub const EC_OP_UNPACKING: [usize; 3] = [83, 84, 85];
+/// `Q` doubled once per row, and the slope of the tangent at it.
+pub const EC_OP_Q_X: usize = 86;
+pub const EC_OP_Q_Y: usize = 87;
+pub const EC_OP_DOUBLING_SLOPE: usize = 88;
+/// Partial sum, which starts at `P`.
+pub const EC_OP_X: usize = 89;
+pub const EC_OP_Y: usize = 90;
+pub const EC_OP_SLOPE: usize = 91;
+/// Inverse of the difference of the x coordinates of the points added on the
+/// row.
+pub const EC_OP_X_DIFF_INV: usize = 92;
+/// Input cells of the instance, shifted down to the rows where the first one
+/// is in the memory.
+pub const EC_OP_CELLS: [usize; 5] = [93, 94, 95, 96, 97];
+pub const EC_OP_SELECTOR: usize = 98;
+/// Address of the first cell of the instance.
+pub const EC_OP_BASE: usize = 99;
+pub const EC_OP_ADDR: usize = 100;
+pub const EC_OP_VALUE: usize = 101;
+
+// Range check 96 builtin columns
+/// One from the first row of the cells of the range check 96 builtin, which
+/// follow the ones of the range check builtin in its columns.
+pub const RC96_BUILTIN_SELECTOR: usize = 102;
+/// Main columns of every layout. The layouts with the Keccak builtin have
+/// the `keccak::COLUMNS` bits of its instances after them, before the
+/// auxiliary columns, which are numbered here as if they weren't there.
+pub const MAIN_COLUMNS: usize = 103;
+
+// Auxiliary range check columns, with the cells of the pool sorted
+pub const RANGE_CHECK_SORTED: [usize; 11] = [103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113];
 
 // Auxiliary memory columns
-pub const MEMORY_ADDR_SORTED_0: usize = 37;
-pub const MEMORY_ADDR_SORTED_1: usize = 38;
-pub const MEMORY_ADDR_SORTED_2: usize = 39;
-pub const MEMORY_ADDR_SORTED_3: usize = 40;
-
-pub const MEMORY_VALUES_SORTED_0: usize = 41;
-pub const MEMORY_VALUES_SORTED_1: usize = 42;
-pub const MEMORY_VALUES_SORTED_2: usize = 43;
-pub const MEMORY_VALUES_SORTED_3: usize = 44;
-
-pub const PERMUTATION_ARGUMENT_COL_0: usize = 45;
-pub const PERMUTATION_ARGUMENT_COL_1: usize = 46;
-pub const PERMUTATION_ARGUMENT_COL_2: usize = 47;
-pub const PERMUTATION_ARGUMENT_COL_3: usize = 48;
-
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1: usize = 49;
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_2: usize = 50;
-pub const PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3: usize = 51;
+pub const MEMORY_ADDR_SORTED_0: usize = 114;
+pub const MEMORY_ADDR_SORTED_1: usize = 115;
+pub const MEMORY_ADDR_SORTED_2: usize = 116;
+pub const MEMORY_ADDR_SORTED_3: usize = 117;
+pub const MEMORY_ADDR_SORTED_4: usize = 118;
+pub const MEMORY_ADDR_SORTED_5: usize = 119;
+pub const MEMORY_ADDR_SORTED_6: usize = 120;
+pub const MEMORY_ADDR_SORTED_7: usize = 121;
+pub const MEMORY_ADDR_SORTED_8: usize = 122;
+
+pub const MEMORY_VALUES_SORTED_0: usize = 123;
+pub const MEMORY_VALUES_SORTED_1: usize = 124;
+pub const MEMORY_VALUES_SORTED_2: usize = 125;
+pub const MEMORY_VALUES_SORTED_3: usize = 126;
+pub const MEMORY_VALUES_SORTED_4: usize = 127;
+pub const MEMORY_VALUES_SORTED_5: usize = 128;
+pub const MEMORY_VALUES_SORTED_6: usize = 129;
+pub const MEMORY_VALUES_SORTED_7: usize = 130;
+pub const MEMORY_VALUES_SORTED_8: usize = 131;
+
+pub const PERMUTATION_ARGUMENT_COL_0: usize = 132;
+pub const PERMUTATION_ARGUMENT_COL_1: usize = 133;
+pub const PERMUTATION_ARGUMENT_COL_2: usize = 134;
+pub const PERMUTATION_ARGUMENT_COL_3: usize = 135;
+pub const PERMUTATION_ARGUMENT_COL_4: usize = 136;
+pub const PERMUTATION_ARGUMENT_COL_5: usize = 137;
+pub const PERMUTATION_ARGUMENT_COL_6: usize = 138;
+pub const PERMUTATION_ARGUMENT_COL_7: usize = 139;
+pub const PERMUTATION_ARGUMENT_COL_8: usize = 140;
+
+pub const PERMUTATION_ARGUMENT_RANGE_CHECK: [usize; 11] =
+    [141, 142, 143, 144, 145, 146, 147, 148, 149, 150, 151];
 
 pub const MEMORY_COLUMNS: [usize; 8] = [
     FRAME_PC,
@@ -137,27 +356,123 @@ pub const MEMORY_COLUMNS: [usize; 8] = [
     FRAME_OP1,
 ];
 
+/// Memory accesses of a row, as addresses and values.
+pub const MEMORY_ADDRESSES: [usize; 9] = [
+    FRAME_PC,
+    FRAME_DST_ADDR,
+    FRAME_OP0_ADDR,
+    FRAME_OP1_ADDR,
+    RC_BUILTIN_ADDR,
+    PEDERSEN_ADDR,
+    POSEIDON_ADDR,
+    KECCAK_ADDR,
+    EC_OP_ADDR,
+];
+pub const MEMORY_VALUES: [usize; 9] = [
+    FRAME_INST,
+    FRAME_DST,
+    FRAME_OP0,
+    FRAME_OP1,
+    RC_BUILTIN_VALUE,
+    PEDERSEN_VALUE,
+    POSEIDON_VALUE,
+    KECCAK_VALUE,
+    EC_OP_VALUE,
+];
+
+/// Accesses of the CPU in a row, the first ones of `MEMORY_ADDRESSES`. The
+/// rows after the execution have them free for the memory holes.
+const HOLES_PER_ROW: usize = 4;
+
 // Trace layout
 pub const MEM_P_TRACE_OFFSET: usize = 17;
 pub const MEM_A_TRACE_OFFSET: usize = 19;
 
+/// Addresses of a memory segment, from `begin_addr` up to `stop_ptr`, which
+/// isn't part of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemorySegment {
+    pub begin_addr: u64,
+    pub stop_ptr: u64,
+}
+
+impl MemorySegment {
+    pub fn len(&self) -> usize {
+        self.stop_ptr.saturating_sub(self.begin_addr) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 // TODO: For memory constraints and builtins, the commented fields may be useful.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PublicInputs {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub pc_init: FE,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub ap_init: FE,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub fp_init: FE,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub pc_final: FE,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub ap_final: FE,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
+    pub fp_final: FE,
     // These are Option because they're not known until
     // the trace is obtained. They represent the minimum
-    // and maximum offsets used during program execution.
+    // and maximum offsets and parts of range checked
+    // builtin cells used during program execution.
     // TODO: A possible refactor is moving them to the proof.
-    // minimum range check value (0 < range_check_min < range_check_max < 2^16)
+    // minimum range check value (range_check_min <= range_check_max < 2^16)
     pub range_check_min: Option<u16>,
     // maximum range check value
     pub range_check_max: Option<u16>,
+    /// Number of memory holes: the addresses below the largest accessed one
+    /// that the execution doesn't access. The prover fills each with a dummy
+    /// access, and sets the number it found in the trace. Before that, it's
+    /// the number of unassigned cells of the memory, the room the AIR leaves.
+    pub memory_holes: Option<usize>,
+    /// Segment of the program, from the initial to the final pc.
+    pub program_segment: Option<MemorySegment>,
+    /// Segment of the execution, from the initial to the final ap.
+    pub execution_segment: Option<MemorySegment>,
+    /// Segment of the range check builtin, if the layout has it.
+    pub range_check_builtin: Option<MemorySegment>,
+    /// Segment of the range check 96 builtin, if the layout has it, whose
+    /// cells are less than 2^96.
+    pub range_check96_builtin: Option<MemorySegment>,
+    /// Segment of the Pedersen builtin, if the layout has it, with the inputs
+    /// and the hash of each instance in consecutive cells.
+    pub pedersen_builtin: Option<MemorySegment>,
+    /// Segment of the Poseidon builtin, if the layout has it, with the inputs
+    /// and the outputs of each instance in consecutive cells.
+    pub poseidon_builtin: Option<MemorySegment>,
+    /// Segment of the Keccak builtin, if the layout has it, with the eight
+    /// inputs and the eight outputs of each instance in consecutive cells.
+    pub keccak_builtin: Option<MemorySegment>,
+    /// Segment of the EC op builtin, if the layout has it, with `P`, `Q`, `m`
+    /// and `R = P + m·Q` of each instance in consecutive cells.
+    pub ec_op_builtin: Option<MemorySegment>,
+    /// Segment of the output builtin, if the layout has it, with the output of
+    /// the program. Its cells have to be a page of `public_memory`.
+    pub output_builtin: Option<MemorySegment>,
+    /// Builtins of the dynamic layout, each with its ratio: the steps per
+    /// instance the program chose. Only executions with the dynamic layout
+    /// have them.
+    pub builtin_ratios: Option<Vec<(Builtin, usize)>>,
+    /// Pages of public cells besides the program, such as the input and the
+    /// output of the program, which the proof binds to their values.
+    pub public_memory: Vec<MemoryPage<Stark252PrimeField>>,
     // pub builtins: Vec<Builtin>, // list of builtins
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
     pub program: Vec<FE>,
     pub num_steps: usize, // number of execution steps
 }
@@ -166,55 +481,443 @@ impl PublicInputs {
     /// Creates a Public Input from register states and memory
     /// - In the future we should use the output of the Cairo Runner. This is not currently supported in Cairo RS
     /// - RangeChecks are not filled, and the prover mutates them inside the prove function. This works but also should be loaded from the Cairo RS output
+    ///
+    /// Panics if the trace is empty or a cell of the program is missing, see
+    /// `try_from_regs_and_mem`.
     pub fn from_regs_and_mem(
         register_states: &CairoTrace,
         memory: &CairoMemory,
         program_size: usize,
     ) -> Self {
-        let mut program = vec![];
-
-        for i in 1..=program_size as u64 {
-            program.push(memory.get(&i).unwrap().clone());
-        }
-
-        let last_step = &register_states.rows[register_states.steps() - 1];
+        Self::try_from_regs_and_mem(register_states, memory, program_size)
+            .unwrap_or_else(|error| panic!("{error:?}"))
+    }
 
-        PublicInputs {
-            pc_init: FE::from(register_states.rows[0].pc),
-            ap_init: FE::from(register_states.rows[0].ap),
-            fp_init: FE::from(register_states.rows[0].fp),
+    /// Same as `from_regs_and_mem`, for register states and memory that may
+    /// be malformed, such as files given by a user. It's an error if the
+    /// trace is empty or a cell of the program is missing.
+    pub fn try_from_regs_and_mem(
+        register_states: &CairoTrace,
+        memory: &CairoMemory,
+        program_size: usize,
+    ) -> Result<Self, ProvingError> {
+        let (first_step, last_step) =
+            match (register_states.rows.first(), register_states.rows.last()) {
+                (Some(first_step), Some(last_step)) => (first_step, last_step),
+                _ => {
+                    return Err(ProvingError::WrongParameter(
+                        "The trace has no steps.".to_string(),
+                    ))
+                }
+            };
+        let program = (1..=program_size as u64)
+            .map(|address| {
+                memory.get(&address).cloned().ok_or_else(|| {
+                    ProvingError::WrongParameter(format!(
+                        "The memory has no cell at the address {address} of the program."
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PublicInputs {
+            pc_init: FE::from(first_step.pc),
+            ap_init: FE::from(first_step.ap),
+            fp_init: FE::from(first_step.fp),
             pc_final: FieldElement::from(last_step.pc),
             ap_final: FieldElement::from(last_step.ap),
+            fp_final: FieldElement::from(last_step.fp),
             range_check_min: None,
             range_check_max: None,
+            memory_holes: Some(memory.holes().len()),
+            program_segment: Some(MemorySegment {
+                begin_addr: first_step.pc,
+                stop_ptr: last_step.pc,
+            }),
+            execution_segment: Some(MemorySegment {
+                begin_addr: first_step.ap,
+                stop_ptr: last_step.ap,
+            }),
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
             program,
             num_steps: register_states.steps(),
+        })
+    }
+
+    /// Pages of the public memory: the program, from the address 1, followed
+    /// by the pages of `public_memory`.
+    pub fn public_pages(&self) -> Vec<MemoryPage<Stark252PrimeField>> {
+        let mut pages = vec![MemoryPage::new(FE::one(), self.program.clone())];
+        pages.extend(self.public_memory.iter().cloned());
+        pages
+    }
+
+    /// Hash of the cells of `public_pages`, in order. Segments of an
+    /// execution have to share their public pages, so the proof of each one
+    /// binds its memory to the same public cells.
+    pub fn memory_commitment(&self) -> FE {
+        let hash = <Stark252PrimeField as IsChallengeField<Stark252PrimeField>>::hash_two;
+        self.public_pages()
+            .iter()
+            .flat_map(MemoryPage::cells)
+            .fold(FE::zero(), |commitment, (address, value)| {
+                hash(commitment, hash(address, value.clone()))
+            })
+    }
+
+    /// Rows at the end of the trace with the dummy memory accesses that the
+    /// public cells replace in the memory permutation, at least four per row.
+    pub fn public_memory_rows(&self) -> usize {
+        (public_cells(&self.public_pages()) >> 2) + 1
+    }
+
+    /// Builtins with a segment in the public input, each with the instances
+    /// in it. The instances of the output builtin are its cells.
+    pub fn builtin_instances(&self) -> Vec<(Builtin, usize)> {
+        [
+            (
+                Builtin::RangeCheck,
+                &self.range_check_builtin,
+                range_check_builtin_instances(self),
+            ),
+            (
+                Builtin::RangeCheck96,
+                &self.range_check96_builtin,
+                range_check96_builtin_instances(self),
+            ),
+            (
+                Builtin::Pedersen,
+                &self.pedersen_builtin,
+                pedersen_builtin_instances(self),
+            ),
+            (
+                Builtin::Poseidon,
+                &self.poseidon_builtin,
+                poseidon_builtin_instances(self),
+            ),
+            (
+                Builtin::Keccak,
+                &self.keccak_builtin,
+                keccak_builtin_instances(self),
+            ),
+            (
+                Builtin::EcOp,
+                &self.ec_op_builtin,
+                ec_op_builtin_instances(self),
+            ),
+            (
+                Builtin::Output,
+                &self.output_builtin,
+                self.output_builtin.as_ref().map_or(0, MemorySegment::len),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, segment, _)| segment.is_some())
+        .map(|(builtin, _, instances)| (builtin, instances))
+        .collect()
+    }
+
+    /// Whether the execution uses `builtin`: it has a segment with an instance
+    /// of it at least. The layouts have room for builtins their executions
+    /// don't use, which are left with unused instances.
+    pub fn uses_builtin(&self, builtin: Builtin) -> bool {
+        self.builtin_instances()
+            .iter()
+            .any(|(other, instances)| *other == builtin && *instances > 0)
+    }
+
+    /// Ratios of a dynamic layout for the builtins of the execution: the
+    /// largest ones with room for their instances. The output builtin has a
+    /// ratio of one, as in the other layouts.
+    pub fn dynamic_ratios(&self) -> Vec<(Builtin, usize)> {
+        self.builtin_instances()
+            .into_iter()
+            .map(|(builtin, instances)| {
+                let ratio = match builtin {
+                    Builtin::Output => 1,
+                    _ => (self.num_steps / instances.max(1)).max(1),
+                };
+                (builtin, ratio)
+            })
+            .collect()
+    }
+
+    /// Output of the program: the values of the page of the output segment,
+    /// which is empty without the output builtin. It's an error if the cells
+    /// of the segment aren't a public page.
+    pub fn public_output(&self) -> Result<CairoPublicOutput, ProvingError> {
+        let segment = match &self.output_builtin {
+            Some(segment) if !segment.is_empty() => segment,
+            _ => return Ok(CairoPublicOutput::default()),
+        };
+        self.public_memory
+            .iter()
+            .find(|page| {
+                page.address == FE::from(segment.begin_addr) && page.values.len() == segment.len()
+            })
+            .map(|page| CairoPublicOutput {
+                values: page.values.clone(),
+            })
+            .ok_or_else(|| {
+                ProvingError::WrongParameter(format!(
+                    "The output segment from {} is not a page of the public memory.",
+                    segment.begin_addr
+                ))
+            })
+    }
+
+    /// Bounds of the range checked values, which the prover sets from the
+    /// trace and the boundary constraints bind to the first and last sorted
+    /// values. It's an error if they're missing or in the wrong order.
+    pub fn range_check_bounds(&self) -> Result<(u16, u16), ProvingError> {
+        match (self.range_check_min, self.range_check_max) {
+            (Some(min), Some(max)) if min <= max => Ok((min, max)),
+            (Some(min), Some(max)) => Err(ProvingError::WrongParameter(format!(
+                "The range check minimum {min} is above the maximum {max}."
+            ))),
+            _ => Err(ProvingError::WrongParameter(
+                "The public input has no range check bounds.".to_string(),
+            )),
+        }
+    }
+
+    /// Segments of the memory by name: the program, the execution and the
+    /// builtins, in the order of the canonical encoding.
+    pub fn segments(&self) -> [(&'static str, &Option<MemorySegment>); 9] {
+        [
+            ("program", &self.program_segment),
+            ("execution", &self.execution_segment),
+            ("range_check", &self.range_check_builtin),
+            ("range_check96", &self.range_check96_builtin),
+            ("pedersen", &self.pedersen_builtin),
+            ("poseidon", &self.poseidon_builtin),
+            ("keccak", &self.keccak_builtin),
+            ("ec_op", &self.ec_op_builtin),
+            ("output", &self.output_builtin),
+        ]
+    }
+
+    /// Checks the segments against the registers, as the Cairo verifier
+    /// does: the program runs from `pc_init` to `pc_final`, and the execution
+    /// from `ap_init`, which is also `fp_init`, to `ap_final`. The boundary
+    /// constraints bind the registers to the trace, and through them the
+    /// segments. It's also an error if a segment stops before it begins.
+    pub fn check_segments(&self) -> Result<(), ProvingError> {
+        for (name, segment) in self.segments() {
+            if let Some(segment) = segment {
+                if segment.stop_ptr < segment.begin_addr {
+                    return Err(ProvingError::WrongParameter(format!(
+                        "The {name} segment stops at {}, before its beginning at {}.",
+                        segment.stop_ptr, segment.begin_addr
+                    )));
+                }
+            }
+        }
+        if let Some(program) = &self.program_segment {
+            if FE::from(program.begin_addr) != self.pc_init
+                || FE::from(program.stop_ptr) != self.pc_final
+            {
+                return Err(ProvingError::WrongParameter(
+                    "The program segment doesn't match the initial and final pc.".to_string(),
+                ));
+            }
+        }
+        if let Some(execution) = &self.execution_segment {
+            if FE::from(execution.begin_addr) != self.ap_init
+                || FE::from(execution.begin_addr) != self.fp_init
+                || FE::from(execution.stop_ptr) != self.ap_final
+            {
+                return Err(ProvingError::WrongParameter(
+                    "The execution segment doesn't match the initial and final ap and fp."
+                        .to_string(),
+                ));
+            }
         }
+        Ok(())
+    }
+
+    /// Checks the claims of the public input that the constraints take for
+    /// granted, as the Cairo verifier does before it reads a proof: the
+    /// segments and the range check bounds, that the program holds the
+    /// initial and final pc, that ap doesn't go back, that the builtin
+    /// segments are after the program without overlapping the execution
+    /// segment or each other, and that the output is public.
+    pub fn validate(&self) -> Result<(), ProvingError> {
+        self.check_segments()?;
+        self.range_check_bounds()?;
+
+        let program_end = self.program.len() as u64;
+        for (name, pc) in [("initial", &self.pc_init), ("final", &self.pc_final)] {
+            if !address(pc).map_or(false, |pc| (1..=program_end).contains(&pc)) {
+                return Err(ProvingError::WrongParameter(format!(
+                    "The {name} pc is not an address of the program."
+                )));
+            }
+        }
+        let (ap_init, ap_final) = match (address(&self.ap_init), address(&self.ap_final)) {
+            (Some(ap_init), Some(ap_final)) if ap_init <= ap_final => (ap_init, ap_final),
+            _ => {
+                return Err(ProvingError::WrongParameter(
+                    "The final ap is not an address after the initial ap.".to_string(),
+                ))
+            }
+        };
+
+        let mut builtins: Vec<_> = self
+            .segments()
+            .into_iter()
+            .filter(|(name, _)| !["program", "execution"].contains(name))
+            .filter_map(|(name, segment)| segment.as_ref().map(|segment| (name, segment)))
+            .collect();
+        builtins.sort_by_key(|(_, segment)| segment.begin_addr);
+        if let Some((name, _)) = builtins
+            .first()
+            .filter(|(_, segment)| segment.begin_addr <= program_end)
+        {
+            return Err(ProvingError::WrongParameter(format!(
+                "The {name} segment overlaps the program."
+            )));
+        }
+        if let Some((name, _)) = builtins
+            .iter()
+            .find(|(_, segment)| segment.begin_addr < ap_final && segment.stop_ptr > ap_init)
+        {
+            return Err(ProvingError::WrongParameter(format!(
+                "The {name} segment overlaps the execution segment."
+            )));
+        }
+        for pair in builtins.windows(2) {
+            let ((name, segment), (next_name, next)) = (pair[0], pair[1]);
+            if segment.stop_ptr > next.begin_addr {
+                return Err(ProvingError::WrongParameter(format!(
+                    "The {name} and {next_name} segments overlap."
+                )));
+            }
+        }
+
+        self.public_output().map(|_| ())
+    }
+
+    /// Hash of the program, as in Cairo: the Pedersen hash chain of its
+    /// length followed by its bytecode, `H(n, H(p₀, ... H(pₙ₋₂, pₙ₋₁)))`.
+    pub fn program_hash(&self) -> FE {
+        let length = FE::from(self.program.len() as u64);
+        match self.program.split_last() {
+            Some((last, rest)) => core::iter::once(&length)
+                .chain(rest)
+                .rev()
+                .fold(last.clone(), |hash, value| pedersen::hash(value, &hash)),
+            None => length,
+        }
+    }
+
+    /// Canonical encoding of the public input, with the program given by its
+    /// hash, which binds proofs to the statement.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for register in [
+            &self.pc_init,
+            &self.ap_init,
+            &self.fp_init,
+            &self.pc_final,
+            &self.ap_final,
+            &self.fp_final,
+        ] {
+            bytes.extend(register.to_bytes_be());
+        }
+        for bound in [self.range_check_min, self.range_check_max] {
+            bytes.push(bound.is_some() as u8);
+            bytes.extend(bound.unwrap_or(0).to_be_bytes());
+        }
+        bytes.push(self.memory_holes.is_some() as u8);
+        bytes.extend((self.memory_holes.unwrap_or(0) as u64).to_be_bytes());
+        for (_, segment) in self.segments() {
+            bytes.push(segment.is_some() as u8);
+            if let Some(segment) = segment {
+                bytes.extend(segment.begin_addr.to_be_bytes());
+                bytes.extend(segment.stop_ptr.to_be_bytes());
+            }
+        }
+        bytes.push(self.builtin_ratios.is_some() as u8);
+        if let Some(ratios) = &self.builtin_ratios {
+            bytes.extend((ratios.len() as u64).to_be_bytes());
+            for (builtin, ratio) in ratios {
+                bytes.push(builtin.id());
+                bytes.extend((*ratio as u64).to_be_bytes());
+            }
+        }
+        bytes.extend(self.program_hash().to_bytes_be());
+        bytes.extend((self.public_memory.len() as u64).to_be_bytes());
+        for page in self.public_memory.iter() {
+            bytes.extend(page.address.to_bytes_be());
+            bytes.extend((page.values.len() as u64).to_be_bytes());
+            for value in page.values.iter() {
+                bytes.extend(value.to_bytes_be());
+            }
+        }
+        bytes.extend((self.num_steps as u64).to_be_bytes());
+        bytes
     }
 }
+/// Output of a Cairo program, the values it wrote to the output segment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CairoPublicOutput {
+    pub values: Vec<FE>,
+}
+
 #[derive(Clone)]
 pub struct CairoAIR {
     pub context: AirContext,
-    pub number_steps: usize,
+    /// Whether the layout has the Keccak builtin, whose bits add columns and
+    /// constraints to the trace.
+    pub keccak_builtin: bool,
+    /// Layout of the executions the AIR proves, which limits the builtins
+    /// they use. Without one, the AIR proves executions with any builtin it
+    /// has columns for.
+    pub layout: Option<Layout>,
 }
 
 impl CairoAIR {
     /// Creates a new CairoAIR from proof_options
     /// full_trace_length: Padding to 2^n
-    /// number_steps: Number of steps of the execution / register steps / rows in cairo runner trace
-    pub fn new(proof_options: ProofOptions, full_trace_length: usize, number_steps: usize) -> Self {
+    /// The number of steps of the execution is the `num_steps` of the public input.
+    pub fn new(proof_options: ProofOptions, full_trace_length: usize) -> Self {
         let context = AirContext {
             options: proof_options,
             trace_length: full_trace_length,
-            trace_columns: 34 + 3 + 12 + 3,
+            trace_columns: MAIN_COLUMNS + 11 + 27 + 11,
             transition_degrees: vec![
                 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // Flags 0-14.
                 1, // Flag 15
                 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, // Other constraints.
-                2, 2, 2, 2, // Increasing memory auxiliary constraints.
-                2, 2, 2, 2, // Consistent memory auxiliary constraints.
-                2, 2, 2, 2, // Permutation auxiliary constraints.
-                2, 2, 2, // Permutation auxiliary constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, // Increasing memory auxiliary constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, // Consistent memory auxiliary constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, // Permutation auxiliary constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // Range check increasing constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // Range check permutation constraints.
+                2, 2, // Selector constraints.
+                2, 2, 3, 2, 2, 2, // Range check builtin constraints.
+                2, 2, 2, 2, 2, 3, 3, 2, 2, 2, 3, 3, 3, // Pedersen input constraints.
+                2, 3, 2, 3, 3, 2, 2, // Pedersen curve constraints.
+                2, 2, 2, 1, 3, 3, // Pedersen memory constraints.
+                3, 3, 3, 3, 3, 3, // Poseidon round constraints.
+                2, 2, 2, 2, 2, 2, 1, 3, 3, // Poseidon memory constraints.
+                2, 2, 2, 1, 3, 3, // Keccak memory constraints.
+                2, 2, 2, 2, 2, 2, 2, // Keccak cell shift constraints.
+                2, 2, 2, 2, 2, 3, 3, 2, 2, 2, 3, 3, 3, // EC op input constraints.
+                3, 3, 3, // EC op doubling constraints.
+                2, 3, 3, 2, 3, 3, // EC op curve constraints.
+                2, 2, 2, 1, 3, 3, // EC op memory constraints.
+                2, 2, 2, 2, 2, 2, 2, 2, 2, // EC op cell constraints.
+                2, 2, 3, 3, // Range check 96 builtin constraints.
             ],
             transition_exemptions: vec![
                 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // flags (16)
@@ -222,19 +925,148 @@ impl CairoAIR {
                 0, 0, 0, // operand consraints (3)
                 1, 1, 1, 1, 0, 0, // register constraints (6)
                 0, 0, 0, 0, 0, // opcode constraints (5)
-                0, 0, 0, 1, // memory continuous (4)
-                0, 0, 0, 1, // memory value consistency (4)
-                0, 0, 0, 1, // memory permutation argument (4)
-                0, 0, 1, // range check continuous (3)
-                0, 0, 0, // range check permutation argument (3)
+                0, 0, 0, 0, 0, 0, 0, 0, 1, // memory continuous (9)
+                0, 0, 0, 0, 0, 0, 0, 0, 1, // memory value consistency (9)
+                0, 0, 0, 0, 0, 0, 0, 0, 1, // memory permutation argument (9)
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // range check continuous (11)
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // range check permutation argument (11)
+                0, 1, // selector (2)
+                0, 1, 1, 0, 0, 0, // range check builtin (6)
+                1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, // pedersen inputs (13)
+                0, 1, 1, 1, 1, 0, 0, // pedersen curve (7)
+                0, 1, 1, 1, 0, 0, // pedersen memory (6)
+                1, 1, 1, 0, 0, 0, // poseidon rounds (6)
+                1, 1, 1, 0, 1, 1, 1, 0, 0, // poseidon memory (9)
+                0, 1, 1, 1, 0, 0, // keccak memory (6)
+                1, 1, 1, 1, 1, 1, 1, // keccak cell shift (7)
+                1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, // ec op inputs (13)
+                0, 1, 1, // ec op doubling (3)
+                0, 0, 1, 1, 1, 1, // ec op curve (6)
+                0, 1, 1, 1, 0, 0, // ec op memory (6)
+                0, 0, 0, 0, 0, 1, 1, 1, 1, // ec op cells (9)
+                0, 1, 0, 0, // range check 96 builtin (4)
             ],
             transition_offsets: vec![0, 1],
-            num_transition_constraints: 49,
+            num_transition_constraints: LAYOUT_CONSTRAINTS,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
         };
 
         Self {
             context,
-            number_steps,
+            keccak_builtin: false,
+            layout: None,
+        }
+    }
+
+    /// The same AIR, for the executions of `layout`. Only the builtins an
+    /// execution uses are active: it has the Keccak bits if it uses the
+    /// Keccak builtin, and the columns of the other builtins it doesn't use
+    /// have their unused instances.
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self {
+            layout: Some(layout),
+            ..self
+        }
+    }
+
+    /// The same AIR, for a layout with or without the Keccak builtin.
+    pub fn with_keccak_builtin(mut self, keccak_builtin: bool) -> Self {
+        if keccak_builtin != self.keccak_builtin {
+            let context = &mut self.context;
+            if keccak_builtin {
+                context.trace_columns += keccak::COLUMNS;
+                context
+                    .transition_degrees
+                    .extend(keccak::transition_degrees());
+                context
+                    .transition_exemptions
+                    .extend(keccak::transition_exemptions());
+            } else {
+                context.trace_columns -= keccak::COLUMNS;
+                context.transition_degrees.truncate(LAYOUT_CONSTRAINTS);
+                context.transition_exemptions.truncate(LAYOUT_CONSTRAINTS);
+            }
+            context.num_transition_constraints = context.transition_degrees.len();
+            self.keccak_builtin = keccak_builtin;
+        }
+        self
+    }
+
+    /// AIR of the execution with this public input, with the trace padded to
+    /// a power of two with room for the public memory and the memory holes.
+    /// It's an error if the public input claims more rows than a trace can
+    /// have.
+    pub fn for_public_input(
+        proof_options: ProofOptions,
+        public_input: &PublicInputs,
+    ) -> Result<Self, ProvingError> {
+        let too_long = || {
+            ProvingError::WrongParameter(
+                "The execution has more rows than a trace can have.".to_string(),
+            )
+        };
+        let program_pad = public_input.public_memory_rows();
+        let holes = public_input.memory_holes.unwrap_or(0);
+        let hole_rows = holes / HOLES_PER_ROW + usize::from(holes % HOLES_PER_ROW != 0);
+        let builtin_rows = [
+            range_check_builtin_instances(public_input)
+                .checked_add(range_check96_builtin_instances(public_input)),
+            pedersen_builtin_instances(public_input).checked_mul(PEDERSEN_ROWS),
+            poseidon_builtin_instances(public_input).checked_mul(POSEIDON_ROWS),
+            keccak_builtin_instances(public_input).checked_mul(KECCAK_ROWS),
+            ec_op_builtin_instances(public_input).checked_mul(EC_OP_ROWS),
+        ];
+        let mut rows = public_input
+            .num_steps
+            .checked_add(hole_rows)
+            .ok_or_else(too_long)?;
+        for builtin_rows in builtin_rows {
+            rows = rows.max(builtin_rows.ok_or_else(too_long)?);
+        }
+        let trace_length = rows
+            .checked_add(program_pad)
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or_else(too_long)?;
+        Ok(Self::new(proof_options, trace_length)
+            .with_keccak_builtin(public_input.uses_builtin(Builtin::Keccak)))
+    }
+
+    /// Checks that the execution with `public_input` only uses the builtins
+    /// of the layout of the AIR, with no more instances than it has room for.
+    fn check_layout(&self, public_input: &PublicInputs) -> Result<(), ProvingError> {
+        let layout = match self.layout {
+            Some(layout) => layout,
+            None => return Ok(()),
+        };
+        let builtins = layout_builtins(layout, public_input)?;
+        for (builtin, instances) in public_input.builtin_instances() {
+            let ratio = builtins
+                .iter()
+                .find(|(other, _)| *other == builtin)
+                .map(|(_, ratio)| *ratio);
+            if ratio.is_none() {
+                return Err(ProvingError::WrongParameter(format!(
+                    "The {layout} layout has no {builtin} builtin."
+                )));
+            }
+            let capacity = layout::capacity(ratio, public_input.num_steps);
+            if instances > capacity {
+                return Err(ProvingError::WrongParameter(format!(
+                    "The execution uses {instances} {builtin} instances, more than the {capacity} of the {layout} layout."
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Column of the trace of the layout of the AIR with the index `column`,
+    /// which counts the auxiliary columns as if there were no Keccak bits.
+    fn trace_column(&self, column: usize) -> usize {
+        if self.keccak_builtin && column >= MAIN_COLUMNS {
+            column + keccak::COLUMNS
+        } else {
+            column
         }
     }
 }
@@ -245,7 +1077,9 @@ pub struct CairoRAPChallenges {
     pub z_range_check: FieldElement<Stark252PrimeField>,
 }
 
-fn add_program_in_public_input_section(
+/// Replaces the last accesses, which are dummies, by the cells of the public
+/// pages of `public_input`.
+fn add_public_memory_in_public_input_section(
     addresses: &Vec<FE>,
     values: &[FE],
     public_input: &PublicInputs,
@@ -253,11 +1087,16 @@ fn add_program_in_public_input_section(
     let mut a_aux = addresses.clone();
     let mut v_aux = values.to_owned();
 
-    let public_input_section = addresses.len() - public_input.program.len();
-    let continous_memory = (1..=public_input.program.len() as u64).map(FieldElement::from);
+    let pages = public_input.public_pages();
+    let public_input_section = addresses.len() - public_cells(&pages);
+    let (public_addresses, public_values): (Vec<_>, Vec<_>) = pages
+        .iter()
+        .flat_map(MemoryPage::cells)
+        .map(|(address, value)| (address, value.clone()))
+        .unzip();
 
-    a_aux.splice(public_input_section.., continous_memory);
-    v_aux.splice(public_input_section.., public_input.program.clone());
+    a_aux.splice(public_input_section.., public_addresses);
+    v_aux.splice(public_input_section.., public_values);
 
     (a_aux, v_aux)
 }
@@ -336,9 +1175,74 @@ fn pad_with_last_row<F: IsFFTField>(
     trace.table.append(&mut pad);
 }
 
+/// The address `value` is, if it fits in 64 bits.
+pub(crate) fn address(value: &FE) -> Option<u64> {
+    let limbs = value.representative().limbs;
+    limbs[..3].iter().all(|limb| *limb == 0).then_some(limbs[3])
+}
+
+/// Addresses below the largest accessed one that neither the accesses of
+/// the trace nor the public memory touch. The continuity of the sorted
+/// addresses needs an access to each of them.
+fn memory_holes(trace: &TraceTable<Stark252PrimeField>, public_input: &PublicInputs) -> Vec<u64> {
+    let pages = public_input.public_pages();
+    let public_addresses = pages
+        .iter()
+        .flat_map(MemoryPage::cells)
+        .map(|(address, _)| address);
+    let accessed: BTreeSet<u64> = trace
+        .get_cols(&MEMORY_ADDRESSES)
+        .table
+        .into_iter()
+        .chain(public_addresses)
+        .filter_map(|value| address(&value))
+        .collect();
+    let last_address = accessed.iter().next_back().copied().unwrap_or(0);
+    (1..last_address)
+        .filter(|address| !accessed.contains(address))
+        .collect()
+}
+
+/// Fills `holes` with accesses to their values in the memory, or to zero for
+/// unassigned cells, in the CPU access slots of the rows after the execution.
+/// Those rows have no transitions and their dummy accesses aren't the ones
+/// replaced by the public memory, so the accesses only close the gaps of
+/// the sorted addresses.
+fn fill_memory_holes(
+    trace: &mut TraceTable<Stark252PrimeField>,
+    memory: &CairoMemory,
+    holes: &[u64],
+    public_input: &PublicInputs,
+) -> Result<(), ProvingError> {
+    let free_rows = public_input.num_steps..trace.n_rows() - public_input.public_memory_rows();
+    if free_rows.len() * HOLES_PER_ROW < holes.len() {
+        return Err(ProvingError::WrongParameter(format!(
+            "Trace length is not large enough for the {} memory holes.",
+            holes.len()
+        )));
+    }
+    let slots = free_rows.flat_map(|row| {
+        MEMORY_ADDRESSES
+            .into_iter()
+            .zip(MEMORY_VALUES)
+            .take(HOLES_PER_ROW)
+            .map(move |columns| (row, columns))
+    });
+    let n_cols = trace.n_cols;
+    for (address, (row, (address_column, value_column))) in holes.iter().zip(slots) {
+        let value = memory.get(address).cloned().unwrap_or_else(FE::zero);
+        trace.table[row * n_cols + address_column] = FE::from(*address);
+        trace.table[row * n_cols + value_column] = value;
+    }
+    Ok(())
+}
+
+/// Values missing for the cells of `columns_indices` and `other_values` to
+/// cover a continuous range, together with its bounds.
 fn get_missing_values_offset_columns<F>(
     trace: &TraceTable<F>,
     columns_indices: &[usize],
+    other_values: &[FieldElement<F>],
 ) -> (Vec<FieldElement<F>>, u16, u16)
 where
     F: IsFFTField + IsPrimeField,
@@ -348,6 +1252,7 @@ where
 
     let mut sorted_offset_representatives: Vec<u16> = offset_columns
         .iter()
+        .chain(other_values)
         .map(|x| x.representative().into())
         .collect();
     sorted_offset_representatives.sort();
@@ -389,8 +1294,610 @@ fn add_missing_values_to_offsets_column<F: IsFFTField>(
     }
 }
 
+/// Builtins of `layout` with their ratios, which the public input gives in
+/// the dynamic layout. It's an error if a dynamic public input has no ratios
+/// or invalid ones, or if another layout's public input has any.
+fn layout_builtins(
+    layout: Layout,
+    public_input: &PublicInputs,
+) -> Result<&[(Builtin, usize)], ProvingError> {
+    let ratios = match (layout, &public_input.builtin_ratios) {
+        (Layout::Dynamic, Some(ratios)) => ratios.as_slice(),
+        (Layout::Dynamic, None) => {
+            return Err(ProvingError::WrongParameter(
+                "The public input has no builtin ratios for the dynamic layout.".to_string(),
+            ))
+        }
+        (_, None) => return Ok(layout.builtins()),
+        (_, Some(_)) => {
+            return Err(ProvingError::WrongParameter(format!(
+                "The {layout} layout has fixed builtin ratios."
+            )))
+        }
+    };
+    for (i, (builtin, ratio)) in ratios.iter().enumerate() {
+        if *ratio == 0 {
+            return Err(ProvingError::WrongParameter(format!(
+                "The {builtin} builtin has a ratio of zero."
+            )));
+        }
+        if ratios[..i].iter().any(|(other, _)| other == builtin) {
+            return Err(ProvingError::WrongParameter(format!(
+                "The {builtin} builtin has more than one ratio."
+            )));
+        }
+    }
+    Ok(ratios)
+}
+
+/// Number of cells of the range check builtin segment.
+fn range_check_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .range_check_builtin
+        .as_ref()
+        .map_or(0, MemorySegment::len)
+}
+
+/// Number of cells of the range check 96 builtin segment.
+fn range_check96_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .range_check96_builtin
+        .as_ref()
+        .map_or(0, MemorySegment::len)
+}
+
+/// Splits the 128 least significant bits of `value` in 16 bit parts, from
+/// the least significant one.
+fn range_check_parts(value: &FE) -> [FE; 8] {
+    let bytes = value.to_bytes_be();
+    core::array::from_fn(|i| {
+        let low = bytes.len() - 2 * i;
+        FE::from(u16::from_be_bytes([bytes[low - 2], bytes[low - 1]]) as u64)
+    })
+}
+
+/// Addresses and values of the cells of the range check builtin segment,
+/// followed by the ones of the range check 96 builtin segment.
+fn range_check_builtin_cells(
+    memory: &CairoMemory,
+    public_input: &PublicInputs,
+) -> Result<Vec<(FE, FE)>, ProvingError> {
+    [
+        ("range check", &public_input.range_check_builtin),
+        ("range check 96", &public_input.range_check96_builtin),
+    ]
+    .into_iter()
+    .filter_map(|(name, segment)| segment.as_ref().map(|segment| (name, segment)))
+    .flat_map(|(name, segment)| {
+        (segment.begin_addr..segment.stop_ptr).map(move |address| match memory.get(&address) {
+            Some(value) => Ok((FE::from(address), value.clone())),
+            None => Err(ProvingError::WrongParameter(format!(
+                "{name} builtin cell {address} isn't in the memory"
+            ))),
+        })
+    })
+    .collect()
+}
+
+/// Appends the range check builtin columns to `trace`. The cells of the
+/// segment are in the first rows, and the rest of the rows are unused, with
+/// zero as address and value, and `filler` as parts so as not to add values
+/// to the range check.
+fn add_range_check_builtin_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    cells: &[(FE, FE)],
+    filler: &FE,
+) -> TraceTable<Stark252PrimeField> {
+    let n_cols = trace.n_cols + 3 + RC_BUILTIN_PARTS.len();
+    let mut table = Vec::with_capacity(trace.n_rows() * n_cols);
+    for (row, values) in trace.table.chunks(trace.n_cols).enumerate() {
+        table.extend_from_slice(values);
+        match cells.get(row) {
+            Some((address, value)) => {
+                table.push(FE::one());
+                table.push(address.clone());
+                table.push(value.clone());
+                table.extend(range_check_parts(value));
+            }
+            None => {
+                table.extend([FE::zero(), FE::zero(), FE::zero()]);
+                table.extend(core::iter::repeat(filler.clone()).take(RC_BUILTIN_PARTS.len()));
+            }
+        }
+    }
+    TraceTable::new(table, n_cols)
+}
+
+/// Appends the selector of the range check 96 builtin to `trace`: one from
+/// the row `first_row` of its cells on if the execution uses it, and zero
+/// everywhere otherwise.
+fn add_range_check96_selector_column(
+    trace: &TraceTable<Stark252PrimeField>,
+    first_row: usize,
+    used: bool,
+) -> TraceTable<Stark252PrimeField> {
+    let n_cols = trace.n_cols + 1;
+    let mut table = Vec::with_capacity(trace.n_rows() * n_cols);
+    for (row, values) in trace.table.chunks(trace.n_cols).enumerate() {
+        table.extend_from_slice(values);
+        table.push(if used && row >= first_row {
+            FE::one()
+        } else {
+            FE::zero()
+        });
+    }
+    TraceTable::new(table, n_cols)
+}
+
+/// Number of instances of the Pedersen builtin segment, of three cells each.
+fn pedersen_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .pedersen_builtin
+        .as_ref()
+        .map_or(0, |segment| segment.len() / 3)
+}
+
+/// Inputs of the instances of the Pedersen builtin segment.
+fn pedersen_builtin_inputs(
+    memory: &CairoMemory,
+    public_input: &PublicInputs,
+) -> Result<Vec<(FE, FE)>, ProvingError> {
+    let segment = match &public_input.pedersen_builtin {
+        Some(segment) => segment,
+        None => return Ok(Vec::new()),
+    };
+    if segment.len() % 3 != 0 {
+        return Err(ProvingError::WrongParameter(format!(
+            "the Pedersen builtin segment has {} cells, which aren't whole instances",
+            segment.len()
+        )));
+    }
+    let cell = |address: u64| match memory.get(&address) {
+        Some(value) => Ok(value.clone()),
+        None => Err(ProvingError::WrongParameter(format!(
+            "Pedersen builtin cell {address} isn't in the memory"
+        ))),
+    };
+    (segment.begin_addr..segment.stop_ptr)
+        .step_by(3)
+        .map(|address| -> Result<_, ProvingError> { Ok((cell(address)?, cell(address + 1)?)) })
+        .collect()
+}
+
+/// Appends the Pedersen builtin columns to `trace`, with an instance every
+/// `PEDERSEN_ROWS` rows. The instances of the segment come first, and the
+/// rest hash zeros without accessing the memory, with zero as address and
+/// value. A trace shorter than an instance has no room for any, and the
+/// columns are zero.
+fn add_pedersen_builtin_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    inputs: &[(FE, FE)],
+    begin_addr: u64,
+) -> TraceTable<Stark252PrimeField> {
+    let builtin_cols = PEDERSEN_VALUE - PEDERSEN_SUFFIX + 1;
+    let n_rows = trace.n_rows();
+    let mut rows = Vec::with_capacity(n_rows);
+    if n_rows < PEDERSEN_ROWS {
+        rows.resize(n_rows, vec![FE::zero(); builtin_cols]);
+    }
+
+    let unused = pedersen::instance_columns(&FE::zero(), &FE::zero());
+    for instance in 0..n_rows / PEDERSEN_ROWS {
+        let used = inputs.get(instance);
+        let columns = used.map(|(a, b)| pedersen::instance_columns(a, b));
+        let columns = columns.as_ref().unwrap_or(&unused);
+        let base = FE::from(begin_addr + 3 * instance as u64);
+        for row in 0..PEDERSEN_ROWS {
+            let access = match row {
+                0 => Some((base.clone(), columns.suffix[row].clone())),
+                _ if row == PEDERSEN_ROWS / 2 => {
+                    Some((&base + FE::one(), columns.suffix[row].clone()))
+                }
+                _ if row == PEDERSEN_ROWS - 1 => {
+                    Some((&base + FE::from(2), columns.x[row].clone()))
+                }
+                _ => None,
+            };
+            let (addr, value) = match (used, access) {
+                (Some(_), Some(access)) => access,
+                _ => (FE::zero(), FE::zero()),
+            };
+            let mut values = vec![columns.suffix[row].clone(), columns.bit[row].clone()];
+            values.extend(columns.unpacking.iter().map(|column| column[row].clone()));
+            values.extend([
+                columns.x[row].clone(),
+                columns.y[row].clone(),
+                columns.slope[row].clone(),
+                FE::from(used.is_some() as u64),
+                base.clone(),
+                addr,
+                value,
+            ]);
+            rows.push(values);
+        }
+    }
+
+    let n_cols = trace.n_cols + builtin_cols;
+    let table = trace
+        .table
+        .chunks(trace.n_cols)
+        .zip(rows)
+        .flat_map(|(values, builtin)| values.iter().cloned().chain(builtin))
+        .collect();
+    TraceTable::new(table, n_cols)
+}
+
+/// Number of instances of the Poseidon builtin segment, of six cells each.
+fn poseidon_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .poseidon_builtin
+        .as_ref()
+        .map_or(0, |segment| segment.len() / 6)
+}
+
+/// Inputs of the instances of the Poseidon builtin segment.
+fn poseidon_builtin_inputs(
+    memory: &CairoMemory,
+    public_input: &PublicInputs,
+) -> Result<Vec<[FE; 3]>, ProvingError> {
+    let segment = match &public_input.poseidon_builtin {
+        Some(segment) => segment,
+        None => return Ok(Vec::new()),
+    };
+    if segment.len() % 6 != 0 {
+        return Err(ProvingError::WrongParameter(format!(
+            "the Poseidon builtin segment has {} cells, which aren't whole instances",
+            segment.len()
+        )));
+    }
+    let cell = |address: u64| match memory.get(&address) {
+        Some(value) => Ok(value.clone()),
+        None => Err(ProvingError::WrongParameter(format!(
+            "Poseidon builtin cell {address} isn't in the memory"
+        ))),
+    };
+    (segment.begin_addr..segment.stop_ptr)
+        .step_by(6)
+        .map(|address| -> Result<_, ProvingError> {
+            Ok([cell(address)?, cell(address + 1)?, cell(address + 2)?])
+        })
+        .collect()
+}
+
+/// Appends the Poseidon builtin columns to `trace`, with an instance every
+/// `POSEIDON_ROWS` rows. The instances of the segment come first, and the
+/// rest permute zeros without accessing the memory, with zero as address and
+/// value. A trace shorter than an instance has no room for any, and the
+/// columns are zero.
+fn add_poseidon_builtin_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    inputs: &[[FE; 3]],
+    begin_addr: u64,
+) -> TraceTable<Stark252PrimeField> {
+    let builtin_cols = POSEIDON_VALUE - POSEIDON_STATE[0] + 1;
+    let n_rows = trace.n_rows();
+    let mut rows = Vec::with_capacity(n_rows);
+    if n_rows < POSEIDON_ROWS {
+        rows.resize(n_rows, vec![FE::zero(); builtin_cols]);
+    }
+
+    let periodic = poseidon::periodic_columns(n_rows);
+    let unused = poseidon::instance_columns(&[FE::zero(), FE::zero(), FE::zero()]);
+    for instance in 0..n_rows / POSEIDON_ROWS {
+        let used = inputs.get(instance);
+        let columns = used.map(poseidon::instance_columns);
+        let columns = columns.as_ref().unwrap_or(&unused);
+        let base = FE::from(begin_addr + 6 * instance as u64);
+        for row in 0..POSEIDON_ROWS {
+            let cell = [
+                &columns.state[0][row],
+                &columns.carries[0][row],
+                &columns.carries[1][row],
+            ]
+            .into_iter()
+            .zip([
+                poseidon::FIRST_CELL,
+                poseidon::SECOND_CELL,
+                poseidon::THIRD_CELL,
+            ])
+            .find(|(_, indicator)| periodic[*indicator][row] == FE::one());
+            let (addr, value) = match (used, cell) {
+                (Some(_), Some((value, _))) => {
+                    (&base + &periodic[poseidon::CELL_OFFSET][row], value.clone())
+                }
+                _ => (FE::zero(), FE::zero()),
+            };
+            let mut values: Vec<_> = columns
+                .state
+                .iter()
+                .chain(&columns.cubes)
+                .chain(&columns.carries)
+                .map(|column| column[row].clone())
+                .collect();
+            values.extend([FE::from(used.is_some() as u64), base.clone(), addr, value]);
+            rows.push(values);
+        }
+    }
+
+    let n_cols = trace.n_cols + builtin_cols;
+    let table = trace
+        .table
+        .chunks(trace.n_cols)
+        .zip(rows)
+        .flat_map(|(values, builtin)| values.iter().cloned().chain(builtin))
+        .collect();
+    TraceTable::new(table, n_cols)
+}
+
+/// Number of instances of the Keccak builtin segment, of sixteen cells each.
+fn keccak_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .keccak_builtin
+        .as_ref()
+        .map_or(0, |segment| segment.len() / keccak::CELLS)
+}
+
+/// Inputs of the instances of the Keccak builtin segment, as the lanes of
+/// the state.
+fn keccak_builtin_inputs(
+    memory: &CairoMemory,
+    public_input: &PublicInputs,
+) -> Result<Vec<[u64; keccak::LANES]>, ProvingError> {
+    let segment = match &public_input.keccak_builtin {
+        Some(segment) => segment,
+        None => return Ok(Vec::new()),
+    };
+    if segment.len() % keccak::CELLS != 0 {
+        return Err(ProvingError::WrongParameter(format!(
+            "the Keccak builtin segment has {} cells, which aren't whole instances",
+            segment.len()
+        )));
+    }
+    let cell = |address: u64| match memory.get(&address) {
+        Some(value) => Ok(value.clone()),
+        None => Err(ProvingError::WrongParameter(format!(
+            "Keccak builtin cell {address} isn't in the memory"
+        ))),
+    };
+    (segment.begin_addr..segment.stop_ptr)
+        .step_by(keccak::CELLS)
+        .map(|address| -> Result<_, ProvingError> {
+            let cells = (address..address + keccak::INPUT_CELLS as u64)
+                .map(cell)
+                .collect::<Result<Vec<_>, _>>()?;
+            keccak::state_from_cells(&cells).ok_or_else(|| {
+                ProvingError::WrongParameter(format!(
+                    "Keccak builtin instance {address} has input cells of more than {} bits",
+                    keccak::CELL_BITS
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Appends the Keccak builtin columns to `trace`, with an instance every
+/// `KECCAK_ROWS` rows. The instances of the segment come first, and the rest permute zeros
+/// without accessing the memory, with zero as address and value. A trace
+/// shorter than an instance has no room for any, and the columns are zero.
+fn add_keccak_builtin_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    inputs: &[[u64; keccak::LANES]],
+    begin_addr: u64,
+) -> TraceTable<Stark252PrimeField> {
+    let builtin_cols = KECCAK_VALUE - KECCAK_CELLS[0] + 1;
+    let n_rows = trace.n_rows();
+    let mut rows = Vec::with_capacity(n_rows);
+    if n_rows < KECCAK_ROWS {
+        rows.resize(n_rows, vec![FE::zero(); builtin_cols]);
+    }
+
+    let periodic = keccak::periodic_columns(n_rows);
+    let zero = [0; keccak::LANES];
+    for instance in 0..n_rows / KECCAK_ROWS {
+        let used = inputs.get(instance);
+        let input = used.unwrap_or(&zero);
+        let input_cells = keccak::cells_from_state(input);
+        let output_cells = keccak::cells_from_state(&keccak::permutation(input));
+        let base = FE::from(begin_addr + keccak::CELLS as u64 * instance as u64);
+        for row in 0..KECCAK_ROWS {
+            // The cells from the one accessed on the row, or zeros.
+            let cells: Vec<FE> = match row {
+                _ if row < keccak::INPUT_CELLS => input_cells[row..].to_vec(),
+                _ if row >= keccak::ROUNDS => output_cells[row - keccak::ROUNDS..].to_vec(),
+                _ => Vec::new(),
+            };
+            let (addr, value) = match (used, cells.first()) {
+                (Some(_), Some(value)) => {
+                    (&base + &periodic[keccak::CELL_OFFSET][row], value.clone())
+                }
+                _ => (FE::zero(), FE::zero()),
+            };
+            let mut values = cells;
+            values.resize(KECCAK_CELLS.len(), FE::zero());
+            values.extend([FE::from(used.is_some() as u64), base.clone(), addr, value]);
+            rows.push(values);
+        }
+    }
+
+    let n_cols = trace.n_cols + builtin_cols;
+    let table = trace
+        .table
+        .chunks(trace.n_cols)
+        .zip(rows)
+        .flat_map(|(values, builtin)| values.iter().cloned().chain(builtin))
+        .collect();
+    TraceTable::new(table, n_cols)
+}
+
+/// Appends the bits of the Keccak builtin instances to `trace`, after the
+/// main columns of every layout. The unused instances permute zeros, and a
+/// trace shorter than an instance has zero bits.
+fn add_keccak_bit_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    inputs: &[[u64; keccak::LANES]],
+) -> TraceTable<Stark252PrimeField> {
+    let n_rows = trace.n_rows();
+    let mut rows = Vec::with_capacity(n_rows);
+    if n_rows < KECCAK_ROWS {
+        rows.resize(n_rows, vec![FE::zero(); keccak::COLUMNS]);
+    }
+
+    let zero = [0; keccak::LANES];
+    let unused = keccak::instance_rows(&zero, &zero);
+    for instance in 0..n_rows / KECCAK_ROWS {
+        match inputs.get(instance) {
+            Some(input) => rows.extend(keccak::instance_rows(
+                input,
+                inputs.get(instance + 1).unwrap_or(&zero),
+            )),
+            None => rows.extend(unused.iter().cloned()),
+        }
+    }
+
+    let n_cols = trace.n_cols + keccak::COLUMNS;
+    let table = trace
+        .table
+        .chunks(trace.n_cols)
+        .zip(rows)
+        .flat_map(|(values, bits)| values.iter().cloned().chain(bits))
+        .collect();
+    TraceTable::new(table, n_cols)
+}
+
+/// Number of instances of the EC op builtin segment, of seven cells each.
+fn ec_op_builtin_instances(public_input: &PublicInputs) -> usize {
+    public_input
+        .ec_op_builtin
+        .as_ref()
+        .map_or(0, |segment| segment.len() / ec_op::CELLS)
+}
+
+/// Inputs of the instances of the EC op builtin segment, as `P`, `Q` and
+/// `m`.
+fn ec_op_builtin_inputs(
+    memory: &CairoMemory,
+    public_input: &PublicInputs,
+) -> Result<Vec<[FE; ec_op::INPUT_CELLS]>, ProvingError> {
+    let segment = match &public_input.ec_op_builtin {
+        Some(segment) => segment,
+        None => return Ok(Vec::new()),
+    };
+    if segment.len() % ec_op::CELLS != 0 {
+        return Err(ProvingError::WrongParameter(format!(
+            "the EC op builtin segment has {} cells, which aren't whole instances",
+            segment.len()
+        )));
+    }
+    let cell = |address: u64| match memory.get(&address) {
+        Some(value) => Ok(value.clone()),
+        None => Err(ProvingError::WrongParameter(format!(
+            "EC op builtin cell {address} isn't in the memory"
+        ))),
+    };
+    (segment.begin_addr..segment.stop_ptr)
+        .step_by(ec_op::CELLS)
+        .map(|address| -> Result<_, ProvingError> {
+            Ok([
+                cell(address)?,
+                cell(address + 1)?,
+                cell(address + 2)?,
+                cell(address + 3)?,
+                cell(address + 4)?,
+            ])
+        })
+        .collect()
+}
+
+/// Appends the EC op builtin columns to `trace`, with an instance every
+/// `EC_OP_ROWS` rows. The instances of the segment come first, and the rest
+/// add nothing to the generator without accessing the memory, with zero as
+/// address and value. A trace shorter than an instance has no room for any,
+/// and the columns are zero.
+fn add_ec_op_builtin_columns(
+    trace: &TraceTable<Stark252PrimeField>,
+    inputs: &[[FE; ec_op::INPUT_CELLS]],
+    begin_addr: u64,
+) -> Result<TraceTable<Stark252PrimeField>, ProvingError> {
+    let builtin_cols = EC_OP_VALUE - EC_OP_SUFFIX + 1;
+    let n_rows = trace.n_rows();
+    let mut rows = Vec::with_capacity(n_rows);
+    if n_rows < EC_OP_ROWS {
+        rows.resize(n_rows, vec![FE::zero(); builtin_cols]);
+    }
+
+    let periodic = ec_op::periodic_columns(n_rows);
+    let generator = ec_op::generator();
+    let unused_input = [
+        generator.0.clone(),
+        generator.1.clone(),
+        generator.0.clone(),
+        generator.1,
+        FE::zero(),
+    ];
+    for instance in 0..n_rows / EC_OP_ROWS {
+        let used = inputs.get(instance);
+        let input = used.unwrap_or(&unused_input);
+        let [p_x, p_y, q_x, q_y, m] = input;
+        let address = begin_addr + ec_op::CELLS as u64 * instance as u64;
+        let base = FE::from(address);
+        let columns =
+            ec_op::instance_columns(&(p_x.clone(), p_y.clone()), &(q_x.clone(), q_y.clone()), m)
+                .ok_or_else(|| {
+                    ProvingError::WrongParameter(format!(
+                        "EC op builtin instance {address} adds points with the same x coordinate"
+                    ))
+                })?;
+        for row in 0..EC_OP_ROWS {
+            // The input cells from the one accessed on the row, or zeros.
+            let mut cells: Vec<FE> = match row {
+                _ if row < ec_op::INPUT_CELLS => input[row..].to_vec(),
+                _ => Vec::new(),
+            };
+            let value = match row {
+                _ if row == ec_op::OUTPUT_ROW => Some(&columns.x[row]),
+                _ if row == ec_op::OUTPUT_ROW + 1 => Some(&columns.y[row]),
+                _ => cells.first(),
+            };
+            let (addr, value) = match (used, value) {
+                (Some(_), Some(value)) => {
+                    (&base + &periodic[ec_op::CELL_OFFSET][row], value.clone())
+                }
+                _ => (FE::zero(), FE::zero()),
+            };
+            let mut values = vec![columns.suffix[row].clone(), columns.bit[row].clone()];
+            values.extend(columns.unpacking.iter().map(|column| column[row].clone()));
+            values.extend(
+                [
+                    &columns.q_x,
+                    &columns.q_y,
+                    &columns.doubling_slope,
+                    &columns.x,
+                    &columns.y,
+                    &columns.slope,
+                    &columns.x_diff_inv,
+                ]
+                .map(|column| column[row].clone()),
+            );
+            cells.resize(EC_OP_CELLS.len(), FE::zero());
+            values.extend(cells);
+            values.extend([FE::from(used.is_some() as u64), base.clone(), addr, value]);
+            rows.push(values);
+        }
+    }
+
+    let n_cols = trace.n_cols + builtin_cols;
+    let table = trace
+        .table
+        .chunks(trace.n_cols)
+        .zip(rows)
+        .flat_map(|(values, builtin)| values.iter().cloned().chain(builtin))
+        .collect();
+    Ok(TraceTable::new(table, n_cols))
+}
+
 impl AIR for CairoAIR {
     type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
     type RawTrace = (CairoTrace, CairoMemory);
     type RAPChallenges = CairoRAPChallenges;
     type PublicInput = PublicInputs;
@@ -398,20 +1905,30 @@ impl AIR for CairoAIR {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        self.check_layout(public_input)?;
         let mut main_trace = build_cairo_execution_trace(&raw_trace.0, &raw_trace.1);
+        let program_pad = public_input.public_memory_rows();
 
-        pad_with_last_row(
-            &mut main_trace,
-            (public_input.program.len() >> 2) + 1,
-            &MEMORY_COLUMNS,
-        );
+        pad_with_last_row(&mut main_trace, program_pad, &MEMORY_COLUMNS);
+
+        let builtin_cells = range_check_builtin_cells(&raw_trace.1, public_input)?;
+        let builtin_parts: Vec<_> = builtin_cells
+            .iter()
+            .flat_map(|(_, value)| range_check_parts(value))
+            .collect();
 
-        let (missing_values, rc_min, rc_max) =
-            get_missing_values_offset_columns(&main_trace, &[OFF_DST, OFF_OP0, OFF_OP1]);
-        public_input.range_check_min = Some(rc_min);
-        public_input.range_check_max = Some(rc_max);
+        let (missing_values, rc_min, rc_max) = get_missing_values_offset_columns(
+            &main_trace,
+            &[OFF_DST, OFF_OP0, OFF_OP1],
+            &builtin_parts,
+        );
+        let public_input = PublicInputs {
+            range_check_min: Some(rc_min),
+            range_check_max: Some(rc_max),
+            ..public_input.clone()
+        };
 
         add_missing_values_to_offsets_column(&mut main_trace, missing_values);
 
@@ -424,7 +1941,88 @@ impl AIR for CairoAIR {
         let padding = self.context().trace_length - main_trace.n_rows();
         pad_with_last_row(&mut main_trace, padding, &MEMORY_COLUMNS);
 
-        Ok(main_trace)
+        // The last rows hold the accesses replaced by the public memory in the
+        // memory permutation, so they can't have builtin cells.
+        if self.context().trace_length < builtin_cells.len() + program_pad {
+            return Err(ProvingError::WrongParameter(
+                "Trace length is not large enough for the range check builtin.".to_string(),
+            ));
+        }
+        let main_trace = add_range_check_builtin_columns(
+            &main_trace,
+            &builtin_cells,
+            &FieldElement::from(rc_min as u64),
+        );
+
+        let pedersen_inputs = pedersen_builtin_inputs(&raw_trace.1, &public_input)?;
+        if self.context().trace_length < pedersen_inputs.len() * PEDERSEN_ROWS + program_pad {
+            return Err(ProvingError::WrongParameter(
+                "Trace length is not large enough for the Pedersen builtin.".to_string(),
+            ));
+        }
+        let pedersen_begin = public_input
+            .pedersen_builtin
+            .as_ref()
+            .map_or(0, |segment| segment.begin_addr);
+        let main_trace =
+            add_pedersen_builtin_columns(&main_trace, &pedersen_inputs, pedersen_begin);
+
+        let poseidon_inputs = poseidon_builtin_inputs(&raw_trace.1, &public_input)?;
+        if self.context().trace_length < poseidon_inputs.len() * POSEIDON_ROWS + program_pad {
+            return Err(ProvingError::WrongParameter(
+                "Trace length is not large enough for the Poseidon builtin.".to_string(),
+            ));
+        }
+        let poseidon_begin = public_input
+            .poseidon_builtin
+            .as_ref()
+            .map_or(0, |segment| segment.begin_addr);
+        let main_trace =
+            add_poseidon_builtin_columns(&main_trace, &poseidon_inputs, poseidon_begin);
+
+        let keccak_inputs = keccak_builtin_inputs(&raw_trace.1, &public_input)?;
+        if self.context().trace_length < keccak_inputs.len() * KECCAK_ROWS + program_pad {
+            return Err(ProvingError::WrongParameter(
+                "Trace length is not large enough for the Keccak builtin.".to_string(),
+            ));
+        }
+        let keccak_begin = public_input
+            .keccak_builtin
+            .as_ref()
+            .map_or(0, |segment| segment.begin_addr);
+        let main_trace = add_keccak_builtin_columns(&main_trace, &keccak_inputs, keccak_begin);
+
+        let ec_op_inputs = ec_op_builtin_inputs(&raw_trace.1, &public_input)?;
+        if self.context().trace_length < ec_op_inputs.len() * EC_OP_ROWS + program_pad {
+            return Err(ProvingError::WrongParameter(
+                "Trace length is not large enough for the EC op builtin.".to_string(),
+            ));
+        }
+        let ec_op_begin = public_input
+            .ec_op_builtin
+            .as_ref()
+            .map_or(0, |segment| segment.begin_addr);
+        let main_trace = add_ec_op_builtin_columns(&main_trace, &ec_op_inputs, ec_op_begin)?;
+        let mut main_trace = add_range_check96_selector_column(
+            &main_trace,
+            range_check_builtin_instances(&public_input),
+            public_input.uses_builtin(Builtin::RangeCheck96),
+        );
+
+        let holes = memory_holes(&main_trace, &public_input);
+        fill_memory_holes(&mut main_trace, &raw_trace.1, &holes, &public_input)?;
+        let public_input = PublicInputs {
+            memory_holes: Some(holes.len()),
+            ..public_input
+        };
+
+        let main_trace = if public_input.uses_builtin(Builtin::Keccak) {
+            add_keccak_bit_columns(&main_trace, &keccak_inputs)
+        } else {
+            main_trace
+        };
+
+        Ok((main_trace, public_input))
     }
 
     fn build_auxiliary_trace(
@@ -433,14 +2031,10 @@ impl AIR for CairoAIR {
         rap_challenges: &Self::RAPChallenges,
         public_input: &Self::PublicInput,
     ) -> TraceTable<Self::Field> {
-        let addresses_original = main_trace
-            .get_cols(&[FRAME_PC, FRAME_DST_ADDR, FRAME_OP0_ADDR, FRAME_OP1_ADDR])
-            .table;
-        let values_original = main_trace
-            .get_cols(&[FRAME_INST, FRAME_DST, FRAME_OP0, FRAME_OP1])
-            .table;
-
-        let (addresses, values) = add_program_in_public_input_section(
+        let addresses_original = main_trace.get_cols(&MEMORY_ADDRESSES).table;
+        let values_original = main_trace.get_cols(&MEMORY_VALUES).table;
+
+        let (addresses, values) = add_public_memory_in_public_input_section(
             &addresses_original,
             &values_original,
             public_input,
@@ -455,7 +2049,7 @@ impl AIR for CairoAIR {
         );
 
         // Range Check
-        let offsets_original = main_trace.get_cols(&[OFF_DST, OFF_OP0, OFF_OP1]).table;
+        let offsets_original = main_trace.get_cols(&RANGE_CHECK_POOL).table;
         let mut offsets_sorted: Vec<u16> = offsets_original
             .iter()
             .map(|x| x.representative().into())
@@ -473,26 +2067,15 @@ impl AIR for CairoAIR {
         );
 
         // Convert from long-format to wide-format again
+        let pool = RANGE_CHECK_POOL.len();
+        let accesses = MEMORY_ADDRESSES.len();
         let mut aux_table = Vec::new();
         for i in 0..main_trace.n_rows() {
-            aux_table.push(offsets_sorted[3 * i].clone());
-            aux_table.push(offsets_sorted[3 * i + 1].clone());
-            aux_table.push(offsets_sorted[3 * i + 2].clone());
-            aux_table.push(addresses[4 * i].clone());
-            aux_table.push(addresses[4 * i + 1].clone());
-            aux_table.push(addresses[4 * i + 2].clone());
-            aux_table.push(addresses[4 * i + 3].clone());
-            aux_table.push(values[4 * i].clone());
-            aux_table.push(values[4 * i + 1].clone());
-            aux_table.push(values[4 * i + 2].clone());
-            aux_table.push(values[4 * i + 3].clone());
-            aux_table.push(permutation_col[4 * i].clone());
-            aux_table.push(permutation_col[4 * i + 1].clone());
-            aux_table.push(permutation_col[4 * i + 2].clone());
-            aux_table.push(permutation_col[4 * i + 3].clone());
-            aux_table.push(range_check_permutation_col[3 * i].clone());
-            aux_table.push(range_check_permutation_col[3 * i + 1].clone());
-            aux_table.push(range_check_permutation_col[3 * i + 2].clone());
+            aux_table.extend_from_slice(&offsets_sorted[pool * i..pool * (i + 1)]);
+            aux_table.extend_from_slice(&addresses[accesses * i..accesses * (i + 1)]);
+            aux_table.extend_from_slice(&values[accesses * i..accesses * (i + 1)]);
+            aux_table.extend_from_slice(&permutation_col[accesses * i..accesses * (i + 1)]);
+            aux_table.extend_from_slice(&range_check_permutation_col[pool * i..pool * (i + 1)]);
         }
 
         TraceTable::new(aux_table, self.number_auxiliary_rap_columns())
@@ -506,27 +2089,82 @@ impl AIR for CairoAIR {
         }
     }
 
+    fn with_public_input(&self, public_input: &Self::PublicInput) -> Result<Self, ProvingError> {
+        self.check_layout(public_input)?;
+        public_input.validate()?;
+        Ok(self
+            .clone()
+            .with_keccak_builtin(public_input.uses_builtin(Builtin::Keccak)))
+    }
+
+    fn public_input_bytes(&self, public_input: &Self::PublicInput) -> Vec<u8> {
+        public_input.to_bytes_be()
+    }
+
     fn number_auxiliary_rap_columns(&self) -> usize {
-        12 + 3 + 3
+        11 + 27 + 11
+    }
+
+    /// The periodic columns of the Pedersen builtin, followed by those of the
+    /// Poseidon, Keccak and EC op builtins.
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        let mut columns = pedersen::periodic_columns(self.context.trace_length);
+        columns.extend(poseidon::periodic_columns(self.context.trace_length));
+        columns.extend(keccak::periodic_columns(self.context.trace_length));
+        columns.extend(ec_op::periodic_columns(self.context.trace_length));
+        columns
+    }
+
+    /// Only the Keccak bits of the state are read in the next row.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        let mut offsets = vec![vec![0, 1]; self.context.trace_columns];
+        if self.keccak_builtin {
+            let columns = MAIN_COLUMNS + keccak::STATE_BITS..MAIN_COLUMNS + keccak::COLUMNS;
+            offsets[columns].fill(vec![0]);
+        }
+        offsets
     }
 
     fn compute_transition(
         &self,
-        frame: &Frame<Self::Field>,
+        frame: &Frame<Self::ChallengeField>,
         rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
-        let mut constraints: Vec<FieldElement<Self::Field>> =
-            vec![FE::zero(); self.num_transition_constraints()];
-
-        compute_instr_constraints(&mut constraints, frame);
-        compute_operand_constraints(&mut constraints, frame);
-        compute_register_constraints(&mut constraints, frame);
-        compute_opcode_constraints(&mut constraints, frame);
-        enforce_selector(&mut constraints, frame);
-        memory_is_increasing(&mut constraints, frame);
-        permutation_argument(&mut constraints, frame, rap_challenges);
-        permutation_argument_range_check(&mut constraints, frame, rap_challenges);
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let mut constraints: Vec<FieldElement<Self::Field>> = vec![FE::zero(); LAYOUT_CONSTRAINTS];
+
+        let without_bits;
+        let layout_frame = if self.keccak_builtin {
+            without_bits = frame_without_keccak_bits(frame);
+            &without_bits
+        } else {
+            frame
+        };
 
+        compute_instr_constraints(&mut constraints, layout_frame);
+        compute_operand_constraints(&mut constraints, layout_frame);
+        compute_register_constraints(&mut constraints, layout_frame);
+        compute_opcode_constraints(&mut constraints, layout_frame);
+        enforce_selector(&mut constraints, layout_frame);
+        memory_is_increasing(&mut constraints, layout_frame);
+        permutation_argument(&mut constraints, layout_frame, rap_challenges);
+        permutation_argument_range_check(&mut constraints, layout_frame, rap_challenges);
+        range_check_builtin(&mut constraints, layout_frame);
+        pedersen_builtin(&mut constraints, layout_frame);
+        poseidon_builtin(&mut constraints, layout_frame);
+        keccak_builtin(&mut constraints, layout_frame);
+        ec_op_builtin(&mut constraints, layout_frame);
+        range_check96_builtin(&mut constraints, layout_frame);
+
+        if self.keccak_builtin {
+            let (curr, next) = (frame.get_row(0), frame.get_row(1));
+            let bits = MAIN_COLUMNS..MAIN_COLUMNS + keccak::COLUMNS;
+            constraints.extend(keccak::evaluate_constraints(
+                &curr[KECCAK_CELLS[0]..=KECCAK_CELLS[KECCAK_CELLS.len() - 1]],
+                &curr[bits.clone()],
+                &next[bits],
+                &frame.periodic_values()[pedersen::PERIODIC_COLUMNS + poseidon::PERIODIC_COLUMNS..],
+            ));
+        }
         constraints
     }
 
@@ -534,78 +2172,246 @@ impl AIR for CairoAIR {
     /// These are part of the register constraints.
     ///
     /// Boundary constraints:
-    ///  * ap_0 = fp_0 = ap_i
+    ///  * ap_0 = ap_i
     ///  * ap_t = ap_f
+    ///  * fp_0 = fp_i
+    ///  * fp_t = fp_f
     ///  * pc_0 = pc_i
     ///  * pc_t = pc_f
+    ///  * selector_{t-1} = 1
+    ///
+    /// where t is the last step of the execution, `num_steps - 1` of the
+    /// public input. The `n` cells of the range check builtin segment are in
+    /// the first rows:
+    ///  * rc_selector_0 = rc_selector_{n-1} = 1, rc_selector_n = 0
+    ///  * rc_addr_0 = rc_begin
+    ///
+    /// or rc_selector_0 = 0 if the segment is empty. Likewise, the `m`
+    /// instances of the Pedersen builtin segment are in the first `512·m`
+    /// rows:
+    ///  * pedersen_selector_0 = pedersen_selector_{512·m-1} = 1
+    ///  * pedersen_selector_{512·m} = 0
+    ///  * pedersen_base_0 = pedersen_begin
+    ///
+    /// or pedersen_selector_0 = 0 if the segment is empty, and the same for
+    /// the Poseidon builtin, with instances of 128 rows, the Keccak builtin,
+    /// with instances of 32 rows, and the EC op builtin, with instances of
+    /// 256 rows.
     fn boundary_constraints(
         &self,
         rap_challenges: &Self::RAPChallenges,
         public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         let initial_pc =
             BoundaryConstraint::new(MEM_A_TRACE_OFFSET, 0, public_input.pc_init.clone());
         let initial_ap =
             BoundaryConstraint::new(MEM_P_TRACE_OFFSET, 0, public_input.ap_init.clone());
+        let initial_fp = BoundaryConstraint::new(FRAME_FP, 0, public_input.fp_init.clone());
+
+        let last_step = public_input.num_steps.saturating_sub(1);
+        let final_pc =
+            BoundaryConstraint::new(MEM_A_TRACE_OFFSET, last_step, public_input.pc_final.clone());
+        let final_ap =
+            BoundaryConstraint::new(MEM_P_TRACE_OFFSET, last_step, public_input.ap_final.clone());
+        let final_fp = BoundaryConstraint::new(FRAME_FP, last_step, public_input.fp_final.clone());
+        // With a single step, there are no transitions to select.
+        let selected_steps = last_step
+            .checked_sub(1)
+            .map(|step| BoundaryConstraint::new(FRAME_SELECTOR, step, FieldElement::one()));
 
-        let final_pc = BoundaryConstraint::new(
-            MEM_A_TRACE_OFFSET,
-            self.number_steps - 1,
-            public_input.pc_final.clone(),
+        // Auxiliary constraint: permutation argument final value
+        let trace_length = self.context.trace_length;
+
+        // The dummy accesses replaced by the public memory only leave the
+        // product of the public cells, which binds the proof to them.
+        let permutation_final = public_product(
+            &public_input.public_pages(),
+            &rap_challenges.z_memory,
+            &rap_challenges.alpha_memory,
         );
-        let final_ap = BoundaryConstraint::new(
-            MEM_P_TRACE_OFFSET,
-            self.number_steps - 1,
-            public_input.ap_final.clone(),
+        let permutation_final_constraint = BoundaryConstraint::new_last_row(
+            self.trace_column(PERMUTATION_ARGUMENT_COL_8),
+            trace_length,
+            permutation_final,
         );
 
-        // Auxiliary constraint: permutation argument final value
-        let final_index = self.context.trace_length - 1;
-
-        let mut cumulative_product = FieldElement::one();
-        for (i, value) in public_input.program.iter().enumerate() {
-            cumulative_product = cumulative_product
-                * (&rap_challenges.z_memory
-                    - (FieldElement::from(i as u64 + 1) + &rap_challenges.alpha_memory * value));
-        }
-        let permutation_final =
-            rap_challenges.z_memory.pow(public_input.program.len()) / cumulative_product;
-        let permutation_final_constraint =
-            BoundaryConstraint::new(PERMUTATION_ARGUMENT_COL_3, final_index, permutation_final);
-
         let one: FieldElement<Self::Field> = FieldElement::one();
-        let range_check_final_constraint =
-            BoundaryConstraint::new(PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3, final_index, one);
+        let range_check_final_constraint = BoundaryConstraint::new_last_row(
+            self.trace_column(PERMUTATION_ARGUMENT_RANGE_CHECK[RANGE_CHECK_POOL.len() - 1]),
+            trace_length,
+            one,
+        );
 
+        // The bounds are checked by `with_public_input`.
+        let (rc_min, rc_max) = public_input.range_check_bounds().unwrap();
         let range_check_min = BoundaryConstraint::new(
-            RANGE_CHECK_COL_1,
+            self.trace_column(RANGE_CHECK_SORTED[0]),
             0,
-            FieldElement::from(public_input.range_check_min.unwrap() as u64),
+            FieldElement::from(rc_min as u64),
         );
-        let range_check_max = BoundaryConstraint::new(
-            RANGE_CHECK_COL_3,
-            final_index,
-            FieldElement::from(public_input.range_check_max.unwrap() as u64),
+        let range_check_max = BoundaryConstraint::new_last_row(
+            self.trace_column(RANGE_CHECK_SORTED[RANGE_CHECK_POOL.len() - 1]),
+            trace_length,
+            FieldElement::from(rc_max as u64),
         );
 
-        let constraints = vec![
+        let mut constraints = vec![
             initial_pc,
             initial_ap,
+            initial_fp,
             final_pc,
             final_ap,
+            final_fp,
             permutation_final_constraint,
             range_check_final_constraint,
             range_check_min,
             range_check_max,
         ];
+        constraints.extend(selected_steps);
+
+        // The cells of the range check 96 builtin follow the ones of the
+        // range check builtin, from the row where its selector turns one.
+        let rc_cells = range_check_builtin_instances(public_input);
+        let rc96_cells = range_check96_builtin_instances(public_input);
+        let builtin_cells = rc_cells + rc96_cells;
+        if builtin_cells > 0 {
+            constraints.extend([
+                BoundaryConstraint::new(RC_BUILTIN_SELECTOR, 0, FieldElement::one()),
+                BoundaryConstraint::new(
+                    RC_BUILTIN_SELECTOR,
+                    builtin_cells - 1,
+                    FieldElement::one(),
+                ),
+                BoundaryConstraint::new(RC_BUILTIN_SELECTOR, builtin_cells, FieldElement::zero()),
+            ]);
+        } else {
+            constraints.push(BoundaryConstraint::new(
+                RC_BUILTIN_SELECTOR,
+                0,
+                FieldElement::zero(),
+            ));
+        }
+        if let Some(segment) = public_input
+            .range_check_builtin
+            .as_ref()
+            .filter(|_| rc_cells > 0)
+        {
+            constraints.extend([
+                BoundaryConstraint::new(RC_BUILTIN_ADDR, 0, FieldElement::from(segment.begin_addr)),
+                BoundaryConstraint::new(RC96_BUILTIN_SELECTOR, rc_cells - 1, FieldElement::zero()),
+            ]);
+        }
+        if let Some(segment) = public_input
+            .range_check96_builtin
+            .as_ref()
+            .filter(|_| rc96_cells > 0)
+        {
+            constraints.extend([
+                BoundaryConstraint::new(RC96_BUILTIN_SELECTOR, rc_cells, FieldElement::one()),
+                BoundaryConstraint::new(
+                    RC_BUILTIN_ADDR,
+                    rc_cells,
+                    FieldElement::from(segment.begin_addr),
+                ),
+            ]);
+        }
+
+        let pedersen_rows = pedersen_builtin_instances(public_input) * PEDERSEN_ROWS;
+        match &public_input.pedersen_builtin {
+            Some(segment) if pedersen_rows > 0 => constraints.extend([
+                BoundaryConstraint::new(PEDERSEN_SELECTOR, 0, FieldElement::one()),
+                BoundaryConstraint::new(PEDERSEN_SELECTOR, pedersen_rows - 1, FieldElement::one()),
+                BoundaryConstraint::new(PEDERSEN_SELECTOR, pedersen_rows, FieldElement::zero()),
+                BoundaryConstraint::new(PEDERSEN_BASE, 0, FieldElement::from(segment.begin_addr)),
+            ]),
+            _ => constraints.push(BoundaryConstraint::new(
+                PEDERSEN_SELECTOR,
+                0,
+                FieldElement::zero(),
+            )),
+        }
+
+        let poseidon_rows = poseidon_builtin_instances(public_input) * POSEIDON_ROWS;
+        match &public_input.poseidon_builtin {
+            Some(segment) if poseidon_rows > 0 => constraints.extend([
+                BoundaryConstraint::new(POSEIDON_SELECTOR, 0, FieldElement::one()),
+                BoundaryConstraint::new(POSEIDON_SELECTOR, poseidon_rows - 1, FieldElement::one()),
+                BoundaryConstraint::new(POSEIDON_SELECTOR, poseidon_rows, FieldElement::zero()),
+                BoundaryConstraint::new(POSEIDON_BASE, 0, FieldElement::from(segment.begin_addr)),
+            ]),
+            _ => constraints.push(BoundaryConstraint::new(
+                POSEIDON_SELECTOR,
+                0,
+                FieldElement::zero(),
+            )),
+        }
+
+        let keccak_rows = keccak_builtin_instances(public_input) * KECCAK_ROWS;
+        match &public_input.keccak_builtin {
+            Some(segment) if keccak_rows > 0 => constraints.extend([
+                BoundaryConstraint::new(KECCAK_SELECTOR, 0, FieldElement::one()),
+                BoundaryConstraint::new(KECCAK_SELECTOR, keccak_rows - 1, FieldElement::one()),
+                BoundaryConstraint::new(KECCAK_SELECTOR, keccak_rows, FieldElement::zero()),
+                BoundaryConstraint::new(KECCAK_BASE, 0, FieldElement::from(segment.begin_addr)),
+            ]),
+            _ => constraints.push(BoundaryConstraint::new(
+                KECCAK_SELECTOR,
+                0,
+                FieldElement::zero(),
+            )),
+        }
+
+        let ec_op_rows = ec_op_builtin_instances(public_input) * EC_OP_ROWS;
+        match &public_input.ec_op_builtin {
+            Some(segment) if ec_op_rows > 0 => constraints.extend([
+                BoundaryConstraint::new(EC_OP_SELECTOR, 0, FieldElement::one()),
+                BoundaryConstraint::new(EC_OP_SELECTOR, ec_op_rows - 1, FieldElement::one()),
+                BoundaryConstraint::new(EC_OP_SELECTOR, ec_op_rows, FieldElement::zero()),
+                BoundaryConstraint::new(EC_OP_BASE, 0, FieldElement::from(segment.begin_addr)),
+            ]),
+            _ => constraints.push(BoundaryConstraint::new(
+                EC_OP_SELECTOR,
+                0,
+                FieldElement::zero(),
+            )),
+        }
 
         BoundaryConstraints::from_constraints(constraints)
     }
 
+    /// The registers at the first step and the commitment to the public
+    /// memory, as `[pc, ap, fp, memory]`. Segments of an execution share
+    /// their boundary step and their public pages, which the memory argument
+    /// of each segment constrains, so the memory they share is linked through
+    /// those pages.
+    fn initial_state(&self, public_input: &Self::PublicInput) -> Vec<FE> {
+        vec![
+            public_input.pc_init.clone(),
+            public_input.ap_init.clone(),
+            public_input.fp_init.clone(),
+            public_input.memory_commitment(),
+        ]
+    }
+
+    /// The registers at the last step and the commitment to the public
+    /// memory, as `[pc, ap, fp, memory]`.
+    fn final_state(&self, public_input: &Self::PublicInput) -> Vec<FE> {
+        vec![
+            public_input.pc_final.clone(),
+            public_input.ap_final.clone(),
+            public_input.fp_final.clone(),
+            public_input.memory_commitment(),
+        ]
+    }
+
     fn context(&self) -> &AirContext {
         &self.context
     }
 
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        Some(&mut self.context)
+    }
+
     fn composition_poly_degree_bound(&self) -> usize {
         2 * self.context().trace_length
     }
@@ -723,11 +2529,20 @@ fn compute_opcode_constraints(constraints: &mut [FE], frame: &Frame<Stark252Prim
     constraints[ASSERT_EQ] = &curr[F_OPC_AEQ] * (&curr[FRAME_DST] - &curr[FRAME_RES]);
 }
 
+/// The constraints of the steps only apply where the selector is one, which
+/// is every step but the last one. The selector is a bit that can only drop
+/// from one to zero, and a boundary constraint sets it to one at the step
+/// before the last, so it can't skip any step.
 fn enforce_selector(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
     let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let selector = &curr[FRAME_SELECTOR];
     for result_cell in constraints.iter_mut().take(ASSERT_EQ + 1).skip(INST) {
-        *result_cell = result_cell.clone() * curr[FRAME_SELECTOR].clone();
+        *result_cell = result_cell.clone() * selector;
     }
+
+    constraints[SELECTOR_BIT] = selector * (selector - FE::one());
+    constraints[SELECTOR_DECREASING] = (FE::one() - selector) * &next[FRAME_SELECTOR];
 }
 
 fn memory_is_increasing(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
@@ -744,8 +2559,23 @@ fn memory_is_increasing(constraints: &mut [FE], frame: &Frame<Stark252PrimeField
     constraints[MEMORY_INCREASING_2] = (&curr[MEMORY_ADDR_SORTED_2] - &curr[MEMORY_ADDR_SORTED_3])
         * (&curr[MEMORY_ADDR_SORTED_3] - &curr[MEMORY_ADDR_SORTED_2] - &one);
 
-    constraints[MEMORY_INCREASING_3] = (&curr[MEMORY_ADDR_SORTED_3] - &next[MEMORY_ADDR_SORTED_0])
-        * (&next[MEMORY_ADDR_SORTED_0] - &curr[MEMORY_ADDR_SORTED_3] - &one);
+    constraints[MEMORY_INCREASING_3] = (&curr[MEMORY_ADDR_SORTED_3] - &curr[MEMORY_ADDR_SORTED_4])
+        * (&curr[MEMORY_ADDR_SORTED_4] - &curr[MEMORY_ADDR_SORTED_3] - &one);
+
+    constraints[MEMORY_INCREASING_4] = (&curr[MEMORY_ADDR_SORTED_4] - &curr[MEMORY_ADDR_SORTED_5])
+        * (&curr[MEMORY_ADDR_SORTED_5] - &curr[MEMORY_ADDR_SORTED_4] - &one);
+
+    constraints[MEMORY_INCREASING_5] = (&curr[MEMORY_ADDR_SORTED_5] - &curr[MEMORY_ADDR_SORTED_6])
+        * (&curr[MEMORY_ADDR_SORTED_6] - &curr[MEMORY_ADDR_SORTED_5] - &one);
+
+    constraints[MEMORY_INCREASING_6] = (&curr[MEMORY_ADDR_SORTED_6] - &curr[MEMORY_ADDR_SORTED_7])
+        * (&curr[MEMORY_ADDR_SORTED_7] - &curr[MEMORY_ADDR_SORTED_6] - &one);
+
+    constraints[MEMORY_INCREASING_7] = (&curr[MEMORY_ADDR_SORTED_7] - &curr[MEMORY_ADDR_SORTED_8])
+        * (&curr[MEMORY_ADDR_SORTED_8] - &curr[MEMORY_ADDR_SORTED_7] - &one);
+
+    constraints[MEMORY_INCREASING_8] = (&curr[MEMORY_ADDR_SORTED_8] - &next[MEMORY_ADDR_SORTED_0])
+        * (&next[MEMORY_ADDR_SORTED_0] - &curr[MEMORY_ADDR_SORTED_8] - &one);
 
     constraints[MEMORY_CONSISTENCY_0] = (&curr[MEMORY_VALUES_SORTED_0]
         - &curr[MEMORY_VALUES_SORTED_1])
@@ -760,8 +2590,28 @@ fn memory_is_increasing(constraints: &mut [FE], frame: &Frame<Stark252PrimeField
         * (&curr[MEMORY_ADDR_SORTED_3] - &curr[MEMORY_ADDR_SORTED_2] - &one);
 
     constraints[MEMORY_CONSISTENCY_3] = (&curr[MEMORY_VALUES_SORTED_3]
+        - &curr[MEMORY_VALUES_SORTED_4])
+        * (&curr[MEMORY_ADDR_SORTED_4] - &curr[MEMORY_ADDR_SORTED_3] - &one);
+
+    constraints[MEMORY_CONSISTENCY_4] = (&curr[MEMORY_VALUES_SORTED_4]
+        - &curr[MEMORY_VALUES_SORTED_5])
+        * (&curr[MEMORY_ADDR_SORTED_5] - &curr[MEMORY_ADDR_SORTED_4] - &one);
+
+    constraints[MEMORY_CONSISTENCY_5] = (&curr[MEMORY_VALUES_SORTED_5]
+        - &curr[MEMORY_VALUES_SORTED_6])
+        * (&curr[MEMORY_ADDR_SORTED_6] - &curr[MEMORY_ADDR_SORTED_5] - &one);
+
+    constraints[MEMORY_CONSISTENCY_6] = (&curr[MEMORY_VALUES_SORTED_6]
+        - &curr[MEMORY_VALUES_SORTED_7])
+        * (&curr[MEMORY_ADDR_SORTED_7] - &curr[MEMORY_ADDR_SORTED_6] - &one);
+
+    constraints[MEMORY_CONSISTENCY_7] = (&curr[MEMORY_VALUES_SORTED_7]
+        - &curr[MEMORY_VALUES_SORTED_8])
+        * (&curr[MEMORY_ADDR_SORTED_8] - &curr[MEMORY_ADDR_SORTED_7] - &one);
+
+    constraints[MEMORY_CONSISTENCY_8] = (&curr[MEMORY_VALUES_SORTED_8]
         - &next[MEMORY_VALUES_SORTED_0])
-        * (&next[MEMORY_ADDR_SORTED_0] - &curr[MEMORY_ADDR_SORTED_3] - &one);
+        * (&next[MEMORY_ADDR_SORTED_0] - &curr[MEMORY_ADDR_SORTED_8] - &one);
 }
 
 fn permutation_argument(
@@ -779,26 +2629,51 @@ fn permutation_argument(
     let p1 = &curr[PERMUTATION_ARGUMENT_COL_1];
     let p2 = &curr[PERMUTATION_ARGUMENT_COL_2];
     let p3 = &curr[PERMUTATION_ARGUMENT_COL_3];
+    let p4 = &curr[PERMUTATION_ARGUMENT_COL_4];
+    let p5 = &curr[PERMUTATION_ARGUMENT_COL_5];
+    let p6 = &curr[PERMUTATION_ARGUMENT_COL_6];
+    let p7 = &curr[PERMUTATION_ARGUMENT_COL_7];
+    let p8 = &curr[PERMUTATION_ARGUMENT_COL_8];
 
     let ap0_next = &next[MEMORY_ADDR_SORTED_0];
     let ap1 = &curr[MEMORY_ADDR_SORTED_1];
     let ap2 = &curr[MEMORY_ADDR_SORTED_2];
     let ap3 = &curr[MEMORY_ADDR_SORTED_3];
+    let ap4 = &curr[MEMORY_ADDR_SORTED_4];
+    let ap5 = &curr[MEMORY_ADDR_SORTED_5];
+    let ap6 = &curr[MEMORY_ADDR_SORTED_6];
+    let ap7 = &curr[MEMORY_ADDR_SORTED_7];
+    let ap8 = &curr[MEMORY_ADDR_SORTED_8];
 
     let vp0_next = &next[MEMORY_VALUES_SORTED_0];
     let vp1 = &curr[MEMORY_VALUES_SORTED_1];
     let vp2 = &curr[MEMORY_VALUES_SORTED_2];
     let vp3 = &curr[MEMORY_VALUES_SORTED_3];
+    let vp4 = &curr[MEMORY_VALUES_SORTED_4];
+    let vp5 = &curr[MEMORY_VALUES_SORTED_5];
+    let vp6 = &curr[MEMORY_VALUES_SORTED_6];
+    let vp7 = &curr[MEMORY_VALUES_SORTED_7];
+    let vp8 = &curr[MEMORY_VALUES_SORTED_8];
 
     let a0_next = &next[FRAME_PC];
     let a1 = &curr[FRAME_DST_ADDR];
     let a2 = &curr[FRAME_OP0_ADDR];
     let a3 = &curr[FRAME_OP1_ADDR];
+    let a4 = &curr[RC_BUILTIN_ADDR];
+    let a5 = &curr[PEDERSEN_ADDR];
+    let a6 = &curr[POSEIDON_ADDR];
+    let a7 = &curr[KECCAK_ADDR];
+    let a8 = &curr[EC_OP_ADDR];
 
     let v0_next = &next[FRAME_INST];
     let v1 = &curr[FRAME_DST];
     let v2 = &curr[FRAME_OP0];
     let v3 = &curr[FRAME_OP1];
+    let v4 = &curr[RC_BUILTIN_VALUE];
+    let v5 = &curr[PEDERSEN_VALUE];
+    let v6 = &curr[POSEIDON_VALUE];
+    let v7 = &curr[KECCAK_VALUE];
+    let v8 = &curr[EC_OP_VALUE];
 
     constraints[PERMUTATION_ARGUMENT_0] =
         (z - (ap1 + alpha * vp1)) * p1 - (z - (a1 + alpha * v1)) * p0;
@@ -807,9 +2682,22 @@ fn permutation_argument(
     constraints[PERMUTATION_ARGUMENT_2] =
         (z - (ap3 + alpha * vp3)) * p3 - (z - (a3 + alpha * v3)) * p2;
     constraints[PERMUTATION_ARGUMENT_3] =
-        (z - (ap0_next + alpha * vp0_next)) * p0_next - (z - (a0_next + alpha * v0_next)) * p3;
+        (z - (ap4 + alpha * vp4)) * p4 - (z - (a4 + alpha * v4)) * p3;
+    constraints[PERMUTATION_ARGUMENT_4] =
+        (z - (ap5 + alpha * vp5)) * p5 - (z - (a5 + alpha * v5)) * p4;
+    constraints[PERMUTATION_ARGUMENT_5] =
+        (z - (ap6 + alpha * vp6)) * p6 - (z - (a6 + alpha * v6)) * p5;
+    constraints[PERMUTATION_ARGUMENT_6] =
+        (z - (ap7 + alpha * vp7)) * p7 - (z - (a7 + alpha * v7)) * p6;
+    constraints[PERMUTATION_ARGUMENT_7] =
+        (z - (ap8 + alpha * vp8)) * p8 - (z - (a8 + alpha * v8)) * p7;
+    constraints[PERMUTATION_ARGUMENT_8] =
+        (z - (ap0_next + alpha * vp0_next)) * p0_next - (z - (a0_next + alpha * v0_next)) * p8;
 }
 
+/// The sorted cells of the range check pool increase by at most one, going
+/// through the columns of a row and then to the next row, and are a
+/// permutation of the cells of the pool.
 fn permutation_argument_range_check(
     constraints: &mut [FE],
     frame: &Frame<Stark252PrimeField>,
@@ -820,30 +2708,345 @@ fn permutation_argument_range_check(
     let one = FieldElement::one();
     let z = &rap_challenges.z_range_check;
 
-    constraints[RANGE_CHECK_INCREASING_0] = (&curr[RANGE_CHECK_COL_1] - &curr[RANGE_CHECK_COL_2])
-        * (&curr[RANGE_CHECK_COL_2] - &curr[RANGE_CHECK_COL_1] - &one);
-    constraints[RANGE_CHECK_INCREASING_1] = (&curr[RANGE_CHECK_COL_2] - &curr[RANGE_CHECK_COL_3])
-        * (&curr[RANGE_CHECK_COL_3] - &curr[RANGE_CHECK_COL_2] - &one);
-    constraints[RANGE_CHECK_INCREASING_2] = (&curr[RANGE_CHECK_COL_3] - &next[RANGE_CHECK_COL_1])
-        * (&next[RANGE_CHECK_COL_1] - &curr[RANGE_CHECK_COL_3] - &one);
-
-    let p0 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1];
-    let p0_next = &next[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_1];
-    let p1 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_2];
-    let p2 = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK_COL_3];
-
-    let ap0_next = &next[RANGE_CHECK_COL_1];
-    let ap1 = &curr[RANGE_CHECK_COL_2];
-    let ap2 = &curr[RANGE_CHECK_COL_3];
-
-    let a0_next = &next[OFF_DST];
-    let a1 = &curr[OFF_OP0];
-    let a2 = &curr[OFF_OP1];
-
-    constraints[RANGE_CHECK_0] = (z - ap1) * p1 - (z - a1) * p0;
-    constraints[RANGE_CHECK_1] = (z - ap2) * p2 - (z - a2) * p1;
-    constraints[RANGE_CHECK_2] = (z - ap0_next) * p0_next - (z - a0_next) * p2;
+    let pool = RANGE_CHECK_POOL.len();
+    for i in 0..pool {
+        // The last cell of a row is followed by the first cell of the next one.
+        let (row, j) = if i + 1 < pool {
+            (curr, i + 1)
+        } else {
+            (next, 0)
+        };
+
+        let sorted = &curr[RANGE_CHECK_SORTED[i]];
+        let sorted_next = &row[RANGE_CHECK_SORTED[j]];
+        constraints[RANGE_CHECK_INCREASING + i] =
+            (sorted - sorted_next) * (sorted_next - sorted - &one);
+
+        let p = &curr[PERMUTATION_ARGUMENT_RANGE_CHECK[i]];
+        let p_next = &row[PERMUTATION_ARGUMENT_RANGE_CHECK[j]];
+        let a_next = &row[RANGE_CHECK_POOL[j]];
+        constraints[RANGE_CHECK + i] = (z - sorted_next) * p_next - (z - a_next) * p;
+    }
+}
+
+/// The cells of the range check builtin segment are in the rows where its
+/// selector is one, with consecutive addresses, and their values are split
+/// in 16 bit parts that are in the range check pool, so they're less than
+/// 2^128. The rest of the rows access the address zero. The addresses jump
+/// where the cells of the range check 96 builtin segment begin.
+fn range_check_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let one = FE::one();
+    let b16 = FE::from(2).pow(16u32);
+
+    let selector = &curr[RC_BUILTIN_SELECTOR];
+    let selector_next = &next[RC_BUILTIN_SELECTOR];
+    let addr = &curr[RC_BUILTIN_ADDR];
+    let value = &curr[RC_BUILTIN_VALUE];
+
+    let recomposed = RC_BUILTIN_PARTS
+        .iter()
+        .rev()
+        .fold(FE::zero(), |acc, part| &curr[*part] + &b16 * acc);
+
+    constraints[RC_BUILTIN_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[RC_BUILTIN_SELECTOR_DECREASING] = (&one - selector) * selector_next;
+    let rc96_begins = &next[RC96_BUILTIN_SELECTOR] - &curr[RC96_BUILTIN_SELECTOR];
+    constraints[RC_BUILTIN_ADDR_INCREASING] =
+        selector_next * (&next[RC_BUILTIN_ADDR] - addr - &one) * (&one - &rc96_begins);
+    constraints[RC_BUILTIN_DECOMPOSITION] = selector * (value - recomposed);
+    constraints[RC_BUILTIN_UNUSED_ADDR] = (&one - selector) * addr;
+    constraints[RC_BUILTIN_UNUSED_VALUE] = (&one - selector) * value;
+}
+
+/// The cells of the range check 96 builtin segment are the rows of the range
+/// check builtin from the one where its selector turns one, which it doesn't
+/// turn back, and the parts of their values above 96 bits are zero, so
+/// they're less than 2^96.
+fn range_check96_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let one = FE::one();
+
+    let selector = &curr[RC96_BUILTIN_SELECTOR];
+    let rc_selector = &curr[RC_BUILTIN_SELECTOR];
+
+    constraints[RC96_BUILTIN_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[RC96_BUILTIN_SELECTOR_INCREASING] =
+        selector * (&one - &next[RC96_BUILTIN_SELECTOR]);
+    for (i, part) in RC_BUILTIN_PARTS[6..].iter().enumerate() {
+        constraints[RC96_BUILTIN_TOP_PARTS + i] = rc_selector * selector * &curr[*part];
+    }
+}
+
+/// Each instance of the Pedersen builtin unpacks the bits of its inputs from
+/// their suffixes, the input shifted right by the row in its half, and adds
+/// the point of each set bit to the sum, copying it on the rest of the rows.
+/// The bits have to be the ones of the representative of the input, less
+/// than the modulus `2^251 + 17·2^192 + 1`, so when bit 251 is set, bits 197
+/// to 250 are zero, bits 193 to 195 are also zero when bit 196 is set, and
+/// the input is the modulus minus one when bit 192 is set too.
+///
+/// The instances where the selector is one read their inputs from the
+/// memory at their base address and the next one, and write the hash at the
+/// following one. The rest of the rows access the address zero.
+fn pedersen_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let periodic = frame.periodic_values();
+    let one = FE::one();
+    let two = FE::from(2);
+
+    let step = &periodic[pedersen::STEP];
+    let input_a = &periodic[pedersen::INPUT_A];
+    let input_b = &periodic[pedersen::INPUT_B];
+    let output = &periodic[pedersen::OUTPUT];
+
+    let suffix = &curr[PEDERSEN_SUFFIX];
+    let bit = &curr[PEDERSEN_BIT];
+    let [bit_251, bit_196, bit_192] = PEDERSEN_UNPACKING.map(|column| &curr[column]);
+
+    constraints[PEDERSEN_SUFFIX_SHIFT] = step * (bit - suffix + &two * &next[PEDERSEN_SUFFIX]);
+    constraints[PEDERSEN_SUFFIX_END] = (&one - step) * suffix;
+    constraints[PEDERSEN_BIT_BINARY] = bit * (bit - &one);
+    constraints[PEDERSEN_BIT_END] = (&one - step) * bit;
+
+    constraints[PEDERSEN_UNPACKING_251] = &periodic[pedersen::TOP_BIT] * (bit_251 - suffix);
+    constraints[PEDERSEN_UNPACKING_196] = &periodic[pedersen::BIT_196] * (bit_196 - bit_251 * bit);
+    constraints[PEDERSEN_UNPACKING_192] = &periodic[pedersen::BIT_192] * (bit_192 - bit_196 * bit);
+    for (i, column) in PEDERSEN_UNPACKING.iter().enumerate() {
+        constraints[PEDERSEN_UNPACKING_COPY + i] = step * (&next[*column] - &curr[*column]);
+    }
+    constraints[PEDERSEN_ZEROES_197] =
+        &periodic[pedersen::BIT_197] * bit_251 * (suffix - two.pow(54u32));
+    constraints[PEDERSEN_ZEROES_193] =
+        &periodic[pedersen::BIT_193] * bit_196 * (suffix - FE::from(8) - two.pow(58u32));
+    constraints[PEDERSEN_MAX_INPUT] = (input_a + input_b) * bit_192 * (suffix + &one);
+
+    let (x, y, slope) = (&curr[PEDERSEN_X], &curr[PEDERSEN_Y], &curr[PEDERSEN_SLOPE]);
+    let (x_next, y_next) = (&next[PEDERSEN_X], &next[PEDERSEN_Y]);
+    let point_x = &periodic[pedersen::POINT_X];
+    let point_y = &periodic[pedersen::POINT_Y];
+    let (shift_x, shift_y) = pedersen::shift_point();
+    let unchanged = (&one - bit) * (&one - output);
+
+    constraints[PEDERSEN_SLOPE_DEFINITION] = bit * (y - point_y) - slope * (x - point_x);
+    constraints[PEDERSEN_ADD_X] = bit * (slope * slope - x - point_x - x_next);
+    constraints[PEDERSEN_ADD_Y] = bit * (y + y_next) - slope * (x - x_next);
+    constraints[PEDERSEN_COPY_X] = &unchanged * (x_next - x);
+    constraints[PEDERSEN_COPY_Y] = &unchanged * (y_next - y);
+    constraints[PEDERSEN_START_X] = input_a * (x - shift_x);
+    constraints[PEDERSEN_START_Y] = input_a * (y - shift_y);
+
+    let selector = &curr[PEDERSEN_SELECTOR];
+    let selector_next = &next[PEDERSEN_SELECTOR];
+    let base = &curr[PEDERSEN_BASE];
+
+    constraints[PEDERSEN_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[PEDERSEN_SELECTOR_CONSTANT] = (&one - output) * (selector_next - selector);
+    constraints[PEDERSEN_SELECTOR_DECREASING] = (&one - selector) * selector_next;
+    constraints[PEDERSEN_BASE_INCREASING] = &next[PEDERSEN_BASE] - base - FE::from(3) * output;
+    constraints[PEDERSEN_ADDR_DEFINITION] = &curr[PEDERSEN_ADDR]
+        - selector * (input_a * base + input_b * (base + &one) + output * (base + &two));
+    constraints[PEDERSEN_VALUE_DEFINITION] =
+        &curr[PEDERSEN_VALUE] - selector * ((input_a + input_b) * suffix + output * x);
+}
+
+/// Each instance of the Poseidon builtin applies a round of the permutation
+/// on each row, from the cubes of the elements of the state plus the
+/// constants of the round, which only replace the last element in partial
+/// rounds. The instances where the selector is one read their inputs from the
+/// memory on their first rows and write their outputs on the rows after the
+/// last round, one cell per row, with the second and third elements carried
+/// down to their rows. The rest of the rows access the address zero.
+fn poseidon_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let periodic = &frame.periodic_values()[pedersen::PERIODIC_COLUMNS..];
+    let one = FE::one();
+
+    let full_round = &periodic[poseidon::FULL_ROUND];
+    let first = &periodic[poseidon::FIRST_CELL];
+    let second = &periodic[poseidon::SECOND_CELL];
+    let third = &periodic[poseidon::THIRD_CELL];
+    let last_row = &periodic[poseidon::LAST_ROW];
+
+    let state = POSEIDON_STATE.map(|column| &curr[column]);
+    let cubes = POSEIDON_CUBES.map(|column| &curr[column]);
+    let [carry_second, carry_third] = POSEIDON_CARRIES.map(|column| &curr[column]);
+    let added: [FE; 3] =
+        core::array::from_fn(|i| state[i] + &periodic[poseidon::ROUND_CONSTANTS[i]]);
+
+    // Partial rounds only cube the last element.
+    let sboxed: [FE; 3] = core::array::from_fn(|i| match i {
+        2 => cubes[i].clone(),
+        _ => full_round * cubes[i] + (&one - full_round) * &added[i],
+    });
+    let mixed = poseidon::mix(&sboxed);
+    for i in 0..3 {
+        constraints[POSEIDON_ROUND + i] =
+            &periodic[poseidon::ROUND] * (&next[POSEIDON_STATE[i]] - &mixed[i]);
+        constraints[POSEIDON_CUBE + i] = cubes[i] - added[i].pow(3u64);
+    }
+
+    constraints[POSEIDON_CARRY_SECOND] = first * (&next[POSEIDON_CARRIES[0]] - state[1]);
+    constraints[POSEIDON_CARRY_THIRD] = first * (&next[POSEIDON_CARRIES[1]] - state[2]);
+    constraints[POSEIDON_CARRY_THIRD_AGAIN] = second * (&next[POSEIDON_CARRIES[1]] - carry_third);
+
+    let selector = &curr[POSEIDON_SELECTOR];
+    let selector_next = &next[POSEIDON_SELECTOR];
+    let base = &curr[POSEIDON_BASE];
+    let access = first + second + third;
+
+    constraints[POSEIDON_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[POSEIDON_SELECTOR_CONSTANT] = (&one - last_row) * (selector_next - selector);
+    constraints[POSEIDON_SELECTOR_DECREASING] = (&one - selector) * selector_next;
+    constraints[POSEIDON_BASE_INCREASING] = &next[POSEIDON_BASE] - base - FE::from(6) * last_row;
+    constraints[POSEIDON_ADDR_DEFINITION] =
+        &curr[POSEIDON_ADDR] - selector * (access * base + &periodic[poseidon::CELL_OFFSET]);
+    constraints[POSEIDON_VALUE_DEFINITION] = &curr[POSEIDON_VALUE]
+        - selector * (first * state[0] + second * carry_second + third * carry_third);
+}
+
+/// Each instance of the Keccak builtin reads its input from the memory on
+/// its first rows and writes its output on the rows after the last round,
+/// one cell per row, with the cells shifted down to their rows. The layouts
+/// with the builtin check that the cells hold the bits of the state on the
+/// first row and the row after the last round. The rest of the rows access
+/// the address zero.
+fn keccak_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let periodic =
+        &frame.periodic_values()[pedersen::PERIODIC_COLUMNS + poseidon::PERIODIC_COLUMNS..];
+    let one = FE::one();
+
+    let last_row = &periodic[keccak::LAST_ROW];
+    let access = &periodic[keccak::ACCESS];
+    let shift = &periodic[keccak::SHIFT];
+
+    let selector = &curr[KECCAK_SELECTOR];
+    let selector_next = &next[KECCAK_SELECTOR];
+    let base = &curr[KECCAK_BASE];
+
+    constraints[KECCAK_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[KECCAK_SELECTOR_CONSTANT] = (&one - last_row) * (selector_next - selector);
+    constraints[KECCAK_SELECTOR_DECREASING] = (&one - selector) * selector_next;
+    constraints[KECCAK_BASE_INCREASING] =
+        &next[KECCAK_BASE] - base - FE::from(keccak::CELLS as u64) * last_row;
+    constraints[KECCAK_ADDR_DEFINITION] =
+        &curr[KECCAK_ADDR] - selector * (access * base + &periodic[keccak::CELL_OFFSET]);
+    constraints[KECCAK_VALUE_DEFINITION] =
+        &curr[KECCAK_VALUE] - selector * access * &curr[KECCAK_CELLS[0]];
+    for i in 0..KECCAK_CELLS.len() - 1 {
+        constraints[KECCAK_SHIFT + i] =
+            shift * (&next[KECCAK_CELLS[i]] - &curr[KECCAK_CELLS[i + 1]]);
+    }
+}
+
+/// Each instance of the EC op builtin adds `Q`, doubled once per row, to the
+/// partial sum on the rows of the set bits of `m`, from `P`. The instances
+/// where the selector is one read `P`, `Q` and `m` from the memory on their
+/// first rows, with the cells shifted down to their rows, and write `R` on
+/// the rows after the bits. The rest of the rows access the address zero.
+fn ec_op_builtin(constraints: &mut [FE], frame: &Frame<Stark252PrimeField>) {
+    let curr = frame.get_row(0);
+    let next = frame.get_row(1);
+    let periodic = &frame.periodic_values()
+        [pedersen::PERIODIC_COLUMNS + poseidon::PERIODIC_COLUMNS + keccak::PERIODIC_COLUMNS..];
+    let one = FE::one();
+    let two = FE::from(2);
+
+    let step = &periodic[ec_op::STEP];
+    let input = &periodic[ec_op::INPUT];
+    let last_row = &periodic[ec_op::LAST_ROW];
+
+    let suffix = &curr[EC_OP_SUFFIX];
+    let bit = &curr[EC_OP_BIT];
+    let [bit_251, bit_196, bit_192] = EC_OP_UNPACKING.map(|column| &curr[column]);
+
+    constraints[EC_OP_SUFFIX_SHIFT] = step * (bit - suffix + &two * &next[EC_OP_SUFFIX]);
+    constraints[EC_OP_SUFFIX_END] = (&one - step) * suffix;
+    constraints[EC_OP_BIT_BINARY] = bit * (bit - &one);
+    constraints[EC_OP_BIT_END] = (&one - step) * bit;
+
+    constraints[EC_OP_UNPACKING_251] = &periodic[ec_op::TOP_BIT] * (bit_251 - suffix);
+    constraints[EC_OP_UNPACKING_196] = &periodic[ec_op::BIT_196] * (bit_196 - bit_251 * bit);
+    constraints[EC_OP_UNPACKING_192] = &periodic[ec_op::BIT_192] * (bit_192 - bit_196 * bit);
+    for (i, column) in EC_OP_UNPACKING.iter().enumerate() {
+        constraints[EC_OP_UNPACKING_COPY + i] = step * (&next[*column] - &curr[*column]);
+    }
+    constraints[EC_OP_ZEROES_197] = &periodic[ec_op::BIT_197] * bit_251 * (suffix - two.pow(54u32));
+    constraints[EC_OP_ZEROES_193] =
+        &periodic[ec_op::BIT_193] * bit_196 * (suffix - FE::from(8) - two.pow(58u32));
+    constraints[EC_OP_MAX_INPUT] = input * bit_192 * (suffix + &one);
+
+    let (q_x, q_y) = (&curr[EC_OP_Q_X], &curr[EC_OP_Q_Y]);
+    let (q_x_next, q_y_next) = (&next[EC_OP_Q_X], &next[EC_OP_Q_Y]);
+    let doubling_slope = &curr[EC_OP_DOUBLING_SLOPE];
+
+    // The curve is y² = x³ + x + β.
+    constraints[EC_OP_DOUBLING_SLOPE_DEFINITION] =
+        step * (&two * q_y * doubling_slope - FE::from(3) * q_x * q_x - &one);
+    constraints[EC_OP_DOUBLING_X] =
+        step * (doubling_slope * doubling_slope - &two * q_x - q_x_next);
+    constraints[EC_OP_DOUBLING_Y] = step * (q_y + q_y_next - doubling_slope * (q_x - q_x_next));
+
+    let (x, y, slope) = (&curr[EC_OP_X], &curr[EC_OP_Y], &curr[EC_OP_SLOPE]);
+    let (x_next, y_next) = (&next[EC_OP_X], &next[EC_OP_Y]);
+    let unchanged = (&one - bit) * (&one - last_row);
+
+    constraints[EC_OP_SLOPE_DEFINITION] = bit * (y - q_y) - slope * (x - q_x);
+    constraints[EC_OP_X_DIFF_INVERSE] = bit * ((x - q_x) * &curr[EC_OP_X_DIFF_INV] - &one);
+    constraints[EC_OP_ADD_X] = bit * (slope * slope - x - q_x - x_next);
+    constraints[EC_OP_ADD_Y] = bit * (y + y_next) - slope * (x - x_next);
+    constraints[EC_OP_COPY_X] = &unchanged * (x_next - x);
+    constraints[EC_OP_COPY_Y] = &unchanged * (y_next - y);
+
+    let selector = &curr[EC_OP_SELECTOR];
+    let selector_next = &next[EC_OP_SELECTOR];
+    let base = &curr[EC_OP_BASE];
+    let input_access = &periodic[ec_op::INPUT_ACCESS];
+    let output_x = &periodic[ec_op::OUTPUT_X];
+    let output_y = &periodic[ec_op::OUTPUT_Y];
+    let access = input_access + output_x + output_y;
+
+    constraints[EC_OP_SELECTOR_BIT] = selector * (selector - &one);
+    constraints[EC_OP_SELECTOR_CONSTANT] = (&one - last_row) * (selector_next - selector);
+    constraints[EC_OP_SELECTOR_DECREASING] = (&one - selector) * selector_next;
+    constraints[EC_OP_BASE_INCREASING] =
+        &next[EC_OP_BASE] - base - FE::from(ec_op::CELLS as u64) * last_row;
+    constraints[EC_OP_ADDR_DEFINITION] =
+        &curr[EC_OP_ADDR] - selector * (access * base + &periodic[ec_op::CELL_OFFSET]);
+    constraints[EC_OP_VALUE_DEFINITION] = &curr[EC_OP_VALUE]
+        - selector * (input_access * &curr[EC_OP_CELLS[0]] + output_x * x + output_y * y);
+
+    let inputs = [x, y, q_x, q_y, suffix];
+    for (i, (cell, value)) in EC_OP_CELLS.iter().zip(inputs).enumerate() {
+        constraints[EC_OP_INPUT + i] = input * (&curr[*cell] - value);
+    }
+    let shift = &periodic[ec_op::SHIFT];
+    for i in 0..EC_OP_CELLS.len() - 1 {
+        constraints[EC_OP_SHIFT + i] = shift * (&next[EC_OP_CELLS[i]] - &curr[EC_OP_CELLS[i + 1]]);
+    }
+}
+
+/// The frame without the columns of the Keccak bits, which the constraints of
+/// every layout read as in the layouts without them.
+fn frame_without_keccak_bits(frame: &Frame<Stark252PrimeField>) -> Frame<Stark252PrimeField> {
+    let bits = MAIN_COLUMNS..MAIN_COLUMNS + keccak::COLUMNS;
+    let data = (0..frame.num_rows())
+        .flat_map(|row| {
+            let values = frame.get_row(row);
+            values[..bits.start].iter().chain(&values[bits.end..])
+        })
+        .cloned()
+        .collect();
+    Frame::new(data, frame.num_columns() - keccak::COLUMNS)
+        .with_periodic_values(frame.periodic_values().to_vec())
 }
+
 fn frame_inst_size(frame_row: &[FE]) -> FE {
     &frame_row[F_OP_1_VAL] + FE::one()
 }
@@ -859,16 +3062,26 @@ mod test {
 
     use crate::{
         air::{
-            cairo_air::air::{
-                add_program_in_public_input_section, CairoAIR, PublicInputs, OFF_DST, OFF_OP1,
+            cairo_air::{
+                air::{
+                    add_public_memory_in_public_input_section, CairoAIR, MemorySegment,
+                    PublicInputs, OFF_DST, OFF_OP1,
+                },
+                layout::{Builtin, Layout},
+                pedersen,
             },
             context::ProofOptions,
             debug::validate_trace,
+            gadgets::public_memory::MemoryPage,
             trace::TraceTable,
             traits::AIR,
         },
         cairo_run::run::Error,
-        cairo_vm::{cairo_mem::CairoMemory, cairo_trace::CairoTrace},
+        cairo_vm::{
+            cairo_mem::CairoMemory,
+            cairo_trace::{CairoTrace, RegistersState},
+        },
+        prover::ProvingError,
         Domain,
     };
 
@@ -909,35 +3122,49 @@ mod test {
             blowup_factor: 4,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         };
 
-        let cairo_air = CairoAIR::new(proof_options, 128, raw_trace.steps());
+        let cairo_air = CairoAIR::new(proof_options, 128);
 
         // PC FINAL AND AP FINAL are not computed correctly since they are extracted after padding to
         // power of two and therefore are zero
         let last_register_state = &raw_trace.rows[raw_trace.steps() - 1];
-        let mut public_input = PublicInputs {
+        let public_input = PublicInputs {
             program,
             ap_final: FieldElement::from(last_register_state.ap),
             pc_final: FieldElement::from(last_register_state.pc),
+            fp_final: FieldElement::from(last_register_state.fp),
             pc_init: FieldElement::from(raw_trace.rows[0].pc),
             ap_init: FieldElement::from(raw_trace.rows[0].ap),
             fp_init: FieldElement::from(raw_trace.rows[0].fp),
             range_check_max: None,
             range_check_min: None,
+            memory_holes: None,
+            program_segment: None,
+            execution_segment: None,
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
             num_steps: raw_trace.steps(),
         };
 
-        let main_trace = cairo_air
-            .build_main_trace(&(raw_trace, memory), &mut public_input)
+        let (main_trace, public_input) = cairo_air
+            .build_main_trace(&(raw_trace, memory), &public_input)
             .unwrap();
-        let mut trace_polys = main_trace.compute_trace_polys();
+        let mut trace_polys = main_trace.compute_trace_polys().unwrap();
         let mut transcript = DefaultTranscript::new();
         let rap_challenges = cairo_air.build_rap_challenges(&mut transcript);
 
         let aux_trace =
             cairo_air.build_auxiliary_trace(&main_trace, &rap_challenges, &public_input);
-        let aux_polys = aux_trace.compute_trace_polys();
+        let aux_polys = aux_trace.compute_trace_polys().unwrap();
 
         trace_polys.extend_from_slice(&aux_polys);
 
@@ -949,7 +3176,8 @@ mod test {
             &domain,
             &public_input,
             &rap_challenges
-        ));
+        )
+        .is_valid());
     }
 
     #[test]
@@ -960,6 +3188,7 @@ mod test {
             fp_init: FieldElement::zero(),
             pc_final: FieldElement::zero(),
             ap_final: FieldElement::zero(),
+            fp_final: FieldElement::zero(),
             program: vec![
                 FieldElement::from(10),
                 FieldElement::from(20),
@@ -967,6 +3196,18 @@ mod test {
             ],
             range_check_max: None,
             range_check_min: None,
+            memory_holes: None,
+            program_segment: None,
+            execution_segment: None,
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
             num_steps: 1,
         };
 
@@ -986,7 +3227,7 @@ mod test {
             FieldElement::zero(),
             FieldElement::zero(),
         ];
-        let (ap, vp) = add_program_in_public_input_section(&a, &v, &dummy_public_input);
+        let (ap, vp) = add_public_memory_in_public_input_section(&a, &v, &dummy_public_input);
         assert_eq!(
             ap,
             vec![
@@ -1011,6 +3252,366 @@ mod test {
         );
     }
 
+    #[test]
+    fn malformed_executions_have_no_public_input() {
+        let memory = CairoMemory::new(std::collections::HashMap::from([(
+            1,
+            FieldElement::from(10),
+        )]));
+        let trace = CairoTrace {
+            rows: vec![RegistersState {
+                pc: 1,
+                fp: 5,
+                ap: 5,
+            }],
+        };
+        assert!(PublicInputs::try_from_regs_and_mem(&trace, &memory, 1).is_ok());
+        assert!(PublicInputs::try_from_regs_and_mem(&trace, &memory, 2).is_err());
+        let empty = CairoTrace { rows: Vec::new() };
+        assert!(PublicInputs::try_from_regs_and_mem(&empty, &memory, 1).is_err());
+    }
+
+    #[test]
+    fn public_pages_are_added_after_the_program() {
+        let public_input = PublicInputs {
+            pc_init: FieldElement::zero(),
+            ap_init: FieldElement::zero(),
+            fp_init: FieldElement::zero(),
+            pc_final: FieldElement::zero(),
+            ap_final: FieldElement::zero(),
+            fp_final: FieldElement::zero(),
+            program: vec![FieldElement::from(10)],
+            range_check_max: None,
+            range_check_min: None,
+            memory_holes: None,
+            program_segment: None,
+            execution_segment: None,
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: vec![MemoryPage::new(
+                FieldElement::from(7),
+                vec![FieldElement::from(70), FieldElement::from(80)],
+            )],
+            num_steps: 1,
+        };
+
+        let a = vec![
+            FieldElement::one(),
+            FieldElement::zero(),
+            FieldElement::zero(),
+            FieldElement::zero(),
+        ];
+        let v = vec![
+            FieldElement::from(10),
+            FieldElement::zero(),
+            FieldElement::zero(),
+            FieldElement::zero(),
+        ];
+        let (ap, vp) = add_public_memory_in_public_input_section(&a, &v, &public_input);
+        assert_eq!(
+            ap,
+            vec![
+                FieldElement::one(),
+                FieldElement::one(),
+                FieldElement::from(7),
+                FieldElement::from(8)
+            ]
+        );
+        assert_eq!(
+            vp,
+            vec![
+                FieldElement::from(10),
+                FieldElement::from(10),
+                FieldElement::from(70),
+                FieldElement::from(80)
+            ]
+        );
+        assert_eq!(public_input.public_memory_rows(), 1);
+
+        // The commitment of a segment changes with any of its public cells.
+        let mut other = public_input.clone();
+        other.public_memory[0].values[1] = FieldElement::from(81);
+        assert_ne!(public_input.memory_commitment(), other.memory_commitment());
+        other.public_memory[0].values[1] = FieldElement::from(80);
+        assert_eq!(public_input.memory_commitment(), other.memory_commitment());
+    }
+
+    #[test]
+    fn program_hash_is_the_hash_chain_of_the_program() {
+        let (a, b, c) = (
+            FieldElement::from(10),
+            FieldElement::from(20),
+            FieldElement::from(30),
+        );
+        let public_input = PublicInputs {
+            pc_init: FieldElement::zero(),
+            ap_init: FieldElement::zero(),
+            fp_init: FieldElement::zero(),
+            pc_final: FieldElement::zero(),
+            ap_final: FieldElement::zero(),
+            fp_final: FieldElement::zero(),
+            program: vec![a.clone(), b.clone(), c.clone()],
+            range_check_max: None,
+            range_check_min: None,
+            memory_holes: None,
+            program_segment: None,
+            execution_segment: None,
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
+            num_steps: 1,
+        };
+        let expected = pedersen::hash(
+            &FieldElement::from(3),
+            &pedersen::hash(&a, &pedersen::hash(&b, &c)),
+        );
+        assert_eq!(public_input.program_hash(), expected);
+
+        let swapped = PublicInputs {
+            program: vec![a, c, b],
+            ..public_input.clone()
+        };
+        assert_ne!(swapped.to_bytes_be(), public_input.to_bytes_be());
+    }
+
+    #[test]
+    fn segments_are_checked_against_the_registers() {
+        let public_input = PublicInputs {
+            pc_init: FieldElement::one(),
+            ap_init: FieldElement::from(10),
+            fp_init: FieldElement::from(10),
+            pc_final: FieldElement::from(5),
+            ap_final: FieldElement::from(20),
+            fp_final: FieldElement::from(10),
+            program: vec![FieldElement::from(10)],
+            range_check_max: None,
+            range_check_min: None,
+            memory_holes: None,
+            program_segment: Some(MemorySegment {
+                begin_addr: 1,
+                stop_ptr: 5,
+            }),
+            execution_segment: Some(MemorySegment {
+                begin_addr: 10,
+                stop_ptr: 20,
+            }),
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
+            num_steps: 1,
+        };
+        assert!(public_input.check_segments().is_ok());
+
+        let moved = PublicInputs {
+            execution_segment: Some(MemorySegment {
+                begin_addr: 10,
+                stop_ptr: 21,
+            }),
+            ..public_input.clone()
+        };
+        assert!(moved.check_segments().is_err());
+        assert_ne!(moved.to_bytes_be(), public_input.to_bytes_be());
+
+        let reversed = PublicInputs {
+            range_check_builtin: Some(MemorySegment {
+                begin_addr: 30,
+                stop_ptr: 25,
+            }),
+            ..public_input
+        };
+        assert!(reversed.check_segments().is_err());
+    }
+
+    #[test]
+    fn public_input_claims_are_validated() {
+        let segment = |begin_addr, stop_ptr| {
+            Some(MemorySegment {
+                begin_addr,
+                stop_ptr,
+            })
+        };
+        let public_input = PublicInputs {
+            pc_init: FieldElement::one(),
+            ap_init: FieldElement::from(10),
+            fp_init: FieldElement::from(10),
+            pc_final: FieldElement::from(5),
+            ap_final: FieldElement::from(20),
+            fp_final: FieldElement::from(10),
+            program: vec![FieldElement::from(10); 5],
+            range_check_max: Some(10),
+            range_check_min: Some(0),
+            memory_holes: None,
+            program_segment: segment(1, 5),
+            execution_segment: segment(10, 20),
+            range_check_builtin: segment(20, 25),
+            range_check96_builtin: None,
+            pedersen_builtin: segment(25, 31),
+            poseidon_builtin: None,
+            keccak_builtin: None,
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
+            num_steps: 1,
+        };
+        assert!(public_input.validate().is_ok());
+
+        let outside_the_program = PublicInputs {
+            pc_final: FieldElement::from(6),
+            program_segment: segment(1, 6),
+            ..public_input.clone()
+        };
+        assert!(outside_the_program.validate().is_err());
+
+        let backwards = PublicInputs {
+            ap_final: FieldElement::from(9),
+            execution_segment: None,
+            ..public_input.clone()
+        };
+        assert!(backwards.validate().is_err());
+
+        let over_the_program = PublicInputs {
+            range_check_builtin: segment(3, 8),
+            ..public_input.clone()
+        };
+        assert!(over_the_program.validate().is_err());
+
+        // Counts that would overflow the trace length are rejected.
+        let too_long = PublicInputs {
+            num_steps: usize::MAX,
+            ..public_input.clone()
+        };
+        assert!(matches!(
+            CairoAIR::for_public_input(ProofOptions::new(4, 3, 3), &too_long),
+            Err(ProvingError::WrongParameter(_))
+        ));
+
+        let over_the_execution = PublicInputs {
+            range_check_builtin: segment(15, 19),
+            ..public_input.clone()
+        };
+        assert!(over_the_execution.validate().is_err());
+
+        let overlapping = PublicInputs {
+            pedersen_builtin: segment(24, 30),
+            ..public_input
+        };
+        assert!(overlapping.validate().is_err());
+    }
+
+    #[test]
+    fn dynamic_layouts_take_the_builtin_ratios_of_the_public_input() {
+        let public_input = PublicInputs {
+            pc_init: FieldElement::one(),
+            ap_init: FieldElement::from(10),
+            fp_init: FieldElement::from(10),
+            pc_final: FieldElement::from(5),
+            ap_final: FieldElement::from(20),
+            fp_final: FieldElement::from(10),
+            program: vec![FieldElement::from(10); 5],
+            range_check_max: Some(10),
+            range_check_min: Some(0),
+            memory_holes: None,
+            program_segment: None,
+            execution_segment: None,
+            range_check_builtin: None,
+            range_check96_builtin: None,
+            pedersen_builtin: None,
+            poseidon_builtin: None,
+            keccak_builtin: Some(MemorySegment {
+                begin_addr: 20,
+                stop_ptr: 36,
+            }),
+            ec_op_builtin: None,
+            output_builtin: None,
+            builtin_ratios: None,
+            public_memory: Vec::new(),
+            num_steps: 10,
+        };
+        assert_eq!(public_input.dynamic_ratios(), [(Builtin::Keccak, 10)]);
+
+        let proof_options = ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        };
+        let air = CairoAIR::new(proof_options, 16).with_layout(Layout::Dynamic);
+        assert!(!air.keccak_builtin);
+        assert!(air.with_public_input(&public_input).is_err());
+
+        let with_ratios = |ratios: Vec<(Builtin, usize)>| PublicInputs {
+            builtin_ratios: Some(ratios),
+            ..public_input.clone()
+        };
+        let dynamic_air = air
+            .with_public_input(&with_ratios(public_input.dynamic_ratios()))
+            .unwrap();
+        assert!(dynamic_air.keccak_builtin);
+        assert!(air
+            .with_public_input(&with_ratios(vec![(Builtin::Keccak, 11)]))
+            .is_err());
+        assert!(air
+            .with_public_input(&with_ratios(vec![(Builtin::Keccak, 0)]))
+            .is_err());
+        assert!(air
+            .with_public_input(&with_ratios(vec![
+                (Builtin::Keccak, 5),
+                (Builtin::Keccak, 5)
+            ]))
+            .is_err());
+        assert!(air
+            .with_public_input(&with_ratios(vec![(Builtin::Pedersen, 5)]))
+            .is_err());
+
+        let fixed_air = air.with_layout(Layout::StarknetWithKeccak);
+        assert!(fixed_air
+            .with_public_input(&with_ratios(public_input.dynamic_ratios()))
+            .is_err());
+
+        // Only dynamic layouts have the range check 96 builtin.
+        let range_checks96 = PublicInputs {
+            range_check96_builtin: Some(MemorySegment {
+                begin_addr: 36,
+                stop_ptr: 40,
+            }),
+            ..public_input.clone()
+        };
+        assert_eq!(
+            range_checks96.dynamic_ratios(),
+            [(Builtin::RangeCheck96, 2), (Builtin::Keccak, 10)]
+        );
+        let ratios = PublicInputs {
+            builtin_ratios: Some(range_checks96.dynamic_ratios()),
+            ..range_checks96
+        };
+        assert!(air.with_public_input(&ratios).is_ok());
+        assert!(fixed_air
+            .with_public_input(&PublicInputs {
+                builtin_ratios: None,
+                ..ratios
+            })
+            .is_err());
+    }
+
     #[test]
     fn test_build_auxiliary_trace_sort_columns_by_memory_address() {
         let a = vec![
@@ -1107,7 +3708,7 @@ mod test {
         ];
         let table = TraceTable::<Stark252PrimeField>::new_from_cols(&columns);
 
-        let (col, rc_min, rc_max) = get_missing_values_offset_columns(&table, &[0, 1, 2]);
+        let (col, rc_min, rc_max) = get_missing_values_offset_columns(&table, &[0, 1, 2], &[]);
         assert_eq!(col, expected_col);
         assert_eq!(rc_min, 1);
         assert_eq!(rc_max, 7);
@@ -0,0 +1,231 @@
+//! EC op builtin of the Cairo AIR.
+//!
+//! An instance computes `R = P + m·Q` on the STARK curve from its input cells
+//! `P`, `Q` and `m`, and writes `R` to its output cells. An instance takes
+//! `EC_OP_ROWS` rows: each of the first `M_BITS` rows holds a bit of `m`, from
+//! the least significant one, with `Q` doubled as many times as the row, and
+//! adds it to the partial sum, which starts at `P`, when the bit is set. The
+//! rows after them keep the sum, which is `R`.
+
+use crate::{
+    air::cairo_air::pedersen::{self, Point},
+    FE,
+};
+
+/// Rows of an instance.
+pub const EC_OP_ROWS: usize = 256;
+/// Bits of `m`, the ones of a field element.
+pub const M_BITS: usize = pedersen::INPUT_BITS;
+/// Input cells of an instance: `P`, `Q` and `m`.
+pub const INPUT_CELLS: usize = 5;
+/// Cells of an instance, the inputs followed by `R`.
+pub const CELLS: usize = INPUT_CELLS + 2;
+/// Row where the sum is `R`.
+pub const OUTPUT_ROW: usize = M_BITS;
+
+// Periodic columns
+/// One on the rows of the bits of `m`.
+pub const STEP: usize = 0;
+/// One on the row of the most significant bit of `m`.
+pub const TOP_BIT: usize = 1;
+/// One on the rows of the bits 192, 193, 196 and 197 of `m`, which check that
+/// the bits are the ones of a field element, as in the Pedersen builtin.
+pub const BIT_192: usize = 2;
+pub const BIT_193: usize = 3;
+pub const BIT_196: usize = 4;
+pub const BIT_197: usize = 5;
+/// One on the first row of an instance, where the cells hold the inputs.
+pub const INPUT: usize = 6;
+/// One on the rows where the cells are shifted to the next row.
+pub const SHIFT: usize = 7;
+/// One on the rows where the first cell is in the memory.
+pub const INPUT_ACCESS: usize = 8;
+/// One on the rows where the coordinates of `R` are in the memory.
+pub const OUTPUT_X: usize = 9;
+pub const OUTPUT_Y: usize = 10;
+/// Offset from the first cell of the instance of the cell accessed on the
+/// row.
+pub const CELL_OFFSET: usize = 11;
+/// One on the last row of an instance.
+pub const LAST_ROW: usize = 12;
+pub const PERIODIC_COLUMNS: usize = 13;
+
+/// Generator of the STARK curve, which every unused instance takes as `P` and
+/// `Q`.
+pub fn generator() -> Point {
+    (
+        FE::from_hex("1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca").unwrap(),
+        FE::from_hex("5668060aa49730b7be4801df46ec62de53ecd11abe43a32873000c36e8dc1f").unwrap(),
+    )
+}
+
+/// Values of the periodic columns over an instance. In a trace shorter than
+/// an instance, there is no room for the builtin, and they're zero.
+pub fn periodic_columns(trace_length: usize) -> Vec<Vec<FE>> {
+    if trace_length < EC_OP_ROWS {
+        return vec![vec![FE::zero()]; PERIODIC_COLUMNS];
+    }
+    let indicator = |rows: &dyn Fn(usize) -> bool| -> Vec<FE> {
+        (0..EC_OP_ROWS)
+            .map(|row| if rows(row) { FE::one() } else { FE::zero() })
+            .collect()
+    };
+    let cell_offset = (0..EC_OP_ROWS)
+        .map(|row| match row {
+            _ if row < INPUT_CELLS => FE::from(row as u64),
+            _ if row == OUTPUT_ROW => FE::from(INPUT_CELLS as u64),
+            _ if row == OUTPUT_ROW + 1 => FE::from(INPUT_CELLS as u64 + 1),
+            _ => FE::zero(),
+        })
+        .collect();
+    vec![
+        indicator(&|row| row < M_BITS),
+        indicator(&|row| row == M_BITS - 1),
+        indicator(&|row| row == 192),
+        indicator(&|row| row == 193),
+        indicator(&|row| row == 196),
+        indicator(&|row| row == 197),
+        indicator(&|row| row == 0),
+        indicator(&|row| row < INPUT_CELLS - 1),
+        indicator(&|row| row < INPUT_CELLS),
+        indicator(&|row| row == OUTPUT_ROW),
+        indicator(&|row| row == OUTPUT_ROW + 1),
+        cell_offset,
+        indicator(&|row| row == EC_OP_ROWS - 1),
+    ]
+}
+
+/// Columns of an instance, one value per row.
+pub struct InstanceColumns {
+    /// `m` shifted right by the row.
+    pub suffix: Vec<FE>,
+    pub bit: Vec<FE>,
+    /// Products of the bits 251, 196 and 192 of `m`, from the first factor,
+    /// in each row.
+    pub unpacking: [Vec<FE>; 3],
+    /// `Q` doubled as many times as the row, and the slope of the tangent at
+    /// it.
+    pub q_x: Vec<FE>,
+    pub q_y: Vec<FE>,
+    pub doubling_slope: Vec<FE>,
+    /// The partial sum, the slope of the line through it and the doubled `Q`
+    /// when the bit is set, and the inverse of the difference of their x
+    /// coordinates, which can't be zero.
+    pub x: Vec<FE>,
+    pub y: Vec<FE>,
+    pub slope: Vec<FE>,
+    pub x_diff_inv: Vec<FE>,
+}
+
+/// Columns of the instance computing `p + m·q`, or `None` if the sum adds
+/// points with the same x coordinate or doubles one with zero y coordinate,
+/// like the builtin refuses to.
+pub fn instance_columns(p: &Point, q: &Point, m: &FE) -> Option<InstanceColumns> {
+    let mut columns = InstanceColumns {
+        suffix: Vec::with_capacity(EC_OP_ROWS),
+        bit: Vec::with_capacity(EC_OP_ROWS),
+        unpacking: Default::default(),
+        q_x: Vec::with_capacity(EC_OP_ROWS),
+        q_y: Vec::with_capacity(EC_OP_ROWS),
+        doubling_slope: Vec::with_capacity(EC_OP_ROWS),
+        x: Vec::with_capacity(EC_OP_ROWS),
+        y: Vec::with_capacity(EC_OP_ROWS),
+        slope: Vec::with_capacity(EC_OP_ROWS),
+        x_diff_inv: Vec::with_capacity(EC_OP_ROWS),
+    };
+    let bits = pedersen::bits(m);
+    let mut suffix = vec![FE::zero(); EC_OP_ROWS];
+    for row in (0..M_BITS).rev() {
+        suffix[row] = FE::from(2) * &suffix[row + 1] + FE::from(bits[row] as u64);
+    }
+    let bit_251 = bits[M_BITS - 1];
+    let bit_196 = bit_251 && bits[196];
+    let bit_192 = bit_196 && bits[192];
+
+    let (mut sum, mut doubled) = (p.clone(), q.clone());
+    for (row, suffix) in suffix.into_iter().enumerate() {
+        let bit = row < M_BITS && bits[row];
+        columns.suffix.push(suffix);
+        columns.bit.push(FE::from(bit as u64));
+        for (column, bit) in columns
+            .unpacking
+            .iter_mut()
+            .zip([bit_251, bit_196, bit_192])
+        {
+            column.push(FE::from(bit as u64));
+        }
+        columns.q_x.push(doubled.0.clone());
+        columns.q_y.push(doubled.1.clone());
+        columns.x.push(sum.0.clone());
+        columns.y.push(sum.1.clone());
+        if bit {
+            let x_diff = &sum.0 - &doubled.0;
+            if x_diff == FE::zero() {
+                return None;
+            }
+            let (next, slope) = pedersen::add(&sum, &doubled);
+            columns.slope.push(slope);
+            columns.x_diff_inv.push(x_diff.inv());
+            sum = next;
+        } else {
+            columns.slope.push(FE::zero());
+            columns.x_diff_inv.push(FE::zero());
+        }
+        if row < M_BITS {
+            if doubled.1 == FE::zero() {
+                return None;
+            }
+            let (next, slope) = pedersen::double(&doubled);
+            columns.doubling_slope.push(slope);
+            doubled = next;
+        } else {
+            columns.doubling_slope.push(FE::zero());
+        }
+    }
+    Some(columns)
+}
+
+/// `p + m·q`, or `None` if the builtin refuses to compute it.
+pub fn ec_op(p: &Point, q: &Point, m: &FE) -> Option<Point> {
+    let columns = instance_columns(p, q, m)?;
+    Some((columns.x[OUTPUT_ROW].clone(), columns.y[OUTPUT_ROW].clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ec_op_matches_a_double_and_add() {
+        let p = pedersen::shift_point();
+        let r = ec_op(&p, &generator(), &FE::from(1234)).unwrap();
+        let expected = (
+            FE::from_hex("1df2a62bfac04ea9b6b3c07ec75e57bfe6ac00b4e84f97073772e6a62a66943")
+                .unwrap(),
+            FE::from_hex("34c605728e01f307a893ab2cba17f62ff3aeb25e71abfec94d074a0c7a91e09")
+                .unwrap(),
+        );
+        assert_eq!(r, expected);
+        assert_eq!(ec_op(&p, &generator(), &FE::zero()).unwrap(), p);
+    }
+
+    #[test]
+    fn sum_of_points_with_the_same_x_is_refused() {
+        let g = generator();
+        assert!(instance_columns(&g, &g, &FE::one()).is_none());
+    }
+
+    #[test]
+    fn unpacking_holds_the_top_bits_of_m() {
+        let p = pedersen::shift_point();
+        let columns = instance_columns(&p, &generator(), &-FE::one()).unwrap();
+        assert_eq!(columns.suffix[0], -FE::one());
+        assert_eq!(columns.unpacking[2][0], FE::one());
+        assert_eq!(columns.suffix[M_BITS], FE::zero());
+        assert_eq!(
+            columns.x[OUTPUT_ROW],
+            FE::from_hex("1e5fddaa97b12ddde1db346bbdc5fbb9c1a5a7c897bd5db74b9b096f21fa914")
+                .unwrap()
+        );
+    }
+}
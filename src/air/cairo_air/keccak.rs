@@ -0,0 +1,459 @@
+//! Keccak builtin of the Cairo AIR.
+//!
+//! An instance applies Keccak-f[1600] to its input, eight cells of 200 bits
+//! whose bits, from the least significant one of the first cell, are the
+//! ones of the state, lane after lane. An instance takes `KECCAK_ROWS` rows,
+//! with the state before each round on its row and the output on the row
+//! after the last round. The rows after it keep applying rounds without
+//! constants, and the last one leads to the input of the next instance.
+//!
+//! Every bit of the state has its own column, with the parities of the
+//! columns of the state, their effect in θ and the state after θ, so that no
+//! constraint has a degree above 3. These columns are only in the trace of
+//! the layouts with the builtin, after the main columns. The AIR has no pool
+//! of diluted values, so the bits aren't diluted.
+
+use crate::FE;
+use lambdaworks_math::traits::ByteConversion;
+
+/// Rows of an instance.
+pub const KECCAK_ROWS: usize = 32;
+pub const ROUNDS: usize = 24;
+/// Lanes of 64 bits of the state, indexed by `x + 5 y`.
+pub const LANES: usize = 25;
+/// Input cells of an instance, followed by as many output cells.
+pub const INPUT_CELLS: usize = 8;
+pub const CELLS: usize = 2 * INPUT_CELLS;
+pub const CELL_BITS: usize = 200;
+
+const LANE_BITS: usize = 64;
+/// Bits of the state, which are the first columns of the bits of an instance.
+pub const STATE_BITS: usize = LANES * LANE_BITS;
+const PARITY_BITS: usize = 5 * LANE_BITS;
+const CELL_BYTES: usize = CELL_BITS / 8;
+/// Row of the output of an instance.
+const OUTPUT_ROW: usize = ROUNDS;
+
+// Periodic columns
+/// One on the last row of an instance.
+pub const LAST_ROW: usize = 0;
+/// One on the rows where the cells hold the bits of the state: the input and
+/// the output.
+pub const PACKED_ROW: usize = 1;
+/// One on the rows where the cells are shifted to the next row.
+pub const SHIFT: usize = 2;
+/// One on the rows where the first cell is in the memory.
+pub const ACCESS: usize = 3;
+/// Offset from the first cell of the instance of the cell accessed on the
+/// row.
+pub const CELL_OFFSET: usize = 4;
+/// Bits of the constant of ι in the round of the row, from the least
+/// significant one.
+pub const ROUND_CONSTANT: usize = 5;
+pub const PERIODIC_COLUMNS: usize = ROUND_CONSTANT + LANE_BITS;
+
+/// Columns of the bits of an instance.
+pub const COLUMNS: usize = 2 * STATE_BITS + 2 * PARITY_BITS + LANE_BITS;
+/// Constraints of the bits of an instance, with the packing of the cells.
+pub const CONSTRAINTS: usize = INPUT_CELLS + 3 * STATE_BITS + 3 * PARITY_BITS + LANE_BITS;
+
+/// Rotation of each lane in ρ, indexed by `[x][y]`.
+const ROTATIONS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// Constants of ι, added to the lane `(0, 0)`.
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+fn lane(x: usize, y: usize) -> usize {
+    x + 5 * y
+}
+
+/// Column of the bit `z` of the lane `(x, y)` of the state, which is also
+/// its bit in the cells.
+pub fn state_column(x: usize, y: usize, z: usize) -> usize {
+    lane(x, y) * LANE_BITS + z
+}
+
+/// Column of the bit `z` of the parity of the column `x`.
+pub fn parity_column(x: usize, z: usize) -> usize {
+    STATE_BITS + x * LANE_BITS + z
+}
+
+/// Column of the bit `z` added by θ to the column `x`.
+pub fn theta_effect_column(x: usize, z: usize) -> usize {
+    STATE_BITS + PARITY_BITS + x * LANE_BITS + z
+}
+
+/// Column of the bit `z` of the lane `(x, y)` after θ.
+pub fn theta_column(x: usize, y: usize, z: usize) -> usize {
+    STATE_BITS + 2 * PARITY_BITS + lane(x, y) * LANE_BITS + z
+}
+
+/// Column of the bit `z` of the lane `(0, 0)` after θ and ι.
+pub fn iota_column(z: usize) -> usize {
+    2 * STATE_BITS + 2 * PARITY_BITS + z
+}
+
+fn round_constant(round: usize) -> u64 {
+    ROUND_CONSTANTS.get(round).copied().unwrap_or(0)
+}
+
+/// Values of the periodic columns over an instance. In a trace shorter than
+/// an instance, there is no room for the builtin, and they're zero.
+pub fn periodic_columns(trace_length: usize) -> Vec<Vec<FE>> {
+    if trace_length < KECCAK_ROWS {
+        return vec![vec![FE::zero()]; PERIODIC_COLUMNS];
+    }
+    let indicator = |rows: &dyn Fn(usize) -> bool| -> Vec<FE> {
+        (0..KECCAK_ROWS)
+            .map(|row| if rows(row) { FE::one() } else { FE::zero() })
+            .collect()
+    };
+    let mut columns = vec![
+        indicator(&|row| row == KECCAK_ROWS - 1),
+        indicator(&|row| row == 0 || row == OUTPUT_ROW),
+        indicator(&|row| row < INPUT_CELLS - 1 || (OUTPUT_ROW..KECCAK_ROWS - 1).contains(&row)),
+        indicator(&|row| row < INPUT_CELLS || row >= OUTPUT_ROW),
+        (0..KECCAK_ROWS)
+            .map(|row| match row {
+                _ if row < INPUT_CELLS => FE::from(row as u64),
+                _ if row >= OUTPUT_ROW => FE::from((row - OUTPUT_ROW + INPUT_CELLS) as u64),
+                _ => FE::zero(),
+            })
+            .collect(),
+    ];
+    columns.extend((0..LANE_BITS).map(|z| {
+        (0..KECCAK_ROWS)
+            .map(|row| FE::from(round_constant(row) >> z & 1))
+            .collect()
+    }));
+    columns
+}
+
+/// The parities of the columns of the state, the lanes θ adds to each
+/// column, and the state after θ.
+fn theta(state: &[u64; LANES]) -> ([u64; 5], [u64; 5], [u64; LANES]) {
+    let parities: [u64; 5] =
+        core::array::from_fn(|x| (0..5).fold(0, |acc, y| acc ^ state[lane(x, y)]));
+    let effects: [u64; 5] =
+        core::array::from_fn(|x| parities[(x + 4) % 5] ^ parities[(x + 1) % 5].rotate_left(1));
+    let after_theta = core::array::from_fn(|i| state[i] ^ effects[i % 5]);
+    (parities, effects, after_theta)
+}
+
+/// ρ and π, which move the lane `(x, y)` to `(y, 2x + 3y)`.
+fn rho_pi(after_theta: &[u64; LANES]) -> [u64; LANES] {
+    let mut b = [0; LANES];
+    for (x, rotations) in ROTATIONS.iter().enumerate() {
+        for (y, rotation) in rotations.iter().enumerate() {
+            b[lane(y, (2 * x + 3 * y) % 5)] = after_theta[lane(x, y)].rotate_left(*rotation as u32);
+        }
+    }
+    b
+}
+
+fn rho_pi_inverse(b: &[u64; LANES]) -> [u64; LANES] {
+    let mut after_theta = [0; LANES];
+    for (x, rotations) in ROTATIONS.iter().enumerate() {
+        for (y, rotation) in rotations.iter().enumerate() {
+            after_theta[lane(x, y)] =
+                b[lane(y, (2 * x + 3 * y) % 5)].rotate_right(*rotation as u32);
+        }
+    }
+    after_theta
+}
+
+fn chi(b: &[u64; LANES]) -> [u64; LANES] {
+    core::array::from_fn(|i| {
+        let (x, y) = (i % 5, i / 5);
+        b[i] ^ (!b[lane((x + 1) % 5, y)] & b[lane((x + 2) % 5, y)])
+    })
+}
+
+/// χ is a permutation of each row of five bits, inverted bit by bit.
+fn chi_inverse(state: &[u64; LANES]) -> [u64; LANES] {
+    let mut inverses = [0; 32];
+    for row in 0..32usize {
+        let bits: [usize; 5] = core::array::from_fn(|x| row >> x & 1);
+        let image = (0..5).fold(0, |acc, x| {
+            acc | (bits[x] ^ ((1 - bits[(x + 1) % 5]) & bits[(x + 2) % 5])) << x
+        });
+        inverses[image] = row;
+    }
+    let mut b = [0; LANES];
+    for y in 0..5 {
+        for z in 0..LANE_BITS {
+            let image = (0..5).fold(0, |acc, x| acc | (state[lane(x, y)] >> z & 1) << x);
+            let row = inverses[image as usize] as u64;
+            for x in 0..5 {
+                b[lane(x, y)] |= (row >> x & 1) << z;
+            }
+        }
+    }
+    b
+}
+
+/// ρ, π, χ and ι of the row `row` of an instance, on the state after θ.
+fn finish_round(after_theta: &[u64; LANES], row: usize) -> [u64; LANES] {
+    let mut state = chi(&rho_pi(after_theta));
+    state[0] ^= round_constant(row);
+    state
+}
+
+/// The Keccak-f[1600] permutation of `state`.
+pub fn permutation(state: &[u64; LANES]) -> [u64; LANES] {
+    (0..ROUNDS).fold(*state, |state, round| finish_round(&theta(&state).2, round))
+}
+
+/// Lanes of the state with the bits of the `INPUT_CELLS` cells of `cells`, or
+/// `None` if a cell doesn't fit in `CELL_BITS` bits.
+pub fn state_from_cells(cells: &[FE]) -> Option<[u64; LANES]> {
+    let mut bytes = Vec::with_capacity(INPUT_CELLS * CELL_BYTES);
+    for cell in cells {
+        let cell_bytes = cell.to_bytes_be();
+        let (high, low) = cell_bytes.split_at(cell_bytes.len() - CELL_BYTES);
+        if high.iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        bytes.extend(low.iter().rev());
+    }
+    Some(core::array::from_fn(|i| {
+        let mut lane = [0; 8];
+        lane.copy_from_slice(&bytes[8 * i..8 * (i + 1)]);
+        u64::from_le_bytes(lane)
+    }))
+}
+
+/// Cells with the bits of the lanes of `state`.
+pub fn cells_from_state(state: &[u64; LANES]) -> [FE; INPUT_CELLS] {
+    let bytes: Vec<u8> = state.iter().flat_map(|lane| lane.to_le_bytes()).collect();
+    core::array::from_fn(|i| {
+        bytes[CELL_BYTES * i..CELL_BYTES * (i + 1)]
+            .iter()
+            .rev()
+            .fold(FE::zero(), |acc, byte| {
+                acc * FE::from(256) + FE::from(*byte as u64)
+            })
+    })
+}
+
+/// Bits of the instance applying the permutation to `input`, with
+/// `COLUMNS` values per row. The state after θ on the last row is the one
+/// that leads to `next_input`, the input of the next instance.
+pub fn instance_rows(input: &[u64; LANES], next_input: &[u64; LANES]) -> Vec<Vec<FE>> {
+    let bit = |lane: u64, z: usize| FE::from(lane >> z & 1);
+    let mut rows = Vec::with_capacity(KECCAK_ROWS);
+    let mut state = *input;
+    for row in 0..KECCAK_ROWS {
+        let (parities, effects, mut after_theta) = theta(&state);
+        if row == KECCAK_ROWS - 1 {
+            after_theta = rho_pi_inverse(&chi_inverse(next_input));
+        }
+        let iota = after_theta[0] ^ round_constant(row);
+
+        let mut values = vec![FE::zero(); COLUMNS];
+        for z in 0..LANE_BITS {
+            for (x, (parity, effect)) in parities.iter().zip(&effects).enumerate() {
+                values[parity_column(x, z)] = bit(*parity, z);
+                values[theta_effect_column(x, z)] = bit(*effect, z);
+                for y in 0..5 {
+                    values[state_column(x, y, z)] = bit(state[lane(x, y)], z);
+                    values[theta_column(x, y, z)] = bit(after_theta[lane(x, y)], z);
+                }
+            }
+            values[iota_column(z)] = bit(iota, z);
+        }
+        rows.push(values);
+        state = finish_round(&after_theta, row);
+    }
+    rows
+}
+
+/// Degrees of the constraints of `evaluate_constraints`, in their order.
+pub fn transition_degrees() -> Vec<usize> {
+    let mut degrees = vec![2; INPUT_CELLS + STATE_BITS + PARITY_BITS];
+    degrees.extend([3; PARITY_BITS]);
+    degrees.extend([2; PARITY_BITS]);
+    degrees.extend([3; STATE_BITS]);
+    degrees.extend([2; LANE_BITS]);
+    degrees.extend([3; STATE_BITS]);
+    degrees
+}
+
+/// Exemptions of the constraints of `evaluate_constraints`, in their order.
+/// Only χ reads the next row.
+pub fn transition_exemptions() -> Vec<usize> {
+    let mut exemptions = vec![0; CONSTRAINTS - STATE_BITS];
+    exemptions.extend([1; STATE_BITS]);
+    exemptions
+}
+
+fn xor(a: &FE, b: &FE) -> FE {
+    a + b - FE::from(2) * a * b
+}
+
+/// Constraints of the bits of an instance, given the cells and the bits of a
+/// row, the bits of the next one and the periodic values of the builtin:
+///
+/// ```text
+/// the cells hold the bits of the state on the input and output rows
+/// A and C are boolean, and Σ_y A[x][y] - C[x] is 0, 2 or 4
+/// D[x] = C[x - 1] ⊕ rot(C[x + 1], 1)
+/// A' = A ⊕ D[x], except on the last row
+/// I = A'[0][0] ⊕ RC
+/// A_next = χ(π(ρ(A'))), with I as the lane (0, 0) added in χ
+/// ```
+///
+/// where `a ⊕ b = a + b - 2ab` on bits.
+pub fn evaluate_constraints(
+    cells: &[FE],
+    bits: &[FE],
+    next_bits: &[FE],
+    periodic: &[FE],
+) -> Vec<FE> {
+    let one = FE::one();
+    let (two, four) = (FE::from(2), FE::from(4));
+    let mut evaluations = Vec::with_capacity(CONSTRAINTS);
+
+    for (i, cell) in cells.iter().enumerate().take(INPUT_CELLS) {
+        let packed = bits[CELL_BITS * i..CELL_BITS * (i + 1)]
+            .iter()
+            .rev()
+            .fold(FE::zero(), |acc, bit| acc * &two + bit);
+        evaluations.push(&periodic[PACKED_ROW] * (cell - packed));
+    }
+    for bit in &bits[..STATE_BITS + PARITY_BITS] {
+        evaluations.push(bit * (bit - &one));
+    }
+    for x in 0..5 {
+        for z in 0..LANE_BITS {
+            let sum = (0..5).fold(FE::zero(), |acc, y| acc + &bits[state_column(x, y, z)]);
+            let even = sum - &bits[parity_column(x, z)];
+            evaluations.push(&even * (&even - &two) * (&even - &four));
+        }
+    }
+    for x in 0..5 {
+        for z in 0..LANE_BITS {
+            let effect = xor(
+                &bits[parity_column((x + 4) % 5, z)],
+                &bits[parity_column((x + 1) % 5, (z + LANE_BITS - 1) % LANE_BITS)],
+            );
+            evaluations.push(&bits[theta_effect_column(x, z)] - effect);
+        }
+    }
+    let not_last_row = &one - &periodic[LAST_ROW];
+    for y in 0..5 {
+        for x in 0..5 {
+            for z in 0..LANE_BITS {
+                let after_theta = xor(
+                    &bits[state_column(x, y, z)],
+                    &bits[theta_effect_column(x, z)],
+                );
+                evaluations.push(&not_last_row * (&bits[theta_column(x, y, z)] - after_theta));
+            }
+        }
+    }
+    for z in 0..LANE_BITS {
+        let iota = xor(&bits[theta_column(0, 0, z)], &periodic[ROUND_CONSTANT + z]);
+        evaluations.push(&bits[iota_column(z)] - iota);
+    }
+
+    // The bit `z` of the lane `(x, y)` after ρ and π, which comes from the
+    // lane `(3y + x, x)`.
+    let b = |x: usize, y: usize, z: usize| {
+        let (from_x, from_y) = ((3 * y + x) % 5, x);
+        let rotation = ROTATIONS[from_x][from_y];
+        &bits[theta_column(from_x, from_y, (z + LANE_BITS - rotation) % LANE_BITS)]
+    };
+    for y in 0..5 {
+        for x in 0..5 {
+            for z in 0..LANE_BITS {
+                let and = (&one - b((x + 1) % 5, y, z)) * b((x + 2) % 5, y, z);
+                let first = match (x, y) {
+                    (0, 0) => &bits[iota_column(z)],
+                    _ => b(x, y, z),
+                };
+                let chi = xor(first, &and);
+                evaluations.push(&next_bits[state_column(x, y, z)] - chi);
+            }
+        }
+    }
+    evaluations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutation_of_zero_matches_keccak() {
+        assert_eq!(permutation(&[0; LANES])[0], 0xf125_8f79_40e1_dde7);
+    }
+
+    #[test]
+    fn cells_hold_the_bits_of_the_state() {
+        let state: [u64; LANES] = core::array::from_fn(|i| (i as u64 + 1) * 0x0123_4567_89ab_cdef);
+        let cells = cells_from_state(&state);
+        assert_eq!(state_from_cells(&cells), Some(state));
+
+        let mut too_big = cells;
+        too_big[3] = FE::from(2).pow(CELL_BITS as u64);
+        assert_eq!(state_from_cells(&too_big), None);
+    }
+
+    #[test]
+    fn instance_rows_satisfy_the_constraints() {
+        let input: [u64; LANES] = core::array::from_fn(|i| 0x9e37_79b9_7f4a_7c15 * i as u64);
+        let next_input = [7; LANES];
+        let mut rows = instance_rows(&input, &next_input);
+        rows.push(instance_rows(&next_input, &next_input).swap_remove(0));
+        let periodic = periodic_columns(KECCAK_ROWS);
+        let zero = vec![FE::zero(); INPUT_CELLS];
+        let input_cells = cells_from_state(&input);
+        let output_cells = cells_from_state(&permutation(&input));
+
+        for row in 0..KECCAK_ROWS {
+            let cells = match row {
+                0 => &input_cells[..],
+                OUTPUT_ROW => &output_cells[..],
+                _ => &zero[..],
+            };
+            let values: Vec<FE> = periodic.iter().map(|column| column[row].clone()).collect();
+            let evaluations = evaluate_constraints(cells, &rows[row], &rows[row + 1], &values);
+            assert_eq!(evaluations.len(), CONSTRAINTS);
+            assert!(evaluations
+                .iter()
+                .all(|evaluation| *evaluation == FE::zero()));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Layouts of the Cairo AIR, as in Stone.
+//!
+//! A layout names the builtins a trace has room for and how many steps each
+//! of their instances takes in Stone's traces. A program runs with a layout,
+//! so its execution can only use those builtins, and the AIR of the layout
+//! refuses public inputs with the segments of other builtins.
+//!
+//! The dynamic layout has no builtins of its own: the public input of each
+//! execution lists the builtins the program uses with their ratios, so a
+//! program only pays for the instances it needs. Ratios bound the instances
+//! of an execution, but the builtins' rows aren't spread by them: the
+//! instances of every builtin take consecutive rows from the first one.
+
+use core::fmt;
+
+/// Builtins of Stone's layouts. The AIR has no columns for the ECDSA and
+/// bitwise builtins, so their cells are only checked by the memory. The
+/// range check 96 builtin of the newer layouts is only in dynamic ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Builtin {
+    Output,
+    Pedersen,
+    RangeCheck,
+    Ecdsa,
+    Bitwise,
+    EcOp,
+    Keccak,
+    Poseidon,
+    RangeCheck96,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Builtin::Output => "output",
+            Builtin::Pedersen => "pedersen",
+            Builtin::RangeCheck => "range_check",
+            Builtin::Ecdsa => "ecdsa",
+            Builtin::Bitwise => "bitwise",
+            Builtin::EcOp => "ec_op",
+            Builtin::Keccak => "keccak",
+            Builtin::Poseidon => "poseidon",
+            Builtin::RangeCheck96 => "range_check96",
+        }
+    }
+
+    /// Index of the builtin in the canonical encoding of public inputs.
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Layouts of Stone whose executions the AIR proves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Plain,
+    Small,
+    Dex,
+    Recursive,
+    Starknet,
+    StarknetWithKeccak,
+    /// Layout with the builtins and ratios of the public input.
+    Dynamic,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 7] = [
+        Layout::Plain,
+        Layout::Small,
+        Layout::Dex,
+        Layout::Recursive,
+        Layout::Starknet,
+        Layout::StarknetWithKeccak,
+        Layout::Dynamic,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Plain => "plain",
+            Layout::Small => "small",
+            Layout::Dex => "dex",
+            Layout::Recursive => "recursive",
+            Layout::Starknet => "starknet",
+            Layout::StarknetWithKeccak => "starknet_with_keccak",
+            Layout::Dynamic => "dynamic",
+        }
+    }
+
+    /// The layout named `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.name() == name)
+    }
+
+    /// Builtins of the layout, each with its ratio: the steps per instance.
+    /// The output builtin has no instances, so its ratio is one. The dynamic
+    /// layout has none, since they're given by the public input.
+    pub fn builtins(&self) -> &'static [(Builtin, usize)] {
+        match self {
+            Layout::Plain | Layout::Dynamic => &[],
+            Layout::Small | Layout::Dex => &[
+                (Builtin::Output, 1),
+                (Builtin::Pedersen, 8),
+                (Builtin::RangeCheck, 8),
+                (Builtin::Ecdsa, 512),
+            ],
+            Layout::Recursive => &[
+                (Builtin::Output, 1),
+                (Builtin::Pedersen, 128),
+                (Builtin::RangeCheck, 8),
+                (Builtin::Bitwise, 8),
+            ],
+            Layout::Starknet => &[
+                (Builtin::Output, 1),
+                (Builtin::Pedersen, 32),
+                (Builtin::RangeCheck, 16),
+                (Builtin::Ecdsa, 2048),
+                (Builtin::Bitwise, 64),
+                (Builtin::EcOp, 1024),
+                (Builtin::Poseidon, 32),
+            ],
+            Layout::StarknetWithKeccak => &[
+                (Builtin::Output, 1),
+                (Builtin::Pedersen, 32),
+                (Builtin::RangeCheck, 16),
+                (Builtin::Ecdsa, 2048),
+                (Builtin::Bitwise, 64),
+                (Builtin::EcOp, 1024),
+                (Builtin::Keccak, 2048),
+                (Builtin::Poseidon, 32),
+            ],
+        }
+    }
+
+    pub fn has(&self, builtin: Builtin) -> bool {
+        self.ratio(builtin).is_some()
+    }
+
+    /// Steps per instance of `builtin`, if the layout has it.
+    pub fn ratio(&self, builtin: Builtin) -> Option<usize> {
+        self.builtins()
+            .iter()
+            .find(|(other, _)| *other == builtin)
+            .map(|(_, ratio)| *ratio)
+    }
+
+    /// Instances of `builtin` the layout has room for in an execution of
+    /// `steps` steps.
+    pub fn capacity(&self, builtin: Builtin, steps: usize) -> usize {
+        capacity(self.ratio(builtin), steps)
+    }
+}
+
+/// Instances of a builtin with `ratio` in an execution of `steps` steps, none
+/// without a ratio.
+pub fn capacity(ratio: Option<usize>, steps: usize) -> usize {
+    ratio.map_or(0, |ratio| steps / ratio)
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_are_found_by_name() {
+        for layout in Layout::ALL {
+            assert_eq!(Layout::from_name(layout.name()), Some(layout));
+        }
+        assert_eq!(Layout::from_name("all_cairo"), None);
+    }
+
+    #[test]
+    fn layouts_have_the_builtins_of_stone() {
+        assert!(Layout::Plain.builtins().is_empty());
+        assert!(!Layout::Starknet.has(Builtin::Keccak));
+        assert_eq!(
+            Layout::StarknetWithKeccak.ratio(Builtin::Keccak),
+            Some(2048)
+        );
+        assert_eq!(Layout::Recursive.ratio(Builtin::Pedersen), Some(128));
+        assert_eq!(Layout::Small.capacity(Builtin::RangeCheck, 100), 12);
+        assert_eq!(Layout::Small.capacity(Builtin::Poseidon, 100), 0);
+        assert!(Layout::Dynamic.builtins().is_empty());
+        assert!(Layout::ALL
+            .iter()
+            .all(|layout| !layout.has(Builtin::RangeCheck96)));
+    }
+}
@@ -1 +1,6 @@
 pub mod air;
+pub mod ec_op;
+pub mod keccak;
+pub mod layout;
+pub mod pedersen;
+pub mod poseidon;
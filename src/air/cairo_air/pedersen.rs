@@ -0,0 +1,246 @@
+//! Pedersen hash builtin of the Cairo AIR.
+//!
+//! An instance hashes its inputs `a` and `b` into the x coordinate of
+//! `P0 + a_low·P1 + a_high·P2 + b_low·P3 + b_high·P4` on the STARK curve,
+//! where the low part of an input is its 248 least significant bits and the
+//! high part the 4 bits above them. An instance takes `PEDERSEN_ROWS` rows:
+//! the first half adds the points of the bits of `a`, from the least
+//! significant one, and the second half those of `b`. The point added on each
+//! row comes from the periodic columns.
+
+use crate::FE;
+use lambdaworks_math::traits::ByteConversion;
+
+/// Rows of an instance.
+pub const PEDERSEN_ROWS: usize = 512;
+const HALF_ROWS: usize = PEDERSEN_ROWS / 2;
+/// Bits of an input, the ones of a field element.
+pub const INPUT_BITS: usize = 252;
+const LOW_BITS: usize = 248;
+
+// Periodic columns
+pub const POINT_X: usize = 0;
+pub const POINT_Y: usize = 1;
+/// One on the first row of an instance, where `a` is read.
+pub const INPUT_A: usize = 2;
+/// One on the first row of the second half, where `b` is read.
+pub const INPUT_B: usize = 3;
+/// One on the last row of an instance, where the hash is.
+pub const OUTPUT: usize = 4;
+/// One on the rows of the bits of the inputs.
+pub const STEP: usize = 5;
+/// One on the rows of the most significant bit of the inputs.
+pub const TOP_BIT: usize = 6;
+/// One on the rows of the bits 192, 193, 196 and 197 of the inputs, which
+/// check that the bits are the ones of a field element, less than the modulus
+/// `2^251 + 17·2^192 + 1`.
+pub const BIT_192: usize = 7;
+pub const BIT_193: usize = 8;
+pub const BIT_196: usize = 9;
+pub const BIT_197: usize = 10;
+pub const PERIODIC_COLUMNS: usize = 11;
+
+pub type Point = (FE, FE);
+
+/// `P0` to `P4`, the shift point first.
+fn constant_points() -> [Point; 5] {
+    let point = |x: &str, y: &str| (FE::from_hex(x).unwrap(), FE::from_hex(y).unwrap());
+    [
+        point(
+            "49ee3eba8c1600700ee1b87eb599f16716b0b1022947733551fde4050ca6804",
+            "3ca0cfe4b3bc6ddf346d49d06ea0ed34e621062c0e056c1d0405d266e10268a",
+        ),
+        point(
+            "234287dcbaffe7f969c748655fca9e58fa8120b6d56eb0c1080d17957ebe47b",
+            "3b056f100f96fb21e889527d41f4e39940135dd7a6c94cc6ed0268ee89e5615",
+        ),
+        point(
+            "4fa56f376c83db33f9dab2656558f3399099ec1de5e3018b7a6932dba8aa378",
+            "3fa0984c931c9e38113e0c0e47e4401562761f92a7a23b45168f4e80ff5b54d",
+        ),
+        point(
+            "4ba4cc166be8dec764910f75b45f74b40c690c74709e90f3aa372f0bd2d6997",
+            "40301cf5c1751f4b971e46c4ede85fcac5c59a5ce5ae7c48151f27b24b219c",
+        ),
+        point(
+            "54302dcb0e6cc1c6e44cca8f61a63bb2ca65048d53fb325d36ff12c49a58202",
+            "1b77b3e37d13504b348046268d8ae25ce98ad783c25561a879dcc77e99c2426",
+        ),
+    ]
+}
+
+/// The shift point `P0`, where the sum of every instance starts.
+pub fn shift_point() -> Point {
+    constant_points()[0].clone()
+}
+
+/// Sum of two points with different x coordinates, and the slope of the line
+/// through them.
+pub(crate) fn add(p: &Point, q: &Point) -> (Point, FE) {
+    let slope = (&p.1 - &q.1) / (&p.0 - &q.0);
+    let x = &slope * &slope - &p.0 - &q.0;
+    let y = &slope * (&p.0 - &x) - &p.1;
+    ((x, y), slope)
+}
+
+/// Double of a point with a nonzero y coordinate, and the slope of the
+/// tangent at it.
+pub(crate) fn double(p: &Point) -> (Point, FE) {
+    // The curve is y² = x³ + x + β.
+    let slope = (FE::from(3) * &p.0 * &p.0 + FE::one()) / (FE::from(2) * &p.1);
+    let x = &slope * &slope - FE::from(2) * &p.0;
+    let y = &slope * (&p.0 - &x) - &p.1;
+    ((x, y), slope)
+}
+
+/// Point added on each row of an instance for a set bit, and zero on the rows
+/// that don't add one.
+fn points() -> Vec<Point> {
+    let [_, p1, p2, p3, p4] = constant_points();
+    let mut points = Vec::with_capacity(PEDERSEN_ROWS);
+    for (low, high) in [(p1, p2), (p3, p4)] {
+        let mut point = low;
+        for bit in 0..HALF_ROWS {
+            if bit == LOW_BITS {
+                point = high.clone();
+            }
+            if bit < INPUT_BITS {
+                points.push(point.clone());
+                point = double(&point).0;
+            } else {
+                points.push((FE::zero(), FE::zero()));
+            }
+        }
+    }
+    points
+}
+
+/// Values of the periodic columns over an instance. In a trace shorter than
+/// an instance, there is no room for the builtin, and they're zero.
+pub fn periodic_columns(trace_length: usize) -> Vec<Vec<FE>> {
+    if trace_length < PEDERSEN_ROWS {
+        return vec![vec![FE::zero()]; PERIODIC_COLUMNS];
+    }
+    let indicator = |rows: &dyn Fn(usize) -> bool| -> Vec<FE> {
+        (0..PEDERSEN_ROWS)
+            .map(|row| if rows(row) { FE::one() } else { FE::zero() })
+            .collect()
+    };
+    let (x, y) = points().into_iter().unzip();
+    vec![
+        x,
+        y,
+        indicator(&|row| row == 0),
+        indicator(&|row| row == HALF_ROWS),
+        indicator(&|row| row == PEDERSEN_ROWS - 1),
+        indicator(&|row| row % HALF_ROWS < INPUT_BITS),
+        indicator(&|row| row % HALF_ROWS == INPUT_BITS - 1),
+        indicator(&|row| row % HALF_ROWS == 192),
+        indicator(&|row| row % HALF_ROWS == 193),
+        indicator(&|row| row % HALF_ROWS == 196),
+        indicator(&|row| row % HALF_ROWS == 197),
+    ]
+}
+
+/// Columns of an instance, one value per row.
+pub struct InstanceColumns {
+    /// The input shifted right by the row of its half.
+    pub suffix: Vec<FE>,
+    pub bit: Vec<FE>,
+    /// Products of the bits 251, 196 and 192 of the input of the half, from
+    /// the first factor, in each row of the half.
+    pub unpacking: [Vec<FE>; 3],
+    pub x: Vec<FE>,
+    pub y: Vec<FE>,
+    pub slope: Vec<FE>,
+}
+
+/// Bits of the representative of `value`, from the least significant one.
+pub(crate) fn bits(value: &FE) -> Vec<bool> {
+    let bytes = value.to_bytes_be();
+    (0..INPUT_BITS)
+        .map(|bit| bytes[bytes.len() - 1 - bit / 8] >> (bit % 8) & 1 == 1)
+        .collect()
+}
+
+/// Columns of the instance hashing `a` and `b`.
+pub fn instance_columns(a: &FE, b: &FE) -> InstanceColumns {
+    let mut columns = InstanceColumns {
+        suffix: Vec::with_capacity(PEDERSEN_ROWS),
+        bit: Vec::with_capacity(PEDERSEN_ROWS),
+        unpacking: Default::default(),
+        x: Vec::with_capacity(PEDERSEN_ROWS),
+        y: Vec::with_capacity(PEDERSEN_ROWS),
+        slope: Vec::with_capacity(PEDERSEN_ROWS),
+    };
+    let points = points();
+    let mut sum = shift_point();
+    for (half, input) in [a, b].into_iter().enumerate() {
+        let bits = bits(input);
+        let mut suffix = vec![FE::zero(); HALF_ROWS];
+        for row in (0..INPUT_BITS).rev() {
+            suffix[row] = FE::from(2) * &suffix[row + 1] + FE::from(bits[row] as u64);
+        }
+        let bit_251 = bits[INPUT_BITS - 1];
+        let bit_196 = bit_251 && bits[196];
+        let bit_192 = bit_196 && bits[192];
+
+        for (row, suffix) in suffix.into_iter().enumerate() {
+            let bit = row < INPUT_BITS && bits[row];
+            columns.suffix.push(suffix);
+            columns.bit.push(FE::from(bit as u64));
+            for (column, bit) in columns
+                .unpacking
+                .iter_mut()
+                .zip([bit_251, bit_196, bit_192])
+            {
+                column.push(FE::from(bit as u64));
+            }
+            columns.x.push(sum.0.clone());
+            columns.y.push(sum.1.clone());
+            if bit {
+                let (next, slope) = add(&sum, &points[half * HALF_ROWS + row]);
+                columns.slope.push(slope);
+                sum = next;
+            } else {
+                columns.slope.push(FE::zero());
+            }
+        }
+    }
+    columns
+}
+
+/// Pedersen hash of `a` and `b`.
+pub fn hash(a: &FE, b: &FE) -> FE {
+    instance_columns(a, b).x[PEDERSEN_ROWS - 1].clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_matches_the_one_of_starknet() {
+        let a = FE::from_hex("3d937c035c878245caf64531a5756109c53068da139362728feb561405371cb")
+            .unwrap();
+        let b = FE::from_hex("208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a")
+            .unwrap();
+        assert_eq!(
+            hash(&a, &b),
+            FE::from_hex("30e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662")
+                .unwrap()
+        );
+        assert_eq!(hash(&FE::zero(), &FE::zero()), shift_point().0);
+    }
+
+    #[test]
+    fn suffix_holds_the_input_at_the_start_of_each_half() {
+        let (a, b) = (FE::from(1234), -FE::one());
+        let columns = instance_columns(&a, &b);
+        assert_eq!(columns.suffix[0], a);
+        assert_eq!(columns.suffix[HALF_ROWS], b);
+        assert_eq!(columns.unpacking[2][HALF_ROWS], FE::one());
+        assert_eq!(columns.unpacking[0][0], FE::zero());
+        assert_eq!(columns.suffix[INPUT_BITS], FE::zero());
+    }
+}
@@ -0,0 +1,780 @@
+//! Several AIRs proved together, with their traces side by side in a single
+//! trace, e.g. the CPU, hash and memory chips of a VM.
+//!
+//! The main columns of the AIRs come first, in order, followed by the
+//! auxiliary columns of each round, also in the order of the AIRs. The
+//! constraints, periodic columns, virtual columns and public inputs are
+//! those of the AIRs one after the other.
+//!
+//! Columns of different AIRs can be linked by lookups and permutations, e.g.
+//! the hash requests of a CPU with the inputs of a hash chip. Their running
+//! sum and product columns are added to the first auxiliary round, after the
+//! columns of the AIRs, and their constraints and challenges are those of
+//! `air::lookup` and `air::permutation`.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{
+    element::FieldElement,
+    traits::{IsFFTField, IsField},
+};
+
+use super::{
+    constraints::{
+        boundary::{BoundaryConstraint, BoundaryConstraints},
+        transition::{RowSubset, Zerofier},
+    },
+    context::{AirContext, ProofOptions},
+    dyn_air::{DynAir, DynPublicInput, DynRAPChallenges, DynRawTrace},
+    frame::Frame,
+    lookup::{Lookup, TableColumn},
+    permutation::PermutationArgument,
+    trace::TraceTable,
+    traits::AIR,
+};
+use crate::{prover::ProvingError, transcript_to_field, IsChallengeField};
+
+/// Main trace column `column` of the AIR `air` of a composition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AirColumn {
+    pub air: usize,
+    pub column: usize,
+}
+
+impl AirColumn {
+    pub fn new(air: usize, column: usize) -> Self {
+        Self { air, column }
+    }
+}
+
+/// Argument between columns of the AIRs of a composition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Link {
+    /// Every value of `values` is in `table`, which is looked up as many times
+    /// as `multiplicities` says on each row.
+    Lookup {
+        values: AirColumn,
+        table: AirColumn,
+        multiplicities: AirColumn,
+    },
+    /// The tuples of the `left` columns over all the rows are a permutation of
+    /// those of the `right` columns.
+    Permutation {
+        left: Vec<AirColumn>,
+        right: Vec<AirColumn>,
+    },
+}
+
+/// A link with its columns in the composed trace.
+#[derive(Clone, Debug)]
+enum Argument {
+    Lookup(Lookup),
+    Permutation(PermutationArgument),
+}
+
+impl Argument {
+    fn transition_degree(&self) -> usize {
+        match self {
+            Self::Lookup(_) => Lookup::TRANSITION_DEGREE,
+            Self::Permutation(_) => PermutationArgument::TRANSITION_DEGREE,
+        }
+    }
+
+    /// Name of the argument column and label of its constraint, given the
+    /// index of the argument.
+    fn name(&self, index: usize) -> String {
+        match self {
+            Self::Lookup(_) => format!("lookup {index}"),
+            Self::Permutation(_) => format!("permutation {index}"),
+        }
+    }
+
+    fn column(&self) -> usize {
+        match self {
+            Self::Lookup(lookup) => lookup.sum,
+            Self::Permutation(permutation) => permutation.product,
+        }
+    }
+}
+
+/// RAP challenges of an `AirComposition`: those of each AIR, and the ones of
+/// the links, which are zero if there are none.
+pub struct CompositionChallenges<F: IsField> {
+    pub airs: Vec<DynRAPChallenges>,
+    /// Challenge of the lookups, also used to compress the permutation tuples.
+    pub alpha: FieldElement<F>,
+    /// Challenge of the permutations.
+    pub z: FieldElement<F>,
+}
+
+/// An AIR of a composition and where its columns are in the composed trace.
+#[derive(Clone)]
+struct Component<F: IsFFTField, E: IsChallengeField<F>> {
+    air: DynAir<F, E>,
+    main_columns: usize,
+    /// Column of the composed trace of each column of the AIR.
+    columns: Vec<usize>,
+    /// Row of the composed frame of each row of the frame of the AIR.
+    frame_rows: Vec<usize>,
+    periodic_columns: Range<usize>,
+    virtual_columns: Range<usize>,
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> Component<F, E> {
+    /// Columns of the AIR out of a table with the columns of the composed
+    /// trace.
+    fn columns_of<T: IsField>(&self, trace: &TraceTable<T>) -> TraceTable<T> {
+        let columns: Vec<_> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|column| *column < trace.n_cols)
+            .collect();
+        trace.get_cols(&columns)
+    }
+
+    /// Frame of the AIR out of a frame of the composed trace, whose rows may
+    /// have the virtual columns after the `trace_columns` committed ones.
+    fn frame_of(&self, frame: &Frame<E>, trace_columns: usize) -> Frame<E> {
+        let has_virtual_columns = frame.num_columns() > trace_columns;
+        let mut width = self.columns.len();
+        if has_virtual_columns {
+            width += self.virtual_columns.len();
+        }
+
+        let mut data = Vec::with_capacity(self.frame_rows.len() * width);
+        for row in &self.frame_rows {
+            let row = frame.get_row(*row);
+            data.extend(self.columns.iter().map(|column| row[*column].clone()));
+            if has_virtual_columns {
+                let start = trace_columns + self.virtual_columns.start;
+                let end = trace_columns + self.virtual_columns.end;
+                data.extend_from_slice(&row[start..end]);
+            }
+        }
+        Frame::new(data, width)
+            .with_periodic_values(frame.periodic_values()[self.periodic_columns.clone()].to_vec())
+    }
+}
+
+/// AIR made of several AIRs over the same field and trace length, proved in a
+/// single trace. Its raw trace and public input hold those of each AIR, in
+/// order, boxed as for a `DynAir`.
+#[derive(Clone)]
+pub struct AirComposition<F: IsFFTField, E: IsChallengeField<F> = F> {
+    context: AirContext,
+    components: Vec<Component<F, E>>,
+    links: Vec<Link>,
+    arguments: Vec<Argument>,
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> AirComposition<F, E> {
+    /// Composes `airs`, proving them with `options`.
+    pub fn new(options: ProofOptions, airs: Vec<DynAir<F, E>>) -> Result<Self, ProvingError> {
+        Self::with_links(options, airs, Vec::new())
+    }
+
+    /// Composes `airs` with `links` between their columns.
+    pub fn with_links(
+        options: ProofOptions,
+        airs: Vec<DynAir<F, E>>,
+        links: Vec<Link>,
+    ) -> Result<Self, ProvingError> {
+        let trace_length = match airs.first() {
+            Some(air) => air.context().trace_length,
+            None => {
+                return Err(ProvingError::WrongParameter(
+                    "there are no AIRs to compose".to_string(),
+                ))
+            }
+        };
+        for (index, air) in airs.iter().enumerate() {
+            let context = air.context();
+            if context.trace_length != trace_length {
+                return Err(ProvingError::WrongParameter(format!(
+                    "AIR {index} has a trace length of {} instead of {trace_length}",
+                    context.trace_length
+                )));
+            }
+            if context.transition_degrees.len() != context.num_transition_constraints {
+                return Err(ProvingError::WrongParameter(format!(
+                    "AIR {index} doesn't have a degree for each transition constraint"
+                )));
+            }
+            if air.auxiliary_rounds().iter().sum::<usize>() != air.number_auxiliary_rap_columns()
+                || air.number_auxiliary_rap_columns() > context.trace_columns
+            {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the auxiliary rounds of AIR {index} don't match its auxiliary columns"
+                )));
+            }
+            if air.number_extension_columns() > 0 {
+                return Err(ProvingError::WrongParameter(format!(
+                    "AIR {index} has columns over the challenge field, which can't be composed"
+                )));
+            }
+            // Neither is forwarded by the composition, so the sub-AIR would
+            // be proven without them.
+            if !air.preprocessed_columns().is_empty() || !air.segments().is_empty() {
+                return Err(ProvingError::WrongParameter(format!(
+                    "AIR {index} has preprocessed columns or segments, which can't be composed"
+                )));
+            }
+        }
+
+        let main_columns: Vec<_> = airs
+            .iter()
+            .map(|air| air.context().trace_columns - air.number_auxiliary_rap_columns())
+            .collect();
+        let main_column = |column: &AirColumn| {
+            if column.air >= airs.len() || column.column >= main_columns[column.air] {
+                return Err(ProvingError::WrongParameter(format!(
+                    "a link refers to {column:?}, which isn't a main column"
+                )));
+            }
+            Ok(main_columns[..column.air].iter().sum::<usize>() + column.column)
+        };
+        let tuple = |columns: &[AirColumn]| -> Result<Vec<usize>, ProvingError> {
+            columns.iter().map(main_column).collect()
+        };
+        // The column of the running sum or product is set with the layout.
+        let mut arguments = Vec::with_capacity(links.len());
+        for link in &links {
+            arguments.push(match link {
+                Link::Lookup {
+                    values,
+                    table,
+                    multiplicities,
+                } => Argument::Lookup(Lookup {
+                    values: main_column(values)?,
+                    table: TableColumn::Trace(main_column(table)?),
+                    multiplicities: main_column(multiplicities)?,
+                    sum: 0,
+                }),
+                Link::Permutation { left, right } => {
+                    if left.is_empty() || left.len() != right.len() {
+                        return Err(ProvingError::WrongParameter(
+                            "a permutation link has tuples of different sizes".to_string(),
+                        ));
+                    }
+                    Argument::Permutation(PermutationArgument {
+                        left: tuple(left)?,
+                        right: tuple(right)?,
+                        product: 0,
+                    })
+                }
+            });
+        }
+
+        let mut transition_offsets: Vec<usize> = airs
+            .iter()
+            .flat_map(|air| air.context().transition_offsets.clone())
+            .collect();
+        // The constraints of the links relate consecutive rows.
+        if !arguments.is_empty() {
+            transition_offsets.extend([0, 1]);
+        }
+        transition_offsets.sort_unstable();
+        transition_offsets.dedup();
+
+        let mut components = Vec::with_capacity(airs.len());
+        let mut next_column = 0;
+        let mut periodic_columns = 0;
+        let mut virtual_columns = 0;
+        for air in airs {
+            let context = air.context();
+            let main_columns = context.trace_columns - air.number_auxiliary_rap_columns();
+            let frame_rows = context
+                .transition_offsets
+                .iter()
+                .map(|offset| transition_offsets.binary_search(offset).unwrap())
+                .collect();
+            let periodic = air.periodic_columns().len();
+            let virtuals = air.virtual_columns().len();
+
+            components.push(Component {
+                columns: (next_column..next_column + main_columns).collect(),
+                main_columns,
+                frame_rows,
+                periodic_columns: periodic_columns..periodic_columns + periodic,
+                virtual_columns: virtual_columns..virtual_columns + virtuals,
+                air,
+            });
+            next_column += main_columns;
+            periodic_columns += periodic;
+            virtual_columns += virtuals;
+        }
+
+        let rounds = components
+            .iter()
+            .map(|component| component.air.auxiliary_rounds().len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for round in 0..rounds {
+            for component in &mut components {
+                let round_columns = component
+                    .air
+                    .auxiliary_rounds()
+                    .get(round)
+                    .copied()
+                    .unwrap_or(0);
+                component
+                    .columns
+                    .extend(next_column..next_column + round_columns);
+                next_column += round_columns;
+            }
+            if round == 0 {
+                for argument in &mut arguments {
+                    match argument {
+                        Argument::Lookup(lookup) => lookup.sum = next_column,
+                        Argument::Permutation(permutation) => permutation.product = next_column,
+                    }
+                    next_column += 1;
+                }
+            }
+        }
+
+        let contexts = || components.iter().map(|component| component.air.context());
+        let mut context = AirContext {
+            options,
+            trace_length,
+            trace_columns: next_column,
+            transition_degrees: contexts()
+                .flat_map(|context| context.transition_degrees.clone())
+                .chain(arguments.iter().map(Argument::transition_degree))
+                .collect(),
+            transition_offsets,
+            transition_exemptions: contexts()
+                .flat_map(|context| context.transition_exemptions.clone())
+                .chain(arguments.iter().map(|_| 0))
+                .collect(),
+            num_transition_constraints: contexts()
+                .map(|context| context.num_transition_constraints)
+                .sum::<usize>()
+                + arguments.len(),
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        // The names and labels of the AIRs are prefixed with their index, and
+        // only kept if some AIR has them.
+        if contexts().any(|context| !context.column_names.is_empty()) {
+            context.column_names = vec![String::new(); next_column];
+            for (index, component) in components.iter().enumerate() {
+                let component_context = component.air.context();
+                for (column, composed) in component.columns.iter().enumerate() {
+                    context.column_names[*composed] = format!(
+                        "{index}: {}",
+                        component_context
+                            .column_names
+                            .get(column)
+                            .cloned()
+                            .unwrap_or_else(|| format!("column {column}"))
+                    );
+                }
+            }
+            for (index, argument) in arguments.iter().enumerate() {
+                context.column_names[argument.column()] = argument.name(index);
+            }
+        }
+        if contexts().any(|context| !context.constraint_labels.is_empty()) {
+            context.constraint_labels = contexts()
+                .enumerate()
+                .flat_map(|(index, context)| {
+                    (0..context.num_transition_constraints).map(move |constraint| {
+                        match context.constraint_label(constraint) {
+                            Some(label) => format!("{index}: {label}"),
+                            None => format!("{index}: constraint {constraint}"),
+                        }
+                    })
+                })
+                .chain(
+                    arguments
+                        .iter()
+                        .enumerate()
+                        .map(|(index, argument)| argument.name(index)),
+                )
+                .collect();
+        }
+
+        Ok(Self {
+            context,
+            components,
+            links,
+            arguments,
+        })
+    }
+
+    /// Columns of the composed trace holding the columns of the AIR `index`.
+    pub fn columns(&self, index: usize) -> &[usize] {
+        &self.components[index].columns
+    }
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> AIR for AirComposition<F, E> {
+    type Field = F;
+    type ChallengeField = E;
+    type RawTrace = Vec<DynRawTrace>;
+    type RAPChallenges = CompositionChallenges<F>;
+    type PublicInput = Vec<DynPublicInput>;
+
+    /// Builds and pads the main trace of each AIR, and puts them side by side.
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        if raw_trace.len() != self.components.len() || public_input.len() != self.components.len() {
+            return Err(ProvingError::WrongParameter(format!(
+                "there are {} raw traces and {} public inputs for {} AIRs",
+                raw_trace.len(),
+                public_input.len(),
+                self.components.len()
+            )));
+        }
+
+        let mut columns = Vec::new();
+        let mut public_values = Vec::with_capacity(self.components.len());
+        for ((component, raw_trace), public_input) in
+            self.components.iter().zip(raw_trace).zip(public_input)
+        {
+            let (mut main_trace, public_input) =
+                component.air.build_main_trace(raw_trace, public_input)?;
+            if let Some(strategy) = component.air.padding_strategy() {
+                let n_rows = main_trace.n_rows();
+                if n_rows == 0 || n_rows > self.context.trace_length {
+                    return Err(ProvingError::InvalidTraceLength(n_rows));
+                }
+                main_trace.pad(strategy, self.context.trace_length);
+            }
+            if main_trace.n_rows() != self.context.trace_length {
+                return Err(ProvingError::InvalidTraceLength(main_trace.n_rows()));
+            }
+            if main_trace.n_cols != component.main_columns {
+                return Err(ProvingError::WrongParameter(format!(
+                    "a main trace has {} columns instead of {}",
+                    main_trace.n_cols, component.main_columns
+                )));
+            }
+            columns.extend(main_trace.cols());
+            public_values.push(public_input);
+        }
+
+        Ok((TraceTable::new_from_cols(&columns), public_values))
+    }
+
+    /// Composes the AIRs of the statements with their public inputs.
+    fn with_public_input(&self, public_input: &Self::PublicInput) -> Result<Self, ProvingError> {
+        if public_input.len() != self.components.len() {
+            return Err(ProvingError::WrongParameter(format!(
+                "there are {} public inputs for {} AIRs",
+                public_input.len(),
+                self.components.len()
+            )));
+        }
+        let airs = self
+            .components
+            .iter()
+            .zip(public_input)
+            .map(|(component, public_input)| component.air.with_public_input(public_input))
+            .collect::<Result<_, _>>()?;
+        Self::with_links(self.context.options.clone(), airs, self.links.clone())
+    }
+
+    /// The encodings of the public inputs of the components, in order.
+    fn public_input_bytes(&self, public_input: &Self::PublicInput) -> Vec<u8> {
+        self.components
+            .iter()
+            .zip(public_input)
+            .flat_map(|(component, public_input)| component.air.public_input_bytes(public_input))
+            .collect()
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        let mut columns: Vec<_> = self
+            .components
+            .iter()
+            .zip(&rap_challenges.airs)
+            .zip(public_input)
+            .flat_map(|((component, rap_challenges), public_input)| {
+                let main_trace = component.columns_of(main_trace);
+                component
+                    .air
+                    .build_auxiliary_trace(&main_trace, rap_challenges, public_input)
+                    .cols()
+            })
+            .collect();
+        let (alpha, z) = (&rap_challenges.alpha, &rap_challenges.z);
+        columns.extend(self.arguments.iter().map(|argument| match argument {
+            Argument::Lookup(lookup) => lookup.build_sum_column(main_trace, &[], alpha),
+            Argument::Permutation(permutation) => {
+                permutation.build_product_column(main_trace, z, alpha)
+            }
+        }));
+        if columns.is_empty() {
+            TraceTable::empty()
+        } else {
+            TraceTable::new_from_cols(&columns)
+        }
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        let airs = self
+            .components
+            .iter()
+            .map(|component| component.air.build_rap_challenges(transcript))
+            .collect();
+        let (alpha, z) = if self.arguments.is_empty() {
+            (FieldElement::zero(), FieldElement::zero())
+        } else {
+            (
+                transcript_to_field(transcript),
+                transcript_to_field(transcript),
+            )
+        };
+        CompositionChallenges { airs, alpha, z }
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.air.number_auxiliary_rap_columns())
+            .sum::<usize>()
+            + self.arguments.len()
+    }
+
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        let mut rounds = vec![0];
+        for component in &self.components {
+            for (round, columns) in component.air.auxiliary_rounds().into_iter().enumerate() {
+                if round == rounds.len() {
+                    rounds.push(0);
+                }
+                rounds[round] += columns;
+            }
+        }
+        rounds[0] += self.arguments.len();
+        rounds
+    }
+
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        round: usize,
+        rap_challenges: &mut Self::RAPChallenges,
+        transcript: &mut T,
+    ) {
+        for (component, rap_challenges) in self.components.iter().zip(&mut rap_challenges.airs) {
+            if round < component.air.auxiliary_rounds().len() {
+                component
+                    .air
+                    .extend_rap_challenges(round, rap_challenges, transcript);
+            }
+        }
+    }
+
+    fn build_auxiliary_round(
+        &self,
+        round: usize,
+        trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        let columns: Vec<_> = self
+            .components
+            .iter()
+            .zip(&rap_challenges.airs)
+            .zip(public_input)
+            .filter(|((component, _), _)| round < component.air.auxiliary_rounds().len())
+            .flat_map(|((component, rap_challenges), public_input)| {
+                let trace = component.columns_of(trace);
+                component
+                    .air
+                    .build_auxiliary_round(round, &trace, rap_challenges, public_input)
+                    .cols()
+            })
+            .collect();
+        if columns.is_empty() {
+            TraceTable::empty()
+        } else {
+            TraceTable::new_from_cols(&columns)
+        }
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.components
+            .iter()
+            .flat_map(|component| component.air.periodic_columns())
+            .collect()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.components
+            .iter()
+            .flat_map(|component| {
+                component.air.lookups().into_iter().map(|lookup| Lookup {
+                    values: component.columns[lookup.values],
+                    table: match lookup.table {
+                        TableColumn::Trace(column) => TableColumn::Trace(component.columns[column]),
+                        TableColumn::Periodic(column) => {
+                            TableColumn::Periodic(component.periodic_columns.start + column)
+                        }
+                    },
+                    multiplicities: component.columns[lookup.multiplicities],
+                    sum: component.columns[lookup.sum],
+                })
+            })
+            .chain(self.arguments.iter().filter_map(|argument| match argument {
+                Argument::Lookup(lookup) => Some(lookup.clone()),
+                Argument::Permutation(_) => None,
+            }))
+            .collect()
+    }
+
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        self.components
+            .iter()
+            .flat_map(|component| component.air.virtual_columns())
+            .collect()
+    }
+
+    fn evaluate_virtual_columns(
+        &self,
+        row: &[FieldElement<Self::ChallengeField>],
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.components
+            .iter()
+            .filter(|component| !component.virtual_columns.is_empty())
+            .flat_map(|component| {
+                let row: Vec<_> = component
+                    .columns
+                    .iter()
+                    .map(|column| row[*column].clone())
+                    .collect();
+                component.air.evaluate_virtual_columns(&row)
+            })
+            .collect()
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        let trace_length = self.context.trace_length;
+        self.components
+            .iter()
+            .map(|component| component.air.composition_poly_degree_bound())
+            .chain(
+                self.arguments
+                    .iter()
+                    .map(|argument| trace_length * (argument.transition_degree() - 1)),
+            )
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let (alpha, z) = (&rap_challenges.alpha, &rap_challenges.z);
+        self.components
+            .iter()
+            .zip(&rap_challenges.airs)
+            .flat_map(|(component, rap_challenges)| {
+                let frame = component.frame_of(frame, self.context.trace_columns);
+                component.air.compute_transition(&frame, rap_challenges)
+            })
+            .chain(self.arguments.iter().map(|argument| match argument {
+                Argument::Lookup(lookup) => lookup.evaluate_transition(frame, alpha),
+                Argument::Permutation(permutation) => {
+                    permutation.evaluate_transition(frame, z, alpha)
+                }
+            }))
+            .collect()
+    }
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let constraints = self
+            .components
+            .iter()
+            .zip(&rap_challenges.airs)
+            .zip(public_input)
+            .flat_map(|((component, rap_challenges), public_input)| {
+                component
+                    .air
+                    .boundary_constraints(rap_challenges, public_input)
+                    .constraints
+                    .into_iter()
+                    .map(|constraint| {
+                        BoundaryConstraint::new(
+                            component.columns[constraint.col],
+                            constraint.step,
+                            constraint.value,
+                        )
+                    })
+            })
+            .chain(self.arguments.iter().map(|argument| match argument {
+                Argument::Lookup(lookup) => lookup.boundary_constraint(),
+                Argument::Permutation(permutation) => permutation.boundary_constraint(),
+            }))
+            .collect();
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        self.components
+            .iter()
+            .flat_map(|component| component.air.transition_row_subsets())
+            .chain(self.arguments.iter().map(|_| RowSubset::ALL))
+            .collect()
+    }
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        self.components
+            .iter()
+            .flat_map(|component| component.air.transition_zerofiers())
+            .chain(self.arguments.iter().map(|_| Zerofier::new(RowSubset::ALL)))
+            .collect()
+    }
+
+    /// The columns of each AIR at its own offsets. The links read their
+    /// columns at the first row, and their sum or product at the first two.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        let mut column_offsets = vec![Vec::new(); self.context.trace_columns];
+        for component in &self.components {
+            for (column, offsets) in component.columns.iter().zip(component.air.column_offsets()) {
+                column_offsets[*column] = offsets;
+            }
+        }
+        for argument in &self.arguments {
+            let column = match argument {
+                Argument::Lookup(lookup) => lookup.sum,
+                Argument::Permutation(permutation) => permutation.product,
+            };
+            column_offsets[column] = vec![0, 1];
+        }
+        column_offsets
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        Some(&mut self.context)
+    }
+}
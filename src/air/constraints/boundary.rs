@@ -1,9 +1,12 @@
+use alloc::{collections::BTreeSet, format, vec::Vec};
 use lambdaworks_math::{
     field::{element::FieldElement, traits::IsField},
     polynomial::Polynomial,
 };
 
-#[derive(Debug)]
+use crate::{air::context::AirContext, prover::ProvingError};
+
+#[derive(Clone, Debug)]
 /// Represents a boundary constraint that must hold in an execution
 /// trace:
 ///   * col: The column of the trace where the constraint must hold
@@ -28,6 +31,12 @@ impl<F: IsField> BoundaryConstraint<F> {
             value,
         }
     }
+
+    /// Constraint on the last row of a trace of length `trace_length`, e.g.
+    /// the final value of a cumulative product.
+    pub fn new_last_row(col: usize, trace_length: usize, value: FieldElement<F>) -> Self {
+        Self::new(col, trace_length - 1, value)
+    }
 }
 
 /// Data structure that stores all the boundary constraints that must
@@ -50,6 +59,31 @@ impl<F: IsField> BoundaryConstraints<F> {
         Self { constraints }
     }
 
+    /// Checks that every constraint is on a column and row of the trace of
+    /// `context`, auxiliary columns included, and that no two constraints are
+    /// on the same cell.
+    pub fn check(&self, context: &AirContext) -> Result<(), ProvingError> {
+        let mut cells = BTreeSet::new();
+        for constraint in &self.constraints {
+            let cell = (constraint.col, constraint.step);
+            if constraint.col >= context.trace_columns || constraint.step >= context.trace_length {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the boundary constraint on {} and row {} is outside the trace",
+                    context.describe_column(cell.0),
+                    cell.1
+                )));
+            }
+            if !cells.insert(cell) {
+                return Err(ProvingError::WrongParameter(format!(
+                    "there are several boundary constraints on {} and row {}",
+                    context.describe_column(cell.0),
+                    cell.1
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Returns all the steps where boundary conditions exist for the given column
     pub fn steps(&self, col: usize) -> Vec<usize> {
         self.constraints
@@ -124,6 +158,26 @@ mod test {
     type PrimeField = Stark252PrimeField;
 
     use super::*;
+    use crate::air::context::ProofOptions;
+
+    fn context(trace_columns: usize) -> AirContext {
+        AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: 8,
+            trace_columns,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![1],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        }
+    }
 
     #[test]
     fn zerofier_is_the_correct_one() {
@@ -154,4 +208,33 @@ mod test {
 
         assert_eq!(expected_zerofier, zerofier);
     }
+
+    #[test]
+    fn constraints_outside_the_trace_or_on_the_same_cell_are_rejected() {
+        let one = FieldElement::<PrimeField>::one();
+        let constraints = |constraints| BoundaryConstraints::from_constraints(constraints);
+
+        let valid = constraints(vec![
+            BoundaryConstraint::new(1, 0, one.clone()),
+            BoundaryConstraint::new_last_row(1, 8, one.clone()),
+        ]);
+        assert!(valid.check(&context(2)).is_ok());
+        assert!(valid.check(&context(1)).is_err());
+
+        let outside = constraints(vec![BoundaryConstraint::new(0, 8, one.clone())]);
+        assert!(outside.check(&context(1)).is_err());
+
+        let repeated = constraints(vec![
+            BoundaryConstraint::new_simple(3, one.clone()),
+            BoundaryConstraint::new_simple(3, one),
+        ]);
+        assert!(repeated.check(&context(1)).is_err());
+
+        let mut named = context(1);
+        named.column_names = vec!["fib".into()];
+        match repeated.check(&named) {
+            Err(ProvingError::WrongParameter(message)) => assert!(message.contains("(fib)")),
+            _ => panic!("the repeated constraint isn't reported"),
+        }
+    }
 }
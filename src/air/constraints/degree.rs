@@ -0,0 +1,121 @@
+//! Checks that the constraints of an AIR have the degrees it declares, which
+//! debug builds and `Prover::with_degree_check` run on every proof.
+//! A constraint of a higher degree than declared makes the composition
+//! polynomial exceed its bound, which otherwise only shows up as a FRI failure
+//! of the verifier.
+
+use alloc::{string::String, vec::Vec};
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    polynomial::Polynomial,
+};
+
+use crate::{
+    air::{frame::Frame, periodic, trace::TraceTable, traits::AIR},
+    prover::ProvingError,
+    Domain, IsChallengeField,
+};
+
+/// Interpolates every transition constraint over the LDE of the trace and
+/// checks that its degree, in multiples of the degree of the trace
+/// polynomials, is at most the one declared in `transition_degrees`. The
+/// columns over the challenge field are in `lde_extension_trace`.
+pub fn check_transition_degrees<F, E, A>(
+    air: &A,
+    lde_trace: &TraceTable<F>,
+    lde_extension_trace: &TraceTable<E>,
+    domain: &Domain<F>,
+    rap_challenges: &A::RAPChallenges,
+) -> Result<(), ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+{
+    let trace_length = air.context().trace_length;
+    let lde_domain_size = domain.lde_domain_size();
+    let periodic_evaluations = periodic::evaluate_on_lde_domain(
+        &air.periodic_columns(),
+        trace_length,
+        domain.blowup_factor,
+        &domain.coset_offset,
+    )?;
+
+    let mut evaluations: Vec<Vec<FieldElement<E>>> = (0..air.num_transition_constraints())
+        .map(|_| Vec::with_capacity(lde_domain_size))
+        .collect();
+    for i in 0..lde_domain_size {
+        let periodic_values = periodic_evaluations
+            .iter()
+            .map(|column| E::embed(&column[i % column.len()]))
+            .collect();
+        let transition_offsets = &air.context().transition_offsets;
+        let frame = Frame::read_from_trace(lde_trace, i, air.blowup_factor(), transition_offsets)
+            .with_extension_columns(
+                lde_extension_trace,
+                i,
+                air.blowup_factor(),
+                transition_offsets,
+            )
+            .with_periodic_values(periodic_values)
+            .with_virtual_columns(|row| air.evaluate_virtual_columns(row));
+
+        let transition = air.compute_transition(&frame, rap_challenges);
+        if transition.len() != evaluations.len() {
+            return Err(ProvingError::TransitionCount {
+                declared: evaluations.len(),
+                found: transition.len(),
+            });
+        }
+        for (constraint, evaluation) in transition.into_iter().enumerate() {
+            evaluations[constraint].push(evaluation);
+        }
+    }
+
+    // The trace polynomials have degree n - 1, so a constraint of degree d
+    // evaluated on them has degree at most d(n - 1).
+    let trace_poly_degree = (trace_length - 1).max(1);
+    let coset_offset = E::embed(&domain.coset_offset);
+    for (constraint, (evaluations, declared)) in evaluations
+        .iter()
+        .zip(air.context().transition_degrees())
+        .enumerate()
+    {
+        let constraint_poly = E::interpolate_offset_fft(evaluations, &coset_offset)
+            .map_err(ProvingError::Interpolation)?;
+        let found = (constraint_poly.degree() + trace_poly_degree - 1) / trace_poly_degree;
+        if found > *declared {
+            return Err(ProvingError::TransitionDegree {
+                constraint,
+                label: air.context().constraint_label(constraint).map(String::from),
+                declared: *declared,
+                found,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that H = ∑ xⁱHᵢ(xᴺ), given by its parts, has a degree below the
+/// composition polynomial degree bound of the AIR.
+pub fn check_composition_degree<A: AIR, E: IsField>(
+    air: &A,
+    composition_poly_parts: &[Polynomial<FieldElement<E>>],
+) -> Result<(), ProvingError> {
+    let parts = composition_poly_parts.len();
+    let degree = composition_poly_parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| *part != &Polynomial::zero())
+        .map(|(i, part)| part.degree() * parts + i)
+        .max()
+        .unwrap_or(0);
+    let bound = air.composition_poly_degree_bound();
+    if degree >= bound {
+        return Err(ProvingError::CompositionDegree { degree, bound });
+    }
+    Ok(())
+}
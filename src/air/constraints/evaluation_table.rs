@@ -1,4 +1,5 @@
-use lambdaworks_fft::polynomial::FFTPoly;
+use alloc::vec::Vec;
+use lambdaworks_fft::errors::FFTError;
 use lambdaworks_math::{
     field::{
         element::FieldElement,
@@ -7,6 +8,8 @@ use lambdaworks_math::{
     polynomial::Polynomial,
 };
 
+use crate::IsChallengeField;
+
 #[derive(Clone, Debug)]
 pub struct ConstraintEvaluationTable<F: IsField> {
     // Accumulation of the evaluation of the constraints
@@ -15,20 +18,35 @@ pub struct ConstraintEvaluationTable<F: IsField> {
 }
 
 impl<F: IsField> ConstraintEvaluationTable<F> {
-    pub fn new(_n_cols: usize, domain: &[FieldElement<F>]) -> Self {
-        let evaluations_acc = Vec::with_capacity(domain.len());
+    pub fn new(n_cols: usize, domain_size: usize) -> Self {
+        Self::new_with_buffer(n_cols, domain_size, Vec::new())
+    }
+
+    /// Same as `new`, but accumulates the evaluations into `evaluations_acc`,
+    /// reusing its allocation.
+    pub fn new_with_buffer(
+        _n_cols: usize,
+        domain_size: usize,
+        mut evaluations_acc: Vec<FieldElement<F>>,
+    ) -> Self {
+        evaluations_acc.clear();
+        evaluations_acc.reserve(domain_size);
 
         ConstraintEvaluationTable {
             evaluations_acc,
-            trace_length: domain.len(),
+            trace_length: domain_size,
         }
     }
 
-    pub fn compute_composition_poly(&self, offset: &FieldElement<F>) -> Polynomial<FieldElement<F>>
+    /// Interpolates the evaluations on the LDE coset of `offset`, with the
+    /// roots of unity of the trace field `B`.
+    pub fn compute_composition_poly<B: IsFFTField>(
+        &self,
+        offset: &FieldElement<F>,
+    ) -> Result<Polynomial<FieldElement<F>>, FFTError>
     where
-        F: IsFFTField,
-        Polynomial<FieldElement<F>>: FFTPoly<F>,
+        F: IsChallengeField<B>,
     {
-        Polynomial::interpolate_offset_fft(&self.evaluations_acc, offset).unwrap()
+        F::interpolate_offset_fft(&self.evaluations_acc, offset)
     }
 }
@@ -1,70 +1,115 @@
+use lambdaworks_fft::errors::FFTError;
 use lambdaworks_math::{
     field::{element::FieldElement, traits::IsFFTField},
     polynomial::Polynomial,
     traits::ByteConversion,
 };
 
-use super::{boundary::BoundaryConstraints, evaluation_table::ConstraintEvaluationTable};
+use super::{
+    boundary::BoundaryConstraints, evaluation_table::ConstraintEvaluationTable,
+    transition::Zerofier,
+};
 use crate::{
-    air::{frame::Frame, trace::TraceTable, traits::AIR},
-    prover::evaluate_polynomial_on_lde_domain,
-    Domain,
+    air::{frame::Frame, periodic, trace::TraceTable, traits::AIR},
+    prover::{
+        evaluate_challenge_polynomial_on_lde_domain, evaluate_polynomial_on_lde_domain,
+        ProvingError,
+    },
+    Domain, IsChallengeField,
+};
+use alloc::vec::Vec;
+use core::{
+    iter::zip,
+    sync::atomic::{AtomicBool, Ordering},
 };
-use std::iter::zip;
 
-pub struct ConstraintEvaluator<'poly, F: IsFFTField, A: AIR> {
+/// Number of rows of the LDE domain evaluated between two checks of the
+/// cancellation flag.
+const CANCELLATION_CHUNK: usize = 1 << 10;
+
+pub struct ConstraintEvaluator<'poly, F: IsFFTField, E: IsChallengeField<F>, A: AIR> {
     air: A,
-    boundary_constraints: BoundaryConstraints<F>,
+    boundary_constraints: BoundaryConstraints<E>,
     trace_polys: &'poly [Polynomial<FieldElement<F>>],
+    extension_polys: &'poly [Polynomial<FieldElement<E>>],
     primitive_root: FieldElement<F>,
+    cancellation: Option<&'poly AtomicBool>,
 }
 
-impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F, A> {
+impl<'poly, F, E, A> ConstraintEvaluator<'poly, F, E, A>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+{
+    /// Evaluator of the constraints of `air` on the trace given by the
+    /// polynomials of its columns over `F` and of those over the challenge
+    /// field, which follow them.
     pub fn new(
         air: &A,
         trace_polys: &'poly [Polynomial<FieldElement<F>>],
+        extension_polys: &'poly [Polynomial<FieldElement<E>>],
         primitive_root: &FieldElement<F>,
         public_input: &A::PublicInput,
         rap_challenges: &A::RAPChallenges,
-    ) -> Self {
+    ) -> Result<Self, ProvingError> {
         let boundary_constraints = air.boundary_constraints(rap_challenges, public_input);
+        boundary_constraints.check(air.context())?;
 
-        Self {
+        Ok(Self {
             air: air.clone(),
             boundary_constraints,
             trace_polys,
+            extension_polys,
             primitive_root: primitive_root.clone(),
-        }
+            cancellation: None,
+        })
+    }
+
+    /// Makes `evaluate` stop with `ProvingError::Cancelled` once `cancellation`
+    /// is set. The flag is checked every `CANCELLATION_CHUNK` rows.
+    pub fn with_cancellation(mut self, cancellation: &'poly AtomicBool) -> Self {
+        self.cancellation = Some(cancellation);
+        self
     }
 
     pub fn evaluate(
         &self,
         lde_trace: &TraceTable<F>,
+        lde_extension_trace: &TraceTable<E>,
         domain: &Domain<F>,
-        alpha_and_beta_transition_coefficients: &[(FieldElement<F>, FieldElement<F>)],
-        alpha_and_beta_boundary_coefficients: &[(FieldElement<F>, FieldElement<F>)],
+        alpha_and_beta_transition_coefficients: &[(FieldElement<E>, FieldElement<E>)],
+        alpha_and_beta_boundary_coefficients: &[(FieldElement<E>, FieldElement<E>)],
         rap_challenges: &A::RAPChallenges,
-    ) -> ConstraintEvaluationTable<F>
+        evaluations_buffer: Vec<FieldElement<E>>,
+    ) -> Result<ConstraintEvaluationTable<E>, ProvingError>
     where
         FieldElement<F>: ByteConversion,
     {
         // The + 1 is for the boundary constraints column
-        let mut evaluation_table = ConstraintEvaluationTable::new(
-            self.air.context().num_transition_constraints() + 1,
-            &domain.lde_roots_of_unity_coset,
+        let mut evaluation_table = ConstraintEvaluationTable::new_with_buffer(
+            self.air.num_transition_constraints() + 1,
+            domain.lde_domain_size(),
+            evaluations_buffer,
         );
-        let n_trace_colums = self.trace_polys.len();
+        let n_trace_colums = self.trace_polys.len() + self.extension_polys.len();
         let boundary_constraints = &self.boundary_constraints;
+        let primitive_root = E::embed(&self.primitive_root);
+        let coset_offset = E::embed(&domain.coset_offset);
 
-        let domains =
-            boundary_constraints.generate_roots_of_unity(&self.primitive_root, n_trace_colums);
+        let domains = boundary_constraints.generate_roots_of_unity(&primitive_root, n_trace_colums);
         let values = boundary_constraints.values(n_trace_colums);
 
         #[cfg(debug_assertions)]
         let mut boundary_polys = Vec::new();
 
-        let boundary_polys_evaluations: Vec<Vec<FieldElement<F>>> = zip(domains, values)
-            .zip(self.trace_polys)
+        let trace_polys = self
+            .trace_polys
+            .iter()
+            .map(E::embed_polynomial)
+            .chain(self.extension_polys.iter().cloned());
+        let boundary_polys_evaluations: Vec<Vec<FieldElement<E>>> = zip(domains, values)
+            .zip(trace_polys)
             .map(|((xs, ys), trace_poly)| {
                 let boundary_poly = trace_poly
                     - &Polynomial::interpolate(&xs, &ys)
@@ -73,39 +118,37 @@ impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F
                 #[cfg(debug_assertions)]
                 boundary_polys.push(boundary_poly.clone());
 
-                evaluate_polynomial_on_lde_domain(
+                evaluate_challenge_polynomial_on_lde_domain::<F, _>(
                     &boundary_poly,
                     domain.blowup_factor,
                     domain.interpolation_domain_size,
-                    &domain.coset_offset,
+                    &coset_offset,
                 )
-                .unwrap()
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         #[cfg(debug_assertions)]
         let mut boundary_zerofiers = Vec::new();
 
-        let boundary_zerofiers_inverse_evaluations: Vec<Vec<FieldElement<F>>> = (0..n_trace_colums)
+        let boundary_zerofiers_inverse_evaluations: Vec<Vec<FieldElement<E>>> = (0..n_trace_colums)
             .map(|col| {
                 let zerofier = self
                     .boundary_constraints
-                    .compute_zerofier(&self.primitive_root, col);
+                    .compute_zerofier(&primitive_root, col);
 
                 #[cfg(debug_assertions)]
                 boundary_zerofiers.push(zerofier.clone());
 
-                let mut evals = evaluate_polynomial_on_lde_domain(
+                let mut evals = evaluate_challenge_polynomial_on_lde_domain::<F, _>(
                     &zerofier,
                     domain.blowup_factor,
                     domain.interpolation_domain_size,
-                    &domain.coset_offset,
-                )
-                .unwrap();
+                    &coset_offset,
+                )?;
                 FieldElement::inplace_batch_inverse(&mut evals);
-                evals
+                Ok(evals)
             })
-            .collect();
+            .collect::<Result<_, FFTError>>()?;
 
         #[cfg(debug_assertions)]
         for (poly, z) in boundary_polys.iter().zip(boundary_zerofiers.iter()) {
@@ -118,75 +161,99 @@ impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F
         #[cfg(debug_assertions)]
         let mut transition_evaluations = Vec::new();
 
-        let transition_exemptions = self.air.transition_exemptions();
         let trace_length = self.air.context().trace_length;
         let composition_poly_degree_bound = self.air.composition_poly_degree_bound();
         let boundary_term_degree_adjustment = composition_poly_degree_bound - trace_length;
 
-        let transition_exemptions_evaluations: Vec<_> = transition_exemptions
-            .iter()
-            .map(|exemption| {
-                evaluate_polynomial_on_lde_domain(
-                    exemption,
-                    domain.blowup_factor,
-                    domain.interpolation_domain_size,
-                    &domain.coset_offset,
-                )
-                .unwrap()
-            })
-            .collect();
-
         let context = self.air.context();
-        let degree_adjustments: Vec<Vec<FieldElement<F>>> = context
+        let zerofiers = self.air.transition_zerofiers();
+        let degree_adjustments: Vec<Vec<FieldElement<E>>> = context
             .transition_degrees()
             .iter()
-            .map(|transition_degree| {
+            .zip(&zerofiers)
+            .map(|(transition_degree, zerofier)| {
                 domain
                     .lde_roots_of_unity_coset
                     .iter()
                     .map(|d| {
                         let degree_adjustment = composition_poly_degree_bound
-                            - (trace_length * (transition_degree - 1));
-                        d.pow(degree_adjustment)
+                            - zerofier.quotient_degree_bound(trace_length, *transition_degree);
+                        E::embed(&d.pow(degree_adjustment))
                     })
                     .collect()
             })
             .collect();
 
-        let x_n = Polynomial::new_monomial(FieldElement::<F>::one(), trace_length);
-        let x_n_1 = x_n - FieldElement::<F>::one();
+        // The inverse of each distinct zerofier, E(x) / V(x) with V its vanishing
+        // polynomial and E its exemptions polynomial, is evaluated once.
+        let mut distinct_inverse_evaluations: Vec<(&Zerofier, Vec<FieldElement<F>>)> = Vec::new();
+        for zerofier in &zerofiers {
+            if distinct_inverse_evaluations
+                .iter()
+                .all(|(other, _)| *other != zerofier)
+            {
+                let evaluate = |poly: Polynomial<FieldElement<F>>| {
+                    evaluate_polynomial_on_lde_domain(
+                        &poly,
+                        domain.blowup_factor,
+                        domain.interpolation_domain_size,
+                        &domain.coset_offset,
+                    )
+                };
+                let mut evaluations =
+                    evaluate(zerofier.vanishing_polynomial(trace_length, &self.primitive_root))?;
+                FieldElement::inplace_batch_inverse(&mut evaluations);
+                let exemptions =
+                    evaluate(zerofier.exemptions_polynomial(trace_length, &self.primitive_root))?;
+                for (evaluation, exemption) in evaluations.iter_mut().zip(&exemptions) {
+                    *evaluation = &*evaluation * exemption;
+                }
+                distinct_inverse_evaluations.push((zerofier, evaluations));
+            }
+        }
 
-        let mut zerofier_evaluations = evaluate_polynomial_on_lde_domain(
-            &x_n_1,
+        let transition_zerofiers_inverse_evaluations: Vec<Vec<FieldElement<E>>> = zerofiers
+            .iter()
+            .map(|zerofier| {
+                let (_, evaluations) = distinct_inverse_evaluations
+                    .iter()
+                    .find(|(other, _)| *other == zerofier)
+                    .expect("every zerofier has its inverse evaluated");
+                evaluations.iter().map(E::embed).collect()
+            })
+            .collect();
+
+        let periodic_evaluations = periodic::evaluate_on_lde_domain(
+            &self.air.periodic_columns(),
+            trace_length,
             domain.blowup_factor,
-            domain.interpolation_domain_size,
             &domain.coset_offset,
-        )
-        .unwrap();
-
-        FieldElement::inplace_batch_inverse(&mut zerofier_evaluations);
-        let transition_zerofiers_inverse_evaluations: Vec<Vec<FieldElement<F>>> =
-            transition_exemptions_evaluations
-                .iter()
-                .map(|row| {
-                    zerofier_evaluations
-                        .iter()
-                        .zip(row.iter())
-                        .map(|(c1, c2)| c1 * c2)
-                        .collect()
-                })
-                .collect();
+        )?;
 
         // Iterate over trace and domain and compute transitions
         for (i, d) in domain.lde_roots_of_unity_coset.iter().enumerate() {
-            let frame = Frame::read_from_trace(
-                lde_trace,
-                i,
-                blowup_factor,
-                &self.air.context().transition_offsets,
-            );
+            if i % CANCELLATION_CHUNK == 0
+                && matches!(self.cancellation, Some(flag) if flag.load(Ordering::Relaxed))
+            {
+                return Err(ProvingError::Cancelled);
+            }
+            let periodic_values = periodic_evaluations
+                .iter()
+                .map(|column| E::embed(&column[i % column.len()]))
+                .collect();
+            let transition_offsets = &self.air.context().transition_offsets;
+            let frame = Frame::read_from_trace(lde_trace, i, blowup_factor, transition_offsets)
+                .with_extension_columns(lde_extension_trace, i, blowup_factor, transition_offsets)
+                .with_periodic_values(periodic_values)
+                .with_virtual_columns(|row| self.air.evaluate_virtual_columns(row));
 
             let evaluations_transition = self.air.compute_transition(&frame, rap_challenges);
+            if evaluations_transition.len() != self.air.num_transition_constraints() {
+                return Err(ProvingError::TransitionCount {
+                    declared: self.air.num_transition_constraints(),
+                    found: evaluations_transition.len(),
+                });
+            }
 
             #[cfg(debug_assertions)]
             transition_evaluations.push(evaluations_transition.clone());
@@ -208,7 +275,7 @@ impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F
                 alpha_and_beta_transition_coefficients,
             );
 
-            let d_adjustment_power = d.pow(boundary_term_degree_adjustment);
+            let d_adjustment_power = E::embed(&d.pow(boundary_term_degree_adjustment));
             let boundary_evaluation = zip(
                 &boundary_polys_evaluations,
                 &boundary_zerofiers_inverse_evaluations,
@@ -224,14 +291,14 @@ impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F
                         * (&boundary_alpha * &d_adjustment_power + &boundary_beta)
                 },
             )
-            .fold(FieldElement::<F>::zero(), |acc, eval| acc + eval);
+            .fold(FieldElement::<E>::zero(), |acc, eval| acc + eval);
 
             evaluations_sum += boundary_evaluation;
 
             evaluation_table.evaluations_acc.push(evaluations_sum);
         }
 
-        evaluation_table
+        Ok(evaluation_table)
     }
 
     /// Given `evaluations` T_i(x) of the trace polynomial composed with the constraint
@@ -252,12 +319,12 @@ impl<'poly, F: IsFFTField, A: AIR + AIR<Field = F>> ConstraintEvaluator<'poly, F
     ///
     /// Returns the sum of the evaluations computed.
     pub fn compute_constraint_composition_poly_evaluations_sum(
-        evaluations: &[FieldElement<F>],
-        inverse_denominators: &[FieldElement<F>],
-        degree_adjustments: &[FieldElement<F>],
-        constraint_coeffs: &[(FieldElement<F>, FieldElement<F>)],
-    ) -> FieldElement<F> {
-        let mut ret = FieldElement::<F>::zero();
+        evaluations: &[FieldElement<E>],
+        inverse_denominators: &[FieldElement<E>],
+        degree_adjustments: &[FieldElement<E>],
+        constraint_coeffs: &[(FieldElement<E>, FieldElement<E>)],
+    ) -> FieldElement<E> {
+        let mut ret = FieldElement::<E>::zero();
         for (((eval, degree_adjustment), inverse_denominator), (alpha, beta)) in evaluations
             .iter()
             .zip(degree_adjustments)
@@ -1,3 +1,6 @@
 pub mod boundary;
+pub mod degree;
 pub mod evaluation_table;
 pub mod evaluator;
+pub mod registry;
+pub mod transition;
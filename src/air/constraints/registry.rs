@@ -0,0 +1,379 @@
+//! Transition constraints as separate objects.
+//!
+//! Instead of evaluating all its constraints in one `compute_transition`, an
+//! AIR can register them in a `TransitionConstraints`, each with its degree,
+//! exemptions and rows. Gadgets register their own constraints the same way,
+//! so the AIR that uses them doesn't need to know how many they have or what
+//! they read. The AIR then fills its context with `update_context`, which
+//! derives the transition offsets from the rows the constraints read, and
+//! evaluates the registered constraints in `compute_transition`.
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec, vec::Vec};
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsField};
+
+use super::transition::{RowSubset, Zerofier};
+use crate::air::{context::AirContext, frame::Frame};
+
+/// A transition constraint over the field `E`, for an AIR with RAP challenges
+/// `C` and public input `P`.
+pub trait TransitionConstraint<E: IsField, C, P>: Send + Sync {
+    /// Degree of the constraint as a polynomial of the trace columns.
+    fn degree(&self) -> usize;
+
+    /// Number of last rows of its subset the constraint doesn't apply to.
+    fn exemptions(&self) -> usize {
+        0
+    }
+
+    /// Rows the constraint applies to.
+    fn row_subset(&self) -> RowSubset {
+        RowSubset::ALL
+    }
+
+    /// Rows the constraint applies to, as the set its zerofier vanishes on.
+    /// By default, the rows of `row_subset` but the last `exemptions`.
+    fn zerofier(&self, trace_length: usize) -> Zerofier {
+        Zerofier::with_exemptions(self.row_subset(), trace_length, self.exemptions())
+    }
+
+    /// Offsets from the current row of the rows the constraint reads, in
+    /// increasing order. The frame it is evaluated on has these rows, in this
+    /// order. By default, it reads the current and the next row.
+    fn offsets(&self) -> Vec<usize> {
+        vec![0, 1]
+    }
+
+    /// Name of the constraint in the reports of invalid traces.
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    /// Evaluates the constraint on `frame`, with the values of the periodic
+    /// columns at its first row.
+    fn evaluate(
+        &self,
+        frame: &Frame<E>,
+        periodic_values: &[FieldElement<E>],
+        challenges: &C,
+        public_input: &P,
+    ) -> FieldElement<E>;
+}
+
+/// Type of the functions registered by `TransitionConstraints::register_fn`.
+type Evaluation<E, C, P> =
+    dyn Fn(&Frame<E>, &[FieldElement<E>], &C, &P) -> FieldElement<E> + Send + Sync;
+
+/// A constraint given by a function, on every row.
+struct FnConstraint<E: IsField, C, P> {
+    degree: usize,
+    exemptions: usize,
+    offsets: Vec<usize>,
+    evaluate: Box<Evaluation<E, C, P>>,
+}
+
+impl<E: IsField, C, P> TransitionConstraint<E, C, P> for FnConstraint<E, C, P> {
+    fn degree(&self) -> usize {
+        self.degree
+    }
+
+    fn exemptions(&self) -> usize {
+        self.exemptions
+    }
+
+    fn offsets(&self) -> Vec<usize> {
+        self.offsets.clone()
+    }
+
+    fn evaluate(
+        &self,
+        frame: &Frame<E>,
+        periodic_values: &[FieldElement<E>],
+        challenges: &C,
+        public_input: &P,
+    ) -> FieldElement<E> {
+        (self.evaluate)(frame, periodic_values, challenges, public_input)
+    }
+}
+
+/// The transition constraints of an AIR, in the order they are registered.
+pub struct TransitionConstraints<E: IsField, C, P> {
+    constraints: Vec<Arc<dyn TransitionConstraint<E, C, P>>>,
+    /// Offsets read by any constraint, and the current row.
+    offsets: Vec<usize>,
+    /// Rows of the frame of the AIR that form the frame of each constraint, or
+    /// `None` if its offsets are the first ones and it can read that frame.
+    frame_rows: Vec<Option<Vec<usize>>>,
+}
+
+impl<E: IsField, C, P> Clone for TransitionConstraints<E, C, P> {
+    fn clone(&self) -> Self {
+        Self {
+            constraints: self.constraints.clone(),
+            offsets: self.offsets.clone(),
+            frame_rows: self.frame_rows.clone(),
+        }
+    }
+}
+
+impl<E: IsField, C, P> Default for TransitionConstraints<E, C, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: IsField, C, P> TransitionConstraints<E, C, P> {
+    pub fn new() -> Self {
+        Self {
+            constraints: Vec::new(),
+            offsets: vec![0],
+            frame_rows: Vec::new(),
+        }
+    }
+
+    /// Registers `constraint` after the previous ones.
+    pub fn register(&mut self, constraint: impl TransitionConstraint<E, C, P> + 'static) {
+        self.constraints.push(Arc::new(constraint));
+
+        let mut offsets = vec![0];
+        offsets.extend(self.constraints.iter().flat_map(|c| c.offsets()));
+        offsets.sort_unstable();
+        offsets.dedup();
+        self.frame_rows = self
+            .constraints
+            .iter()
+            .map(|c| {
+                let rows: Vec<usize> = c
+                    .offsets()
+                    .iter()
+                    .map(|offset| offsets.binary_search(offset).unwrap())
+                    .collect();
+                let is_prefix = rows.iter().enumerate().all(|(i, row)| i == *row);
+                (!is_prefix).then_some(rows)
+            })
+            .collect();
+        self.offsets = offsets;
+    }
+
+    /// Registers the constraint `evaluate` of degree `degree`, which applies
+    /// to every row but the last `exemptions` and reads the current and the
+    /// next row. Without exemptions, the constraint is cyclic: the next row
+    /// of the last one is the first.
+    pub fn register_fn(
+        &mut self,
+        degree: usize,
+        exemptions: usize,
+        evaluate: impl Fn(&Frame<E>, &[FieldElement<E>], &C, &P) -> FieldElement<E>
+            + Send
+            + Sync
+            + 'static,
+    ) where
+        C: 'static,
+        P: 'static,
+    {
+        self.register_fn_at(&[0, 1], degree, exemptions, evaluate);
+    }
+
+    /// Same as `register_fn`, for a constraint that reads the rows at
+    /// `offsets`, in increasing order, which are the rows of its frame.
+    pub fn register_fn_at(
+        &mut self,
+        offsets: &[usize],
+        degree: usize,
+        exemptions: usize,
+        evaluate: impl Fn(&Frame<E>, &[FieldElement<E>], &C, &P) -> FieldElement<E>
+            + Send
+            + Sync
+            + 'static,
+    ) where
+        C: 'static,
+        P: 'static,
+    {
+        self.register(FnConstraint {
+            degree,
+            exemptions,
+            offsets: offsets.to_vec(),
+            evaluate: Box::new(evaluate),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    pub fn degrees(&self) -> Vec<usize> {
+        self.constraints.iter().map(|c| c.degree()).collect()
+    }
+
+    pub fn exemptions(&self) -> Vec<usize> {
+        self.constraints.iter().map(|c| c.exemptions()).collect()
+    }
+
+    /// Offsets read by any of the constraints, and the current row, in
+    /// increasing order.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// The rows of each constraint, for `AIR::transition_row_subsets`.
+    pub fn row_subsets(&self) -> Vec<RowSubset> {
+        self.constraints.iter().map(|c| c.row_subset()).collect()
+    }
+
+    /// The zerofier of each constraint, for `AIR::transition_zerofiers`.
+    pub fn zerofiers(&self, trace_length: usize) -> Vec<Zerofier> {
+        self.constraints
+            .iter()
+            .map(|c| c.zerofier(trace_length))
+            .collect()
+    }
+
+    /// Sets the number, degrees, exemptions, labels and offsets of the
+    /// transition constraints of `context` to the ones of the registered
+    /// constraints. The labels are left empty if no constraint has one.
+    pub fn update_context(&self, context: &mut AirContext) {
+        context.num_transition_constraints = self.len();
+        context.transition_offsets = self.offsets.clone();
+        context.transition_degrees = self.degrees();
+        context.transition_exemptions = self.exemptions();
+        let labels: Vec<_> = self.constraints.iter().map(|c| c.label()).collect();
+        context.constraint_labels = if labels.iter().all(Option::is_none) {
+            Vec::new()
+        } else {
+            labels.into_iter().map(Option::unwrap_or_default).collect()
+        };
+    }
+
+    /// Evaluates every constraint on `frame`, whose rows are at `offsets`, for
+    /// `AIR::compute_transition`.
+    pub fn evaluate(
+        &self,
+        frame: &Frame<E>,
+        challenges: &C,
+        public_input: &P,
+    ) -> Vec<FieldElement<E>> {
+        let periodic_values = frame.periodic_values();
+        self.constraints
+            .iter()
+            .zip(&self.frame_rows)
+            .map(|(c, rows)| match rows {
+                None => c.evaluate(frame, periodic_values, challenges, public_input),
+                Some(rows) => c.evaluate(
+                    &frame.select_rows(rows),
+                    periodic_values,
+                    challenges,
+                    public_input,
+                ),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::context::ProofOptions;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    /// `next = current + step`, with `step` from the public input.
+    struct Step;
+
+    impl TransitionConstraint<F, (), FE> for Step {
+        fn degree(&self) -> usize {
+            1
+        }
+
+        fn exemptions(&self) -> usize {
+            1
+        }
+
+        fn label(&self) -> Option<String> {
+            Some("step".to_string())
+        }
+
+        fn evaluate(
+            &self,
+            frame: &Frame<F>,
+            _periodic_values: &[FE],
+            _challenges: &(),
+            step: &FE,
+        ) -> FE {
+            &frame.get_row(1)[0] - &frame.get_row(0)[0] - step
+        }
+    }
+
+    #[test]
+    fn registered_constraints_fill_the_context_and_are_evaluated_in_order() {
+        let mut constraints = TransitionConstraints::<F, (), FE>::new();
+        constraints.register(Step);
+        constraints.register_fn(2, 0, |frame, periodic_values, _, _| {
+            let value = &frame.get_row(0)[0];
+            value * value - &periodic_values[0]
+        });
+
+        let mut context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: 4,
+            trace_columns: 1,
+            transition_degrees: Vec::new(),
+            transition_exemptions: Vec::new(),
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 0,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        constraints.update_context(&mut context);
+        assert_eq!(context.num_transition_constraints, 2);
+        assert_eq!(context.transition_degrees, vec![1, 2]);
+        assert_eq!(context.transition_exemptions, vec![1, 0]);
+        assert_eq!(context.transition_offsets, vec![0, 1]);
+        assert_eq!(
+            context.constraint_labels,
+            vec!["step".to_string(), String::new()]
+        );
+        assert_eq!(constraints.row_subsets(), vec![RowSubset::ALL; 2]);
+
+        let frame =
+            Frame::new(vec![FE::from(3), FE::from(5)], 1).with_periodic_values(vec![FE::from(9)]);
+        assert_eq!(
+            constraints.evaluate(&frame, &(), &FE::from(2)),
+            vec![FE::zero(), FE::zero()]
+        );
+        assert_ne!(
+            constraints.evaluate(&frame, &(), &FE::from(1))[0],
+            FE::zero()
+        );
+    }
+
+    #[test]
+    fn constraints_are_evaluated_on_the_rows_at_their_offsets() {
+        let mut constraints = TransitionConstraints::<F, (), ()>::new();
+        constraints.register_fn(1, 1, |frame, _, _, _| {
+            &frame.get_row(1)[0] - &frame.get_row(0)[0] - FE::one()
+        });
+        constraints.register_fn_at(&[0, 2], 1, 2, |frame, _, _, _| {
+            &frame.get_row(1)[0] - &frame.get_row(0)[0] - FE::from(2)
+        });
+        assert_eq!(constraints.offsets(), &[0, 1, 2]);
+
+        let frame = Frame::new(vec![FE::from(3), FE::from(4), FE::from(5)], 1);
+        assert_eq!(
+            constraints.evaluate(&frame, &(), &()),
+            vec![FE::zero(), FE::zero()]
+        );
+    }
+}
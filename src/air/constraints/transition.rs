@@ -0,0 +1,383 @@
+use alloc::{format, vec::Vec};
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+};
+
+use crate::prover::ProvingError;
+
+/// Rows a transition constraint applies to: `offset`, `offset + period`,
+/// `offset + 2 * period`, ... The zerofier of the constraint only vanishes on
+/// them, so strictly periodic structure doesn't need selector columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowSubset {
+    pub period: usize,
+    pub offset: usize,
+}
+
+impl RowSubset {
+    /// Every row of the trace.
+    pub const ALL: Self = Self {
+        period: 1,
+        offset: 0,
+    };
+
+    pub fn new(period: usize, offset: usize) -> Self {
+        Self { period, offset }
+    }
+
+    /// Number of rows of the subset in a trace of length `trace_length`.
+    pub fn rows(&self, trace_length: usize) -> usize {
+        trace_length / self.period
+    }
+
+    pub fn contains(&self, row: usize) -> bool {
+        row % self.period == self.offset
+    }
+
+    /// The last `exemptions` rows of the subset, which the constraint doesn't
+    /// apply to.
+    pub fn exempted_rows(
+        &self,
+        trace_length: usize,
+        exemptions: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let len = self.rows(trace_length);
+        (1..=exemptions).map(move |i| self.offset + (len - i) * self.period)
+    }
+
+    /// Polynomial vanishing on the rows of the subset, `x^(n/k) - g^(r n/k)`
+    /// for a trace of length `n` and primitive root `g`, period `k` and
+    /// offset `r`.
+    pub fn vanishing_polynomial<F: IsFFTField>(
+        &self,
+        trace_length: usize,
+        primitive_root: &FieldElement<F>,
+    ) -> Polynomial<FieldElement<F>> {
+        let len = self.rows(trace_length);
+        Polynomial::new_monomial(FieldElement::one(), len) - primitive_root.pow(self.offset * len)
+    }
+
+    /// Degree bound of the quotient of a constraint of degree
+    /// `transition_degree` by the vanishing polynomial of the subset.
+    pub fn quotient_degree_bound(&self, trace_length: usize, transition_degree: usize) -> usize {
+        trace_length * transition_degree - self.rows(trace_length)
+    }
+}
+
+/// Rows a transition constraint applies to, as the set its zerofier vanishes
+/// on: the rows of a subset, except those of some smaller subsets of it and
+/// some single rows, plus some other rows. The zerofier is the quotient of the
+/// polynomials `vanishing_polynomial` and `exemptions_polynomial`, for
+/// constraints such as one on the rows ≡ 0 mod 16 but the last block, or one
+/// on every row but those ≡ 15 mod 16.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Zerofier {
+    subset: RowSubset,
+    excluded_subsets: Vec<RowSubset>,
+    excluded_rows: Vec<usize>,
+    included_rows: Vec<usize>,
+}
+
+impl Zerofier {
+    /// Every row of `subset`.
+    pub fn new(subset: RowSubset) -> Self {
+        Self {
+            subset,
+            excluded_subsets: Vec::new(),
+            excluded_rows: Vec::new(),
+            included_rows: Vec::new(),
+        }
+    }
+
+    /// Every row of `subset` but its last `exemptions`, in a trace of length
+    /// `trace_length`. This is the zerofier of a constraint by default.
+    pub fn with_exemptions(subset: RowSubset, trace_length: usize, exemptions: usize) -> Self {
+        Self::new(subset).excluding_rows(subset.exempted_rows(trace_length, exemptions))
+    }
+
+    /// The same rows, except those of `subset`, which has to be contained in
+    /// the subset of the zerofier.
+    pub fn excluding(mut self, subset: RowSubset) -> Self {
+        self.excluded_subsets.push(subset);
+        self
+    }
+
+    /// The same rows, except `rows`.
+    pub fn excluding_rows(mut self, rows: impl IntoIterator<Item = usize>) -> Self {
+        self.excluded_rows.extend(rows);
+        self
+    }
+
+    /// The same rows, plus `rows`, which are outside the subset.
+    pub fn including_rows(mut self, rows: impl IntoIterator<Item = usize>) -> Self {
+        self.included_rows.extend(rows);
+        self
+    }
+
+    pub fn contains(&self, row: usize) -> bool {
+        self.included_rows.contains(&row)
+            || (self.subset.contains(row)
+                && !self.excluded_rows.contains(&row)
+                && !self
+                    .excluded_subsets
+                    .iter()
+                    .any(|subset| subset.contains(row)))
+    }
+
+    /// Polynomial vanishing on the rows of the subset and the included rows.
+    pub fn vanishing_polynomial<F: IsFFTField>(
+        &self,
+        trace_length: usize,
+        primitive_root: &FieldElement<F>,
+    ) -> Polynomial<FieldElement<F>> {
+        let rows = rows_polynomial(&self.included_rows, primitive_root);
+        self.subset
+            .vanishing_polynomial(trace_length, primitive_root)
+            * rows
+    }
+
+    /// Polynomial vanishing on the excluded subsets and rows, which divides
+    /// `vanishing_polynomial`.
+    pub fn exemptions_polynomial<F: IsFFTField>(
+        &self,
+        trace_length: usize,
+        primitive_root: &FieldElement<F>,
+    ) -> Polynomial<FieldElement<F>> {
+        self.excluded_subsets
+            .iter()
+            .map(|subset| subset.vanishing_polynomial(trace_length, primitive_root))
+            .fold(
+                rows_polynomial(&self.excluded_rows, primitive_root),
+                |acc, vanishing| acc * vanishing,
+            )
+    }
+
+    /// Degree bound of the quotient of a constraint of degree
+    /// `transition_degree` by the zerofier. As with the exemptions of a row
+    /// subset, the excluded rows are left out of the bound.
+    pub fn quotient_degree_bound(&self, trace_length: usize, transition_degree: usize) -> usize {
+        let excluded: usize = self
+            .excluded_subsets
+            .iter()
+            .map(|subset| subset.rows(trace_length))
+            .sum();
+        self.subset
+            .quotient_degree_bound(trace_length, transition_degree)
+            + excluded
+            - self.included_rows.len()
+    }
+
+    /// Why the zerofier doesn't fit a trace of length `trace_length`, if it
+    /// doesn't.
+    fn invalid_reason(&self, trace_length: usize) -> Option<&'static str> {
+        let fits = |subset: &RowSubset| {
+            subset.period.is_power_of_two()
+                && subset.period <= trace_length
+                && subset.offset < subset.period
+        };
+        if !fits(&self.subset) || !self.excluded_subsets.iter().all(fits) {
+            return Some("a subset doesn't fit the trace");
+        }
+        if !self.excluded_subsets.iter().all(|excluded| {
+            excluded.period % self.subset.period == 0
+                && excluded.offset % self.subset.period == self.subset.offset
+        }) {
+            return Some("an excluded subset isn't part of the subset");
+        }
+        for (i, first) in self.excluded_subsets.iter().enumerate() {
+            for second in &self.excluded_subsets[i + 1..] {
+                let (small, large) = if first.period <= second.period {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                if large.offset % small.period == small.offset {
+                    return Some("two excluded subsets overlap");
+                }
+            }
+        }
+        let distinct = |rows: &[usize]| {
+            rows.iter()
+                .enumerate()
+                .all(|(i, row)| !rows[i + 1..].contains(row))
+        };
+        if !distinct(&self.excluded_rows) || !distinct(&self.included_rows) {
+            return Some("a row is excluded or included twice");
+        }
+        if !self.excluded_rows.iter().all(|row| {
+            *row < trace_length
+                && self.subset.contains(*row)
+                && !self
+                    .excluded_subsets
+                    .iter()
+                    .any(|subset| subset.contains(*row))
+        }) {
+            return Some("an excluded row isn't in the rest of the subset");
+        }
+        if !self
+            .included_rows
+            .iter()
+            .all(|row| *row < trace_length && !self.subset.contains(*row))
+        {
+            return Some("an included row is in the subset or outside the trace");
+        }
+        if !(0..trace_length).any(|row| self.contains(row)) {
+            return Some("there are no rows left");
+        }
+        None
+    }
+}
+
+/// Polynomial vanishing on the given rows, ∏ (x - gʳ).
+fn rows_polynomial<F: IsFFTField>(
+    rows: &[usize],
+    primitive_root: &FieldElement<F>,
+) -> Polynomial<FieldElement<F>> {
+    let x = Polynomial::new_monomial(FieldElement::one(), 1);
+    rows.iter().fold(
+        Polynomial::new_monomial(FieldElement::one(), 0),
+        |acc, row| acc * (&x - &primitive_root.pow(*row)),
+    )
+}
+
+/// Checks that there is a zerofier per transition constraint, and that the
+/// rows it excludes and includes are consistent with its subset.
+pub(crate) fn check_zerofiers(
+    zerofiers: &[Zerofier],
+    num_transition_constraints: usize,
+    trace_length: usize,
+) -> Result<(), ProvingError> {
+    if zerofiers.len() != num_transition_constraints {
+        return Err(ProvingError::WrongParameter(format!(
+            "there are {} zerofiers for {num_transition_constraints} transition constraints",
+            zerofiers.len(),
+        )));
+    }
+    for (index, zerofier) in zerofiers.iter().enumerate() {
+        if let Some(reason) = zerofier.invalid_reason(trace_length) {
+            return Err(ProvingError::WrongParameter(format!(
+                "transition constraint {index} has an invalid zerofier: {reason}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that there is a row subset per transition constraint, with a
+/// period that divides the trace length and more rows than its exemptions.
+pub(crate) fn check_row_subsets(
+    subsets: &[RowSubset],
+    exemptions: &[usize],
+    trace_length: usize,
+) -> Result<(), ProvingError> {
+    if subsets.len() != exemptions.len() {
+        return Err(ProvingError::WrongParameter(format!(
+            "there are {} row subsets for {} transition constraints",
+            subsets.len(),
+            exemptions.len()
+        )));
+    }
+    for (index, (subset, exemptions)) in subsets.iter().zip(exemptions).enumerate() {
+        if !subset.period.is_power_of_two()
+            || subset.period > trace_length
+            || subset.offset >= subset.period
+            || *exemptions >= subset.rows(trace_length)
+        {
+            return Err(ProvingError::WrongParameter(format!(
+                "transition constraint {index} applies to an invalid row subset {subset:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// For each transition constraint, whether it applies to each row of a trace
+/// of length `trace_length`.
+pub(crate) fn active_rows(zerofiers: &[Zerofier], trace_length: usize) -> Vec<Vec<bool>> {
+    zerofiers
+        .iter()
+        .map(|zerofier| {
+            (0..trace_length)
+                .map(|row| zerofier.contains(row))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::{
+        fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, traits::IsFFTField,
+    };
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+
+    #[test]
+    fn vanishing_polynomial_vanishes_exactly_on_the_subset() {
+        let root = F::get_primitive_root_of_unity(4).unwrap();
+        let subset = RowSubset::new(4, 1);
+        let poly = subset.vanishing_polynomial(16, &root);
+
+        for row in 0..16 {
+            let vanishes = poly.evaluate(&root.pow(row)) == FieldElement::zero();
+            assert_eq!(vanishes, subset.contains(row));
+        }
+        assert_eq!(subset.exempted_rows(16, 2).collect::<Vec<_>>(), vec![13, 9]);
+    }
+
+    #[test]
+    fn zerofier_vanishes_exactly_on_its_rows() {
+        let root = F::get_primitive_root_of_unity(4).unwrap();
+        // The even rows, but those of 2 mod 4 and row 8, plus row 3.
+        let zerofier = Zerofier::new(RowSubset::new(2, 0))
+            .excluding(RowSubset::new(4, 2))
+            .excluding_rows([8])
+            .including_rows([3]);
+        let vanishing = zerofier.vanishing_polynomial(16, &root);
+        let exemptions = zerofier.exemptions_polynomial(16, &root);
+
+        for row in 0..16 {
+            let x = root.pow(row);
+            let vanishes = vanishing.evaluate(&x) == FieldElement::zero()
+                && exemptions.evaluate(&x) != FieldElement::zero();
+            assert_eq!(vanishes, zerofier.contains(row));
+        }
+        assert_eq!(
+            (0..16)
+                .filter(|row| zerofier.contains(*row))
+                .collect::<Vec<_>>(),
+            vec![0, 3, 4, 12]
+        );
+        assert_eq!(zerofier.quotient_degree_bound(16, 2), 32 - 5);
+        assert!(check_zerofiers(&[zerofier], 1, 16).is_ok());
+    }
+
+    #[test]
+    fn inconsistent_zerofiers_are_rejected() {
+        let every_row = || Zerofier::new(RowSubset::ALL);
+        assert!(check_zerofiers(&[every_row()], 2, 16).is_err());
+        assert!(check_zerofiers(&[every_row().excluding(RowSubset::ALL)], 1, 16).is_err());
+        assert!(check_zerofiers(&[every_row().excluding(RowSubset::new(3, 0))], 1, 16).is_err());
+        let overlapping = every_row()
+            .excluding(RowSubset::new(2, 0))
+            .excluding(RowSubset::new(4, 0));
+        assert!(check_zerofiers(&[overlapping], 1, 16).is_err());
+        let outside = Zerofier::new(RowSubset::new(2, 0)).excluding(RowSubset::new(4, 1));
+        assert!(check_zerofiers(&[outside], 1, 16).is_err());
+        assert!(check_zerofiers(&[every_row().excluding_rows([16])], 1, 16).is_err());
+        assert!(check_zerofiers(&[every_row().including_rows([3])], 1, 16).is_err());
+        assert!(check_zerofiers(&[every_row().excluding_rows([2, 2])], 1, 16).is_err());
+    }
+
+    #[test]
+    fn subsets_that_dont_fit_the_trace_are_rejected() {
+        assert!(check_row_subsets(&[RowSubset::new(4, 3)], &[3], 16).is_ok());
+        assert!(check_row_subsets(&[RowSubset::new(4, 4)], &[0], 16).is_err());
+        assert!(check_row_subsets(&[RowSubset::new(3, 0)], &[0], 16).is_err());
+        assert!(check_row_subsets(&[RowSubset::new(4, 0)], &[4], 16).is_err());
+        assert!(check_row_subsets(&[RowSubset::ALL], &[], 16).is_err());
+    }
+}
@@ -1,3 +1,11 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::prover::ProvingError;
+
 #[derive(Clone, Debug)]
 pub struct AirContext {
     pub options: ProofOptions,
@@ -9,13 +17,55 @@ pub struct AirContext {
     /// an evaluation frame. Note that, because of how we write all constraints
     /// in one method (`compute_transitions`), this vector needs to include the
     /// offsets that are needed to compute EVERY transition constraint, even if some
-    /// constraints don't use all of the indexes in said offsets.
+    /// constraints don't use all of the indexes in said offsets. The DSL and
+    /// `TransitionConstraints::update_context` derive it from the constraints.
     pub transition_offsets: Vec<usize>,
     pub transition_exemptions: Vec<usize>,
     pub num_transition_constraints: usize,
+    /// Names of the trace columns, auxiliary ones included, or empty if the
+    /// columns aren't named. Error messages and the debug validator use them.
+    pub column_names: Vec<String>,
+    /// Labels of the transition constraints, in the order of
+    /// `compute_transition`, or empty if they aren't labeled.
+    pub constraint_labels: Vec<String>,
 }
 
 impl AirContext {
+    /// Context of a trace of `trace_columns` columns and `trace_length` rows,
+    /// with a transition constraint of each degree in `transition_degrees`.
+    /// The columns and constraints have no names, see `with_column_names`
+    /// and `with_constraint_labels`.
+    pub fn new(
+        options: ProofOptions,
+        trace_length: usize,
+        trace_columns: usize,
+        transition_degrees: Vec<usize>,
+        transition_offsets: Vec<usize>,
+        transition_exemptions: Vec<usize>,
+    ) -> Self {
+        Self {
+            options,
+            trace_length,
+            trace_columns,
+            num_transition_constraints: transition_degrees.len(),
+            transition_degrees,
+            transition_offsets,
+            transition_exemptions,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        }
+    }
+
+    pub fn with_column_names(mut self, column_names: Vec<String>) -> Self {
+        self.column_names = column_names;
+        self
+    }
+
+    pub fn with_constraint_labels(mut self, constraint_labels: Vec<String>) -> Self {
+        self.constraint_labels = constraint_labels;
+        self
+    }
+
     pub fn num_transition_constraints(&self) -> usize {
         self.num_transition_constraints
     }
@@ -27,11 +77,222 @@ impl AirContext {
     pub fn transition_degrees_len(&self) -> usize {
         self.transition_degrees.len()
     }
+
+    /// Label of the transition constraint `constraint`, if it has one.
+    pub fn constraint_label(&self, constraint: usize) -> Option<&str> {
+        self.constraint_labels.get(constraint).map(String::as_str)
+    }
+
+    /// The column `column` for a message, with its name if it has one.
+    pub fn describe_column(&self, column: usize) -> String {
+        match self.column_names.get(column) {
+            Some(name) => format!("column {column} ({name})"),
+            None => format!("column {column}"),
+        }
+    }
+
+    /// The transition constraint `constraint` for a message, with its label
+    /// if it has one.
+    pub fn describe_constraint(&self, constraint: usize) -> String {
+        match self.constraint_label(constraint) {
+            Some(label) => format!("transition constraint {constraint} ({label})"),
+            None => format!("transition constraint {constraint}"),
+        }
+    }
+
+    /// Checks the options and that the transition constraints are consistent
+    /// with the trace, so that a wrong parameter is reported before proving or
+    /// verifying instead of failing deep inside the FFT.
+    pub fn validate(&self) -> Result<(), ProvingError> {
+        self.options.validate()?;
+        let wrong = |message: String| Err(ProvingError::WrongParameter(message));
+
+        if !self.trace_length.is_power_of_two() {
+            return wrong(format!(
+                "the trace length {} isn't a power of two",
+                self.trace_length
+            ));
+        }
+        if self.trace_columns == 0 {
+            return wrong("the trace has no columns".to_string());
+        }
+        match self.transition_offsets.iter().max() {
+            None => return wrong("the transition offsets are empty".to_string()),
+            Some(&offset) if offset >= self.trace_length => {
+                return wrong(format!(
+                    "the transition offset {offset} exceeds the trace length {}",
+                    self.trace_length
+                ))
+            }
+            _ => {}
+        }
+        if self.transition_exemptions.len() != self.num_transition_constraints {
+            return wrong(format!(
+                "there are {} transition exemptions for {} transition constraints",
+                self.transition_exemptions.len(),
+                self.num_transition_constraints
+            ));
+        }
+        if self.transition_degrees.len() != self.num_transition_constraints {
+            return wrong(format!(
+                "there are {} transition degrees for {} transition constraints",
+                self.transition_degrees.len(),
+                self.num_transition_constraints
+            ));
+        }
+        if !self.column_names.is_empty() && self.column_names.len() != self.trace_columns {
+            return wrong(format!(
+                "there are {} column names for {} trace columns",
+                self.column_names.len(),
+                self.trace_columns
+            ));
+        }
+        if !self.constraint_labels.is_empty()
+            && self.constraint_labels.len() != self.num_transition_constraints
+        {
+            return wrong(format!(
+                "there are {} constraint labels for {} transition constraints",
+                self.constraint_labels.len(),
+                self.num_transition_constraints
+            ));
+        }
+        if let Some(&exemptions) = self
+            .transition_exemptions
+            .iter()
+            .find(|&&exemptions| exemptions >= self.trace_length)
+        {
+            return wrong(format!(
+                "a transition constraint is exempted from {exemptions} rows of a trace of {}",
+                self.trace_length
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProofOptions {
     pub blowup_factor: u8,
     pub fri_number_of_queries: usize,
     pub coset_offset: u64,
+    /// Checks the trace against the constraints before committing to it, and
+    /// fails with `ProvingError::InvalidTrace` if it violates some. Debug
+    /// builds always check it, but only log the violations.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub validate_trace: bool,
+}
+
+impl ProofOptions {
+    /// Options that don't validate the trace before proving it, see
+    /// `with_trace_validation`.
+    pub fn new(blowup_factor: u8, fri_number_of_queries: usize, coset_offset: u64) -> Self {
+        Self {
+            blowup_factor,
+            fri_number_of_queries,
+            coset_offset,
+            validate_trace: false,
+        }
+    }
+
+    /// Options with about 100 bits of conjectured security: each FRI query
+    /// over an LDE with a blowup factor of 4 adds 2 bits. The prover has no
+    /// proof of work to add to them.
+    pub fn secure() -> Self {
+        Self::new(4, 50, 3)
+    }
+
+    /// Checks the trace against the constraints before committing to it, in
+    /// release builds too.
+    pub fn with_trace_validation(mut self) -> Self {
+        self.validate_trace = true;
+        self
+    }
+
+    /// Checks that the options define a valid LDE domain and FRI query phase.
+    pub fn validate(&self) -> Result<(), ProvingError> {
+        let wrong = |message: String| Err(ProvingError::WrongParameter(message));
+        if !self.blowup_factor.is_power_of_two() {
+            return wrong(format!(
+                "the blowup factor {} isn't a power of two",
+                self.blowup_factor
+            ));
+        }
+        if self.fri_number_of_queries == 0 {
+            return wrong("the number of FRI queries is zero".to_string());
+        }
+        if self.coset_offset == 0 {
+            return wrong("the coset offset is zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> AirContext {
+        AirContext::new(
+            ProofOptions::new(2, 1, 3),
+            8,
+            1,
+            vec![1],
+            vec![0, 1, 2],
+            vec![2],
+        )
+    }
+
+    #[test]
+    fn valid_context_passes() {
+        assert!(context().validate().is_ok());
+    }
+
+    #[test]
+    fn secure_options_have_at_least_80_bits_of_security() {
+        let options = ProofOptions::secure();
+        assert!(options.validate().is_ok());
+        let bits_per_query = options.blowup_factor.trailing_zeros() as usize;
+        assert!(options.fri_number_of_queries * bits_per_query >= 80);
+    }
+
+    #[test]
+    fn columns_and_constraints_are_described_with_their_names() {
+        let mut context = context();
+        assert_eq!(context.describe_column(0), "column 0");
+        assert_eq!(context.describe_constraint(0), "transition constraint 0");
+
+        context = context
+            .with_column_names(vec!["fib".to_string()])
+            .with_constraint_labels(vec!["fibonacci step".to_string()]);
+        assert!(context.validate().is_ok());
+        assert_eq!(context.describe_column(0), "column 0 (fib)");
+        assert_eq!(
+            context.describe_constraint(0),
+            "transition constraint 0 (fibonacci step)"
+        );
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let invalid: [fn(&mut AirContext); 9] = [
+            |c| c.options.blowup_factor = 3,
+            |c| c.options.fri_number_of_queries = 0,
+            |c| c.options.coset_offset = 0,
+            |c| c.transition_offsets = vec![0, 8],
+            |c| c.num_transition_constraints = 2,
+            |c| c.transition_exemptions = vec![8],
+            |c| c.transition_degrees = Vec::new(),
+            |c| c.column_names = vec!["a".to_string(), "b".to_string()],
+            |c| c.constraint_labels = vec![String::new(); 2],
+        ];
+        for change in invalid {
+            let mut context = context();
+            change(&mut context);
+            assert!(matches!(
+                context.validate(),
+                Err(ProvingError::WrongParameter(_))
+            ));
+        }
+    }
 }
@@ -1,27 +1,151 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
 use lambdaworks_fft::polynomial::FFTPoly;
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
     polynomial::Polynomial,
 };
 use log::{error, info};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::{
-    air::{frame::Frame, trace::TraceTable},
-    Domain,
+    air::{constraints::transition::active_rows, frame::Frame, periodic, trace::TraceTable},
+    Domain, IsChallengeField, MaybeSync,
 };
 
 use super::traits::AIR;
 
-/// Validates that the trace is valid with respect to the supplied AIR constraints
+/// A boundary constraint the trace doesn't satisfy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundaryViolation<E: IsField> {
+    pub column: usize,
+    /// Name of the column in the AIR context, if it has one.
+    pub column_name: Option<String>,
+    pub row: usize,
+    pub expected: FieldElement<E>,
+    pub actual: FieldElement<E>,
+}
+
+impl<E: IsField> fmt::Display for BoundaryViolation<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "boundary constraint on column {}", self.column)?;
+        if let Some(name) = &self.column_name {
+            write!(f, " ({name})")?;
+        }
+        write!(
+            f,
+            " and row {}: expected {:?}, found {:?}",
+            self.row, self.expected, self.actual
+        )
+    }
+}
+
+/// A transition constraint that doesn't evaluate to zero on a row it applies
+/// to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransitionViolation<E: IsField> {
+    pub constraint: usize,
+    /// Label of the constraint in the AIR context, if it has one.
+    pub label: Option<String>,
+    pub row: usize,
+    /// Rows of the evaluation frame, in the order of the transition offsets.
+    pub frame: Vec<Vec<FieldElement<E>>>,
+    /// Values of the periodic columns at the row.
+    pub periodic_values: Vec<FieldElement<E>>,
+    /// Evaluation of the constraint on the frame.
+    pub residue: FieldElement<E>,
+}
+
+impl<E: IsField> fmt::Display for TransitionViolation<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transition constraint {}", self.constraint)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({label})")?;
+        }
+        write!(
+            f,
+            " at row {}: evaluates to {:?} on the frame {:?}",
+            self.row, self.residue, self.frame
+        )
+    }
+}
+
+/// The constraints a trace violates, as found by `validate_trace`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceReport<E: IsField> {
+    pub boundary: Vec<BoundaryViolation<E>>,
+    /// Violations ordered by row, then by constraint.
+    pub transition: Vec<TransitionViolation<E>>,
+}
+
+impl<E: IsField> TraceReport<E> {
+    /// Whether the trace satisfies every constraint.
+    pub fn is_valid(&self) -> bool {
+        self.boundary.is_empty() && self.transition.is_empty()
+    }
+
+    /// The first violation, boundary ones before transition ones.
+    pub fn first_violation(&self) -> Option<String> {
+        match (self.boundary.first(), self.transition.first()) {
+            (Some(violation), _) => Some(violation.to_string()),
+            (None, Some(violation)) => Some(violation.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// The first row on which some transition constraint is violated.
+    pub fn first_failing_row(&self) -> Option<usize> {
+        self.transition.first().map(|violation| violation.row)
+    }
+}
+
+/// Evaluates every constraint of `air` on the trace given by its polynomials
+/// and reports the ones it violates, also logging them as errors.
 pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
     air: &A,
     trace_polys: &[Polynomial<FieldElement<A::Field>>],
     domain: &Domain<A::Field>,
     public_input: &A::PublicInput,
     rap_challenges: &A::RAPChallenges,
-) -> bool {
+) -> TraceReport<A::ChallengeField>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    validate_extended_trace(air, trace_polys, &[], domain, public_input, rap_challenges)
+}
+
+/// Same as `validate_trace`, for a trace with columns over the challenge field
+/// after the ones over `F`, given by `extension_polys`.
+pub fn validate_extended_trace<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    trace_polys: &[Polynomial<FieldElement<A::Field>>],
+    extension_polys: &[Polynomial<FieldElement<A::ChallengeField>>],
+    domain: &Domain<A::Field>,
+    public_input: &A::PublicInput,
+    rap_challenges: &A::RAPChallenges,
+) -> TraceReport<A::ChallengeField>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
     info!("Starting constraints validation over trace...");
-    let mut ret = true;
+    let context = air.context();
+    let mut report = TraceReport {
+        boundary: Vec::new(),
+        transition: Vec::new(),
+    };
 
     let trace_columns: Vec<_> = trace_polys
         .iter()
@@ -31,51 +155,99 @@ pub fn validate_trace<F: IsFFTField, A: AIR<Field = F>>(
         })
         .collect();
     let trace = TraceTable::new_from_cols(&trace_columns);
+    let extension_columns: Vec<_> = extension_polys
+        .iter()
+        .map(|poly| {
+            A::ChallengeField::evaluate_offset_fft(
+                poly,
+                1,
+                Some(domain.interpolation_domain_size),
+                &FieldElement::one(),
+            )
+            .unwrap()
+        })
+        .collect();
+    let extension_trace = if extension_columns.is_empty() {
+        TraceTable::empty()
+    } else {
+        TraceTable::new_from_cols(&extension_columns)
+    };
 
     // --------- VALIDATE BOUNDARY CONSTRAINTS ------------
-    air.boundary_constraints(rap_challenges, public_input)
+    for constraint in air
+        .boundary_constraints(rap_challenges, public_input)
         .constraints
-        .iter()
-        .for_each(|constraint| {
-            let col = constraint.col;
-            let step = constraint.step;
-            let boundary_value = constraint.value.clone();
-            let trace_value = trace.get(step, col);
-
-            if boundary_value != trace_value {
-                ret = false;
-                error!("Boundary constraint inconsistency - Expected value {:?} in step {} and column {}, found: {:?}", boundary_value, step, col, trace_value);
-            }
-        });
+    {
+        let actual = if constraint.col < trace.n_cols {
+            A::ChallengeField::embed(&trace.get(constraint.step, constraint.col))
+        } else {
+            extension_trace.get(constraint.step, constraint.col - trace.n_cols)
+        };
+        if constraint.value != actual {
+            let violation = BoundaryViolation {
+                column: constraint.col,
+                column_name: context.column_names.get(constraint.col).cloned(),
+                row: constraint.step,
+                expected: constraint.value,
+                actual,
+            };
+            error!("Boundary constraint inconsistency - {violation}");
+            report.boundary.push(violation);
+        }
+    }
 
     // --------- VALIDATE TRANSITION CONSTRAINTS -----------
-    let n_transition_constraints = air.context().num_transition_constraints();
-    let transition_exemptions = &air.context().transition_exemptions;
+    let active_rows = active_rows(&air.transition_zerofiers(), trace.n_rows());
 
-    let exemption_steps: Vec<usize> = vec![trace.n_rows(); n_transition_constraints]
-        .iter()
-        .zip(transition_exemptions)
-        .map(|(trace_steps, exemptions)| trace_steps - exemptions)
-        .collect();
+    let periodic_columns = air.periodic_columns();
+
+    let frame_at = |step: usize| {
+        Frame::read_from_trace(&trace, step, 1, &context.transition_offsets)
+            .with_extension_columns(&extension_trace, step, 1, &context.transition_offsets)
+            .with_periodic_values(periodic::values_at_row(&periodic_columns, step))
+            .with_virtual_columns(|row| air.evaluate_virtual_columns(row))
+    };
+    let is_violated = |step: &usize| {
+        air.compute_transition(&frame_at(*step), rap_challenges)
+            .iter()
+            .zip(&active_rows)
+            .any(|(residue, active)| active[*step] && residue != &FieldElement::zero())
+    };
 
-    // Iterate over trace and compute transitions
-    for step in 0..trace.n_rows() {
-        let frame = Frame::read_from_trace(&trace, step, 1, &air.context().transition_offsets);
+    // Each row is checked independently of the others, so with the `parallel`
+    // feature the rows are spread over the available threads. Only the rows
+    // that violate some constraint are evaluated again to report them.
+    #[cfg(not(feature = "parallel"))]
+    let failing_rows: Vec<usize> = (0..trace.n_rows()).filter(is_violated).collect();
+    #[cfg(feature = "parallel")]
+    let failing_rows: Vec<usize> = (0..trace.n_rows())
+        .into_par_iter()
+        .filter(is_violated)
+        .collect();
 
+    for step in failing_rows {
+        let frame = frame_at(step);
         let evaluations = air.compute_transition(&frame, rap_challenges);
-        // Iterate over each transition evaluation. When the evaluated step is not from
-        // the exemption steps corresponding to the transition, it should have zero as a
-        // result
-        evaluations.iter().enumerate().for_each(|(i, eval)| {
-            if step < exemption_steps[i] && eval != &FieldElement::<F>::zero() {
-                ret = false;
-                error!(
-                    "Inconsistent evaluation of transition {} in step {} - expected 0, got {:?}",
-                    i, step, eval
-                );
+        // When the evaluated step is one the transition applies to, it should
+        // have zero as a result. A wrong number of evaluations is reported by
+        // the prover as `ProvingError::TransitionCount`.
+        for (i, (residue, active)) in evaluations.into_iter().zip(&active_rows).enumerate() {
+            if active[step] && residue != FieldElement::zero() {
+                let violation = TransitionViolation {
+                    constraint: i,
+                    label: context.constraint_label(i).map(String::from),
+                    row: step,
+                    frame: (0..frame.num_rows())
+                        .map(|row| frame.get_row(row).to_vec())
+                        .collect(),
+                    periodic_values: frame.periodic_values().to_vec(),
+                    residue,
+                };
+                error!("Inconsistent evaluation of {violation}");
+                report.transition.push(violation);
             }
-        })
+        }
     }
     info!("Constraints validation check ended");
-    ret
+    report
 }
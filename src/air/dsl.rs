@@ -0,0 +1,627 @@
+//! Declarative AIRs.
+//!
+//! An AIR whose transition constraints are polynomials of the trace columns
+//! can be described as expressions instead of implementing `AIR` by hand. The
+//! degrees, offsets, exemptions and number of constraints of the context are
+//! derived from the expressions, so they can't get out of sync with them:
+//!
+//! ```
+//! use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+//! use lambdaworks_stark::air::{context::ProofOptions, dsl::AirBuilder};
+//!
+//! let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+//!     columns: [a, b];
+//!     transitions: [a.next() - b, b.next() - a - b];
+//!     boundaries: [a[0] = 1, b[0] = 1];
+//! };
+//! let options = ProofOptions {
+//!     blowup_factor: 2,
+//!     fri_number_of_queries: 1,
+//!     coset_offset: 3,
+//!     validate_trace: false,
+//! };
+//! let air = builder.build(options, 16);
+//! ```
+//!
+//! Each transition is an expression that has to be zero, and `a.next()` is the
+//! value of `a` in the next row. The prover evaluates the same expressions on
+//! the LDE and the verifier at the out of domain point, so both sides can't
+//! disagree on the constraints.
+//!
+//! Periodic columns are declared after the columns and read on the current
+//! row:
+//!
+//! ```text
+//! columns: [sum];
+//! periodic: [k = vec![FE::from(1), FE::from(2)]];
+//! transitions: [sum.next() - sum - k];
+//! ```
+//!
+//! A transition doesn't apply to the last rows, whose next rows would be
+//! past the end of the trace. Cyclic transitions, declared after the others,
+//! apply to every row instead, and the last rows read the first ones:
+//!
+//! ```text
+//! columns: [a, b];
+//! transitions: [b.next() - a];
+//! cyclic: [a.next() - b];
+//! ```
+
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+use core::ops::{Add, Mul, Neg, Sub};
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use super::{
+    constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+    context::{AirContext, ProofOptions},
+    frame::Frame,
+    trace::TraceTable,
+    traits::AIR,
+};
+use crate::{prover::ProvingError, IsChallengeField};
+
+/// Polynomial expression of the trace columns.
+#[derive(Clone, Debug)]
+pub enum Expr<F: IsFFTField> {
+    /// Value of the column `index`, `offset` rows after the current one.
+    Column {
+        index: usize,
+        offset: usize,
+    },
+    /// Value of the periodic column `index` in the current row.
+    Periodic(usize),
+    Constant(FieldElement<F>),
+    Add(Box<Expr<F>>, Box<Expr<F>>),
+    Sub(Box<Expr<F>>, Box<Expr<F>>),
+    Mul(Box<Expr<F>>, Box<Expr<F>>),
+    Neg(Box<Expr<F>>),
+}
+
+impl<F: IsFFTField> Expr<F> {
+    /// Value of the column `index` in the current row.
+    pub fn column(index: usize) -> Self {
+        Self::Column { index, offset: 0 }
+    }
+
+    pub fn constant(value: FieldElement<F>) -> Self {
+        Self::Constant(value)
+    }
+
+    /// Value of the periodic column `index` in the current row.
+    pub fn periodic(index: usize) -> Self {
+        Self::Periodic(index)
+    }
+
+    /// The same expression, on the next row.
+    pub fn next(&self) -> Self {
+        self.shift(1)
+    }
+
+    /// The same expression, `rows` rows later. Periodic columns are only read
+    /// on the current row, so they aren't shifted.
+    pub fn shift(&self, rows: usize) -> Self {
+        match self {
+            Self::Column { index, offset } => Self::Column {
+                index: *index,
+                offset: offset + rows,
+            },
+            Self::Periodic(index) => Self::Periodic(*index),
+            Self::Constant(value) => Self::Constant(value.clone()),
+            Self::Add(lhs, rhs) => lhs.shift(rows) + rhs.shift(rows),
+            Self::Sub(lhs, rhs) => lhs.shift(rows) - rhs.shift(rows),
+            Self::Mul(lhs, rhs) => lhs.shift(rows) * rhs.shift(rows),
+            Self::Neg(expr) => -expr.shift(rows),
+        }
+    }
+
+    /// Index of the column if the expression is a column in the current row.
+    pub fn column_index(&self) -> Option<usize> {
+        match self {
+            Self::Column { index, offset: 0 } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Degree of the expression as a polynomial of the trace columns. The
+    /// polynomial of a periodic column has a degree below the trace length, so
+    /// it counts as a column.
+    pub fn degree(&self) -> usize {
+        match self {
+            Self::Column { .. } | Self::Periodic(_) => 1,
+            Self::Constant(_) => 0,
+            Self::Add(lhs, rhs) | Self::Sub(lhs, rhs) => lhs.degree().max(rhs.degree()),
+            Self::Mul(lhs, rhs) => lhs.degree() + rhs.degree(),
+            Self::Neg(expr) => expr.degree(),
+        }
+    }
+
+    /// Largest row offset the expression reads.
+    pub fn max_offset(&self) -> usize {
+        match self {
+            Self::Column { offset, .. } => *offset,
+            Self::Periodic(_) | Self::Constant(_) => 0,
+            Self::Add(lhs, rhs) | Self::Sub(lhs, rhs) | Self::Mul(lhs, rhs) => {
+                lhs.max_offset().max(rhs.max_offset())
+            }
+            Self::Neg(expr) => expr.max_offset(),
+        }
+    }
+
+    /// Column and row offset of every value the expression reads.
+    pub fn reads(&self) -> Vec<(usize, usize)> {
+        match self {
+            Self::Column { index, offset } => vec![(*index, *offset)],
+            Self::Periodic(_) | Self::Constant(_) => Vec::new(),
+            Self::Add(lhs, rhs) | Self::Sub(lhs, rhs) | Self::Mul(lhs, rhs) => {
+                let mut reads = lhs.reads();
+                reads.extend(rhs.reads());
+                reads
+            }
+            Self::Neg(expr) => expr.reads(),
+        }
+    }
+
+    /// Row offsets the expression reads, in increasing order.
+    pub fn offsets(&self) -> Vec<usize> {
+        let mut offsets: Vec<usize> = self.reads().into_iter().map(|(_, offset)| offset).collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    /// Evaluates the expression on a frame whose rows are at `offsets` from
+    /// the current one, which have to include every offset it reads.
+    pub fn evaluate<E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        offsets: &[usize],
+    ) -> FieldElement<E> {
+        match self {
+            Self::Column { index, offset } => {
+                let row = offsets
+                    .iter()
+                    .position(|other| other == offset)
+                    .expect("the frame has a row at every offset the expression reads");
+                frame.get_row(row)[*index].clone()
+            }
+            Self::Periodic(index) => frame.periodic_values()[*index].clone(),
+            Self::Constant(value) => E::embed(value),
+            Self::Add(lhs, rhs) => lhs.evaluate(frame, offsets) + rhs.evaluate(frame, offsets),
+            Self::Sub(lhs, rhs) => lhs.evaluate(frame, offsets) - rhs.evaluate(frame, offsets),
+            Self::Mul(lhs, rhs) => lhs.evaluate(frame, offsets) * rhs.evaluate(frame, offsets),
+            Self::Neg(expr) => -expr.evaluate(frame, offsets),
+        }
+    }
+}
+
+impl<F: IsFFTField> From<FieldElement<F>> for Expr<F> {
+    fn from(value: FieldElement<F>) -> Self {
+        Self::Constant(value)
+    }
+}
+
+macro_rules! impl_binary_operation {
+    ($trait:ident, $method:ident, $variant:ident) => {
+        impl<F: IsFFTField> $trait<Expr<F>> for Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: Expr<F>) -> Expr<F> {
+                Expr::$variant(Box::new(self), Box::new(rhs))
+            }
+        }
+
+        impl<F: IsFFTField> $trait<&Expr<F>> for Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: &Expr<F>) -> Expr<F> {
+                self.$method(rhs.clone())
+            }
+        }
+
+        impl<F: IsFFTField> $trait<Expr<F>> for &Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: Expr<F>) -> Expr<F> {
+                self.clone().$method(rhs)
+            }
+        }
+
+        impl<F: IsFFTField> $trait<&Expr<F>> for &Expr<F> {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: &Expr<F>) -> Expr<F> {
+                self.clone().$method(rhs.clone())
+            }
+        }
+    };
+}
+
+impl_binary_operation!(Add, add, Add);
+impl_binary_operation!(Sub, sub, Sub);
+impl_binary_operation!(Mul, mul, Mul);
+
+impl<F: IsFFTField> Neg for Expr<F> {
+    type Output = Expr<F>;
+
+    fn neg(self) -> Expr<F> {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+impl<F: IsFFTField> Neg for &Expr<F> {
+    type Output = Expr<F>;
+
+    fn neg(self) -> Expr<F> {
+        -self.clone()
+    }
+}
+
+/// Description of an AIR by its columns and constraints.
+#[derive(Clone, Debug)]
+pub struct AirBuilder<F: IsFFTField> {
+    columns: Vec<&'static str>,
+    periodic: Vec<Vec<FieldElement<F>>>,
+    transitions: Vec<Expr<F>>,
+    /// Number of last rows each transition doesn't apply to.
+    exemptions: Vec<usize>,
+    boundaries: Vec<BoundaryConstraint<F>>,
+}
+
+impl<F: IsFFTField> AirBuilder<F> {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            periodic: Vec::new(),
+            transitions: Vec::new(),
+            exemptions: Vec::new(),
+            boundaries: Vec::new(),
+        }
+    }
+
+    /// The columns, in order, as expressions on the current row.
+    pub fn columns(&self) -> Vec<Expr<F>> {
+        (0..self.columns.len()).map(Expr::column).collect()
+    }
+
+    /// Adds a periodic column with the values of one period, read by
+    /// `Expr::periodic(self.num_periodic_columns() - 1)`.
+    pub fn periodic(mut self, values: Vec<FieldElement<F>>) -> Self {
+        self.periodic.push(values);
+        self
+    }
+
+    pub fn num_periodic_columns(&self) -> usize {
+        self.periodic.len()
+    }
+
+    /// Adds a transition constraint: `transition` is zero on every row for
+    /// which all the rows it reads are in the trace.
+    pub fn transition(mut self, transition: Expr<F>) -> Self {
+        self.exemptions.push(transition.max_offset());
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Adds a cyclic transition constraint: `transition` is zero on every row,
+    /// reading the rows past the end of the trace from its start.
+    pub fn cyclic_transition(mut self, transition: Expr<F>) -> Self {
+        self.exemptions.push(0);
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Adds the boundary constraint `column[row] = value`.
+    pub fn boundary(mut self, column: usize, row: usize, value: FieldElement<F>) -> Self {
+        self.boundaries
+            .push(BoundaryConstraint::new(column, row, value));
+        self
+    }
+
+    /// Builds the AIR for traces of length `trace_length`.
+    pub fn build(self, options: ProofOptions, trace_length: usize) -> DslAir<F> {
+        // The frame only has the rows the transitions read, and the current
+        // one, which the boundary constraints read.
+        let mut transition_offsets = vec![0];
+        transition_offsets.extend(self.transitions.iter().flat_map(Expr::offsets));
+        transition_offsets.sort_unstable();
+        transition_offsets.dedup();
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: self.columns.len(),
+            transition_degrees: self.transitions.iter().map(Expr::degree).collect(),
+            transition_exemptions: self.exemptions,
+            transition_offsets,
+            num_transition_constraints: self.transitions.len(),
+            column_names: self.columns.iter().map(|name| name.to_string()).collect(),
+            constraint_labels: Vec::new(),
+        };
+
+        DslAir {
+            context,
+            columns: self.columns,
+            periodic: self.periodic,
+            transitions: self.transitions,
+            boundaries: self.boundaries,
+        }
+    }
+}
+
+/// AIR built by an `AirBuilder`. Its raw trace is the list of its columns.
+#[derive(Clone, Debug)]
+pub struct DslAir<F: IsFFTField> {
+    context: AirContext,
+    columns: Vec<&'static str>,
+    periodic: Vec<Vec<FieldElement<F>>>,
+    transitions: Vec<Expr<F>>,
+    boundaries: Vec<BoundaryConstraint<F>>,
+}
+
+impl<F: IsFFTField> DslAir<F> {
+    /// Index of the column `name`.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| *column == name)
+    }
+}
+
+/// The DSL only describes constraints over the trace field, which is also
+/// the challenge field.
+impl<F> AIR for DslAir<F>
+where
+    F: IsFFTField + IsChallengeField<F>,
+{
+    type Field = F;
+    type ChallengeField = F;
+    type RawTrace = Vec<Vec<FieldElement<F>>>;
+    type RAPChallenges = ();
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.periodic.clone()
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        let max_degree = self.context.transition_degrees.iter().copied().max();
+        self.context.trace_length * max_degree.unwrap_or(1).max(1)
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.transitions
+            .iter()
+            .map(|transition| transition.evaluate(frame, &self.context.transition_offsets))
+            .collect()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(self.boundaries.clone())
+    }
+
+    /// Each column at the offsets the transitions read it.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        let mut column_offsets = vec![Vec::new(); self.columns.len()];
+        for (index, offset) in self.transitions.iter().flat_map(Expr::reads) {
+            column_offsets[index].push(offset);
+        }
+        for offsets in &mut column_offsets {
+            offsets.sort_unstable();
+            offsets.dedup();
+        }
+        column_offsets
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        Some(&mut self.context)
+    }
+}
+
+/// Describes an AIR as an `AirBuilder`. Columns and periodic columns are
+/// bound to expressions on the current row, transitions are expressions that
+/// have to be zero, cyclic ones also on the last rows, and boundaries set the
+/// value of a column at a row:
+///
+/// ```text
+/// air! {
+///     columns: [a, b];
+///     periodic: [k = vec![FE::from(1), FE::from(2)]];
+///     transitions: [a.next() - b, b.next() - a - b];
+///     cyclic: [a - a.next().next()];
+///     boundaries: [a[0] = 1, b[0] = 1];
+/// }
+/// ```
+#[macro_export]
+macro_rules! air {
+    (
+        columns: [$($column:ident),+ $(,)?];
+        $(periodic: [$($periodic:ident = $values:expr),* $(,)?];)?
+        transitions: [$($transition:expr),+ $(,)?];
+        $(cyclic: [$($cyclic:expr),+ $(,)?];)?
+        boundaries: [$($boundary_column:ident[$row:expr] = $value:expr),* $(,)?] $(;)?
+    ) => {{
+        let mut builder = $crate::air::dsl::AirBuilder::new(&[$(stringify!($column)),+]);
+        let mut columns = builder.columns().into_iter();
+        $(
+            #[allow(unused_variables)]
+            let $column = &columns.next().unwrap();
+        )+
+        $($(
+            builder = builder.periodic($values);
+            let $periodic = &$crate::air::dsl::Expr::periodic(builder.num_periodic_columns() - 1);
+        )*)?
+        $(builder = builder.transition($transition);)+
+        $($(builder = builder.cyclic_transition($cyclic);)+)?
+        $(
+            builder = builder.boundary(
+                $boundary_column.column_index().unwrap(),
+                $row,
+                $crate::fri::FieldElement::from($value),
+            );
+        )*
+        builder
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn options() -> ProofOptions {
+        ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        }
+    }
+
+    #[test]
+    fn context_is_derived_from_the_constraints() {
+        let builder: AirBuilder<F> = crate::air! {
+            columns: [a, b];
+            transitions: [a.next().next() - a.next() - a, b * b - a];
+            boundaries: [a[0] = 1, a[1] = 1];
+        };
+        let air = builder.build(options(), 8);
+        let context = air.context();
+
+        assert_eq!(context.trace_columns, 2);
+        assert_eq!(context.transition_degrees, vec![1, 2]);
+        assert_eq!(context.transition_offsets, vec![0, 1, 2]);
+        assert_eq!(context.transition_exemptions, vec![2, 0]);
+        assert_eq!(context.num_transition_constraints, 2);
+        assert_eq!(air.column_offsets(), vec![vec![0, 1, 2], vec![0]]);
+        assert_eq!(air.composition_poly_degree_bound(), 16);
+        assert_eq!(air.boundary_constraints(&(), &()).constraints.len(), 2);
+        assert_eq!(air.column("b"), Some(1));
+    }
+
+    #[test]
+    fn frame_only_has_the_rows_the_transitions_read() {
+        let builder: AirBuilder<F> = crate::air! {
+            columns: [a];
+            transitions: [a.shift(2) - a - a];
+            boundaries: [a[0] = 1];
+        };
+        let air = builder.build(options(), 8);
+        assert_eq!(air.context().transition_offsets, vec![0, 2]);
+        assert_eq!(air.context().transition_exemptions, vec![2]);
+
+        let trace =
+            TraceTable::new_from_cols(&[vec![1, 1, 2, 2, 4, 4, 8, 8].map(FE::from).to_vec()]);
+        for step in 0..6 {
+            let frame = Frame::read_from_trace(&trace, step, 1, &air.context().transition_offsets);
+            assert_eq!(frame.num_rows(), 2);
+            assert_eq!(air.compute_transition(&frame, &()), vec![FE::zero()]);
+        }
+    }
+
+    #[test]
+    fn cyclic_transitions_apply_to_the_last_rows() {
+        let builder: AirBuilder<F> = crate::air! {
+            columns: [a, b];
+            transitions: [b.next() - a];
+            cyclic: [a.next() - b];
+            boundaries: [a[0] = 1];
+        };
+        let air = builder.build(options(), 4);
+        assert_eq!(air.context().transition_exemptions, vec![1, 0]);
+
+        let trace = TraceTable::new_from_cols(&[
+            vec![1, 2, 1, 2].map(FE::from).to_vec(),
+            vec![2, 1, 2, 1].map(FE::from).to_vec(),
+        ]);
+        // The last frame reads the first row as the next one.
+        let frame = Frame::read_from_trace(&trace, 3, 1, &air.context().transition_offsets);
+        assert_eq!(frame.get_row(1), trace.get_row(0));
+        assert_eq!(air.compute_transition(&frame, &())[1], FE::zero());
+    }
+
+    #[test]
+    fn periodic_columns_are_read_from_the_frame() {
+        let builder: AirBuilder<F> = crate::air! {
+            columns: [sum];
+            periodic: [k = vec![FE::from(1), FE::from(2)], m = vec![FE::from(3)]];
+            transitions: [sum.next() - sum - k * m];
+            boundaries: [sum[0] = 0];
+        };
+        let air = builder.build(options(), 4);
+        assert_eq!(air.periodic_columns().len(), 2);
+        assert_eq!(air.context().transition_degrees, vec![2]);
+        assert_eq!(air.column_offsets(), vec![vec![0, 1]]);
+
+        let trace = TraceTable::new_from_cols(&[vec![0, 3, 9, 12].map(FE::from).to_vec()]);
+        for step in 0..3 {
+            let periodic = vec![FE::from(1 + step as u64 % 2), FE::from(3)];
+            let frame = Frame::read_from_trace(&trace, step, 1, &air.context().transition_offsets)
+                .with_periodic_values(periodic);
+            assert_eq!(air.compute_transition(&frame, &()), vec![FE::zero()]);
+        }
+    }
+
+    #[test]
+    fn transitions_vanish_on_a_valid_trace() {
+        let builder: AirBuilder<F> = crate::air! {
+            columns: [a, b];
+            transitions: [a.next() - b, b.next() - a - b];
+            boundaries: [a[0] = 1, b[0] = 1];
+        };
+        let air = builder.build(options(), 8);
+
+        let mut a = vec![FE::one()];
+        let mut b = vec![FE::one()];
+        for step in 0..7 {
+            a.push(b[step].clone());
+            b.push(&a[step] + &b[step]);
+        }
+        let trace = TraceTable::new_from_cols(&[a, b]);
+        for step in 0..7 {
+            let frame = Frame::read_from_trace(&trace, step, 1, &air.context().transition_offsets);
+            assert!(air
+                .compute_transition(&frame, &())
+                .iter()
+                .all(|value| value == &FE::zero()));
+        }
+    }
+}
@@ -0,0 +1,559 @@
+use alloc::{boxed::Box, string::ToString, sync::Arc, vec::Vec};
+use core::any::Any;
+
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use crate::{prover::ProvingError, IsChallengeField};
+
+use super::{
+    constraints::{
+        boundary::BoundaryConstraints,
+        transition::{RowSubset, Zerofier},
+    },
+    context::AirContext,
+    frame::Frame,
+    lookup::Lookup,
+    segment::Segment,
+    trace::{PaddingStrategy, TraceTable},
+    traits::AIR,
+};
+
+/// Raw trace of an AIR wrapped in a `DynAir`.
+pub type DynRawTrace = Box<dyn Any + Send + Sync>;
+/// Public input of an AIR wrapped in a `DynAir`.
+pub type DynPublicInput = Arc<dyn Any + Send + Sync>;
+/// RAP challenges of an AIR wrapped in a `DynAir`.
+pub type DynRAPChallenges = Box<dyn Any + Send + Sync>;
+
+/// Object safe version of `AIR`, with the associated types replaced by `Any`.
+trait ErasedAir<F: IsFFTField, E: IsChallengeField<F>>: Send + Sync {
+    fn build_main_trace(
+        &self,
+        raw_trace: &DynRawTrace,
+        public_input: &DynPublicInput,
+    ) -> Result<(TraceTable<F>, DynPublicInput), ProvingError>;
+
+    fn with_public_input(
+        &self,
+        public_input: &DynPublicInput,
+    ) -> Result<Box<dyn ErasedAir<F, E>>, ProvingError>;
+
+    fn public_input_bytes(&self, public_input: &DynPublicInput) -> Vec<u8>;
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<F>;
+
+    fn build_rap_challenges(&self, transcript: &mut dyn Transcript) -> DynRAPChallenges;
+
+    fn number_auxiliary_rap_columns(&self) -> usize;
+
+    fn auxiliary_rounds(&self) -> Vec<usize>;
+
+    fn extend_rap_challenges(
+        &self,
+        round: usize,
+        rap_challenges: &mut DynRAPChallenges,
+        transcript: &mut dyn Transcript,
+    );
+
+    fn build_auxiliary_round(
+        &self,
+        round: usize,
+        trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<F>;
+
+    fn number_extension_columns(&self) -> usize;
+
+    fn build_extension_columns(
+        &self,
+        trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<E>;
+
+    fn padding_strategy(&self) -> Option<PaddingStrategy>;
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<F>>>;
+
+    fn lookups(&self) -> Vec<Lookup>;
+
+    fn virtual_columns(&self) -> Vec<&'static str>;
+
+    fn evaluate_virtual_columns(&self, row: &[FieldElement<E>]) -> Vec<FieldElement<E>>;
+
+    fn composition_poly_degree_bound(&self) -> usize;
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<E>,
+        rap_challenges: &DynRAPChallenges,
+    ) -> Vec<FieldElement<E>>;
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> BoundaryConstraints<E>;
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset>;
+
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<F>>>;
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<F>>>;
+    fn segments(&self) -> Vec<Segment>;
+    fn column_offsets(&self) -> Vec<Vec<usize>>;
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier>;
+
+    fn initial_state(&self, public_input: &DynPublicInput) -> Vec<FieldElement<F>>;
+
+    fn final_state(&self, public_input: &DynPublicInput) -> Vec<FieldElement<F>>;
+
+    fn context(&self) -> &AirContext;
+
+    fn context_mut(&mut self) -> Option<&mut AirContext>;
+
+    fn clone_box(&self) -> Box<dyn ErasedAir<F, E>>;
+}
+
+/// Lets a `&mut dyn Transcript` be passed where a sized transcript is expected.
+struct DynTranscript<'a>(&'a mut dyn Transcript);
+
+impl Transcript for DynTranscript<'_> {
+    fn append(&mut self, new_data: &[u8]) {
+        self.0.append(new_data)
+    }
+
+    fn challenge(&mut self) -> [u8; 32] {
+        self.0.challenge()
+    }
+}
+
+fn downcast<'a, T: 'static>(value: &'a (dyn Any + Send + Sync), what: &str) -> &'a T {
+    value
+        .downcast_ref::<T>()
+        .unwrap_or_else(|| panic!("{what} doesn't match the type of the wrapped AIR"))
+}
+
+fn downcast_mut<'a, T: 'static>(value: &'a mut (dyn Any + Send + Sync), what: &str) -> &'a mut T {
+    value
+        .downcast_mut::<T>()
+        .unwrap_or_else(|| panic!("{what} doesn't match the type of the wrapped AIR"))
+}
+
+struct Erased<A: AIR>(A);
+
+impl<F, E, A> ErasedAir<F, E> for Erased<A>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E> + Send + Sync + 'static,
+    A::RawTrace: 'static,
+    A::PublicInput: Send + Sync + 'static,
+    A::RAPChallenges: Send + Sync + 'static,
+{
+    fn build_main_trace(
+        &self,
+        raw_trace: &DynRawTrace,
+        public_input: &DynPublicInput,
+    ) -> Result<(TraceTable<F>, DynPublicInput), ProvingError> {
+        let raw_trace = raw_trace.downcast_ref::<A::RawTrace>().ok_or_else(|| {
+            ProvingError::WrongParameter("raw trace doesn't match the wrapped AIR".to_string())
+        })?;
+        let public_input = public_input
+            .downcast_ref::<A::PublicInput>()
+            .ok_or_else(|| {
+                ProvingError::WrongParameter(
+                    "public input doesn't match the wrapped AIR".to_string(),
+                )
+            })?;
+
+        let (main_trace, public_input) = self.0.build_main_trace(raw_trace, public_input)?;
+        Ok((main_trace, Arc::new(public_input)))
+    }
+
+    fn with_public_input(
+        &self,
+        public_input: &DynPublicInput,
+    ) -> Result<Box<dyn ErasedAir<F, E>>, ProvingError> {
+        let public_input = public_input
+            .downcast_ref::<A::PublicInput>()
+            .ok_or_else(|| {
+                ProvingError::WrongParameter(
+                    "public input doesn't match the wrapped AIR".to_string(),
+                )
+            })?;
+        Ok(Box::new(Erased(self.0.with_public_input(public_input)?)))
+    }
+
+    fn public_input_bytes(&self, public_input: &DynPublicInput) -> Vec<u8> {
+        self.0
+            .public_input_bytes(downcast(public_input.as_ref(), "public input"))
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<F> {
+        self.0.build_auxiliary_trace(
+            main_trace,
+            downcast(rap_challenges.as_ref(), "RAP challenges"),
+            downcast(public_input.as_ref(), "public input"),
+        )
+    }
+
+    fn build_rap_challenges(&self, transcript: &mut dyn Transcript) -> DynRAPChallenges {
+        Box::new(self.0.build_rap_challenges(&mut DynTranscript(transcript)))
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.0.number_auxiliary_rap_columns()
+    }
+
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        self.0.auxiliary_rounds()
+    }
+
+    fn extend_rap_challenges(
+        &self,
+        round: usize,
+        rap_challenges: &mut DynRAPChallenges,
+        transcript: &mut dyn Transcript,
+    ) {
+        self.0.extend_rap_challenges(
+            round,
+            downcast_mut(rap_challenges.as_mut(), "RAP challenges"),
+            &mut DynTranscript(transcript),
+        )
+    }
+
+    fn build_auxiliary_round(
+        &self,
+        round: usize,
+        trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<F> {
+        self.0.build_auxiliary_round(
+            round,
+            trace,
+            downcast(rap_challenges.as_ref(), "RAP challenges"),
+            downcast(public_input.as_ref(), "public input"),
+        )
+    }
+
+    fn number_extension_columns(&self) -> usize {
+        self.0.number_extension_columns()
+    }
+
+    fn build_extension_columns(
+        &self,
+        trace: &TraceTable<F>,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> TraceTable<E> {
+        self.0.build_extension_columns(
+            trace,
+            downcast(rap_challenges.as_ref(), "RAP challenges"),
+            downcast(public_input.as_ref(), "public input"),
+        )
+    }
+
+    fn padding_strategy(&self) -> Option<PaddingStrategy> {
+        self.0.padding_strategy()
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<F>>> {
+        self.0.periodic_columns()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.0.lookups()
+    }
+
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        self.0.virtual_columns()
+    }
+
+    fn evaluate_virtual_columns(&self, row: &[FieldElement<E>]) -> Vec<FieldElement<E>> {
+        self.0.evaluate_virtual_columns(row)
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.0.composition_poly_degree_bound()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<E>,
+        rap_challenges: &DynRAPChallenges,
+    ) -> Vec<FieldElement<E>> {
+        self.0
+            .compute_transition(frame, downcast(rap_challenges.as_ref(), "RAP challenges"))
+    }
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &DynRAPChallenges,
+        public_input: &DynPublicInput,
+    ) -> BoundaryConstraints<E> {
+        self.0.boundary_constraints(
+            downcast(rap_challenges.as_ref(), "RAP challenges"),
+            downcast(public_input.as_ref(), "public input"),
+        )
+    }
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        self.0.transition_row_subsets()
+    }
+
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<F>>> {
+        self.0.preprocessed_columns()
+    }
+
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<F>>> {
+        self.0.preprocessed_roots()
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        self.0.segments()
+    }
+
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        self.0.column_offsets()
+    }
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        self.0.transition_zerofiers()
+    }
+
+    fn initial_state(&self, public_input: &DynPublicInput) -> Vec<FieldElement<F>> {
+        self.0
+            .initial_state(downcast(public_input.as_ref(), "public input"))
+    }
+
+    fn final_state(&self, public_input: &DynPublicInput) -> Vec<FieldElement<F>> {
+        self.0
+            .final_state(downcast(public_input.as_ref(), "public input"))
+    }
+
+    fn context(&self) -> &AirContext {
+        self.0.context()
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        self.0.context_mut()
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedAir<F, E>> {
+        Box::new(Erased(self.0.clone()))
+    }
+}
+
+/// AIR over the field `F`, with challenges in `E`, whose raw trace, public
+/// input and RAP challenges types are erased, so that AIRs chosen at runtime can be stored and proved
+/// through a single type.
+///
+/// The raw trace and public input handed to the prover and verifier have to
+/// be boxed values of the wrapped AIR's types; the public values returned by
+/// the prover can be downcast back to them.
+pub struct DynAir<F: IsFFTField, E: IsChallengeField<F> = F> {
+    inner: Box<dyn ErasedAir<F, E>>,
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> DynAir<F, E> {
+    pub fn new<A>(air: A) -> Self
+    where
+        A: AIR<Field = F, ChallengeField = E> + Send + Sync + 'static,
+        A::RawTrace: 'static,
+        A::PublicInput: Send + Sync + 'static,
+        A::RAPChallenges: Send + Sync + 'static,
+    {
+        Self {
+            inner: Box::new(Erased(air)),
+        }
+    }
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> Clone for DynAir<F, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone_box(),
+        }
+    }
+}
+
+impl<F: IsFFTField, E: IsChallengeField<F>> AIR for DynAir<F, E> {
+    type Field = F;
+    type ChallengeField = E;
+    type RawTrace = DynRawTrace;
+    type RAPChallenges = DynRAPChallenges;
+    type PublicInput = DynPublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        self.inner.build_main_trace(raw_trace, public_input)
+    }
+
+    fn with_public_input(&self, public_input: &Self::PublicInput) -> Result<Self, ProvingError> {
+        Ok(Self {
+            inner: self.inner.with_public_input(public_input)?,
+        })
+    }
+
+    fn public_input_bytes(&self, public_input: &Self::PublicInput) -> Vec<u8> {
+        self.inner.public_input_bytes(public_input)
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        self.inner
+            .build_auxiliary_trace(main_trace, rap_challenges, public_input)
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        self.inner.build_rap_challenges(transcript)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.inner.number_auxiliary_rap_columns()
+    }
+
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        self.inner.auxiliary_rounds()
+    }
+
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        round: usize,
+        rap_challenges: &mut Self::RAPChallenges,
+        transcript: &mut T,
+    ) {
+        self.inner
+            .extend_rap_challenges(round, rap_challenges, transcript)
+    }
+
+    fn build_auxiliary_round(
+        &self,
+        round: usize,
+        trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        self.inner
+            .build_auxiliary_round(round, trace, rap_challenges, public_input)
+    }
+
+    fn number_extension_columns(&self) -> usize {
+        self.inner.number_extension_columns()
+    }
+
+    fn build_extension_columns(
+        &self,
+        trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::ChallengeField> {
+        self.inner
+            .build_extension_columns(trace, rap_challenges, public_input)
+    }
+
+    fn padding_strategy(&self) -> Option<PaddingStrategy> {
+        self.inner.padding_strategy()
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.inner.periodic_columns()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.inner.lookups()
+    }
+
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        self.inner.virtual_columns()
+    }
+
+    fn evaluate_virtual_columns(
+        &self,
+        row: &[FieldElement<Self::ChallengeField>],
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.inner.evaluate_virtual_columns(row)
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.inner.composition_poly_degree_bound()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.inner.compute_transition(frame, rap_challenges)
+    }
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        self.inner
+            .boundary_constraints(rap_challenges, public_input)
+    }
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        self.inner.transition_row_subsets()
+    }
+
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.inner.preprocessed_columns()
+    }
+
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<Self::Field>>> {
+        self.inner.preprocessed_roots()
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        self.inner.segments()
+    }
+
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        self.inner.column_offsets()
+    }
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        self.inner.transition_zerofiers()
+    }
+
+    fn initial_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        self.inner.initial_state(public_input)
+    }
+
+    fn final_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        self.inner.final_state(public_input)
+    }
+
+    fn context(&self) -> &AirContext {
+        self.inner.context()
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        self.inner.context_mut()
+    }
+}
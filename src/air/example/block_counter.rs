@@ -0,0 +1,221 @@
+use crate::{
+    air::{
+        self,
+        constraints::{
+            boundary::{BoundaryConstraint, BoundaryConstraints},
+            transition::{RowSubset, Zerofier},
+        },
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// Counter that restarts at zero every `block_size` rows, constrained without
+/// selector columns: the first row of each block is zero, and each of the
+/// other rows is the previous one plus one.
+#[derive(Clone)]
+pub struct BlockCounterAIR {
+    context: AirContext,
+    block_size: usize,
+}
+
+impl BlockCounterAIR {
+    /// The context has to declare `block_size` transition constraints of
+    /// degree 1.
+    pub fn new(context: AirContext, block_size: usize) -> Self {
+        Self {
+            context,
+            block_size,
+        }
+    }
+}
+
+impl AIR for BlockCounterAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    /// The first constraint applies to the first row of each block, and the
+    /// constraint `i` from the second one on to its row `i - 1`.
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        let mut subsets = vec![RowSubset::new(self.block_size, 0)];
+        subsets.extend((0..self.block_size - 1).map(|row| RowSubset::new(self.block_size, row)));
+        subsets
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = &frame.get_row(0)[0];
+        let next = &frame.get_row(1)[0];
+        let increment = next - current - FieldElement::one();
+
+        let mut constraints = vec![current.clone()];
+        constraints.extend(core::iter::repeat(increment).take(self.block_size - 1));
+        constraints
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let c0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::zero());
+
+        BoundaryConstraints::from_constraints(vec![c0])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
+
+/// The counter of `BlockCounterAIR` with two constraints, whose zerofiers
+/// vanish on the first row of each block and on every row but the last one of
+/// each block.
+#[derive(Clone)]
+pub struct CompactBlockCounterAIR {
+    context: AirContext,
+    block_size: usize,
+}
+
+impl CompactBlockCounterAIR {
+    pub fn new(options: ProofOptions, trace_length: usize, block_size: usize) -> Self {
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: 1,
+            transition_degrees: vec![1, 1],
+            transition_exemptions: vec![0, 0],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 2,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        Self {
+            context,
+            block_size,
+        }
+    }
+}
+
+impl AIR for CompactBlockCounterAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        let last_rows = RowSubset::new(self.block_size, self.block_size - 1);
+        vec![
+            Zerofier::new(RowSubset::new(self.block_size, 0)),
+            Zerofier::new(RowSubset::ALL).excluding(last_rows),
+        ]
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = &frame.get_row(0)[0];
+        let next = &frame.get_row(1)[0];
+        vec![current.clone(), next - current - FieldElement::one()]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let c0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::zero());
+
+        BoundaryConstraints::from_constraints(vec![c0])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
+
+pub fn block_counter_trace(
+    block_size: usize,
+    trace_length: usize,
+) -> Vec<Vec<FieldElement<Stark252PrimeField>>> {
+    vec![(0..trace_length)
+        .map(|row| FieldElement::from((row % block_size) as u64))
+        .collect()]
+}
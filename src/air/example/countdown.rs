@@ -0,0 +1,156 @@
+use crate::{
+    air::{
+        self,
+        constraints::{
+            boundary::{BoundaryConstraint, BoundaryConstraints},
+            registry::TransitionConstraints,
+        },
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        gadgets::is_zero::IsZero,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type F = Stark252PrimeField;
+type FE = FieldElement<F>;
+
+/// A counter that goes down from the public input to zero and then stays at
+/// zero, `x[i + 1] = (x[i] - 1)(1 - z[i])`, where `z` is the result of an
+/// `IsZero` gadget on `x`. Its constraints are registered in a
+/// `TransitionConstraints`, the gadget registering its own.
+#[derive(Clone)]
+pub struct CountdownAIR {
+    context: AirContext,
+    is_zero: IsZero,
+    constraints: TransitionConstraints<F, (), FE>,
+    start: FE,
+}
+
+impl CountdownAIR {
+    pub fn new(options: ProofOptions, trace_length: usize) -> Self {
+        let is_zero = IsZero::new(0, 1);
+        let mut constraints = TransitionConstraints::new();
+        let result_column = is_zero.result_column();
+        constraints.register_fn(2, 1, move |frame: &Frame<F>, _, _, _| {
+            let current = frame.get_row(0);
+            let next = frame.get_row(1);
+            &next[0] - (&current[0] - FE::one()) * (FE::one() - &current[result_column])
+        });
+        is_zero.register_constraints(&mut constraints);
+
+        let mut context = AirContext {
+            options,
+            trace_length,
+            trace_columns: 1 + is_zero.num_main_columns(),
+            transition_degrees: Vec::new(),
+            transition_exemptions: Vec::new(),
+            transition_offsets: Vec::new(),
+            num_transition_constraints: 0,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        constraints.update_context(&mut context);
+        Self {
+            context,
+            is_zero,
+            constraints,
+            start: FE::zero(),
+        }
+    }
+}
+
+impl AIR for CountdownAIR {
+    type Field = F;
+    type ChallengeField = F;
+    type RawTrace = ();
+    type RAPChallenges = ();
+    type PublicInput = FE;
+
+    fn build_main_trace(
+        &self,
+        _raw_trace: &Self::RawTrace,
+        start: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let mut counter = vec![start.clone()];
+        for step in 1..self.context.trace_length {
+            let previous = &counter[step - 1];
+            let value = if previous == &FE::zero() {
+                FE::zero()
+            } else {
+                previous - FE::one()
+            };
+            counter.push(value);
+        }
+        let mut columns = vec![counter.clone()];
+        columns.extend(self.is_zero.build_main_columns(&counter));
+        Ok((TraceTable::new_from_cols(&columns), start.clone()))
+    }
+
+    fn with_public_input(&self, start: &Self::PublicInput) -> Result<Self, ProvingError> {
+        Ok(Self {
+            start: start.clone(),
+            ..self.clone()
+        })
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn transition_row_subsets(&self) -> Vec<air::constraints::transition::RowSubset> {
+        self.constraints.row_subsets()
+    }
+
+    fn transition_zerofiers(&self) -> Vec<air::constraints::transition::Zerofier> {
+        self.constraints.zerofiers(self.context().trace_length)
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.constraints
+            .evaluate(frame, rap_challenges, &self.start)
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        start: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(vec![
+            BoundaryConstraint::new_simple(0, start.clone()),
+            BoundaryConstraint::new_last_row(0, self.context.trace_length, FE::zero()),
+        ])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
@@ -27,6 +27,7 @@ impl From<AirContext> for DummyAIR {
 
 impl AIR for DummyAIR {
     type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
     type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
     type RAPChallenges = ();
     type PublicInput = ();
@@ -34,9 +35,9 @@ impl AIR for DummyAIR {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        _public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
-        Ok(TraceTable::new_from_cols(raw_trace))
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
     }
 
     fn build_auxiliary_trace(
@@ -51,9 +52,9 @@ impl AIR for DummyAIR {
     fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
     fn compute_transition(
         &self,
-        frame: &air::frame::Frame<Self::Field>,
+        frame: &air::frame::Frame<Self::ChallengeField>,
         _rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
         let first_row = frame.get_row(0);
         let second_row = frame.get_row(1);
         let third_row = frame.get_row(2);
@@ -69,7 +70,7 @@ impl AIR for DummyAIR {
         &self,
         _rap_challenges: &Self::RAPChallenges,
         _public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         let a0 = BoundaryConstraint::new(1, 0, FieldElement::<Self::Field>::one());
         let a1 = BoundaryConstraint::new(1, 1, FieldElement::<Self::Field>::one());
 
@@ -84,6 +85,10 @@ impl AIR for DummyAIR {
         &self.context
     }
 
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+
     fn composition_poly_degree_bound(&self) -> usize {
         self.context().trace_length
     }
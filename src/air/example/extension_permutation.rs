@@ -0,0 +1,157 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+    IsChallengeField,
+};
+use core::marker::PhantomData;
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::{
+    fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, traits::IsFFTField,
+};
+
+/// Two main columns `a` and `b`, where `b` is a permutation of `a`, checked
+/// with a running product `z` that is a column over the challenge field:
+/// z₀ = 1 and zᵢ₊₁(bᵢ + γ) = zᵢ(aᵢ + γ). The last row of `a` and `b` is a
+/// zero that isn't part of the permutation, so the product is 1 on it.
+///
+/// The trace is over `F` and `z` and γ are over `E`.
+#[derive(Clone)]
+pub struct ExtensionPermutationAIR<F = Stark252PrimeField, E = F> {
+    context: AirContext,
+    phantom: PhantomData<(F, E)>,
+}
+
+impl ExtensionPermutationAIR {
+    pub fn new(options: ProofOptions, trace_length: usize) -> Self {
+        Self::with_fields(options, trace_length)
+    }
+}
+
+impl<F, E> ExtensionPermutationAIR<F, E> {
+    pub fn with_fields(options: ProofOptions, trace_length: usize) -> Self {
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: 3,
+            transition_degrees: vec![2],
+            transition_exemptions: vec![1],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 1,
+            column_names: vec!["a".to_string(), "b".to_string(), "z".to_string()],
+            constraint_labels: Vec::new(),
+        };
+        Self {
+            context,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, E> AIR for ExtensionPermutationAIR<F, E>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+{
+    type Field = F;
+    type ChallengeField = E;
+    type RawTrace = Vec<Vec<FieldElement<F>>>;
+    type RAPChallenges = FieldElement<E>;
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        E::sample(transcript)
+    }
+
+    fn number_extension_columns(&self) -> usize {
+        1
+    }
+
+    fn build_extension_columns(
+        &self,
+        trace: &TraceTable<Self::Field>,
+        gamma: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::ChallengeField> {
+        let mut products = vec![FieldElement::<E>::one()];
+        for row in 0..trace.n_rows() - 1 {
+            let a = E::embed(&trace.get(row, 0)) + gamma;
+            let b = E::embed(&trace.get(row, 1)) + gamma;
+            products.push(products.last().unwrap() * a / b);
+        }
+        TraceTable::new_from_cols(&[products])
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        gamma: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        vec![&next[2] * (&current[1] + gamma) - &current[2] * (&current[0] + gamma)]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(vec![
+            BoundaryConstraint::new(2, 0, FieldElement::one()),
+            BoundaryConstraint::new_last_row(2, self.context.trace_length, FieldElement::one()),
+        ])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
+
+/// Columns `a` and `b` for `values`, with `b` the reverse of `a` and a zero
+/// appended to both.
+pub fn extension_permutation_trace<F: IsFFTField>(
+    values: &[FieldElement<F>],
+) -> Vec<Vec<FieldElement<F>>> {
+    let mut a = values.to_vec();
+    let mut b: Vec<_> = values.iter().rev().cloned().collect();
+    a.push(FieldElement::zero());
+    b.push(FieldElement::zero());
+    vec![a, b]
+}
@@ -0,0 +1,69 @@
+use crate::{fri::FieldElement, IsChallengeField};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::{
+    field::{
+        extensions::quadratic::{HasQuadraticNonResidue, QuadraticExtensionField},
+        fields::u64_prime_field::F17,
+    },
+    traits::ByteConversion,
+};
+
+/// 3 isn't a square mod 17, so X² - 3 is irreducible over F17.
+#[derive(Clone, Debug)]
+pub struct F17Residue;
+
+impl HasQuadraticNonResidue for F17Residue {
+    type BaseField = F17;
+
+    fn residue() -> FieldElement<F17> {
+        FieldElement::from(3)
+    }
+}
+
+/// F17[X] / (X² - 3), a challenge field for traces over F17.
+pub type F17Squared = QuadraticExtensionField<F17Residue>;
+
+impl IsChallengeField<F17> for F17Squared {
+    fn embed(element: &FieldElement<F17>) -> FieldElement<Self> {
+        FieldElement::new([element.clone(), FieldElement::zero()])
+    }
+
+    fn to_bytes_be(element: &FieldElement<Self>) -> Vec<u8> {
+        let [a, b] = element.value();
+        [a.to_bytes_be(), b.to_bytes_be()].concat()
+    }
+
+    fn from_bytes_be(bytes: &[u8]) -> Option<FieldElement<Self>> {
+        if bytes.len() % 2 != 0 {
+            return None;
+        }
+        let (a, b) = bytes.split_at(bytes.len() / 2);
+        Some(FieldElement::new([
+            FieldElement::from_bytes_be(a).ok()?,
+            FieldElement::from_bytes_be(b).ok()?,
+        ]))
+    }
+
+    fn sample<T: Transcript>(transcript: &mut T) -> FieldElement<Self> {
+        let a = <F17 as IsChallengeField<F17>>::sample(transcript);
+        let b = <F17 as IsChallengeField<F17>>::sample(transcript);
+        FieldElement::new([a, b])
+    }
+
+    fn hash_one(element: FieldElement<Self>) -> FieldElement<Self> {
+        let [a, b] = element.value().clone();
+        FieldElement::new([
+            <F17 as IsChallengeField<F17>>::hash_two(a.clone(), b.clone()),
+            <F17 as IsChallengeField<F17>>::hash_two(b, a),
+        ])
+    }
+
+    fn hash_two(left: FieldElement<Self>, right: FieldElement<Self>) -> FieldElement<Self> {
+        let [left_a, left_b] = left.value().clone();
+        let [right_a, right_b] = right.value().clone();
+        Self::hash_one(FieldElement::new([
+            <F17 as IsChallengeField<F17>>::hash_two(left_a, right_a),
+            <F17 as IsChallengeField<F17>>::hash_two(left_b, right_b),
+        ]))
+    }
+}
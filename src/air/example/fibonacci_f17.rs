@@ -25,6 +25,7 @@ impl From<AirContext> for Fibonacci17AIR {
 
 impl AIR for Fibonacci17AIR {
     type Field = F17;
+    type ChallengeField = Self::Field;
     type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
     type RAPChallenges = ();
     type PublicInput = ();
@@ -32,9 +33,9 @@ impl AIR for Fibonacci17AIR {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        _public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
-        Ok(TraceTable::new_from_cols(raw_trace))
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
     }
 
     fn build_auxiliary_trace(
@@ -50,9 +51,9 @@ impl AIR for Fibonacci17AIR {
 
     fn compute_transition(
         &self,
-        frame: &air::frame::Frame<Self::Field>,
+        frame: &air::frame::Frame<Self::ChallengeField>,
         _rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
         let first_row = frame.get_row(0);
         let second_row = frame.get_row(1);
         let third_row = frame.get_row(2);
@@ -64,7 +65,7 @@ impl AIR for Fibonacci17AIR {
         &self,
         _rap_challenges: &Self::RAPChallenges,
         _public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         let a0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::one());
         let a1 = BoundaryConstraint::new_simple(1, FieldElement::<Self::Field>::one());
         let result = BoundaryConstraint::new_simple(3, FieldElement::<Self::Field>::from(3));
@@ -80,6 +81,10 @@ impl AIR for Fibonacci17AIR {
         &self.context
     }
 
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+
     fn composition_poly_degree_bound(&self) -> usize {
         self.context().trace_length
     }
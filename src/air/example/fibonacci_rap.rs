@@ -30,6 +30,7 @@ impl FibonacciRAP {
 
 impl AIR for FibonacciRAP {
     type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
     type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
     type RAPChallenges = FieldElement<Self::Field>;
     type PublicInput = ();
@@ -37,9 +38,9 @@ impl AIR for FibonacciRAP {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        _public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
-        Ok(TraceTable::new_from_cols(raw_trace))
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
     }
 
     fn build_auxiliary_trace(
@@ -79,9 +80,9 @@ impl AIR for FibonacciRAP {
 
     fn compute_transition(
         &self,
-        frame: &Frame<Self::Field>,
+        frame: &Frame<Self::ChallengeField>,
         gamma: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
         // Main constraints
         let first_row = frame.get_row(0);
         let second_row = frame.get_row(1);
@@ -107,7 +108,7 @@ impl AIR for FibonacciRAP {
         &self,
         _rap_challenges: &Self::RAPChallenges,
         _public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         // Main boundary constraints
         let a0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::one());
         let a1 = BoundaryConstraint::new_simple(1, FieldElement::<Self::Field>::one());
@@ -122,6 +123,10 @@ impl AIR for FibonacciRAP {
         &self.context
     }
 
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        Some(&mut self.context)
+    }
+
     fn composition_poly_degree_bound(&self) -> usize {
         self.context().trace_length
     }
@@ -0,0 +1,167 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// First and last row of a segment of the two column Fibonacci sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FibonacciSegment {
+    pub first: [FE; 2],
+    /// Completed by `build_main_trace`.
+    pub last: [FE; 2],
+}
+
+/// The sequence of `Fibonacci2ColsAIR` from any row, so that a long sequence
+/// can be proven as a chain of segments. The last row of a segment is the
+/// first one of the next.
+#[derive(Clone, Debug)]
+pub struct FibonacciSegmentAIR {
+    context: AirContext,
+}
+
+impl FibonacciSegmentAIR {
+    pub fn new(options: ProofOptions, trace_length: usize) -> Self {
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: 2,
+            transition_degrees: vec![1, 1],
+            transition_exemptions: vec![1, 1],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 2,
+            column_names: vec!["a".to_string(), "b".to_string()],
+            constraint_labels: Vec::new(),
+        };
+        Self { context }
+    }
+}
+
+impl AIR for FibonacciSegmentAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = FibonacciSegment;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable::new_from_cols(raw_trace);
+        let last_row = trace.last_row();
+        let public_input = FibonacciSegment {
+            first: public_input.first.clone(),
+            last: [last_row[0].clone(), last_row[1].clone()],
+        };
+        Ok((trace, public_input))
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn compute_transition(
+        &self,
+        frame: &air::frame::Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+
+        vec![
+            &second_row[0] - &first_row[0] - &first_row[1],
+            &second_row[1] - &first_row[1] - &second_row[0],
+        ]
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let trace_length = self.context.trace_length;
+        let mut constraints = Vec::new();
+        for column in 0..2 {
+            constraints.push(BoundaryConstraint::new(
+                column,
+                0,
+                public_input.first[column].clone(),
+            ));
+            constraints.push(BoundaryConstraint::new_last_row(
+                column,
+                trace_length,
+                public_input.last[column].clone(),
+            ));
+        }
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn initial_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        public_input.first.to_vec()
+    }
+
+    fn final_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        public_input.last.to_vec()
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+}
+
+/// The sequence starting at `first`, split in `segments` segments of
+/// `trace_length` rows, each starting at the last row of the previous one.
+/// The last rows of the public inputs are left for the prover to complete.
+pub fn fibonacci_segments(
+    first: [FE; 2],
+    segments: usize,
+    trace_length: usize,
+) -> Vec<(Vec<Vec<FE>>, FibonacciSegment)> {
+    let mut first = first;
+    let mut chain = Vec::with_capacity(segments);
+    for _ in 0..segments {
+        let trace =
+            super::fibonacci_2_columns::fibonacci_trace_2_columns(first.clone(), trace_length);
+        let public_input = FibonacciSegment {
+            first: first.clone(),
+            last: [FE::zero(), FE::zero()],
+        };
+        first = [
+            trace[0][trace_length - 1].clone(),
+            trace[1][trace_length - 1].clone(),
+        ];
+        chain.push((trace, public_input));
+    }
+    chain
+}
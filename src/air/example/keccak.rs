@@ -0,0 +1,372 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// Lanes of 64 bits of the state, indexed by `x + 5 y`.
+pub const LANES: usize = 25;
+pub const ROUNDS: usize = 24;
+/// Rows of each permutation of a chain: the state before each round, then the
+/// output repeated up to a power of two.
+pub const ROWS_PER_PERMUTATION: usize = 32;
+
+const LANE_BITS: usize = 64;
+const STATE_BITS: usize = LANES * LANE_BITS;
+const PARITY_BITS: usize = 5 * LANE_BITS;
+/// Main trace columns: the bits of the state, the bits of the parities of its
+/// columns, and the bits of the state after θ.
+pub const TRACE_COLUMNS: usize = 2 * STATE_BITS + PARITY_BITS;
+
+/// Rotation of each lane in ρ, indexed by `[x][y]`.
+const ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// Constants of ι, added to the lane `(0, 0)`.
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+fn lane(x: usize, y: usize) -> usize {
+    x + 5 * y
+}
+
+/// Main trace column of the bit `z` of the lane `(x, y)` of the state.
+fn state_column(x: usize, y: usize, z: usize) -> usize {
+    lane(x, y) * LANE_BITS + z
+}
+
+/// Main trace column of the bit `z` of the parity of the column `x`.
+fn parity_column(x: usize, z: usize) -> usize {
+    STATE_BITS + x * LANE_BITS + z
+}
+
+/// Main trace column of the bit `z` of the lane `(x, y)` after θ.
+fn theta_column(x: usize, y: usize, z: usize) -> usize {
+    STATE_BITS + PARITY_BITS + lane(x, y) * LANE_BITS + z
+}
+
+/// The parities of the columns of the state and the state after θ.
+fn theta(state: &[u64; LANES]) -> ([u64; 5], [u64; LANES]) {
+    let parities: [u64; 5] =
+        core::array::from_fn(|x| (0..5).fold(0, |acc, y| acc ^ state[lane(x, y)]));
+    let after_theta = core::array::from_fn(|i| {
+        let x = i % 5;
+        state[i] ^ parities[(x + 4) % 5] ^ parities[(x + 1) % 5].rotate_left(1)
+    });
+    (parities, after_theta)
+}
+
+/// ρ, π, χ and ι of the round `round`, on the state after θ.
+fn finish_round(after_theta: &[u64; LANES], round: usize) -> [u64; LANES] {
+    let mut b = [0; LANES];
+    for (x, rotations) in ROTATIONS.iter().enumerate() {
+        for (y, rotation) in rotations.iter().enumerate() {
+            b[lane(y, (2 * x + 3 * y) % 5)] = after_theta[lane(x, y)].rotate_left(*rotation);
+        }
+    }
+    let mut state: [u64; LANES] = core::array::from_fn(|i| {
+        let (x, y) = (i % 5, i / 5);
+        b[i] ^ (!b[lane((x + 1) % 5, y)] & b[lane((x + 2) % 5, y)])
+    });
+    state[0] ^= ROUND_CONSTANTS[round];
+    state
+}
+
+/// The Keccak-f[1600] permutation of `state`.
+pub fn permutation(state: &[u64; LANES]) -> [u64; LANES] {
+    (0..ROUNDS).fold(*state, |state, round| finish_round(&theta(&state).1, round))
+}
+
+/// Trace of a chain of permutations starting at `input`, where each one is
+/// applied to the output of the previous one, with `ROWS_PER_PERMUTATION`
+/// rows each.
+pub fn keccak_chain_trace(input: &[u64; LANES], trace_length: usize) -> Vec<Vec<FE>> {
+    let mut columns = vec![Vec::new(); TRACE_COLUMNS];
+    let mut state = *input;
+    for row in 0..trace_length {
+        let (parities, after_theta) = theta(&state);
+        for z in 0..LANE_BITS {
+            for (x, parity) in parities.iter().enumerate() {
+                columns[parity_column(x, z)].push(FE::from((parity >> z) & 1));
+                for y in 0..5 {
+                    columns[state_column(x, y, z)].push(FE::from((state[lane(x, y)] >> z) & 1));
+                    columns[theta_column(x, y, z)]
+                        .push(FE::from((after_theta[lane(x, y)] >> z) & 1));
+                }
+            }
+        }
+        let round = row % ROWS_PER_PERMUTATION;
+        if round < ROUNDS {
+            state = finish_round(&after_theta, round);
+        }
+    }
+    columns
+}
+
+/// The input of a chain of permutations and the output of the last one. The
+/// prover completes the output from the trace.
+#[derive(Clone, Debug)]
+pub struct KeccakPublicInput {
+    pub input: [u64; LANES],
+    pub output: [u64; LANES],
+}
+
+/// A chain of Keccak-f[1600] permutations, with a row per round and every bit
+/// in its own column. Besides the state, each row has the parities `C` of the
+/// columns of the state and the state `A'` after θ, so that no constraint has
+/// a degree above 4:
+///
+/// ```text
+/// C is boolean, and Σ_y A[x][y] - C[x] is 0, 2 or 4
+/// A' = A ⊕ C[x - 1] ⊕ rot(C[x + 1], 1)
+/// A_next = χ(π(ρ(A'))) ⊕ RC
+/// ```
+///
+/// where `a ⊕ b = a + b - 2ab` on bits. The bits of the round constants, zero
+/// on the rows after the last round, and whether the row is a round are
+/// periodic columns. The state is kept as is on the rows that aren't rounds.
+#[derive(Clone)]
+pub struct KeccakAIR {
+    context: AirContext,
+    /// Column of `A'` with each bit of the state after ρ and π.
+    permuted_columns: Vec<usize>,
+}
+
+impl KeccakAIR {
+    /// AIR of a chain of `trace_length / ROWS_PER_PERMUTATION` permutations.
+    pub fn new(options: ProofOptions, trace_length: usize) -> Result<Self, ProvingError> {
+        if !trace_length.is_power_of_two() || trace_length < ROWS_PER_PERMUTATION {
+            return Err(ProvingError::WrongParameter(format!(
+                "a chain of Keccak permutations needs a power of two of at least {ROWS_PER_PERMUTATION} rows, not {trace_length}"
+            )));
+        }
+        let mut transition_degrees = vec![3; PARITY_BITS];
+        transition_degrees.extend([2; PARITY_BITS]);
+        transition_degrees.extend([3; STATE_BITS]);
+        transition_degrees.extend([4; STATE_BITS]);
+        let mut transition_exemptions = vec![0; 2 * PARITY_BITS + STATE_BITS];
+        transition_exemptions.extend([1; STATE_BITS]);
+
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: TRACE_COLUMNS,
+            num_transition_constraints: transition_degrees.len(),
+            transition_degrees,
+            transition_exemptions,
+            transition_offsets: vec![0, 1],
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+
+        let mut permuted_columns = vec![0; STATE_BITS];
+        for (x, rotations) in ROTATIONS.iter().enumerate() {
+            for (y, rotation) in rotations.iter().enumerate() {
+                let rotation = *rotation as usize;
+                for z in 0..LANE_BITS {
+                    permuted_columns
+                        [state_column(y, (2 * x + 3 * y) % 5, (z + rotation) % LANE_BITS)] =
+                        theta_column(x, y, z);
+                }
+            }
+        }
+        Ok(Self {
+            context,
+            permuted_columns,
+        })
+    }
+}
+
+fn xor(a: &FE, b: &FE) -> FE {
+    a + b - FE::from(2) * a * b
+}
+
+impl AIR for KeccakAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = KeccakPublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable::new_from_cols(raw_trace);
+        let last_row = trace.get_row(trace.n_rows() - 1);
+        let output = core::array::from_fn(|i| {
+            (0..LANE_BITS)
+                .filter(|z| last_row[i * LANE_BITS + z] == FE::one())
+                .fold(0, |acc, z| acc | 1 << z)
+        });
+        let public_input = KeccakPublicInput {
+            input: public_input.input,
+            output,
+        };
+        Ok((trace, public_input))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        3 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    /// The bits of the round constants, and whether the row is a round.
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        let mut columns = vec![vec![FE::zero(); ROWS_PER_PERMUTATION]; LANE_BITS + 1];
+        for (round, constant) in ROUND_CONSTANTS.iter().enumerate() {
+            for (z, column) in columns.iter_mut().take(LANE_BITS).enumerate() {
+                column[round] = FE::from((constant >> z) & 1);
+            }
+            columns[LANE_BITS][round] = FE::one();
+        }
+        columns
+    }
+
+    /// Only the state is read in the next row.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        let mut offsets = vec![vec![0, 1]; STATE_BITS];
+        offsets.resize(TRACE_COLUMNS, vec![0]);
+        offsets
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let periodic_values = frame.periodic_values();
+        let round = &periodic_values[LANE_BITS];
+        let mut evaluations = Vec::with_capacity(self.context.num_transition_constraints);
+
+        let (two, four) = (FE::from(2), FE::from(4));
+        for x in 0..5 {
+            for z in 0..LANE_BITS {
+                let parity = &current[parity_column(x, z)];
+                let sum = (0..5).fold(FE::zero(), |acc, y| acc + &current[state_column(x, y, z)]);
+                let even = sum - parity;
+                evaluations.push(&even * (&even - &two) * (&even - &four));
+            }
+        }
+        for x in 0..5 {
+            for z in 0..LANE_BITS {
+                let parity = &current[parity_column(x, z)];
+                evaluations.push(parity * (parity - FE::one()));
+            }
+        }
+        for y in 0..5 {
+            for x in 0..5 {
+                for z in 0..LANE_BITS {
+                    let d = xor(
+                        &current[parity_column((x + 4) % 5, z)],
+                        &current[parity_column((x + 1) % 5, (z + LANE_BITS - 1) % LANE_BITS)],
+                    );
+                    let after_theta = xor(&current[state_column(x, y, z)], &d);
+                    evaluations.push(&current[theta_column(x, y, z)] - after_theta);
+                }
+            }
+        }
+        let b =
+            |x: usize, y: usize, z: usize| &current[self.permuted_columns[state_column(x, y, z)]];
+        let constants = &periodic_values[..LANE_BITS];
+        for y in 0..5 {
+            for x in 0..5 {
+                for (z, constant) in constants.iter().enumerate() {
+                    let and = (FE::one() - b((x + 1) % 5, y, z)) * b((x + 2) % 5, y, z);
+                    let chi = xor(b(x, y, z), &and);
+                    let column = state_column(x, y, z);
+                    let mut evaluation = round * (&next[column] - &chi)
+                        + (FE::one() - round) * (&next[column] - &current[column]);
+                    if (x, y) == (0, 0) {
+                        evaluation = evaluation - constant * (FE::one() - &two * &chi);
+                    }
+                    evaluations.push(evaluation);
+                }
+            }
+        }
+        evaluations
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let last_row = self.context().trace_length - 1;
+        let mut constraints = Vec::with_capacity(2 * STATE_BITS);
+        for (row, lanes) in [(0, &public_input.input), (last_row, &public_input.output)] {
+            for (i, lane) in lanes.iter().enumerate() {
+                for z in 0..LANE_BITS {
+                    let bit = FE::from((lane >> z) & 1);
+                    constraints.push(BoundaryConstraint::new(i * LANE_BITS + z, row, bit));
+                }
+            }
+        }
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
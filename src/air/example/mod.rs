@@ -1,6 +1,23 @@
+pub mod block_counter;
+pub mod countdown;
 pub mod dummy_air;
+pub mod extension_permutation;
+pub mod f17_squared;
 pub mod fibonacci_2_columns;
 pub mod fibonacci_f17;
 pub mod fibonacci_rap;
+pub mod fibonacci_segment;
+pub mod keccak;
+pub mod multi_round_permutation;
+pub mod parallel_fibonacci;
+pub mod periodic_lookup;
+pub mod periodic_sum;
+pub mod poseidon;
+pub mod preprocessed_sum;
 pub mod quadratic_air;
+pub mod range_check;
+pub mod rescue;
+pub mod segment_sum;
 pub mod simple_fibonacci;
+pub mod vm;
+pub mod zero_count;
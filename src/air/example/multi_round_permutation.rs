@@ -0,0 +1,151 @@
+use crate::{
+    air::{
+        constraints::boundary::BoundaryConstraints, context::AirContext, frame::Frame,
+        permutation::PermutationArgument, trace::TraceTable, traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+    transcript_to_field,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// Permutation of pairs in two auxiliary rounds. The main trace has the pairs
+/// `(x, y)` and `(x', y')`. The first round draws α and commits the
+/// compressed columns `c = x + αy` and `c' = x' + αy'`, and the second one
+/// draws z and commits the cumulative product of `(z - c) / (z - c')`.
+#[derive(Clone)]
+pub struct MultiRoundPermutationAIR {
+    context: AirContext,
+}
+
+impl MultiRoundPermutationAIR {
+    pub fn new(context: AirContext) -> Self {
+        Self { context }
+    }
+
+    fn permutation(&self) -> PermutationArgument {
+        PermutationArgument {
+            left: vec![4],
+            right: vec![5],
+            product: 6,
+        }
+    }
+}
+
+impl AIR for MultiRoundPermutationAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    /// α, and z once the second round draws it.
+    type RAPChallenges = Vec<FieldElement<Self::Field>>;
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        let alpha = &challenges[0];
+        let columns = main_trace.cols();
+        let compress = |x: &[FieldElement<Self::Field>], y: &[FieldElement<Self::Field>]| {
+            x.iter()
+                .zip(y)
+                .map(|(x, y)| x + alpha * y)
+                .collect::<Vec<_>>()
+        };
+
+        TraceTable::new_from_cols(&[
+            compress(&columns[0], &columns[1]),
+            compress(&columns[2], &columns[3]),
+        ])
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        vec![transcript_to_field(transcript)]
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        3
+    }
+
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        vec![2, 1]
+    }
+
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        _round: usize,
+        challenges: &mut Self::RAPChallenges,
+        transcript: &mut T,
+    ) {
+        challenges.push(transcript_to_field(transcript));
+    }
+
+    fn build_auxiliary_round(
+        &self,
+        _round: usize,
+        trace: &TraceTable<Self::Field>,
+        challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        let product =
+            self.permutation()
+                .build_product_column(trace, &challenges[1], &challenges[0]);
+        TraceTable::new_from_cols(&[product])
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let alpha = &challenges[0];
+        let row = frame.get_row(0);
+
+        vec![
+            &row[4] - &row[0] - alpha * &row[1],
+            &row[5] - &row[2] - alpha * &row[3],
+            self.permutation()
+                .evaluate_transition(frame, &challenges[1], alpha),
+        ]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(vec![self.permutation().boundary_constraint()])
+    }
+
+    fn context(&self) -> &AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        Some(&mut self.context)
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+}
+
+/// Main trace with the pairs `(i, i²)` and the same pairs in reverse order.
+pub fn reversed_pairs_trace(trace_length: usize) -> Vec<Vec<FieldElement<Stark252PrimeField>>> {
+    let x: Vec<_> = (0..trace_length as u64).map(FieldElement::from).collect();
+    let y: Vec<_> = x.iter().map(|x| x * x).collect();
+    let reversed = |column: &Vec<_>| column.iter().rev().cloned().collect();
+
+    vec![reversed(&x), reversed(&y), x, y]
+}
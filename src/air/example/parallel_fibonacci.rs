@@ -0,0 +1,131 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// Any number of Fibonacci sequences starting with 1, 1, one per column. The
+/// number of sequences is the public input, and the columns and constraints of
+/// the AIR are derived from it in `with_public_input`.
+#[derive(Clone)]
+pub struct ParallelFibonacciAIR {
+    context: AirContext,
+}
+
+impl ParallelFibonacciAIR {
+    /// AIR with a single sequence, until it gets its public input.
+    pub fn new(options: ProofOptions, trace_length: usize) -> Self {
+        Self {
+            context: Self::context_for(options, trace_length, 1),
+        }
+    }
+
+    fn context_for(options: ProofOptions, trace_length: usize, sequences: usize) -> AirContext {
+        AirContext {
+            options,
+            trace_length,
+            trace_columns: sequences,
+            transition_degrees: vec![1; sequences],
+            transition_exemptions: vec![2; sequences],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: sequences,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        }
+    }
+}
+
+impl AIR for ParallelFibonacciAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = usize;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), raw_trace.len()))
+    }
+
+    fn with_public_input(&self, sequences: &Self::PublicInput) -> Result<Self, ProvingError> {
+        if *sequences == 0 {
+            return Err(ProvingError::WrongParameter(
+                "there are no sequences".to_string(),
+            ));
+        }
+        let context = &self.context;
+        Ok(Self {
+            context: Self::context_for(context.options.clone(), context.trace_length, *sequences),
+        })
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+        let third_row = frame.get_row(2);
+
+        (0..self.context.trace_columns)
+            .map(|column| &third_row[column] - &second_row[column] - &first_row[column])
+            .collect()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let constraints = (0..self.context.trace_columns)
+            .flat_map(|column| {
+                [
+                    BoundaryConstraint::new(column, 0, FieldElement::one()),
+                    BoundaryConstraint::new(column, 1, FieldElement::one()),
+                ]
+            })
+            .collect();
+
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
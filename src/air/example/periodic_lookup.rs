@@ -0,0 +1,119 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::BoundaryConstraints,
+        context::AirContext,
+        frame::Frame,
+        lookup::{self, Lookup, TableColumn},
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+    transcript_to_field,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// Range check of a column: every value is looked up in the periodic column
+/// 0, 1, ..., `table_size` - 1. The main trace has the values and their
+/// multiplicities, and the auxiliary trace the running sum of the lookup.
+#[derive(Clone)]
+pub struct PeriodicLookupAIR {
+    context: AirContext,
+    table_size: usize,
+}
+
+impl PeriodicLookupAIR {
+    pub fn new(context: AirContext, table_size: usize) -> Self {
+        Self {
+            context,
+            table_size,
+        }
+    }
+}
+
+impl AIR for PeriodicLookupAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<FieldElement<Self::Field>>;
+    type RAPChallenges = FieldElement<Self::Field>;
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let table = &self.periodic_columns()[0];
+        let mut multiplicities = lookup::multiplicities(raw_trace, table).ok_or_else(|| {
+            ProvingError::WrongParameter("a value is out of the range of the table".to_string())
+        })?;
+        multiplicities.resize(raw_trace.len(), FieldElement::zero());
+
+        Ok((
+            TraceTable::new_from_cols(&[raw_trace.clone(), multiplicities]),
+            (),
+        ))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        alpha: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        lookup::build_sum_columns(&self.lookups(), main_trace, &self.periodic_columns(), alpha)
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        transcript_to_field(transcript)
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        vec![(0..self.table_size as u64)
+            .map(FieldElement::from)
+            .collect()]
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        vec![Lookup {
+            values: 0,
+            table: TableColumn::Periodic(0),
+            multiplicities: 1,
+            sum: 2,
+        }]
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        alpha: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        lookup::evaluate_transitions(&self.lookups(), frame, alpha)
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(lookup::boundary_constraints(&self.lookups()))
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        1
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::AirContext,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// Running sum of a periodic column: t₀ = 0 and tᵢ₊₁ = tᵢ + pᵢ, where p
+/// repeats `period` over the whole trace.
+#[derive(Clone)]
+pub struct PeriodicSumAIR {
+    context: AirContext,
+    period: Vec<FieldElement<Stark252PrimeField>>,
+}
+
+impl PeriodicSumAIR {
+    pub fn new(context: AirContext, period: Vec<FieldElement<Stark252PrimeField>>) -> Self {
+        Self { context, period }
+    }
+}
+
+impl AIR for PeriodicSumAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        vec![self.period.clone()]
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &air::frame::Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let first_row = frame.get_row(0);
+        let second_row = frame.get_row(1);
+        let periodic_value = &frame.periodic_values()[0];
+
+        vec![&second_row[0] - &first_row[0] - periodic_value]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let t0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::zero());
+
+        BoundaryConstraints::from_constraints(vec![t0])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
+
+pub fn periodic_sum_trace(
+    period: &[FieldElement<Stark252PrimeField>],
+    trace_length: usize,
+) -> Vec<Vec<FieldElement<Stark252PrimeField>>> {
+    let mut ret = vec![FieldElement::zero()];
+    for i in 1..trace_length {
+        ret.push(&ret[i - 1] + &period[(i - 1) % period.len()]);
+    }
+
+    vec![ret]
+}
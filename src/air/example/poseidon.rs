@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// Width of the Poseidon state, as in the Starknet instance over Stark252.
+pub const STATE_WIDTH: usize = 3;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 83;
+pub const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+/// Rows of each permutation of a chain: the state before each round, then the
+/// output repeated up to a power of two.
+pub const ROWS_PER_PERMUTATION: usize = 128;
+
+const FIELD_BITS: usize = 252;
+/// The modulus 2²⁵¹ + 17·2¹⁹² + 1 of Stark252, in big-endian limbs.
+const MODULUS_LIMBS: [u64; 4] = [0x0800_0000_0000_0011, 0, 0, 1];
+
+/// The Grain LFSR of the Poseidon paper, which generates round constants from
+/// the parameters of a permutation over Stark252.
+pub(crate) struct Grain {
+    bits: VecDeque<bool>,
+}
+
+impl Grain {
+    /// LFSR of a permutation with the S-box `sbox` (0 for xᵅ, 1 for x⁻¹).
+    pub(crate) fn new(
+        sbox: u64,
+        state_width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Self {
+        let mut grain = Self {
+            bits: VecDeque::with_capacity(80),
+        };
+        grain.push(1, 2);
+        grain.push(sbox, 4);
+        grain.push(FIELD_BITS as u64, 12);
+        grain.push(state_width as u64, 12);
+        grain.push(full_rounds as u64, 10);
+        grain.push(partial_rounds as u64, 10);
+        grain.push((1 << 30) - 1, 30);
+        for _ in 0..160 {
+            grain.update();
+        }
+        grain
+    }
+
+    fn push(&mut self, value: u64, bits: usize) {
+        for bit in (0..bits).rev() {
+            self.bits.push_back((value >> bit) & 1 == 1);
+        }
+    }
+
+    fn update(&mut self) -> bool {
+        let bit = [62, 51, 38, 23, 13, 0]
+            .iter()
+            .fold(false, |acc, position| acc ^ self.bits[*position]);
+        self.bits.pop_front();
+        self.bits.push_back(bit);
+        bit
+    }
+
+    /// Next bit of the self-shrinking output: of each pair of bits, the second
+    /// one is kept if the first one is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.update();
+            let bit = self.update();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Next field element, drawing values of 252 bits until one is below the
+    /// modulus.
+    pub(crate) fn field_element(&mut self) -> FE {
+        loop {
+            let mut limbs = [0u64; 4];
+            for position in (0..FIELD_BITS).rev() {
+                if self.next_bit() {
+                    limbs[3 - position / 64] |= 1 << (position % 64);
+                }
+            }
+            if limbs < MODULUS_LIMBS {
+                let base = FE::from(u64::MAX) + FE::one();
+                return limbs
+                    .iter()
+                    .fold(FE::zero(), |acc, limb| acc * &base + FE::from(*limb));
+            }
+        }
+    }
+}
+
+/// Round constants of the permutation, `STATE_WIDTH` per round.
+pub fn round_constants() -> Vec<FE> {
+    let mut grain = Grain::new(0, STATE_WIDTH, FULL_ROUNDS, PARTIAL_ROUNDS);
+    (0..ROUNDS * STATE_WIDTH)
+        .map(|_| grain.field_element())
+        .collect()
+}
+
+/// Half of the full rounds come before the partial ones and half after them.
+fn is_full_round(round: usize) -> bool {
+    round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+}
+
+/// The MDS matrix ((3, 1, 1), (1, -1, 1), (1, 1, -2)) applied to a state.
+pub(crate) fn mix(state: &[FE]) -> Vec<FE> {
+    vec![
+        FE::from(3) * &state[0] + &state[1] + &state[2],
+        &state[0] - &state[1] + &state[2],
+        &state[0] + &state[1] - FE::from(2) * &state[2],
+    ]
+}
+
+/// Applies the round `round`: adds its constants, cubes the whole state in
+/// full rounds and only its last element in partial rounds, and mixes it.
+fn apply_round(state: &[FE], round: usize, round_constants: &[FE]) -> Vec<FE> {
+    let sboxed: Vec<_> = state
+        .iter()
+        .zip(&round_constants[round * STATE_WIDTH..])
+        .enumerate()
+        .map(|(i, (value, constant))| {
+            let value = value + constant;
+            if is_full_round(round) || i == STATE_WIDTH - 1 {
+                value.pow(3u64)
+            } else {
+                value
+            }
+        })
+        .collect();
+    mix(&sboxed)
+}
+
+/// The Poseidon permutation of `state`.
+pub fn permutation(state: &[FE], round_constants: &[FE]) -> Vec<FE> {
+    (0..ROUNDS).fold(state.to_vec(), |state, round| {
+        apply_round(&state, round, round_constants)
+    })
+}
+
+/// Trace of a chain of permutations starting at `input`, where each one is
+/// applied to the output of the previous one, with `ROWS_PER_PERMUTATION`
+/// rows each.
+pub fn poseidon_chain_trace(
+    input: &[FE; STATE_WIDTH],
+    trace_length: usize,
+    round_constants: &[FE],
+) -> Vec<Vec<FE>> {
+    let mut columns = vec![Vec::new(); STATE_WIDTH];
+    let mut state = input.to_vec();
+    for row in 0..trace_length {
+        for (column, value) in columns.iter_mut().zip(&state) {
+            column.push(value.clone());
+        }
+        let round = row % ROWS_PER_PERMUTATION;
+        if round < ROUNDS {
+            state = apply_round(&state, round, round_constants);
+        }
+    }
+    columns
+}
+
+/// The input of a chain of permutations and the output of the last one. The
+/// prover completes the output from the trace.
+#[derive(Clone, Debug)]
+pub struct PoseidonPublicInput {
+    pub input: [FE; STATE_WIDTH],
+    pub output: [FE; STATE_WIDTH],
+}
+
+/// A chain of Poseidon permutations over Stark252, with the parameters of the
+/// Starknet instance: a state of 3 elements, the S-box x³, 8 full rounds and
+/// 83 partial rounds. The round constants are generated with the Grain LFSR of
+/// the Poseidon paper.
+///
+/// Each row has the state before a round. The round constants and whether
+/// the round is a full one are periodic columns, with zero constants on the
+/// rows after the last round, where the state is kept as is. Each constraint
+/// is of degree 4: the S-box of degree 3, gated by a periodic column.
+#[derive(Clone)]
+pub struct PoseidonAIR {
+    context: AirContext,
+    round_constants: Vec<FE>,
+}
+
+impl PoseidonAIR {
+    /// AIR of a chain of `trace_length / ROWS_PER_PERMUTATION` permutations.
+    pub fn new(options: ProofOptions, trace_length: usize) -> Result<Self, ProvingError> {
+        if !trace_length.is_power_of_two() || trace_length < ROWS_PER_PERMUTATION {
+            return Err(ProvingError::WrongParameter(format!(
+                "a chain of Poseidon permutations needs a power of two of at least {ROWS_PER_PERMUTATION} rows, not {trace_length}"
+            )));
+        }
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: STATE_WIDTH,
+            transition_degrees: vec![4; STATE_WIDTH],
+            transition_exemptions: vec![1; STATE_WIDTH],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: STATE_WIDTH,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        Ok(Self {
+            context,
+            round_constants: round_constants(),
+        })
+    }
+
+    pub fn round_constants(&self) -> &[FE] {
+        &self.round_constants
+    }
+}
+
+impl AIR for PoseidonAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = PoseidonPublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable::new_from_cols(raw_trace);
+        let last_row = trace.get_row(trace.n_rows() - 1);
+        let public_input = PoseidonPublicInput {
+            input: public_input.input.clone(),
+            output: [0, 1, 2].map(|column| last_row[column].clone()),
+        };
+        Ok((trace, public_input))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        3 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    /// The round constants of each element of the state, whether the round is
+    /// a full one, and whether the row is a round at all.
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        let mut columns = vec![vec![FE::zero(); ROWS_PER_PERMUTATION]; STATE_WIDTH + 2];
+        for round in 0..ROUNDS {
+            for (i, column) in columns.iter_mut().take(STATE_WIDTH).enumerate() {
+                column[round] = self.round_constants[round * STATE_WIDTH + i].clone();
+            }
+            if is_full_round(round) {
+                columns[STATE_WIDTH][round] = FE::one();
+            }
+            columns[STATE_WIDTH + 1][round] = FE::one();
+        }
+        columns
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let periodic_values = frame.periodic_values();
+        let (full, round) = (
+            &periodic_values[STATE_WIDTH],
+            &periodic_values[STATE_WIDTH + 1],
+        );
+
+        // The constants are zero outside the rounds, so gating the sum by
+        // `round` only needs the state.
+        let sboxed: Vec<_> = (0..STATE_WIDTH)
+            .map(|i| {
+                let value = &current[i] + &periodic_values[i];
+                let cubed = if i == STATE_WIDTH - 1 { round } else { full };
+                round * &current[i] + &periodic_values[i] + cubed * (value.pow(3u64) - &value)
+            })
+            .collect();
+        mix(&sboxed)
+            .iter()
+            .zip(current)
+            .zip(next)
+            .map(|((mixed, current), next)| next - mixed - (FE::one() - round) * current)
+            .collect()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let last_row = self.context().trace_length - 1;
+        let constraints = (0..STATE_WIDTH)
+            .flat_map(|column| {
+                [
+                    BoundaryConstraint::new(column, 0, public_input.input[column].clone()),
+                    BoundaryConstraint::new(column, last_row, public_input.output[column].clone()),
+                ]
+            })
+            .collect();
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// Running sum of a preprocessed column of constants: t₀ = 0 and
+/// tᵢ₊₁ = tᵢ + cᵢ. The constants are the first column, and the public input
+/// is the last value of the sum.
+#[derive(Clone)]
+pub struct PreprocessedSumAIR {
+    context: AirContext,
+    constants: Vec<FE>,
+    roots: Option<Vec<FE>>,
+}
+
+impl PreprocessedSumAIR {
+    pub fn new(options: ProofOptions, constants: Vec<FE>) -> Self {
+        let context = AirContext {
+            options,
+            trace_length: constants.len(),
+            trace_columns: 2,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![1],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: 1,
+            column_names: vec!["constant".to_string(), "sum".to_string()],
+            constraint_labels: Vec::new(),
+        };
+        Self {
+            context,
+            constants,
+            roots: None,
+        }
+    }
+
+    /// The same AIR, checking proofs against the given roots of the constants
+    /// instead of committing to them.
+    pub fn with_roots(mut self, roots: Vec<FE>) -> Self {
+        self.roots = Some(roots);
+        self
+    }
+}
+
+impl AIR for PreprocessedSumAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = ();
+    type RAPChallenges = ();
+    type PublicInput = FE;
+
+    fn build_main_trace(
+        &self,
+        _raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let mut sums = vec![FE::zero()];
+        for constant in &self.constants[..self.constants.len() - 1] {
+            sums.push(sums.last().unwrap() + constant);
+        }
+        let sum = sums.last().unwrap().clone();
+        Ok((TraceTable::new_from_cols(&[sums]), sum))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        vec![self.constants.clone()]
+    }
+
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<Self::Field>>> {
+        self.roots.clone()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        vec![&next[1] - &current[1] - &current[0]]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(vec![
+            BoundaryConstraint::new(1, 0, FE::zero()),
+            BoundaryConstraint::new_last_row(1, self.context.trace_length, public_input.clone()),
+        ])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
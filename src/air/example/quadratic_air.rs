@@ -27,6 +27,7 @@ impl From<AirContext> for QuadraticAIR {
 
 impl AIR for QuadraticAIR {
     type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
     type RawTrace = Vec<FieldElement<Self::Field>>;
     type RAPChallenges = ();
     type PublicInput = ();
@@ -34,12 +35,13 @@ impl AIR for QuadraticAIR {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        _public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
-        Ok(TraceTable {
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable {
             table: raw_trace.clone(),
             n_cols: 1,
-        })
+        };
+        Ok((trace, ()))
     }
 
     fn build_auxiliary_trace(
@@ -55,9 +57,9 @@ impl AIR for QuadraticAIR {
 
     fn compute_transition(
         &self,
-        frame: &air::frame::Frame<Self::Field>,
+        frame: &air::frame::Frame<Self::ChallengeField>,
         _rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
         let first_row = frame.get_row(0);
         let second_row = frame.get_row(1);
 
@@ -72,7 +74,7 @@ impl AIR for QuadraticAIR {
         &self,
         _rap_challenges: &Self::RAPChallenges,
         _public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         let a0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::from(3));
 
         BoundaryConstraints::from_constraints(vec![a0])
@@ -82,6 +84,10 @@ impl AIR for QuadraticAIR {
         &self.context
     }
 
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+
     fn composition_poly_degree_bound(&self) -> usize {
         2 * self.context().trace_length
     }
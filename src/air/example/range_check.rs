@@ -0,0 +1,122 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::BoundaryConstraints,
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        gadgets::range_check::RangeCheck,
+        lookup::{self, Lookup},
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+    transcript_to_field,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+/// A column of values below 2ᵇ, checked with a `RangeCheck` gadget whose
+/// columns follow it in the main and auxiliary traces.
+#[derive(Clone)]
+pub struct RangeCheckAIR {
+    context: AirContext,
+    check: RangeCheck,
+}
+
+impl RangeCheckAIR {
+    pub fn new(
+        options: ProofOptions,
+        trace_length: usize,
+        bits: usize,
+    ) -> Result<Self, ProvingError> {
+        let mut check = RangeCheck::new(0, bits, trace_length, 1, 0, 0)?;
+        // The running sums follow the main columns.
+        let main_columns = 1 + check.num_main_columns();
+        check.first_sum_column = main_columns;
+        let degrees = check.transition_degrees();
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: main_columns + check.num_lookups(),
+            transition_exemptions: vec![0; degrees.len()],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: degrees.len(),
+            transition_degrees: degrees,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        Ok(Self { context, check })
+    }
+}
+
+impl AIR for RangeCheckAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<FieldElement<Self::Field>>;
+    type RAPChallenges = FieldElement<Self::Field>;
+    type PublicInput = ();
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let mut columns = vec![raw_trace.clone()];
+        columns.extend(self.check.build_main_columns(raw_trace)?);
+        Ok((TraceTable::new_from_cols(&columns), ()))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        alpha: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        lookup::build_sum_columns(&self.lookups(), main_trace, &self.periodic_columns(), alpha)
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        transcript_to_field(transcript)
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        vec![self.check.table()]
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.check.lookups()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        alpha: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.check.evaluate_transitions(frame, alpha)
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        BoundaryConstraints::from_constraints(self.check.boundary_constraints())
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.check.num_lookups()
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+use super::poseidon::{mix, Grain};
+
+type FE = FieldElement<Stark252PrimeField>;
+
+pub const STATE_WIDTH: usize = 3;
+pub const ROUNDS: usize = 7;
+/// Rows of each permutation of a chain: the state before each round, then the
+/// output.
+pub const ROWS_PER_PERMUTATION: usize = 8;
+
+/// The inverse 3⁻¹ of the S-box exponent modulo p - 1, in big-endian limbs.
+const INVERSE_EXPONENT: [u64; 4] = [
+    0x0555_5555_5555_5560,
+    0xaaaa_aaaa_aaaa_aaaa,
+    0xaaaa_aaaa_aaaa_aaaa,
+    0xaaaa_aaaa_aaaa_aaab,
+];
+
+/// Round constants of the permutation, `2 * STATE_WIDTH` per round: those
+/// added after the S-box and those added after the inverse S-box.
+pub fn round_constants() -> Vec<FE> {
+    let mut grain = Grain::new(0, STATE_WIDTH, 2 * ROUNDS, 0);
+    (0..2 * ROUNDS * STATE_WIDTH)
+        .map(|_| grain.field_element())
+        .collect()
+}
+
+/// x^(1/3), by square and multiply over the limbs of the exponent.
+fn inverse_sbox(value: &FE) -> FE {
+    INVERSE_EXPONENT.iter().fold(FE::one(), |acc, limb| {
+        (0..64).rev().fold(acc, |acc, bit| {
+            let acc = &acc * &acc;
+            if (limb >> bit) & 1 == 1 {
+                acc * value
+            } else {
+                acc
+            }
+        })
+    })
+}
+
+/// 8 M⁻¹ applied to a state, for the MDS matrix M of `mix`, whose
+/// determinant is 8.
+fn scaled_inverse_mix(state: &[FE]) -> Vec<FE> {
+    vec![
+        &state[0] + FE::from(3) * &state[1] + FE::from(2) * &state[2],
+        FE::from(3) * &state[0] - FE::from(7) * &state[1] - FE::from(2) * &state[2],
+        FE::from(2) * &state[0] - FE::from(2) * &state[1] - FE::from(4) * &state[2],
+    ]
+}
+
+/// Applies the round `round`: the S-box, the MDS matrix and the first
+/// constants, then the inverse S-box, the MDS matrix and the second constants.
+fn apply_round(state: &[FE], round: usize, round_constants: &[FE]) -> Vec<FE> {
+    let constants = &round_constants[2 * round * STATE_WIDTH..];
+    let cubed: Vec<_> = state.iter().map(|value| value.pow(3u64)).collect();
+    let middle: Vec<_> = mix(&cubed)
+        .iter()
+        .zip(constants)
+        .map(|(value, constant)| inverse_sbox(&(value + constant)))
+        .collect();
+    mix(&middle)
+        .iter()
+        .zip(&constants[STATE_WIDTH..])
+        .map(|(value, constant)| value + constant)
+        .collect()
+}
+
+/// The Rescue-Prime permutation of `state`.
+pub fn permutation(state: &[FE], round_constants: &[FE]) -> Vec<FE> {
+    (0..ROUNDS).fold(state.to_vec(), |state, round| {
+        apply_round(&state, round, round_constants)
+    })
+}
+
+/// Trace of a chain of permutations starting at `input`, where each one is
+/// applied to the output of the previous one, with `ROWS_PER_PERMUTATION`
+/// rows each.
+pub fn rescue_chain_trace(
+    input: &[FE; STATE_WIDTH],
+    trace_length: usize,
+    round_constants: &[FE],
+) -> Vec<Vec<FE>> {
+    let mut columns = vec![Vec::new(); STATE_WIDTH];
+    let mut state = input.to_vec();
+    for row in 0..trace_length {
+        for (column, value) in columns.iter_mut().zip(&state) {
+            column.push(value.clone());
+        }
+        let round = row % ROWS_PER_PERMUTATION;
+        if round < ROUNDS {
+            state = apply_round(&state, round, round_constants);
+        }
+    }
+    columns
+}
+
+/// The input of a chain of permutations and the output of the last one. The
+/// prover completes the output from the trace.
+#[derive(Clone, Debug)]
+pub struct RescuePublicInput {
+    pub input: [FE; STATE_WIDTH],
+    pub output: [FE; STATE_WIDTH],
+}
+
+/// A chain of Rescue-Prime permutations over Stark252, with a state of 3
+/// elements, the S-box x³ and 7 rounds. The MDS matrix is the one of the
+/// Poseidon example, and the round constants come from the same Grain LFSR
+/// instead of the SHAKE256 of the specification.
+///
+/// Each row has the state before a round. The inverse S-box isn't computed in
+/// the constraints: with the state `s` of a row, `s'` of the next one and the
+/// round constants `c₁` and `c₂`, a round is
+///
+/// ```text
+/// 512 (M s³ + c₁) = (8 M⁻¹ (s' - c₂))³
+/// ```
+///
+/// which has degree 3 in both rows. The round constants and whether the row
+/// is a round are periodic columns, and gating the rounds raises the degree
+/// of the constraints to 4, so the composition polynomial has three parts.
+#[derive(Clone)]
+pub struct RescueAIR {
+    context: AirContext,
+    round_constants: Vec<FE>,
+}
+
+impl RescueAIR {
+    /// AIR of a chain of `trace_length / ROWS_PER_PERMUTATION` permutations.
+    pub fn new(options: ProofOptions, trace_length: usize) -> Result<Self, ProvingError> {
+        if !trace_length.is_power_of_two() || trace_length < ROWS_PER_PERMUTATION {
+            return Err(ProvingError::WrongParameter(format!(
+                "a chain of Rescue-Prime permutations needs a power of two of at least {ROWS_PER_PERMUTATION} rows, not {trace_length}"
+            )));
+        }
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: STATE_WIDTH,
+            transition_degrees: vec![4; STATE_WIDTH],
+            transition_exemptions: vec![1; STATE_WIDTH],
+            transition_offsets: vec![0, 1],
+            num_transition_constraints: STATE_WIDTH,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        Ok(Self {
+            context,
+            round_constants: round_constants(),
+        })
+    }
+
+    pub fn round_constants(&self) -> &[FE] {
+        &self.round_constants
+    }
+}
+
+impl AIR for RescueAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    type RAPChallenges = ();
+    type PublicInput = RescuePublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable::new_from_cols(raw_trace);
+        let last_row = trace.get_row(trace.n_rows() - 1);
+        let public_input = RescuePublicInput {
+            input: public_input.input.clone(),
+            output: [0, 1, 2].map(|column| last_row[column].clone()),
+        };
+        Ok((trace, public_input))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        3 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    /// The first and second round constants of each element of the state, and
+    /// whether the row is a round.
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        let mut columns = vec![vec![FE::zero(); ROWS_PER_PERMUTATION]; 2 * STATE_WIDTH + 1];
+        for round in 0..ROUNDS {
+            for (i, column) in columns.iter_mut().take(2 * STATE_WIDTH).enumerate() {
+                column[round] = self.round_constants[2 * round * STATE_WIDTH + i].clone();
+            }
+            columns[2 * STATE_WIDTH][round] = FE::one();
+        }
+        columns
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let periodic_values = frame.periodic_values();
+        let (first_constants, rest) = periodic_values.split_at(STATE_WIDTH);
+        let (second_constants, round) = (&rest[..STATE_WIDTH], &rest[STATE_WIDTH]);
+
+        let cubed: Vec<_> = current[..STATE_WIDTH]
+            .iter()
+            .map(|value| value.pow(3u64))
+            .collect();
+        let unmixed: Vec<_> = next[..STATE_WIDTH]
+            .iter()
+            .zip(second_constants)
+            .map(|(value, constant)| value - constant)
+            .collect();
+        let scale = FE::from(512);
+        mix(&cubed)
+            .iter()
+            .zip(first_constants)
+            .zip(scaled_inverse_mix(&unmixed))
+            .enumerate()
+            .map(|(i, ((mixed, constant), unmixed))| {
+                let rescue_round = &scale * (mixed + constant) - unmixed.pow(3u64);
+                round * rescue_round + (FE::one() - round) * (&next[i] - &current[i])
+            })
+            .collect()
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let last_row = self.context().trace_length - 1;
+        let constraints = (0..STATE_WIDTH)
+            .flat_map(|column| {
+                [
+                    BoundaryConstraint::new(column, 0, public_input.input[column].clone()),
+                    BoundaryConstraint::new(column, last_row, public_input.output[column].clone()),
+                ]
+            })
+            .collect();
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
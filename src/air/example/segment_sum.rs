@@ -0,0 +1,158 @@
+use crate::{
+    air::{
+        self,
+        constraints::{
+            boundary::{BoundaryConstraint, BoundaryConstraints},
+            transition::RowSubset,
+        },
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        segment::Segment,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// A counter over the whole trace, and a segment of period `period` with the
+/// running sum of the squares of the counter on its rows:
+///
+/// ```text
+/// c[i + 1] = c[i] + 1                      on every row
+/// s[j + 1] = s[j] + c[(j + 1)·period]²     on every segment row
+/// ```
+///
+/// The public input is the last value of the sum.
+#[derive(Clone)]
+pub struct SegmentSumAIR {
+    context: AirContext,
+    segment: Segment,
+}
+
+impl SegmentSumAIR {
+    pub fn new(
+        options: ProofOptions,
+        trace_length: usize,
+        period: usize,
+    ) -> Result<Self, ProvingError> {
+        if !period.is_power_of_two() || period < 2 || period >= trace_length {
+            return Err(ProvingError::WrongParameter(format!(
+                "the segment period {period} isn't a power of two between 2 and half of {trace_length}"
+            )));
+        }
+        let segment = Segment::new(1..2, period);
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: 2,
+            transition_degrees: vec![1, 2],
+            transition_exemptions: vec![1, 1],
+            transition_offsets: vec![0, 1, segment.offset(1)],
+            num_transition_constraints: 2,
+            column_names: vec!["counter".to_string(), "sum".to_string()],
+            constraint_labels: vec!["count".to_string(), "sum squares".to_string()],
+        };
+        Ok(Self { context, segment })
+    }
+}
+
+impl AIR for SegmentSumAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = ();
+    type RAPChallenges = ();
+    type PublicInput = FE;
+
+    fn build_main_trace(
+        &self,
+        _raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace_length = self.context.trace_length;
+        let counter: Vec<FE> = (0..trace_length as u64).map(FE::from).collect();
+        let mut sums = vec![FE::zero()];
+        for row in 1..self.segment.rows(trace_length) {
+            let value = &counter[self.segment.trace_row(row)];
+            sums.push(&sums[row - 1] + value * value);
+        }
+        let sum = sums.last().unwrap().clone();
+        let columns = [counter, self.segment.spread(&sums, trace_length)];
+        Ok((TraceTable::new_from_cols(&columns), sum))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn segments(&self) -> Vec<Segment> {
+        vec![self.segment.clone()]
+    }
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        vec![RowSubset::ALL, self.segment.row_subset()]
+    }
+
+    /// The counter reads the next row, and the sum the next segment row.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        let next_segment_row = self.segment.offset(1);
+        vec![vec![0, 1, next_segment_row], vec![0, next_segment_row]]
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let next_segment_row = frame.get_row(2);
+
+        let count = &next[0] - &current[0] - FE::one();
+        let counter = &next_segment_row[0];
+        let sum = &next_segment_row[1] - &current[1] - counter * counter;
+        vec![count, sum]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let last_segment_row = self
+            .segment
+            .trace_row(self.segment.rows(self.context.trace_length) - 1);
+        BoundaryConstraints::from_constraints(vec![
+            BoundaryConstraint::new_simple(0, FE::zero()),
+            BoundaryConstraint::new(1, 0, FE::zero()),
+            BoundaryConstraint::new(1, last_segment_row, public_input.clone()),
+        ])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
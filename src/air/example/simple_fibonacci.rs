@@ -27,6 +27,7 @@ impl From<AirContext> for FibonacciAIR {
 
 impl AIR for FibonacciAIR {
     type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
     type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
     type RAPChallenges = ();
     type PublicInput = ();
@@ -34,9 +35,9 @@ impl AIR for FibonacciAIR {
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        _public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError> {
-        Ok(TraceTable::new_from_cols(raw_trace))
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        Ok((TraceTable::new_from_cols(raw_trace), ()))
     }
 
     fn composition_poly_degree_bound(&self) -> usize {
@@ -55,9 +56,9 @@ impl AIR for FibonacciAIR {
     fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
     fn compute_transition(
         &self,
-        frame: &air::frame::Frame<Self::Field>,
+        frame: &air::frame::Frame<Self::ChallengeField>,
         _rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>> {
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
         let first_row = frame.get_row(0);
         let second_row = frame.get_row(1);
         let third_row = frame.get_row(2);
@@ -69,7 +70,7 @@ impl AIR for FibonacciAIR {
         &self,
         _rap_challenges: &Self::RAPChallenges,
         _public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field> {
+    ) -> BoundaryConstraints<Self::ChallengeField> {
         let a0 = BoundaryConstraint::new_simple(0, FieldElement::<Self::Field>::one());
         let a1 = BoundaryConstraint::new_simple(1, FieldElement::<Self::Field>::one());
 
@@ -83,6 +84,10 @@ impl AIR for FibonacciAIR {
     fn context(&self) -> &air::context::AirContext {
         &self.context
     }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
 }
 
 pub fn fibonacci_trace<F: IsField>(
@@ -0,0 +1,491 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::{AirContext, ProofOptions},
+        frame::Frame,
+        gadgets::{
+            is_zero::IsZero,
+            memory::{AccessColumns, Memory},
+            range_check::RangeCheck,
+            selector::{is_boolean, select, when, when_not},
+        },
+        lookup::{self, Lookup, TableColumn},
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+    transcript_to_field,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+type FE = FieldElement<Stark252PrimeField>;
+
+/// Instruction of the VM, which has two registers `r0` and `r1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// r0 ← r0 + r1
+    Add,
+    /// r0 ← r0 · r1
+    Mul,
+    /// r1 ← the immediate
+    Set(u16),
+    /// r0 ← memory[r1]
+    Load,
+    /// memory[r1] ← r0
+    Store,
+    /// Jumps to the immediate if r0 isn't zero.
+    Jnz(u16),
+    /// Stops the machine: it is executed again on every row that follows.
+    Halt,
+}
+
+/// Number of instructions with a flag, all but `Halt`.
+const FLAGS: usize = 6;
+const ADD: usize = 0;
+const MUL: usize = 1;
+const SET: usize = 2;
+const LOAD: usize = 3;
+const STORE: usize = 4;
+const JNZ: usize = 5;
+
+/// Shift of the immediate and of the program counter in a fetched word.
+const IMMEDIATE_SHIFT: u64 = 8;
+const PC_SHIFT: u64 = 32;
+/// Bits of the range checks of the immediates and of the memory gadget.
+const RANGE_CHECK_BITS: usize = 16;
+
+impl Instruction {
+    fn flag(&self) -> Option<usize> {
+        match self {
+            Self::Add => Some(ADD),
+            Self::Mul => Some(MUL),
+            Self::Set(_) => Some(SET),
+            Self::Load => Some(LOAD),
+            Self::Store => Some(STORE),
+            Self::Jnz(_) => Some(JNZ),
+            Self::Halt => None,
+        }
+    }
+
+    fn immediate(&self) -> u64 {
+        match self {
+            Self::Set(immediate) | Self::Jnz(immediate) => u64::from(*immediate),
+            _ => 0,
+        }
+    }
+
+    /// The instruction at `pc` as it is looked up in the program: its flag
+    /// bit, plus its immediate and `pc` shifted.
+    fn fetched_word(&self, pc: usize) -> FE {
+        let flag = self.flag().map_or(0, |flag| 1 << flag);
+        FE::from(flag + (self.immediate() << IMMEDIATE_SHIFT))
+            + FE::from(pc as u64) * FE::from(1u64 << PC_SHIFT)
+    }
+}
+
+// Columns of the main trace before those of the gadgets.
+const PC: usize = 0;
+const R0: usize = 1;
+const R1: usize = 2;
+const FIRST_FLAG: usize = 3;
+const IMMEDIATE: usize = FIRST_FLAG + FLAGS;
+const FETCHED: usize = IMMEDIATE + 1;
+const FETCH_MULTIPLICITY: usize = FETCHED + 1;
+const CLK: usize = FETCH_MULTIPLICITY + 1;
+const ADDRESS: usize = CLK + 1;
+const VALUE: usize = ADDRESS + 1;
+const IS_WRITE: usize = VALUE + 1;
+const FIRST_GADGET_COLUMN: usize = IS_WRITE + 1;
+
+/// Periodic columns: the program and the table of the range checks.
+const PROGRAM_TABLE: usize = 0;
+const RANGE_CHECK_TABLE: usize = 1;
+
+/// The final value of `r0`.
+#[derive(Clone, Debug)]
+pub struct VmPublicInput {
+    pub output: FE,
+}
+
+/// Template of a fetch-decode-execute AIR for a small register machine, with
+/// a row per executed instruction. Each row has:
+///
+/// - the program counter `pc`, the registers and the clock,
+/// - a flag per instruction, none of them set for `Halt`, and the immediate,
+/// - the fetched word, `flags + immediate · 2⁸ + pc · 2³²`, which is looked up
+///   in the program, a periodic column. The immediates are range checked to
+///   16 bits, so the word determines the instruction at `pc`,
+/// - one memory access, of a `Memory` gadget: `Load` reads at `r1`, `Store`
+///   writes `r0` at `r1`, and the other instructions write zero at address 0,
+///   which programs shouldn't use,
+/// - an `IsZero` gadget on `r0` for `Jnz`.
+///
+/// Each instruction only constrains the next state through its flag, with the
+/// helpers of `gadgets::selector`; adding one is a flag, a case in the
+/// decoding and its terms in the transitions of the state. The registers
+/// start at zero, and the public input is the final value of `r0`.
+#[derive(Clone)]
+pub struct VmAIR {
+    context: AirContext,
+    program: Vec<Instruction>,
+    r0_is_zero: IsZero,
+    memory: Memory,
+    immediate_check: RangeCheck,
+    program_lookup: Lookup,
+}
+
+impl VmAIR {
+    /// AIR of `trace_length` steps of `program`, which starts at `pc` zero.
+    pub fn new(
+        options: ProofOptions,
+        trace_length: usize,
+        program: Vec<Instruction>,
+    ) -> Result<Self, ProvingError> {
+        if program.is_empty() || program.len() > trace_length {
+            return Err(ProvingError::WrongParameter(format!(
+                "a program of {} instructions doesn't fit in {trace_length} rows",
+                program.len()
+            )));
+        }
+        let r0_is_zero = IsZero::new(R0, FIRST_GADGET_COLUMN);
+        let accesses = AccessColumns {
+            address: ADDRESS,
+            clk: CLK,
+            value: VALUE,
+            is_write: IS_WRITE,
+        };
+        let memory_column = FIRST_GADGET_COLUMN + r0_is_zero.num_main_columns();
+        let memory = Memory::new(
+            accesses,
+            RANGE_CHECK_BITS,
+            trace_length,
+            memory_column,
+            RANGE_CHECK_TABLE,
+            0,
+        )?;
+        let immediate_column = memory_column + memory.num_main_columns();
+        let mut immediate_check = RangeCheck::new(
+            IMMEDIATE,
+            RANGE_CHECK_BITS,
+            trace_length,
+            immediate_column,
+            RANGE_CHECK_TABLE,
+            0,
+        )?;
+        let main_columns = immediate_column + immediate_check.num_main_columns();
+
+        // The auxiliary columns follow the main ones, in the same order.
+        let memory = Memory::new(
+            accesses,
+            RANGE_CHECK_BITS,
+            trace_length,
+            memory_column,
+            RANGE_CHECK_TABLE,
+            main_columns,
+        )?;
+        immediate_check.first_sum_column = main_columns + memory.num_auxiliary_columns();
+        let program_lookup = Lookup {
+            values: FETCHED,
+            table: TableColumn::Periodic(PROGRAM_TABLE),
+            multiplicities: FETCH_MULTIPLICITY,
+            sum: immediate_check.first_sum_column + immediate_check.num_lookups(),
+        };
+
+        // Flags and decoding, then the transitions of pc, r0, r1 and the
+        // clock, then the memory access, then the gadgets.
+        let mut transition_degrees = vec![2; FLAGS + 1];
+        transition_degrees.extend([1, 3, 3, 2, 1, 2, 1, 2, 2]);
+        let mut transition_exemptions = vec![0; FLAGS + 2];
+        transition_exemptions.extend([1, 1, 1, 1, 0, 0, 0, 0]);
+        transition_degrees.extend(r0_is_zero.transition_degrees());
+        transition_degrees.extend(memory.transition_degrees());
+        transition_exemptions.extend(vec![0; r0_is_zero.transition_degrees().len()]);
+        transition_exemptions.extend(memory.transition_exemptions());
+        transition_degrees.extend(immediate_check.transition_degrees());
+        transition_degrees.push(Lookup::TRANSITION_DEGREE);
+        transition_exemptions.resize(transition_degrees.len(), 0);
+
+        let context = AirContext {
+            options,
+            trace_length,
+            trace_columns: program_lookup.sum + 1,
+            num_transition_constraints: transition_degrees.len(),
+            transition_degrees,
+            transition_exemptions,
+            transition_offsets: vec![0, 1],
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        Ok(Self {
+            context,
+            program,
+            r0_is_zero,
+            memory,
+            immediate_check,
+            program_lookup,
+        })
+    }
+
+    /// The fetched words of the program, padded with `Halt` up to a power of
+    /// two.
+    fn program_table(&self) -> Vec<FE> {
+        (0..self.program.len().next_power_of_two())
+            .map(|pc| self.instruction(pc).fetched_word(pc))
+            .collect()
+    }
+
+    fn instruction(&self, pc: usize) -> Instruction {
+        self.program.get(pc).copied().unwrap_or(Instruction::Halt)
+    }
+
+    /// Executes the program and builds the main trace, which is the raw trace
+    /// of the AIR. Fails if the program leaves its table or loads an address
+    /// before storing to it.
+    pub fn execute(&self) -> Result<Vec<Vec<FE>>, ProvingError> {
+        let trace_length = self.context.trace_length;
+        let table_length = self.program.len().next_power_of_two();
+        let mut columns = vec![Vec::new(); FIRST_GADGET_COLUMN];
+        let mut memory = BTreeMap::new();
+        let (mut pc, mut r0, mut r1) = (0, FE::zero(), 0u16);
+
+        for row in 0..trace_length {
+            if pc >= table_length {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the program counter {pc} at row {row} is outside of the program"
+                )));
+            }
+            let instruction = self.instruction(pc);
+            let (address, value, is_write) = match instruction {
+                Instruction::Load => {
+                    let value = memory.get(&r1).cloned().ok_or_else(|| {
+                        ProvingError::WrongParameter(format!(
+                            "the load at row {row} reads address {r1} before it is stored to"
+                        ))
+                    })?;
+                    (r1, value, 0)
+                }
+                Instruction::Store => (r1, r0.clone(), 1),
+                _ => (0, FE::zero(), 1),
+            };
+            if is_write == 1 {
+                memory.insert(address, value.clone());
+            }
+
+            let mut row_values = vec![FE::from(pc as u64), r0.clone(), FE::from(u64::from(r1))];
+            row_values.extend(
+                (0..FLAGS).map(|flag| FE::from(u64::from(instruction.flag() == Some(flag)))),
+            );
+            row_values.extend([
+                FE::from(instruction.immediate()),
+                instruction.fetched_word(pc),
+                FE::zero(),
+                FE::from(row as u64),
+                FE::from(u64::from(address)),
+                value.clone(),
+                FE::from(is_write),
+            ]);
+            for (column, value) in columns.iter_mut().zip(row_values) {
+                column.push(value);
+            }
+
+            pc = match instruction {
+                Instruction::Add => {
+                    r0 = &r0 + FE::from(u64::from(r1));
+                    pc + 1
+                }
+                Instruction::Mul => {
+                    r0 = &r0 * FE::from(u64::from(r1));
+                    pc + 1
+                }
+                Instruction::Set(immediate) => {
+                    r1 = immediate;
+                    pc + 1
+                }
+                Instruction::Load => {
+                    r0 = value;
+                    pc + 1
+                }
+                Instruction::Store => pc + 1,
+                Instruction::Jnz(target) if r0 != FE::zero() => usize::from(target),
+                Instruction::Jnz(_) => pc + 1,
+                Instruction::Halt => pc,
+            };
+        }
+
+        let mut multiplicities = lookup::multiplicities(&columns[FETCHED], &self.program_table())
+            .expect("the fetched words are in the program");
+        multiplicities.resize(trace_length, FE::zero());
+        columns[FETCH_MULTIPLICITY] = multiplicities;
+
+        let is_zero_columns = self.r0_is_zero.build_main_columns(&columns[R0]);
+        let memory_columns = self.memory.build_main_columns(
+            &columns[ADDRESS],
+            &columns[CLK],
+            &columns[VALUE],
+            &columns[IS_WRITE],
+        )?;
+        let immediate_columns = self
+            .immediate_check
+            .build_main_columns(&columns[IMMEDIATE])?;
+        columns.extend(is_zero_columns);
+        columns.extend(memory_columns);
+        columns.extend(immediate_columns);
+        Ok(columns)
+    }
+}
+
+impl AIR for VmAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<Vec<FieldElement<Self::Field>>>;
+    /// The challenges `z` and `α` of the permutation of the memory. `z` is
+    /// also the one of the lookups.
+    type RAPChallenges = (FieldElement<Self::Field>, FieldElement<Self::Field>);
+    type PublicInput = VmPublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let trace = TraceTable::new_from_cols(raw_trace);
+        let output = trace.get_row(trace.n_rows() - 1)[R0].clone();
+        Ok((trace, VmPublicInput { output }))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        (z, alpha): &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        let periodic_columns = self.periodic_columns();
+        let memory = self
+            .memory
+            .build_auxiliary_columns(main_trace, &periodic_columns, z, alpha);
+        let mut lookups = self.immediate_check.lookups();
+        lookups.push(self.program_lookup.clone());
+        let sums = lookup::build_sum_columns(&lookups, main_trace, &periodic_columns, z);
+        memory.concatenate(sums.table, sums.n_cols)
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        (
+            transcript_to_field(transcript),
+            transcript_to_field(transcript),
+        )
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        vec![self.program_table(), self.immediate_check.table()]
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        let mut lookups = self.memory.lookups();
+        lookups.extend(self.immediate_check.lookups());
+        lookups.push(self.program_lookup.clone());
+        lookups
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        (z, alpha): &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let flags = &current[FIRST_FLAG..FIRST_FLAG + FLAGS];
+        let any_flag = flags.iter().fold(FE::zero(), |acc, flag| acc + flag);
+        let one = FE::one();
+
+        let mut evaluations: Vec<_> = flags.iter().map(is_boolean).collect();
+        evaluations.push(is_boolean(&any_flag));
+
+        // Decoding.
+        let word = flags
+            .iter()
+            .rev()
+            .fold(FE::zero(), |acc, flag| acc * FE::from(2) + flag)
+            + &current[IMMEDIATE] * FE::from(1u64 << IMMEDIATE_SHIFT)
+            + &current[PC] * FE::from(1u64 << PC_SHIFT);
+        evaluations.push(&current[FETCHED] - word);
+
+        // State transitions.
+        let (pc, r0, r1, immediate) = (
+            &current[PC],
+            &current[R0],
+            &current[R1],
+            &current[IMMEDIATE],
+        );
+        let halt = &one - &any_flag;
+        let jump = when_not(self.r0_is_zero.result(frame), flags[JNZ].clone());
+        let next_pc = when(&halt, pc.clone())
+            + when(&jump, immediate.clone())
+            + (&one - &halt - &jump) * (pc + &one);
+        evaluations.push(&next[PC] - next_pc);
+        let next_r0 = r0
+            + when(&flags[ADD], r1.clone())
+            + when(&flags[MUL], r0 * r1 - r0)
+            + when(&flags[LOAD], &current[VALUE] - r0);
+        evaluations.push(&next[R0] - next_r0);
+        evaluations.push(&next[R1] - select(&flags[SET], immediate, r1));
+        evaluations.push(&next[CLK] - &current[CLK] - &one);
+
+        // Memory access.
+        let accesses = &flags[LOAD] + &flags[STORE];
+        evaluations.extend([
+            &current[ADDRESS] - when(&accesses, r1.clone()),
+            &current[IS_WRITE] - (&one - &flags[LOAD]),
+            when(&flags[STORE], &current[VALUE] - r0),
+            when_not(&accesses, current[VALUE].clone()),
+        ]);
+
+        evaluations.extend(self.r0_is_zero.evaluate_transitions(frame));
+        evaluations.extend(self.memory.evaluate_transitions(frame, z, alpha));
+        evaluations.extend(self.immediate_check.evaluate_transitions(frame, z));
+        evaluations.push(self.program_lookup.evaluate_transition(frame, z));
+        evaluations
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let last_row = self.context().trace_length - 1;
+        let mut constraints: Vec<_> = [PC, R0, R1, CLK]
+            .into_iter()
+            .map(|column| BoundaryConstraint::new(column, 0, FE::zero()))
+            .collect();
+        constraints.push(BoundaryConstraint::new(
+            R0,
+            last_row,
+            public_input.output.clone(),
+        ));
+        constraints.extend(self.memory.boundary_constraints());
+        constraints.extend(self.immediate_check.boundary_constraints());
+        constraints.push(self.program_lookup.boundary_constraint());
+        BoundaryConstraints::from_constraints(constraints)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.memory.num_auxiliary_columns() + self.immediate_check.num_lookups() + 1
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
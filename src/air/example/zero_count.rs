@@ -0,0 +1,139 @@
+use crate::{
+    air::{
+        self,
+        constraints::boundary::{BoundaryConstraint, BoundaryConstraints},
+        context::AirContext,
+        frame::Frame,
+        trace::TraceTable,
+        traits::AIR,
+    },
+    fri::FieldElement,
+    prover::ProvingError,
+};
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+const VALUE: usize = 0;
+const INVERSE: usize = 1;
+const COUNT: usize = 2;
+
+/// Counts the zeros of a column. The main trace has the values `a`, their
+/// inverses (zero for a zero value) and the running count `s`, and the
+/// virtual column `is_zero = 1 - a·a⁻¹` is computed from the first two:
+///
+/// * `a · is_zero = 0`, so `is_zero` is zero when `a` isn't.
+/// * `sᵢ₊₁ = sᵢ + is_zeroᵢ`.
+///
+/// The public input is the number of zeros before the last row.
+#[derive(Clone)]
+pub struct ZeroCountAIR {
+    context: AirContext,
+}
+
+impl ZeroCountAIR {
+    pub fn new(context: AirContext) -> Self {
+        Self { context }
+    }
+}
+
+impl AIR for ZeroCountAIR {
+    type Field = Stark252PrimeField;
+    type ChallengeField = Self::Field;
+    type RawTrace = Vec<FieldElement<Self::Field>>;
+    type RAPChallenges = ();
+    type PublicInput = FieldElement<Self::Field>;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        _public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let inverses: Vec<_> = raw_trace
+            .iter()
+            .map(|value| {
+                if value == &FieldElement::zero() {
+                    FieldElement::zero()
+                } else {
+                    value.inv()
+                }
+            })
+            .collect();
+
+        let mut count = vec![FieldElement::zero()];
+        for value in &raw_trace[..raw_trace.len() - 1] {
+            let is_zero = u64::from(value == &FieldElement::zero());
+            count.push(count.last().unwrap() + FieldElement::from(is_zero));
+        }
+        let zeros = count.last().unwrap().clone();
+
+        Ok((
+            TraceTable::new_from_cols(&[raw_trace.clone(), inverses, count]),
+            zeros,
+        ))
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        2 * self.context().trace_length
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        _main_trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, _transcript: &mut T) -> Self::RAPChallenges {}
+
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        vec!["is_zero"]
+    }
+
+    fn evaluate_virtual_columns(
+        &self,
+        row: &[FieldElement<Self::ChallengeField>],
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        vec![FieldElement::one() - &row[VALUE] * &row[INVERSE]]
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        _rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        let is_zero = self.virtual_column("is_zero").unwrap();
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+
+        vec![
+            &current[VALUE] * &current[is_zero],
+            &next[COUNT] - &current[COUNT] - &current[is_zero],
+        ]
+    }
+
+    fn boundary_constraints(
+        &self,
+        _rap_challenges: &Self::RAPChallenges,
+        zeros: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        let trace_length = self.context().trace_length;
+        let first = BoundaryConstraint::new(COUNT, 0, FieldElement::zero());
+        let last = BoundaryConstraint::new_last_row(COUNT, trace_length, zeros.clone());
+
+        BoundaryConstraints::from_constraints(vec![first, last])
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        0
+    }
+
+    fn context(&self) -> &air::context::AirContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> Option<&mut air::context::AirContext> {
+        Some(&mut self.context)
+    }
+}
@@ -1,20 +1,99 @@
+use alloc::{format, vec, vec::Vec};
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
     polynomial::Polynomial,
 };
 
-use super::trace::TraceTable;
+use super::{trace::TraceTable, traits::AIR};
+use crate::{prover::ProvingError, IsChallengeField};
 
 #[derive(Clone, Debug)]
-pub struct Frame<F: IsFFTField> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound = "FieldElement<F>: lambdaworks_math::traits::ByteConversion")
+)]
+pub struct Frame<F: IsField> {
     // Vector of rows
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
     data: Vec<FieldElement<F>>,
     row_width: usize,
+    /// Values of the periodic columns at the first row of the frame. They
+    /// aren't part of a proof: the verifier computes them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    periodic_values: Vec<FieldElement<F>>,
 }
 
-impl<F: IsFFTField> Frame<F> {
+impl<F: IsField> Frame<F> {
     pub fn new(data: Vec<FieldElement<F>>, row_width: usize) -> Self {
-        Self { data, row_width }
+        Self {
+            data,
+            row_width,
+            periodic_values: Vec::new(),
+        }
+    }
+
+    /// Sets the values of the periodic columns of the AIR at the first row.
+    pub fn with_periodic_values(mut self, periodic_values: Vec<FieldElement<F>>) -> Self {
+        self.periodic_values = periodic_values;
+        self
+    }
+
+    /// Values of the periodic columns of the AIR at the first row, in the
+    /// order of `AIR::periodic_columns`.
+    pub fn periodic_values(&self) -> &[FieldElement<F>] {
+        &self.periodic_values
+    }
+
+    /// Appends to every row the values of the virtual columns computed from
+    /// it, as returned by `AIR::evaluate_virtual_columns`. The frame is kept
+    /// as is if there are none.
+    pub fn with_virtual_columns(
+        self,
+        evaluate: impl Fn(&[FieldElement<F>]) -> Vec<FieldElement<F>>,
+    ) -> Self {
+        let mut rows = self.data.chunks(self.row_width);
+        let first_values = match rows.next() {
+            Some(row) => evaluate(row),
+            None => return self,
+        };
+        if first_values.is_empty() {
+            return self;
+        }
+
+        let row_width = self.row_width + first_values.len();
+        let mut data = Vec::with_capacity(self.num_rows() * row_width);
+        data.extend_from_slice(&self.data[..self.row_width]);
+        data.extend(first_values);
+        for row in rows {
+            data.extend_from_slice(row);
+            data.extend(evaluate(row));
+        }
+        Self {
+            data,
+            row_width,
+            periodic_values: self.periodic_values,
+        }
+    }
+
+    /// Frame with the rows `rows` of this one, in that order, and the same
+    /// periodic values.
+    pub fn select_rows(&self, rows: &[usize]) -> Self {
+        let data = rows
+            .iter()
+            .flat_map(|row| self.get_row(*row).iter().cloned())
+            .collect();
+        Self {
+            data,
+            row_width: self.row_width,
+            periodic_values: self.periodic_values.clone(),
+        }
     }
 
     pub fn num_rows(&self) -> usize {
@@ -35,11 +114,28 @@ impl<F: IsFFTField> Frame<F> {
         &mut self.data[row_offset..row_offset + self.row_width]
     }
 
-    pub fn read_from_trace(
-        trace: &TraceTable<F>,
+    /// Reads the rows of `trace` at the given offsets from `step`, mapping the
+    /// values into the challenge field the constraints are evaluated in.
+    pub fn read_from_trace<B: IsFFTField>(
+        trace: &TraceTable<B>,
         step: usize,
         blowup: u8,
         offsets: &[usize],
+    ) -> Self
+    where
+        F: IsChallengeField<B>,
+    {
+        Self::read_rows(trace, step, blowup, offsets, F::embed)
+    }
+
+    /// Reads the rows of `trace` at the given offsets from `step`, mapping
+    /// each value with `map`.
+    fn read_rows<B: IsField>(
+        trace: &TraceTable<B>,
+        step: usize,
+        blowup: u8,
+        offsets: &[usize],
+        map: impl Fn(&FieldElement<B>) -> FieldElement<F>,
     ) -> Self {
         // Get trace length to apply module with it when getting elements of
         // the frame from the trace.
@@ -49,34 +145,178 @@ impl<F: IsFFTField> Frame<F> {
             .flat_map(|frame_row_idx| {
                 trace
                     .get_row((step + (frame_row_idx * blowup as usize)) % trace_steps)
-                    .to_vec()
+                    .iter()
+                    .map(&map)
+                    .collect::<Vec<_>>()
             })
             .collect();
 
         Self::new(data, trace.n_cols)
     }
 
+    /// Appends to every row the values of the columns of `extension_trace`,
+    /// which is over the challenge field, read at the same rows. The frame is
+    /// kept as is if there are none.
+    pub fn with_extension_columns(
+        self,
+        extension_trace: &TraceTable<F>,
+        step: usize,
+        blowup: u8,
+        offsets: &[usize],
+    ) -> Self {
+        if extension_trace.is_empty() {
+            return self;
+        }
+        let extension =
+            Self::read_rows(extension_trace, step, blowup, offsets, FieldElement::clone);
+
+        let row_width = self.row_width + extension.row_width;
+        let mut data = Vec::with_capacity(self.num_rows() * row_width);
+        for row in 0..self.num_rows() {
+            data.extend_from_slice(self.get_row(row));
+            data.extend_from_slice(extension.get_row(row));
+        }
+        Self {
+            data,
+            row_width,
+            periodic_values: self.periodic_values,
+        }
+    }
+
     /// Given a slice of trace polynomials, an evaluation point `x`, the frame offsets
     /// corresponding to the computation of the transitions, and a primitive root,
     /// outputs the trace evaluations of each trace polynomial over the values used to
-    /// compute a transition.
+    /// compute a transition, in the order of the entries of `layout`.
     /// Example: For a simple Fibonacci computation, if t(x) is the trace polynomial of
     /// the computation, this will output evaluations t(x), t(g * x), t(g^2 * z).
-    pub fn get_trace_evaluations(
-        trace_polys: &[Polynomial<FieldElement<F>>],
+    /// The trace polynomials have coefficients in the trace field `B`, and are
+    /// followed by the polynomials of the columns over the challenge field, in
+    /// which `x` is.
+    pub fn get_trace_evaluations<B: IsFFTField>(
+        trace_polys: &[Polynomial<FieldElement<B>>],
+        extension_polys: &[Polynomial<FieldElement<F>>],
         x: &FieldElement<F>,
         frame_offsets: &[usize],
-        primitive_root: &FieldElement<F>,
-    ) -> Vec<Vec<FieldElement<F>>> {
-        frame_offsets
+        layout: &FrameLayout,
+        primitive_root: &FieldElement<B>,
+    ) -> Vec<FieldElement<F>>
+    where
+        F: IsChallengeField<B>,
+    {
+        let eval_points: Vec<_> = frame_offsets
             .iter()
-            .map(|offset| x * primitive_root.pow(*offset))
-            .map(|eval_point| {
-                trace_polys
+            .map(|offset| x * F::embed(&primitive_root.pow(*offset)))
+            .collect();
+        layout
+            .entries()
+            .map(|(row, column)| match trace_polys.get(column) {
+                // Horner's rule, embedding each coefficient on the way.
+                Some(poly) => poly
+                    .coefficients()
                     .iter()
-                    .map(|poly| poly.evaluate(&eval_point))
-                    .collect::<Vec<FieldElement<F>>>()
+                    .rev()
+                    .fold(FieldElement::<F>::zero(), |acc, coefficient| {
+                        acc * &eval_points[row] + F::embed(coefficient)
+                    }),
+                None => extension_polys[column - trace_polys.len()].evaluate(&eval_points[row]),
             })
             .collect()
     }
 }
+
+/// Values of the frame that the constraints read: each column at the rows of
+/// its offsets in `AIR::column_offsets`, and every column at the first row,
+/// which the boundary constraints use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameLayout {
+    columns: usize,
+    /// Whether each value of the frame is read, row by row.
+    read: Vec<bool>,
+}
+
+impl FrameLayout {
+    pub fn new(transition_offsets: &[usize], column_offsets: &[Vec<usize>]) -> Self {
+        let read = transition_offsets
+            .iter()
+            .enumerate()
+            .flat_map(|(row, offset)| {
+                column_offsets
+                    .iter()
+                    .map(move |offsets| row == 0 || offsets.contains(offset))
+            })
+            .collect();
+        Self {
+            columns: column_offsets.len(),
+            read,
+        }
+    }
+
+    /// Layout of the frame of `air`.
+    pub fn of<A: AIR>(air: &A) -> Self {
+        Self::new(&air.context().transition_offsets, &air.column_offsets())
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.read.len().checked_div(self.columns).unwrap_or(0)
+    }
+
+    /// Number of values read.
+    pub fn num_entries(&self) -> usize {
+        self.read.iter().filter(|read| **read).count()
+    }
+
+    /// Row and column of the values read, row by row. This is the order of the
+    /// out of domain evaluations in a proof.
+    pub fn entries(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.read
+            .iter()
+            .enumerate()
+            .filter(|(_, read)| **read)
+            .map(|(index, _)| (index / self.columns, index % self.columns))
+    }
+
+    /// Row and column of the values read, column by column. This is the order
+    /// of the trace terms of the DEEP composition polynomial.
+    pub fn entries_by_column(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rows = self.num_rows();
+        (0..self.columns)
+            .flat_map(move |column| (0..rows).map(move |row| (row, column)))
+            .filter(|(row, column)| self.read[row * self.columns + column])
+    }
+
+    /// Frame with the `values` of the entries, row by row, and zero in the
+    /// values that aren't read.
+    pub fn frame<F: IsField>(&self, values: &[FieldElement<F>]) -> Frame<F> {
+        let mut data = vec![FieldElement::zero(); self.read.len()];
+        for ((row, column), value) in self.entries().zip(values) {
+            data[row * self.columns + column] = value.clone();
+        }
+        Frame::new(data, self.columns)
+    }
+}
+
+/// Checks that there are offsets for every column and that they are
+/// transition offsets.
+pub(crate) fn check_column_offsets(
+    column_offsets: &[Vec<usize>],
+    transition_offsets: &[usize],
+    trace_columns: usize,
+) -> Result<(), ProvingError> {
+    if column_offsets.len() != trace_columns {
+        return Err(ProvingError::WrongParameter(format!(
+            "there are offsets for {} columns in a trace of {trace_columns} columns",
+            column_offsets.len()
+        )));
+    }
+    for (column, offsets) in column_offsets.iter().enumerate() {
+        if let Some(offset) = offsets
+            .iter()
+            .find(|offset| !transition_offsets.contains(offset))
+        {
+            return Err(ProvingError::WrongParameter(format!(
+                "column {column} is read at offset {offset}, which isn't a transition offset"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Bit decomposition of a main trace column.
+//!
+//! A `BitDecomposition` of `b` bits adds `b` main columns with the bits of
+//! each value, least significant first, and the constraints
+//!
+//! ```text
+//! bᵢ · (bᵢ - 1) = 0        for every bit
+//! v - Σᵢ bᵢ · 2ⁱ = 0
+//! ```
+//!
+//! so the bit columns can be used in bitwise and comparison constraints. The
+//! decomposition also proves that the values are below 2ᵇ.
+
+use alloc::{format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::to_u64;
+use crate::{air::frame::Frame, prover::ProvingError};
+
+/// Decomposition of a main trace column in `bits` bit columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitDecomposition {
+    /// Main trace column with the decomposed values.
+    pub column: usize,
+    pub bits: usize,
+    /// Main trace column of the least significant bit. The others follow it.
+    pub first_bit_column: usize,
+}
+
+impl BitDecomposition {
+    /// Decomposition of `column` in `bits` bits, between 1 and 64, with the
+    /// bit columns from `first_bit_column` on.
+    pub fn new(column: usize, bits: usize, first_bit_column: usize) -> Result<Self, ProvingError> {
+        if !(1..=64).contains(&bits) {
+            return Err(ProvingError::WrongParameter(format!(
+                "a decomposition in {bits} bits isn't between 1 and 64 bits"
+            )));
+        }
+        Ok(Self {
+            column,
+            bits,
+            first_bit_column,
+        })
+    }
+
+    /// Main trace column of the bit `bit`, the least significant being 0.
+    pub fn bit_column(&self, bit: usize) -> usize {
+        self.first_bit_column + bit
+    }
+
+    /// Number of main trace columns the gadget adds, one per bit.
+    pub fn num_main_columns(&self) -> usize {
+        self.bits
+    }
+
+    /// First main trace column after those of the gadget.
+    pub fn next_column(&self) -> usize {
+        self.first_bit_column + self.bits
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`. They have no exemptions.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![2; self.bits];
+        degrees.push(1);
+        degrees
+    }
+
+    /// Builds the bit columns from the decomposed `values`.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        values: &[FieldElement<F>],
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let mut bit_columns = vec![Vec::new(); self.bits];
+        for (row, value) in values.iter().enumerate() {
+            let value = to_u64(value)
+                .filter(|value| self.bits == 64 || *value >> self.bits == 0)
+                .ok_or_else(|| {
+                    ProvingError::WrongParameter(format!(
+                        "the value of column {} at row {row} doesn't fit in {} bits",
+                        self.column, self.bits
+                    ))
+                })?;
+            for (bit, column) in bit_columns.iter_mut().enumerate() {
+                column.push(FieldElement::from((value >> bit) & 1));
+            }
+        }
+        Ok(bit_columns)
+    }
+
+    /// The bits of the first row of `frame`, least significant first.
+    pub fn bits_of<'a, F: IsFFTField>(&self, frame: &'a Frame<F>) -> &'a [FieldElement<F>] {
+        &frame.get_row(0)[self.first_bit_column..self.next_column()]
+    }
+
+    /// Evaluates the transition constraints of the gadget on the first row of
+    /// `frame`: the booleanity of every bit, then the recomposition.
+    pub fn evaluate_transitions<F: IsFFTField>(&self, frame: &Frame<F>) -> Vec<FieldElement<F>> {
+        let bits = self.bits_of(frame);
+        let mut evaluations: Vec<_> = bits
+            .iter()
+            .map(|bit| bit * (bit - FieldElement::one()))
+            .collect();
+
+        let two = FieldElement::<F>::from(2);
+        let recomposed = bits
+            .iter()
+            .rev()
+            .fold(FieldElement::zero(), |acc, bit| acc * &two + bit);
+        evaluations.push(&frame.get_row(0)[self.column] - recomposed);
+        evaluations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::trace::TraceTable;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn frame(values: &[FE]) -> Frame<F> {
+        Frame::new(values.to_vec(), values.len())
+    }
+
+    #[test]
+    fn bit_columns_satisfy_the_constraints() {
+        let decomposition = BitDecomposition::new(0, 4, 1).unwrap();
+        assert_eq!(decomposition.transition_degrees(), vec![2, 2, 2, 2, 1]);
+        assert_eq!(decomposition.next_column(), 5);
+
+        let values: Vec<_> = [0, 5, 9, 15].into_iter().map(FE::from).collect();
+        let mut columns = vec![values.clone()];
+        columns.extend(decomposition.build_main_columns(&values).unwrap());
+        let trace = TraceTable::new_from_cols(&columns);
+        assert_eq!(trace.get_row(2), [9, 1, 0, 0, 1].map(FE::from).as_slice());
+        for step in 0..trace.n_rows() {
+            let frame = Frame::read_from_trace(&trace, step, 1, &[0]);
+            assert!(decomposition
+                .evaluate_transitions(&frame)
+                .iter()
+                .all(|evaluation| evaluation == &FE::zero()));
+        }
+    }
+
+    #[test]
+    fn non_boolean_bits_and_wrong_recompositions_are_caught() {
+        let decomposition = BitDecomposition::new(0, 2, 1).unwrap();
+
+        // 2 = 2 + 2 · 0 recomposes, but its first bit isn't boolean.
+        let evaluations = decomposition.evaluate_transitions(&frame(&[2, 2, 0].map(FE::from)));
+        assert_ne!(evaluations[0], FE::zero());
+        assert_eq!(evaluations[2], FE::zero());
+
+        let evaluations = decomposition.evaluate_transitions(&frame(&[3, 1, 0].map(FE::from)));
+        assert_ne!(evaluations[2], FE::zero());
+    }
+
+    #[test]
+    fn values_that_dont_fit_are_rejected() {
+        let decomposition = BitDecomposition::new(0, 4, 1).unwrap();
+        assert!(decomposition.build_main_columns(&[FE::from(16)]).is_err());
+        assert!(BitDecomposition::new(0, 0, 1).is_err());
+        assert!(BitDecomposition::new(0, 65, 1).is_err());
+    }
+}
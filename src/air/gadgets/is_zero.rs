@@ -0,0 +1,158 @@
+//! Whether the values of a main trace column are zero.
+//!
+//! An `IsZero` adds a main column with the inverse of each value, or zero
+//! for a zero value, and a main column `z` that is one where the value is
+//! zero and zero elsewhere, with the constraints
+//!
+//! ```text
+//! x · x⁻¹ + z - 1 = 0
+//! x · z = 0
+//! ```
+//!
+//! The first one makes `z` one wherever `x` has no inverse, and the second
+//! one makes it zero wherever `x` isn't zero. `z` can then gate constraints
+//! with the functions of `gadgets::selector`.
+
+use alloc::{vec, vec::Vec};
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+
+use crate::air::{constraints::registry::TransitionConstraints, frame::Frame};
+
+/// Zero test of a main trace column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IsZero {
+    /// Main trace column with the tested values.
+    pub column: usize,
+    /// Main trace column with the inverses of the values, followed by the one
+    /// with the result.
+    pub first_column: usize,
+}
+
+impl IsZero {
+    /// Degree of both transition constraints of the gadget.
+    pub const TRANSITION_DEGREE: usize = 2;
+
+    /// Zero test of `column`, with its columns from `first_column` on.
+    pub fn new(column: usize, first_column: usize) -> Self {
+        Self {
+            column,
+            first_column,
+        }
+    }
+
+    /// Main trace column with the inverses of the values.
+    pub fn inverse_column(&self) -> usize {
+        self.first_column
+    }
+
+    /// Main trace column that is one where the value is zero.
+    pub fn result_column(&self) -> usize {
+        self.first_column + 1
+    }
+
+    /// Number of main trace columns the gadget adds.
+    pub fn num_main_columns(&self) -> usize {
+        2
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`. They have no exemptions.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        vec![Self::TRANSITION_DEGREE; 2]
+    }
+
+    /// Builds the inverse and result columns from the tested `values`.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        values: &[FieldElement<F>],
+    ) -> Vec<Vec<FieldElement<F>>> {
+        let (inverses, results): (Vec<_>, Vec<_>) = values
+            .iter()
+            .map(|value| {
+                if value == &FieldElement::zero() {
+                    (FieldElement::zero(), FieldElement::one())
+                } else {
+                    (value.inv(), FieldElement::zero())
+                }
+            })
+            .unzip();
+        vec![inverses, results]
+    }
+
+    /// Result of the test on the first row of `frame`.
+    pub fn result<'a, F: IsFFTField>(&self, frame: &'a Frame<F>) -> &'a FieldElement<F> {
+        &frame.get_row(0)[self.result_column()]
+    }
+
+    /// Evaluates the transition constraints of the gadget on the first row of
+    /// `frame`.
+    pub fn evaluate_transitions<F: IsFFTField>(&self, frame: &Frame<F>) -> Vec<FieldElement<F>> {
+        let row = frame.get_row(0);
+        let value = &row[self.column];
+        let result = &row[self.result_column()];
+        vec![
+            value * &row[self.inverse_column()] + result - FieldElement::one(),
+            value * result,
+        ]
+    }
+
+    /// Registers the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`.
+    pub fn register_constraints<F: IsFFTField, C: 'static, P: 'static>(
+        &self,
+        constraints: &mut TransitionConstraints<F, C, P>,
+    ) {
+        for index in 0..2 {
+            let gadget = self.clone();
+            constraints.register_fn_at(&[0], Self::TRANSITION_DEGREE, 0, move |frame, _, _, _| {
+                gadget.evaluate_transitions(frame).swap_remove(index)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::trace::TraceTable;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    #[test]
+    fn result_is_one_exactly_on_zero_values() {
+        let is_zero = IsZero::new(0, 1);
+        let values: Vec<_> = [0, 7, 0, 1].into_iter().map(FE::from).collect();
+        let mut columns = vec![values.clone()];
+        columns.extend(is_zero.build_main_columns(&values));
+        let trace = TraceTable::new_from_cols(&columns);
+
+        for step in 0..trace.n_rows() {
+            let frame = Frame::read_from_trace(&trace, step, 1, &[0]);
+            let expected = if values[step] == FE::zero() {
+                FE::one()
+            } else {
+                FE::zero()
+            };
+            assert_eq!(is_zero.result(&frame), &expected);
+            assert!(is_zero
+                .evaluate_transitions(&frame)
+                .iter()
+                .all(|evaluation| evaluation == &FE::zero()));
+        }
+    }
+
+    #[test]
+    fn wrong_results_are_caught() {
+        let is_zero = IsZero::new(0, 1);
+        // A non zero value claimed to be zero, with any inverse.
+        let frame = Frame::new([7, 0, 1].map(FE::from).to_vec(), 3);
+        assert_ne!(is_zero.evaluate_transitions(&frame)[1], FE::zero());
+        // A zero value claimed not to be zero.
+        let frame = Frame::new([0, 5, 0].map(FE::from).to_vec(), 3);
+        assert_ne!(is_zero.evaluate_transitions(&frame)[0], FE::zero());
+    }
+}
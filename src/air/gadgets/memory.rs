@@ -0,0 +1,472 @@
+//! Read/write memory consistency.
+//!
+//! The accesses of a VM to its memory are rows of four main trace columns:
+//! the address, the clock of the access, the value and whether it is a
+//! write. The `Memory` gadget adds a copy of the accesses sorted by address
+//! and then clock, proves with a `PermutationArgument` that it is a
+//! permutation of them, and checks on consecutive sorted accesses that
+//!
+//! ```text
+//! the first access to each address is a write,
+//! a read returns the value of the previous access to its address,
+//! addresses increase, and clocks increase at the same address.
+//! ```
+//!
+//! A boolean column `same` tells whether the next sorted access is at the
+//! same address, and the increase of the address or of the clock, minus one,
+//! is range checked to `bits` bits with a `RangeCheck` gadget. Consecutive
+//! addresses and clocks can then differ by at most 2ᵇ.
+//!
+//! Every row of the trace is an access, so rows of an AIR that don't access
+//! the memory can repeat a read of a previous one.
+
+use alloc::{format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{
+    range_check::RangeCheck,
+    selector::{is_boolean, select, when, when_not},
+    to_u64,
+};
+use crate::{
+    air::{
+        constraints::boundary::BoundaryConstraint,
+        frame::Frame,
+        lookup::{self, Lookup},
+        permutation::PermutationArgument,
+        trace::TraceTable,
+    },
+    prover::ProvingError,
+    IsChallengeField,
+};
+
+/// Main trace columns of memory accesses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccessColumns {
+    pub address: usize,
+    pub clk: usize,
+    pub value: usize,
+    /// One for writes and zero for reads.
+    pub is_write: usize,
+}
+
+impl AccessColumns {
+    fn to_vec(self) -> Vec<usize> {
+        vec![self.address, self.clk, self.value, self.is_write]
+    }
+}
+
+/// Consistency of the memory accesses of `accesses`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Memory {
+    pub accesses: AccessColumns,
+    /// First main trace column of the gadget, with the sorted accesses.
+    pub first_main_column: usize,
+    pub permutation: PermutationArgument,
+    /// Range check of the increases of the sorted addresses and clocks.
+    pub range_check: RangeCheck,
+}
+
+impl Memory {
+    /// Memory of `accesses` in a trace of `trace_length` rows, whose
+    /// consecutive addresses and clocks differ by at most 2ᵇ. The main columns
+    /// of the gadget start at `first_main_column`, the table of its range
+    /// check is the periodic column `periodic_column`, and its auxiliary
+    /// columns start at the trace column `first_auxiliary_column`.
+    pub fn new(
+        accesses: AccessColumns,
+        bits: usize,
+        trace_length: usize,
+        first_main_column: usize,
+        periodic_column: usize,
+        first_auxiliary_column: usize,
+    ) -> Result<Self, ProvingError> {
+        let sorted = Self::sorted_at(first_main_column);
+        let range_check = RangeCheck::new(
+            first_main_column + 5,
+            bits,
+            trace_length,
+            first_main_column + 6,
+            periodic_column,
+            first_auxiliary_column + 1,
+        )?;
+        let permutation = PermutationArgument {
+            left: accesses.to_vec(),
+            right: sorted.to_vec(),
+            product: first_auxiliary_column,
+        };
+        Ok(Self {
+            accesses,
+            first_main_column,
+            permutation,
+            range_check,
+        })
+    }
+
+    fn sorted_at(first_main_column: usize) -> AccessColumns {
+        AccessColumns {
+            address: first_main_column,
+            clk: first_main_column + 1,
+            value: first_main_column + 2,
+            is_write: first_main_column + 3,
+        }
+    }
+
+    /// Main trace columns of the sorted accesses.
+    pub fn sorted(&self) -> AccessColumns {
+        Self::sorted_at(self.first_main_column)
+    }
+
+    /// Main trace column telling whether the next sorted access is at the same
+    /// address.
+    pub fn same_address_column(&self) -> usize {
+        self.first_main_column + 4
+    }
+
+    /// Main trace column with the range checked increase of the address, or
+    /// of the clock at the same address, minus one.
+    pub fn increase_column(&self) -> usize {
+        self.first_main_column + 5
+    }
+
+    /// Number of main trace columns the gadget adds.
+    pub fn num_main_columns(&self) -> usize {
+        6 + self.range_check.num_main_columns()
+    }
+
+    /// Number of auxiliary columns the gadget adds: the cumulative product of
+    /// the permutation, then the running sums of the range check.
+    pub fn num_auxiliary_columns(&self) -> usize {
+        1 + self.range_check.num_lookups()
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        let mut degrees = vec![2, 2, 2, 2, 3, 2, PermutationArgument::TRANSITION_DEGREE];
+        degrees.extend(self.range_check.transition_degrees());
+        degrees
+    }
+
+    /// Exemptions of the transition constraints of the gadget. The ones that
+    /// compare consecutive sorted accesses don't hold from the last row to the
+    /// first.
+    pub fn transition_exemptions(&self) -> Vec<usize> {
+        let mut exemptions = vec![0, 0, 1, 1, 1, 1];
+        exemptions.resize(self.transition_degrees().len(), 0);
+        exemptions
+    }
+
+    /// The table of the range check, for `AIR::periodic_columns`.
+    pub fn table<F: IsFFTField>(&self) -> Vec<FieldElement<F>> {
+        self.range_check.table()
+    }
+
+    /// The lookups of the range check, for `AIR::lookups`.
+    pub fn lookups(&self) -> Vec<Lookup> {
+        self.range_check.lookups()
+    }
+
+    /// Builds the main columns of the gadget from the columns of the accesses,
+    /// in the order of `AccessColumns`. Fails if the accesses aren't
+    /// consistent.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        address: &[FieldElement<F>],
+        clk: &[FieldElement<F>],
+        value: &[FieldElement<F>],
+        is_write: &[FieldElement<F>],
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let integer = |values: &[FieldElement<F>], column: usize, row: usize| {
+            to_u64(&values[row]).ok_or_else(|| {
+                ProvingError::WrongParameter(format!(
+                    "the value of column {column} at row {row} doesn't fit in 64 bits"
+                ))
+            })
+        };
+        let mut keys = Vec::with_capacity(address.len());
+        for row in 0..address.len() {
+            keys.push((
+                integer(address, self.accesses.address, row)?,
+                integer(clk, self.accesses.clk, row)?,
+                row,
+            ));
+        }
+        keys.sort_unstable();
+
+        let sorted = |values: &[FieldElement<F>]| -> Vec<_> {
+            keys.iter()
+                .map(|(_, _, row)| values[*row].clone())
+                .collect()
+        };
+        let columns_sorted = [
+            sorted(address),
+            sorted(clk),
+            sorted(value),
+            sorted(is_write),
+        ];
+        let [_, _, sorted_value, sorted_is_write] = &columns_sorted;
+
+        let mut same = Vec::with_capacity(keys.len());
+        let mut increase = Vec::with_capacity(keys.len());
+        for (i, (address, clk, row)) in keys.iter().enumerate() {
+            let is_write = &sorted_is_write[i];
+            if is_write != &FieldElement::zero() && is_write != &FieldElement::one() {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the access at row {row} is neither a read nor a write"
+                )));
+            }
+            let first_access = i == 0 || keys[i - 1].0 != *address;
+            if first_access && is_write == &FieldElement::zero() {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the access at row {row} reads address {address} before it is written"
+                )));
+            }
+            if !first_access
+                && is_write == &FieldElement::zero()
+                && sorted_value[i] != sorted_value[i - 1]
+            {
+                return Err(ProvingError::WrongParameter(format!(
+                    "the read at row {row} doesn't return the value of address {address}"
+                )));
+            }
+
+            let (is_same, difference) = match keys.get(i + 1) {
+                Some((next_address, next_clk, _)) if next_address == address => {
+                    (1, next_clk - clk - 1)
+                }
+                Some((next_address, _, _)) => (0, next_address - address - 1),
+                None => (0, 0),
+            };
+            same.push(FieldElement::from(is_same));
+            increase.push(FieldElement::from(difference));
+        }
+
+        let range_check_columns = self.range_check.build_main_columns(&increase)?;
+        let mut columns = columns_sorted.to_vec();
+        columns.push(same);
+        columns.push(increase);
+        columns.extend(range_check_columns);
+        Ok(columns)
+    }
+
+    /// Builds the auxiliary columns of the gadget from the main trace, with
+    /// the periodic columns of the AIR.
+    pub fn build_auxiliary_columns<F: IsFFTField>(
+        &self,
+        main_trace: &TraceTable<F>,
+        periodic_columns: &[Vec<FieldElement<F>>],
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> TraceTable<F> {
+        let product = self.permutation.build_product_column(main_trace, z, alpha);
+        let sums = lookup::build_sum_columns(&self.lookups(), main_trace, periodic_columns, z);
+        TraceTable::new_from_cols(&[product]).concatenate(sums.table, sums.n_cols)
+    }
+
+    /// Evaluates the transition constraints of the gadget: the booleanity of
+    /// `same` and of the sorted writes, the checks on consecutive sorted
+    /// accesses, the permutation and the range check, in this order.
+    pub fn evaluate_transitions<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<E>> {
+        let sorted = self.sorted();
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let same = &current[self.same_address_column()];
+        let address_increase = &next[sorted.address] - &current[sorted.address];
+        let clk_increase = &next[sorted.clk] - &current[sorted.clk];
+        let next_is_read = FieldElement::<E>::one() - &next[sorted.is_write];
+
+        let mut evaluations = vec![
+            is_boolean(same),
+            is_boolean(&current[sorted.is_write]),
+            when(same, address_increase.clone()),
+            &current[self.increase_column()] + FieldElement::one()
+                - select(same, &clk_increase, &address_increase),
+            when(
+                same,
+                &next_is_read * (&next[sorted.value] - &current[sorted.value]),
+            ),
+            when_not(same, next_is_read),
+            self.permutation.evaluate_transition(frame, z, alpha),
+        ];
+        evaluations.extend(self.range_check.evaluate_transitions(frame, z));
+        evaluations
+    }
+
+    /// The boundary constraints of the gadget: the first sorted access is a
+    /// write, and those of the permutation and of the range check.
+    pub fn boundary_constraints<E: IsField>(&self) -> Vec<BoundaryConstraint<E>> {
+        let mut constraints = vec![
+            BoundaryConstraint::new(self.sorted().is_write, 0, FieldElement::one()),
+            self.permutation.boundary_constraint(),
+        ];
+        constraints.extend(self.range_check.boundary_constraints());
+        constraints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::periodic;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    const ACCESSES: AccessColumns = AccessColumns {
+        address: 0,
+        clk: 1,
+        value: 2,
+        is_write: 3,
+    };
+
+    fn memory() -> Memory {
+        let memory = Memory::new(ACCESSES, 4, 16, 4, 0, 0).unwrap();
+        Memory::new(ACCESSES, 4, 16, 4, 0, 4 + memory.num_main_columns()).unwrap()
+    }
+
+    /// Accesses in the order of their clocks, as `(address, value, is_write)`.
+    fn access_columns(accesses: &[(u64, u64, u64)]) -> Vec<Vec<FE>> {
+        let mut columns = vec![Vec::new(); 4];
+        for (clk, (address, value, is_write)) in accesses.iter().enumerate() {
+            columns[0].push(FE::from(*address));
+            columns[1].push(FE::from(clk as u64));
+            columns[2].push(FE::from(*value));
+            columns[3].push(FE::from(*is_write));
+        }
+        columns
+    }
+
+    fn consistent_accesses() -> Vec<Vec<FE>> {
+        access_columns(&[
+            (5, 10, 1),
+            (3, 7, 1),
+            (5, 10, 0),
+            (4, 1, 1),
+            (3, 7, 0),
+            (5, 11, 1),
+            (5, 11, 0),
+            (4, 1, 0),
+            (3, 8, 1),
+            (3, 8, 0),
+            (4, 2, 1),
+            (5, 11, 0),
+            (4, 2, 0),
+            (3, 8, 0),
+            (5, 12, 1),
+            (4, 2, 0),
+        ])
+    }
+
+    fn trace(memory: &Memory, accesses: &[Vec<FE>], z: &FE, alpha: &FE) -> TraceTable<F> {
+        let mut columns = accesses.to_vec();
+        columns.extend(
+            memory
+                .build_main_columns(&accesses[0], &accesses[1], &accesses[2], &accesses[3])
+                .unwrap(),
+        );
+        let main_trace = TraceTable::new_from_cols(&columns);
+        let auxiliary = memory.build_auxiliary_columns(&main_trace, &[memory.table()], z, alpha);
+        main_trace.concatenate(auxiliary.table, auxiliary.n_cols)
+    }
+
+    fn transitions_vanish(memory: &Memory, trace: &TraceTable<F>, z: &FE, alpha: &FE) -> bool {
+        let exemptions = memory.transition_exemptions();
+        (0..trace.n_rows()).all(|step| {
+            let frame = Frame::read_from_trace(trace, step, 1, &[0, 1])
+                .with_periodic_values(periodic::values_at_row::<F, F>(&[memory.table()], step));
+            memory
+                .evaluate_transitions::<F, F>(&frame, z, alpha)
+                .iter()
+                .zip(&exemptions)
+                .all(|(evaluation, exemption)| {
+                    evaluation == &FE::zero() || step + exemption >= trace.n_rows()
+                })
+        })
+    }
+
+    #[test]
+    fn consistent_accesses_satisfy_the_constraints() {
+        let memory = memory();
+        let (z, alpha) = (FE::from(1000), FE::from(17));
+        let trace = trace(&memory, &consistent_accesses(), &z, &alpha);
+        assert_eq!(
+            trace.n_cols,
+            4 + memory.num_main_columns() + memory.num_auxiliary_columns()
+        );
+        assert_eq!(
+            memory.transition_degrees().len(),
+            memory.transition_exemptions().len()
+        );
+
+        let sorted = memory.sorted();
+        assert_eq!(trace.get_row(0)[sorted.address], FE::from(3));
+        assert_eq!(trace.get_row(15)[sorted.clk], FE::from(14));
+        assert!(transitions_vanish(&memory, &trace, &z, &alpha));
+    }
+
+    #[test]
+    fn reads_of_other_values_are_caught() {
+        let memory = memory();
+        let (z, alpha) = (FE::from(1000), FE::from(17));
+        let accesses = consistent_accesses();
+        let trace = trace(&memory, &accesses, &z, &alpha);
+
+        // The second sorted access reads 7 from address 3. Reading 8 instead,
+        // in both copies of the access, keeps the permutation.
+        let mut columns = trace.cols();
+        columns[ACCESSES.value][4] = FE::from(8);
+        columns[memory.sorted().value][1] = FE::from(8);
+        let product = memory.permutation.build_product_column(
+            &TraceTable::new_from_cols(&columns[..4 + memory.num_main_columns()]),
+            &z,
+            &alpha,
+        );
+        columns[memory.permutation.product] = product;
+        let forged = TraceTable::new_from_cols(&columns);
+        assert!(!transitions_vanish(&memory, &forged, &z, &alpha));
+
+        let mut accesses = accesses;
+        accesses[2][4] = FE::from(8);
+        assert!(memory
+            .build_main_columns(&accesses[0], &accesses[1], &accesses[2], &accesses[3])
+            .is_err());
+    }
+
+    #[test]
+    fn reads_before_writes_and_far_apart_accesses_are_rejected() {
+        let memory = memory();
+        let reads_first = access_columns(&[(1, 0, 0), (1, 0, 1)]);
+        assert!(memory
+            .build_main_columns(
+                &reads_first[0],
+                &reads_first[1],
+                &reads_first[2],
+                &reads_first[3]
+            )
+            .is_err());
+
+        // Addresses 1 and 20 differ by more than 2⁴.
+        let far_apart = access_columns(&[(1, 0, 1), (20, 0, 1)]);
+        assert!(memory
+            .build_main_columns(&far_apart[0], &far_apart[1], &far_apart[2], &far_apart[3])
+            .is_err());
+    }
+}
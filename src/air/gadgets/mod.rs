@@ -0,0 +1,32 @@
+//! Reusable pieces of AIRs. A gadget takes a few columns of a trace, declares
+//! the constraints it adds and builds its columns, and the AIR that uses it
+//! places them after its own.
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+pub mod bits;
+pub mod is_zero;
+pub mod memory;
+pub mod public_memory;
+pub mod range_check;
+pub mod selector;
+pub mod uint32;
+
+/// The value as an integer, if it fits in 64 bits.
+fn to_u64<F: IsFFTField>(value: &FieldElement<F>) -> Option<u64>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let bytes = value.to_bytes_be();
+    let (high, low) = bytes.split_at(bytes.len().saturating_sub(8));
+    if high.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(
+        low.iter()
+            .fold(0, |acc, byte| (acc << 8) | u64::from(*byte)),
+    )
+}
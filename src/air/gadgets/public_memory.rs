@@ -0,0 +1,365 @@
+//! Public memory of a read-only memory, in the style of Cairo.
+//!
+//! The accesses of a VM to a read-only memory are rows of two main trace
+//! columns, the address and the value. Some cells of the memory, such as the
+//! program or its inputs and outputs, are public: they are given by pages of
+//! consecutive addresses in the public input. The trace has a dummy access
+//! `(0, 0)` for each public cell, and the `PublicMemory` gadget adds a copy of
+//! the accesses sorted by address where the dummies are replaced by the
+//! public cells. Its auxiliary column accumulates the quotients of both
+//! sides of a `PermutationArgument`:
+//!
+//! ```text
+//! pᵢ = Πⱼ≤ᵢ (z - lⱼ) / (z - rⱼ)
+//! ```
+//!
+//! Each dummy contributes `z` to the numerator and each public cell `c`
+//! contributes `z - c` to the denominator, so the last value of the product is
+//! `z^|M| / Π (z - (a + α v))` over the public cells, which the verifier
+//! computes from the public input alone. The continuity of the sorted accesses
+//! is left to the AIR that uses the gadget.
+
+use alloc::{format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::to_u64;
+use crate::{
+    air::{
+        constraints::{boundary::BoundaryConstraint, transition::RowSubset},
+        frame::Frame,
+        permutation::{compress, PermutationArgument},
+        trace::TraceTable,
+    },
+    prover::ProvingError,
+    IsChallengeField,
+};
+
+/// Public cells at consecutive addresses, from `address`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound = "FieldElement<F>: lambdaworks_math::traits::ByteConversion")
+)]
+pub struct MemoryPage<F: IsFFTField> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
+    pub address: FieldElement<F>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub values: Vec<FieldElement<F>>,
+}
+
+impl<F: IsFFTField> MemoryPage<F> {
+    pub fn new(address: FieldElement<F>, values: Vec<FieldElement<F>>) -> Self {
+        Self { address, values }
+    }
+
+    /// The cells of the page, as `(address, value)` pairs.
+    pub fn cells(&self) -> impl Iterator<Item = (FieldElement<F>, &FieldElement<F>)> {
+        let address = self.address.clone();
+        self.values
+            .iter()
+            .enumerate()
+            .map(move |(offset, value)| (&address + FieldElement::from(offset as u64), value))
+    }
+}
+
+/// Number of public cells of `pages`.
+pub fn public_cells<F: IsFFTField>(pages: &[MemoryPage<F>]) -> usize {
+    pages.iter().map(|page| page.values.len()).sum()
+}
+
+/// The last value of the cumulative product of a `PublicMemory` with the
+/// public cells of `pages`: `z^|M| / Π (z - (a + α v))`.
+pub fn public_product<F: IsFFTField>(
+    pages: &[MemoryPage<F>],
+    z: &FieldElement<F>,
+    alpha: &FieldElement<F>,
+) -> FieldElement<F> {
+    let denominator = pages
+        .iter()
+        .flat_map(MemoryPage::cells)
+        .fold(FieldElement::one(), |acc, (address, value)| {
+            acc * (z - (address + alpha * value))
+        });
+    z.pow(public_cells(pages)) * denominator.inv()
+}
+
+/// Public memory of the read-only accesses in the columns `address` and
+/// `value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicMemory {
+    pub trace_length: usize,
+    /// Sorted accesses on the right, with the public cells instead of the
+    /// dummies, and the cumulative product of the quotients.
+    pub permutation: PermutationArgument,
+}
+
+impl PublicMemory {
+    /// Public memory of the accesses in the main columns `address` and `value`
+    /// of a trace of `trace_length` rows. The sorted accesses are the main
+    /// columns `first_main_column` and the next one, and the product is the
+    /// trace column `product_column`.
+    pub fn new(
+        address: usize,
+        value: usize,
+        trace_length: usize,
+        first_main_column: usize,
+        product_column: usize,
+    ) -> Self {
+        let permutation = PermutationArgument {
+            left: vec![address, value],
+            right: vec![first_main_column, first_main_column + 1],
+            product: product_column,
+        };
+        Self {
+            trace_length,
+            permutation,
+        }
+    }
+
+    /// Number of main trace columns the gadget adds.
+    pub fn num_main_columns(&self) -> usize {
+        2
+    }
+
+    /// Number of auxiliary columns the gadget adds.
+    pub fn num_auxiliary_columns(&self) -> usize {
+        1
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        vec![2, 2]
+    }
+
+    /// Exemptions of the transition constraints of the gadget. The product
+    /// doesn't wrap around from the last row to the first.
+    pub fn transition_exemptions(&self) -> Vec<usize> {
+        vec![0, 1]
+    }
+
+    /// Rows of the transition constraints of the gadget, for
+    /// `AIR::transition_row_subsets`: the first constraint only applies to
+    /// the first row.
+    pub fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        vec![RowSubset::new(self.trace_length, 0), RowSubset::ALL]
+    }
+
+    /// Builds the sorted columns of the gadget from the columns of the
+    /// accesses, replacing the dummy accesses by the cells of `pages`. Fails
+    /// if there are fewer dummies than public cells.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        address: &[FieldElement<F>],
+        value: &[FieldElement<F>],
+        pages: &[MemoryPage<F>],
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let mut dummies = public_cells(pages);
+        let mut accesses = Vec::with_capacity(address.len());
+        for (address, value) in address.iter().zip(value) {
+            if dummies > 0 && address == &FieldElement::zero() && value == &FieldElement::zero() {
+                dummies -= 1;
+            } else {
+                accesses.push((address.clone(), value.clone()));
+            }
+        }
+        if dummies > 0 {
+            return Err(ProvingError::WrongParameter(format!(
+                "the trace is missing {dummies} dummy accesses for the public memory"
+            )));
+        }
+        accesses.extend(
+            pages
+                .iter()
+                .flat_map(MemoryPage::cells)
+                .map(|(address, value)| (address, value.clone())),
+        );
+
+        let mut keys = Vec::with_capacity(accesses.len());
+        for (index, (address, _)) in accesses.iter().enumerate() {
+            let key = to_u64(address).ok_or_else(|| {
+                ProvingError::WrongParameter(format!(
+                    "the memory address {address:?} doesn't fit in 64 bits"
+                ))
+            })?;
+            keys.push((key, index));
+        }
+        keys.sort_unstable();
+
+        let (sorted_address, sorted_value): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|(_, index)| accesses[*index].clone())
+            .unzip();
+        Ok(vec![sorted_address, sorted_value])
+    }
+
+    /// Builds the cumulative product column from the main trace.
+    pub fn build_auxiliary_columns<F: IsFFTField>(
+        &self,
+        main_trace: &TraceTable<F>,
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> TraceTable<F> {
+        let n_rows = main_trace.n_rows();
+        let mut denominators: Vec<_> = (0..n_rows)
+            .map(|step| z - compress(main_trace.get_row(step), &self.permutation.right, alpha))
+            .collect();
+        FieldElement::inplace_batch_inverse(&mut denominators);
+
+        let mut product = Vec::with_capacity(n_rows);
+        let mut acc = FieldElement::one();
+        for (step, denominator) in denominators.iter().enumerate() {
+            let numerator = z - compress(main_trace.get_row(step), &self.permutation.left, alpha);
+            acc = acc * numerator * denominator;
+            product.push(acc.clone());
+        }
+        TraceTable::new_from_cols(&[product])
+    }
+
+    /// Evaluates the transition constraints of the gadget: the first value of
+    /// the product, `p₀ (z - r₀) - (z - l₀)`, and the accumulation,
+    /// `pᵢ₊₁ (z - rᵢ₊₁) - pᵢ (z - lᵢ₊₁)`.
+    pub fn evaluate_transitions<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<E>> {
+        let (z, alpha) = (E::embed(z), E::embed(alpha));
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let left = |row: &[FieldElement<E>]| &z - compress(row, &self.permutation.left, &alpha);
+        let right = |row: &[FieldElement<E>]| &z - compress(row, &self.permutation.right, &alpha);
+        let product = self.permutation.product;
+
+        vec![
+            &current[product] * right(current) - left(current),
+            &next[product] * right(next) - &current[product] * left(next),
+        ]
+    }
+
+    /// The last value of the product is the one of the public cells of
+    /// `pages`.
+    pub fn boundary_constraint<F: IsFFTField>(
+        &self,
+        pages: &[MemoryPage<F>],
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> BoundaryConstraint<F> {
+        BoundaryConstraint::new_last_row(
+            self.permutation.product,
+            self.trace_length,
+            public_product(pages, z, alpha),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn column(values: &[u64]) -> Vec<FE> {
+        values.iter().map(|value| FE::from(*value)).collect()
+    }
+
+    fn pages() -> Vec<MemoryPage<F>> {
+        vec![
+            MemoryPage::new(FE::from(1), column(&[10, 11, 12])),
+            MemoryPage::new(FE::from(6), column(&[13])),
+        ]
+    }
+
+    /// Accesses with four dummies, reading the cells of `pages` and others.
+    fn accesses() -> [Vec<FE>; 2] {
+        [
+            column(&[4, 0, 2, 0, 5, 0, 1, 0]),
+            column(&[20, 0, 11, 0, 21, 0, 10, 0]),
+        ]
+    }
+
+    fn trace(memory: &PublicMemory, pages: &[MemoryPage<F>], z: &FE, alpha: &FE) -> TraceTable<F> {
+        let [address, value] = accesses();
+        let mut columns = vec![address.clone(), value.clone()];
+        columns.extend(memory.build_main_columns(&address, &value, pages).unwrap());
+        let main_trace = TraceTable::new_from_cols(&columns);
+        let auxiliary = memory.build_auxiliary_columns(&main_trace, z, alpha);
+        main_trace.concatenate(auxiliary.table, auxiliary.n_cols)
+    }
+
+    fn transitions_vanish(
+        memory: &PublicMemory,
+        trace: &TraceTable<F>,
+        z: &FE,
+        alpha: &FE,
+    ) -> bool {
+        let exemptions = memory.transition_exemptions();
+        let subsets = memory.transition_row_subsets();
+        (0..trace.n_rows()).all(|step| {
+            let frame = Frame::read_from_trace(trace, step, 1, &[0, 1]);
+            memory
+                .evaluate_transitions::<F, F>(&frame, z, alpha)
+                .iter()
+                .zip(exemptions.iter().zip(&subsets))
+                .all(|(evaluation, (exemption, subset))| {
+                    evaluation == &FE::zero()
+                        || !subset.contains(step)
+                        || step + exemption >= trace.n_rows()
+                })
+        })
+    }
+
+    #[test]
+    fn public_cells_replace_the_dummies_in_the_sorted_accesses() {
+        let memory = PublicMemory::new(0, 1, 8, 2, 4);
+        let [address, value] = accesses();
+        let columns = memory
+            .build_main_columns(&address, &value, &pages())
+            .unwrap();
+        assert_eq!(columns[0], column(&[1, 1, 2, 2, 3, 4, 5, 6]));
+        assert_eq!(columns[1], column(&[10, 10, 11, 11, 12, 20, 21, 13]));
+
+        let too_many = [MemoryPage::new(FE::from(7), column(&[1, 2, 3, 4, 5]))];
+        assert!(memory
+            .build_main_columns(&address, &value, &too_many)
+            .is_err());
+    }
+
+    #[test]
+    fn the_product_ends_at_the_product_of_the_public_cells() {
+        let memory = PublicMemory::new(0, 1, 8, 2, 4);
+        let (z, alpha) = (FE::from(1000), FE::from(17));
+        let trace = trace(&memory, &pages(), &z, &alpha);
+
+        assert!(transitions_vanish(&memory, &trace, &z, &alpha));
+        let boundary = memory.boundary_constraint(&pages(), &z, &alpha);
+        assert_eq!(trace.get(boundary.step, boundary.col), boundary.value);
+    }
+
+    #[test]
+    fn a_wrong_public_value_breaks_the_boundary_constraint() {
+        let memory = PublicMemory::new(0, 1, 8, 2, 4);
+        let (z, alpha) = (FE::from(1000), FE::from(17));
+        let trace = trace(&memory, &pages(), &z, &alpha);
+
+        let mut claimed = pages();
+        claimed[1].values[0] = FE::from(14);
+        let boundary = memory.boundary_constraint(&claimed, &z, &alpha);
+        assert_ne!(trace.get(boundary.step, boundary.col), boundary.value);
+    }
+}
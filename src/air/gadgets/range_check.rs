@@ -0,0 +1,342 @@
+//! Range checks of main trace columns.
+//!
+//! A `RangeCheck` proves that every value of a column is below 2ᵇ with the
+//! lookups of `air::lookup`, in a periodic column with the table
+//! `0, 1, ..., 2ˡ - 1`. The table can't be longer than the trace, so if `b` is
+//! above `log₂ n`, the values are split in limbs of `l = log₂ n` bits:
+//!
+//! ```text
+//! v = Σᵢ limbᵢ · 2^(l·i)
+//! ```
+//!
+//! and every limb is looked up instead. A last limb of `b' < l` bits is also
+//! looked up multiplied by 2^(l - b'), which is below 2ˡ only if the limb is
+//! below 2^b'.
+//!
+//! The gadget uses main columns for the limbs, the shifted last limb and the
+//! multiplicities of each lookup, in this order, one periodic column and an
+//! auxiliary running sum column per lookup. The AIR places them, and
+//! forwards to the gadget in `lookups`, `periodic_columns`,
+//! `build_main_trace`, `build_auxiliary_trace`, `compute_transition` and
+//! `boundary_constraints`. Frames need the rows at offsets 0 and 1.
+
+use alloc::{format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::to_u64;
+use crate::{
+    air::{
+        constraints::boundary::BoundaryConstraint,
+        frame::Frame,
+        lookup::{self, Lookup, TableColumn},
+    },
+    prover::ProvingError,
+    IsChallengeField,
+};
+
+/// Range check of a main trace column to `bits` bits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeCheck {
+    /// Main trace column with the checked values.
+    pub column: usize,
+    pub bits: usize,
+    /// Bits of each limb, and of the values of the table.
+    pub limb_bits: usize,
+    /// First main trace column of the gadget.
+    pub first_main_column: usize,
+    /// Index of the table among the periodic columns of the AIR.
+    pub periodic_column: usize,
+    /// Column of the first running sum, as an index into the whole trace.
+    pub first_sum_column: usize,
+}
+
+impl RangeCheck {
+    /// Range check of `column` to `bits` bits, between 1 and 64, in a trace of
+    /// `trace_length` rows. Its main columns start at `first_main_column`, its
+    /// table is the periodic column `periodic_column` and its running sums
+    /// start at the trace column `first_sum_column`.
+    pub fn new(
+        column: usize,
+        bits: usize,
+        trace_length: usize,
+        first_main_column: usize,
+        periodic_column: usize,
+        first_sum_column: usize,
+    ) -> Result<Self, ProvingError> {
+        if !(1..=64).contains(&bits) {
+            return Err(ProvingError::WrongParameter(format!(
+                "a range check of {bits} bits isn't between 1 and 64 bits"
+            )));
+        }
+        if trace_length < 2 || !trace_length.is_power_of_two() {
+            return Err(ProvingError::WrongParameter(format!(
+                "a range check needs a trace length that is a power of two, not {trace_length}"
+            )));
+        }
+        Ok(Self {
+            column,
+            bits,
+            limb_bits: bits.min(trace_length.trailing_zeros() as usize),
+            first_main_column,
+            periodic_column,
+            first_sum_column,
+        })
+    }
+
+    /// Number of limbs the values are split in. With a single one, the values
+    /// are looked up directly.
+    pub fn limbs(&self) -> usize {
+        (self.bits + self.limb_bits - 1) / self.limb_bits
+    }
+
+    /// Power of two the last limb is multiplied by to be looked up, if it has
+    /// fewer bits than the others. There is no shift with a single limb.
+    fn top_limb_shift(&self) -> Option<usize> {
+        let top_limb_bits = self.bits - (self.limbs() - 1) * self.limb_bits;
+        (top_limb_bits < self.limb_bits).then_some(self.limb_bits - top_limb_bits)
+    }
+
+    fn limb_columns(&self) -> usize {
+        if self.limbs() > 1 {
+            self.limbs()
+        } else {
+            0
+        }
+    }
+
+    fn shifted_column(&self) -> usize {
+        self.first_main_column + self.limb_columns()
+    }
+
+    /// Main trace columns with the looked up values.
+    fn looked_up_columns(&self) -> Vec<usize> {
+        if self.limbs() == 1 {
+            return vec![self.column];
+        }
+        let mut columns: Vec<_> =
+            (self.first_main_column..self.first_main_column + self.limbs()).collect();
+        if self.top_limb_shift().is_some() {
+            columns.push(self.shifted_column());
+        }
+        columns
+    }
+
+    /// Number of main trace columns the gadget adds.
+    pub fn num_main_columns(&self) -> usize {
+        let shifted_columns = usize::from(self.top_limb_shift().is_some());
+        self.limb_columns() + shifted_columns + self.num_lookups()
+    }
+
+    /// Number of lookups, and of auxiliary columns the gadget adds.
+    pub fn num_lookups(&self) -> usize {
+        self.looked_up_columns().len()
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`. They have no exemptions.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        let mut degrees = Vec::new();
+        if self.limbs() > 1 {
+            degrees.push(1);
+        }
+        if self.top_limb_shift().is_some() {
+            degrees.push(1);
+        }
+        degrees.extend(self.lookups().iter().map(|_| Lookup::TRANSITION_DEGREE));
+        degrees
+    }
+
+    /// The table `0, 1, ..., 2ˡ - 1`, for `AIR::periodic_columns`.
+    pub fn table<F: IsFFTField>(&self) -> Vec<FieldElement<F>> {
+        (0..1u64 << self.limb_bits)
+            .map(FieldElement::from)
+            .collect()
+    }
+
+    /// The lookups of the gadget, for `AIR::lookups`.
+    pub fn lookups(&self) -> Vec<Lookup> {
+        let first_multiplicities =
+            self.shifted_column() + usize::from(self.top_limb_shift().is_some());
+        self.looked_up_columns()
+            .into_iter()
+            .enumerate()
+            .map(|(index, values)| Lookup {
+                values,
+                table: TableColumn::Periodic(self.periodic_column),
+                multiplicities: first_multiplicities + index,
+                sum: self.first_sum_column + index,
+            })
+            .collect()
+    }
+
+    /// Builds the main columns of the gadget from the checked `values`.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        values: &[FieldElement<F>],
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let mut limbs = vec![Vec::new(); self.limb_columns()];
+        let mut shifted = Vec::new();
+        for (row, value) in values.iter().enumerate() {
+            let value = to_u64(value)
+                .filter(|value| self.bits == 64 || *value >> self.bits == 0)
+                .ok_or_else(|| {
+                    ProvingError::WrongParameter(format!(
+                        "the value of column {} at row {row} isn't below 2^{}",
+                        self.column, self.bits
+                    ))
+                })?;
+            for (index, limb) in limbs.iter_mut().enumerate() {
+                let limb_value = (value >> (index * self.limb_bits)) & ((1 << self.limb_bits) - 1);
+                limb.push(FieldElement::from(limb_value));
+            }
+            if let (Some(shift), Some(top_limb)) = (self.top_limb_shift(), limbs.last()) {
+                shifted.push(&top_limb[row] * FieldElement::from(1u64 << shift));
+            }
+        }
+
+        let mut columns = limbs;
+        if self.top_limb_shift().is_some() {
+            columns.push(shifted);
+        }
+        let table = self.table();
+        let looked_up: Vec<_> = if self.limbs() == 1 {
+            vec![values.to_vec()]
+        } else {
+            columns.clone()
+        };
+        for values in &looked_up {
+            let mut multiplicities =
+                lookup::multiplicities(values, &table).expect("the values are in the table");
+            multiplicities.resize(values.len(), FieldElement::zero());
+            columns.push(multiplicities);
+        }
+        Ok(columns)
+    }
+
+    /// Evaluates the transition constraints of the gadget: the decomposition
+    /// in limbs, the shift of the last limb and the lookups, in this order.
+    pub fn evaluate_transitions<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<E>> {
+        let row = frame.get_row(0);
+        let mut evaluations = Vec::new();
+        if self.limbs() > 1 {
+            let limbs = &row[self.first_main_column..self.first_main_column + self.limbs()];
+            let base = FieldElement::<E>::from(1u64 << self.limb_bits);
+            let recomposed = limbs
+                .iter()
+                .rev()
+                .fold(FieldElement::zero(), |acc, limb| acc * &base + limb);
+            evaluations.push(&row[self.column] - recomposed);
+
+            if let Some(shift) = self.top_limb_shift() {
+                let top_limb = &limbs[self.limbs() - 1];
+                evaluations.push(
+                    &row[self.shifted_column()] - top_limb * FieldElement::from(1u64 << shift),
+                );
+            }
+        }
+        evaluations.extend(lookup::evaluate_transitions(&self.lookups(), frame, alpha));
+        evaluations
+    }
+
+    /// The boundary constraints of the running sums.
+    pub fn boundary_constraints<E: IsField>(&self) -> Vec<BoundaryConstraint<E>> {
+        lookup::boundary_constraints(&self.lookups())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::{periodic, trace::TraceTable};
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    /// Trace with the checked column, the main columns of `check` and its
+    /// running sums.
+    fn trace(check: &RangeCheck, values: &[FE], alpha: &FE) -> TraceTable<F> {
+        let mut columns = vec![values.to_vec()];
+        columns.extend(check.build_main_columns(values).unwrap());
+        let main_trace = TraceTable::new_from_cols(&columns);
+        let sums =
+            lookup::build_sum_columns(&check.lookups(), &main_trace, &[check.table()], alpha);
+        main_trace.concatenate(sums.table, sums.n_cols)
+    }
+
+    fn transitions_vanish(check: &RangeCheck, trace: &TraceTable<F>, alpha: &FE) -> bool {
+        (0..trace.n_rows()).all(|step| {
+            let frame = Frame::read_from_trace(trace, step, 1, &[0, 1])
+                .with_periodic_values(periodic::values_at_row::<F, F>(&[check.table()], step));
+            check
+                .evaluate_transitions::<F, F>(&frame, alpha)
+                .iter()
+                .all(|evaluation| evaluation == &FE::zero())
+        })
+    }
+
+    #[test]
+    fn values_that_fit_the_table_are_looked_up_directly() {
+        let check = RangeCheck::new(0, 3, 16, 1, 0, 2).unwrap();
+        assert_eq!(check.limbs(), 1);
+        assert_eq!(check.num_main_columns(), 1);
+        assert_eq!(check.transition_degrees(), vec![3]);
+
+        let values: Vec<_> = (0..16).map(|row| FE::from(row % 8)).collect();
+        let alpha = FE::from(1234);
+        let trace = trace(&check, &values, &alpha);
+        assert_eq!(trace.n_cols, 3);
+        assert!(transitions_vanish(&check, &trace, &alpha));
+    }
+
+    #[test]
+    fn wide_values_are_split_in_limbs_with_a_shifted_last_limb() {
+        // 10 bits in 16 rows are two limbs of 4 bits and one of 2 bits.
+        let check = RangeCheck::new(0, 10, 16, 1, 0, 9).unwrap();
+        assert_eq!(check.limbs(), 3);
+        assert_eq!(check.num_main_columns(), 8);
+        assert_eq!(check.num_lookups(), 4);
+        assert_eq!(check.transition_degrees(), vec![1, 1, 3, 3, 3, 3]);
+
+        let values: Vec<_> = (0..16).map(|row| FE::from(row * 63)).collect();
+        let alpha = FE::from(1234);
+        let trace = trace(&check, &values, &alpha);
+        assert_eq!(trace.n_cols, 1 + 8 + 4);
+        assert!(transitions_vanish(&check, &trace, &alpha));
+
+        // 1213 = 13 + 11·2⁴ + 4·2⁸ has limbs of 4 bits, but its shifted last
+        // limb 16 isn't in the table.
+        let mut columns = trace.cols();
+        columns[0][3] = FE::from(1213);
+        columns[3][3] = FE::from(4);
+        columns[4][3] = FE::from(16);
+        let forged = TraceTable::new_from_cols(&columns);
+        assert!(!transitions_vanish(&check, &forged, &alpha));
+    }
+
+    #[test]
+    fn values_out_of_range_and_wrong_parameters_are_rejected() {
+        let check = RangeCheck::new(0, 10, 16, 1, 0, 9).unwrap();
+        assert!(check.build_main_columns(&[FE::from(1024)]).is_err());
+        assert!(check.build_main_columns(&[-FE::one()]).is_err());
+
+        assert!(RangeCheck::new(0, 0, 16, 1, 0, 2).is_err());
+        assert!(RangeCheck::new(0, 65, 16, 1, 0, 2).is_err());
+        assert!(RangeCheck::new(0, 8, 12, 1, 0, 2).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Conditional transition constraints.
+//!
+//! A selector is a column, or an expression of columns, whose values are zero
+//! or one. A constraint `c` that only has to hold where the selector `s` is
+//! one is the constraint `s · c`, and where it is zero, `(1 - s) · c`. Gating
+//! raises the degree of the constraint by that of the selector, and the
+//! selector needs its own `is_boolean` constraint unless it comes from a
+//! gadget that already makes it boolean, like `IsZero`.
+
+use lambdaworks_math::field::{element::FieldElement, traits::IsField};
+
+/// `s · (s - 1)`, which is zero only if the selector is zero or one.
+pub fn is_boolean<F: IsField>(selector: &FieldElement<F>) -> FieldElement<F> {
+    selector * (selector - FieldElement::one())
+}
+
+/// The constraint `constraint`, gated to hold where `selector` is one.
+pub fn when<F: IsField>(
+    selector: &FieldElement<F>,
+    constraint: FieldElement<F>,
+) -> FieldElement<F> {
+    selector * constraint
+}
+
+/// The constraint `constraint`, gated to hold where `selector` is zero.
+pub fn when_not<F: IsField>(
+    selector: &FieldElement<F>,
+    constraint: FieldElement<F>,
+) -> FieldElement<F> {
+    (FieldElement::one() - selector) * constraint
+}
+
+/// `a` where `selector` is one and `b` where it is zero.
+pub fn select<F: IsField>(
+    selector: &FieldElement<F>,
+    a: &FieldElement<F>,
+    b: &FieldElement<F>,
+) -> FieldElement<F> {
+    b + selector * (a - b)
+}
+
+/// Degree of a constraint of degree `constraint_degree` gated by a selector
+/// of degree `selector_degree`.
+pub fn gated_degree(selector_degree: usize, constraint_degree: usize) -> usize {
+    selector_degree + constraint_degree
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn gated_constraints_only_hold_where_the_selector_says() {
+        let (zero, one) = (FE::zero(), FE::one());
+        let violated = FE::from(5);
+
+        assert_eq!(when(&zero, violated.clone()), zero);
+        assert_ne!(when(&one, violated.clone()), zero);
+        assert_eq!(when_not(&one, violated.clone()), zero);
+        assert_ne!(when_not(&zero, violated), zero);
+
+        let (a, b) = (FE::from(3), FE::from(4));
+        assert_eq!(select(&one, &a, &b), a);
+        assert_eq!(select(&zero, &a, &b), b);
+
+        assert_eq!(is_boolean(&zero), zero);
+        assert_eq!(is_boolean(&one), zero);
+        assert_ne!(is_boolean(&FE::from(2)), zero);
+        assert_eq!(gated_degree(1, 2), 3);
+    }
+}
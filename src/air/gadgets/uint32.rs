@@ -0,0 +1,355 @@
+//! Arithmetic on 32 bit words.
+//!
+//! A `U32Gadget` computes an operation on two main trace columns `a` and `b`
+//! of words, with two output columns:
+//!
+//! ```text
+//! Add       result, carry   a + b = result + carry · 2³²
+//! Mul       lo, hi          a · b = lo + hi · 2³²
+//! LessThan  lt, difference  a - b + lt · 2³² = difference
+//! ```
+//!
+//! The carry and `lt` are boolean, and the outputs that are words are range
+//! checked with a `RangeCheck` gadget each, so that the equations have a
+//! single solution. The inputs have to be words too, range checked by the
+//! AIR or outputs of other operations. Over the Stark252 field none of the
+//! equations can wrap around the modulus.
+//!
+//! The outputs come first in the main columns of the gadget, followed by
+//! those of its range checks. All of them share one periodic table, and
+//! their running sums are consecutive auxiliary columns.
+
+use alloc::{format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{range_check::RangeCheck, selector::is_boolean, to_u64};
+use crate::{
+    air::{constraints::boundary::BoundaryConstraint, frame::Frame, lookup::Lookup},
+    prover::ProvingError,
+    IsChallengeField,
+};
+
+const WORD_BITS: usize = 32;
+
+/// Operation of a `U32Gadget`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum U32Operation {
+    /// Outputs the sum modulo 2³² and the carry.
+    Add,
+    /// Outputs the low and high words of the product.
+    Mul,
+    /// Outputs whether `a < b`, and `a - b` modulo 2³².
+    LessThan,
+}
+
+impl U32Operation {
+    /// Outputs that are words, which are range checked.
+    fn word_outputs(&self) -> &'static [usize] {
+        match self {
+            Self::Add => &[0],
+            Self::Mul => &[0, 1],
+            Self::LessThan => &[1],
+        }
+    }
+
+    /// Degrees of the constraints of the operation, without the range checks.
+    fn degrees(&self) -> &'static [usize] {
+        match self {
+            Self::Add | Self::LessThan => &[1, 2],
+            Self::Mul => &[2],
+        }
+    }
+}
+
+/// Operation on the words of two main trace columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct U32Gadget {
+    pub operation: U32Operation,
+    pub a: usize,
+    pub b: usize,
+    /// Main trace column of the first output. The second one follows it.
+    pub first_main_column: usize,
+    /// Range checks of the outputs that are words.
+    pub range_checks: Vec<RangeCheck>,
+}
+
+impl U32Gadget {
+    /// `operation` on the columns `a` and `b` of a trace of `trace_length`
+    /// rows. The main columns of the gadget start at `first_main_column`, its
+    /// table is the periodic column `periodic_column` and its running sums
+    /// start at the trace column `first_sum_column`.
+    pub fn new(
+        operation: U32Operation,
+        a: usize,
+        b: usize,
+        trace_length: usize,
+        first_main_column: usize,
+        periodic_column: usize,
+        first_sum_column: usize,
+    ) -> Result<Self, ProvingError> {
+        let mut next_main_column = first_main_column + 2;
+        let mut next_sum_column = first_sum_column;
+        let mut range_checks = Vec::new();
+        for output in operation.word_outputs() {
+            let range_check = RangeCheck::new(
+                first_main_column + output,
+                WORD_BITS,
+                trace_length,
+                next_main_column,
+                periodic_column,
+                next_sum_column,
+            )?;
+            next_main_column += range_check.num_main_columns();
+            next_sum_column += range_check.num_lookups();
+            range_checks.push(range_check);
+        }
+        Ok(Self {
+            operation,
+            a,
+            b,
+            first_main_column,
+            range_checks,
+        })
+    }
+
+    /// Main trace column of the output `index`, 0 or 1.
+    pub fn output(&self, index: usize) -> usize {
+        self.first_main_column + index
+    }
+
+    /// Number of main trace columns the gadget adds.
+    pub fn num_main_columns(&self) -> usize {
+        2 + self
+            .range_checks
+            .iter()
+            .map(RangeCheck::num_main_columns)
+            .sum::<usize>()
+    }
+
+    /// Number of auxiliary columns the gadget adds.
+    pub fn num_auxiliary_columns(&self) -> usize {
+        self.range_checks.iter().map(RangeCheck::num_lookups).sum()
+    }
+
+    /// Degrees of the transition constraints of the gadget, in the order of
+    /// `evaluate_transitions`. They have no exemptions.
+    pub fn transition_degrees(&self) -> Vec<usize> {
+        let mut degrees = self.operation.degrees().to_vec();
+        for range_check in &self.range_checks {
+            degrees.extend(range_check.transition_degrees());
+        }
+        degrees
+    }
+
+    /// The table of the range checks, for `AIR::periodic_columns`.
+    pub fn table<F: IsFFTField>(&self) -> Vec<FieldElement<F>> {
+        self.range_checks[0].table()
+    }
+
+    /// The lookups of the range checks, for `AIR::lookups`.
+    pub fn lookups(&self) -> Vec<Lookup> {
+        self.range_checks
+            .iter()
+            .flat_map(RangeCheck::lookups)
+            .collect()
+    }
+
+    /// Builds the main columns of the gadget from the words of `a` and `b`.
+    pub fn build_main_columns<F: IsFFTField>(
+        &self,
+        a: &[FieldElement<F>],
+        b: &[FieldElement<F>],
+    ) -> Result<Vec<Vec<FieldElement<F>>>, ProvingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let word = |value: &FieldElement<F>, column: usize, row: usize| {
+            to_u64(value)
+                .filter(|value| *value >> WORD_BITS == 0)
+                .ok_or_else(|| {
+                    ProvingError::WrongParameter(format!(
+                        "the value of column {column} at row {row} isn't a 32 bit word"
+                    ))
+                })
+        };
+        let mask = (1u64 << WORD_BITS) - 1;
+
+        let mut outputs = vec![Vec::with_capacity(a.len()), Vec::with_capacity(a.len())];
+        for (row, (a_value, b_value)) in a.iter().zip(b).enumerate() {
+            let (a_value, b_value) = (word(a_value, self.a, row)?, word(b_value, self.b, row)?);
+            let (first, second) = match self.operation {
+                U32Operation::Add => {
+                    let sum = a_value + b_value;
+                    (sum & mask, sum >> WORD_BITS)
+                }
+                U32Operation::Mul => {
+                    let product = a_value * b_value;
+                    (product & mask, product >> WORD_BITS)
+                }
+                U32Operation::LessThan => {
+                    let lt = u64::from(a_value < b_value);
+                    (lt, (a_value + (lt << WORD_BITS)) - b_value)
+                }
+            };
+            outputs[0].push(FieldElement::from(first));
+            outputs[1].push(FieldElement::from(second));
+        }
+
+        let mut columns = outputs;
+        for range_check in &self.range_checks {
+            let output = range_check.column - self.first_main_column;
+            let checked = columns[output].clone();
+            columns.extend(range_check.build_main_columns(&checked)?);
+        }
+        Ok(columns)
+    }
+
+    /// Evaluates the transition constraints of the gadget: those of the
+    /// operation, then those of the range checks.
+    pub fn evaluate_transitions<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<E>> {
+        let row = frame.get_row(0);
+        let (a, b) = (&row[self.a], &row[self.b]);
+        let (first, second) = (&row[self.output(0)], &row[self.output(1)]);
+        let base = FieldElement::<E>::from(1u64 << WORD_BITS);
+
+        let mut evaluations = match self.operation {
+            U32Operation::Add => vec![a + b - first - second * &base, is_boolean(second)],
+            U32Operation::Mul => vec![a * b - first - second * &base],
+            U32Operation::LessThan => vec![second - a + b - first * &base, is_boolean(first)],
+        };
+        for range_check in &self.range_checks {
+            evaluations.extend(range_check.evaluate_transitions(frame, alpha));
+        }
+        evaluations
+    }
+
+    /// The boundary constraints of the running sums.
+    pub fn boundary_constraints<E: IsField>(&self) -> Vec<BoundaryConstraint<E>> {
+        self.range_checks
+            .iter()
+            .flat_map(RangeCheck::boundary_constraints)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::air::{lookup, periodic, trace::TraceTable};
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    const ROWS: usize = 16;
+
+    fn gadget(operation: U32Operation) -> U32Gadget {
+        let gadget = U32Gadget::new(operation, 0, 1, ROWS, 2, 0, 0).unwrap();
+        let first_sum_column = 2 + gadget.num_main_columns();
+        U32Gadget::new(operation, 0, 1, ROWS, 2, 0, first_sum_column).unwrap()
+    }
+
+    fn words() -> (Vec<FE>, Vec<FE>) {
+        let a = (0..ROWS as u64).map(|row| FE::from(row * 0x1234_5679 % (1 << 32)));
+        let b = (0..ROWS as u64).map(|row| FE::from(0xffff_fff0 - row * 0x0f0f_0f0f));
+        (a.collect(), b.collect())
+    }
+
+    fn trace(gadget: &U32Gadget, a: &[FE], b: &[FE], alpha: &FE) -> TraceTable<F> {
+        let mut columns = vec![a.to_vec(), b.to_vec()];
+        columns.extend(gadget.build_main_columns(a, b).unwrap());
+        let main_trace = TraceTable::new_from_cols(&columns);
+        let sums =
+            lookup::build_sum_columns(&gadget.lookups(), &main_trace, &[gadget.table()], alpha);
+        main_trace.concatenate(sums.table, sums.n_cols)
+    }
+
+    fn transitions_vanish(gadget: &U32Gadget, trace: &TraceTable<F>, alpha: &FE) -> bool {
+        (0..trace.n_rows()).all(|step| {
+            let frame = Frame::read_from_trace(trace, step, 1, &[0, 1])
+                .with_periodic_values(periodic::values_at_row::<F, F>(&[gadget.table()], step));
+            gadget
+                .evaluate_transitions::<F, F>(&frame, alpha)
+                .iter()
+                .all(|evaluation| evaluation == &FE::zero())
+        })
+    }
+
+    #[test]
+    fn operations_satisfy_their_constraints() {
+        let (a, b) = words();
+        let alpha = FE::from(1234);
+        for operation in [U32Operation::Add, U32Operation::Mul, U32Operation::LessThan] {
+            let gadget = gadget(operation);
+            let trace = trace(&gadget, &a, &b, &alpha);
+            assert_eq!(
+                trace.n_cols,
+                2 + gadget.num_main_columns() + gadget.num_auxiliary_columns()
+            );
+            let frame = Frame::read_from_trace(&trace, 0, 1, &[0, 1])
+                .with_periodic_values(periodic::values_at_row::<F, F>(&[gadget.table()], 0));
+            assert_eq!(
+                gadget.transition_degrees().len(),
+                gadget.evaluate_transitions::<F, F>(&frame, &alpha).len()
+            );
+            assert!(transitions_vanish(&gadget, &trace, &alpha));
+        }
+    }
+
+    #[test]
+    fn outputs_are_the_results_of_the_operations() {
+        let a = vec![FE::from(0xffff_ffff), FE::from(3)];
+        let b = vec![FE::from(2), FE::from(5)];
+
+        let add = gadget(U32Operation::Add)
+            .build_main_columns(&a, &b)
+            .unwrap();
+        assert_eq!(add[0], vec![FE::from(1), FE::from(8)]);
+        assert_eq!(add[1], vec![FE::one(), FE::zero()]);
+
+        let mul = gadget(U32Operation::Mul)
+            .build_main_columns(&a, &b)
+            .unwrap();
+        assert_eq!(mul[0], vec![FE::from(0xffff_fffe), FE::from(15)]);
+        assert_eq!(mul[1], vec![FE::one(), FE::zero()]);
+
+        let lt = gadget(U32Operation::LessThan)
+            .build_main_columns(&a, &b)
+            .unwrap();
+        assert_eq!(lt[0], vec![FE::zero(), FE::one()]);
+        assert_eq!(lt[1], vec![FE::from(0xffff_fffd), FE::from(0xffff_fffe)]);
+    }
+
+    #[test]
+    fn wrong_outputs_and_inputs_are_rejected() {
+        let (a, b) = words();
+        let alpha = FE::from(1234);
+        let gadget = gadget(U32Operation::Add);
+        let trace = trace(&gadget, &a, &b, &alpha);
+
+        // A result off by 2³² with no carry satisfies the sum, but not the
+        // range check.
+        let mut columns = trace.cols();
+        let carry = columns[3][5].clone();
+        columns[2][5] = &columns[2][5] + carry * FE::from(1u64 << 32);
+        columns[3][5] = FE::zero();
+        let forged = TraceTable::new_from_cols(&columns);
+        assert!(!transitions_vanish(&gadget, &forged, &alpha));
+
+        assert!(gadget
+            .build_main_columns(&[FE::from(1u64 << 32)], &[FE::zero()])
+            .is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! LogUp lookup arguments.
+//!
+//! A lookup proves that every value of a column appears in a table column,
+//! by checking with a random challenge α that
+//!
+//! ```text
+//! Σᵢ 1 / (α - vᵢ) = Σᵢ mᵢ / (α - tᵢ)
+//! ```
+//!
+//! where `mᵢ` is the number of times `tᵢ` is looked up. An auxiliary column
+//! `s` accumulates the difference of both sums row by row, starting at zero:
+//! `sᵢ₊₁ = sᵢ + 1 / (α - vᵢ) - mᵢ / (α - tᵢ)`. The transition constraint has
+//! no exemptions, so it also holds from the last row to the first, which is
+//! only possible if the sums are equal.
+//!
+//! AIRs declare their lookups in `AIR::lookups` and build and constrain the
+//! running sum columns with the functions of this module.
+
+use alloc::{collections::BTreeMap, format, vec, vec::Vec};
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{constraints::boundary::BoundaryConstraint, frame::Frame, periodic, trace::TraceTable};
+use crate::{prover::ProvingError, IsChallengeField};
+
+/// Column holding the table of a lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableColumn {
+    /// Column of the main trace with this index.
+    Trace(usize),
+    /// Periodic column of the AIR with this index. Its values are looked up
+    /// in every period, so multiplicities can be set on the first one only.
+    Periodic(usize),
+}
+
+/// Lookup of the values of a main trace column in a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup {
+    /// Main trace column with the looked up values.
+    pub values: usize,
+    pub table: TableColumn,
+    /// Main trace column with the number of times each row of the table is
+    /// looked up.
+    pub multiplicities: usize,
+    /// Column of the running sum, as an index into the whole trace: main
+    /// columns first, then auxiliary ones.
+    pub sum: usize,
+}
+
+impl Lookup {
+    /// Degree of the transition constraint of the lookup.
+    pub const TRANSITION_DEGREE: usize = 3;
+
+    fn table_value<'a, F: IsFFTField>(
+        &self,
+        row: &'a [FieldElement<F>],
+        periodic_values: &'a [FieldElement<F>],
+    ) -> &'a FieldElement<F> {
+        match self.table {
+            TableColumn::Trace(column) => &row[column],
+            TableColumn::Periodic(column) => &periodic_values[column],
+        }
+    }
+
+    /// Builds the running sum column from the main trace. `periodic_columns`
+    /// are the ones returned by `AIR::periodic_columns`.
+    pub fn build_sum_column<F: IsFFTField>(
+        &self,
+        main_trace: &TraceTable<F>,
+        periodic_columns: &[Vec<FieldElement<F>>],
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<F>> {
+        let n_rows = main_trace.n_rows();
+        let mut denominators = Vec::with_capacity(2 * n_rows);
+        for step in 0..n_rows {
+            let row = main_trace.get_row(step);
+            let periodic_values = periodic::values_at_row::<F, F>(periodic_columns, step);
+            denominators.push(alpha - &row[self.values]);
+            denominators.push(alpha - self.table_value(row, &periodic_values));
+        }
+        FieldElement::inplace_batch_inverse(&mut denominators);
+
+        let mut sum = Vec::with_capacity(n_rows);
+        sum.push(FieldElement::zero());
+        for step in 0..n_rows - 1 {
+            let multiplicity = &main_trace.get_row(step)[self.multiplicities];
+            let term = &denominators[2 * step] - multiplicity * &denominators[2 * step + 1];
+            sum.push(&sum[step] + term);
+        }
+        sum
+    }
+
+    /// Evaluates the transition constraint on a frame whose first two rows are
+    /// consecutive:
+    /// `(sᵢ₊₁ - sᵢ)(α - vᵢ)(α - tᵢ) - (α - tᵢ) + mᵢ(α - vᵢ)`.
+    pub fn evaluate_transition<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        alpha: &FieldElement<F>,
+    ) -> FieldElement<E> {
+        let alpha = E::embed(alpha);
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let value = &alpha - &current[self.values];
+        let table = &alpha - self.table_value(current, frame.periodic_values());
+        let multiplicity = &current[self.multiplicities];
+
+        (&next[self.sum] - &current[self.sum]) * &value * &table - table + multiplicity * value
+    }
+
+    /// The running sum starts at zero.
+    pub fn boundary_constraint<E: IsField>(&self) -> BoundaryConstraint<E> {
+        BoundaryConstraint::new(self.sum, 0, FieldElement::zero())
+    }
+}
+
+/// Builds the running sum columns of `lookups`, in order.
+pub fn build_sum_columns<F: IsFFTField>(
+    lookups: &[Lookup],
+    main_trace: &TraceTable<F>,
+    periodic_columns: &[Vec<FieldElement<F>>],
+    alpha: &FieldElement<F>,
+) -> TraceTable<F> {
+    let columns: Vec<_> = lookups
+        .iter()
+        .map(|lookup| lookup.build_sum_column(main_trace, periodic_columns, alpha))
+        .collect();
+    if columns.is_empty() {
+        TraceTable::empty()
+    } else {
+        TraceTable::new_from_cols(&columns)
+    }
+}
+
+/// Counts how many times each value of `table` is looked up by `values`, on
+/// the first row the value appears in the table. Returns `None` if a value
+/// isn't in the table.
+pub fn multiplicities<F: IsFFTField>(
+    values: &[FieldElement<F>],
+    table: &[FieldElement<F>],
+) -> Option<Vec<FieldElement<F>>>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let mut first_rows = BTreeMap::new();
+    for (row, value) in table.iter().enumerate().rev() {
+        first_rows.insert(value.to_bytes_be(), row);
+    }
+
+    let mut counts = vec![0u64; table.len()];
+    for value in values {
+        counts[*first_rows.get(&value.to_bytes_be())?] += 1;
+    }
+    Some(counts.into_iter().map(FieldElement::from).collect())
+}
+
+/// Evaluates the transition constraints of `lookups`, in order. Their
+/// exemptions are 0 and their degrees `Lookup::TRANSITION_DEGREE`.
+pub fn evaluate_transitions<F: IsFFTField, E: IsChallengeField<F>>(
+    lookups: &[Lookup],
+    frame: &Frame<E>,
+    alpha: &FieldElement<F>,
+) -> Vec<FieldElement<E>> {
+    lookups
+        .iter()
+        .map(|lookup| lookup.evaluate_transition(frame, alpha))
+        .collect()
+}
+
+/// The boundary constraints of `lookups`, in order.
+pub fn boundary_constraints<E: IsField>(lookups: &[Lookup]) -> Vec<BoundaryConstraint<E>> {
+    lookups.iter().map(Lookup::boundary_constraint).collect()
+}
+
+/// Checks that the columns of every lookup exist.
+pub(crate) fn check_lookups(
+    lookups: &[Lookup],
+    trace_columns: usize,
+    periodic_columns: usize,
+) -> Result<(), ProvingError> {
+    for (index, lookup) in lookups.iter().enumerate() {
+        let table_exists = match lookup.table {
+            TableColumn::Trace(column) => column < trace_columns,
+            TableColumn::Periodic(column) => column < periodic_columns,
+        };
+        let columns = [lookup.values, lookup.multiplicities, lookup.sum];
+        if !table_exists || columns.iter().any(|&column| column >= trace_columns) {
+            return Err(ProvingError::WrongParameter(format!(
+                "lookup {index} refers to a column the trace doesn't have"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn frame(trace: &TraceTable<F>, step: usize, periodic: &[Vec<FE>]) -> Frame<F> {
+        Frame::read_from_trace(trace, step, 1, &[0, 1])
+            .with_periodic_values(periodic::values_at_row::<F, F>(periodic, step))
+    }
+
+    #[test]
+    fn running_sum_satisfies_its_constraint_on_every_row() {
+        let table: Vec<FE> = (0..4).map(FE::from).collect();
+        let values = vec![FE::from(3), FE::from(1), FE::from(3), FE::from(0)];
+        let main_trace =
+            TraceTable::new_from_cols(&[values.clone(), multiplicities(&values, &table).unwrap()]);
+        let periodic = vec![table];
+        let lookup = Lookup {
+            values: 0,
+            table: TableColumn::Periodic(0),
+            multiplicities: 1,
+            sum: 2,
+        };
+        let alpha = FE::from(1234);
+
+        let sum = build_sum_columns(&[lookup.clone()], &main_trace, &periodic, &alpha);
+        let trace = main_trace.concatenate(sum.table, 1);
+        for step in 0..4 {
+            let frame = frame(&trace, step, &periodic);
+            assert_eq!(
+                lookup.evaluate_transition::<F, F>(&frame, &alpha),
+                FE::zero()
+            );
+        }
+        assert!(check_lookups(&[lookup], 3, 1).is_ok());
+    }
+
+    #[test]
+    fn values_missing_from_the_table_have_no_multiplicities() {
+        let table: Vec<FE> = (0..4).map(FE::from).collect();
+        assert_eq!(multiplicities(&[FE::from(4)], &table), None);
+        assert_eq!(
+            multiplicities(&[FE::from(2), FE::from(2)], &table),
+            Some(vec![FE::zero(), FE::zero(), FE::from(2), FE::zero()])
+        );
+    }
+}
@@ -1,9 +1,19 @@
+#[cfg(feature = "std")]
 pub mod cairo_air;
+pub mod composition;
 pub mod constraints;
 pub mod context;
-#[cfg(debug_assertions)]
 pub mod debug;
+pub mod dsl;
+pub mod dyn_air;
+#[cfg(feature = "std")]
 pub mod example;
 pub mod frame;
+pub mod gadgets;
+pub mod lookup;
+pub(crate) mod periodic;
+pub mod permutation;
+pub mod segment;
+pub mod testing;
 pub mod trace;
 pub mod traits;
@@ -0,0 +1,134 @@
+//! Evaluation of the periodic columns declared by `AIR::periodic_columns`.
+//!
+//! A column with period `m` over a trace of length `n` is the polynomial
+//! `P(x^(n/m))`, where `P` interpolates the `m` values of a period over the
+//! roots of unity of order `m`. Neither the prover nor the verifier commit to
+//! it: both evaluate it wherever the constraints are evaluated.
+
+use alloc::{format, vec::Vec};
+
+use lambdaworks_fft::{errors::FFTError, polynomial::FFTPoly};
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+};
+
+use crate::{
+    prover::{evaluate_polynomial_on_lde_domain, ProvingError},
+    IsChallengeField,
+};
+
+/// Checks that the period of every periodic column is a power of two that
+/// divides the trace length.
+pub(crate) fn check_periods<F: IsFFTField>(
+    columns: &[Vec<FieldElement<F>>],
+    trace_length: usize,
+) -> Result<(), ProvingError> {
+    for (index, column) in columns.iter().enumerate() {
+        let period = column.len();
+        if !period.is_power_of_two() || period > trace_length {
+            return Err(ProvingError::WrongParameter(format!(
+                "the period {period} of periodic column {index} doesn't divide the trace length {trace_length}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Polynomial `P` of each periodic column, with the exponent `n/m` it is
+/// composed with. The periods have to pass `check_periods`.
+fn interpolants<F: IsFFTField>(
+    columns: &[Vec<FieldElement<F>>],
+    trace_length: usize,
+) -> Vec<(Polynomial<FieldElement<F>>, usize)> {
+    columns
+        .iter()
+        .map(|values| {
+            let poly = if values.len() == 1 {
+                Polynomial::new(values)
+            } else {
+                Polynomial::interpolate_fft(values)
+                    .expect("the period is a power of two no larger than the trace")
+            };
+            (poly, trace_length / values.len())
+        })
+        .collect()
+}
+
+/// Evaluations of each periodic column over one cycle of the LDE coset with
+/// the given blowup factor and offset: the column takes the value at index
+/// `i % len` on the `i`-th point of the coset.
+pub(crate) fn evaluate_on_lde_domain<F: IsFFTField>(
+    columns: &[Vec<FieldElement<F>>],
+    trace_length: usize,
+    blowup_factor: usize,
+    coset_offset: &FieldElement<F>,
+) -> Result<Vec<Vec<FieldElement<F>>>, FFTError> {
+    interpolants(columns, trace_length)
+        .iter()
+        .map(|(poly, exponent)| {
+            evaluate_polynomial_on_lde_domain(
+                poly,
+                blowup_factor,
+                trace_length / exponent,
+                &coset_offset.pow(*exponent),
+            )
+        })
+        .collect()
+}
+
+/// Values of the periodic columns at a point `x` of the challenge field.
+pub(crate) fn evaluate_at<F: IsFFTField, E: IsChallengeField<F>>(
+    columns: &[Vec<FieldElement<F>>],
+    trace_length: usize,
+    x: &FieldElement<E>,
+) -> Vec<FieldElement<E>> {
+    interpolants(columns, trace_length)
+        .iter()
+        .map(|(poly, exponent)| E::embed_polynomial(poly).evaluate(&x.pow(*exponent)))
+        .collect()
+}
+
+/// Values of the periodic columns on a row of the trace.
+pub(crate) fn values_at_row<F: IsFFTField, E: IsChallengeField<F>>(
+    columns: &[Vec<FieldElement<F>>],
+    row: usize,
+) -> Vec<FieldElement<E>> {
+    columns
+        .iter()
+        .map(|values| E::embed(&values[row % values.len()]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::{
+        fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, traits::IsFFTField,
+    };
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    #[test]
+    fn evaluations_match_the_values_on_the_trace_domain() {
+        let columns = vec![vec![FE::from(3), FE::from(5)], vec![FE::from(7)]];
+        assert!(check_periods(&columns, 8).is_ok());
+
+        let evaluations = evaluate_on_lde_domain(&columns, 8, 1, &FE::one()).unwrap();
+        let root = F::get_primitive_root_of_unity(3).unwrap();
+        for row in 0..8 {
+            let values: Vec<FE> = values_at_row(&columns, row);
+            assert_eq!(evaluate_at(&columns, 8, &root.pow(row)), values);
+            assert_eq!(evaluations[0][row % evaluations[0].len()], values[0]);
+        }
+    }
+
+    #[test]
+    fn periods_that_dont_divide_the_trace_are_rejected() {
+        let columns = vec![vec![FE::from(1); 3]];
+        assert!(check_periods(&columns, 8).is_err());
+        assert!(check_periods(&[vec![FE::from(1); 16]], 8).is_err());
+    }
+}
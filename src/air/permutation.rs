@@ -0,0 +1,151 @@
+//! Multiset equality (permutation) arguments.
+//!
+//! Each row has a tuple of the left multiset and one of the right multiset.
+//! With random challenges `z` and `α`, a tuple `(c₀, c₁, ...)` is compressed
+//! into `c₀ + α c₁ + α² c₂ + ...`, and an auxiliary column `p` accumulates
+//! the quotient of both sides: `p₀ = 1` and
+//! `pᵢ₊₁ = pᵢ (z - lᵢ) / (z - rᵢ)`. The transition constraint has no
+//! exemptions, so it also holds from the last row to the first, which is only
+//! possible if the product of all the quotients is one.
+
+use alloc::vec::Vec;
+
+use lambdaworks_math::field::{
+    element::FieldElement,
+    traits::{IsFFTField, IsField},
+};
+
+use super::{constraints::boundary::BoundaryConstraint, frame::Frame, trace::TraceTable};
+use crate::IsChallengeField;
+
+/// Argument that the tuples of the `left` columns over all the rows are a
+/// permutation of the tuples of the `right` columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PermutationArgument {
+    /// Main trace columns of the tuples of the left multiset.
+    pub left: Vec<usize>,
+    /// Main trace columns of the tuples of the right multiset, as many as
+    /// `left`.
+    pub right: Vec<usize>,
+    /// Column of the cumulative product, as an index into the whole trace:
+    /// main columns first, then auxiliary ones.
+    pub product: usize,
+}
+
+/// Compresses the tuple of `columns` in `row` into `c₀ + α c₁ + α² c₂ + ...`.
+pub(crate) fn compress<F: IsFFTField>(
+    row: &[FieldElement<F>],
+    columns: &[usize],
+    alpha: &FieldElement<F>,
+) -> FieldElement<F> {
+    columns
+        .iter()
+        .rev()
+        .fold(FieldElement::zero(), |acc, column| {
+            acc * alpha + &row[*column]
+        })
+}
+
+impl PermutationArgument {
+    /// Degree of the transition constraint of the argument.
+    pub const TRANSITION_DEGREE: usize = 2;
+
+    /// Builds the cumulative product column from the main trace.
+    pub fn build_product_column<F: IsFFTField>(
+        &self,
+        main_trace: &TraceTable<F>,
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> Vec<FieldElement<F>> {
+        let n_rows = main_trace.n_rows();
+        let mut denominators: Vec<_> = (0..n_rows)
+            .map(|step| z - compress(main_trace.get_row(step), &self.right, alpha))
+            .collect();
+        FieldElement::inplace_batch_inverse(&mut denominators);
+
+        let mut product = Vec::with_capacity(n_rows);
+        product.push(FieldElement::one());
+        for step in 0..n_rows - 1 {
+            let numerator = z - compress(main_trace.get_row(step), &self.left, alpha);
+            product.push(&product[step] * numerator * &denominators[step]);
+        }
+        product
+    }
+
+    /// Evaluates the transition constraint on a frame whose first two rows are
+    /// consecutive: `pᵢ₊₁ (z - rᵢ) - pᵢ (z - lᵢ)`.
+    pub fn evaluate_transition<F: IsFFTField, E: IsChallengeField<F>>(
+        &self,
+        frame: &Frame<E>,
+        z: &FieldElement<F>,
+        alpha: &FieldElement<F>,
+    ) -> FieldElement<E> {
+        let (z, alpha) = (E::embed(z), E::embed(alpha));
+        let current = frame.get_row(0);
+        let next = frame.get_row(1);
+        let left = &z - compress(current, &self.left, &alpha);
+        let right = &z - compress(current, &self.right, &alpha);
+
+        &next[self.product] * right - &current[self.product] * left
+    }
+
+    /// The cumulative product starts at one.
+    pub fn boundary_constraint<E: IsField>(&self) -> BoundaryConstraint<E> {
+        BoundaryConstraint::new(self.product, 0, FieldElement::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    fn evaluations(main_trace: &TraceTable<F>) -> Vec<FE> {
+        let argument = PermutationArgument {
+            left: vec![0, 1],
+            right: vec![2, 3],
+            product: 4,
+        };
+        let (z, alpha) = (FE::from(1000), FE::from(17));
+        let product = argument.build_product_column(main_trace, &z, &alpha);
+        let trace = main_trace.concatenate(product, 1);
+
+        (0..trace.n_rows())
+            .map(|step| {
+                let frame = Frame::read_from_trace(&trace, step, 1, &[0, 1]);
+                argument.evaluate_transition::<F, F>(&frame, &z, &alpha)
+            })
+            .collect()
+    }
+
+    fn column(values: [u64; 4]) -> Vec<FE> {
+        values.into_iter().map(FE::from).collect()
+    }
+
+    #[test]
+    fn permuted_tuples_satisfy_the_constraint_on_every_row() {
+        let main_trace = TraceTable::new_from_cols(&[
+            column([1, 2, 3, 4]),
+            column([5, 6, 7, 8]),
+            column([3, 1, 4, 2]),
+            column([7, 5, 8, 6]),
+        ]);
+        assert!(evaluations(&main_trace).iter().all(|e| *e == FE::zero()));
+    }
+
+    #[test]
+    fn tuples_that_arent_permuted_break_the_constraint() {
+        // The values of each column are permuted, but not the tuples.
+        let main_trace = TraceTable::new_from_cols(&[
+            column([1, 2, 3, 4]),
+            column([5, 6, 7, 8]),
+            column([3, 1, 4, 2]),
+            column([5, 7, 8, 6]),
+        ]);
+        assert!(evaluations(&main_trace).iter().any(|e| *e != FE::zero()));
+    }
+}
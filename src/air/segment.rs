@@ -0,0 +1,165 @@
+//! Trace segments: groups of main trace columns with fewer rows than the
+//! trace, declared by `AIR::segments`.
+//!
+//! A segment of period `k` over a trace of length `n` has `n / k` rows, its
+//! row `j` being the row `j·k` of the trace. Those rows are the roots of unity
+//! of order `n / k`, so its columns are interpolated over them alone: their
+//! polynomials have degree below `n / k` and the prover interpolates `n / k`
+//! values per column instead of `n`. The values of the segment columns on the
+//! other rows of the trace are ignored.
+//!
+//! The constraints on a segment apply to the rows of `Segment::row_subset`,
+//! read its next row at the transition offset `Segment::offset(1)`, and relate
+//! it to the rest of the trace through `Segment::trace_row`.
+
+use alloc::{format, vec, vec::Vec};
+use core::ops::Range;
+
+use lambdaworks_fft::{errors::FFTError, polynomial::FFTPoly};
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    polynomial::Polynomial,
+};
+
+use super::{constraints::transition::RowSubset, trace::TraceTable};
+use crate::prover::ProvingError;
+
+/// Main trace columns with a row every `period` rows of the trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub columns: Range<usize>,
+    pub period: usize,
+}
+
+impl Segment {
+    pub fn new(columns: Range<usize>, period: usize) -> Self {
+        Self { columns, period }
+    }
+
+    /// Number of rows of the segment in a trace of length `trace_length`.
+    pub fn rows(&self, trace_length: usize) -> usize {
+        trace_length / self.period
+    }
+
+    /// Row of the trace of the row `row` of the segment.
+    pub fn trace_row(&self, row: usize) -> usize {
+        row * self.period
+    }
+
+    /// Transition offset of the row `rows` rows after the current one.
+    pub fn offset(&self, rows: usize) -> usize {
+        rows * self.period
+    }
+
+    /// Rows of the trace the segment has a row on, for the constraints on it.
+    pub fn row_subset(&self) -> RowSubset {
+        RowSubset::new(self.period, 0)
+    }
+
+    /// Lays `values`, the rows of a segment column, on a trace column of
+    /// `trace_length` rows, with zeros on the rows the segment doesn't have.
+    pub fn spread<F: IsFFTField>(
+        &self,
+        values: &[FieldElement<F>],
+        trace_length: usize,
+    ) -> Vec<FieldElement<F>> {
+        let mut column = vec![FieldElement::zero(); trace_length];
+        for (row, value) in values.iter().enumerate() {
+            column[self.trace_row(row)] = value.clone();
+        }
+        column
+    }
+}
+
+/// Interpolates the columns of `trace`, those of a segment over its rows and
+/// the others over every row.
+pub fn interpolate_columns<F: IsFFTField>(
+    trace: &TraceTable<F>,
+    segments: &[Segment],
+) -> Result<Vec<Polynomial<FieldElement<F>>>, FFTError> {
+    (0..trace.n_cols)
+        .map(|column| {
+            let period = segments
+                .iter()
+                .find(|segment| segment.columns.contains(&column))
+                .map_or(1, |segment| segment.period);
+            let values: Vec<_> = (0..trace.n_rows())
+                .step_by(period)
+                .map(|row| trace.get(row, column))
+                .collect();
+            Polynomial::interpolate_fft(&values)
+        })
+        .collect()
+}
+
+/// Checks that every segment has a power of two period that divides the trace
+/// length, and that the segments are disjoint groups of main columns.
+pub(crate) fn check_segments(
+    segments: &[Segment],
+    main_columns: usize,
+    trace_length: usize,
+) -> Result<(), ProvingError> {
+    for (index, segment) in segments.iter().enumerate() {
+        if !segment.period.is_power_of_two() || segment.period > trace_length {
+            return Err(ProvingError::WrongParameter(format!(
+                "segment {index} has a period of {} in a trace of length {trace_length}",
+                segment.period
+            )));
+        }
+        if segment.columns.is_empty() || segment.columns.end > main_columns {
+            return Err(ProvingError::WrongParameter(format!(
+                "segment {index} has the columns {:?}, which aren't main columns",
+                segment.columns
+            )));
+        }
+        if segments[..index].iter().any(|other| {
+            other.columns.start < segment.columns.end && segment.columns.start < other.columns.end
+        }) {
+            return Err(ProvingError::WrongParameter(format!(
+                "segment {index} shares columns with a previous one"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type F = Stark252PrimeField;
+    type FE = FieldElement<F>;
+
+    #[test]
+    fn segment_columns_are_interpolated_over_their_rows() {
+        let segment = Segment::new(1..2, 4);
+        let full: Vec<FE> = (0..16).map(FE::from).collect();
+        let sparse = segment.spread(&[5, 6, 7, 8].map(FE::from), 16);
+        let trace = TraceTable::new_from_cols(&[full.clone(), sparse]);
+
+        let polys = interpolate_columns(&trace, &[segment.clone()]).unwrap();
+        assert_eq!(polys[1].degree(), 3);
+        let root = F::get_primitive_root_of_unity(4).unwrap();
+        for row in 0..4 {
+            let x = root.pow(segment.trace_row(row));
+            assert_eq!(polys[0].evaluate(&x), full[segment.trace_row(row)]);
+            assert_eq!(polys[1].evaluate(&x), FE::from(5 + row as u64));
+        }
+    }
+
+    #[test]
+    fn invalid_segments_are_rejected() {
+        assert!(check_segments(&[Segment::new(1..3, 4)], 3, 16).is_ok());
+        let invalid = [
+            vec![Segment::new(1..3, 3)],
+            vec![Segment::new(1..3, 32)],
+            vec![Segment::new(1..4, 4)],
+            vec![Segment::new(1..3, 4), Segment::new(2..3, 2)],
+        ];
+        for segments in invalid {
+            assert!(check_segments(&segments, 3, 16).is_err());
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Soundness checks for AIRs.
+//!
+//! An AIR is under-constrained when traces that aren't the computation it
+//! describes still satisfy its constraints. `assert_air_sound` proves a valid
+//! trace, then changes each cell of its main trace in turn and checks that the
+//! verifier rejects the proof of the changed trace, keeping the statement of
+//! the valid one.
+
+use alloc::vec::Vec;
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use crate::{
+    prover::{prove, ProvingError},
+    verifier::verify,
+    MaybeSync,
+};
+
+use super::{
+    constraints::{
+        boundary::BoundaryConstraints,
+        transition::{RowSubset, Zerofier},
+    },
+    context::AirContext,
+    frame::Frame,
+    lookup::Lookup,
+    segment::Segment,
+    trace::{PaddingStrategy, TraceTable},
+    traits::AIR,
+};
+
+/// Panics unless the proof of `trace` is accepted and the proof of every
+/// trace with one cell of the main trace changed is rejected. There is a
+/// proof per cell, so the trace should be small.
+pub fn assert_air_sound<F, A>(air: &A, trace: &A::RawTrace, public_input: &A::PublicInput)
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    let (main_trace, _) = air
+        .build_main_trace(trace, public_input)
+        .expect("the main trace of the valid trace can't be built");
+    let cells = (0..main_trace.n_rows())
+        .flat_map(|row| (0..main_trace.n_cols).map(move |column| (row, column)));
+    let unconstrained = find_unconstrained_cells(air, trace, public_input, cells);
+    assert!(
+        unconstrained.is_empty(),
+        "the verifier accepts changes of the cells (row, column) {unconstrained:?}"
+    );
+}
+
+/// Cells of the main trace, as `(row, column)`, among `cells`, that can be
+/// changed without the verifier noticing. Each cell is changed by adding one
+/// to it, and the trace is proven with the public input of `trace`. Panics
+/// if the proof of `trace` itself isn't accepted.
+pub fn find_unconstrained_cells<F, A>(
+    air: &A,
+    trace: &A::RawTrace,
+    public_input: &A::PublicInput,
+    cells: impl IntoIterator<Item = (usize, usize)>,
+) -> Vec<(usize, usize)>
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    // The prover would refuse the changed traces before the verifier sees them.
+    let mut air = air.clone();
+    if let Some(context) = air.context_mut() {
+        context.options.validate_trace = false;
+    }
+
+    let output = prove(trace, &air, public_input).expect("the valid trace can't be proven");
+    assert!(
+        verify(&output.proof, &air, &output.public_values).is_ok(),
+        "the proof of the valid trace is rejected"
+    );
+
+    cells
+        .into_iter()
+        .filter(|&(row, column)| {
+            let mutated = Mutated {
+                air: air.clone(),
+                row,
+                column,
+            };
+            match prove(trace, &mutated, public_input) {
+                Ok(output) => verify(&output.proof, &air, &output.public_values).is_ok(),
+                Err(_) => false,
+            }
+        })
+        .collect()
+}
+
+/// `air` with one added to a cell of the main trace it builds.
+#[derive(Clone)]
+struct Mutated<A: AIR> {
+    air: A,
+    row: usize,
+    column: usize,
+}
+
+impl<A: AIR> AIR for Mutated<A> {
+    type Field = A::Field;
+    type ChallengeField = A::ChallengeField;
+    type RawTrace = A::RawTrace;
+    type RAPChallenges = A::RAPChallenges;
+    type PublicInput = A::PublicInput;
+
+    fn build_main_trace(
+        &self,
+        raw_trace: &Self::RawTrace,
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError> {
+        let (mut trace, public_input) = self.air.build_main_trace(raw_trace, public_input)?;
+        let cell = &mut trace.table[self.row * trace.n_cols + self.column];
+        *cell = cell.clone() + FieldElement::one();
+        Ok((trace, public_input))
+    }
+
+    fn with_public_input(&self, public_input: &Self::PublicInput) -> Result<Self, ProvingError> {
+        Ok(Self {
+            air: self.air.with_public_input(public_input)?,
+            ..self.clone()
+        })
+    }
+
+    fn public_input_bytes(&self, public_input: &Self::PublicInput) -> Vec<u8> {
+        self.air.public_input_bytes(public_input)
+    }
+
+    fn build_auxiliary_trace(
+        &self,
+        main_trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        self.air
+            .build_auxiliary_trace(main_trace, rap_challenges, public_input)
+    }
+
+    fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges {
+        self.air.build_rap_challenges(transcript)
+    }
+
+    fn number_auxiliary_rap_columns(&self) -> usize {
+        self.air.number_auxiliary_rap_columns()
+    }
+
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        self.air.auxiliary_rounds()
+    }
+
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        round: usize,
+        rap_challenges: &mut Self::RAPChallenges,
+        transcript: &mut T,
+    ) {
+        self.air
+            .extend_rap_challenges(round, rap_challenges, transcript)
+    }
+
+    fn build_auxiliary_round(
+        &self,
+        round: usize,
+        trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        self.air
+            .build_auxiliary_round(round, trace, rap_challenges, public_input)
+    }
+
+    fn number_extension_columns(&self) -> usize {
+        self.air.number_extension_columns()
+    }
+
+    fn build_extension_columns(
+        &self,
+        trace: &TraceTable<Self::Field>,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::ChallengeField> {
+        self.air
+            .build_extension_columns(trace, rap_challenges, public_input)
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.air.periodic_columns()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        self.air.lookups()
+    }
+
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        self.air.virtual_columns()
+    }
+
+    fn evaluate_virtual_columns(
+        &self,
+        row: &[FieldElement<Self::ChallengeField>],
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.air.evaluate_virtual_columns(row)
+    }
+
+    fn padding_strategy(&self) -> Option<PaddingStrategy> {
+        self.air.padding_strategy()
+    }
+
+    fn composition_poly_degree_bound(&self) -> usize {
+        self.air.composition_poly_degree_bound()
+    }
+
+    fn composition_poly_parts(&self) -> usize {
+        self.air.composition_poly_parts()
+    }
+
+    fn compute_transition(
+        &self,
+        frame: &Frame<Self::ChallengeField>,
+        rap_challenges: &Self::RAPChallenges,
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        self.air.compute_transition(frame, rap_challenges)
+    }
+
+    fn boundary_constraints(
+        &self,
+        rap_challenges: &Self::RAPChallenges,
+        public_input: &Self::PublicInput,
+    ) -> BoundaryConstraints<Self::ChallengeField> {
+        self.air.boundary_constraints(rap_challenges, public_input)
+    }
+
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        self.air.transition_row_subsets()
+    }
+
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        self.air.preprocessed_columns()
+    }
+
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<Self::Field>>> {
+        self.air.preprocessed_roots()
+    }
+
+    fn segments(&self) -> Vec<Segment> {
+        self.air.segments()
+    }
+
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        self.air.column_offsets()
+    }
+
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        self.air.transition_zerofiers()
+    }
+
+    fn initial_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        self.air.initial_state(public_input)
+    }
+
+    fn final_state(&self, public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        self.air.final_state(public_input)
+    }
+
+    fn context(&self) -> &AirContext {
+        self.air.context()
+    }
+
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        self.air.context_mut()
+    }
+}
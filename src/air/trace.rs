@@ -1,18 +1,44 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::{Index, IndexMut};
+
+pub mod encoding;
+
 use lambdaworks_fft::errors::FFTError;
 use lambdaworks_fft::polynomial::FFTPoly;
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
     polynomial::Polynomial,
 };
 
+/// How a trace shorter than the trace length of its AIR is filled up, as
+/// returned by `AIR::padding_strategy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingStrategy {
+    /// Repeats the last row. Fits AIRs whose transition constraints hold
+    /// between two equal rows.
+    RepeatLastRow,
+    /// Appends rows of zeros. The AIR has to exempt its constraints from the
+    /// padded rows.
+    Zeros,
+    /// Appends rows of zeros, and a last column that is one on the rows of the
+    /// trace and zero on the padding, for the constraints to be multiplied by.
+    Selector,
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
-pub struct TraceTable<F: IsFFTField> {
+pub struct TraceTable<F: IsField> {
     /// `table` is row-major trace element description
     pub table: Vec<FieldElement<F>>,
     pub n_cols: usize,
 }
 
-impl<F: IsFFTField> TraceTable<F> {
+impl<F: IsField> TraceTable<F> {
     pub fn empty() -> Self {
         Self {
             table: Vec::new(),
@@ -43,12 +69,22 @@ impl<F: IsFFTField> TraceTable<F> {
     }
 
     pub fn new_from_cols(cols: &[Vec<FieldElement<F>>]) -> Self {
+        Self::new_from_cols_with_buffer(cols, Vec::new())
+    }
+
+    /// Same as `new_from_cols`, but writes the row-major table into `table`,
+    /// reusing its allocation instead of requesting a new one.
+    pub fn new_from_cols_with_buffer(
+        cols: &[Vec<FieldElement<F>>],
+        mut table: Vec<FieldElement<F>>,
+    ) -> Self {
         let n_rows = cols[0].len();
         debug_assert!(cols.iter().all(|c| c.len() == n_rows));
 
         let n_cols = cols.len();
 
-        let mut table = Vec::with_capacity(n_cols * n_rows);
+        table.clear();
+        table.reserve(n_cols * n_rows);
 
         for row_idx in 0..n_rows {
             for col in cols {
@@ -101,12 +137,39 @@ impl<F: IsFFTField> TraceTable<F> {
         self.table[idx].clone()
     }
 
-    pub fn compute_trace_polys(&self) -> Vec<Polynomial<FieldElement<F>>> {
-        self.cols()
-            .iter()
-            .map(|col| Polynomial::interpolate_fft(col))
-            .collect::<Result<Vec<Polynomial<FieldElement<F>>>, FFTError>>()
-            .unwrap()
+    /// Fills the trace up to `length` rows with `strategy`. With
+    /// `PaddingStrategy::Selector` the selector column is added even if the
+    /// trace already has `length` rows.
+    pub fn pad(&mut self, strategy: PaddingStrategy, length: usize) {
+        let n_rows = self.n_rows();
+        assert!(n_rows <= length, "the trace is longer than {length} rows");
+        match strategy {
+            PaddingStrategy::RepeatLastRow => {
+                assert!(n_rows > 0, "there is no row to pad with");
+                let last_row = self.last_row().to_vec();
+                for _ in n_rows..length {
+                    self.table.extend_from_slice(&last_row);
+                }
+            }
+            PaddingStrategy::Zeros => {
+                self.table
+                    .resize(length * self.n_cols, FieldElement::zero());
+            }
+            PaddingStrategy::Selector => {
+                let selector = (0..length)
+                    .map(|row| {
+                        if row < n_rows {
+                            FieldElement::one()
+                        } else {
+                            FieldElement::zero()
+                        }
+                    })
+                    .collect();
+                self.table
+                    .resize(length * self.n_cols, FieldElement::zero());
+                *self = self.concatenate(selector, 1);
+            }
+        }
     }
 
     pub fn concatenate(&self, new_cols: Vec<FieldElement<F>>, n_cols: usize) -> Self {
@@ -124,9 +187,151 @@ impl<F: IsFFTField> TraceTable<F> {
     }
 }
 
+impl<F: IsFFTField> TraceTable<F> {
+    pub fn compute_trace_polys(&self) -> Result<Vec<Polynomial<FieldElement<F>>>, FFTError> {
+        self.cols()
+            .iter()
+            .map(|col| Polynomial::interpolate_fft(col))
+            .collect()
+    }
+}
+
+/// Handle of a column declared in a `TraceBuilder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Column(usize);
+
+impl Column {
+    /// Position of the column in the trace table.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Builds a `TraceTable` one row at a time.
+///
+/// Columns are declared with a name before the first row is pushed, and the
+/// cells of a pushed row are set through the returned `Column` handles. Cells
+/// that aren't set are zero. The trace isn't padded implicitly: its length is
+/// the number of rows pushed until `pad_to_power_of_two` is called.
+#[derive(Clone, Debug)]
+pub struct TraceBuilder<F: IsField> {
+    names: Vec<String>,
+    table: Vec<FieldElement<F>>,
+}
+
+impl<F: IsField> Default for TraceBuilder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: IsField> TraceBuilder<F> {
+    pub fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            table: Vec::new(),
+        }
+    }
+
+    /// Declares a new column. Panics if a row was already pushed or if there
+    /// is a column with the same name.
+    pub fn column(&mut self, name: &str) -> Column {
+        assert!(
+            self.table.is_empty(),
+            "columns have to be declared before pushing rows"
+        );
+        assert!(
+            self.find_column(name).is_none(),
+            "column {name} is declared twice"
+        );
+        self.names.push(name.to_string());
+        Column(self.names.len() - 1)
+    }
+
+    /// Declares a column for each of `names`, in order.
+    pub fn columns<const N: usize>(&mut self, names: [&str; N]) -> [Column; N] {
+        names.map(|name| self.column(name))
+    }
+
+    pub fn find_column(&self, name: &str) -> Option<Column> {
+        self.names.iter().position(|n| n == name).map(Column)
+    }
+
+    pub fn column_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn n_rows(&self) -> usize {
+        if self.names.is_empty() {
+            0
+        } else {
+            self.table.len() / self.names.len()
+        }
+    }
+
+    /// Appends a row of zeros and returns it to set its cells.
+    pub fn push_row(&mut self) -> TraceRow<'_, F> {
+        assert!(!self.names.is_empty(), "the trace has no columns");
+        let start = self.table.len();
+        self.table
+            .resize(start + self.names.len(), FieldElement::zero());
+        TraceRow {
+            values: &mut self.table[start..],
+        }
+    }
+
+    /// Appends a row with a value for each column, in declaration order.
+    pub fn push_values(&mut self, values: &[FieldElement<F>]) {
+        assert_eq!(
+            values.len(),
+            self.names.len(),
+            "a row needs a value for each column"
+        );
+        self.table.extend_from_slice(values);
+    }
+
+    /// Value of `column` in a row that was already pushed.
+    pub fn get(&self, row: usize, column: Column) -> &FieldElement<F> {
+        &self.table[row * self.names.len() + column.0]
+    }
+
+    /// Repeats the last row until the length of the trace is a power of two.
+    pub fn pad_to_power_of_two(&mut self) {
+        let n_rows = self.n_rows();
+        assert!(n_rows > 0, "there is no row to pad with");
+        let last_row = self.table[self.table.len() - self.names.len()..].to_vec();
+        for _ in n_rows..n_rows.next_power_of_two() {
+            self.table.extend_from_slice(&last_row);
+        }
+    }
+
+    pub fn build(self) -> TraceTable<F> {
+        TraceTable::new(self.table, self.names.len())
+    }
+}
+
+/// Row pushed into a `TraceBuilder`, indexed by its column handles.
+pub struct TraceRow<'a, F: IsField> {
+    values: &'a mut [FieldElement<F>],
+}
+
+impl<F: IsField> Index<Column> for TraceRow<'_, F> {
+    type Output = FieldElement<F>;
+
+    fn index(&self, column: Column) -> &Self::Output {
+        &self.values[column.0]
+    }
+}
+
+impl<F: IsField> IndexMut<Column> for TraceRow<'_, F> {
+    fn index_mut(&mut self, column: Column) -> &mut Self::Output {
+        &mut self.values[column.0]
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::TraceTable;
+    use super::{PaddingStrategy, TraceBuilder, TraceTable};
     use lambdaworks_math::field::{element::FieldElement, fields::u64_prime_field::F17};
     type FE = FieldElement<F17>;
 
@@ -203,4 +408,58 @@ mod test {
         let table1 = TraceTable::new_from_cols(&table1_columns);
         assert_eq!(table1.concatenate(new_columns, 2), expected_table)
     }
+
+    #[test]
+    fn test_padding_strategies() {
+        let trace = TraceTable::new_from_cols(&[vec![FE::new(3), FE::new(4), FE::new(5)]]);
+
+        let mut repeated = trace.clone();
+        repeated.pad(PaddingStrategy::RepeatLastRow, 4);
+        assert_eq!(
+            repeated.cols(),
+            vec![vec![FE::new(3), FE::new(4), FE::new(5), FE::new(5)]]
+        );
+
+        let mut zeros = trace.clone();
+        zeros.pad(PaddingStrategy::Zeros, 4);
+        assert_eq!(
+            zeros.cols(),
+            vec![vec![FE::new(3), FE::new(4), FE::new(5), FE::new(0)]]
+        );
+
+        let mut selected = trace;
+        selected.pad(PaddingStrategy::Selector, 4);
+        assert_eq!(
+            selected.cols(),
+            vec![
+                vec![FE::new(3), FE::new(4), FE::new(5), FE::new(0)],
+                vec![FE::new(1), FE::new(1), FE::new(1), FE::new(0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_pushes_rows_and_pads() {
+        let mut builder = TraceBuilder::new();
+        let [a, b] = builder.columns(["a", "b"]);
+        builder.push_values(&[FE::new(1), FE::new(1)]);
+        for step in 1..3 {
+            let (prev_a, prev_b) = (
+                builder.get(step - 1, a).clone(),
+                builder.get(step - 1, b).clone(),
+            );
+            let mut row = builder.push_row();
+            row[a] = prev_b.clone();
+            row[b] = prev_a + prev_b;
+        }
+        assert_eq!(builder.find_column("b"), Some(b));
+        assert_eq!(builder.n_rows(), 3);
+
+        builder.pad_to_power_of_two();
+        let expected = TraceTable::new_from_cols(&[
+            vec![FE::new(1), FE::new(1), FE::new(2), FE::new(2)],
+            vec![FE::new(1), FE::new(2), FE::new(3), FE::new(3)],
+        ]);
+        assert_eq!(builder.build(), expected);
+    }
 }
@@ -0,0 +1,170 @@
+//! Binary encoding of a `TraceTable`, to build a trace in one process and
+//! prove it in another, or attach a failing trace to a bug report.
+//!
+//! Every integer is big-endian:
+//!
+//! ```text
+//! magic             4 bytes  "TRCE"
+//! version           u16
+//! element width     u16      bytes of an element of the trace field
+//! columns           u32
+//! rows              u32
+//! table             the row-major elements, each in its `to_bytes_be` encoding
+//! ```
+
+use alloc::vec::Vec;
+
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsFFTField},
+    traits::ByteConversion,
+};
+
+use super::TraceTable;
+
+const MAGIC: &[u8; 4] = b"TRCE";
+const HEADER_SIZE: usize = 16;
+/// Version of the layout written by `TraceTable::to_bytes`.
+pub const TRACE_ENCODING_VERSION: u16 = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TraceDecodingError {
+    /// The bytes don't start with the magic of an encoded trace.
+    InvalidMagic,
+    /// The trace was encoded with a layout version this crate can't read.
+    UnsupportedVersion(u16),
+    /// The width of the elements in the header doesn't match the field the
+    /// trace is decoded into.
+    FieldWidthMismatch { expected: usize, found: usize },
+    /// The table isn't as long as its number of rows and columns says.
+    InvalidLength,
+    /// The bytes at this offset aren't the canonical encoding of a field element.
+    InvalidFieldElement(usize),
+}
+
+impl<F: IsFFTField> TraceTable<F>
+where
+    FieldElement<F>: ByteConversion,
+{
+    /// Encodes the trace in the layout described in `trace::encoding`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let width = FieldElement::<F>::zero().to_bytes_be().len();
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.table.len() * width);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&TRACE_ENCODING_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&(width as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.n_cols as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.n_rows() as u32).to_be_bytes());
+        for element in &self.table {
+            bytes.extend_from_slice(&element.to_bytes_be());
+        }
+        bytes
+    }
+
+    /// Decodes a trace encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TraceDecodingError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(TraceDecodingError::InvalidLength);
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(TraceDecodingError::InvalidMagic);
+        }
+        let u16_at = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap()) as usize;
+
+        let version = u16_at(4) as u16;
+        if version != TRACE_ENCODING_VERSION {
+            return Err(TraceDecodingError::UnsupportedVersion(version));
+        }
+        let expected = FieldElement::<F>::zero().to_bytes_be().len();
+        let found = u16_at(6);
+        if found != expected {
+            return Err(TraceDecodingError::FieldWidthMismatch { expected, found });
+        }
+        let (n_cols, n_rows) = (u32_at(8), u32_at(12));
+
+        let table_bytes = &bytes[HEADER_SIZE..];
+        let table_len = n_cols
+            .checked_mul(n_rows)
+            .filter(|len| len.checked_mul(expected) == Some(table_bytes.len()))
+            .ok_or(TraceDecodingError::InvalidLength)?;
+        if n_cols == 0 && n_rows != 0 {
+            return Err(TraceDecodingError::InvalidLength);
+        }
+
+        let mut table = Vec::with_capacity(table_len);
+        for (i, chunk) in table_bytes.chunks_exact(expected).enumerate() {
+            let offset = HEADER_SIZE + i * expected;
+            let element = FieldElement::from_bytes_be(chunk)
+                .map_err(|_| TraceDecodingError::InvalidFieldElement(offset))?;
+            if element.to_bytes_be() != chunk {
+                return Err(TraceDecodingError::InvalidFieldElement(offset));
+            }
+            table.push(element);
+        }
+        Ok(Self::new(table, n_cols))
+    }
+
+    /// Writes the trace to `path` in the layout of `to_bytes`.
+    #[cfg(feature = "std")]
+    pub fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Reads a trace written by `write_to_file`.
+    #[cfg(feature = "std")]
+    pub fn read_from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                alloc::format!("invalid trace: {e:?}"),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    fn trace() -> TraceTable<Stark252PrimeField> {
+        TraceTable::new_from_cols(&[
+            vec![FE::from(1), FE::from(2), FE::from(3), FE::from(4)],
+            vec![FE::from(5), FE::from(6), FE::from(7), -FE::one()],
+        ])
+    }
+
+    #[test]
+    fn trace_round_trips_through_bytes() {
+        let trace = trace();
+        let decoded = TraceTable::from_bytes(&trace.to_bytes()).unwrap();
+        assert_eq!(decoded, trace);
+    }
+
+    #[test]
+    fn malformed_traces_are_rejected() {
+        let bytes = trace().to_bytes();
+        assert_eq!(
+            TraceTable::<Stark252PrimeField>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(TraceDecodingError::InvalidLength)
+        );
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            TraceTable::<Stark252PrimeField>::from_bytes(&bad_magic),
+            Err(TraceDecodingError::InvalidMagic)
+        );
+
+        let mut unreduced = bytes;
+        unreduced[HEADER_SIZE..HEADER_SIZE + 32].fill(0xff);
+        assert_eq!(
+            TraceTable::<Stark252PrimeField>::from_bytes(&unreduced),
+            Err(TraceDecodingError::InvalidFieldElement(HEADER_SIZE))
+        );
+    }
+}
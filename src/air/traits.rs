@@ -1,30 +1,61 @@
+use alloc::{vec, vec::Vec};
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
-use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
-    polynomial::Polynomial,
-};
+use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
 
-use crate::prover::ProvingError;
+use crate::{prover::ProvingError, IsChallengeField};
 
 use super::{
-    constraints::boundary::BoundaryConstraints,
+    constraints::{
+        boundary::BoundaryConstraints,
+        transition::{RowSubset, Zerofier},
+    },
     context::{AirContext, ProofOptions},
     frame::Frame,
-    trace::TraceTable,
+    lookup::Lookup,
+    segment::Segment,
+    trace::{PaddingStrategy, TraceTable},
 };
-use crate::get_powers_of_primitive_root_coset;
 /// AIR is a representation of the Constraints
 pub trait AIR: Clone {
     type Field: IsFFTField;
+    /// Field of the challenges. Transition and boundary constraints are
+    /// evaluated in it, since they are combined with the challenges.
+    type ChallengeField: IsChallengeField<Self::Field>;
     type RawTrace;
     type RAPChallenges;
     type PublicInput;
 
+    /// Builds the main trace from the raw trace. Together with it, returns the
+    /// public input completed with the values derived from the execution (e.g.
+    /// the range check bounds in Cairo), which is the statement the verifier has
+    /// to check the proof against.
+    ///
+    /// The trace can be shorter than the trace length of the context if the
+    /// AIR has a `padding_strategy`. The public input then has to hold the
+    /// number of rows before padding if the constraints depend on it.
     fn build_main_trace(
         &self,
         raw_trace: &Self::RawTrace,
-        public_input: &mut Self::PublicInput,
-    ) -> Result<TraceTable<Self::Field>, ProvingError>;
+        public_input: &Self::PublicInput,
+    ) -> Result<(TraceTable<Self::Field>, Self::PublicInput), ProvingError>;
+
+    /// The AIR of the statement with `public_input`, for AIRs whose columns or
+    /// constraints depend on it (e.g. on the builtins a program uses). The
+    /// prover calls it with the public input completed by `build_main_trace`
+    /// and the verifier with the one it is given, so both derive the same
+    /// counts. The trace length and options have to stay the same, and errors
+    /// are reported as `ProvingError::WrongParameter`. By default, the AIR
+    /// doesn't depend on the public input.
+    fn with_public_input(&self, _public_input: &Self::PublicInput) -> Result<Self, ProvingError> {
+        Ok(self.clone())
+    }
+
+    /// Canonical encoding of `public_input`, which the prover and the
+    /// verifier append to the transcript before the trace commitments, so the
+    /// challenges depend on the statement. By default, nothing is appended.
+    fn public_input_bytes(&self, _public_input: &Self::PublicInput) -> Vec<u8> {
+        Vec::new()
+    }
 
     fn build_auxiliary_trace(
         &self,
@@ -35,54 +66,208 @@ pub trait AIR: Clone {
 
     fn build_rap_challenges<T: Transcript>(&self, transcript: &mut T) -> Self::RAPChallenges;
 
+    /// Number of auxiliary columns, over all the auxiliary rounds.
     fn number_auxiliary_rap_columns(&self) -> usize;
 
+    /// Number of auxiliary columns committed in each auxiliary round. The
+    /// first round draws `build_rap_challenges` and builds
+    /// `build_auxiliary_trace`, and each later round draws
+    /// `extend_rap_challenges` after the columns of the previous rounds are
+    /// committed, and builds `build_auxiliary_round`. By default there is a
+    /// single round.
+    fn auxiliary_rounds(&self) -> Vec<usize> {
+        vec![self.number_auxiliary_rap_columns()]
+    }
+
+    /// Draws the challenges of the auxiliary round `round`, from the second
+    /// one on, adding them to those of the previous rounds.
+    fn extend_rap_challenges<T: Transcript>(
+        &self,
+        _round: usize,
+        _rap_challenges: &mut Self::RAPChallenges,
+        _transcript: &mut T,
+    ) {
+    }
+
+    /// Builds the columns of the auxiliary round `round`, from the second one
+    /// on. `trace` has the main columns followed by the auxiliary columns of
+    /// the previous rounds.
+    fn build_auxiliary_round(
+        &self,
+        _round: usize,
+        _trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::Field> {
+        TraceTable::empty()
+    }
+
+    /// Number of auxiliary columns over the challenge field. Permutation and
+    /// lookup columns over a small base field are only sound in an extension
+    /// field, while the main columns can stay in the base field. They follow
+    /// every column over the base field in the frame rows, and are counted in
+    /// the trace columns of the context. By default, there are none.
+    fn number_extension_columns(&self) -> usize {
+        0
+    }
+
+    /// Builds the auxiliary columns over the challenge field, after every
+    /// auxiliary round. `trace` has the main columns followed by all the
+    /// auxiliary columns over the base field.
+    fn build_extension_columns(
+        &self,
+        _trace: &TraceTable<Self::Field>,
+        _rap_challenges: &Self::RAPChallenges,
+        _public_input: &Self::PublicInput,
+    ) -> TraceTable<Self::ChallengeField> {
+        TraceTable::empty()
+    }
+
+    /// Values over one period of each periodic column. Periodic columns aren't
+    /// committed, and constraints read them from `Frame::periodic_values`.
+    /// Each period has to be a power of two that divides the trace length.
+    fn periodic_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        Vec::new()
+    }
+
+    /// Lookups of main trace columns in tables. Their running sum columns are
+    /// built and constrained by the AIR with the functions of `air::lookup`.
+    fn lookups(&self) -> Vec<Lookup> {
+        Vec::new()
+    }
+
+    /// Names of the virtual columns: expressions of the committed columns of a
+    /// row (e.g. a linear combination, or `1 - a·a⁻¹` with a committed inverse)
+    /// that are computed wherever the constraints are evaluated instead of
+    /// being committed. Frame rows have them after the committed columns.
+    fn virtual_columns(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Values of the virtual columns, in the order of `virtual_columns`, on a
+    /// row of committed values.
+    fn evaluate_virtual_columns(
+        &self,
+        _row: &[FieldElement<Self::ChallengeField>],
+    ) -> Vec<FieldElement<Self::ChallengeField>> {
+        Vec::new()
+    }
+
+    /// Index in the frame rows of the virtual column `name`.
+    fn virtual_column(&self, name: &str) -> Option<usize> {
+        self.virtual_columns()
+            .iter()
+            .position(|column| *column == name)
+            .map(|position| self.context().trace_columns + position)
+    }
+
+    /// How the prover fills the main trace up to the trace length of the
+    /// context. Without one, the main trace has to have exactly that length.
+    fn padding_strategy(&self) -> Option<PaddingStrategy> {
+        None
+    }
+
     fn composition_poly_degree_bound(&self) -> usize;
 
+    /// Number of parts Hᵢ the composition polynomial is split into, so that each
+    /// of them has a degree below the trace length. There are always at least two.
+    fn composition_poly_parts(&self) -> usize {
+        let trace_length = self.context().trace_length;
+        let parts = (self.composition_poly_degree_bound() + trace_length - 1) / trace_length;
+        parts.max(2)
+    }
+
     fn compute_transition(
         &self,
-        frame: &Frame<Self::Field>,
+        frame: &Frame<Self::ChallengeField>,
         rap_challenges: &Self::RAPChallenges,
-    ) -> Vec<FieldElement<Self::Field>>;
+    ) -> Vec<FieldElement<Self::ChallengeField>>;
 
+    /// Values of the trace at given rows. Constraints can be on auxiliary
+    /// columns, which follow the main ones, and their values can depend on
+    /// the RAP challenges (e.g. the final value of a cumulative product).
+    /// Their rows can also come from the public input, like the row of the
+    /// final state of an execution shorter than the trace; the prover and
+    /// the verifier both reject rows outside the trace.
     fn boundary_constraints(
         &self,
         rap_challenges: &Self::RAPChallenges,
         public_input: &Self::PublicInput,
-    ) -> BoundaryConstraints<Self::Field>;
+    ) -> BoundaryConstraints<Self::ChallengeField>;
 
-    fn transition_exemptions(&self) -> Vec<Polynomial<FieldElement<Self::Field>>> {
-        let trace_length = self.context().trace_length;
-        let roots_of_unity_order = trace_length.trailing_zeros();
-        let roots_of_unity = get_powers_of_primitive_root_coset(
-            roots_of_unity_order as u64,
-            self.context().trace_length,
-            &FieldElement::<Self::Field>::one(),
-        )
-        .unwrap();
-        let root_of_unity_len = roots_of_unity.len();
-
-        let x = Polynomial::new_monomial(FieldElement::one(), 1);
-
-        self.context()
-            .transition_exemptions
-            .iter()
-            .take(self.context().num_transition_constraints)
-            .map(|cant_take| {
-                roots_of_unity
-                    .iter()
-                    .take(root_of_unity_len)
-                    .rev()
-                    .take(*cant_take)
-                    .fold(
-                        Polynomial::new_monomial(FieldElement::one(), 0),
-                        |acc, root| acc * (&x - root),
-                    )
+    /// Rows each transition constraint applies to, one per constraint. Its
+    /// exemptions are the last rows of its subset. By default, every
+    /// constraint applies to every row.
+    fn transition_row_subsets(&self) -> Vec<RowSubset> {
+        vec![RowSubset::ALL; self.context().num_transition_constraints]
+    }
+
+    /// Columns fixed by the AIR, such as selectors, tables or round constants
+    /// laid over the whole trace. They are the first main columns, before the
+    /// ones of `build_main_trace`. A `Prover` commits to them once for all its
+    /// proofs, and the verifier checks that a proof uses them.
+    fn preprocessed_columns(&self) -> Vec<Vec<FieldElement<Self::Field>>> {
+        Vec::new()
+    }
+
+    /// Merkle roots of the preprocessed columns, as computed offline by
+    /// `prover::commit_preprocessed_columns`. When there are none, the verifier
+    /// commits to `preprocessed_columns` itself to check a proof.
+    fn preprocessed_roots(&self) -> Option<Vec<FieldElement<Self::Field>>> {
+        None
+    }
+
+    /// Groups of main columns with a row every few rows of the trace, which
+    /// are interpolated over those rows only. By default, there are none.
+    fn segments(&self) -> Vec<Segment> {
+        Vec::new()
+    }
+
+    /// Offsets of `transition_offsets` at which the constraints read each
+    /// column of the trace. Only these values, and those of the first frame
+    /// row that the boundary constraints use, are evaluated out of domain and
+    /// sent in the proof; the others are zero in the frame of the verifier.
+    /// By default, every column is read at every offset.
+    fn column_offsets(&self) -> Vec<Vec<usize>> {
+        vec![self.context().transition_offsets.clone(); self.context().trace_columns]
+    }
+
+    /// Rows each transition constraint applies to, one per constraint, given
+    /// by the set its zerofier vanishes on. By default, the rows of its subset
+    /// in `transition_row_subsets` but the last ones of its exemptions.
+    fn transition_zerofiers(&self) -> Vec<Zerofier> {
+        let context = self.context();
+        self.transition_row_subsets()
+            .into_iter()
+            .zip(&context.transition_exemptions)
+            .map(|(subset, exemptions)| {
+                Zerofier::with_exemptions(subset, context.trace_length, *exemptions)
             })
             .collect()
     }
+
+    /// State the execution of the statement with `public_input` starts from,
+    /// for executions proven as a chain of segments with
+    /// `prover::prove_chain`. The boundary constraints have to fix it. By
+    /// default, there is none.
+    fn initial_state(&self, _public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        Vec::new()
+    }
+
+    /// State the execution of the statement with `public_input` ends in,
+    /// which is the initial state of the next segment of a chain.
+    fn final_state(&self, _public_input: &Self::PublicInput) -> Vec<FieldElement<Self::Field>> {
+        Vec::new()
+    }
+
     fn context(&self) -> &AirContext;
 
+    /// Mutable access to the context, used by `Prover::with_options` to change
+    /// the proof options. AIRs that don't allow it keep the default.
+    fn context_mut(&mut self) -> Option<&mut AirContext> {
+        None
+    }
+
     fn options(&self) -> &ProofOptions {
         &self.context().options
     }
@@ -0,0 +1,138 @@
+//! Command line prover and verifier for Cairo executions.
+//!
+//! ```text
+//! cairo-stark prove --trace trace.bin --memory memory.bin --program-size 12 \
+//!     --out proof.bin --public-input pub.json --options opts.toml
+//! cairo-stark verify --proof proof.bin --public-input pub.json --options opts.toml
+//! ```
+//!
+//! The trace and memory files are the binary outputs of the Cairo runner. The
+//! options file holds the fields of `ProofOptions`; without it, both commands
+//! use `ProofOptions::secure`, with about 100 bits of conjectured security.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use lambdaworks_stark::{
+    air::{
+        cairo_air::air::{CairoAIR, PublicInputs},
+        context::ProofOptions,
+    },
+    cairo_vm::{cairo_mem::CairoMemory, cairo_trace::CairoTrace},
+    prover::prove,
+    verifier::verify_bytes,
+};
+
+#[derive(Parser)]
+#[command(name = "cairo-stark", about = "Prove and verify Cairo executions")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Proves the execution given by a trace and a memory file.
+    Prove {
+        #[arg(long)]
+        trace: PathBuf,
+        #[arg(long)]
+        memory: PathBuf,
+        /// Number of memory cells holding the program bytecode, from address 1.
+        #[arg(long)]
+        program_size: usize,
+        /// Where to write the proof.
+        #[arg(long)]
+        out: PathBuf,
+        /// Where to write the public input the proof has to be verified against.
+        #[arg(long)]
+        public_input: PathBuf,
+        #[arg(long)]
+        options: Option<PathBuf>,
+    },
+    /// Verifies a proof written by `prove`.
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        #[arg(long)]
+        public_input: PathBuf,
+        #[arg(long)]
+        options: Option<PathBuf>,
+    },
+}
+
+fn read_options(path: Option<PathBuf>) -> Result<ProofOptions, String> {
+    match path {
+        None => Ok(ProofOptions::secure()),
+        Some(path) => {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+            toml::from_str(&text).map_err(|e| format!("invalid options in {}: {e}", path.display()))
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Prove {
+            trace,
+            memory,
+            program_size,
+            out,
+            public_input,
+            options,
+        } => {
+            let options = read_options(options)?;
+            let register_states = CairoTrace::from_file(&trace.to_string_lossy())
+                .map_err(|e| format!("couldn't load {}: {e}", trace.display()))?;
+            let memory_cells = CairoMemory::from_file(&memory.to_string_lossy())
+                .map_err(|e| format!("couldn't load {}: {e}", memory.display()))?;
+
+            let pub_inputs =
+                PublicInputs::try_from_regs_and_mem(&register_states, &memory_cells, program_size)
+                    .map_err(|e| format!("invalid execution: {e:?}"))?;
+            let air = CairoAIR::for_public_input(options, &pub_inputs)
+                .map_err(|e| format!("invalid execution: {e:?}"))?;
+            let output = prove(&(register_states, memory_cells), &air, &pub_inputs)
+                .map_err(|e| format!("proving failed: {e:?}"))?;
+
+            fs::write(&out, output.proof.to_bytes())
+                .map_err(|e| format!("couldn't write {}: {e}", out.display()))?;
+            let json = serde_json::to_string_pretty(&output.public_values)
+                .map_err(|e| format!("couldn't encode the public input: {e}"))?;
+            fs::write(&public_input, json)
+                .map_err(|e| format!("couldn't write {}: {e}", public_input.display()))?;
+            Ok(())
+        }
+        Command::Verify {
+            proof,
+            public_input,
+            options,
+        } => {
+            let options = read_options(options)?;
+            let proof_bytes =
+                fs::read(&proof).map_err(|e| format!("couldn't read {}: {e}", proof.display()))?;
+            let json = fs::read_to_string(&public_input)
+                .map_err(|e| format!("couldn't read {}: {e}", public_input.display()))?;
+            let pub_inputs: PublicInputs = serde_json::from_str(&json)
+                .map_err(|e| format!("invalid public input in {}: {e}", public_input.display()))?;
+
+            let air = CairoAIR::for_public_input(options, &pub_inputs)
+                .map_err(|e| format!("invalid public input: {e:?}"))?;
+            verify_bytes(&proof_bytes, &air, &pub_inputs)
+                .map_err(|e| format!("the proof is invalid: {e:?}"))?;
+            println!("The proof is valid");
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::air::cairo_air::layout::Layout;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CairoLayout {
     Plain,
     Small,
@@ -26,4 +29,19 @@ impl CairoLayout {
             CairoLayout::Dynamic => "dynamic",
         }
     }
+
+    /// Layout of the Cairo AIR with the builtins of this one, if the AIR has
+    /// it.
+    pub fn air_layout(&self) -> Option<Layout> {
+        match self {
+            CairoLayout::Plain => Some(Layout::Plain),
+            CairoLayout::Small => Some(Layout::Small),
+            CairoLayout::Dex => Some(Layout::Dex),
+            CairoLayout::Recursive => Some(Layout::Recursive),
+            CairoLayout::Starknet => Some(Layout::Starknet),
+            CairoLayout::StarknetWithKeccak => Some(Layout::StarknetWithKeccak),
+            CairoLayout::Dynamic => Some(Layout::Dynamic),
+            _ => None,
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::air::cairo_air::air::MemorySegment;
 use crate::cairo_vm::cairo_mem::CairoMemory;
 use crate::cairo_vm::cairo_trace::CairoTrace;
 
@@ -5,9 +6,17 @@ use super::cairo_layout::CairoLayout;
 use super::vec_writer::VecWriter;
 use cairo_vm::cairo_run::{self, EncodeTraceError};
 use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::hint_processor::hint_processor_definition::HintProcessor;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::errors::memory_errors::MemoryError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
+use cairo_vm::vm::runners::builtin_runner::{
+    EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, KECCAK_BUILTIN_NAME, OUTPUT_BUILTIN_NAME,
+    POSEIDON_BUILTIN_NAME, RANGE_CHECK_BUILTIN_NAME,
+};
+use cairo_vm::vm::vm_core::VirtualMachine;
+use std::collections::BTreeSet;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -22,6 +31,27 @@ pub enum Error {
     VirtualMachine(#[from] VirtualMachineError),
     #[error(transparent)]
     Trace(#[from] TraceError),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error("The program is not a compiled Cairo program")]
+    Program(#[from] serde_json::Error),
+    #[error("The program has hints that are not allowed: {0:?}")]
+    HintsNotAllowed(Vec<String>),
+    #[error("Output cell at address {0} was not written by the program")]
+    MissingOutputCell(u64),
+}
+
+/// Segments of the builtins used by an execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuiltinSegments {
+    pub range_check: Option<MemorySegment>,
+    pub range_check96: Option<MemorySegment>,
+    pub pedersen: Option<MemorySegment>,
+    pub poseidon: Option<MemorySegment>,
+    pub keccak: Option<MemorySegment>,
+    pub ec_op: Option<MemorySegment>,
+    /// Cells written by the program as its output.
+    pub output: Option<MemorySegment>,
 }
 
 /// Runs a cairo program in JSON format and returns trace, memory and program length.
@@ -44,24 +74,53 @@ pub fn run_program(
     layout: CairoLayout,
     filename: &str,
 ) -> Result<(CairoTrace, CairoMemory, usize), Error> {
+    let program_content = std::fs::read(filename).map_err(Error::IO)?;
+    let (trace, memory, data_len, _) =
+        run_program_content(entrypoint_function, layout, &program_content, false)?;
+    Ok((trace, memory, data_len))
+}
+
+/// Same as `run_program`, for the content of the JSON file of the program,
+/// also returning the segments of the builtins of the execution. In proof mode, the program has to be compiled with `--proof_mode`.
+pub fn run_program_content(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    proof_mode: bool,
+) -> Result<(CairoTrace, CairoMemory, usize, BuiltinSegments), Error> {
+    run_program_content_with_hint_processor(
+        entrypoint_function,
+        layout,
+        program_content,
+        proof_mode,
+        &mut BuiltinHintProcessor::new_empty(),
+    )
+}
+
+/// Same as `run_program_content`, with the hints of the program executed by
+/// `hint_executor`.
+pub fn run_program_content_with_hint_processor(
+    entrypoint_function: Option<&str>,
+    layout: CairoLayout,
+    program_content: &[u8],
+    proof_mode: bool,
+    hint_executor: &mut dyn HintProcessor,
+) -> Result<(CairoTrace, CairoMemory, usize, BuiltinSegments), Error> {
     // default value for entrypoint is "main"
     let entrypoint = entrypoint_function.unwrap_or("main");
 
     let trace_enabled = true;
-    let mut hint_executor = BuiltinHintProcessor::new_empty();
     let cairo_run_config = cairo_run::CairoRunConfig {
         entrypoint,
         trace_enabled,
         relocate_mem: true,
         layout: layout.as_str(),
-        proof_mode: false,
+        proof_mode,
         secure_run: None,
     };
 
-    let program_content = std::fs::read(filename).map_err(Error::IO)?;
-
     let (cairo_runner, vm) =
-        match cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_executor) {
+        match cairo_run::cairo_run(program_content, &cairo_run_config, hint_executor) {
             Ok(runner) => runner,
             Err(error) => {
                 eprintln!("{error}");
@@ -87,8 +146,72 @@ pub fn run_program(
     let cairo_trace = CairoTrace::from_bytes_le(&trace_vec).unwrap();
 
     let data_len = cairo_runner.get_program().data_len();
+    let builtins = BuiltinSegments {
+        range_check: builtin_segment(&vm, RANGE_CHECK_BUILTIN_NAME)?,
+        // The Cairo VM of this crate doesn't have the range check 96 builtin.
+        range_check96: None,
+        pedersen: builtin_segment(&vm, HASH_BUILTIN_NAME)?,
+        poseidon: builtin_segment(&vm, POSEIDON_BUILTIN_NAME)?,
+        keccak: builtin_segment(&vm, KECCAK_BUILTIN_NAME)?,
+        ec_op: builtin_segment(&vm, EC_OP_BUILTIN_NAME)?,
+        output: builtin_segment(&vm, OUTPUT_BUILTIN_NAME)?,
+    };
+
+    Ok((cairo_trace, cairo_mem, data_len, builtins))
+}
 
-    Ok((cairo_trace, cairo_mem, data_len))
+/// Codes of the hints of the program given by the content of its compiled
+/// JSON file.
+pub fn program_hints(program_content: &[u8]) -> Result<BTreeSet<String>, Error> {
+    let program: serde_json::Value = serde_json::from_slice(program_content)?;
+    let hints = program["hints"]
+        .as_object()
+        .into_iter()
+        .flat_map(|hints| hints.values())
+        .filter_map(|hints| hints.as_array())
+        .flatten()
+        .filter_map(|hint| hint["code"].as_str())
+        .map(str::to_string)
+        .collect();
+    Ok(hints)
+}
+
+/// Whether the program given by the content of its compiled JSON file was
+/// compiled with `--proof_mode`, which gives it the `__start__` and
+/// `__end__` labels of the runs in proof mode.
+pub fn is_proof_mode_program(program_content: &[u8]) -> Result<bool, Error> {
+    let program: serde_json::Value = serde_json::from_slice(program_content)?;
+    let main_scope = program["main_scope"].as_str().unwrap_or("__main__");
+    let identifiers = &program["identifiers"];
+    Ok(["__start__", "__end__"]
+        .iter()
+        .all(|label| !identifiers[format!("{main_scope}.{label}")].is_null()))
+}
+
+/// Relocated addresses of the cells used by the builtin `name`, rounded up to
+/// whole instances. Builtins of the layout that the execution doesn't use
+/// have no segment.
+fn builtin_segment(vm: &VirtualMachine, name: &str) -> Result<Option<MemorySegment>, Error> {
+    let builtin = match vm
+        .get_builtin_runners()
+        .iter()
+        .find(|builtin| builtin.name() == name)
+    {
+        Some(builtin) => builtin,
+        None => return Ok(None),
+    };
+    let used_cells = builtin.get_used_cells(&vm.segments)? as u64;
+    if used_cells == 0 {
+        return Ok(None);
+    }
+    let relocation_table = vm.segments.relocate_segments()?;
+    let begin_addr = relocation_table[builtin.base()] as u64;
+    let cells_per_instance = builtin.cells_per_instance() as u64;
+    let instances = (used_cells + cells_per_instance - 1) / cells_per_instance;
+    Ok(Some(MemorySegment {
+        begin_addr,
+        stop_ptr: begin_addr + instances * cells_per_instance,
+    }))
 }
 
 #[cfg(test)]
@@ -106,6 +229,41 @@ mod tests {
     pub type Stark252PrimeField = U256PrimeField<MontgomeryConfigStark252PrimeField>;
     type FE = FieldElement<Stark252PrimeField>;
 
+    #[test]
+    fn program_hints_are_the_codes_of_the_hints() {
+        let program = br#"{
+            "data": [],
+            "hints": {
+                "0": [{"code": "memory[ap] = 1", "accessible_scopes": []}],
+                "4": [
+                    {"code": "memory[ap] = 1", "accessible_scopes": []},
+                    {"code": "ids.x = 2", "accessible_scopes": []}
+                ]
+            }
+        }"#;
+        let hints: Vec<_> = super::program_hints(program).unwrap().into_iter().collect();
+        assert_eq!(hints, ["ids.x = 2", "memory[ap] = 1"]);
+        assert!(super::program_hints(b"not a program").is_err());
+    }
+
+    #[test]
+    fn proof_mode_programs_have_the_start_and_end_labels() {
+        let program = br#"{
+            "main_scope": "__main__",
+            "identifiers": {
+                "__main__.__start__": {"pc": 0, "type": "label"},
+                "__main__.__end__": {"pc": 4, "type": "label"},
+                "__main__.main": {"pc": 6, "type": "function"}
+            }
+        }"#;
+        assert!(super::is_proof_mode_program(program).unwrap());
+
+        let base_dir = env!("CARGO_MANIFEST_DIR");
+        let program = std::fs::read(base_dir.to_owned() + "/src/cairo_run/program.json").unwrap();
+        assert!(!super::is_proof_mode_program(&program).unwrap());
+        assert!(super::is_proof_mode_program(b"not a program").is_err());
+    }
+
     #[test]
     fn test_parse_cairo_file() {
         let base_dir = env!("CARGO_MANIFEST_DIR");
@@ -0,0 +1,438 @@
+//! Runs compiled Cairo programs and gives their executions in the form the
+//! prover takes:
+//!
+//! ```no_run
+//! use lambdaworks_stark::{
+//!     air::context::ProofOptions, cairo_run::cairo_layout::CairoLayout,
+//!     cairo_runner::CairoRunner, verifier::verify,
+//! };
+//!
+//! let program = std::fs::read("fibonacci.json").unwrap();
+//! let execution = CairoRunner::new(CairoLayout::Plain).run(&program).unwrap();
+//! let options = ProofOptions {
+//!     blowup_factor: 4,
+//!     fri_number_of_queries: 3,
+//!     coset_offset: 3,
+//!     validate_trace: false,
+//! };
+//! let output = execution.prove(options.clone()).unwrap();
+//! let air = execution.air(options).unwrap();
+//! assert!(verify(&output.proof, &air, &output.public_values).is_ok());
+//! ```
+
+use std::collections::BTreeSet;
+
+use cairo_vm::hint_processor::{
+    builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    hint_processor_definition::HintProcessor,
+};
+
+use crate::{
+    air::{
+        cairo_air::{
+            air::{CairoAIR, PublicInputs},
+            layout::Layout,
+        },
+        context::ProofOptions,
+        gadgets::public_memory::MemoryPage,
+    },
+    cairo_run::{
+        cairo_layout::CairoLayout,
+        run::{
+            is_proof_mode_program, program_hints, run_program_content_with_hint_processor,
+            BuiltinSegments, Error,
+        },
+    },
+    cairo_vm::{
+        cairo_mem::CairoMemory, cairo_pie::CairoPie, cairo_trace::CairoTrace,
+        errors::CairoImportError,
+    },
+    prover::{prove, ProverOutput, ProvingError},
+    FE,
+};
+
+/// `jmp rel 0`, the instruction of the final loop of proof mode. Its offset,
+/// in the next cell, is 0.
+const JMP_REL: u64 = 0x10780017fff7fff;
+
+/// Runs Cairo programs with the Cairo VM. By default, programs start at
+/// `main`, they can have any hint, and they run in proof mode if they were
+/// compiled with `--proof_mode`. Only executions in proof mode are provable.
+#[derive(Clone, Debug)]
+pub struct CairoRunner {
+    layout: CairoLayout,
+    entrypoint: String,
+    /// Whether to run in proof mode, or to find it from the program.
+    proof_mode: Option<bool>,
+    allowed_hints: Option<BTreeSet<String>>,
+}
+
+impl CairoRunner {
+    pub fn new(layout: CairoLayout) -> Self {
+        Self {
+            layout,
+            entrypoint: "main".to_string(),
+            proof_mode: None,
+            allowed_hints: None,
+        }
+    }
+
+    /// Starts the programs at the function `entrypoint`.
+    pub fn with_entrypoint(mut self, entrypoint: &str) -> Self {
+        self.entrypoint = entrypoint.to_string();
+        self
+    }
+
+    /// Runs the programs outside proof mode, even if they were compiled with
+    /// `--proof_mode`. Their executions can't be proven, see `CairoExecution`.
+    pub fn without_proof_mode(mut self) -> Self {
+        self.proof_mode = Some(false);
+        self
+    }
+
+    /// Only runs programs whose hints are in `hints`, given by their code.
+    /// Hints aren't proven, so these are the ones trusted to compute what the
+    /// program checks.
+    pub fn with_allowed_hints<I, S>(mut self, hints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_hints = Some(hints.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Runs the program given by the content of its compiled JSON file, with
+    /// the hints of the Cairo common library.
+    pub fn run(&self, program: &[u8]) -> Result<CairoExecution, Error> {
+        self.run_with_hint_processor(program, &mut BuiltinHintProcessor::new_empty())
+    }
+
+    /// Same as `run`, with the hints executed by `hint_processor`, for
+    /// programs with custom hints.
+    pub fn run_with_hint_processor(
+        &self,
+        program: &[u8],
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<CairoExecution, Error> {
+        let hints = program_hints(program)?;
+        if let Some(allowed_hints) = &self.allowed_hints {
+            let not_allowed: Vec<_> = hints.difference(allowed_hints).cloned().collect();
+            if !not_allowed.is_empty() {
+                return Err(Error::HintsNotAllowed(not_allowed));
+            }
+        }
+        // Proof mode starts at `__start__`, whatever the entrypoint.
+        let proof_mode = match self.proof_mode {
+            Some(proof_mode) => proof_mode,
+            None => self.entrypoint == "main" && is_proof_mode_program(program)?,
+        };
+        let (mut trace, memory, program_size, builtins) = run_program_content_with_hint_processor(
+            Some(&self.entrypoint),
+            self.layout,
+            program,
+            proof_mode,
+            hint_processor,
+        )?;
+        trace.trim_final_loop();
+        let mut public_input = public_input(&trace, &memory, program_size, builtins)
+            .map_err(Error::MissingOutputCell)?;
+        // A dynamic layout has room for just the builtins the program used.
+        if self.layout == CairoLayout::Dynamic {
+            public_input.builtin_ratios = Some(public_input.dynamic_ratios());
+        }
+        Ok(CairoExecution {
+            raw_trace: (trace, memory),
+            public_input,
+            layout: self.layout.air_layout(),
+            hints: hints.into_iter().collect(),
+        })
+    }
+
+    /// Runs the program compiled to the JSON file at `path`.
+    pub fn run_file(&self, path: &str) -> Result<CairoExecution, Error> {
+        let program = std::fs::read(path).map_err(Error::IO)?;
+        self.run(&program)
+    }
+}
+
+/// Execution of a Cairo program, as the raw trace and public input of a
+/// `CairoAIR`.
+///
+/// Executions in proof mode end in a `jmp rel 0` loop, which is trimmed to
+/// its first step, and the prover pads the trace by repeating that step. The
+/// memory cells the execution doesn't access are filled by the prover.
+/// Executions outside proof mode end at the return of the entrypoint, whose
+/// step can't be repeated, so they can't be proven.
+#[derive(Clone, Debug)]
+pub struct CairoExecution {
+    pub raw_trace: (CairoTrace, CairoMemory),
+    /// The range check bounds are left for the prover to complete.
+    pub public_input: PublicInputs,
+    /// Layout of the AIR matching the one the program ran with. Layouts the
+    /// AIR doesn't have, such as `all_cairo`, leave the builtins unchecked.
+    pub layout: Option<Layout>,
+    /// Codes of the hints of the program. They aren't proven, so the proof
+    /// of the execution trusts them.
+    pub hints: Vec<String>,
+}
+
+impl CairoExecution {
+    /// Execution dumped by `cairo-run` with `--trace_file` and
+    /// `--memory_file`, in proof mode or not, given by the content of those
+    /// files, with the first `program_size` cells of the memory as the
+    /// program. The files don't say which builtins the program used, so the
+    /// execution has none and no layout. Only dumps of runs in proof mode
+    /// can be proven.
+    pub fn from_dumps(
+        trace: &[u8],
+        memory: &[u8],
+        program_size: usize,
+    ) -> Result<Self, CairoImportError> {
+        let mut trace = CairoTrace::from_bytes_le(trace)?;
+        trace.trim_final_loop();
+        let memory = CairoMemory::from_bytes_le(memory)?;
+        if let Some(address) =
+            (1..=program_size as u64).find(|address| memory.get(address).is_none())
+        {
+            return Err(CairoImportError::MissingProgramCell(address));
+        }
+        let public_input = PublicInputs::from_regs_and_mem(&trace, &memory, program_size);
+        Ok(Self {
+            raw_trace: (trace, memory),
+            public_input,
+            layout: None,
+            hints: Vec::new(),
+        })
+    }
+
+    /// Same as `from_dumps`, with the files at `trace_path` and
+    /// `memory_path`.
+    pub fn from_files(
+        trace_path: &str,
+        memory_path: &str,
+        program_size: usize,
+    ) -> Result<Self, CairoImportError> {
+        let trace = std::fs::read(trace_path)?;
+        let memory = std::fs::read(memory_path)?;
+        Self::from_dumps(&trace, &memory, program_size)
+    }
+
+    /// Execution of a Cairo PIE, relocated as `cairo-run` relocates it, with
+    /// the builtins it used and its output. The PIE doesn't have the hints
+    /// nor the layout the program ran with, so the execution has neither.
+    /// PIEs come from runs outside proof mode, which can't be proven.
+    pub fn from_pie(pie: &CairoPie) -> Result<Self, CairoImportError> {
+        let (trace, memory, program_size, builtins) = pie.relocate()?;
+        let public_input = public_input(&trace, &memory, program_size, builtins)
+            .map_err(CairoImportError::MissingOutputCell)?;
+        Ok(Self {
+            raw_trace: (trace, memory),
+            public_input,
+            layout: None,
+            hints: Vec::new(),
+        })
+    }
+
+    /// Same as `from_pie`, with the zip file of the PIE at `path`.
+    #[cfg(feature = "pie")]
+    pub fn from_pie_file(path: &str) -> Result<Self, CairoImportError> {
+        Self::from_pie(&CairoPie::from_file(path)?)
+    }
+
+    /// Whether the last step of the execution is the `jmp rel 0` of the
+    /// final loop of proof mode.
+    pub fn ends_in_final_loop(&self) -> bool {
+        let (trace, memory) = &self.raw_trace;
+        trace.rows.last().map_or(false, |step| {
+            memory.get(&step.pc) == Some(&FE::from(JMP_REL))
+                && memory.get(&(step.pc + 1)) == Some(&FE::zero())
+        })
+    }
+
+    /// AIR of the execution, with a trace long enough for its steps, for its
+    /// layout. It's an error if the execution doesn't end in the final loop
+    /// of proof mode.
+    pub fn air(&self, options: ProofOptions) -> Result<CairoAIR, ProvingError> {
+        if !self.ends_in_final_loop() {
+            return Err(ProvingError::WrongParameter(
+                "The execution doesn't end in the final loop of proof mode, so its trace can't be padded."
+                    .to_string(),
+            ));
+        }
+        let air = CairoAIR::for_public_input(options, &self.public_input)?;
+        Ok(match self.layout {
+            Some(layout) => air.with_layout(layout),
+            None => air,
+        })
+    }
+
+    /// Proves the execution. The public values of the output are the
+    /// statement to verify it against.
+    pub fn prove(&self, options: ProofOptions) -> Result<ProverOutput<CairoAIR>, ProvingError> {
+        prove(&self.raw_trace, &self.air(options)?, &self.public_input)
+    }
+
+    pub fn num_steps(&self) -> usize {
+        self.raw_trace.0.steps()
+    }
+}
+
+/// Public input of an execution, with the segments of the builtins it used
+/// and its output as a public page, or the address of the first cell of the
+/// output the program didn't write.
+fn public_input(
+    trace: &CairoTrace,
+    memory: &CairoMemory,
+    program_size: usize,
+    builtins: BuiltinSegments,
+) -> Result<PublicInputs, u64> {
+    // The output of the program is public, so the proof is bound to every
+    // cell of it.
+    let (output_builtin, output_page) = match builtins.output {
+        Some(output) => {
+            let values = (output.begin_addr..output.stop_ptr)
+                .map(|address| memory.get(&address).cloned().ok_or(address))
+                .collect::<Result<Vec<_>, _>>()?;
+            let page = MemoryPage::new(FE::from(output.begin_addr), values);
+            (Some(output), Some(page))
+        }
+        None => (None, None),
+    };
+    Ok(PublicInputs {
+        range_check_builtin: builtins.range_check,
+        range_check96_builtin: builtins.range_check96,
+        pedersen_builtin: builtins.pedersen,
+        poseidon_builtin: builtins.poseidon,
+        keccak_builtin: builtins.keccak,
+        ec_op_builtin: builtins.ec_op,
+        output_builtin,
+        public_memory: output_page
+            .into_iter()
+            .filter(|page| !page.values.is_empty())
+            .collect(),
+        ..PublicInputs::from_regs_and_mem(trace, memory, program_size)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{air::cairo_air::air::MemorySegment, cairo_run::run::run_program};
+    use std::collections::HashMap;
+
+    #[test]
+    fn execution_is_the_one_of_the_program() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/src/cairo_run/program.json";
+        let execution = CairoRunner::new(CairoLayout::AllCairo)
+            .without_proof_mode()
+            .run_file(&path)
+            .unwrap();
+        let (trace, memory, program_size) =
+            run_program(None, CairoLayout::AllCairo, &path).unwrap();
+
+        assert_eq!(execution.num_steps(), trace.steps());
+        assert_eq!(
+            execution.public_input,
+            PublicInputs::from_regs_and_mem(&trace, &memory, program_size)
+        );
+        assert_eq!(execution.raw_trace, (trace, memory));
+        assert_eq!(execution.layout, None);
+        assert!(execution.hints.is_empty());
+
+        // The program wasn't compiled with `--proof_mode`, so it runs outside
+        // proof mode by default.
+        let default_execution = CairoRunner::new(CairoLayout::AllCairo)
+            .run_file(&path)
+            .unwrap();
+        assert_eq!(default_execution.raw_trace, execution.raw_trace);
+
+        // It ends at the return of `main`, so it can't be proven.
+        assert!(!execution.ends_in_final_loop());
+        assert!(matches!(
+            execution.prove(ProofOptions::new(4, 3, 3)),
+            Err(ProvingError::WrongParameter(_))
+        ));
+    }
+
+    #[test]
+    fn executions_are_read_from_the_dumps_of_cairo_run() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/src/cairo_run/program.json";
+        let execution = CairoRunner::new(CairoLayout::AllCairo)
+            .without_proof_mode()
+            .run_file(&path)
+            .unwrap();
+        let (trace, memory) = &execution.raw_trace;
+        let program_size = execution.public_input.program.len();
+
+        let dumped =
+            CairoExecution::from_dumps(&trace.to_bytes_le(), &memory.to_bytes_le(), program_size)
+                .unwrap();
+        assert_eq!(dumped.raw_trace, execution.raw_trace);
+        assert_eq!(dumped.public_input, execution.public_input);
+
+        assert!(matches!(
+            CairoExecution::from_dumps(&[], &memory.to_bytes_le(), program_size),
+            Err(CairoImportError::EmptyTrace)
+        ));
+        let too_large = memory.len() + 1;
+        assert!(matches!(
+            CairoExecution::from_dumps(&trace.to_bytes_le(), &memory.to_bytes_le(), too_large),
+            Err(CairoImportError::MissingProgramCell(_))
+        ));
+    }
+
+    #[test]
+    fn the_whole_output_is_public() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/src/cairo_run/program.json";
+        let (trace, memory, program_size) =
+            run_program(None, CairoLayout::AllCairo, &path).unwrap();
+        // The program followed by an output with a hole at 101.
+        let mut cells: HashMap<_, _> = (1..=program_size as u64)
+            .map(|address| (address, memory.get(&address).unwrap().clone()))
+            .collect();
+        cells.insert(100, FE::from(1));
+        cells.insert(102, FE::from(3));
+        let memory = CairoMemory::new(cells);
+        let builtins = |stop_ptr| BuiltinSegments {
+            output: Some(MemorySegment {
+                begin_addr: 100,
+                stop_ptr,
+            }),
+            ..Default::default()
+        };
+
+        let input = public_input(&trace, &memory, program_size, builtins(101)).unwrap();
+        assert_eq!(input.output_builtin, builtins(101).output);
+        assert_eq!(
+            input.public_memory,
+            vec![MemoryPage::new(FE::from(100), vec![FE::from(1)])]
+        );
+
+        // The cell after the hole isn't dropped, the output is rejected.
+        assert_eq!(
+            public_input(&trace, &memory, program_size, builtins(103)),
+            Err(101)
+        );
+    }
+
+    #[test]
+    fn programs_with_hints_that_are_not_allowed_are_not_run() {
+        let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/src/cairo_run/program.json";
+        let runner = CairoRunner::new(CairoLayout::AllCairo)
+            .without_proof_mode()
+            .with_allowed_hints(["memory[ap] = 1"]);
+        assert!(runner.run_file(&path).is_ok());
+
+        let mut program: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        program["hints"] = serde_json::json!({
+            "0": [{"code": "memory[ap] = 2", "accessible_scopes": []}]
+        });
+        let program = serde_json::to_vec(&program).unwrap();
+        assert!(matches!(
+            runner.run(&program),
+            Err(Error::HintsNotAllowed(hints)) if hints == ["memory[ap] = 2"]
+        ));
+    }
+}
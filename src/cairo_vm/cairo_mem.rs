@@ -30,6 +30,15 @@ impl CairoMemory {
         self.data.is_empty()
     }
 
+    /// Addresses from 1 to the largest one of the memory without a value, the
+    /// cells the execution left unassigned, in increasing order.
+    pub fn holes(&self) -> Vec<u64> {
+        let last_address = self.data.keys().max().copied().unwrap_or(0);
+        (1..last_address)
+            .filter(|address| !self.data.contains_key(address))
+            .collect()
+    }
+
     pub fn from_bytes_le(bytes: &[u8]) -> Result<Self, CairoImportError> {
         // Each row is an 8 bytes address
         // and a value of 32 bytes (which is a field)
@@ -62,6 +71,19 @@ impl CairoMemory {
         let data = fs::read(path)?;
         Self::from_bytes_le(&data)
     }
+
+    /// Encodes the memory in the layout read by `from_bytes_le`, in increasing
+    /// order of address.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut addresses: Vec<_> = self.data.keys().collect();
+        addresses.sort();
+        let mut bytes = Vec::with_capacity(40 * addresses.len());
+        for address in addresses {
+            bytes.extend_from_slice(&address.to_le_bytes());
+            bytes.extend_from_slice(&self.data[address].to_bytes_le());
+        }
+        bytes
+    }
 }
 
 #[cfg(test)]
@@ -127,4 +149,29 @@ mod tests {
             assert_eq!(addr, (i + 1) as u64);
         }
     }
+
+    #[test]
+    fn memory_round_trips_through_bytes() {
+        let base_dir = env!("CARGO_MANIFEST_DIR");
+        let bytes = fs::read(base_dir.to_owned() + "/src/cairo_vm/test_data/mul_mem.out").unwrap();
+
+        let memory = CairoMemory::from_bytes_le(&bytes).unwrap();
+        let decoded = CairoMemory::from_bytes_le(&memory.to_bytes_le()).unwrap();
+        assert_eq!(decoded, memory);
+    }
+
+    #[test]
+    fn holes_are_the_addresses_without_values() {
+        let data = [1, 2, 5, 7]
+            .into_iter()
+            .map(|address| (address, FE::from(address)))
+            .collect();
+        assert_eq!(CairoMemory::new(data).holes(), vec![3, 4, 6]);
+
+        let base_dir = env!("CARGO_MANIFEST_DIR");
+        let memory =
+            CairoMemory::from_file(&(base_dir.to_owned() + "/src/cairo_vm/test_data/mul_mem.out"))
+                .unwrap();
+        assert!(memory.holes().is_empty());
+    }
 }
@@ -0,0 +1,461 @@
+//! Cairo PIEs, the position independent executions `cairo-run` writes with
+//! `--cairo_pie_output`: the memory of an execution by segment, the size of
+//! each segment and the builtins the program used.
+//!
+//! The segments are relocated as `cairo-run` relocates them, one after the
+//! other from the address 1, in the order of their indexes. A PIE has no
+//! trace, so the trace is replayed from the memory: it holds every cell the
+//! execution wrote, the ones of the hints too, so each step is given by the
+//! cells it reads. The hints themselves aren't part of the PIE.
+
+use std::collections::HashMap;
+
+use lambdaworks_math::traits::ByteConversion;
+use serde_json::Value;
+
+use super::{
+    cairo_mem::CairoMemory,
+    cairo_trace::{CairoTrace, RegistersState},
+    errors::{CairoImportError, InstructionDecodingError},
+    instruction_flags::{
+        ApUpdate, CairoInstructionFlags, CairoOpcode, DstReg, Op0Reg, Op1Src, PcUpdate, ResLogic,
+    },
+    instruction_offsets::InstructionOffsets,
+};
+use crate::{
+    air::cairo_air::{air::address, air::MemorySegment, ec_op, keccak},
+    cairo_run::run::BuiltinSegments,
+    FE,
+};
+
+/// Bits of the offset of a relocatable value of `memory.bin`, which is
+/// `segment · 2^47 + offset` with the top bit set.
+const OFFSET_BITS: u32 = 47;
+
+/// Address of a cell in a segment, before the relocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Relocatable {
+    pub segment: usize,
+    pub offset: u64,
+}
+
+/// Value of a cell of a PIE: a felt, or a pointer to a cell of a segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PieValue {
+    Felt(FE),
+    Relocatable(Relocatable),
+}
+
+/// Cairo PIE, as the files of its zip: `metadata.json`, `memory.bin` and
+/// `execution_resources.json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CairoPie {
+    /// Offset of `main` in the program segment, where the execution starts.
+    pub main: u64,
+    /// Number of builtins of the program, whose pointers are the first
+    /// arguments of `main`.
+    pub program_builtins: usize,
+    pub program_segment: usize,
+    pub execution_segment: usize,
+    pub ret_fp_segment: usize,
+    pub ret_pc_segment: usize,
+    /// Segment of each builtin the execution used, by its name.
+    pub builtin_segments: Vec<(String, usize)>,
+    /// Size of each segment, by its index.
+    pub segment_sizes: Vec<u64>,
+    pub memory: Vec<(Relocatable, PieValue)>,
+    pub n_steps: usize,
+}
+
+impl CairoPie {
+    /// PIE given by the content of its files.
+    pub fn from_parts(
+        metadata: &[u8],
+        memory: &[u8],
+        execution_resources: &[u8],
+    ) -> Result<Self, CairoImportError> {
+        let metadata: Value = serde_json::from_slice(metadata)?;
+        let execution_resources: Value = serde_json::from_slice(execution_resources)?;
+
+        let mut segment_sizes = Vec::new();
+        let mut segment = |value: &Value, name: &str| -> Result<usize, CairoImportError> {
+            let index = json_u64(&value["index"], name)? as usize;
+            let size = json_u64(&value["size"], name)?;
+            if segment_sizes.len() <= index {
+                segment_sizes.resize(index + 1, 0);
+            }
+            segment_sizes[index] = size;
+            Ok(index)
+        };
+        let program_segment = segment(&metadata["program_segment"], "program_segment")?;
+        let execution_segment = segment(&metadata["execution_segment"], "execution_segment")?;
+        let ret_fp_segment = segment(&metadata["ret_fp_segment"], "ret_fp_segment")?;
+        let ret_pc_segment = segment(&metadata["ret_pc_segment"], "ret_pc_segment")?;
+        let builtin_segments = match metadata["builtin_segments"].as_object() {
+            Some(builtins) => builtins
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), segment(value, name)?)))
+                .collect::<Result<_, CairoImportError>>()?,
+            None => Vec::new(),
+        };
+        if let Some(extra_segments) = metadata["extra_segments"].as_array() {
+            for value in extra_segments {
+                segment(value, "extra_segments")?;
+            }
+        }
+        let program_builtins = metadata["program"]["builtins"]
+            .as_array()
+            .map_or(0, Vec::len);
+
+        Ok(Self {
+            main: json_u64(&metadata["program"]["main"], "main")?,
+            program_builtins,
+            program_segment,
+            execution_segment,
+            ret_fp_segment,
+            ret_pc_segment,
+            builtin_segments,
+            segment_sizes,
+            memory: memory_from_bytes_le(memory)?,
+            n_steps: json_u64(&execution_resources["n_steps"], "n_steps")? as usize,
+        })
+    }
+
+    /// PIE given by the content of its zip file.
+    #[cfg(feature = "pie")]
+    pub fn from_zip(bytes: &[u8]) -> Result<Self, CairoImportError> {
+        use std::io::Read;
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+        let mut read = |name: &str| -> Result<Vec<u8>, CairoImportError> {
+            let mut content = Vec::new();
+            archive.by_name(name)?.read_to_end(&mut content)?;
+            Ok(content)
+        };
+        let metadata = read("metadata.json")?;
+        let memory = read("memory.bin")?;
+        let execution_resources = read("execution_resources.json")?;
+        Self::from_parts(&metadata, &memory, &execution_resources)
+    }
+
+    /// Same as `from_zip`, with the file at `path`.
+    #[cfg(feature = "pie")]
+    pub fn from_file(path: &str) -> Result<Self, CairoImportError> {
+        Self::from_zip(&std::fs::read(path)?)
+    }
+
+    /// Relocates the execution, and replays its trace from the memory.
+    /// Returns the trace, the memory, the program size and the segments of
+    /// the builtins, as the runner of `cairo_run` does.
+    pub fn relocate(
+        &self,
+    ) -> Result<(CairoTrace, CairoMemory, usize, BuiltinSegments), CairoImportError> {
+        let mut bases = Vec::with_capacity(self.segment_sizes.len());
+        let mut next_base = 1;
+        for size in self.segment_sizes.iter() {
+            bases.push(next_base);
+            next_base += size;
+        }
+        let relocate = |value: &Relocatable| -> Result<u64, CairoImportError> {
+            bases
+                .get(value.segment)
+                .map(|base| base + value.offset)
+                .ok_or_else(|| {
+                    CairoImportError::InvalidPie(format!("there is no segment {}", value.segment))
+                })
+        };
+
+        let mut memory = HashMap::with_capacity(self.memory.len());
+        for (cell, value) in self.memory.iter() {
+            if cell.offset >= self.segment_sizes.get(cell.segment).copied().unwrap_or(0) {
+                return Err(CairoImportError::InvalidPie(format!(
+                    "the cell {}:{} is out of its segment",
+                    cell.segment, cell.offset
+                )));
+            }
+            let value = match value {
+                PieValue::Felt(felt) => felt.clone(),
+                PieValue::Relocatable(pointer) => FE::from(relocate(pointer)?),
+            };
+            memory.insert(relocate(cell)?, value);
+        }
+
+        let execution_base = relocate(&Relocatable {
+            segment: self.execution_segment,
+            offset: 0,
+        })?;
+        let frame = execution_base + self.program_builtins as u64 + 2;
+        let mut state = RegistersState {
+            pc: relocate(&Relocatable {
+                segment: self.program_segment,
+                offset: self.main,
+            })?,
+            fp: frame,
+            ap: frame,
+        };
+        let final_pc = relocate(&Relocatable {
+            segment: self.ret_pc_segment,
+            offset: 0,
+        })?;
+        let mut rows = Vec::with_capacity(self.n_steps);
+        while state.pc != final_pc {
+            if rows.len() == self.n_steps {
+                return Err(CairoImportError::InvalidPie(format!(
+                    "the execution doesn't return in {} steps",
+                    self.n_steps
+                )));
+            }
+            let next_state = step(&state, &memory)?;
+            rows.push(state);
+            state = next_state;
+        }
+        if rows.len() != self.n_steps {
+            return Err(CairoImportError::InvalidPie(format!(
+                "the execution returns in {} steps instead of {}",
+                rows.len(),
+                self.n_steps
+            )));
+        }
+        if rows.is_empty() {
+            return Err(CairoImportError::EmptyTrace);
+        }
+
+        let builtin = |name: &str, cells_per_instance: u64| -> Result<_, CairoImportError> {
+            let index = match self.builtin_segments.iter().find(|(n, _)| n == name) {
+                Some((_, index)) => *index,
+                None => return Ok(None),
+            };
+            let used_cells = self.segment_sizes[index];
+            if used_cells == 0 {
+                return Ok(None);
+            }
+            let begin_addr = relocate(&Relocatable {
+                segment: index,
+                offset: 0,
+            })?;
+            let instances = (used_cells + cells_per_instance - 1) / cells_per_instance;
+            Ok(Some(MemorySegment {
+                begin_addr,
+                stop_ptr: begin_addr + instances * cells_per_instance,
+            }))
+        };
+        let builtins = BuiltinSegments {
+            range_check: builtin("range_check", 1)?,
+            range_check96: builtin("range_check96", 1)?,
+            pedersen: builtin("pedersen", 3)?,
+            poseidon: builtin("poseidon", 6)?,
+            keccak: builtin("keccak", keccak::CELLS as u64)?,
+            ec_op: builtin("ec_op", ec_op::CELLS as u64)?,
+            output: builtin("output", 1)?,
+        };
+        let program_size = self.segment_sizes[self.program_segment] as usize;
+
+        Ok((
+            CairoTrace { rows },
+            CairoMemory::new(memory),
+            program_size,
+            builtins,
+        ))
+    }
+}
+
+fn json_u64(value: &Value, name: &str) -> Result<u64, CairoImportError> {
+    value
+        .as_u64()
+        .ok_or_else(|| CairoImportError::InvalidPie(format!("the metadata has no valid {name}")))
+}
+
+/// Reads `memory.bin`: rows of an 8 bytes address and a 32 bytes value, both
+/// little endian, where the top bit marks the relocatable ones.
+fn memory_from_bytes_le(bytes: &[u8]) -> Result<Vec<(Relocatable, PieValue)>, CairoImportError> {
+    const ROW_SIZE: usize = 8 + 32;
+
+    if bytes.len() % ROW_SIZE != 0 {
+        return Err(CairoImportError::IncorrectNumberOfBytes);
+    }
+    let relocatable = |bytes: &[u8]| {
+        let value = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        Relocatable {
+            segment: ((value >> OFFSET_BITS) & 0xffff) as usize,
+            offset: value & ((1 << OFFSET_BITS) - 1),
+        }
+    };
+    bytes
+        .chunks(ROW_SIZE)
+        .map(|row| {
+            let (cell, value) = row.split_at(8);
+            if cell[7] & 0x80 == 0 {
+                return Err(CairoImportError::InvalidPie(
+                    "an address of the memory is not relocatable".to_string(),
+                ));
+            }
+            let value = if value[31] & 0x80 != 0 {
+                PieValue::Relocatable(relocatable(value))
+            } else {
+                PieValue::Felt(FE::from_bytes_le(value).map_err(|_| {
+                    CairoImportError::InvalidPie("a value of the memory is not a felt".to_string())
+                })?)
+            };
+            Ok((relocatable(cell), value))
+        })
+        .collect()
+}
+
+/// Registers after the instruction at `state.pc`, as the Cairo VM runs it.
+fn step(
+    state: &RegistersState,
+    memory: &HashMap<u64, FE>,
+) -> Result<RegistersState, CairoImportError> {
+    let cell = |address: u64| {
+        memory.get(&address).ok_or_else(|| {
+            CairoImportError::InvalidPie(format!("the memory has no cell at {address}"))
+        })
+    };
+    let to_address = |value: &FE| {
+        address(value).ok_or_else(|| {
+            CairoImportError::InvalidPie(format!(
+                "the step at pc {} has no valid address",
+                state.pc
+            ))
+        })
+    };
+    let offset = |base: u64, offset: i32| {
+        base.checked_add_signed(offset.into()).ok_or_else(|| {
+            CairoImportError::InvalidPie(format!(
+                "the step at pc {} has no valid address",
+                state.pc
+            ))
+        })
+    };
+
+    let instruction = memory
+        .get(&state.pc)
+        .ok_or(InstructionDecodingError::InstructionNotFound)?;
+    let flags = CairoInstructionFlags::try_from(instruction)?;
+    let offsets = InstructionOffsets::new(instruction);
+
+    let dst_base = match flags.dst_reg {
+        DstReg::AP => state.ap,
+        DstReg::FP => state.fp,
+    };
+    let dst = cell(offset(dst_base, offsets.off_dst)?)?;
+    let op0_base = match flags.op0_reg {
+        Op0Reg::AP => state.ap,
+        Op0Reg::FP => state.fp,
+    };
+    let op0 = cell(offset(op0_base, offsets.off_op0)?)?;
+    let (op1_base, size) = match flags.op1_src {
+        Op1Src::Op0 => (to_address(op0)?, 1),
+        Op1Src::Imm => (state.pc, 2),
+        Op1Src::AP => (state.ap, 1),
+        Op1Src::FP => (state.fp, 1),
+    };
+    let op1 = cell(offset(op1_base, offsets.off_op1)?)?;
+    let res = match flags.res_logic {
+        ResLogic::Op1 => op1.clone(),
+        ResLogic::Add => op0 + op1,
+        ResLogic::Mul => op0 * op1,
+        ResLogic::Unconstrained => FE::zero(),
+    };
+
+    let pc = match flags.pc_update {
+        PcUpdate::Regular => state.pc + size,
+        PcUpdate::Jump => to_address(&res)?,
+        PcUpdate::JumpRel => to_address(&(FE::from(state.pc) + res))?,
+        PcUpdate::Jnz if *dst == FE::zero() => state.pc + size,
+        PcUpdate::Jnz => to_address(&(FE::from(state.pc) + op1))?,
+    };
+    let (ap, fp) = match flags.opcode {
+        CairoOpcode::Call => (state.ap + 2, state.ap + 2),
+        opcode => {
+            let ap = match flags.ap_update {
+                ApUpdate::Regular => state.ap,
+                ApUpdate::Add => to_address(&(FE::from(state.ap) + res))?,
+                ApUpdate::Add1 => state.ap + 1,
+                ApUpdate::Add2 => state.ap + 2,
+            };
+            let fp = match opcode {
+                CairoOpcode::Ret => to_address(dst)?,
+                _ => state.fp,
+            };
+            (ap, fp)
+        }
+    };
+    Ok(RegistersState { pc, fp, ap })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relocatable_bytes(segment: u64, offset: u64) -> u64 {
+        1 << 63 | segment << OFFSET_BITS | offset
+    }
+
+    /// PIE of `simple_program`: its five cells of program, and an execution
+    /// segment with the return fp, the return pc and the cell it writes.
+    fn simple_program_pie(n_steps: usize) -> CairoPie {
+        let base_dir = env!("CARGO_MANIFEST_DIR");
+        let dumped = CairoMemory::from_file(
+            &(base_dir.to_owned() + "/src/cairo_vm/test_data/simple_program.memory"),
+        )
+        .unwrap();
+        let mut memory = Vec::new();
+        let mut push = |segment: u64, offset: u64, value: &[u8]| {
+            memory.extend(relocatable_bytes(segment, offset).to_le_bytes());
+            memory.extend(value);
+        };
+        for offset in 0..5 {
+            push(0, offset, &dumped.get(&(offset + 1)).unwrap().to_bytes_le());
+        }
+        let mut pointer = [0; 32];
+        pointer[..8].copy_from_slice(&relocatable_bytes(2, 0).to_le_bytes());
+        push(1, 0, &pointer);
+        pointer[..8].copy_from_slice(&relocatable_bytes(3, 0).to_le_bytes());
+        push(1, 1, &pointer);
+        push(1, 2, &FE::from(3).to_bytes_le());
+
+        let metadata = serde_json::json!({
+            "program": {"main": 0, "builtins": []},
+            "program_segment": {"index": 0, "size": 5},
+            "execution_segment": {"index": 1, "size": 3},
+            "ret_fp_segment": {"index": 2, "size": 0},
+            "ret_pc_segment": {"index": 3, "size": 0},
+            "builtin_segments": {},
+            "extra_segments": [],
+        });
+        let execution_resources = serde_json::json!({ "n_steps": n_steps });
+        CairoPie::from_parts(
+            metadata.to_string().as_bytes(),
+            &memory,
+            execution_resources.to_string().as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pies_are_relocated_and_their_trace_is_replayed() {
+        let base_dir = env!("CARGO_MANIFEST_DIR").to_owned() + "/src/cairo_vm/test_data/";
+        let (trace, memory, program_size, builtins) = simple_program_pie(3).relocate().unwrap();
+
+        assert_eq!(
+            trace,
+            CairoTrace::from_file(&(base_dir.clone() + "simple_program.trace")).unwrap()
+        );
+        assert_eq!(
+            memory,
+            CairoMemory::from_file(&(base_dir + "simple_program.memory")).unwrap()
+        );
+        assert_eq!(program_size, 5);
+        assert_eq!(builtins, BuiltinSegments::default());
+
+        assert!(matches!(
+            simple_program_pie(2).relocate(),
+            Err(CairoImportError::InvalidPie(_))
+        ));
+        assert!(matches!(
+            simple_program_pie(4).relocate(),
+            Err(CairoImportError::InvalidPie(_))
+        ));
+    }
+}
@@ -57,6 +57,9 @@ impl CairoTrace {
         if bytes.len() % ROW_SIZE != 0 {
             return Err(CairoImportError::IncorrectNumberOfBytes);
         }
+        if bytes.is_empty() {
+            return Err(CairoImportError::EmptyTrace);
+        }
         let num_rows = bytes.len() / ROW_SIZE;
 
         let mut rows: Vec<RegistersState> = Vec::with_capacity(num_rows);
@@ -84,6 +87,25 @@ impl CairoTrace {
         let data = fs::read(path)?;
         Self::from_bytes_le(&data)
     }
+
+    /// Removes the steps of the final `jmp rel 0` loop but the first one.
+    /// Runs in proof mode repeat it until the steps are a power of two, and
+    /// a step with the same registers as the one before it can only be one of
+    /// these. Other traces are left as they are.
+    pub fn trim_final_loop(&mut self) {
+        if let Some(end) = self.rows.windows(2).position(|pair| pair[0] == pair[1]) {
+            self.rows.truncate(end + 1);
+        }
+    }
+
+    /// Encodes the trace in the layout read by `from_bytes_le`.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        self.rows
+            .iter()
+            .flat_map(|row| [row.ap, row.fp, row.pc])
+            .flat_map(u64::to_le_bytes)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -149,6 +171,20 @@ mod tests {
         assert_eq!(trace.unwrap(), expected_trace)
     }
 
+    #[test]
+    fn final_loop_is_trimmed_to_its_first_step() {
+        let row = |pc, ap| RegistersState { pc, ap, fp: 10 };
+        let mut trace = CairoTrace {
+            rows: vec![row(1, 10), row(3, 11), row(5, 12), row(5, 12), row(5, 12)],
+        };
+        trace.trim_final_loop();
+        assert_eq!(trace.rows, [row(1, 10), row(3, 11), row(5, 12)]);
+
+        let untouched = trace.clone();
+        trace.trim_final_loop();
+        assert_eq!(trace, untouched);
+    }
+
     #[test]
     fn wrong_amount_of_bytes_gives_err() {
         let bytes = hex::decode("080000000000").unwrap();
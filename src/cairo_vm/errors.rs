@@ -6,6 +6,21 @@ pub enum CairoImportError {
     IncorrectNumberOfBytes,
     #[error("IO Error")]
     FileError(#[from] std::io::Error),
+    #[error("Trace has no steps")]
+    EmptyTrace,
+    #[error("Program cell at address {0} is not in the memory")]
+    MissingProgramCell(u64),
+    #[error("Output cell at address {0} is not in the memory")]
+    MissingOutputCell(u64),
+    #[error("Invalid Cairo PIE: {0}")]
+    InvalidPie(String),
+    #[error("The metadata of the Cairo PIE is not valid JSON")]
+    PieMetadata(#[from] serde_json::Error),
+    #[error(transparent)]
+    InvalidInstruction(#[from] InstructionDecodingError),
+    #[cfg(feature = "pie")]
+    #[error("The Cairo PIE is not a valid zip file")]
+    Zip(#[from] zip::result::ZipError),
 }
 
 #[derive(Error, Debug, PartialEq)]
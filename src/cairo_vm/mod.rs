@@ -1,4 +1,5 @@
 pub mod cairo_mem;
+pub mod cairo_pie;
 pub mod cairo_trace;
 pub mod errors;
 pub mod execution_trace;
@@ -0,0 +1,436 @@
+//! On-disk format of the prover checkpoints written by `prove_with_checkpoints`.
+//!
+//! A checkpoint holds the polynomials and LDE evaluations of every completed
+//! round. The Merkle trees are rebuilt from the evaluations, and the transcript
+//! is rebuilt by replaying the commitments, so neither is stored.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    polynomial::Polynomial,
+    traits::ByteConversion,
+};
+
+use crate::{air::trace::TraceTable, IsChallengeField};
+
+const MAGIC: &[u8; 8] = b"STARKCKP";
+const VERSION: u32 = 1;
+
+/// Parameters of the proof a checkpoint belongs to. A checkpoint is only
+/// resumed if they match the ones of the AIR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CheckpointHeader {
+    pub trace_length: u64,
+    pub blowup_factor: u64,
+    pub coset_offset: u64,
+    pub main_columns: u64,
+    pub composition_parts: u64,
+    /// Digest of the public input and the roots of the main trace.
+    pub digest: [u8; 32],
+}
+
+/// Composition polynomial parts Hᵢ with their LDE evaluations.
+pub(crate) struct CompositionCheckpoint<E: IsField> {
+    pub parts: Vec<Polynomial<FieldElement<E>>>,
+    pub evaluations: Vec<Vec<FieldElement<E>>>,
+}
+
+/// Data of the rounds completed when the checkpoint was written. `composition`
+/// is `None` if only round 1 was.
+pub(crate) struct Checkpoint<F: IsFFTField, E: IsField> {
+    pub header: CheckpointHeader,
+    pub trace_polys: Vec<Polynomial<FieldElement<F>>>,
+    pub lde_trace: TraceTable<F>,
+    /// Columns over the challenge field, empty if the AIR has none.
+    pub extension_polys: Vec<Polynomial<FieldElement<E>>>,
+    pub lde_extension_trace: TraceTable<E>,
+    pub composition: Option<CompositionCheckpoint<E>>,
+}
+
+/// Borrowed version of `Checkpoint`, so that writing doesn't copy the LDE.
+pub(crate) struct CheckpointRef<'a, F: IsFFTField, E: IsField> {
+    pub header: CheckpointHeader,
+    pub trace_polys: &'a [Polynomial<FieldElement<F>>],
+    pub lde_trace: &'a TraceTable<F>,
+    pub extension_polys: &'a [Polynomial<FieldElement<E>>],
+    pub lde_extension_trace: &'a TraceTable<E>,
+    pub composition: Option<Vec<(&'a Polynomial<FieldElement<E>>, &'a [FieldElement<E>])>>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Reader of a checkpoint file that counts the bytes left in it, so that
+/// the lengths it holds are checked before anything is allocated for them.
+struct CheckpointReader<R: Read> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: Read> Read for CheckpointReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.remaining = self.remaining.saturating_sub(read as u64);
+        Ok(read)
+    }
+}
+
+/// Reads a length, checking that the rest of the file can hold that many
+/// items of at least `min_item_size` bytes.
+fn read_len<R: Read>(reader: &mut CheckpointReader<R>, min_item_size: u64) -> io::Result<usize> {
+    let len = read_u64(reader)?;
+    if len.saturating_mul(min_item_size) > reader.remaining {
+        return Err(invalid_data("length exceeds the size of the checkpoint"));
+    }
+    usize::try_from(len).map_err(|_| invalid_data("length doesn't fit in usize"))
+}
+
+/// Encoding of the elements of a field: the `ByteConversion` of the trace
+/// field, or the one of the `IsChallengeField` implementation.
+struct Codec<F: IsField> {
+    to_bytes: fn(&FieldElement<F>) -> Vec<u8>,
+    from_bytes: fn(&[u8]) -> Option<FieldElement<F>>,
+}
+
+impl<F: IsField> Codec<F> {
+    fn trace_field() -> Self
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        Self {
+            to_bytes: |element| element.to_bytes_be(),
+            from_bytes: |bytes| FieldElement::from_bytes_be(bytes).ok(),
+        }
+    }
+
+    fn challenge_field<B: IsFFTField>() -> Self
+    where
+        F: IsChallengeField<B>,
+    {
+        Self {
+            to_bytes: F::to_bytes_be,
+            from_bytes: F::from_bytes_be,
+        }
+    }
+}
+
+fn write_elements<F: IsField>(
+    writer: &mut impl Write,
+    elements: &[FieldElement<F>],
+    codec: &Codec<F>,
+) -> io::Result<()> {
+    write_u64(writer, elements.len() as u64)?;
+    for element in elements {
+        let bytes = (codec.to_bytes)(element);
+        writer.write_all(&[bytes.len() as u8])?;
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+fn read_elements<F: IsField, R: Read>(
+    reader: &mut CheckpointReader<R>,
+    codec: &Codec<F>,
+) -> io::Result<Vec<FieldElement<F>>> {
+    // Each element takes its size byte and at least a byte of value.
+    let len = read_len(reader, 2)?;
+    let mut elements = Vec::with_capacity(len);
+    let mut bytes = [0; 255];
+    for _ in 0..len {
+        let mut size = [0];
+        reader.read_exact(&mut size)?;
+        let bytes = &mut bytes[..size[0] as usize];
+        reader.read_exact(bytes)?;
+        let element =
+            (codec.from_bytes)(bytes).ok_or_else(|| invalid_data("invalid field element"))?;
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+fn write_polys<F: IsField>(
+    writer: &mut impl Write,
+    polys: &[&Polynomial<FieldElement<F>>],
+    codec: &Codec<F>,
+) -> io::Result<()> {
+    write_u64(writer, polys.len() as u64)?;
+    for poly in polys {
+        write_elements(writer, poly.coefficients(), codec)?;
+    }
+    Ok(())
+}
+
+fn read_polys<F: IsField, R: Read>(
+    reader: &mut CheckpointReader<R>,
+    codec: &Codec<F>,
+) -> io::Result<Vec<Polynomial<FieldElement<F>>>> {
+    // Each polynomial takes at least the length of its coefficients.
+    let len = read_len(reader, 8)?;
+    (0..len)
+        .map(|_| Ok(Polynomial::new(&read_elements(reader, codec)?)))
+        .collect()
+}
+
+/// Writes `checkpoint` to `path`. The data goes to a temporary file first,
+/// which then replaces `path`, so an interrupted write leaves the previous
+/// checkpoint intact.
+pub(crate) fn save<F, E>(path: &Path, checkpoint: CheckpointRef<F, E>) -> io::Result<()>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    FieldElement<F>: ByteConversion,
+{
+    let trace_codec = Codec::trace_field();
+    let challenge_codec = Codec::challenge_field::<F>();
+    let temporary_path = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_be_bytes())?;
+    let header = &checkpoint.header;
+    write_u64(&mut writer, header.trace_length)?;
+    write_u64(&mut writer, header.blowup_factor)?;
+    write_u64(&mut writer, header.coset_offset)?;
+    write_u64(&mut writer, header.main_columns)?;
+    write_u64(&mut writer, header.composition_parts)?;
+    writer.write_all(&header.digest)?;
+
+    let trace_polys: Vec<_> = checkpoint.trace_polys.iter().collect();
+    write_polys(&mut writer, &trace_polys, &trace_codec)?;
+    write_u64(&mut writer, checkpoint.lde_trace.n_cols as u64)?;
+    write_elements(&mut writer, &checkpoint.lde_trace.table, &trace_codec)?;
+
+    let extension_polys: Vec<_> = checkpoint.extension_polys.iter().collect();
+    write_polys(&mut writer, &extension_polys, &challenge_codec)?;
+    write_u64(&mut writer, checkpoint.lde_extension_trace.n_cols as u64)?;
+    write_elements(
+        &mut writer,
+        &checkpoint.lde_extension_trace.table,
+        &challenge_codec,
+    )?;
+
+    match checkpoint.composition {
+        None => writer.write_all(&[0])?,
+        Some(parts) => {
+            writer.write_all(&[1])?;
+            write_u64(&mut writer, parts.len() as u64)?;
+            for (poly, evaluations) in parts {
+                write_polys(&mut writer, &[poly], &challenge_codec)?;
+                write_elements(&mut writer, evaluations, &challenge_codec)?;
+            }
+        }
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(temporary_path, path)
+}
+
+/// Reads the checkpoint stored at `path`.
+pub(crate) fn load<F, E>(path: &Path) -> io::Result<Checkpoint<F, E>>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    FieldElement<F>: ByteConversion,
+{
+    let trace_codec = Codec::trace_field();
+    let challenge_codec = Codec::challenge_field::<F>();
+    let file = File::open(path)?;
+    let mut reader = CheckpointReader {
+        remaining: file.metadata()?.len(),
+        inner: BufReader::new(file),
+    };
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a prover checkpoint"));
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    if u32::from_be_bytes(version) != VERSION {
+        return Err(invalid_data("unsupported checkpoint version"));
+    }
+
+    let mut header = CheckpointHeader {
+        trace_length: read_u64(&mut reader)?,
+        blowup_factor: read_u64(&mut reader)?,
+        coset_offset: read_u64(&mut reader)?,
+        main_columns: read_u64(&mut reader)?,
+        composition_parts: read_u64(&mut reader)?,
+        digest: [0; 32],
+    };
+    reader.read_exact(&mut header.digest)?;
+
+    let trace_polys = read_polys(&mut reader, &trace_codec)?;
+    let n_cols = read_len(&mut reader, 0)?;
+    let table = read_elements(&mut reader, &trace_codec)?;
+    if n_cols == 0 || n_cols > table.len() || table.len() % n_cols != 0 {
+        return Err(invalid_data("malformed LDE trace"));
+    }
+
+    let extension_polys = read_polys(&mut reader, &challenge_codec)?;
+    let extension_cols = read_len(&mut reader, 0)?;
+    let extension_table = read_elements(&mut reader, &challenge_codec)?;
+    if (extension_cols == 0 && !extension_table.is_empty())
+        || (extension_cols > 0
+            && (extension_cols > extension_table.len()
+                || extension_table.len() % extension_cols != 0))
+    {
+        return Err(invalid_data("malformed LDE of the extension columns"));
+    }
+
+    let mut has_composition = [0];
+    reader.read_exact(&mut has_composition)?;
+    let composition = match has_composition[0] {
+        0 => None,
+        1 => {
+            // Each part takes at least the lengths of its polynomials, of its
+            // coefficients and of its evaluations.
+            let len = read_len(&mut reader, 24)?;
+            if len as u64 != header.composition_parts {
+                return Err(invalid_data("wrong number of composition polynomial parts"));
+            }
+            let mut parts = Vec::with_capacity(len);
+            let mut evaluations = Vec::with_capacity(len);
+            for _ in 0..len {
+                let poly = read_polys(&mut reader, &challenge_codec)?
+                    .pop()
+                    .ok_or_else(|| invalid_data("missing composition polynomial"))?;
+                parts.push(poly);
+                evaluations.push(read_elements(&mut reader, &challenge_codec)?);
+            }
+            Some(CompositionCheckpoint { parts, evaluations })
+        }
+        _ => return Err(invalid_data("malformed composition polynomial")),
+    };
+
+    Ok(Checkpoint {
+        header,
+        trace_polys,
+        lde_trace: TraceTable::new(table, n_cols),
+        extension_polys,
+        lde_extension_trace: TraceTable::new(extension_table, extension_cols),
+        composition,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn checkpoint_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!(
+            "lambdaworks-stark-checkpoint-{}.bin",
+            std::process::id()
+        ));
+        let poly = Polynomial::new(&[FE::from(1), FE::from(2), FE::from(3)]);
+        let lde_trace = TraceTable::new((0..8).map(FE::from).collect(), 2);
+        let lde_extension_trace = TraceTable::new((8..12).map(FE::from).collect(), 1);
+        let even_evaluations = vec![FE::from(7), FE::from(9)];
+        let header = CheckpointHeader {
+            trace_length: 4,
+            blowup_factor: 2,
+            coset_offset: 3,
+            main_columns: 1,
+            composition_parts: 2,
+            digest: [7; 32],
+        };
+
+        save::<Stark252PrimeField, Stark252PrimeField>(
+            &path,
+            CheckpointRef {
+                header: header.clone(),
+                trace_polys: &[poly.clone(), poly.clone()],
+                lde_trace: &lde_trace,
+                extension_polys: &[poly.clone()],
+                lde_extension_trace: &lde_extension_trace,
+                composition: Some(vec![(&poly, even_evaluations.as_slice()), (&poly, &[])]),
+            },
+        )
+        .unwrap();
+        let checkpoint = load::<Stark252PrimeField, Stark252PrimeField>(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(checkpoint.header, header);
+        assert_eq!(checkpoint.trace_polys, vec![poly.clone(), poly.clone()]);
+        assert_eq!(checkpoint.lde_trace, lde_trace);
+        assert_eq!(checkpoint.extension_polys, vec![poly.clone()]);
+        assert_eq!(checkpoint.lde_extension_trace, lde_extension_trace);
+        let composition = checkpoint.composition.unwrap();
+        assert_eq!(composition.parts, vec![poly.clone(), poly]);
+        assert_eq!(composition.evaluations[0], even_evaluations);
+        assert!(composition.evaluations[1].is_empty());
+    }
+
+    #[test]
+    fn corrupt_checkpoints_are_rejected() {
+        let path = std::env::temp_dir().join(format!(
+            "lambdaworks-stark-corrupt-checkpoint-{}.bin",
+            std::process::id()
+        ));
+        let poly = Polynomial::new(&[FE::from(1), FE::from(2)]);
+        let lde_trace = TraceTable::new((0..4).map(FE::from).collect(), 1);
+        save::<Stark252PrimeField, Stark252PrimeField>(
+            &path,
+            CheckpointRef {
+                header: CheckpointHeader {
+                    trace_length: 2,
+                    blowup_factor: 2,
+                    coset_offset: 3,
+                    main_columns: 1,
+                    composition_parts: 1,
+                    digest: [7; 32],
+                },
+                trace_polys: &[poly],
+                lde_trace: &lde_trace,
+                extension_polys: &[],
+                lde_extension_trace: &TraceTable::new(Vec::new(), 0),
+                composition: None,
+            },
+        )
+        .unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let load_bytes = |bytes: &[u8]| {
+            fs::write(&path, bytes).unwrap();
+            load::<Stark252PrimeField, Stark252PrimeField>(&path).map(|_| ())
+        };
+
+        assert!(load_bytes(&bytes).is_ok());
+        assert!(load_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // The number of trace polynomials, right after the header.
+        let mut inflated = bytes;
+        let count = 8 + 4 + 5 * 8 + 32;
+        inflated[count..count + 8].copy_from_slice(&u64::MAX.to_be_bytes());
+        let error = load_bytes(&inflated).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,285 @@
+//! C interface of the Cairo prover and verifier, enabled by the `ffi` feature.
+//!
+//! Options and AIRs are opaque handles created and released by this module.
+//! Byte inputs are passed as a pointer and a length, and the bytes returned
+//! (proofs and public inputs) are `StarkBuffer`s that have to be released with
+//! `stark_buffer_free`. Every function returns a `StarkStatus`, and writes its
+//! outputs only when it returns `StarkStatus::Ok`. Panics don't cross the
+//! interface: they are reported as `StarkStatus::Panic`.
+//!
+//! Proofs are the bytes of `StarkProof::to_bytes`, and public inputs are the
+//! JSON written by the `cairo-stark` command line tool.
+
+use std::{panic, ptr, slice};
+
+use crate::{
+    air::{
+        cairo_air::air::{CairoAIR, PublicInputs},
+        context::ProofOptions,
+    },
+    cairo_vm::{cairo_mem::CairoMemory, cairo_trace::CairoTrace},
+    prover, verifier,
+};
+
+/// Result of a call to this interface.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StarkStatus {
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// The trace, memory, public input or options couldn't be read.
+    InvalidInput = 2,
+    /// The prover failed on a valid input.
+    ProvingFailed = 3,
+    /// The proof is malformed or doesn't verify.
+    InvalidProof = 4,
+    /// The call panicked.
+    Panic = 5,
+}
+
+/// Bytes owned by this library.
+#[repr(C)]
+pub struct StarkBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl StarkBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// AIR of a Cairo execution together with its public input.
+pub struct StarkCairoAir {
+    air: CairoAIR,
+    public_input: PublicInputs,
+}
+
+/// # Safety
+/// `data` must be null or point to `len` readable bytes.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], StarkStatus> {
+    if data.is_null() {
+        return Err(StarkStatus::NullPointer);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+fn catch(f: impl FnOnce() -> Result<(), StarkStatus>) -> StarkStatus {
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(Ok(())) => StarkStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => StarkStatus::Panic,
+    }
+}
+
+/// Creates proof options, to be released with `stark_options_free`.
+#[no_mangle]
+pub extern "C" fn stark_options_new(
+    blowup_factor: u8,
+    fri_number_of_queries: usize,
+    coset_offset: u64,
+) -> *mut ProofOptions {
+    Box::into_raw(Box::new(ProofOptions {
+        blowup_factor,
+        fri_number_of_queries,
+        coset_offset,
+        validate_trace: false,
+    }))
+}
+
+/// # Safety
+/// `options` must be null or come from `stark_options_new`, and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn stark_options_free(options: *mut ProofOptions) {
+    if !options.is_null() {
+        drop(Box::from_raw(options));
+    }
+}
+
+/// Creates the AIR of the execution with the JSON public input in
+/// `public_input`, to be released with `stark_cairo_air_free`.
+///
+/// # Safety
+/// `public_input` must point to `public_input_len` readable bytes, `options`
+/// must come from `stark_options_new` and `air` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn stark_cairo_air_new(
+    public_input: *const u8,
+    public_input_len: usize,
+    options: *const ProofOptions,
+    air: *mut *mut StarkCairoAir,
+) -> StarkStatus {
+    catch(|| {
+        let public_input = bytes(public_input, public_input_len)?;
+        let options = options.as_ref().ok_or(StarkStatus::NullPointer)?;
+        if air.is_null() {
+            return Err(StarkStatus::NullPointer);
+        }
+
+        let public_input: PublicInputs =
+            serde_json::from_slice(public_input).map_err(|_| StarkStatus::InvalidInput)?;
+        let cairo_air = CairoAIR::for_public_input(options.clone(), &public_input)
+            .map_err(|_| StarkStatus::InvalidInput)?;
+        *air = Box::into_raw(Box::new(StarkCairoAir {
+            air: cairo_air,
+            public_input,
+        }));
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `air` must be null or come from `stark_cairo_air_new`, and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn stark_cairo_air_free(air: *mut StarkCairoAir) {
+    if !air.is_null() {
+        drop(Box::from_raw(air));
+    }
+}
+
+/// Proves the execution given by the trace and memory files of the Cairo
+/// runner, whose program bytecode is in the `program_size` memory cells from
+/// address 1. Writes the proof and the JSON public input it has to be
+/// verified against.
+///
+/// # Safety
+/// `trace` and `memory` must point to `trace_len` and `memory_len` readable
+/// bytes, `options` must come from `stark_options_new` and `proof` and
+/// `public_input` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn stark_prove_cairo(
+    trace: *const u8,
+    trace_len: usize,
+    memory: *const u8,
+    memory_len: usize,
+    program_size: usize,
+    options: *const ProofOptions,
+    proof: *mut StarkBuffer,
+    public_input: *mut StarkBuffer,
+) -> StarkStatus {
+    catch(|| {
+        let trace = bytes(trace, trace_len)?;
+        let memory = bytes(memory, memory_len)?;
+        let options = options.as_ref().ok_or(StarkStatus::NullPointer)?;
+        if proof.is_null() || public_input.is_null() {
+            return Err(StarkStatus::NullPointer);
+        }
+
+        let register_states =
+            CairoTrace::from_bytes_le(trace).map_err(|_| StarkStatus::InvalidInput)?;
+        let memory = CairoMemory::from_bytes_le(memory).map_err(|_| StarkStatus::InvalidInput)?;
+        let pub_inputs =
+            PublicInputs::try_from_regs_and_mem(&register_states, &memory, program_size)
+                .map_err(|_| StarkStatus::InvalidInput)?;
+        let air = CairoAIR::for_public_input(options.clone(), &pub_inputs)
+            .map_err(|_| StarkStatus::InvalidInput)?;
+        let output = prover::prove(&(register_states, memory), &air, &pub_inputs)
+            .map_err(|_| StarkStatus::ProvingFailed)?;
+        let json =
+            serde_json::to_vec(&output.public_values).map_err(|_| StarkStatus::ProvingFailed)?;
+
+        ptr::write(proof, StarkBuffer::new(output.proof.to_bytes()));
+        ptr::write(public_input, StarkBuffer::new(json));
+        Ok(())
+    })
+}
+
+/// Verifies `proof` against the AIR and public input of `air`. Returns
+/// `StarkStatus::Ok` if the proof is valid.
+///
+/// # Safety
+/// `air` must come from `stark_cairo_air_new` and `proof` must point to
+/// `proof_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stark_verify_cairo(
+    air: *const StarkCairoAir,
+    proof: *const u8,
+    proof_len: usize,
+) -> StarkStatus {
+    catch(|| {
+        let air = air.as_ref().ok_or(StarkStatus::NullPointer)?;
+        let proof = bytes(proof, proof_len)?;
+        verifier::verify_bytes(proof, &air.air, &air.public_input)
+            .map_err(|_| StarkStatus::InvalidProof)
+    })
+}
+
+/// # Safety
+/// `buffer` must have been written by this library and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn stark_buffer_free(buffer: StarkBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(slice::from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(extension: &str) -> Vec<u8> {
+        let path = format!(
+            "{}/src/cairo_vm/test_data/simple_program.{extension}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn proof_made_through_the_interface_verifies() {
+        let (trace, memory) = (load("trace"), load("memory"));
+        let empty = || StarkBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let (mut proof, mut public_input) = (empty(), empty());
+        let mut air = ptr::null_mut();
+
+        unsafe {
+            let options = stark_options_new(4, 3, 3);
+            let status = stark_prove_cairo(
+                trace.as_ptr(),
+                trace.len(),
+                memory.as_ptr(),
+                memory.len(),
+                5,
+                options,
+                &mut proof,
+                &mut public_input,
+            );
+            assert_eq!(status, StarkStatus::Ok);
+
+            let status =
+                stark_cairo_air_new(public_input.data, public_input.len, options, &mut air);
+            assert_eq!(status, StarkStatus::Ok);
+            assert_eq!(
+                stark_verify_cairo(air, proof.data, proof.len),
+                StarkStatus::Ok
+            );
+            assert_eq!(
+                stark_verify_cairo(air, proof.data, proof.len - 1),
+                StarkStatus::InvalidProof
+            );
+
+            stark_cairo_air_free(air);
+            stark_options_free(options);
+            stark_buffer_free(proof);
+            stark_buffer_free(public_input);
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_reported() {
+        let status = unsafe { stark_verify_cairo(ptr::null(), ptr::null(), 0) };
+        assert_eq!(status, StarkStatus::NullPointer);
+    }
+}
@@ -1,14 +1,13 @@
-use lambdaworks_math::{
-    field::{
-        element::FieldElement,
-        traits::{IsFFTField, IsField},
-    },
-    traits::ByteConversion,
+use alloc::{boxed::Box, vec::Vec};
+use lambdaworks_math::field::{
+    element::FieldElement,
+    traits::{IsFFTField, IsField},
 };
 
-use super::HASHER;
+use super::ChallengeHasher;
 pub use super::{FriMerkleTree, Polynomial};
-use lambdaworks_fft::polynomial::FFTPoly;
+use crate::IsChallengeField;
+use lambdaworks_fft::errors::FFTError;
 
 #[derive(Clone)]
 pub struct FriLayer<F: IsField> {
@@ -19,28 +18,27 @@ pub struct FriLayer<F: IsField> {
     pub domain_size: usize,
 }
 
-impl<F> FriLayer<F>
-where
-    F: IsField + IsFFTField,
-    FieldElement<F>: ByteConversion,
-{
-    pub fn new(
-        poly: Polynomial<FieldElement<F>>,
-        coset_offset: &FieldElement<F>,
+impl<E: IsField> FriLayer<E> {
+    /// Evaluates `poly` on the coset of `coset_offset` of size `domain_size`,
+    /// with the roots of unity of the trace field `F`, and commits to it.
+    pub fn new<F: IsFFTField>(
+        poly: Polynomial<FieldElement<E>>,
+        coset_offset: &FieldElement<E>,
         domain_size: usize,
-    ) -> Self {
-        let evaluation = poly
-            .evaluate_offset_fft(1, Some(domain_size), coset_offset)
-            .unwrap(); // TODO: return error
+    ) -> Result<Self, FFTError>
+    where
+        E: IsChallengeField<F>,
+    {
+        let evaluation = E::evaluate_offset_fft(&poly, 1, Some(domain_size), coset_offset)?;
 
-        let merkle_tree = FriMerkleTree::build(&evaluation, Box::new(HASHER));
+        let merkle_tree = FriMerkleTree::build(&evaluation, Box::new(ChallengeHasher::new::<F>()));
 
-        Self {
+        Ok(Self {
             poly,
             evaluation,
             merkle_tree,
             coset_offset: coset_offset.clone(),
             domain_size,
-        }
+        })
     }
 }
@@ -4,9 +4,21 @@ use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::field::traits::IsField;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound = "FieldElement<F>: lambdaworks_math::traits::ByteConversion")
+)]
 pub struct FriDecommitment<F: IsField> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::merkle_proofs"))]
     pub layers_auth_paths_sym: Vec<Proof<F>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
     pub layers_evaluations_sym: Vec<FieldElement<F>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
     pub first_layer_evaluation: FieldElement<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::merkle_proof"))]
     pub first_layer_auth_path: Proof<F>,
 }
@@ -1,4 +1,5 @@
 use super::Polynomial;
+use alloc::vec::Vec;
 use lambdaworks_math::field::{element::FieldElement, traits::IsField};
 
 pub fn fold_polynomial<F>(
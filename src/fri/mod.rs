@@ -3,13 +3,14 @@ pub mod fri_decommit;
 mod fri_functions;
 use crate::air::traits::AIR;
 use crate::fri::fri_commitment::FriLayer;
-use crate::{transcript_to_field, transcript_to_usize};
-use lambdaworks_crypto::hash::sha3::Sha3Hasher;
+use crate::prover::ProvingError;
+use crate::{transcript_to_index, IsChallengeField};
+use alloc::{vec, vec::Vec};
+use lambdaworks_crypto::hash::{sha3::Sha3Hasher, traits::IsCryptoHash};
 
 pub use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 pub use lambdaworks_crypto::merkle_tree::merkle::MerkleTree;
 use lambdaworks_math::field::traits::{IsFFTField, IsField};
-use lambdaworks_math::traits::ByteConversion;
 pub use lambdaworks_math::{
     field::{element::FieldElement, fields::u64_prime_field::U64PrimeField},
     polynomial::Polynomial,
@@ -21,47 +22,80 @@ use self::fri_functions::fold_polynomial;
 pub type FriMerkleTree<F> = MerkleTree<F>;
 pub(crate) const HASHER: Sha3Hasher = Sha3Hasher::new();
 
-pub fn fri_commit_phase<F: IsField + IsFFTField, T: Transcript>(
+/// Hasher of the Merkle trees over a challenge field, with the hashes given by
+/// its `IsChallengeField` implementation.
+pub(crate) struct ChallengeHasher<E: IsField> {
+    hash_one: fn(FieldElement<E>) -> FieldElement<E>,
+    hash_two: fn(FieldElement<E>, FieldElement<E>) -> FieldElement<E>,
+}
+
+impl<E: IsField> ChallengeHasher<E> {
+    pub(crate) fn new<F: IsFFTField>() -> Self
+    where
+        E: IsChallengeField<F>,
+    {
+        Self {
+            hash_one: <E as IsChallengeField<F>>::hash_one,
+            hash_two: <E as IsChallengeField<F>>::hash_two,
+        }
+    }
+}
+
+impl<E: IsField> IsCryptoHash<E> for ChallengeHasher<E> {
+    fn hash_one(&self, input: FieldElement<E>) -> FieldElement<E> {
+        (self.hash_one)(input)
+    }
+
+    fn hash_two(&self, left: FieldElement<E>, right: FieldElement<E>) -> FieldElement<E> {
+        (self.hash_two)(left, right)
+    }
+}
+
+/// Commits to the `number_layers` FRI layers of `p_0`. `on_layer` is called
+/// with the index of each layer before it is computed, and an error it returns
+/// stops the commit phase.
+pub fn fri_commit_phase<F: IsFFTField, E: IsChallengeField<F>, T: Transcript>(
     number_layers: usize,
-    p_0: Polynomial<FieldElement<F>>,
+    p_0: Polynomial<FieldElement<E>>,
     transcript: &mut T,
-    coset_offset: &FieldElement<F>,
+    coset_offset: &FieldElement<E>,
     domain_size: usize,
-) -> (FieldElement<F>, Vec<FriLayer<F>>)
-where
-    FieldElement<F>: ByteConversion,
-{
+    on_layer: &mut dyn FnMut(usize) -> Result<(), ProvingError>,
+) -> Result<(FieldElement<E>, Vec<FriLayer<E>>), ProvingError> {
     let mut domain_size = domain_size;
 
     let mut fri_layer_list = Vec::with_capacity(number_layers);
-    let mut current_layer = FriLayer::new(p_0, coset_offset, domain_size);
-    fri_layer_list.push(current_layer.clone());
+    on_layer(0)?;
+    let mut current_layer = FriLayer::new::<F>(p_0, coset_offset, domain_size)?;
 
     // >>>> Send commitment: [p₀]
-    transcript.append(&current_layer.merkle_tree.root.to_bytes_be());
+    transcript.append(&E::to_bytes_be(&current_layer.merkle_tree.root));
 
     let mut coset_offset = coset_offset.clone();
 
-    for _ in 1..number_layers {
+    for layer in 1..number_layers {
+        on_layer(layer)?;
+
         // <<<< Receive challenge 𝜁ₖ₋₁
-        let zeta = transcript_to_field(transcript);
+        let zeta = E::sample(transcript);
         coset_offset = coset_offset.square();
         domain_size /= 2;
 
         // Compute layer polynomial and domain
         let next_poly = fold_polynomial(&current_layer.poly, &zeta);
-        current_layer = FriLayer::new(next_poly, &coset_offset, domain_size);
-        let new_data = &current_layer.merkle_tree.root.to_bytes_be();
-        fri_layer_list.push(current_layer.clone()); // TODO: remove this clone
+        let next_layer = FriLayer::new::<F>(next_poly, &coset_offset, domain_size)?;
 
         // >>>> Send commitment: [pₖ]
-        transcript.append(new_data);
+        transcript.append(&E::to_bytes_be(&next_layer.merkle_tree.root));
+
+        fri_layer_list.push(core::mem::replace(&mut current_layer, next_layer));
     }
 
     // <<<< Receive challenge: 𝜁ₙ₋₁
-    let zeta = transcript_to_field(transcript);
+    let zeta = E::sample(transcript);
 
     let last_poly = fold_polynomial(&current_layer.poly, &zeta);
+    fri_layer_list.push(current_layer);
 
     let last_value = last_poly
         .coefficients()
@@ -70,31 +104,30 @@ where
         .clone();
 
     // >>>> Send value: pₙ
-    transcript.append(&last_value.to_bytes_be());
+    transcript.append(&E::to_bytes_be(&last_value));
 
-    (last_value, fri_layer_list)
+    Ok((last_value, fri_layer_list))
 }
 
-pub fn fri_query_phase<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+pub fn fri_query_phase<E: IsField, A: AIR, T: Transcript>(
     air: &A,
     domain_size: usize,
-    fri_layers: &Vec<FriLayer<F>>,
+    fri_layers: &Vec<FriLayer<E>>,
     transcript: &mut T,
-) -> (Vec<FriDecommitment<F>>, usize)
-where
-    FieldElement<F>: ByteConversion,
-{
+) -> Result<(Vec<FriDecommitment<E>>, usize), ProvingError> {
     if let Some(first_layer) = fri_layers.get(0) {
         let number_of_queries = air.context().options.fri_number_of_queries;
         let mut iotas: Vec<usize> = Vec::with_capacity(number_of_queries);
         let query_list = (0..number_of_queries)
             .map(|_| {
                 // <<<< Receive challenge 𝜄ₛ (iota_s)
-                let iota_s = transcript_to_usize(transcript) % domain_size;
+                let iota_s = transcript_to_index(transcript, domain_size);
 
                 let first_layer_evaluation = first_layer.evaluation[iota_s].clone();
-                let first_layer_auth_path =
-                    first_layer.merkle_tree.get_proof_by_pos(iota_s).unwrap();
+                let first_layer_auth_path = first_layer
+                    .merkle_tree
+                    .get_proof_by_pos(iota_s)
+                    .ok_or(ProvingError::Commitment(iota_s))?;
 
                 let mut layers_auth_paths_sym = vec![];
                 let mut layers_evaluations_sym = vec![];
@@ -103,23 +136,26 @@ where
                     // symmetric element
                     let index_sym = (iota_s + layer.domain_size / 2) % layer.domain_size;
                     let evaluation_sym = layer.evaluation[index_sym].clone();
-                    let auth_path_sym = layer.merkle_tree.get_proof_by_pos(index_sym).unwrap();
+                    let auth_path_sym = layer
+                        .merkle_tree
+                        .get_proof_by_pos(index_sym)
+                        .ok_or(ProvingError::Commitment(index_sym))?;
                     layers_auth_paths_sym.push(auth_path_sym);
                     layers_evaluations_sym.push(evaluation_sym);
                 }
                 iotas.push(iota_s);
 
-                FriDecommitment {
+                Ok(FriDecommitment {
                     layers_auth_paths_sym,
                     layers_evaluations_sym,
                     first_layer_evaluation,
                     first_layer_auth_path,
-                }
+                })
             })
-            .collect();
+            .collect::<Result<_, ProvingError>>()?;
 
-        (query_list, iotas[0])
+        Ok((query_list, iotas[0]))
     } else {
-        (vec![], 0)
+        Ok((vec![], 0))
     }
 }
@@ -1,19 +1,49 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod advanced;
 pub mod air;
+#[cfg(feature = "std")]
 pub mod cairo_run;
+#[cfg(feature = "std")]
+pub mod cairo_runner;
+#[cfg(feature = "std")]
 pub mod cairo_vm;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod fri;
 pub mod proof;
 pub mod prover;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+#[cfg(feature = "std")]
+pub mod sharp;
+#[cfg(feature = "std")]
+pub mod stone;
 pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 use air::traits::AIR;
-use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
-use lambdaworks_fft::roots_of_unity::get_powers_of_primitive_root_coset;
-use lambdaworks_math::field::{
-    element::FieldElement,
-    fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
-    traits::{IsFFTField, IsField},
+use alloc::{format, string::String, vec::Vec};
+use fri::HASHER;
+use lambdaworks_crypto::{fiat_shamir::transcript::Transcript, hash::traits::IsCryptoHash};
+use lambdaworks_fft::{
+    errors::FFTError, polynomial::FFTPoly, roots_of_unity::get_powers_of_primitive_root_coset,
+};
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+        traits::{IsFFTField, IsField},
+    },
+    polynomial::Polynomial,
+    traits::ByteConversion,
 };
+use prover::ProvingError;
 
 pub struct ProofConfig {
     pub count_queries: usize,
@@ -23,40 +53,240 @@ pub struct ProofConfig {
 pub type PrimeField = Stark252PrimeField;
 pub type FE = FieldElement<PrimeField>;
 
-// TODO: change this to use more bits
+/// Field the verifier challenges are drawn from: z, the constraint and DEEP
+/// coefficients and the FRI foldings. It contains the field `F` of the trace.
+///
+/// Everything computed from a challenge (constraint evaluations, composition
+/// polynomial, DEEP polynomial, FRI layers, and the auxiliary columns of
+/// `AIR::build_extension_columns`) lives in this field. It doesn't need roots
+/// of unity of its own: the LDE domain is the one of `F`, so the FFTs over this
+/// field use the roots of `F`. Every `IsFFTField` is the challenge field of its
+/// own traces; an extension of `F` implements this trait directly.
+pub trait IsChallengeField<F: IsFFTField>: IsField + 'static {
+    /// Maps an element of the trace field into this field.
+    fn embed(element: &FieldElement<F>) -> FieldElement<Self>;
+
+    fn embed_polynomial(poly: &Polynomial<FieldElement<F>>) -> Polynomial<FieldElement<Self>> {
+        let coefficients: Vec<_> = poly.coefficients().iter().map(Self::embed).collect();
+        Polynomial::new(&coefficients)
+    }
+
+    /// Encoding of `element` appended to the transcript.
+    fn to_bytes_be(element: &FieldElement<Self>) -> Vec<u8>;
+
+    /// Inverse of `to_bytes_be`, `None` if `bytes` doesn't encode an element.
+    fn from_bytes_be(bytes: &[u8]) -> Option<FieldElement<Self>>;
+
+    /// Draws an element of this field from `transcript`.
+    fn sample<T: Transcript>(transcript: &mut T) -> FieldElement<Self> {
+        transcript_to_field(transcript)
+    }
+
+    /// Hash of the leaves of the Merkle trees over this field.
+    fn hash_one(element: FieldElement<Self>) -> FieldElement<Self>;
+
+    /// Hash of the inner nodes of the Merkle trees over this field.
+    fn hash_two(left: FieldElement<Self>, right: FieldElement<Self>) -> FieldElement<Self>;
+
+    /// Evaluates `poly` on the coset `offset * <w>`, where `w` is the root of
+    /// unity of `F` of order `blowup_factor` times the domain size rounded up to
+    /// a power of two. The domain size defaults to the number of coefficients.
+    fn evaluate_offset_fft(
+        poly: &Polynomial<FieldElement<Self>>,
+        blowup_factor: usize,
+        domain_size: Option<usize>,
+        offset: &FieldElement<Self>,
+    ) -> Result<Vec<FieldElement<Self>>, FFTError> {
+        let coefficients = poly.coefficients();
+        let domain_size = domain_size
+            .unwrap_or(coefficients.len())
+            .max(coefficients.len())
+            .next_power_of_two()
+            * blowup_factor;
+
+        let mut offset_power = FieldElement::one();
+        let mut scaled = Vec::with_capacity(domain_size);
+        for coefficient in coefficients {
+            scaled.push(coefficient * &offset_power);
+            offset_power = offset_power * offset;
+        }
+        scaled.resize(domain_size, FieldElement::zero());
+
+        radix2_fft::<F, Self>(scaled, false)
+    }
+
+    /// Interpolates the polynomial taking `evaluations` on the coset
+    /// `offset * <w>`, with `w` the root of unity of `F` of order
+    /// `evaluations.len()`, which has to be a power of two.
+    fn interpolate_offset_fft(
+        evaluations: &[FieldElement<Self>],
+        offset: &FieldElement<Self>,
+    ) -> Result<Polynomial<FieldElement<Self>>, FFTError> {
+        let mut coefficients = radix2_fft::<F, Self>(evaluations.to_vec(), true)?;
+
+        let size_inverse = Self::embed(&FieldElement::<F>::from(evaluations.len() as u64).inv());
+        let offset_inverse = offset.inv();
+        let mut factor = size_inverse;
+        for coefficient in coefficients.iter_mut() {
+            *coefficient = &*coefficient * &factor;
+            factor = factor * &offset_inverse;
+        }
+
+        Ok(Polynomial::new(&coefficients))
+    }
+}
+
+/// Every field can be used as the challenge field of its own traces.
+impl<F: IsFFTField + 'static> IsChallengeField<F> for F
+where
+    FieldElement<F>: ByteConversion,
+{
+    fn embed(element: &FieldElement<F>) -> FieldElement<F> {
+        element.clone()
+    }
+
+    fn to_bytes_be(element: &FieldElement<F>) -> Vec<u8> {
+        element.to_bytes_be()
+    }
+
+    fn from_bytes_be(bytes: &[u8]) -> Option<FieldElement<F>> {
+        FieldElement::from_bytes_be(bytes).ok()
+    }
+
+    fn hash_one(element: FieldElement<F>) -> FieldElement<F> {
+        HASHER.hash_one(element)
+    }
+
+    fn hash_two(left: FieldElement<F>, right: FieldElement<F>) -> FieldElement<F> {
+        HASHER.hash_two(left, right)
+    }
+
+    fn evaluate_offset_fft(
+        poly: &Polynomial<FieldElement<F>>,
+        blowup_factor: usize,
+        domain_size: Option<usize>,
+        offset: &FieldElement<F>,
+    ) -> Result<Vec<FieldElement<F>>, FFTError> {
+        poly.evaluate_offset_fft(blowup_factor, domain_size, offset)
+    }
+
+    fn interpolate_offset_fft(
+        evaluations: &[FieldElement<F>],
+        offset: &FieldElement<F>,
+    ) -> Result<Polynomial<FieldElement<F>>, FFTError> {
+        Polynomial::interpolate_offset_fft(evaluations, offset)
+    }
+}
+
+/// Radix-2 FFT over `E` of `values`, whose length is a power of two, with the
+/// roots of unity of `F`. With `inverse` it uses the inverse roots and doesn't
+/// divide by the length. The result is in natural order.
+fn radix2_fft<F: IsFFTField, E: IsChallengeField<F>>(
+    mut values: Vec<FieldElement<E>>,
+    inverse: bool,
+) -> Result<Vec<FieldElement<E>>, FFTError> {
+    let size = values.len();
+    if !size.is_power_of_two() {
+        return Err(FFTError::InputError(size));
+    }
+    if size == 1 {
+        return Ok(values);
+    }
+    let order = size.trailing_zeros();
+    let roots = get_powers_of_primitive_root_coset(order as u64, size, &FieldElement::<F>::one())?;
+
+    for i in 0..size {
+        let j = i.reverse_bits() >> (usize::BITS - order);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < size {
+        let stride = size / (2 * half);
+        for start in (0..size).step_by(2 * half) {
+            for k in 0..half {
+                let root_index = if inverse {
+                    (size - k * stride) % size
+                } else {
+                    k * stride
+                };
+                let twiddle = E::embed(&roots[root_index]);
+                let odd = &values[start + k + half] * &twiddle;
+                let even = values[start + k].clone();
+                values[start + k] = &even + &odd;
+                values[start + k + half] = even - odd;
+            }
+        }
+        half *= 2;
+    }
+
+    Ok(values)
+}
+
+/// `Sync` when the `parallel` feature is enabled and no bound at all otherwise,
+/// so that the data shared between the verifier threads only has to be `Sync`
+/// in the builds that actually spawn them.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+/// Samples an element of `F` from two challenges of `transcript`. Their 512 bits
+/// are reduced into the field, so the bias is negligible for fields of up to
+/// 256 bits. Extension fields get a single coordinate; `IsChallengeField::sample`
+/// fills all of them.
 pub fn transcript_to_field<F: IsField, T: Transcript>(transcript: &mut T) -> FieldElement<F> {
-    let value: u64 = u64::from_be_bytes(transcript.challenge()[..8].try_into().unwrap());
-    FieldElement::from(value)
+    let shift = FieldElement::<F>::from(u64::MAX) + FieldElement::one();
+    let mut value = FieldElement::zero();
+    for _ in 0..2 {
+        for limb in transcript.challenge().chunks_exact(8) {
+            let limb = u64::from_be_bytes(limb.try_into().unwrap());
+            value = value * &shift + FieldElement::from(limb);
+        }
+    }
+    value
 }
 
-pub fn transcript_to_usize<T: Transcript>(transcript: &mut T) -> usize {
-    const CANT_BYTES_USIZE: usize = (usize::BITS / 8) as usize;
-    let value = transcript.challenge()[..CANT_BYTES_USIZE]
-        .try_into()
-        .unwrap();
-    usize::from_be_bytes(value)
+/// Samples an index in `[0, upper_bound)`. The challenge is always read as a `u64`
+/// so that the sampled index doesn't depend on the pointer width of the platform:
+/// a verifier compiled to wasm32 has to derive the same queries as a 64-bit prover.
+pub fn transcript_to_index<T: Transcript>(transcript: &mut T, upper_bound: usize) -> usize {
+    let value = u64::from_be_bytes(transcript.challenge()[..8].try_into().unwrap());
+    (value % upper_bound as u64) as usize
 }
 
-pub fn sample_z_ood<F: IsField, T: Transcript>(
-    lde_roots_of_unity_coset: &[FieldElement<F>],
-    trace_roots_of_unity: &[FieldElement<F>],
+pub fn sample_z_ood<F: IsFFTField, E: IsChallengeField<F>, T: Transcript>(
+    domain: &Domain<F>,
     transcript: &mut T,
-) -> FieldElement<F> {
+) -> FieldElement<E> {
+    let trace_length = domain.interpolation_domain_size;
+    let lde_domain_size = domain.lde_domain_size();
+    let coset_offset_inverse = E::embed(&domain.coset_offset.inv());
     loop {
-        let value: FieldElement<F> = transcript_to_field(transcript);
-        if !lde_roots_of_unity_coset.iter().any(|x| x == &value)
-            && !trace_roots_of_unity.iter().any(|x| x == &value)
-        {
+        let value = E::sample(transcript);
+        // `value` is in the trace domain iff value^n = 1, and it is in the
+        // LDE coset h<w> iff (value / h)^N = 1.
+        let in_trace_domain = value.pow(trace_length) == FieldElement::one();
+        let in_lde_domain =
+            (&value * &coset_offset_inverse).pow(lde_domain_size) == FieldElement::one();
+        if !in_trace_domain && !in_lde_domain {
             return value;
         }
     }
 }
 
-pub fn batch_sample_challenges<F: IsFFTField, T: Transcript>(
+pub fn batch_sample_challenges<F: IsFFTField, E: IsChallengeField<F>, T: Transcript>(
     size: usize,
     transcript: &mut T,
-) -> Vec<FieldElement<F>> {
-    (0..size).map(|_| transcript_to_field(transcript)).collect()
+) -> Vec<FieldElement<E>> {
+    (0..size).map(|_| E::sample(transcript)).collect()
 }
 
 pub struct Domain<F: IsFFTField> {
@@ -64,45 +294,117 @@ pub struct Domain<F: IsFFTField> {
     lde_roots_of_unity_coset: Vec<FieldElement<F>>,
     lde_root_order: u32,
     trace_primitive_root: FieldElement<F>,
-    trace_roots_of_unity: Vec<FieldElement<F>>,
     coset_offset: FieldElement<F>,
     blowup_factor: usize,
     interpolation_domain_size: usize,
 }
 
 impl<F: IsFFTField> Domain<F> {
-    fn new<A: AIR<Field = F>>(air: &A) -> Self {
-        // Initial definitions
-        let blowup_factor = air.options().blowup_factor as usize;
-        let coset_offset = FieldElement::<F>::from(air.options().coset_offset);
-        let interpolation_domain_size = air.context().trace_length;
-        let root_order = air.context().trace_length.trailing_zeros();
+    pub fn new<A: AIR<Field = F>>(air: &A) -> Self {
+        Self::from_parameters(
+            air.context().trace_length,
+            air.options().blowup_factor as usize,
+            air.options().coset_offset,
+        )
+        .expect("the domain parameters of the AIR are checked before building its domain")
+    }
+
+    /// Builds the domain of a trace of `trace_length` rows, extended by
+    /// `blowup_factor` over the coset of `coset_offset`, without an AIR.
+    /// Fails unless both are powers of two and the field has roots of unity
+    /// of order `trace_length * blowup_factor`.
+    pub fn from_parameters(
+        trace_length: usize,
+        blowup_factor: usize,
+        coset_offset: u64,
+    ) -> Result<Self, ProvingError> {
+        if !trace_length.is_power_of_two() {
+            return Err(ProvingError::InvalidTraceLength(trace_length));
+        }
+        if !blowup_factor.is_power_of_two() {
+            return Err(ProvingError::WrongParameter(format!(
+                "the blowup factor {blowup_factor} isn't a power of two"
+            )));
+        }
+        let lde_domain_size = trace_length.checked_mul(blowup_factor).ok_or_else(|| {
+            ProvingError::WrongParameter(String::from("the LDE domain size overflows"))
+        })?;
+        let lde_root_order = lde_domain_size.trailing_zeros();
+        if lde_root_order as u64 > F::TWO_ADICITY {
+            return Err(ProvingError::WrongParameter(format!(
+                "the LDE domain of size 2^{lde_root_order} is larger than the two-adicity of the field"
+            )));
+        }
+
+        let mut domain = Self::succinct_from_parameters(trace_length, blowup_factor, coset_offset);
+
         // * Generate Coset
-        let trace_primitive_root = F::get_primitive_root_of_unity(root_order as u64).unwrap();
-        let trace_roots_of_unity = get_powers_of_primitive_root_coset(
-            root_order as u64,
-            interpolation_domain_size,
-            &FieldElement::<F>::one(),
+        domain.lde_roots_of_unity_coset = get_powers_of_primitive_root_coset(
+            domain.lde_root_order as u64,
+            domain.lde_domain_size(),
+            &domain.coset_offset,
         )
-        .unwrap();
+        .map_err(ProvingError::Fft)?;
 
-        let lde_root_order = (air.context().trace_length * blowup_factor).trailing_zeros();
-        let lde_roots_of_unity_coset = get_powers_of_primitive_root_coset(
-            lde_root_order as u64,
-            air.context().trace_length * blowup_factor,
-            &coset_offset,
+        Ok(domain)
+    }
+
+    /// Builds the domain without materializing the points of the LDE coset, which
+    /// has `blowup_factor * trace_length` elements. The verifier only needs the
+    /// roots and sizes, so this keeps its memory usage independent of the trace length.
+    fn new_succinct<A: AIR<Field = F>>(air: &A) -> Self {
+        Self::succinct_from_parameters(
+            air.context().trace_length,
+            air.options().blowup_factor as usize,
+            air.options().coset_offset,
         )
-        .unwrap();
+    }
+
+    fn succinct_from_parameters(
+        trace_length: usize,
+        blowup_factor: usize,
+        coset_offset: u64,
+    ) -> Self {
+        // Initial definitions
+        let coset_offset = FieldElement::<F>::from(coset_offset);
+        let interpolation_domain_size = trace_length;
+        let root_order = trace_length.trailing_zeros();
+        let trace_primitive_root = F::get_primitive_root_of_unity(root_order as u64).unwrap();
+        let lde_root_order = (trace_length * blowup_factor).trailing_zeros();
 
         Self {
             root_order,
-            lde_roots_of_unity_coset,
+            lde_roots_of_unity_coset: Vec::new(),
             lde_root_order,
             trace_primitive_root,
-            trace_roots_of_unity,
             blowup_factor,
             coset_offset,
             interpolation_domain_size,
         }
     }
+
+    pub fn lde_domain_size(&self) -> usize {
+        self.interpolation_domain_size * self.blowup_factor
+    }
+
+    pub fn trace_length(&self) -> usize {
+        self.interpolation_domain_size
+    }
+
+    pub fn blowup_factor(&self) -> usize {
+        self.blowup_factor
+    }
+
+    pub fn coset_offset(&self) -> &FieldElement<F> {
+        &self.coset_offset
+    }
+
+    pub fn trace_primitive_root(&self) -> &FieldElement<F> {
+        &self.trace_primitive_root
+    }
+
+    /// Points of the LDE coset, in the order the LDE evaluations are stored.
+    pub fn lde_roots_of_unity_coset(&self) -> &[FieldElement<F>] {
+        &self.lde_roots_of_unity_coset
+    }
 }
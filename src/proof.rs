@@ -1,39 +1,108 @@
+use alloc::vec::Vec;
 use lambdaworks_crypto::merkle_tree::proof::Proof;
-use lambdaworks_math::field::{element::FieldElement, traits::IsFFTField};
+use lambdaworks_math::field::{
+    element::FieldElement,
+    traits::{IsFFTField, IsField},
+};
 
-use crate::{air::frame::Frame, fri::fri_decommit::FriDecommitment};
+use crate::fri::fri_decommit::FriDecommitment;
+
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod encoding;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod size_report;
 
 #[derive(Debug, Clone)]
-pub struct DeepPolynomialOpenings<F: IsFFTField> {
-    pub lde_composition_poly_even_proof: Proof<F>,
-    pub lde_composition_poly_even_evaluation: FieldElement<F>,
-    pub lde_composition_poly_odd_proof: Proof<F>,
-    pub lde_composition_poly_odd_evaluation: FieldElement<F>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound = "FieldElement<F>: lambdaworks_math::traits::ByteConversion, FieldElement<E>: lambdaworks_math::traits::ByteConversion"
+    )
+)]
+pub struct DeepPolynomialOpenings<F: IsFFTField, E: IsField = F> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::merkle_proofs"))]
+    pub lde_composition_poly_proofs: Vec<Proof<E>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub lde_composition_poly_evaluations: Vec<FieldElement<E>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::merkle_proofs"))]
     pub lde_trace_merkle_proofs: Vec<Proof<F>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
     pub lde_trace_evaluations: Vec<FieldElement<F>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::serde_helpers::merkle_proofs")
+    )]
+    pub lde_extension_merkle_proofs: Vec<Proof<E>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::serde_helpers::field_elements")
+    )]
+    pub lde_extension_evaluations: Vec<FieldElement<E>>,
 }
 
+/// STARK proof of a trace over the field `F`. The values derived from the
+/// challenges are in the challenge field `E`, which is `F` unless the AIR says
+/// otherwise.
 #[derive(Debug)]
-pub struct StarkProof<F: IsFFTField> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound = "FieldElement<F>: lambdaworks_math::traits::ByteConversion, FieldElement<E>: lambdaworks_math::traits::ByteConversion"
+    )
+)]
+pub struct StarkProof<F: IsFFTField, E: IsField = F> {
     // Commitments of the trace columns
     // [tⱼ]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
     pub lde_trace_merkle_roots: Vec<FieldElement<F>>,
-    // tⱼ(zgᵏ)
-    pub trace_ood_frame_evaluations: Frame<F>,
-    // [H₁]
-    pub composition_poly_even_root: FieldElement<F>,
-    // H₁(z²)
-    pub composition_poly_even_ood_evaluation: FieldElement<F>,
-    // [H₂]
-    pub composition_poly_odd_root: FieldElement<F>,
-    // H₂(z²)
-    pub composition_poly_odd_ood_evaluation: FieldElement<F>,
+    // [tⱼ] of the columns over the challenge field, after the others
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::serde_helpers::field_elements")
+    )]
+    pub lde_extension_merkle_roots: Vec<FieldElement<E>>,
+    // tⱼ(zgᵏ) of the frame layout, row by row
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub trace_ood_evaluations: Vec<FieldElement<E>>,
+    // [Hᵢ]
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub composition_poly_roots: Vec<FieldElement<E>>,
+    // Hᵢ(zᴺ)
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub composition_poly_ood_evaluations: Vec<FieldElement<E>>,
     // [pₖ]
-    pub fri_layers_merkle_roots: Vec<FieldElement<F>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::field_elements")
+    )]
+    pub fri_layers_merkle_roots: Vec<FieldElement<E>>,
     // pₙ
-    pub fri_last_value: FieldElement<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::field_element"))]
+    pub fri_last_value: FieldElement<E>,
     // Open(p₀(D₀), 𝜐ₛ), Opwn(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
-    pub query_list: Vec<FriDecommitment<F>>,
-    // Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
-    pub deep_poly_openings: DeepPolynomialOpenings<F>,
+    pub query_list: Vec<FriDecommitment<E>>,
+    // Open(Hᵢ(D_LDE), 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
+    pub deep_poly_openings: DeepPolynomialOpenings<F, E>,
 }
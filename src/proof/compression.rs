@@ -0,0 +1,181 @@
+//! Compressed form of a proof, enabled by the `compression` feature.
+//!
+//! The proof is written in the layout of `proof::encoding`, except that each
+//! field element is an index into a dictionary of the distinct elements of
+//! the proof. This stores the Merkle nodes shared by the authentication paths
+//! of different queries, and any repeated root or value, only once. The
+//! dictionary and the proof are then compressed with zstd:
+//!
+//! ```text
+//! magic             4 bytes  "STRZ"
+//! version           u16
+//! zstd frame        u32 number of entries, each a u8 length and its bytes,
+//!                   followed by the proof
+//! ```
+
+use std::io::Read;
+
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{
+    encoding::{Dictionary, ProofDecodingError, Reader, Writer},
+    StarkProof,
+};
+
+const MAGIC: &[u8; 4] = b"STRZ";
+/// Version of the layout written by `StarkProof::to_compressed_bytes`.
+pub const COMPRESSION_VERSION: u16 = 1;
+const COMPRESSION_LEVEL: i32 = 19;
+/// Largest decompressed proof accepted, so that a small input can't make the
+/// verifier allocate arbitrary amounts of memory.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+impl<F: IsFFTField, E: IsField> StarkProof<F, E>
+where
+    FieldElement<F>: ByteConversion,
+    FieldElement<E>: ByteConversion,
+{
+    /// Encodes the proof in the compressed layout described in `proof::compression`.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer {
+            bytes: Vec::new(),
+            dictionary: Some(Dictionary::default()),
+        };
+        self.write(&mut writer);
+        let entries = writer.dictionary.take().unwrap().entries;
+
+        let mut payload = Writer {
+            bytes: Vec::new(),
+            dictionary: None,
+        };
+        payload.u32(entries.len());
+        for entry in entries {
+            payload.bytes.push(entry.len() as u8);
+            payload.bytes.extend_from_slice(&entry);
+        }
+        payload.bytes.extend_from_slice(&writer.bytes);
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&COMPRESSION_VERSION.to_be_bytes());
+        bytes.extend_from_slice(
+            &zstd::bulk::compress(&payload.bytes, COMPRESSION_LEVEL)
+                .expect("compressing into memory can't fail"),
+        );
+        bytes
+    }
+
+    /// Decodes a proof encoded with `to_compressed_bytes`.
+    pub fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, ProofDecodingError> {
+        let mut reader = Reader {
+            bytes,
+            position: 0,
+            dictionary: None,
+        };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ProofDecodingError::InvalidMagic);
+        }
+        let version = reader.take(2)?;
+        let version = u16::from_be_bytes([version[0], version[1]]);
+        if version != COMPRESSION_VERSION {
+            return Err(ProofDecodingError::UnsupportedVersion(version));
+        }
+
+        let decompression_error =
+            |error: std::io::Error| ProofDecodingError::Decompression(error.to_string());
+        let mut payload = Vec::new();
+        zstd::stream::read::Decoder::new(&bytes[reader.position..])
+            .map_err(decompression_error)?
+            .take(MAX_DECOMPRESSED_SIZE + 1)
+            .read_to_end(&mut payload)
+            .map_err(decompression_error)?;
+        if payload.len() as u64 > MAX_DECOMPRESSED_SIZE {
+            return Err(ProofDecodingError::Decompression(
+                "the decompressed proof is too large".to_string(),
+            ));
+        }
+
+        let mut reader = Reader {
+            bytes: &payload,
+            position: 0,
+            dictionary: None,
+        };
+        let len = reader.len(1)?;
+        let mut entries = Vec::with_capacity(len);
+        for _ in 0..len {
+            let entry_len = reader.take(1)?[0] as usize;
+            entries.push(reader.take(entry_len)?.to_vec());
+        }
+
+        Self::read(&mut Reader {
+            bytes: &payload,
+            position: reader.position,
+            dictionary: Some(&entries),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use crate::{
+        air::{
+            context::{AirContext, ProofOptions},
+            example::simple_fibonacci,
+        },
+        prover::prove,
+        verifier::verify_compressed,
+    };
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn compressed_proof_is_smaller_and_verifies() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 8,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: trace[0].len(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        let air = simple_fibonacci::FibonacciAIR::from(context);
+        let proof = prove(&trace, &air, &()).unwrap().proof;
+
+        let compressed = proof.to_compressed_bytes();
+        assert!(compressed.len() < proof.to_bytes().len());
+
+        let decompressed = StarkProof::<Stark252PrimeField>::from_compressed_bytes(&compressed);
+        assert_eq!(decompressed.unwrap().to_bytes(), proof.to_bytes());
+        assert!(verify_compressed(&compressed, &air, &()).is_ok());
+    }
+
+    #[test]
+    fn corrupted_compressed_proof_is_rejected() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&COMPRESSION_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&[1, 2, 3, 4]);
+
+        assert!(matches!(
+            StarkProof::<Stark252PrimeField>::from_compressed_bytes(&bytes),
+            Err(ProofDecodingError::Decompression(_))
+        ));
+    }
+}
@@ -0,0 +1,514 @@
+//! Canonical binary encoding of a `StarkProof`.
+//!
+//! The layout is deterministic, so that the bytes of a proof can be hashed or
+//! signed, and simple enough to be decoded by verifiers in other languages.
+//! Every integer is big-endian:
+//!
+//! ```text
+//! magic             4 bytes  "STRK"
+//! version           u16
+//! width of F        u16      bytes of an element of the trace field
+//! width of E        u16      bytes of an element of the challenge field
+//! proof fields      in the order of the `StarkProof` declaration
+//! ```
+//!
+//! A field element is its `to_bytes_be` encoding, which has to be the fixed
+//! width declared in the header and the canonical representative. A vector is
+//! a `u32` length followed by its items and a Merkle proof is its path as a
+//! vector of elements. Decoding rejects anything that doesn't follow this
+//! layout exactly, including trailing bytes.
+//!
+//! Proofs are always written in the latest layout, and `StarkProof::from_bytes`
+//! picks the decoder of the version in the header. A change in the layout of a
+//! released version gets a new version and a new `read_vN` function, and the
+//! old ones are left as they are.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{DeepPolynomialOpenings, StarkProof};
+use crate::fri::fri_decommit::FriDecommitment;
+
+const MAGIC: &[u8; 4] = b"STRK";
+/// Bytes of the magic, the version and the widths of both fields.
+pub(super) const HEADER_SIZE: usize = 10;
+/// Version of the layout written by `StarkProof::to_bytes`.
+pub const ENCODING_VERSION: u16 = 1;
+/// Versions of the layout that `StarkProof::from_bytes` can read.
+pub const SUPPORTED_ENCODING_VERSIONS: &[u16] = &[1];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProofDecodingError {
+    /// The bytes don't start with the magic of an encoded proof.
+    InvalidMagic,
+    /// The proof was encoded with a layout version this crate can't read.
+    UnsupportedVersion(u16),
+    /// The width of the field elements in the header doesn't match the field
+    /// the proof is decoded into.
+    FieldWidthMismatch { expected: usize, found: usize },
+    /// The input ends before the proof does.
+    UnexpectedEnd,
+    /// The bytes at this offset aren't the canonical encoding of a field element.
+    InvalidFieldElement(usize),
+    /// There are bytes left after the proof.
+    TrailingBytes(usize),
+    /// The compressed proof couldn't be decompressed.
+    Decompression(String),
+}
+
+pub(super) fn element_width<F: IsField>() -> usize
+where
+    FieldElement<F>: ByteConversion,
+{
+    FieldElement::<F>::zero().to_bytes_be().len()
+}
+
+/// Distinct field elements of a proof, in order of first appearance. Merkle
+/// paths of different queries share most of their nodes, so a proof written
+/// with a dictionary stores each node once and refers to it by index.
+#[derive(Default)]
+pub(super) struct Dictionary {
+    indices: BTreeMap<Vec<u8>, u32>,
+    pub(super) entries: Vec<Vec<u8>>,
+}
+
+pub(super) struct Writer {
+    pub(super) bytes: Vec<u8>,
+    pub(super) dictionary: Option<Dictionary>,
+}
+
+impl Writer {
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub(super) fn u32(&mut self, value: usize) {
+        let value = u32::try_from(value).expect("proof vectors have less than 2^32 items");
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn element<F: IsField>(&mut self, element: &FieldElement<F>)
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let bytes = element.to_bytes_be();
+        match self.dictionary.as_mut() {
+            None => self.bytes.extend_from_slice(&bytes),
+            Some(dictionary) => {
+                let next_index = dictionary.entries.len();
+                let index = *dictionary.indices.entry(bytes).or_insert_with_key(|bytes| {
+                    dictionary.entries.push(bytes.clone());
+                    next_index as u32
+                });
+                self.bytes.extend_from_slice(&index.to_be_bytes());
+            }
+        }
+    }
+
+    fn elements<F: IsField>(&mut self, elements: &[FieldElement<F>])
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        self.u32(elements.len());
+        for element in elements {
+            self.element(element);
+        }
+    }
+
+    fn merkle_proof<F: IsField>(&mut self, proof: &Proof<F>)
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        self.elements(&proof.merkle_path);
+    }
+
+    fn merkle_proofs<F: IsField>(&mut self, proofs: &[Proof<F>])
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        self.u32(proofs.len());
+        for proof in proofs {
+            self.merkle_proof(proof);
+        }
+    }
+}
+
+pub(super) struct Reader<'a> {
+    pub(super) bytes: &'a [u8],
+    pub(super) position: usize,
+    /// Entries that the elements refer to, if the proof was written with a
+    /// `Dictionary`.
+    pub(super) dictionary: Option<&'a [Vec<u8>]>,
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn take(&mut self, len: usize) -> Result<&'a [u8], ProofDecodingError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(ProofDecodingError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, ProofDecodingError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Reads a vector length, checking that the rest of the input can hold
+    /// that many items of at least `min_item_size` bytes before anything is
+    /// allocated for them.
+    pub(super) fn len(&mut self, min_item_size: usize) -> Result<usize, ProofDecodingError> {
+        let bytes = self.take(4)?;
+        let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        if len.saturating_mul(min_item_size) > self.bytes.len() - self.position {
+            return Err(ProofDecodingError::UnexpectedEnd);
+        }
+        Ok(len)
+    }
+
+    /// Checks the widths of the trace and challenge field elements in the header.
+    fn field_widths<F: IsField, E: IsField>(&mut self) -> Result<(), ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+        FieldElement<E>: ByteConversion,
+    {
+        for expected in [element_width::<F>(), element_width::<E>()] {
+            let found = self.u16()? as usize;
+            if found != expected {
+                return Err(ProofDecodingError::FieldWidthMismatch { expected, found });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the whole input was read.
+    fn finish(&self) -> Result<(), ProofDecodingError> {
+        let trailing = self.bytes.len() - self.position;
+        if trailing != 0 {
+            return Err(ProofDecodingError::TrailingBytes(trailing));
+        }
+        Ok(())
+    }
+
+    /// Bytes taken by each element in the input.
+    fn element_size<F: IsField>(&self) -> usize
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        match self.dictionary {
+            None => element_width::<F>(),
+            Some(_) => 4,
+        }
+    }
+
+    fn element<F: IsField>(&mut self) -> Result<FieldElement<F>, ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let offset = self.position;
+        let bytes: &[u8] = match self.dictionary {
+            None => self.take(element_width::<F>())?,
+            Some(entries) => {
+                let index = self.take(4)?;
+                let index = u32::from_be_bytes([index[0], index[1], index[2], index[3]]);
+                entries
+                    .get(index as usize)
+                    .filter(|entry| entry.len() == element_width::<F>())
+                    .ok_or(ProofDecodingError::InvalidFieldElement(offset))?
+            }
+        };
+        let element = FieldElement::from_bytes_be(bytes)
+            .map_err(|_| ProofDecodingError::InvalidFieldElement(offset))?;
+        // Values that are not reduced would give several encodings of a proof.
+        if element.to_bytes_be() != bytes {
+            return Err(ProofDecodingError::InvalidFieldElement(offset));
+        }
+        Ok(element)
+    }
+
+    fn elements<F: IsField>(&mut self) -> Result<Vec<FieldElement<F>>, ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let len = self.len(self.element_size::<F>())?;
+        (0..len).map(|_| self.element()).collect()
+    }
+
+    fn merkle_proof<F: IsField>(&mut self) -> Result<Proof<F>, ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        Ok(Proof {
+            merkle_path: self.elements()?,
+        })
+    }
+
+    fn merkle_proofs<F: IsField>(&mut self) -> Result<Vec<Proof<F>>, ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        let len = self.len(4)?;
+        (0..len).map(|_| self.merkle_proof()).collect()
+    }
+
+    fn fri_decommitment<F: IsField>(&mut self) -> Result<FriDecommitment<F>, ProofDecodingError>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        Ok(FriDecommitment {
+            layers_auth_paths_sym: self.merkle_proofs()?,
+            layers_evaluations_sym: self.elements()?,
+            first_layer_evaluation: self.element()?,
+            first_layer_auth_path: self.merkle_proof()?,
+        })
+    }
+}
+
+/// Version of the layout of an encoded proof, read from its header.
+pub fn encoded_version(bytes: &[u8]) -> Result<u16, ProofDecodingError> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+        dictionary: None,
+    };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(ProofDecodingError::InvalidMagic);
+    }
+    reader.u16()
+}
+
+impl<F: IsFFTField, E: IsField> StarkProof<F, E>
+where
+    FieldElement<F>: ByteConversion,
+    FieldElement<E>: ByteConversion,
+{
+    /// Encodes the proof in the canonical layout described in `proof::encoding`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer {
+            bytes: Vec::new(),
+            dictionary: None,
+        };
+        self.write(&mut writer);
+        writer.bytes
+    }
+
+    /// Decodes a proof encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofDecodingError> {
+        Self::read(&mut Reader {
+            bytes,
+            position: 0,
+            dictionary: None,
+        })
+    }
+
+    pub(super) fn write(&self, writer: &mut Writer) {
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.u16(ENCODING_VERSION);
+        writer.u16(element_width::<F>() as u16);
+        writer.u16(element_width::<E>() as u16);
+
+        writer.elements(&self.lde_trace_merkle_roots);
+        writer.elements(&self.lde_extension_merkle_roots);
+        writer.elements(&self.trace_ood_evaluations);
+        writer.elements(&self.composition_poly_roots);
+        writer.elements(&self.composition_poly_ood_evaluations);
+        writer.elements(&self.fri_layers_merkle_roots);
+        writer.element(&self.fri_last_value);
+
+        writer.u32(self.query_list.len());
+        for decommitment in &self.query_list {
+            writer.merkle_proofs(&decommitment.layers_auth_paths_sym);
+            writer.elements(&decommitment.layers_evaluations_sym);
+            writer.element(&decommitment.first_layer_evaluation);
+            writer.merkle_proof(&decommitment.first_layer_auth_path);
+        }
+
+        let openings = &self.deep_poly_openings;
+        writer.merkle_proofs(&openings.lde_composition_poly_proofs);
+        writer.elements(&openings.lde_composition_poly_evaluations);
+        writer.merkle_proofs(&openings.lde_trace_merkle_proofs);
+        writer.elements(&openings.lde_trace_evaluations);
+        writer.merkle_proofs(&openings.lde_extension_merkle_proofs);
+        writer.elements(&openings.lde_extension_evaluations);
+    }
+
+    /// Reads a proof that takes the rest of the input of `reader`, with the
+    /// decoder of the version in its header.
+    pub(super) fn read(reader: &mut Reader) -> Result<Self, ProofDecodingError> {
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ProofDecodingError::InvalidMagic);
+        }
+        match reader.u16()? {
+            1 => Self::read_v1(reader),
+            version => Err(ProofDecodingError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn read_v1(reader: &mut Reader) -> Result<Self, ProofDecodingError> {
+        reader.field_widths::<F, E>()?;
+
+        let lde_trace_merkle_roots = reader.elements()?;
+        let lde_extension_merkle_roots = reader.elements()?;
+        let trace_ood_evaluations = reader.elements()?;
+        let composition_poly_roots = reader.elements()?;
+        let composition_poly_ood_evaluations = reader.elements()?;
+        let fri_layers_merkle_roots = reader.elements()?;
+        let fri_last_value = reader.element()?;
+        let queries = reader.len(3 * 4)?;
+        let query_list = (0..queries)
+            .map(|_| reader.fri_decommitment())
+            .collect::<Result<_, _>>()?;
+        let deep_poly_openings = DeepPolynomialOpenings {
+            lde_composition_poly_proofs: reader.merkle_proofs()?,
+            lde_composition_poly_evaluations: reader.elements()?,
+            lde_trace_merkle_proofs: reader.merkle_proofs()?,
+            lde_trace_evaluations: reader.elements()?,
+            lde_extension_merkle_proofs: reader.merkle_proofs()?,
+            lde_extension_evaluations: reader.elements()?,
+        };
+        reader.finish()?;
+
+        Ok(StarkProof {
+            lde_trace_merkle_roots,
+            lde_extension_merkle_roots,
+            trace_ood_evaluations,
+            composition_poly_roots,
+            composition_poly_ood_evaluations,
+            fri_layers_merkle_roots,
+            fri_last_value,
+            query_list,
+            deep_poly_openings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::{
+        fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::F17,
+    };
+
+    use super::*;
+    use crate::{
+        air::{
+            context::{AirContext, ProofOptions},
+            example::simple_fibonacci,
+        },
+        prover::prove,
+        verifier::{verify, verify_bytes},
+    };
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    fn fibonacci_proof() -> (Vec<u8>, simple_fibonacci::FibonacciAIR) {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: trace[0].len(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        let air = simple_fibonacci::FibonacciAIR::from(context);
+        let proof = prove(&trace, &air, &()).unwrap().proof;
+        (proof.to_bytes(), air)
+    }
+
+    #[test]
+    fn decoded_proof_verifies_and_encodes_to_the_same_bytes() {
+        let (bytes, air) = fibonacci_proof();
+        let proof = StarkProof::<Stark252PrimeField>::from_bytes(&bytes).unwrap();
+
+        assert!(verify(&proof, &air, &()).is_ok());
+        assert!(verify_bytes(&bytes, &air, &()).is_ok());
+        assert_eq!(proof.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn every_truncation_of_a_proof_is_rejected() {
+        let (bytes, _) = fibonacci_proof();
+        for len in 0..bytes.len() {
+            assert!(StarkProof::<Stark252PrimeField>::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn malformed_headers_and_trailing_bytes_are_rejected() {
+        let (bytes, _) = fibonacci_proof();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] ^= 1;
+        assert_eq!(
+            StarkProof::<Stark252PrimeField>::from_bytes(&wrong_magic).unwrap_err(),
+            ProofDecodingError::InvalidMagic
+        );
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[5] = 99;
+        assert_eq!(
+            StarkProof::<Stark252PrimeField>::from_bytes(&wrong_version).unwrap_err(),
+            ProofDecodingError::UnsupportedVersion(99)
+        );
+
+        assert!(matches!(
+            StarkProof::<F17>::from_bytes(&bytes).unwrap_err(),
+            ProofDecodingError::FieldWidthMismatch { .. }
+        ));
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            StarkProof::<Stark252PrimeField>::from_bytes(&trailing).unwrap_err(),
+            ProofDecodingError::TrailingBytes(1)
+        );
+    }
+
+    #[test]
+    fn every_supported_version_is_dispatched_to_a_decoder() {
+        let (mut bytes, _) = fibonacci_proof();
+        assert_eq!(encoded_version(&bytes).unwrap(), ENCODING_VERSION);
+        assert!(SUPPORTED_ENCODING_VERSIONS.contains(&ENCODING_VERSION));
+
+        for version in SUPPORTED_ENCODING_VERSIONS {
+            bytes[4..6].copy_from_slice(&version.to_be_bytes());
+            assert!(!matches!(
+                StarkProof::<Stark252PrimeField>::from_bytes(&bytes),
+                Err(ProofDecodingError::UnsupportedVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn unreduced_field_element_is_rejected() {
+        let (mut bytes, _) = fibonacci_proof();
+        // The first trace root starts after the header and the length of the roots.
+        let first_root = 10 + 4;
+        bytes[first_root..first_root + 32].fill(0xff);
+
+        assert_eq!(
+            StarkProof::<Stark252PrimeField>::from_bytes(&bytes).unwrap_err(),
+            ProofDecodingError::InvalidFieldElement(first_root)
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Human readable JSON form of a `StarkProof`, enabled by the `json` feature.
+//!
+//! The proof is split in named sections (trace, composition polynomial, FRI
+//! and DEEP openings), field elements are `0x` prefixed hex strings, and each
+//! opened value sits next to its authentication path, so that two proofs can
+//! be diffed and the output fed to explorers. The binary layout of
+//! `proof::encoding` stays the format to hash or transmit proofs.
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+use serde::{de::Error, Deserialize, Serialize};
+
+use super::{DeepPolynomialOpenings, StarkProof};
+use crate::fri::fri_decommit::FriDecommitment;
+
+/// Version of the JSON layout written by `StarkProof::to_json`.
+pub const JSON_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<F>: ByteConversion")]
+struct Opening<F: IsField> {
+    #[serde(with = "crate::serde_helpers::field_element")]
+    value: FieldElement<F>,
+    #[serde(with = "crate::serde_helpers::field_elements")]
+    auth_path: Vec<FieldElement<F>>,
+}
+
+impl<F: IsField> Opening<F> {
+    fn new(value: &FieldElement<F>, proof: &Proof<F>) -> Self {
+        Self {
+            value: value.clone(),
+            auth_path: proof.merkle_path.clone(),
+        }
+    }
+
+    fn into_parts(self) -> (FieldElement<F>, Proof<F>) {
+        (
+            self.value,
+            Proof {
+                merkle_path: self.auth_path,
+            },
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<F>: ByteConversion, FieldElement<E>: ByteConversion")]
+struct TraceSection<F: IsField, E: IsField> {
+    #[serde(with = "crate::serde_helpers::field_elements")]
+    merkle_roots: Vec<FieldElement<F>>,
+    /// Roots of the columns over the challenge field.
+    #[serde(with = "crate::serde_helpers::field_elements")]
+    extension_merkle_roots: Vec<FieldElement<E>>,
+    /// Values of the frame layout of the AIR, row by row.
+    #[serde(with = "crate::serde_helpers::field_elements")]
+    ood_evaluations: Vec<FieldElement<E>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<E>: ByteConversion")]
+struct CompositionPart<E: IsField> {
+    #[serde(with = "crate::serde_helpers::field_element")]
+    merkle_root: FieldElement<E>,
+    #[serde(with = "crate::serde_helpers::field_element")]
+    ood_evaluation: FieldElement<E>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<E>: ByteConversion")]
+struct CompositionSection<E: IsField> {
+    /// The parts Hᵢ of H = ∑ xⁱHᵢ(xᴺ), in order.
+    parts: Vec<CompositionPart<E>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<E>: ByteConversion")]
+struct FriQuery<E: IsField> {
+    first_layer: Opening<E>,
+    /// Openings of the symmetric element in each layer after the first.
+    layers_sym: Vec<Opening<E>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<E>: ByteConversion")]
+struct FriSection<E: IsField> {
+    #[serde(with = "crate::serde_helpers::field_elements")]
+    layer_roots: Vec<FieldElement<E>>,
+    #[serde(with = "crate::serde_helpers::field_element")]
+    last_value: FieldElement<E>,
+    queries: Vec<FriQuery<E>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<F>: ByteConversion, FieldElement<E>: ByteConversion")]
+struct DeepOpeningsSection<F: IsField, E: IsField> {
+    composition: Vec<Opening<E>>,
+    trace: Vec<Opening<F>>,
+    extension: Vec<Opening<E>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "FieldElement<F>: ByteConversion, FieldElement<E>: ByteConversion")]
+struct JsonProof<F: IsField, E: IsField> {
+    version: u32,
+    trace: TraceSection<F, E>,
+    composition: CompositionSection<E>,
+    fri: FriSection<E>,
+    deep_openings: DeepOpeningsSection<F, E>,
+}
+
+impl<F: IsFFTField, E: IsField> StarkProof<F, E>
+where
+    FieldElement<F>: ByteConversion,
+    FieldElement<E>: ByteConversion,
+{
+    /// Pretty printed JSON representation of the proof.
+    pub fn to_json(&self) -> String {
+        let openings = &self.deep_poly_openings;
+        let json = JsonProof {
+            version: JSON_VERSION,
+            trace: TraceSection {
+                merkle_roots: self.lde_trace_merkle_roots.clone(),
+                extension_merkle_roots: self.lde_extension_merkle_roots.clone(),
+                ood_evaluations: self.trace_ood_evaluations.clone(),
+            },
+            composition: CompositionSection {
+                parts: self
+                    .composition_poly_roots
+                    .iter()
+                    .zip(&self.composition_poly_ood_evaluations)
+                    .map(|(merkle_root, ood_evaluation)| CompositionPart {
+                        merkle_root: merkle_root.clone(),
+                        ood_evaluation: ood_evaluation.clone(),
+                    })
+                    .collect(),
+            },
+            fri: FriSection {
+                layer_roots: self.fri_layers_merkle_roots.clone(),
+                last_value: self.fri_last_value.clone(),
+                queries: self
+                    .query_list
+                    .iter()
+                    .map(|query| FriQuery {
+                        first_layer: Opening::new(
+                            &query.first_layer_evaluation,
+                            &query.first_layer_auth_path,
+                        ),
+                        layers_sym: query
+                            .layers_evaluations_sym
+                            .iter()
+                            .zip(&query.layers_auth_paths_sym)
+                            .map(|(value, proof)| Opening::new(value, proof))
+                            .collect(),
+                    })
+                    .collect(),
+            },
+            deep_openings: DeepOpeningsSection {
+                composition: openings
+                    .lde_composition_poly_evaluations
+                    .iter()
+                    .zip(&openings.lde_composition_poly_proofs)
+                    .map(|(value, proof)| Opening::new(value, proof))
+                    .collect(),
+                trace: openings
+                    .lde_trace_evaluations
+                    .iter()
+                    .zip(&openings.lde_trace_merkle_proofs)
+                    .map(|(value, proof)| Opening::new(value, proof))
+                    .collect(),
+                extension: openings
+                    .lde_extension_evaluations
+                    .iter()
+                    .zip(&openings.lde_extension_merkle_proofs)
+                    .map(|(value, proof)| Opening::new(value, proof))
+                    .collect(),
+            },
+        };
+        serde_json::to_string_pretty(&json).expect("a proof is always serializable")
+    }
+
+    /// Reads a proof written by `to_json`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let json: JsonProof<F, E> = serde_json::from_str(json)?;
+        if json.version != JSON_VERSION {
+            return Err(serde_json::Error::custom(format!(
+                "unsupported proof JSON version {}",
+                json.version
+            )));
+        }
+
+        let query_list = json
+            .fri
+            .queries
+            .into_iter()
+            .map(|query| {
+                let (first_layer_evaluation, first_layer_auth_path) =
+                    query.first_layer.into_parts();
+                let (layers_evaluations_sym, layers_auth_paths_sym) = query
+                    .layers_sym
+                    .into_iter()
+                    .map(Opening::into_parts)
+                    .unzip();
+                FriDecommitment {
+                    layers_auth_paths_sym,
+                    layers_evaluations_sym,
+                    first_layer_evaluation,
+                    first_layer_auth_path,
+                }
+            })
+            .collect();
+
+        let openings = json.deep_openings;
+        let (lde_composition_poly_evaluations, lde_composition_poly_proofs) = openings
+            .composition
+            .into_iter()
+            .map(Opening::into_parts)
+            .unzip();
+        let (composition_poly_roots, composition_poly_ood_evaluations) = json
+            .composition
+            .parts
+            .into_iter()
+            .map(|part| (part.merkle_root, part.ood_evaluation))
+            .unzip();
+        let (lde_trace_evaluations, lde_trace_merkle_proofs) =
+            openings.trace.into_iter().map(Opening::into_parts).unzip();
+        let (lde_extension_evaluations, lde_extension_merkle_proofs) = openings
+            .extension
+            .into_iter()
+            .map(Opening::into_parts)
+            .unzip();
+
+        Ok(StarkProof {
+            lde_trace_merkle_roots: json.trace.merkle_roots,
+            lde_extension_merkle_roots: json.trace.extension_merkle_roots,
+            trace_ood_evaluations: json.trace.ood_evaluations,
+            composition_poly_roots,
+            composition_poly_ood_evaluations,
+            fri_layers_merkle_roots: json.fri.layer_roots,
+            fri_last_value: json.fri.last_value,
+            query_list,
+            deep_poly_openings: DeepPolynomialOpenings {
+                lde_composition_poly_proofs,
+                lde_composition_poly_evaluations,
+                lde_trace_merkle_proofs,
+                lde_trace_evaluations,
+                lde_extension_merkle_proofs,
+                lde_extension_evaluations,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+    use crate::{
+        air::{
+            context::{AirContext, ProofOptions},
+            example::simple_fibonacci,
+        },
+        prover::prove,
+        verifier::verify,
+    };
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn proof_round_trips_through_json() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: trace[0].len(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        let air = simple_fibonacci::FibonacciAIR::from(context);
+        let proof = prove(&trace, &air, &()).unwrap().proof;
+
+        let json = proof.to_json();
+        assert!(json.contains("\"deep_openings\""));
+        let decoded = StarkProof::<Stark252PrimeField>::from_json(&json).unwrap();
+
+        assert!(verify(&decoded, &air, &()).is_ok());
+        assert_eq!(decoded.to_json(), json);
+    }
+}
@@ -0,0 +1,155 @@
+//! Breakdown of the size of a proof in its binary encoding.
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
+    traits::ByteConversion,
+};
+
+use super::{
+    encoding::{element_width, HEADER_SIZE},
+    StarkProof,
+};
+
+/// Bytes of each part of a proof in the layout of `StarkProof::to_bytes`.
+/// Every length prefix is counted with the values it precedes, so the parts
+/// add up to the size of the encoded proof.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofSizeReport {
+    /// Magic, version and field widths.
+    pub header: usize,
+    /// Merkle roots of the trace columns.
+    pub trace_commitments: usize,
+    /// Trace values and authentication paths opened at the queries.
+    pub trace_openings: usize,
+    /// Merkle roots of the composition polynomial parts Hᵢ.
+    pub composition_commitments: usize,
+    /// Hᵢ values and authentication paths opened at the queries.
+    pub composition_openings: usize,
+    /// Out of domain evaluations of the trace in the frame layout and Hᵢ(zᴺ).
+    pub ood_values: usize,
+    /// Merkle roots of the FRI layers and the last FRI value.
+    pub fri_layer_roots: usize,
+    /// FRI layer values and authentication paths opened at the queries.
+    pub fri_queries: usize,
+}
+
+impl ProofSizeReport {
+    pub fn total(&self) -> usize {
+        self.header
+            + self.trace_commitments
+            + self.trace_openings
+            + self.composition_commitments
+            + self.composition_openings
+            + self.ood_values
+            + self.fri_layer_roots
+            + self.fri_queries
+    }
+}
+
+const LENGTH_SIZE: usize = 4;
+
+fn elements_size<F: IsField>(count: usize) -> usize
+where
+    FieldElement<F>: ByteConversion,
+{
+    LENGTH_SIZE + count * element_width::<F>()
+}
+
+fn merkle_proofs_size<F: IsField>(proofs: &[Proof<F>]) -> usize
+where
+    FieldElement<F>: ByteConversion,
+{
+    LENGTH_SIZE
+        + proofs
+            .iter()
+            .map(|proof| elements_size::<F>(proof.merkle_path.len()))
+            .sum::<usize>()
+}
+
+impl<F: IsFFTField, E: IsField> StarkProof<F, E>
+where
+    FieldElement<F>: ByteConversion,
+    FieldElement<E>: ByteConversion,
+{
+    /// Where the bytes of the encoded proof go, to help choosing the blowup
+    /// factor and the number of queries.
+    pub fn size_report(&self) -> ProofSizeReport {
+        let width = element_width::<E>();
+        let openings = &self.deep_poly_openings;
+
+        ProofSizeReport {
+            header: HEADER_SIZE,
+            trace_commitments: elements_size::<F>(self.lde_trace_merkle_roots.len())
+                + elements_size::<E>(self.lde_extension_merkle_roots.len()),
+            trace_openings: merkle_proofs_size(&openings.lde_trace_merkle_proofs)
+                + elements_size::<F>(openings.lde_trace_evaluations.len())
+                + merkle_proofs_size(&openings.lde_extension_merkle_proofs)
+                + elements_size::<E>(openings.lde_extension_evaluations.len()),
+            composition_commitments: elements_size::<E>(self.composition_poly_roots.len()),
+            composition_openings: merkle_proofs_size(&openings.lde_composition_poly_proofs)
+                + elements_size::<E>(openings.lde_composition_poly_evaluations.len()),
+            ood_values: elements_size::<E>(self.trace_ood_evaluations.len())
+                + elements_size::<E>(self.composition_poly_ood_evaluations.len()),
+            fri_layer_roots: elements_size::<E>(self.fri_layers_merkle_roots.len()) + width,
+            fri_queries: LENGTH_SIZE
+                + self
+                    .query_list
+                    .iter()
+                    .map(|query| {
+                        merkle_proofs_size(&query.layers_auth_paths_sym)
+                            + elements_size::<E>(query.layers_evaluations_sym.len())
+                            + width
+                            + elements_size::<E>(query.first_layer_auth_path.merkle_path.len())
+                    })
+                    .sum::<usize>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use crate::{
+        air::{
+            context::{AirContext, ProofOptions},
+            example::simple_fibonacci,
+        },
+        prover::prove,
+    };
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[test]
+    fn size_report_adds_up_to_the_encoded_size() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 3,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: trace[0].len(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        let air = simple_fibonacci::FibonacciAIR::from(context);
+        let proof = prove(&trace, &air, &()).unwrap().proof;
+
+        let report = proof.size_report();
+        assert_eq!(report.total(), proof.to_bytes().len());
+        assert_eq!(report.composition_commitments, 64);
+    }
+}
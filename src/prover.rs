@@ -1,14 +1,28 @@
 use super::{
-    air::{constraints::evaluator::ConstraintEvaluator, frame::Frame, trace::TraceTable},
+    air::{
+        constraints::evaluator::ConstraintEvaluator,
+        frame::{check_column_offsets, Frame, FrameLayout},
+        trace::TraceTable,
+    },
     fri::fri_commit_phase,
     sample_z_ood,
 };
 use crate::{
-    air::traits::AIR,
+    air::{
+        constraints::{
+            degree::{check_composition_degree, check_transition_degrees},
+            transition::{check_row_subsets, check_zerofiers},
+        },
+        context::ProofOptions,
+        lookup::check_lookups,
+        periodic::check_periods,
+        segment::{self, check_segments, Segment},
+        traits::AIR,
+    },
     batch_sample_challenges,
-    fri::{fri_decommit::FriDecommitment, fri_query_phase, HASHER},
+    fri::{fri_decommit::FriDecommitment, fri_query_phase, ChallengeHasher, HASHER},
     proof::{DeepPolynomialOpenings, StarkProof},
-    transcript_to_field, Domain,
+    Domain, IsChallengeField, MaybeSync,
 };
 #[cfg(not(feature = "test_fiat_shamir"))]
 use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
@@ -17,61 +31,309 @@ use lambdaworks_crypto::{fiat_shamir::transcript::Transcript, merkle_tree::merkl
 #[cfg(feature = "test_fiat_shamir")]
 use lambdaworks_crypto::fiat_shamir::test_transcript::TestTranscript;
 
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 use lambdaworks_fft::{errors::FFTError, polynomial::FFTPoly};
 use lambdaworks_math::{
-    field::{element::FieldElement, traits::IsFFTField},
+    field::{
+        element::FieldElement,
+        traits::{IsFFTField, IsField},
+    },
     polynomial::Polynomial,
     traits::ByteConversion,
 };
 use log::info;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::time::Instant;
+#[cfg(feature = "std")]
+use std::{io, path::Path};
 
-#[cfg(debug_assertions)]
-use crate::air::debug::validate_trace;
+#[cfg(feature = "std")]
+use crate::checkpoint::{self, CheckpointHeader, CheckpointRef, CompositionCheckpoint};
+
+use crate::air::debug::validate_extended_trace;
 
 #[derive(Debug)]
 pub enum ProvingError {
     WrongParameter(String),
+    /// The trace doesn't have the power of two length declared in the AIR context.
+    InvalidTraceLength(usize),
+    /// Evaluating a polynomial over the LDE domain or a FRI layer domain failed.
+    Fft(FFTError),
+    /// Interpolating the trace columns or the constraint evaluations failed.
+    Interpolation(FFTError),
+    /// A Merkle tree has no authentication path for the leaf at this position.
+    Commitment(usize),
+    /// The cancellation flag was set while the proof was being built.
+    Cancelled,
+    /// `AIR::compute_transition` returned `found` evaluations for the `declared`
+    /// transition constraints of the AIR context.
+    TransitionCount {
+        declared: usize,
+        found: usize,
+    },
+    /// The transition constraint with this index has a higher degree than the
+    /// one declared in the AIR context. Debug builds always check it.
+    TransitionDegree {
+        constraint: usize,
+        /// Label of the constraint in the AIR context, if it has one.
+        label: Option<String>,
+        declared: usize,
+        found: usize,
+    },
+    /// With `ProofOptions::validate_trace` on, the trace violates a constraint,
+    /// the first of which is described.
+    InvalidTrace(String),
+    /// With the degree check on, the composition polynomial has a degree that
+    /// isn't below the bound of the AIR.
+    CompositionDegree {
+        degree: usize,
+        bound: usize,
+    },
+    /// A checkpoint couldn't be written or read back, or doesn't belong to this proof.
+    #[cfg(feature = "std")]
+    Checkpoint(io::Error),
 }
 
-struct Round1<F: IsFFTField, A: AIR<Field = F>> {
-    trace_polys: Vec<Polynomial<FieldElement<F>>>,
-    lde_trace: TraceTable<F>,
-    lde_trace_merkle_trees: Vec<MerkleTree<F>>,
-    lde_trace_merkle_roots: Vec<FieldElement<F>>,
-    rap_challenges: A::RAPChallenges,
+impl From<FFTError> for ProvingError {
+    fn from(error: FFTError) -> Self {
+        Self::Fft(error)
+    }
+}
+
+/// Everything a proving job produces.
+pub struct ProverOutput<A: AIR> {
+    pub proof: StarkProof<A::Field, A::ChallengeField>,
+    /// Public input completed by `AIR::build_main_trace`. This is the statement
+    /// the proof has to be verified against.
+    pub public_values: A::PublicInput,
+    pub metrics: ProverMetrics,
+}
+
+/// Stage the prover is starting, as reported by `prove_with_progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvingStage {
+    /// Building, interpolating and committing the main trace.
+    MainTrace,
+    /// Drawing the RAP challenges and committing the auxiliary trace.
+    AuxiliaryTrace,
+    /// Evaluating the constraints and committing the composition polynomial.
+    ConstraintEvaluation,
+    /// Evaluating the trace and composition polynomials out of the domain.
+    OutOfDomainEvaluation,
+    /// Building the DEEP composition polynomial.
+    DeepComposition,
+    /// Committing the FRI layer with this index.
+    FriLayer(usize),
+    /// Opening the FRI layers and the trace at the queried positions.
+    FriQueries,
+    /// The proof is built.
+    Finished,
+}
+
+/// Wall-clock time spent in each round of the prover.
+#[derive(Clone, Debug, Default)]
+pub struct ProverMetrics {
+    /// Building, interpolating and committing the main and auxiliary traces.
+    pub round_1: Duration,
+    /// Evaluating the constraints and committing the composition polynomial.
+    pub round_2: Duration,
+    /// Out of domain evaluations.
+    pub round_3: Duration,
+    /// DEEP composition polynomial and FRI.
+    pub round_4: Duration,
+}
+
+impl ProverMetrics {
+    pub fn total(&self) -> Duration {
+        self.round_1 + self.round_2 + self.round_3 + self.round_4
+    }
+}
+
+/// Measures the duration of a round. Without `std` there is no clock, and on
+/// wasm32 `Instant::now` panics, so in both cases every round takes zero time.
+struct Timer {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    start: Instant,
+}
+
+impl Timer {
+    fn start() -> Self {
+        Self {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            start: Instant::now(),
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        return self.start.elapsed();
+        #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+        return Duration::ZERO;
+    }
+}
+
+/// What a single proof carries besides the protocol data: its timings, where
+/// to report progress, the flag that cancels it, the file it checkpoints to and
+/// whether the degrees of the constraints are checked.
+#[derive(Default)]
+struct ProvingJob<'a> {
+    metrics: ProverMetrics,
+    progress: Option<&'a mut dyn FnMut(ProvingStage, f32)>,
+    cancellation: Option<&'a AtomicBool>,
+    check_degrees: bool,
+    #[cfg(feature = "std")]
+    checkpoint: Option<&'a Path>,
+}
+
+impl ProvingJob<'_> {
+    /// Reports that `stage` starts, unless the proof was cancelled.
+    fn start(&mut self, stage: ProvingStage, done: f32) -> Result<(), ProvingError> {
+        self.check_cancellation()?;
+        self.report(stage, done);
+        Ok(())
+    }
+
+    fn check_cancellation(&self) -> Result<(), ProvingError> {
+        if matches!(self.cancellation, Some(flag) if flag.load(Ordering::Relaxed)) {
+            return Err(ProvingError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn report(&mut self, stage: ProvingStage, done: f32) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress(stage, done);
+        }
+    }
+
+    /// Writes the rounds completed so far to the checkpoint file, if any.
+    #[cfg(feature = "std")]
+    fn save_checkpoint<F, E>(&self, checkpoint: CheckpointRef<F, E>) -> Result<(), ProvingError>
+    where
+        F: IsFFTField,
+        E: IsChallengeField<F>,
+        FieldElement<F>: ByteConversion,
+    {
+        match self.checkpoint {
+            Some(path) => checkpoint::save(path, checkpoint).map_err(ProvingError::Checkpoint),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Scratch buffers that the prover reuses across successive calls to
+/// `prove_with_workspace`: the row-major LDE trace table and the constraint
+/// evaluations, both of the size of the LDE domain, and nothing else. The
+/// per-column LDE evaluations and the FRI layers are the outputs of the FFT,
+/// which allocates them, so they are still allocated on every proof.
+/// `E` is the challenge field of the AIR.
+pub struct ProverWorkspace<F: IsFFTField, E: IsField = F> {
+    // Row-major table holding the LDE of the main and auxiliary trace columns.
+    lde_trace_table: Vec<FieldElement<F>>,
+    // Accumulated constraint evaluations over the LDE domain.
+    constraint_evaluations: Vec<FieldElement<E>>,
 }
 
-struct Round2<F: IsFFTField> {
-    composition_poly_even: Polynomial<FieldElement<F>>,
-    lde_composition_poly_even_evaluations: Vec<FieldElement<F>>,
-    composition_poly_even_merkle_tree: MerkleTree<F>,
-    composition_poly_even_root: FieldElement<F>,
-    composition_poly_odd: Polynomial<FieldElement<F>>,
-    lde_composition_poly_odd_evaluations: Vec<FieldElement<F>>,
-    composition_poly_odd_merkle_tree: MerkleTree<F>,
-    composition_poly_odd_root: FieldElement<F>,
+impl<F: IsFFTField, E: IsField> ProverWorkspace<F, E> {
+    pub fn new() -> Self {
+        Self {
+            lde_trace_table: Vec::new(),
+            constraint_evaluations: Vec::new(),
+        }
+    }
+}
+
+impl<F: IsFFTField, E: IsField> Default for ProverWorkspace<F, E> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-struct Round3<F: IsFFTField> {
-    trace_ood_evaluations: Vec<Vec<FieldElement<F>>>,
-    composition_poly_even_ood_evaluation: FieldElement<F>,
-    composition_poly_odd_ood_evaluation: FieldElement<F>,
+/// Main trace columns interpolated and committed. This is the part of round 1
+/// that doesn't depend on any challenge.
+pub struct MainTraceCommitment<F: IsFFTField> {
+    pub main_trace: TraceTable<F>,
+    pub trace_polys: Vec<Polynomial<FieldElement<F>>>,
+    pub lde_trace_evaluations: Vec<Vec<FieldElement<F>>>,
+    pub lde_trace_merkle_trees: Vec<MerkleTree<F>>,
+    pub lde_trace_merkle_roots: Vec<FieldElement<F>>,
 }
 
-struct Round4<F: IsFFTField> {
-    fri_last_value: FieldElement<F>,
-    fri_layers_merkle_roots: Vec<FieldElement<F>>,
-    deep_poly_openings: DeepPolynomialOpenings<F>,
-    query_list: Vec<FriDecommitment<F>>,
+/// Output of round 1: the trace polynomials, their LDE and its commitments,
+/// and the RAP challenges. The columns over the challenge field are kept apart
+/// from the ones over `F`, which they follow.
+pub struct Round1<F: IsFFTField, A: AIR<Field = F>> {
+    pub trace_polys: Vec<Polynomial<FieldElement<F>>>,
+    pub lde_trace: TraceTable<F>,
+    pub lde_trace_merkle_trees: Vec<MerkleTree<F>>,
+    pub lde_trace_merkle_roots: Vec<FieldElement<F>>,
+    pub extension_polys: Vec<Polynomial<FieldElement<A::ChallengeField>>>,
+    pub lde_extension_trace: TraceTable<A::ChallengeField>,
+    pub lde_extension_merkle_trees: Vec<MerkleTree<A::ChallengeField>>,
+    pub lde_extension_merkle_roots: Vec<FieldElement<A::ChallengeField>>,
+    pub rap_challenges: A::RAPChallenges,
+}
+
+impl<F: IsFFTField, A: AIR<Field = F>> Round1<F, A> {
+    /// Number of committed columns, over both fields.
+    pub fn num_columns(&self) -> usize {
+        self.trace_polys.len() + self.extension_polys.len()
+    }
+
+    /// The polynomials of every committed column over the challenge field:
+    /// those of the columns over `F`, embedded, followed by the others.
+    pub fn embedded_trace_polys(&self) -> Vec<Polynomial<FieldElement<A::ChallengeField>>> {
+        self.trace_polys
+            .iter()
+            .map(<A::ChallengeField as IsChallengeField<F>>::embed_polynomial)
+            .chain(self.extension_polys.iter().cloned())
+            .collect()
+    }
+}
+
+/// Output of round 2: the parts H₀, ..., Hₙ₋₁ of the composition polynomial,
+/// with their LDE and commitments. They are over the challenge field.
+pub struct Round2<F: IsField> {
+    pub composition_poly_parts: Vec<Polynomial<FieldElement<F>>>,
+    pub lde_composition_poly_evaluations: Vec<Vec<FieldElement<F>>>,
+    pub composition_poly_merkle_trees: Vec<MerkleTree<F>>,
+    pub composition_poly_roots: Vec<FieldElement<F>>,
+}
+
+/// Output of round 3: the out of domain evaluations of the trace polynomials,
+/// in the order of the entries of the frame layout, and of the parts Hᵢ, in
+/// the challenge field.
+pub struct Round3<F: IsField> {
+    pub trace_ood_evaluations: Vec<FieldElement<F>>,
+    pub composition_poly_ood_evaluations: Vec<FieldElement<F>>,
+}
+
+/// Output of round 4: the FRI commitments and decommitments, and the openings
+/// of the committed polynomials used to check the DEEP composition polynomial.
+pub struct Round4<F: IsFFTField, E: IsField> {
+    pub fri_last_value: FieldElement<E>,
+    pub fri_layers_merkle_roots: Vec<FieldElement<E>>,
+    pub deep_poly_openings: DeepPolynomialOpenings<F, E>,
+    pub query_list: Vec<FriDecommitment<E>>,
 }
 
 #[cfg(feature = "test_fiat_shamir")]
-fn round_0_transcript_initialization() -> TestTranscript {
+type ProverTranscript = TestTranscript;
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+type ProverTranscript = DefaultTranscript;
+
+#[cfg(feature = "test_fiat_shamir")]
+fn round_0_transcript_initialization() -> ProverTranscript {
     TestTranscript::new()
 }
 
 #[cfg(not(feature = "test_fiat_shamir"))]
-fn round_0_transcript_initialization() -> DefaultTranscript {
+fn round_0_transcript_initialization() -> ProverTranscript {
     // TODO: add strong fiat shamir
     DefaultTranscript::new()
 }
@@ -92,6 +354,23 @@ where
     (trees, roots)
 }
 
+/// Same as `batch_commit`, for vectors over the challenge field.
+fn batch_commit_challenge<F, E>(
+    vectors: Vec<&Vec<FieldElement<E>>>,
+) -> (Vec<MerkleTree<E>>, Vec<FieldElement<E>>)
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+{
+    let trees: Vec<_> = vectors
+        .iter()
+        .map(|col| MerkleTree::build(col, Box::new(ChallengeHasher::new::<F>())))
+        .collect();
+
+    let roots = trees.iter().map(|tree| tree.root.clone()).collect();
+    (trees, roots)
+}
+
 pub fn evaluate_polynomial_on_lde_domain<F>(
     p: &Polynomial<FieldElement<F>>,
     blowup_factor: usize,
@@ -102,32 +381,85 @@ where
     F: IsFFTField,
     Polynomial<FieldElement<F>>: FFTPoly<F>,
 {
-    // Evaluate those polynomials t_j on the large domain D_LDE.
-    let evaluations = p.evaluate_offset_fft(blowup_factor, Some(domain_size), offset)?;
-    let step = evaluations.len() / (domain_size * blowup_factor);
-    match step {
-        1 => Ok(evaluations),
-        _ => Ok(evaluations.into_iter().step_by(step).collect()),
+    let lde_domain_size = domain_size * blowup_factor;
+    match reduce_on_lde_domain(p, lde_domain_size, offset) {
+        Some(reduced) => reduced.evaluate_offset_fft(1, Some(lde_domain_size), offset),
+        None => p.evaluate_offset_fft(1, Some(lde_domain_size), offset),
     }
 }
 
+/// Same as `evaluate_polynomial_on_lde_domain`, for a polynomial over the
+/// challenge field `E`. The LDE domain is still the one of `F`.
+pub fn evaluate_challenge_polynomial_on_lde_domain<F, E>(
+    p: &Polynomial<FieldElement<E>>,
+    blowup_factor: usize,
+    domain_size: usize,
+    offset: &FieldElement<E>,
+) -> Result<Vec<FieldElement<E>>, FFTError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+{
+    let lde_domain_size = domain_size * blowup_factor;
+    match reduce_on_lde_domain(p, lde_domain_size, offset) {
+        Some(reduced) => E::evaluate_offset_fft(&reduced, 1, Some(lde_domain_size), offset),
+        None => E::evaluate_offset_fft(p, 1, Some(lde_domain_size), offset),
+    }
+}
+
+/// On the coset `offset * <w>` of size N every point satisfies X^N = offset^N, so
+/// a polynomial can be reduced modulo X^N - offset^N before evaluating it. This
+/// keeps the FFT at exactly the size of D_LDE no matter the degree of `p`.
+/// Returns `None` when `p` has at most N coefficients and needs no reduction.
+fn reduce_on_lde_domain<F: IsField>(
+    p: &Polynomial<FieldElement<F>>,
+    lde_domain_size: usize,
+    offset: &FieldElement<F>,
+) -> Option<Polynomial<FieldElement<F>>> {
+    let coefficients = p.coefficients();
+    if coefficients.len() <= lde_domain_size {
+        return None;
+    }
+
+    let offset_power = offset.pow(lde_domain_size);
+    let mut reduced_coefficients = vec![FieldElement::<F>::zero(); lde_domain_size];
+    let mut factor = FieldElement::<F>::one();
+    for chunk in coefficients.chunks(lde_domain_size) {
+        for (reduced, coefficient) in reduced_coefficients.iter_mut().zip(chunk) {
+            *reduced += coefficient * &factor;
+        }
+        factor = factor * &offset_power;
+    }
+
+    Some(Polynomial::new(&reduced_coefficients))
+}
+
+/// Interpolates the columns of `trace`, over the trace field, and commits to
+/// their LDE.
 #[allow(clippy::type_complexity)]
-fn interpolate_and_commit<T, F>(
+fn interpolate_and_commit<F>(
     trace: &TraceTable<F>,
+    segments: &[Segment],
     domain: &Domain<F>,
-    transcript: &mut T,
-) -> (
-    Vec<Polynomial<FieldElement<F>>>,
-    Vec<Vec<FieldElement<F>>>,
-    Vec<MerkleTree<F>>,
-    Vec<FieldElement<F>>,
-)
+) -> Result<
+    (
+        Vec<Polynomial<FieldElement<F>>>,
+        Vec<Vec<FieldElement<F>>>,
+        Vec<MerkleTree<F>>,
+        Vec<FieldElement<F>>,
+    ),
+    ProvingError,
+>
 where
-    T: Transcript,
     F: IsFFTField,
     FieldElement<F>: ByteConversion,
 {
-    let trace_polys = trace.compute_trace_polys();
+    if trace.n_rows() != domain.interpolation_domain_size {
+        return Err(ProvingError::InvalidTraceLength(trace.n_rows()));
+    }
+
+    let trace_polys =
+        segment::interpolate_columns(trace, segments).map_err(ProvingError::Interpolation)?;
 
     // Evaluate those polynomials t_j on the large domain D_LDE.
     let lde_trace_evaluations = trace_polys
@@ -140,150 +472,472 @@ where
                 &domain.coset_offset,
             )
         })
-        .collect::<Result<Vec<Vec<FieldElement<F>>>, FFTError>>()
-        .unwrap();
+        .collect::<Result<Vec<Vec<FieldElement<F>>>, FFTError>>()?;
 
     // Compute commitments [t_j].
-    let lde_trace = TraceTable::new_from_cols(&lde_trace_evaluations);
     let (lde_trace_merkle_trees, lde_trace_merkle_roots) =
-        batch_commit(lde_trace.cols().iter().collect());
+        batch_commit(lde_trace_evaluations.iter().collect());
 
-    // >>>> Send commitments: [tⱼ]
-    for root in lde_trace_merkle_roots.iter() {
-        transcript.append(&root.to_bytes_be());
-    }
+    Ok((
+        trace_polys,
+        lde_trace_evaluations,
+        lde_trace_merkle_trees,
+        lde_trace_merkle_roots,
+    ))
+}
 
+/// Same as `interpolate_and_commit`, for the columns over the challenge field,
+/// which are always interpolated over every row.
+#[allow(clippy::type_complexity)]
+fn interpolate_and_commit_extension<F, E>(
+    trace: &TraceTable<E>,
+    domain: &Domain<F>,
+) -> Result<
     (
+        Vec<Polynomial<FieldElement<E>>>,
+        Vec<Vec<FieldElement<E>>>,
+        Vec<MerkleTree<E>>,
+        Vec<FieldElement<E>>,
+    ),
+    ProvingError,
+>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+{
+    if trace.n_rows() != domain.interpolation_domain_size {
+        return Err(ProvingError::InvalidTraceLength(trace.n_rows()));
+    }
+
+    let trace_polys = trace
+        .cols()
+        .iter()
+        .map(|col| E::interpolate_offset_fft(col, &FieldElement::one()))
+        .collect::<Result<Vec<_>, FFTError>>()
+        .map_err(ProvingError::Interpolation)?;
+
+    let coset_offset = E::embed(&domain.coset_offset);
+    let lde_trace_evaluations = trace_polys
+        .iter()
+        .map(|poly| {
+            evaluate_challenge_polynomial_on_lde_domain::<F, _>(
+                poly,
+                domain.blowup_factor,
+                domain.interpolation_domain_size,
+                &coset_offset,
+            )
+        })
+        .collect::<Result<Vec<Vec<FieldElement<E>>>, FFTError>>()?;
+
+    let (lde_trace_merkle_trees, lde_trace_merkle_roots) =
+        batch_commit_challenge::<F, _>(lde_trace_evaluations.iter().collect());
+
+    Ok((
         trace_polys,
         lde_trace_evaluations,
         lde_trace_merkle_trees,
         lde_trace_merkle_roots,
-    )
+    ))
+}
+
+/// Fills the main trace up to the trace length of the AIR with its padding
+/// strategy, if it has one.
+fn pad_main_trace<A: AIR>(
+    air: &A,
+    main_trace: &mut TraceTable<A::Field>,
+) -> Result<(), ProvingError> {
+    if let Some(strategy) = air.padding_strategy() {
+        let n_rows = main_trace.n_rows();
+        if n_rows == 0 || n_rows > air.context().trace_length {
+            return Err(ProvingError::InvalidTraceLength(n_rows));
+        }
+        main_trace.pad(strategy, air.context().trace_length);
+    }
+    Ok(())
 }
 
-fn round_1_randomized_air_with_preprocessing<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+/// Interpolates and commits to the preprocessed columns of the AIR. They only
+/// depend on the AIR, so a `Prover` commits to them once for all its proofs,
+/// and their roots are the ones `AIR::preprocessed_roots` can return.
+pub fn commit_preprocessed_columns<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    domain: &Domain<F>,
+) -> Result<MainTraceCommitment<F>, ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let columns = air.preprocessed_columns();
+    if columns.is_empty() {
+        return Ok(MainTraceCommitment {
+            main_trace: TraceTable::empty(),
+            trace_polys: Vec::new(),
+            lde_trace_evaluations: Vec::new(),
+            lde_trace_merkle_trees: Vec::new(),
+            lde_trace_merkle_roots: Vec::new(),
+        });
+    }
+    if let Some(column) = columns
+        .iter()
+        .position(|column| column.len() != air.context().trace_length)
+    {
+        return Err(ProvingError::WrongParameter(format!(
+            "the preprocessed column {column} doesn't have the length of the trace"
+        )));
+    }
+
+    let main_trace = TraceTable::new_from_cols(&columns);
+    let (trace_polys, lde_trace_evaluations, lde_trace_merkle_trees, lde_trace_merkle_roots) =
+        interpolate_and_commit(&main_trace, &[], domain)?;
+    Ok(MainTraceCommitment {
+        main_trace,
+        trace_polys,
+        lde_trace_evaluations,
+        lde_trace_merkle_trees,
+        lde_trace_merkle_roots,
+    })
+}
+
+/// Builds the main trace and commits to it, without touching any transcript.
+pub fn round_1_commit_main_trace<F: IsFFTField, A: AIR<Field = F>>(
     air: &A,
     raw_trace: &A::RawTrace,
     domain: &Domain<F>,
-    public_input: &mut A::PublicInput,
-    transcript: &mut T,
-) -> Result<Round1<F, A>, ProvingError>
+    public_input: &A::PublicInput,
+) -> Result<(MainTraceCommitment<F>, A::PublicInput), ProvingError>
 where
     FieldElement<F>: ByteConversion,
 {
-    let main_trace = air.build_main_trace(raw_trace, public_input)?;
+    let preprocessed = commit_preprocessed_columns(air, domain)?;
+    commit_main_trace(air, raw_trace, domain, public_input, &preprocessed)
+}
 
-    let (mut trace_polys, mut evaluations, mut lde_trace_merkle_trees, mut lde_trace_merkle_roots) =
-        interpolate_and_commit(&main_trace, domain, transcript);
+/// Same as `round_1_commit_main_trace`, with the preprocessed columns already
+/// committed. Their commitments are copied in front of the ones of the columns
+/// built by the AIR.
+fn commit_main_trace<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    raw_trace: &A::RawTrace,
+    domain: &Domain<F>,
+    public_input: &A::PublicInput,
+    preprocessed: &MainTraceCommitment<F>,
+) -> Result<(MainTraceCommitment<F>, A::PublicInput), ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let (mut main_trace, public_input) = air.build_main_trace(raw_trace, public_input)?;
+    pad_main_trace(air, &mut main_trace)?;
+
+    // The segments are numbered among all the main columns, the preprocessed
+    // ones included, which are always interpolated over every row.
+    let first_column = preprocessed.main_trace.n_cols;
+    let segments: Vec<_> = air
+        .segments()
+        .into_iter()
+        .filter(|segment| segment.columns.start >= first_column)
+        .map(|segment| {
+            Segment::new(
+                segment.columns.start - first_column..segment.columns.end - first_column,
+                segment.period,
+            )
+        })
+        .collect();
+    let (trace_polys, lde_trace_evaluations, lde_trace_merkle_trees, lde_trace_merkle_roots) =
+        interpolate_and_commit(&main_trace, &segments, domain)?;
+
+    if first_column == 0 {
+        let main_trace_commitment = MainTraceCommitment {
+            main_trace,
+            trace_polys,
+            lde_trace_evaluations,
+            lde_trace_merkle_trees,
+            lde_trace_merkle_roots,
+        };
+        return Ok((main_trace_commitment, public_input));
+    }
+    let main_trace_commitment = MainTraceCommitment {
+        main_trace: preprocessed
+            .main_trace
+            .concatenate(main_trace.table, main_trace.n_cols),
+        trace_polys: [preprocessed.trace_polys.clone(), trace_polys].concat(),
+        lde_trace_evaluations: [
+            preprocessed.lde_trace_evaluations.clone(),
+            lde_trace_evaluations,
+        ]
+        .concat(),
+        lde_trace_merkle_trees: [
+            preprocessed.lde_trace_merkle_trees.clone(),
+            lde_trace_merkle_trees,
+        ]
+        .concat(),
+        lde_trace_merkle_roots: [
+            preprocessed.lde_trace_merkle_roots.clone(),
+            lde_trace_merkle_roots,
+        ]
+        .concat(),
+    };
+    Ok((main_trace_commitment, public_input))
+}
 
-    let rap_challenges = air.build_rap_challenges(transcript);
+/// Appends the main trace commitments to `transcript`, and then runs the
+/// auxiliary rounds of the AIR: each one draws RAP challenges and builds and
+/// commits to its auxiliary columns. The columns over the challenge field are
+/// built and committed last.
+pub fn round_1_randomized_air_with_preprocessing<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    air: &A,
+    main_trace_commitment: MainTraceCommitment<F>,
+    domain: &Domain<F>,
+    public_input: &A::PublicInput,
+    transcript: &mut T,
+    workspace: &mut ProverWorkspace<F, A::ChallengeField>,
+) -> Result<Round1<F, A>, ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let MainTraceCommitment {
+        main_trace,
+        mut trace_polys,
+        lde_trace_evaluations: mut evaluations,
+        mut lde_trace_merkle_trees,
+        mut lde_trace_merkle_roots,
+    } = main_trace_commitment;
+
+    // >>>> Send public input
+    append_public_input(air, public_input, transcript);
+    // >>>> Send commitments: [tⱼ]
+    for root in lde_trace_merkle_roots.iter() {
+        transcript.append(&root.to_bytes_be());
+    }
 
-    let aux_trace = air.build_auxiliary_trace(&main_trace, &rap_challenges, public_input);
+    let mut rap_challenges = air.build_rap_challenges(transcript);
+
+    let rounds = air.auxiliary_rounds().len();
+    let extension_columns = air.number_extension_columns();
+    // Main columns and the auxiliary columns of the rounds so far, only kept
+    // when there are later rounds or extension columns to build from them.
+    let mut trace = main_trace;
+    for round in 0..rounds {
+        let aux_trace = if round == 0 {
+            air.build_auxiliary_trace(&trace, &rap_challenges, public_input)
+        } else {
+            air.extend_rap_challenges(round, &mut rap_challenges, transcript);
+            air.build_auxiliary_round(round, &trace, &rap_challenges, public_input)
+        };
+        if aux_trace.is_empty() {
+            continue;
+        }
 
-    if !aux_trace.is_empty() {
         // Check that this is valid for interpolation
         let (aux_trace_polys, aux_trace_polys_evaluations, aux_merkle_trees, aux_merkle_roots) =
-            interpolate_and_commit(&aux_trace, domain, transcript);
+            interpolate_and_commit(&aux_trace, &[], domain)?;
+
+        // >>>> Send commitments: [tⱼ] of the auxiliary columns
+        for root in aux_merkle_roots.iter() {
+            transcript.append(&root.to_bytes_be());
+        }
+
         trace_polys.extend_from_slice(&aux_trace_polys);
         evaluations.extend_from_slice(&aux_trace_polys_evaluations);
         lde_trace_merkle_trees.extend_from_slice(&aux_merkle_trees);
         lde_trace_merkle_roots.extend_from_slice(&aux_merkle_roots);
+
+        if round + 1 < rounds || extension_columns > 0 {
+            trace = trace.concatenate(aux_trace.table, aux_trace.n_cols);
+        }
+    }
+
+    let (
+        extension_polys,
+        extension_evaluations,
+        lde_extension_merkle_trees,
+        lde_extension_merkle_roots,
+    ) = if extension_columns > 0 {
+        let extension_trace = air.build_extension_columns(&trace, &rap_challenges, public_input);
+        if extension_trace.n_cols != extension_columns {
+            return Err(ProvingError::WrongParameter(format!(
+                "the AIR built {} columns over the challenge field instead of {extension_columns}",
+                extension_trace.n_cols
+            )));
+        }
+        interpolate_and_commit_extension::<F, _>(&extension_trace, domain)?
+    } else {
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new())
+    };
+
+    // >>>> Send commitments: [tⱼ] of the columns over the challenge field
+    for root in lde_extension_merkle_roots.iter() {
+        transcript.append(&A::ChallengeField::to_bytes_be(root));
     }
+    let lde_extension_trace = if extension_evaluations.is_empty() {
+        TraceTable::empty()
+    } else {
+        TraceTable::new_from_cols(&extension_evaluations)
+    };
 
-    let lde_trace = TraceTable::new_from_cols(&evaluations);
+    let lde_trace = TraceTable::new_from_cols_with_buffer(
+        &evaluations,
+        core::mem::take(&mut workspace.lde_trace_table),
+    );
 
     Ok(Round1 {
         trace_polys,
         lde_trace,
         lde_trace_merkle_roots,
         lde_trace_merkle_trees,
+        extension_polys,
+        lde_extension_trace,
+        lde_extension_merkle_trees,
+        lde_extension_merkle_roots,
         rap_challenges,
     })
 }
 
-fn round_2_compute_composition_polynomial<F, A>(
+/// Evaluates the constraints over the LDE domain with the given coefficients,
+/// interpolates the composition polynomial H = ∑ xⁱHᵢ(xᴺ), with N the number of
+/// parts of the AIR, and commits to every Hᵢ. The commitments are not appended
+/// to the transcript.
+pub fn round_2_compute_composition_polynomial<F, E, A>(
     air: &A,
     domain: &Domain<F>,
     round_1_result: &Round1<F, A>,
     public_input: &A::PublicInput,
-    transition_coeffs: &[(FieldElement<F>, FieldElement<F>)],
-    boundary_coeffs: &[(FieldElement<F>, FieldElement<F>)],
-) -> Round2<F>
+    transition_coeffs: &[(FieldElement<E>, FieldElement<E>)],
+    boundary_coeffs: &[(FieldElement<E>, FieldElement<E>)],
+    workspace: &mut ProverWorkspace<F, E>,
+) -> Result<Round2<E>, ProvingError>
 where
     F: IsFFTField,
-    A: AIR<Field = F>,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
     FieldElement<F>: ByteConversion,
 {
+    compute_composition_polynomial(
+        air,
+        domain,
+        round_1_result,
+        public_input,
+        transition_coeffs,
+        boundary_coeffs,
+        workspace,
+        None,
+    )
+}
+
+/// Same as `round_2_compute_composition_polynomial`, but the evaluation of the
+/// constraints stops with `ProvingError::Cancelled` once `cancellation` is set.
+#[allow(clippy::too_many_arguments)]
+fn compute_composition_polynomial<F, E, A>(
+    air: &A,
+    domain: &Domain<F>,
+    round_1_result: &Round1<F, A>,
+    public_input: &A::PublicInput,
+    transition_coeffs: &[(FieldElement<E>, FieldElement<E>)],
+    boundary_coeffs: &[(FieldElement<E>, FieldElement<E>)],
+    workspace: &mut ProverWorkspace<F, E>,
+    cancellation: Option<&AtomicBool>,
+) -> Result<Round2<E>, ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    FieldElement<F>: ByteConversion,
+{
+    let coset_offset = E::embed(&domain.coset_offset);
+
     // Create evaluation table
-    let evaluator = ConstraintEvaluator::new(
+    let mut evaluator = ConstraintEvaluator::new(
         air,
         &round_1_result.trace_polys,
+        &round_1_result.extension_polys,
         &domain.trace_primitive_root,
         public_input,
         &round_1_result.rap_challenges,
-    );
+    )?;
+    if let Some(cancellation) = cancellation {
+        evaluator = evaluator.with_cancellation(cancellation);
+    }
 
     let constraint_evaluations = evaluator.evaluate(
         &round_1_result.lde_trace,
+        &round_1_result.lde_extension_trace,
         domain,
         transition_coeffs,
         boundary_coeffs,
         &round_1_result.rap_challenges,
-    );
+        core::mem::take(&mut workspace.constraint_evaluations),
+    )?;
 
     // Get the composition poly H
-    let composition_poly = constraint_evaluations.compute_composition_poly(&domain.coset_offset);
-    let (composition_poly_even, composition_poly_odd) = composition_poly.even_odd_decomposition();
-
-    let lde_composition_poly_even_evaluations = evaluate_polynomial_on_lde_domain(
-        &composition_poly_even,
-        domain.blowup_factor,
-        domain.interpolation_domain_size,
-        &domain.coset_offset,
-    )
-    .unwrap();
-    let lde_composition_poly_odd_evaluations = evaluate_polynomial_on_lde_domain(
-        &composition_poly_odd,
-        domain.blowup_factor,
-        domain.interpolation_domain_size,
-        &domain.coset_offset,
-    )
-    .unwrap();
+    let composition_poly = constraint_evaluations
+        .compute_composition_poly::<F>(&coset_offset)
+        .map_err(ProvingError::Interpolation)?;
+    workspace.constraint_evaluations = constraint_evaluations.evaluations_acc;
+    let composition_poly_parts =
+        split_composition_poly(&composition_poly, air.composition_poly_parts());
+
+    let lde_composition_poly_evaluations = composition_poly_parts
+        .iter()
+        .map(|part| {
+            evaluate_challenge_polynomial_on_lde_domain::<F, _>(
+                part,
+                domain.blowup_factor,
+                domain.interpolation_domain_size,
+                &coset_offset,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let (composition_poly_merkle_trees, composition_poly_roots) = batch_commit(vec![
-        &lde_composition_poly_even_evaluations,
-        &lde_composition_poly_odd_evaluations,
-    ]);
+    let (composition_poly_merkle_trees, composition_poly_roots) =
+        batch_commit_challenge::<F, _>(lde_composition_poly_evaluations.iter().collect());
 
-    Round2 {
-        composition_poly_even,
-        lde_composition_poly_even_evaluations,
-        composition_poly_even_merkle_tree: composition_poly_merkle_trees[0].clone(),
-        composition_poly_even_root: composition_poly_roots[0].clone(),
-        composition_poly_odd,
-        lde_composition_poly_odd_evaluations,
-        composition_poly_odd_merkle_tree: composition_poly_merkle_trees[1].clone(),
-        composition_poly_odd_root: composition_poly_roots[1].clone(),
-    }
+    Ok(Round2 {
+        composition_poly_parts,
+        lde_composition_poly_evaluations,
+        composition_poly_merkle_trees,
+        composition_poly_roots,
+    })
+}
+
+/// Splits H into the parts H₀, ..., Hₙ₋₁ such that H = ∑ xⁱHᵢ(xᴺ). The
+/// coefficients of Hᵢ are the ones of H at the positions congruent to i mod N.
+fn split_composition_poly<F: IsField>(
+    composition_poly: &Polynomial<FieldElement<F>>,
+    parts: usize,
+) -> Vec<Polynomial<FieldElement<F>>> {
+    let coefficients = composition_poly.coefficients();
+    (0..parts)
+        .map(|part| {
+            let part_coefficients: Vec<_> = coefficients
+                .iter()
+                .skip(part)
+                .step_by(parts)
+                .cloned()
+                .collect();
+            Polynomial::new(&part_coefficients)
+        })
+        .collect()
 }
 
-fn round_3_evaluate_polynomials_in_out_of_domain_element<F: IsFFTField, A: AIR<Field = F>>(
+/// Evaluates the parts Hᵢ at zᴺ, and the trace polynomials on the frame of z.
+pub fn round_3_evaluate_polynomials_in_out_of_domain_element<F, E, A>(
     air: &A,
     domain: &Domain<F>,
     round_1_result: &Round1<F, A>,
-    round_2_result: &Round2<F>,
-    z: &FieldElement<F>,
-) -> Round3<F>
+    round_2_result: &Round2<E>,
+    z: &FieldElement<E>,
+) -> Round3<E>
 where
-    FieldElement<F>: ByteConversion,
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
 {
-    let z_squared = z.square();
+    let z_power = z.pow(round_2_result.composition_poly_parts.len());
 
-    // Evaluate H_1 and H_2 in z^2.
-    let composition_poly_even_ood_evaluation =
-        round_2_result.composition_poly_even.evaluate(&z_squared);
-    let composition_poly_odd_ood_evaluation =
-        round_2_result.composition_poly_odd.evaluate(&z_squared);
+    // Evaluate every Hᵢ in zᴺ.
+    let composition_poly_ood_evaluations = round_2_result
+        .composition_poly_parts
+        .iter()
+        .map(|part| part.evaluate(&z_power))
+        .collect();
 
     // Returns the Out of Domain Frame for the given trace polynomials, out of domain evaluation point (called `z` in the literature),
     // frame offsets given by the AIR and primitive root used for interpolating the trace polynomials.
@@ -292,54 +946,94 @@ where
     //
     // In the fibonacci example, the ood frame is simply the evaluations `[t(z), t(z * g), t(z * g^2)]`, where `t` is the trace
     // polynomial and `g` is the primitive root of unity used when interpolating `t`.
-    let trace_ood_evaluations = Frame::get_trace_evaluations(
+    // Only the values in the frame layout of the AIR are evaluated.
+    let transition_offsets = &air.context().transition_offsets;
+    let layout = FrameLayout::of(air);
+    let trace_ood_evaluations = Frame::<E>::get_trace_evaluations(
         &round_1_result.trace_polys,
+        &round_1_result.extension_polys,
         z,
-        &air.context().transition_offsets,
+        transition_offsets,
+        &layout,
         &domain.trace_primitive_root,
     );
 
     Round3 {
         trace_ood_evaluations,
-        composition_poly_even_ood_evaluation,
-        composition_poly_odd_ood_evaluation,
+        composition_poly_ood_evaluations,
     }
 }
 
-fn round_4_compute_and_run_fri_on_the_deep_composition_polynomial<
+/// Builds the DEEP composition polynomial, runs FRI on it and opens the trace
+/// and composition polynomials at the first query.
+#[allow(clippy::too_many_arguments)]
+pub fn round_4_compute_and_run_fri_on_the_deep_composition_polynomial<F, E, A, T>(
+    air: &A,
+    domain: &Domain<F>,
+    round_1_result: &Round1<F, A>,
+    round_2_result: &Round2<E>,
+    round_3_result: &Round3<E>,
+    z: &FieldElement<E>,
+    transcript: &mut T,
+    progress: &mut dyn FnMut(ProvingStage, f32),
+) -> Result<Round4<F, E>, ProvingError>
+where
     F: IsFFTField,
-    A: AIR<Field = F>,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
     T: Transcript,
->(
+    FieldElement<F>: ByteConversion,
+{
+    run_fri_on_the_deep_composition_polynomial(
+        air,
+        domain,
+        round_1_result,
+        round_2_result,
+        round_3_result,
+        z,
+        transcript,
+        &mut |stage, done| {
+            progress(stage, done);
+            Ok(())
+        },
+    )
+}
+
+/// Same as `round_4_compute_and_run_fri_on_the_deep_composition_polynomial`,
+/// but an error returned by `start`, which is called with each stage of the
+/// round, stops it. Stages include each layer of the FRI commit phase.
+#[allow(clippy::too_many_arguments)]
+fn run_fri_on_the_deep_composition_polynomial<F, E, A, T>(
     air: &A,
     domain: &Domain<F>,
     round_1_result: &Round1<F, A>,
-    round_2_result: &Round2<F>,
-    round_3_result: &Round3<F>,
-    z: &FieldElement<F>,
+    round_2_result: &Round2<E>,
+    round_3_result: &Round3<E>,
+    z: &FieldElement<E>,
     transcript: &mut T,
-) -> Round4<F>
+    start: &mut dyn FnMut(ProvingStage, f32) -> Result<(), ProvingError>,
+) -> Result<Round4<F, E>, ProvingError>
 where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
     FieldElement<F>: ByteConversion,
 {
-    let coset_offset_u64 = air.context().options.coset_offset;
-    let coset_offset = FieldElement::<F>::from(coset_offset_u64);
+    let coset_offset = E::embed(&domain.coset_offset);
 
-    // <<<< Receive challenges: 𝛾, 𝛾'
-    let composition_poly_coeffients = [
-        transcript_to_field(transcript),
-        transcript_to_field(transcript),
-    ];
+    // <<<< Receive challenges: 𝛾ᵢ
+    let composition_poly_coeffients =
+        batch_sample_challenges::<F, E, T>(round_2_result.composition_poly_parts.len(), transcript);
     // <<<< Receive challenges: 𝛾ⱼ, 𝛾ⱼ'
-    let trace_poly_coeffients = batch_sample_challenges::<F, T>(
-        air.context().transition_offsets.len() * air.context().trace_columns,
-        transcript,
-    );
+    let trace_poly_coeffients =
+        batch_sample_challenges::<F, E, T>(FrameLayout::of(air).num_entries(), transcript);
 
     // Compute p₀ (deep composition polynomial)
+    start(ProvingStage::DeepComposition, 0.65)?;
     let deep_composition_poly = compute_deep_composition_poly(
         air,
-        &round_1_result.trace_polys,
+        &round_1_result.embedded_trace_polys(),
         round_2_result,
         round_3_result,
         z,
@@ -351,14 +1045,20 @@ where
     let domain_size = domain.lde_roots_of_unity_coset.len();
 
     // FRI commit and query phases
-    let (fri_last_value, fri_layers) = fri_commit_phase(
-        domain.root_order as usize,
+    let number_layers = domain.root_order as usize;
+    let (fri_last_value, fri_layers) = fri_commit_phase::<F, E, _>(
+        number_layers,
         deep_composition_poly,
         transcript,
         &coset_offset,
         domain_size,
-    );
-    let (query_list, iota_0) = fri_query_phase(air, domain_size, &fri_layers, transcript);
+        &mut |layer| {
+            let done = layer as f32 / number_layers as f32;
+            start(ProvingStage::FriLayer(layer), 0.7 + 0.25 * done)
+        },
+    )?;
+    start(ProvingStage::FriQueries, 0.95)?;
+    let (query_list, iota_0) = fri_query_phase(air, domain_size, &fri_layers, transcript)?;
 
     let fri_layers_merkle_roots: Vec<_> = fri_layers
         .iter()
@@ -366,238 +1066,1197 @@ where
         .collect();
 
     let deep_poly_openings =
-        open_deep_composition_poly(domain, round_1_result, round_2_result, iota_0);
+        open_deep_composition_poly(domain, round_1_result, round_2_result, iota_0)?;
 
-    Round4 {
+    Ok(Round4 {
         fri_last_value,
         fri_layers_merkle_roots,
         deep_poly_openings,
         query_list,
+    })
+}
+
+/// Returns the DEEP composition polynomial that the prover then commits to using
+/// FRI. This polynomial is a linear combination of the trace polynomial and the
+/// composition polynomial, with coefficients sampled by the verifier (i.e. using Fiat-Shamir).
+/// The trace polynomials are those of every column, over the challenge field.
+#[allow(clippy::too_many_arguments)]
+fn compute_deep_composition_poly<A: AIR, F: IsFFTField, E: IsChallengeField<F>>(
+    air: &A,
+    trace_polys: &[Polynomial<FieldElement<E>>],
+    round_2_result: &Round2<E>,
+    round_3_result: &Round3<E>,
+    z: &FieldElement<E>,
+    primitive_root: &FieldElement<F>,
+    composition_poly_gammas: &[FieldElement<E>],
+    trace_terms_gammas: &[FieldElement<E>],
+) -> Polynomial<FieldElement<E>> {
+    // Compute composition polynomial terms of the deep composition polynomial.
+    // ∑ᵢ 𝛾ᵢ ( Hᵢ − Hᵢ(zᴺ) ) / ( X − zᴺ )
+    let z_power = z.pow(round_2_result.composition_poly_parts.len());
+    let mut h_terms = Polynomial::zero();
+    for ((h_i, h_i_z_power), gamma) in round_2_result
+        .composition_poly_parts
+        .iter()
+        .zip(&round_3_result.composition_poly_ood_evaluations)
+        .zip(composition_poly_gammas)
+    {
+        let mut h_i_term = gamma * (h_i - h_i_z_power);
+        h_i_term.ruffini_division_inplace(&z_power);
+        h_terms = h_terms + h_i_term;
+    }
+
+    // Get trace evaluations needed for the trace terms of the deep composition polynomial
+    let transition_offsets = &air.context().transition_offsets;
+    let layout = FrameLayout::of(air);
+    let trace_frame_evaluations = layout.frame(&round_3_result.trace_ood_evaluations);
+
+    // Compute the sum of all the trace terms of the deep composition polynomial.
+    // There is one term for every value of the frame layout, taken column by column.
+    // ∑ ⱼₖ [ 𝛾ⱼₖ ( tⱼ − tⱼ(zgᵏ) ) / ( X − zgᵏ )]
+    let mut trace_terms = Polynomial::zero();
+    for ((row, column), trace_gamma) in layout.entries_by_column().zip(trace_terms_gammas) {
+        let t_j_z = trace_frame_evaluations.get_row(row)[column].clone();
+        let z_shifted = z * E::embed(&primitive_root.pow(transition_offsets[row]));
+        let mut poly = &trace_polys[column] - t_j_z;
+        poly.ruffini_division_inplace(&z_shifted);
+        trace_terms = trace_terms + poly * trace_gamma;
+    }
+
+    h_terms + trace_terms
+}
+
+fn open_deep_composition_poly<F, E, A>(
+    domain: &Domain<F>,
+    round_1_result: &Round1<F, A>,
+    round_2_result: &Round2<E>,
+    index_to_open: usize,
+) -> Result<DeepPolynomialOpenings<F, E>, ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    FieldElement<F>: ByteConversion,
+{
+    let index = index_to_open % domain.lde_roots_of_unity_coset.len();
+
+    // Hᵢ openings
+    let lde_composition_poly_proofs = round_2_result
+        .composition_poly_merkle_trees
+        .iter()
+        .map(|tree| {
+            tree.get_proof_by_pos(index)
+                .ok_or(ProvingError::Commitment(index))
+        })
+        .collect::<Result<_, _>>()?;
+    let lde_composition_poly_evaluations = round_2_result
+        .lde_composition_poly_evaluations
+        .iter()
+        .map(|evaluations| evaluations[index].clone())
+        .collect();
+
+    // Trace polynomials openings
+    let lde_trace_merkle_proofs = round_1_result
+        .lde_trace_merkle_trees
+        .iter()
+        .map(|tree| {
+            tree.get_proof_by_pos(index)
+                .ok_or(ProvingError::Commitment(index))
+        })
+        .collect::<Result<_, _>>()?;
+    let lde_trace_evaluations = round_1_result.lde_trace.get_row(index).to_vec();
+
+    // Openings of the columns over the challenge field
+    let lde_extension_merkle_proofs = round_1_result
+        .lde_extension_merkle_trees
+        .iter()
+        .map(|tree| {
+            tree.get_proof_by_pos(index)
+                .ok_or(ProvingError::Commitment(index))
+        })
+        .collect::<Result<_, _>>()?;
+    let lde_extension_evaluations = if round_1_result.lde_extension_trace.is_empty() {
+        Vec::new()
+    } else {
+        round_1_result.lde_extension_trace.get_row(index).to_vec()
+    };
+
+    Ok(DeepPolynomialOpenings {
+        lde_composition_poly_proofs,
+        lde_composition_poly_evaluations,
+        lde_trace_merkle_proofs,
+        lde_trace_evaluations,
+        lde_extension_merkle_proofs,
+        lde_extension_evaluations,
+    })
+}
+
+/// Proves the execution in `trace`. Besides the proof, the output holds the
+/// public input completed by `AIR::build_main_trace`, which is the statement
+/// the verifier has to be given.
+pub fn prove<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    prove_with_workspace(trace, air, public_input, &mut ProverWorkspace::new())
+}
+
+/// Same as `prove`, but takes the LDE trace table and the constraint
+/// evaluations from `workspace` and gives them back once the proof is built,
+/// so that successive calls reuse those two allocations.
+pub fn prove_with_workspace<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    workspace: &mut ProverWorkspace<F, A::ChallengeField>,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+
+    prove_rounds(
+        trace,
+        air,
+        public_input,
+        &domain,
+        None,
+        workspace,
+        &mut round_0_transcript_initialization(),
+        ProvingJob::default(),
+    )
+}
+
+/// Proves an execution split in segments, given by their raw traces and
+/// public inputs, with a proof per segment. The final state of each segment,
+/// as given by `AIR::final_state`, has to be the initial state of the next
+/// one, so that `verifier::verify_chain` accepts the proofs as a whole
+/// execution. AIRs without a state can't be chained.
+pub fn prove_chain<F: IsFFTField, A: AIR<Field = F>>(
+    segments: &[(A::RawTrace, A::PublicInput)],
+    air: &A,
+) -> Result<Vec<ProverOutput<A>>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    if let Some((_, public_input)) = segments.first() {
+        if air.initial_state(public_input).is_empty() {
+            return Err(ProvingError::WrongParameter(String::from(
+                "the AIR has no state to link the segments with",
+            )));
+        }
+    }
+    let mut workspace = ProverWorkspace::new();
+    let mut outputs: Vec<ProverOutput<A>> = Vec::with_capacity(segments.len());
+    for (index, (trace, public_input)) in segments.iter().enumerate() {
+        let output = prove_with_workspace(trace, air, public_input, &mut workspace)?;
+        if let Some(previous) = outputs.last() {
+            if air.final_state(&previous.public_values) != air.initial_state(&output.public_values)
+            {
+                return Err(ProvingError::WrongParameter(format!(
+                    "segment {index} doesn't start from the final state of the previous one"
+                )));
+            }
+        }
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
+/// Same as `prove`, but runs the protocol on `transcript` instead of a fresh
+/// one. Anything appended to the transcript beforehand, such as other proofs or
+/// statements, is bound to this proof. The verifier has to replay it with
+/// `verify_with_transcript` on a transcript in the same state.
+pub fn prove_with_transcript<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    transcript: &mut T,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+
+    prove_rounds(
+        trace,
+        air,
+        public_input,
+        &domain,
+        None,
+        &mut ProverWorkspace::new(),
+        transcript,
+        ProvingJob::default(),
+    )
+}
+
+/// Same as `prove`, but calls `progress` with each stage the prover starts and
+/// an approximate fraction of the work done so far, between 0 and 1. It is
+/// called from the proving thread, so it should return quickly.
+pub fn prove_with_progress<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    mut progress: impl FnMut(ProvingStage, f32),
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+
+    prove_rounds(
+        trace,
+        air,
+        public_input,
+        &domain,
+        None,
+        &mut ProverWorkspace::new(),
+        &mut round_0_transcript_initialization(),
+        ProvingJob {
+            progress: Some(&mut progress),
+            ..Default::default()
+        },
+    )
+}
+
+/// Same as `prove`, but stops once `cancellation` is set and returns
+/// `ProvingError::Cancelled`, releasing every buffer of the proof. The flag is
+/// checked at the start of each stage, while the constraints are evaluated and
+/// before each FRI layer.
+/// The flag can be set from another thread, e.g. through an `Arc<AtomicBool>`.
+pub fn prove_with_cancellation<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    cancellation: &AtomicBool,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+
+    prove_rounds(
+        trace,
+        air,
+        public_input,
+        &domain,
+        None,
+        &mut ProverWorkspace::new(),
+        &mut round_0_transcript_initialization(),
+        ProvingJob {
+            cancellation: Some(cancellation),
+            ..Default::default()
+        },
+    )
+}
+
+/// Same as `prove`, but writes the data of each completed round to the file
+/// `checkpoint`, overwriting the previous one. If the process dies, the proof
+/// can be finished from that file with `resume_proof`.
+#[cfg(feature = "std")]
+pub fn prove_with_checkpoints<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    checkpoint: &Path,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+
+    prove_rounds(
+        trace,
+        air,
+        public_input,
+        &domain,
+        None,
+        &mut ProverWorkspace::new(),
+        &mut round_0_transcript_initialization(),
+        ProvingJob {
+            checkpoint: Some(checkpoint),
+            ..Default::default()
+        },
+    )
+}
+
+/// Finishes a proof started by `prove_with_checkpoints` from the last round
+/// saved in `checkpoint`, and keeps checkpointing to it. `trace`, `air` and
+/// `public_input` have to be the ones the proof was started with: the main
+/// trace is built again to recover the completed public input, but it is not
+/// interpolated nor committed.
+#[cfg(feature = "std")]
+pub fn resume_proof<F: IsFFTField, A: AIR<Field = F>>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    checkpoint: &Path,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+    let saved =
+        checkpoint::load::<F, A::ChallengeField>(checkpoint).map_err(ProvingError::Checkpoint)?;
+
+    let mut job = ProvingJob {
+        checkpoint: Some(checkpoint),
+        ..Default::default()
+    };
+    let mut workspace = ProverWorkspace::new();
+    let mut transcript = round_0_transcript_initialization();
+
+    job.start(ProvingStage::MainTrace, 0.0)?;
+    let timer = Timer::start();
+    let (_, public_input) = air.build_main_trace(trace, public_input)?;
+    let air = &air_for_public_input(air, &public_input)?;
+
+    let trace_columns = saved.trace_polys.len();
+    let extension_columns = saved.extension_polys.len();
+    if saved.header != checkpoint_header(air, trace_columns, saved.header.digest)
+        || saved.lde_trace.n_cols != trace_columns
+        || saved.lde_trace.n_rows() != domain.lde_domain_size()
+        || extension_columns != air.number_extension_columns()
+        || saved.lde_extension_trace.n_cols != extension_columns
+        || (extension_columns > 0 && saved.lde_extension_trace.n_rows() != domain.lde_domain_size())
+    {
+        return Err(ProvingError::Checkpoint(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the checkpoint doesn't belong to a proof of this AIR",
+        )));
+    }
+
+    let round_1_result = restore_round_1(
+        air,
+        &public_input,
+        saved.header.main_columns as usize,
+        saved.trace_polys,
+        saved.lde_trace,
+        saved.extension_polys,
+        saved.lde_extension_trace,
+        &mut transcript,
+    );
+    if checkpoint_digest(air, &public_input, &round_1_result) != saved.header.digest {
+        return Err(ProvingError::Checkpoint(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the checkpoint doesn't belong to this trace and public input",
+        )));
+    }
+    job.metrics.round_1 = timer.elapsed();
+
+    let proof = match saved.composition {
+        None => rounds_2_to_4(
+            air,
+            &public_input,
+            &domain,
+            round_1_result,
+            &mut workspace,
+            &mut transcript,
+            &mut job,
+        )?,
+        Some(composition) => {
+            job.start(ProvingStage::ConstraintEvaluation, 0.3)?;
+            let round_2_result =
+                restore_round_2(air, &round_1_result, composition, &mut transcript);
+            rounds_3_and_4(
+                air,
+                &domain,
+                round_1_result,
+                round_2_result,
+                &mut workspace,
+                &mut transcript,
+                &mut job,
+            )?
+        }
+    };
+    Ok(ProverOutput {
+        proof,
+        public_values: public_input,
+        metrics: job.metrics,
+    })
+}
+
+/// Appends the encoding of `public_input` to `transcript`, if the AIR has
+/// one.
+fn append_public_input<A: AIR, T: Transcript>(
+    air: &A,
+    public_input: &A::PublicInput,
+    transcript: &mut T,
+) {
+    let bytes = air.public_input_bytes(public_input);
+    if !bytes.is_empty() {
+        transcript.append(&bytes);
+    }
+}
+
+/// Appends the public input and the trace commitments of round 1 to
+/// `transcript` in the order the prover sent them, drawing the RAP challenges
+/// of each auxiliary round in between. Returns the challenges of all the
+/// rounds.
+pub(crate) fn replay_round_1<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    air: &A,
+    public_input: &A::PublicInput,
+    lde_trace_merkle_roots: &[FieldElement<F>],
+    lde_extension_merkle_roots: &[FieldElement<A::ChallengeField>],
+    main_columns: usize,
+    transcript: &mut T,
+) -> A::RAPChallenges
+where
+    FieldElement<F>: ByteConversion,
+{
+    let (main_roots, mut aux_roots) = lde_trace_merkle_roots.split_at(main_columns);
+
+    // >>>> Send public input
+    append_public_input(air, public_input, transcript);
+    // >>>> Send commitments: [tⱼ]
+    for root in main_roots {
+        transcript.append(&root.to_bytes_be());
+    }
+    let mut rap_challenges = air.build_rap_challenges(transcript);
+    for (round, columns) in air.auxiliary_rounds().into_iter().enumerate() {
+        if round > 0 {
+            air.extend_rap_challenges(round, &mut rap_challenges, transcript);
+        }
+        let (round_roots, rest) = aux_roots.split_at(columns.min(aux_roots.len()));
+        // >>>> Send commitments: [tⱼ] of the auxiliary columns
+        for root in round_roots {
+            transcript.append(&root.to_bytes_be());
+        }
+        aux_roots = rest;
+    }
+    // >>>> Send commitments: [tⱼ] of the columns over the challenge field
+    for root in lde_extension_merkle_roots {
+        transcript.append(&A::ChallengeField::to_bytes_be(root));
+    }
+    rap_challenges
+}
+
+/// Rebuilds the output of round 1 from a checkpoint, replaying its messages
+/// on `transcript` to draw the same RAP challenges.
+#[cfg(feature = "std")]
+fn restore_round_1<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    air: &A,
+    public_input: &A::PublicInput,
+    main_columns: usize,
+    trace_polys: Vec<Polynomial<FieldElement<F>>>,
+    lde_trace: TraceTable<F>,
+    extension_polys: Vec<Polynomial<FieldElement<A::ChallengeField>>>,
+    lde_extension_trace: TraceTable<A::ChallengeField>,
+    transcript: &mut T,
+) -> Round1<F, A>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let (lde_trace_merkle_trees, lde_trace_merkle_roots) =
+        batch_commit(lde_trace.cols().iter().collect());
+    let (lde_extension_merkle_trees, lde_extension_merkle_roots) =
+        batch_commit_challenge::<F, _>(lde_extension_trace.cols().iter().collect());
+    let rap_challenges = replay_round_1(
+        air,
+        public_input,
+        &lde_trace_merkle_roots,
+        &lde_extension_merkle_roots,
+        main_columns,
+        transcript,
+    );
+
+    Round1 {
+        trace_polys,
+        lde_trace,
+        lde_trace_merkle_trees,
+        lde_trace_merkle_roots,
+        extension_polys,
+        lde_extension_trace,
+        lde_extension_merkle_trees,
+        lde_extension_merkle_roots,
+        rap_challenges,
+    }
+}
+
+/// Rebuilds the output of round 2 from a checkpoint, replaying its challenges
+/// and messages on `transcript`.
+#[cfg(feature = "std")]
+fn restore_round_2<F, E, A, T>(
+    air: &A,
+    round_1_result: &Round1<F, A>,
+    composition: CompositionCheckpoint<E>,
+    transcript: &mut T,
+) -> Round2<E>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
+{
+    // The coefficients were only needed to compute the composition polynomial.
+    sample_composition_coefficients(air, round_1_result.num_columns(), transcript);
+
+    let (composition_poly_merkle_trees, composition_poly_roots) =
+        batch_commit_challenge::<F, _>(composition.evaluations.iter().collect());
+
+    // >>>> Send commitments: [Hᵢ]
+    for root in composition_poly_roots.iter() {
+        transcript.append(&E::to_bytes_be(root));
+    }
+
+    Round2 {
+        composition_poly_parts: composition.parts,
+        lde_composition_poly_evaluations: composition.evaluations,
+        composition_poly_merkle_trees,
+        composition_poly_roots,
+    }
+}
+
+/// Prover for a fixed AIR. It keeps the evaluation domain and the buffers of
+/// a `ProverWorkspace` between proofs, and gathers the proving configuration
+/// in one place. The proofs have no zero-knowledge masking and no proof of
+/// work, so there are no options for them:
+///
+/// ```ignore
+/// let mut prover = Prover::new(air).with_options(options)?;
+/// let output = prover.prove(&raw_trace, &public_input)?;
+/// ```
+pub struct Prover<A: AIR, T: Transcript> {
+    air: A,
+    new_transcript: fn() -> T,
+    domain: Option<Domain<A::Field>>,
+    /// Commitment to the preprocessed columns, made with the domain.
+    preprocessed: Option<MainTraceCommitment<A::Field>>,
+    workspace: ProverWorkspace<A::Field, A::ChallengeField>,
+    check_degrees: bool,
+}
+
+impl<A: AIR> Prover<A, ProverTranscript> {
+    pub fn new(air: A) -> Self {
+        Self {
+            air,
+            new_transcript: round_0_transcript_initialization,
+            domain: None,
+            preprocessed: None,
+            workspace: ProverWorkspace::new(),
+            check_degrees: false,
+        }
     }
 }
 
-/// Returns the DEEP composition polynomial that the prover then commits to using
-/// FRI. This polynomial is a linear combination of the trace polynomial and the
-/// composition polynomial, with coefficients sampled by the verifier (i.e. using Fiat-Shamir).
-#[allow(clippy::too_many_arguments)]
-fn compute_deep_composition_poly<A: AIR, F: IsFFTField>(
-    air: &A,
-    trace_polys: &[Polynomial<FieldElement<F>>],
-    round_2_result: &Round2<F>,
-    round_3_result: &Round3<F>,
-    z: &FieldElement<F>,
-    primitive_root: &FieldElement<F>,
-    composition_poly_gammas: &[FieldElement<F>; 2],
-    trace_terms_gammas: &[FieldElement<F>],
-) -> Polynomial<FieldElement<F>> {
-    // Compute composition polynomial terms of the deep composition polynomial.
-    let h_1 = &round_2_result.composition_poly_even;
-    let h_1_z2 = &round_3_result.composition_poly_even_ood_evaluation;
-    let h_2 = &round_2_result.composition_poly_odd;
-    let h_2_z2 = &round_3_result.composition_poly_odd_ood_evaluation;
-    let gamma = &composition_poly_gammas[0];
-    let gamma_p = &composition_poly_gammas[1];
-    let z_squared = z.square();
-
-    // 𝛾 ( H₁ − H₁(z²) ) / ( X − z² )
-    let mut h_1_term = gamma * (h_1 - h_1_z2);
-    h_1_term.ruffini_division_inplace(&z_squared);
-
-    // 𝛾' ( H₂ − H₂(z²) ) / ( X − z² )
-    let mut h_2_term = gamma_p * (h_2 - h_2_z2);
-    h_2_term.ruffini_division_inplace(&z_squared);
+impl<A: AIR, T: Transcript> Prover<A, T> {
+    /// Replaces the proof options of the AIR. Proofs have to be verified with
+    /// an AIR that has the same options. Fails if the AIR doesn't implement
+    /// `AIR::context_mut`.
+    pub fn with_options(mut self, options: ProofOptions) -> Result<Self, ProvingError> {
+        let context = self.air.context_mut().ok_or_else(|| {
+            ProvingError::WrongParameter(String::from(
+                "the proof options of this AIR can't be changed",
+            ))
+        })?;
+        context.options = options;
+        self.domain = None;
+        self.preprocessed = None;
+        Ok(self)
+    }
 
-    // Get trace evaluations needed for the trace terms of the deep composition polynomial
-    let transition_offsets = &air.context().transition_offsets;
-    let trace_frame_evaluations = &round_3_result.trace_ood_evaluations;
+    /// Sets the function that creates the Fiat-Shamir transcript of each proof.
+    /// The verifier has to replay the proof on a transcript built the same way.
+    pub fn with_transcript<U: Transcript>(self, new_transcript: fn() -> U) -> Prover<A, U> {
+        Prover {
+            air: self.air,
+            new_transcript,
+            domain: self.domain,
+            preprocessed: self.preprocessed,
+            workspace: self.workspace,
+            check_degrees: self.check_degrees,
+        }
+    }
 
-    // Compute the sum of all the trace terms of the deep composition polynomial.
-    // There is one term for every trace polynomial and for every row in the frame.
-    // ∑ ⱼₖ [ 𝛾ₖ ( tⱼ − tⱼ(z) ) / ( X − zgᵏ )]
+    /// Checks, in every proof, that each transition constraint has at most its
+    /// declared degree and that the composition polynomial is below its degree
+    /// bound, failing with `ProvingError::TransitionDegree` or
+    /// `ProvingError::CompositionDegree` otherwise. This interpolates every
+    /// constraint separately, so it is meant for developing an AIR. Debug
+    /// builds always check the degrees of the transition constraints.
+    pub fn with_degree_check(mut self) -> Self {
+        self.check_degrees = true;
+        self
+    }
 
-    // @@@ this could be const
-    let mut trace_terms = Polynomial::zero();
-    for (i, t_j) in trace_polys.iter().enumerate() {
-        let i_times_trace_frame_evaluation = i * trace_frame_evaluations.len();
-        let iter_trace_gammas = trace_terms_gammas
-            .iter()
-            .skip(i_times_trace_frame_evaluation);
-        for ((evaluations, offset), elemen_trace_gamma) in trace_frame_evaluations
-            .iter()
-            .zip(transition_offsets)
-            .zip(iter_trace_gammas)
-        {
-            // @@@ we can avoid this clone
-            let t_j_z = evaluations[i].clone();
-            // @@@ this can be pre-computed
-            let z_shifted = z * primitive_root.pow(*offset);
-            let mut poly = t_j - t_j_z;
-            poly.ruffini_division_inplace(&z_shifted);
-            trace_terms = trace_terms + poly * elemen_trace_gamma;
+    pub fn air(&self) -> &A {
+        &self.air
+    }
+
+    pub fn prove(
+        &mut self,
+        trace: &A::RawTrace,
+        public_input: &A::PublicInput,
+    ) -> Result<ProverOutput<A>, ProvingError>
+    where
+        A: MaybeSync,
+        A::RAPChallenges: MaybeSync,
+        FieldElement<A::Field>: ByteConversion + MaybeSync,
+        FieldElement<A::ChallengeField>: MaybeSync,
+    {
+        if self.domain.is_none() {
+            check_domain_parameters(&self.air)?;
+            let domain = Domain::new(&self.air);
+            self.preprocessed = Some(commit_preprocessed_columns(&self.air, &domain)?);
+            self.domain = Some(domain);
         }
+        let domain = self.domain.as_ref().unwrap();
+
+        prove_rounds(
+            trace,
+            &self.air,
+            public_input,
+            domain,
+            self.preprocessed.as_ref(),
+            &mut self.workspace,
+            &mut (self.new_transcript)(),
+            ProvingJob {
+                check_degrees: self.check_degrees,
+                ..Default::default()
+            },
+        )
     }
+}
 
-    h_1_term + h_2_term + trace_terms
+/// Parameters of the proof that a checkpoint is tied to. `digest` is the
+/// `checkpoint_digest` of its round 1.
+#[cfg(feature = "std")]
+fn checkpoint_header<A: AIR>(
+    air: &A,
+    num_trace_polys: usize,
+    digest: [u8; 32],
+) -> CheckpointHeader {
+    CheckpointHeader {
+        trace_length: air.context().trace_length as u64,
+        blowup_factor: air.options().blowup_factor as u64,
+        coset_offset: air.options().coset_offset,
+        main_columns: num_trace_polys.saturating_sub(air.number_auxiliary_rap_columns()) as u64,
+        composition_parts: air.composition_poly_parts() as u64,
+        digest,
+    }
 }
 
-fn open_deep_composition_poly<F: IsFFTField, A: AIR<Field = F>>(
-    domain: &Domain<F>,
+/// Hash of the public input and the roots of the main trace, which ties a
+/// checkpoint to the trace and the public input it was written for.
+#[cfg(feature = "std")]
+fn checkpoint_digest<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    public_input: &A::PublicInput,
     round_1_result: &Round1<F, A>,
-    round_2_result: &Round2<F>,
-    index_to_open: usize,
-) -> DeepPolynomialOpenings<F>
+) -> [u8; 32]
 where
     FieldElement<F>: ByteConversion,
 {
-    let index = index_to_open % domain.lde_roots_of_unity_coset.len();
-
-    // H₁ openings
-    let lde_composition_poly_even_proof = round_2_result
-        .composition_poly_even_merkle_tree
-        .get_proof_by_pos(index)
-        .unwrap();
-    let lde_composition_poly_even_evaluation =
-        round_2_result.lde_composition_poly_even_evaluations[index].clone();
+    let main_columns = round_1_result
+        .trace_polys
+        .len()
+        .saturating_sub(air.number_auxiliary_rap_columns());
+    let mut transcript = round_0_transcript_initialization();
+    append_public_input(air, public_input, &mut transcript);
+    for root in &round_1_result.lde_trace_merkle_roots[..main_columns] {
+        transcript.append(&root.to_bytes_be());
+    }
+    transcript.challenge()
+}
 
-    // H₂ openings
-    let lde_composition_poly_odd_proof = round_2_result
-        .composition_poly_odd_merkle_tree
-        .get_proof_by_pos(index)
-        .unwrap();
-    let lde_composition_poly_odd_evaluation =
-        round_2_result.lde_composition_poly_odd_evaluations[index].clone();
+/// Checks that the auxiliary rounds of the AIR add up to its number of
+/// auxiliary columns, and that the trace has room for them and for the
+/// columns over the challenge field.
+pub(crate) fn check_auxiliary_rounds<A: AIR>(air: &A) -> Result<(), ProvingError> {
+    let round_columns: usize = air.auxiliary_rounds().iter().sum();
+    let aux_columns = air.number_auxiliary_rap_columns();
+    if round_columns != aux_columns {
+        return Err(ProvingError::WrongParameter(format!(
+            "the auxiliary rounds have {round_columns} columns instead of {aux_columns}"
+        )));
+    }
+    let extension_columns = air.number_extension_columns();
+    if aux_columns + extension_columns > air.context().trace_columns {
+        return Err(ProvingError::WrongParameter(format!(
+            "there are {} auxiliary columns in a trace of {} columns",
+            aux_columns + extension_columns,
+            air.context().trace_columns
+        )));
+    }
+    Ok(())
+}
 
-    // Trace polynomials openings
-    let lde_trace_merkle_proofs = round_1_result
-        .lde_trace_merkle_trees
-        .iter()
-        .map(|tree| tree.get_proof_by_pos(index).unwrap())
-        .collect();
-    let lde_trace_evaluations = round_1_result.lde_trace.get_row(index).to_vec();
+/// Checks that the parameters of the AIR are valid and that its trace length
+/// and blowup factor define an LDE domain over its field.
+pub(crate) fn check_domain_parameters<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+) -> Result<(), ProvingError> {
+    let trace_length = air.context().trace_length;
+    if !trace_length.is_power_of_two() {
+        return Err(ProvingError::InvalidTraceLength(trace_length));
+    }
+    air.context().validate()?;
+    let periodic_columns = air.periodic_columns();
+    check_periods(&periodic_columns, trace_length)?;
+    check_lookups(
+        &air.lookups(),
+        air.context().trace_columns,
+        periodic_columns.len(),
+    )?;
+    check_auxiliary_rounds(air)?;
+    check_row_subsets(
+        &air.transition_row_subsets(),
+        &air.context().transition_exemptions,
+        trace_length,
+    )?;
+    check_zerofiers(
+        &air.transition_zerofiers(),
+        air.num_transition_constraints(),
+        trace_length,
+    )?;
+    check_column_offsets(
+        &air.column_offsets(),
+        &air.context().transition_offsets,
+        air.context().trace_columns,
+    )?;
+    check_segments(
+        &air.segments(),
+        air.context().trace_columns
+            - air.number_auxiliary_rap_columns()
+            - air.number_extension_columns(),
+        trace_length,
+    )?;
+    let lde_root_order = (trace_length * air.options().blowup_factor as usize).trailing_zeros();
+    if lde_root_order as u64 > F::TWO_ADICITY {
+        return Err(ProvingError::WrongParameter(format!(
+            "the LDE domain of size 2^{lde_root_order} is larger than the two-adicity of the field"
+        )));
+    }
+    Ok(())
+}
 
-    DeepPolynomialOpenings {
-        lde_composition_poly_even_proof,
-        lde_composition_poly_even_evaluation,
-        lde_composition_poly_odd_proof,
-        lde_composition_poly_odd_evaluation,
-        lde_trace_merkle_proofs,
-        lde_trace_evaluations,
+/// The AIR of the statement with the completed `public_input`, checked like
+/// the one the proof started with. It must keep the trace length and the
+/// options, since the main trace is already committed with them.
+fn air_for_public_input<F: IsFFTField, A: AIR<Field = F>>(
+    air: &A,
+    public_input: &A::PublicInput,
+) -> Result<A, ProvingError> {
+    let resolved = air.with_public_input(public_input)?;
+    let (context, resolved_context) = (air.context(), resolved.context());
+    if resolved_context.trace_length != context.trace_length
+        || resolved_context.options.blowup_factor != context.options.blowup_factor
+        || resolved_context.options.coset_offset != context.options.coset_offset
+        || resolved_context.options.fri_number_of_queries != context.options.fri_number_of_queries
+    {
+        return Err(ProvingError::WrongParameter(String::from(
+            "the AIR of the public input has another trace length or other options",
+        )));
     }
+    check_domain_parameters(&resolved)?;
+    Ok(resolved)
 }
 
-// FIXME remove unwrap() calls and return errors
-pub fn prove<F: IsFFTField, A: AIR<Field = F>>(
+/// Commitments that the prover can send before drawing any challenge.
+pub struct Commitments<F: IsFFTField> {
+    /// Merkle roots of the main trace columns, [tⱼ].
+    pub lde_trace_merkle_roots: Vec<FieldElement<F>>,
+}
+
+/// Prover data kept between `commit` and `finish`.
+pub struct ProverState<A: AIR> {
+    domain: Domain<A::Field>,
+    main_trace_commitment: MainTraceCommitment<A::Field>,
+    public_input: A::PublicInput,
+    metrics: ProverMetrics,
+}
+
+/// First phase of a two-phase proof: builds and commits to the main trace.
+/// The returned commitments can be absorbed into an external transcript before
+/// calling `finish`, which draws every challenge of the proof.
+pub fn commit<F: IsFFTField, A: AIR<Field = F>>(
     trace: &A::RawTrace,
     air: &A,
-    public_input: &mut A::PublicInput,
-) -> Result<StarkProof<F>, ProvingError>
+    public_input: &A::PublicInput,
+) -> Result<(Commitments<F>, ProverState<A>), ProvingError>
 where
     FieldElement<F>: ByteConversion,
+{
+    check_domain_parameters(air)?;
+    let domain = Domain::new(air);
+    let timer = Timer::start();
+    let (main_trace_commitment, public_input) =
+        round_1_commit_main_trace(air, trace, &domain, public_input)?;
+    let metrics = ProverMetrics {
+        round_1: timer.elapsed(),
+        ..Default::default()
+    };
+
+    let commitments = Commitments {
+        lde_trace_merkle_roots: main_trace_commitment.lde_trace_merkle_roots.clone(),
+    };
+    let state = ProverState {
+        domain,
+        main_trace_commitment,
+        public_input,
+        metrics,
+    };
+    Ok((commitments, state))
+}
+
+/// Second phase of a two-phase proof. The commitments of `state` are appended
+/// to `transcript`, and the rest of the proof is built from the challenges drawn
+/// from it.
+pub fn finish<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    state: ProverState<A>,
+    air: &A,
+    transcript: &mut T,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    let mut job = ProvingJob {
+        metrics: state.metrics,
+        ..Default::default()
+    };
+    let proof = finish_rounds(
+        state.main_trace_commitment,
+        air,
+        &state.public_input,
+        &state.domain,
+        &mut ProverWorkspace::new(),
+        transcript,
+        &mut job,
+    )?;
+    Ok(ProverOutput {
+        proof,
+        public_values: state.public_input,
+        metrics: job.metrics,
+    })
+}
+
+/// Proves with the preprocessed columns already committed to, if they are
+/// given.
+#[allow(clippy::too_many_arguments)]
+fn prove_rounds<F: IsFFTField, A: AIR<Field = F>, T: Transcript>(
+    trace: &A::RawTrace,
+    air: &A,
+    public_input: &A::PublicInput,
+    domain: &Domain<F>,
+    preprocessed: Option<&MainTraceCommitment<F>>,
+    workspace: &mut ProverWorkspace<F, A::ChallengeField>,
+    transcript: &mut T,
+    mut job: ProvingJob,
+) -> Result<ProverOutput<A>, ProvingError>
+where
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
 {
     info!("Starting proof generation...");
 
-    let domain = Domain::new(air);
+    job.start(ProvingStage::MainTrace, 0.0)?;
+    let timer = Timer::start();
+    let (main_trace_commitment, public_input) = match preprocessed {
+        Some(preprocessed) => commit_main_trace(air, trace, domain, public_input, preprocessed)?,
+        None => round_1_commit_main_trace(air, trace, domain, public_input)?,
+    };
+    job.metrics.round_1 = timer.elapsed();
 
-    let mut transcript = round_0_transcript_initialization();
+    let proof = finish_rounds(
+        main_trace_commitment,
+        air,
+        &public_input,
+        domain,
+        workspace,
+        transcript,
+        &mut job,
+    )?;
+    Ok(ProverOutput {
+        proof,
+        public_values: public_input,
+        metrics: job.metrics,
+    })
+}
+
+fn finish_rounds<F, E, A, T>(
+    main_trace_commitment: MainTraceCommitment<F>,
+    air: &A,
+    public_input: &A::PublicInput,
+    domain: &Domain<F>,
+    workspace: &mut ProverWorkspace<F, E>,
+    transcript: &mut T,
+    job: &mut ProvingJob,
+) -> Result<StarkProof<F, E>, ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
+    A: MaybeSync,
+    A::RAPChallenges: MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<E>: MaybeSync,
+{
+    let air = &air_for_public_input(air, public_input)?;
 
     // ===================================
     // ==========|   Round 1   |==========
     // ===================================
 
-    let round_1_result = round_1_randomized_air_with_preprocessing::<F, A, _>(
+    job.start(ProvingStage::AuxiliaryTrace, 0.2)?;
+    let timer = Timer::start();
+    let round_1_result = round_1_randomized_air_with_preprocessing::<F, A, T>(
         air,
-        trace,
-        &domain,
+        main_trace_commitment,
+        domain,
         public_input,
-        &mut transcript,
+        transcript,
+        workspace,
     )?;
+    job.metrics.round_1 += timer.elapsed();
+
+    if cfg!(debug_assertions) || air.options().validate_trace {
+        let report = validate_extended_trace(
+            air,
+            &round_1_result.trace_polys,
+            &round_1_result.extension_polys,
+            domain,
+            public_input,
+            &round_1_result.rap_challenges,
+        );
+        if air.options().validate_trace {
+            if let Some(message) = report.first_violation() {
+                return Err(ProvingError::InvalidTrace(message));
+            }
+        }
+    }
 
-    #[cfg(debug_assertions)]
-    validate_trace(
+    #[cfg(feature = "std")]
+    job.save_checkpoint::<F, E>(CheckpointRef {
+        header: checkpoint_header(
+            air,
+            round_1_result.trace_polys.len(),
+            checkpoint_digest(air, public_input, &round_1_result),
+        ),
+        trace_polys: &round_1_result.trace_polys,
+        lde_trace: &round_1_result.lde_trace,
+        extension_polys: &round_1_result.extension_polys,
+        lde_extension_trace: &round_1_result.lde_extension_trace,
+        composition: None,
+    })?;
+
+    rounds_2_to_4(
         air,
-        &round_1_result.trace_polys,
-        &domain,
         public_input,
-        &round_1_result.rap_challenges,
-    );
-
-    // ===================================
-    // ==========|   Round 2   |==========
-    // ===================================
+        domain,
+        round_1_result,
+        workspace,
+        transcript,
+        job,
+    )
+}
 
+/// Draws the coefficients of the boundary and transition constraints in the
+/// composition polynomial.
+#[allow(clippy::type_complexity)]
+fn sample_composition_coefficients<F, E, A, T>(
+    air: &A,
+    num_trace_polys: usize,
+    transcript: &mut T,
+) -> (
+    Vec<(FieldElement<E>, FieldElement<E>)>,
+    Vec<(FieldElement<E>, FieldElement<E>)>,
+)
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
+{
     // <<<< Receive challenges: 𝛼_j^B
-    let boundary_coeffs_alphas =
-        batch_sample_challenges(round_1_result.trace_polys.len(), &mut transcript);
+    let boundary_coeffs_alphas = batch_sample_challenges::<F, E, T>(num_trace_polys, transcript);
     // <<<< Receive challenges: 𝛽_j^B
-    let boundary_coeffs_betas =
-        batch_sample_challenges(round_1_result.trace_polys.len(), &mut transcript);
+    let boundary_coeffs_betas = batch_sample_challenges::<F, E, T>(num_trace_polys, transcript);
     // <<<< Receive challenges: 𝛼_j^T
     let transition_coeffs_alphas =
-        batch_sample_challenges(air.context().num_transition_constraints, &mut transcript);
+        batch_sample_challenges::<F, E, T>(air.num_transition_constraints(), transcript);
     // <<<< Receive challenges: 𝛽_j^T
     let transition_coeffs_betas =
-        batch_sample_challenges(air.context().num_transition_constraints, &mut transcript);
+        batch_sample_challenges::<F, E, T>(air.num_transition_constraints(), transcript);
 
-    let boundary_coeffs: Vec<_> = boundary_coeffs_alphas
+    let boundary_coeffs = boundary_coeffs_alphas
         .into_iter()
         .zip(boundary_coeffs_betas)
         .collect();
-    let transition_coeffs: Vec<_> = transition_coeffs_alphas
+    let transition_coeffs = transition_coeffs_alphas
         .into_iter()
         .zip(transition_coeffs_betas)
         .collect();
+    (boundary_coeffs, transition_coeffs)
+}
+
+fn rounds_2_to_4<F, E, A, T>(
+    air: &A,
+    public_input: &A::PublicInput,
+    domain: &Domain<F>,
+    round_1_result: Round1<F, A>,
+    workspace: &mut ProverWorkspace<F, E>,
+    transcript: &mut T,
+    job: &mut ProvingJob,
+) -> Result<StarkProof<F, E>, ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
+    FieldElement<F>: ByteConversion,
+{
+    // ===================================
+    // ==========|   Round 2   |==========
+    // ===================================
+
+    job.start(ProvingStage::ConstraintEvaluation, 0.3)?;
+    let timer = Timer::start();
 
-    let round_2_result = round_2_compute_composition_polynomial(
+    let (boundary_coeffs, transition_coeffs) =
+        sample_composition_coefficients(air, round_1_result.num_columns(), transcript);
+
+    let round_2_result = compute_composition_polynomial(
         air,
-        &domain,
+        domain,
         &round_1_result,
         public_input,
         &transition_coeffs,
         &boundary_coeffs,
-    );
+        workspace,
+        job.cancellation,
+    )?;
+    if cfg!(debug_assertions) || job.check_degrees {
+        check_transition_degrees(
+            air,
+            &round_1_result.lde_trace,
+            &round_1_result.lde_extension_trace,
+            domain,
+            &round_1_result.rap_challenges,
+        )?;
+    }
+    // An invalid trace exceeds the bound too, so this is only checked on demand.
+    if job.check_degrees {
+        check_composition_degree(air, &round_2_result.composition_poly_parts)?;
+    }
 
-    // >>>> Send commitments: [H₁], [H₂]
-    transcript.append(&round_2_result.composition_poly_even_root.to_bytes_be());
-    transcript.append(&round_2_result.composition_poly_odd_root.to_bytes_be());
+    // >>>> Send commitments: [Hᵢ]
+    for root in round_2_result.composition_poly_roots.iter() {
+        transcript.append(&E::to_bytes_be(root));
+    }
+    job.metrics.round_2 = timer.elapsed();
+
+    #[cfg(feature = "std")]
+    job.save_checkpoint(CheckpointRef {
+        header: checkpoint_header(
+            air,
+            round_1_result.trace_polys.len(),
+            checkpoint_digest(air, public_input, &round_1_result),
+        ),
+        trace_polys: &round_1_result.trace_polys,
+        lde_trace: &round_1_result.lde_trace,
+        extension_polys: &round_1_result.extension_polys,
+        lde_extension_trace: &round_1_result.lde_extension_trace,
+        composition: Some(
+            round_2_result
+                .composition_poly_parts
+                .iter()
+                .zip(&round_2_result.lde_composition_poly_evaluations)
+                .map(|(part, evaluations)| (part, evaluations.as_slice()))
+                .collect(),
+        ),
+    })?;
+
+    rounds_3_and_4(
+        air,
+        domain,
+        round_1_result,
+        round_2_result,
+        workspace,
+        transcript,
+        job,
+    )
+}
 
+fn rounds_3_and_4<F, E, A, T>(
+    air: &A,
+    domain: &Domain<F>,
+    round_1_result: Round1<F, A>,
+    round_2_result: Round2<E>,
+    workspace: &mut ProverWorkspace<F, E>,
+    transcript: &mut T,
+    job: &mut ProvingJob,
+) -> Result<StarkProof<F, E>, ProvingError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+    T: Transcript,
+    FieldElement<F>: ByteConversion,
+{
     // ===================================
     // ==========|   Round 3   |==========
     // ===================================
 
+    job.start(ProvingStage::OutOfDomainEvaluation, 0.6)?;
+    let timer = Timer::start();
+
     // <<<< Receive challenge: z
-    let z = sample_z_ood(
-        &domain.lde_roots_of_unity_coset,
-        &domain.trace_roots_of_unity,
-        &mut transcript,
-    );
+    let z = sample_z_ood::<F, E, T>(domain, transcript);
 
     let round_3_result = round_3_evaluate_polynomials_in_out_of_domain_element(
         air,
-        &domain,
+        domain,
         &round_1_result,
         &round_2_result,
         &z,
     );
 
-    // >>>> Send value: H₁(z²)
-    transcript.append(
-        &round_3_result
-            .composition_poly_even_ood_evaluation
-            .to_bytes_be(),
-    );
-
-    // >>>> Send value: H₂(z²)
-    transcript.append(
-        &round_3_result
-            .composition_poly_odd_ood_evaluation
-            .to_bytes_be(),
-    );
+    // >>>> Send values: Hᵢ(zᴺ)
+    for evaluation in round_3_result.composition_poly_ood_evaluations.iter() {
+        transcript.append(&E::to_bytes_be(evaluation));
+    }
     // >>>> Send values: tⱼ(zgᵏ)
-    for row in round_3_result.trace_ood_evaluations.iter() {
-        for element in row.iter() {
-            transcript.append(&element.to_bytes_be());
-        }
+    for element in round_3_result.trace_ood_evaluations.iter() {
+        transcript.append(&E::to_bytes_be(element));
     }
+    job.metrics.round_3 = timer.elapsed();
 
     // ===================================
     // ==========|   Round 4   |==========
@@ -606,47 +2265,43 @@ where
     // Part of this round is running FRI, which is an interactive
     // protocol on its own. Therefore we pass it the transcript
     // to simulate the interactions with the verifier.
-    let round_4_result = round_4_compute_and_run_fri_on_the_deep_composition_polynomial(
+    let timer = Timer::start();
+    let round_4_result = run_fri_on_the_deep_composition_polynomial(
         air,
-        &domain,
+        domain,
         &round_1_result,
         &round_2_result,
         &round_3_result,
         &z,
-        &mut transcript,
-    );
+        transcript,
+        &mut |stage, done| job.start(stage, done),
+    )?;
+
+    job.metrics.round_4 = timer.elapsed();
 
     info!("End proof generation");
 
-    let trace_ood_frame_evaluations = Frame::new(
-        round_3_result
-            .trace_ood_evaluations
-            .into_iter()
-            .flatten()
-            .collect(),
-        round_1_result.trace_polys.len(),
-    );
+    workspace.lde_trace_table = round_1_result.lde_trace.table;
+    job.report(ProvingStage::Finished, 1.0);
 
     Ok(StarkProof {
         // [tⱼ]
         lde_trace_merkle_roots: round_1_result.lde_trace_merkle_roots,
+        // [tⱼ] of the columns over the challenge field
+        lde_extension_merkle_roots: round_1_result.lde_extension_merkle_roots,
         // tⱼ(zgᵏ)
-        trace_ood_frame_evaluations,
-        // [H₁]
-        composition_poly_even_root: round_2_result.composition_poly_even_root,
-        // H₁(z²)
-        composition_poly_even_ood_evaluation: round_3_result.composition_poly_even_ood_evaluation,
-        // [H₂]
-        composition_poly_odd_root: round_2_result.composition_poly_odd_root,
-        // H₂(z²)
-        composition_poly_odd_ood_evaluation: round_3_result.composition_poly_odd_ood_evaluation,
+        trace_ood_evaluations: round_3_result.trace_ood_evaluations,
+        // [Hᵢ]
+        composition_poly_roots: round_2_result.composition_poly_roots,
+        // Hᵢ(zᴺ)
+        composition_poly_ood_evaluations: round_3_result.composition_poly_ood_evaluations,
         // [pₖ]
         fri_layers_merkle_roots: round_4_result.fri_layers_merkle_roots,
         // pₙ
         fri_last_value: round_4_result.fri_last_value,
         // Open(p₀(D₀), 𝜐ₛ), Open(pₖ(Dₖ), −𝜐ₛ^(2ᵏ))
         query_list: round_4_result.query_list,
-        // Open(H₁(D_LDE, 𝜐₀), Open(H₂(D_LDE, 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
+        // Open(Hᵢ(D_LDE), 𝜐₀), Open(tⱼ(D_LDE), 𝜐₀)
         deep_poly_openings: round_4_result.deep_poly_openings,
     })
 }
@@ -667,10 +2322,17 @@ mod tests {
             example::simple_fibonacci,
             trace::TraceTable,
         },
+        prover::ProvingError,
         Domain,
     };
+    use core::sync::atomic::AtomicBool;
 
-    use super::evaluate_polynomial_on_lde_domain;
+    use super::{
+        compute_composition_polynomial, evaluate_polynomial_on_lde_domain,
+        round_0_transcript_initialization, round_1_commit_main_trace,
+        round_1_randomized_air_with_preprocessing, sample_composition_coefficients,
+        ProverWorkspace,
+    };
 
     pub type FE = FieldElement<Stark252PrimeField>;
 
@@ -687,6 +2349,7 @@ mod tests {
                 blowup_factor: blowup_factor as u8,
                 fri_number_of_queries: 1,
                 coset_offset,
+                validate_trace: false,
             },
             trace_length,
             trace_columns: trace_table.n_cols,
@@ -694,6 +2357,8 @@ mod tests {
             transition_exemptions: vec![2],
             transition_offsets: vec![0, 1, 2],
             num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
         };
 
         let domain = Domain::new(&simple_fibonacci::FibonacciAIR::from(context));
@@ -723,12 +2388,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_domain_from_parameters_matches_domain_from_air() {
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 4,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: 16,
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+
+        let from_air = Domain::new(&simple_fibonacci::FibonacciAIR::from(context));
+        let from_parameters = Domain::<Stark252PrimeField>::from_parameters(16, 4, 3).unwrap();
+
+        assert_eq!(from_parameters.lde_domain_size(), 64);
+        assert_eq!(
+            from_parameters.trace_primitive_root(),
+            from_air.trace_primitive_root()
+        );
+        assert_eq!(
+            from_parameters.lde_roots_of_unity_coset(),
+            from_air.lde_roots_of_unity_coset()
+        );
+    }
+
+    #[test]
+    fn test_domain_from_invalid_parameters_is_an_error() {
+        assert!(matches!(
+            Domain::<Stark252PrimeField>::from_parameters(12, 4, 3),
+            Err(ProvingError::InvalidTraceLength(12))
+        ));
+        assert!(matches!(
+            Domain::<Stark252PrimeField>::from_parameters(16, 3, 3),
+            Err(ProvingError::WrongParameter(_))
+        ));
+        assert!(matches!(
+            Domain::<Stark252PrimeField>::from_parameters(usize::MAX / 2 + 1, 4, 3),
+            Err(ProvingError::WrongParameter(_))
+        ));
+    }
+
     #[test]
     fn test_evaluate_polynomial_on_lde_domain_on_trace_polys() {
         let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
         let trace_length = trace[0].len();
         let trace_table = TraceTable::new_from_cols(&trace);
-        let trace_polys = trace_table.compute_trace_polys();
+        let trace_polys = trace_table.compute_trace_polys().unwrap();
         let coset_offset = FE::from(3);
         let blowup_factor: usize = 2;
         let domain_size = 8;
@@ -770,4 +2484,81 @@ mod tests {
             assert_eq!(*eval, poly.evaluate(&(&offset * &primitive_root.pow(i))));
         }
     }
+
+    #[test]
+    fn test_evaluate_polynomial_on_lde_domain_with_degree_larger_than_lde_domain() {
+        let coefficients: Vec<FE> = (1..=45).map(FE::from).collect();
+        let poly = Polynomial::new(&coefficients);
+        let blowup_factor: usize = 2;
+        let domain_size: usize = 8;
+        let offset = FE::from(3);
+        let evaluations =
+            evaluate_polynomial_on_lde_domain(&poly, blowup_factor, domain_size, &offset).unwrap();
+        assert_eq!(evaluations.len(), domain_size * blowup_factor);
+
+        let primitive_root: FE = Stark252PrimeField::get_primitive_root_of_unity(
+            (domain_size * blowup_factor).trailing_zeros() as u64,
+        )
+        .unwrap();
+        for (i, eval) in evaluations.iter().enumerate() {
+            assert_eq!(*eval, poly.evaluate(&(&offset * &primitive_root.pow(i))));
+        }
+    }
+
+    #[test]
+    fn test_constraint_evaluation_stops_once_cancelled() {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let context = AirContext {
+            options: ProofOptions {
+                blowup_factor: 2,
+                fri_number_of_queries: 1,
+                coset_offset: 3,
+                validate_trace: false,
+            },
+            trace_length: trace[0].len(),
+            trace_columns: 1,
+            transition_degrees: vec![1],
+            transition_exemptions: vec![2],
+            transition_offsets: vec![0, 1, 2],
+            num_transition_constraints: 1,
+            column_names: Vec::new(),
+            constraint_labels: Vec::new(),
+        };
+        let air = simple_fibonacci::FibonacciAIR::from(context);
+        let domain = Domain::new(&air);
+        let mut transcript = round_0_transcript_initialization();
+        let mut workspace = ProverWorkspace::new();
+
+        let (main_trace_commitment, public_input) =
+            round_1_commit_main_trace(&air, &trace, &domain, &()).unwrap();
+        let round_1_result = round_1_randomized_air_with_preprocessing(
+            &air,
+            main_trace_commitment,
+            &domain,
+            &public_input,
+            &mut transcript,
+            &mut workspace,
+        )
+        .unwrap();
+        let (boundary_coeffs, transition_coeffs) =
+            sample_composition_coefficients(&air, round_1_result.num_columns(), &mut transcript);
+
+        let evaluate = |workspace: &mut ProverWorkspace<_, _>, cancelled: bool| {
+            compute_composition_polynomial(
+                &air,
+                &domain,
+                &round_1_result,
+                &public_input,
+                &transition_coeffs,
+                &boundary_coeffs,
+                workspace,
+                Some(&AtomicBool::new(cancelled)),
+            )
+        };
+        assert!(evaluate(&mut workspace, false).is_ok());
+        assert!(matches!(
+            evaluate(&mut workspace, true),
+            Err(ProvingError::Cancelled)
+        ));
+    }
 }
@@ -0,0 +1,255 @@
+//! Serde support for the proof types, enabled by the `serde` feature.
+//!
+//! Field elements are encoded through their big-endian bytes: as a `0x`
+//! prefixed hex string in human readable formats like JSON, and as raw bytes
+//! in binary formats like bincode. Merkle proofs are encoded as their path.
+//! The modules here are meant for `#[serde(with = "...")]`.
+
+use std::fmt;
+
+use lambdaworks_crypto::merkle_tree::proof::Proof;
+use lambdaworks_math::{
+    field::{element::FieldElement, traits::IsField},
+    traits::ByteConversion,
+};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + 2 * bytes.len());
+    hex.push_str("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{byte:02x}"));
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits = hex
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("{hex} is not a 0x prefixed hex string"))?;
+    if digits.len() % 2 != 0 {
+        return Err(format!("{hex} has an odd number of digits"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("{hex} is not a hex string"))
+        })
+        .collect()
+}
+
+/// Accepts a byte string, or a sequence of bytes from formats without one.
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the bytes of a field element")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Field element that serializes through the helpers of this module, so that
+/// collections of elements can reuse the serde implementations of `Vec`.
+struct SerdeFieldElement<F: IsField>(FieldElement<F>);
+
+struct SerdeFieldElementRef<'a, F: IsField>(&'a FieldElement<F>);
+
+impl<F: IsField> Serialize for SerdeFieldElementRef<'_, F>
+where
+    FieldElement<F>: ByteConversion,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        field_element::serialize(self.0, serializer)
+    }
+}
+
+impl<'de, F: IsField> Deserialize<'de> for SerdeFieldElement<F>
+where
+    FieldElement<F>: ByteConversion,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        field_element::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Serde functions for a `FieldElement`.
+pub mod field_element {
+    use super::*;
+
+    pub fn serialize<F, S>(element: &FieldElement<F>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        S: Serializer,
+    {
+        let bytes = element.to_bytes_be();
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&to_hex(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+
+    pub fn deserialize<'de, F, D>(deserializer: D) -> Result<FieldElement<F>, D::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        D: Deserializer<'de>,
+    {
+        let bytes = if deserializer.is_human_readable() {
+            from_hex(&String::deserialize(deserializer)?).map_err(de::Error::custom)?
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)?
+        };
+        FieldElement::from_bytes_be(&bytes)
+            .map_err(|_| de::Error::custom("invalid field element bytes"))
+    }
+}
+
+/// Serde functions for a `Vec<FieldElement>`.
+pub mod field_elements {
+    use super::*;
+
+    pub fn serialize<F, S>(elements: &[FieldElement<F>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        S: Serializer,
+    {
+        serializer.collect_seq(elements.iter().map(SerdeFieldElementRef))
+    }
+
+    pub fn deserialize<'de, F, D>(deserializer: D) -> Result<Vec<FieldElement<F>>, D::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        D: Deserializer<'de>,
+    {
+        let elements = Vec::<SerdeFieldElement<F>>::deserialize(deserializer)?;
+        Ok(elements.into_iter().map(|e| e.0).collect())
+    }
+}
+
+/// Serde functions for a Merkle `Proof`.
+pub mod merkle_proof {
+    use super::*;
+
+    pub fn serialize<F, S>(proof: &Proof<F>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        S: Serializer,
+    {
+        field_elements::serialize(&proof.merkle_path, serializer)
+    }
+
+    pub fn deserialize<'de, F, D>(deserializer: D) -> Result<Proof<F>, D::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        D: Deserializer<'de>,
+    {
+        let merkle_path = field_elements::deserialize(deserializer)?;
+        Ok(Proof { merkle_path })
+    }
+}
+
+/// Serde functions for a `Vec<Proof>`.
+pub mod merkle_proofs {
+    use super::*;
+
+    struct SerdeProof<F: IsField>(Proof<F>);
+
+    struct SerdeProofRef<'a, F: IsField>(&'a Proof<F>);
+
+    impl<F: IsField> Serialize for SerdeProofRef<'_, F>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            merkle_proof::serialize(self.0, serializer)
+        }
+    }
+
+    impl<'de, F: IsField> Deserialize<'de> for SerdeProof<F>
+    where
+        FieldElement<F>: ByteConversion,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            merkle_proof::deserialize(deserializer).map(Self)
+        }
+    }
+
+    pub fn serialize<F, S>(proofs: &[Proof<F>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        S: Serializer,
+    {
+        serializer.collect_seq(proofs.iter().map(SerdeProofRef))
+    }
+
+    pub fn deserialize<'de, F, D>(deserializer: D) -> Result<Vec<Proof<F>>, D::Error>
+    where
+        F: IsField,
+        FieldElement<F>: ByteConversion,
+        D: Deserializer<'de>,
+    {
+        let proofs = Vec::<SerdeProof<F>>::deserialize(deserializer)?;
+        Ok(proofs.into_iter().map(|p| p.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+
+    use super::*;
+
+    type FE = FieldElement<Stark252PrimeField>;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper {
+        #[serde(with = "field_element")]
+        element: FE,
+    }
+
+    #[test]
+    fn field_element_round_trips_through_json_as_hex() {
+        let wrapper = Wrapper {
+            element: FE::from(0x1234),
+        };
+        let json = serde_json::to_string(&wrapper).unwrap();
+        assert!(json.contains("1234"));
+
+        let decoded: Wrapper = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.element, wrapper.element);
+    }
+
+    #[test]
+    fn hex_without_prefix_is_rejected() {
+        assert!(from_hex("1234").is_err());
+        assert_eq!(from_hex("0x01ff").unwrap(), vec![1, 255]);
+    }
+}
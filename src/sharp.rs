@@ -0,0 +1,233 @@
+//! Facts of Cairo proofs, as SHARP registers them.
+//!
+//! The fact of an execution is `keccak256(program_hash ‖ output_hash)`, where
+//! `output_hash` is the Keccak-256 digest of the output, each value as 32 big
+//! endian bytes, the encoding of a `uint256[]` in Solidity. The program hash
+//! is `PublicInputs::program_hash`, so facts of the proofs of this crate are
+//! the ones of SHARP for the same program and output.
+//!
+//! `GpsStatement` is the statement of a batch of proofs as the general
+//! proving service (GPS) states it: the tasks with their facts, and the
+//! output of the simple bootloader that runs them, split in pages.
+
+use std::fmt::Write;
+
+use lambdaworks_math::{
+    field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, traits::ByteConversion,
+};
+
+use crate::{
+    air::{
+        cairo_air::air::{CairoAIR, PublicInputs},
+        gadgets::public_memory::MemoryPage,
+    },
+    proof::StarkProof,
+    stone::keccak256,
+    verifier::{verify_cairo, VerificationError},
+    FE,
+};
+
+/// Fact of an execution of the program with `program_hash` and `output`.
+pub fn fact_hash(program_hash: &FE, output: &[FE]) -> [u8; 32] {
+    let output_bytes: Vec<u8> = output
+        .iter()
+        .flat_map(|value| value.to_bytes_be())
+        .collect();
+    let mut fact_data = program_hash.to_bytes_be();
+    fact_data.extend(keccak256(&output_bytes));
+    keccak256(&fact_data)
+}
+
+/// Verifies the proof of a Cairo program, and returns the fact of the
+/// execution it proves.
+pub fn fact_of_proof(
+    proof: &StarkProof<Stark252PrimeField, Stark252PrimeField>,
+    air: &CairoAIR,
+    public_input: &PublicInputs,
+) -> Result<[u8; 32], VerificationError> {
+    let output = verify_cairo(proof, air, public_input)?;
+    Ok(fact_hash(&public_input.program_hash(), &output.values))
+}
+
+/// Task of a batch: a proven execution of a Cairo program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GpsTask {
+    pub program_hash: FE,
+    pub output: Vec<FE>,
+    /// Keccak-256 digest of the canonical encoding of the public input.
+    pub public_input_hash: [u8; 32],
+    pub fact: [u8; 32],
+}
+
+impl GpsTask {
+    /// Verifies the proof of a Cairo program, and returns the task of the
+    /// execution it proves.
+    pub fn from_proof(
+        proof: &StarkProof<Stark252PrimeField, Stark252PrimeField>,
+        air: &CairoAIR,
+        public_input: &PublicInputs,
+    ) -> Result<Self, VerificationError> {
+        let output = verify_cairo(proof, air, public_input)?.values;
+        let program_hash = public_input.program_hash();
+        Ok(Self {
+            fact: fact_hash(&program_hash, &output),
+            program_hash,
+            output,
+            public_input_hash: keccak256(&public_input.to_bytes_be()),
+        })
+    }
+
+    /// Output of the task in the simple bootloader: its size, which counts
+    /// the two cells of the header, the program hash and the output.
+    pub fn bootloader_output(&self) -> Vec<FE> {
+        let mut output = vec![
+            FE::from(self.output.len() as u64 + 2),
+            self.program_hash.clone(),
+        ];
+        output.extend(self.output.iter().cloned());
+        output
+    }
+}
+
+/// Statement of a batch of Cairo proofs, in the form of GPS.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GpsStatement {
+    pub tasks: Vec<GpsTask>,
+}
+
+impl GpsStatement {
+    /// Verifies every proof of the batch, and returns the statement of their
+    /// tasks. It's an error if a proof is invalid.
+    pub fn from_proofs<'a, I>(proofs: I) -> Result<Self, VerificationError>
+    where
+        I: IntoIterator<
+            Item = (
+                &'a StarkProof<Stark252PrimeField, Stark252PrimeField>,
+                &'a CairoAIR,
+                &'a PublicInputs,
+            ),
+        >,
+    {
+        let tasks = proofs
+            .into_iter()
+            .map(|(proof, air, public_input)| GpsTask::from_proof(proof, air, public_input))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { tasks })
+    }
+
+    /// Output of the simple bootloader running the tasks: their number
+    /// followed by the output of each task.
+    pub fn bootloader_output(&self) -> Vec<FE> {
+        let mut output = vec![FE::from(self.tasks.len() as u64)];
+        for task in self.tasks.iter() {
+            output.extend(task.bootloader_output());
+        }
+        output
+    }
+
+    /// Pages of the bootloader output written from `output_begin`: the first
+    /// one has the number of tasks, and each task has a page of its own.
+    pub fn output_pages(&self, output_begin: u64) -> Vec<MemoryPage<Stark252PrimeField>> {
+        let mut pages = vec![MemoryPage::new(
+            FE::from(output_begin),
+            vec![FE::from(self.tasks.len() as u64)],
+        )];
+        let mut address = output_begin + 1;
+        for task in self.tasks.iter() {
+            let values = task.bootloader_output();
+            let len = values.len() as u64;
+            pages.push(MemoryPage::new(FE::from(address), values));
+            address += len;
+        }
+        pages
+    }
+
+    /// The statement as JSON, with field elements and digests as `0x`
+    /// prefixed hex strings.
+    pub fn to_json(&self) -> String {
+        let tasks: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|task| {
+                serde_json::json!({
+                    "program_hash": hex(&task.program_hash.to_bytes_be()),
+                    "output": task
+                        .output
+                        .iter()
+                        .map(|value| hex(&value.to_bytes_be()))
+                        .collect::<Vec<_>>(),
+                    "public_input_hash": hex(&task.public_input_hash),
+                    "fact": hex(&task.fact),
+                })
+            })
+            .collect();
+        let json = serde_json::json!({
+            "n_tasks": self.tasks.len(),
+            "tasks": tasks,
+            "bootloader_output": self
+                .bootloader_output()
+                .iter()
+                .map(|value| hex(&value.to_bytes_be()))
+                .collect::<Vec<_>>(),
+        });
+        serde_json::to_string_pretty(&json).expect("a statement is always serializable")
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::from("0x");
+    for byte in bytes {
+        write!(hex, "{byte:02x}").expect("writing to a string can't fail");
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fact_hashes_the_program_hash_and_the_output_hash() {
+        let program_hash = FE::from(7);
+        let output = [FE::from(1), FE::from(2)];
+
+        let mut output_bytes = [0; 64];
+        output_bytes[31] = 1;
+        output_bytes[63] = 2;
+        let mut fact_data = [0; 64];
+        fact_data[31] = 7;
+        fact_data[32..].copy_from_slice(&keccak256(&output_bytes));
+
+        assert_eq!(fact_hash(&program_hash, &output), keccak256(&fact_data));
+        assert_ne!(
+            fact_hash(&program_hash, &output),
+            fact_hash(&program_hash, &output[..1])
+        );
+    }
+
+    #[test]
+    fn statement_has_the_bootloader_output_of_its_tasks() {
+        let task = |program_hash: u64, output: &[u64]| GpsTask {
+            program_hash: FE::from(program_hash),
+            output: output.iter().map(|value| FE::from(*value)).collect(),
+            public_input_hash: [0; 32],
+            fact: [0; 32],
+        };
+        let statement = GpsStatement {
+            tasks: vec![task(7, &[1, 2]), task(8, &[])],
+        };
+        let expected: Vec<_> = [2u64, 4, 7, 1, 2, 2, 8].into_iter().map(FE::from).collect();
+        assert_eq!(statement.bootloader_output(), expected);
+
+        let pages = statement.output_pages(10);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[1].address, FE::from(11));
+        assert_eq!(pages[2].address, FE::from(15));
+        let paged: Vec<_> = pages
+            .iter()
+            .flat_map(|page| page.values.iter().cloned())
+            .collect();
+        assert_eq!(paged, expected);
+        assert!(statement.to_json().contains("\"n_tasks\": 2"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Conventions of StarkWare's Stone prover.
+//!
+//! `StoneParameters` reads Stone's parameter JSON and gives the options of a
+//! proof with the same security parameters.
+//!
+//! The proofs of this crate can't be checked by Stone's verifiers, nor Stone's
+//! proofs by this crate: the Cairo AIR has other columns and constraints, and
+//! the channel, the commitments, the FRI layers and the proof serialization
+//! differ. Proofs with Stone's parameters are still proofs of this crate.
+
+use crate::{
+    air::{cairo_air::keccak, context::ProofOptions},
+    prover::ProvingError,
+};
+
+/// Bytes absorbed per permutation by Keccak-256.
+const KECCAK_RATE: usize = 136;
+
+/// Keccak-256 digest of `data`, with the padding of the original Keccak, as
+/// in Stone and Ethereum.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut padded = data.to_vec();
+    padded.push(0x01);
+    padded.resize(
+        (padded.len() + KECCAK_RATE - 1) / KECCAK_RATE * KECCAK_RATE,
+        0,
+    );
+    let last = padded.len() - 1;
+    padded[last] |= 0x80;
+
+    let mut state = [0u64; keccak::LANES];
+    for block in padded.chunks(KECCAK_RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            let mut lane_bytes = [0; 8];
+            lane_bytes.copy_from_slice(bytes);
+            *lane ^= u64::from_le_bytes(lane_bytes);
+        }
+        state = keccak::permutation(&state);
+    }
+
+    let mut digest = [0; 32];
+    for (bytes, lane) in digest.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+/// Parameters of FRI in Stone's parameter JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoneFriParameters {
+    pub fri_step_list: Vec<usize>,
+    pub last_layer_degree_bound: usize,
+    pub n_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoneStarkParameters {
+    pub fri: StoneFriParameters,
+    /// Logarithm of the blowup factor.
+    pub log_n_cosets: u32,
+}
+
+/// Stone's parameter JSON, with the fields that define the proof options.
+/// The fields this crate has no use for are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoneParameters {
+    pub field: String,
+    pub stark: StoneStarkParameters,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub use_extension_field: bool,
+}
+
+impl StoneParameters {
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Options of a proof with the blowup factor and the queries of the
+    /// parameters, and Stone's coset offset. FRI folds by two in every layer,
+    /// whatever the steps of the parameters. It's an error if the parameters
+    /// need what the prover doesn't have: another field, an extension field,
+    /// or a proof of work.
+    pub fn proof_options(&self) -> Result<ProofOptions, ProvingError> {
+        if self.field != "PrimeField0" {
+            return Err(ProvingError::WrongParameter(format!(
+                "The field {} is not the Stark field.",
+                self.field
+            )));
+        }
+        if self.use_extension_field {
+            return Err(ProvingError::WrongParameter(
+                "Stone's extension field is not supported.".to_string(),
+            ));
+        }
+        let fri = &self.stark.fri;
+        if fri.proof_of_work_bits > 0 {
+            return Err(ProvingError::WrongParameter(format!(
+                "The prover has no proof of work, but the parameters have {} bits of it.",
+                fri.proof_of_work_bits
+            )));
+        }
+        if self.stark.log_n_cosets >= u8::BITS {
+            return Err(ProvingError::WrongParameter(format!(
+                "The blowup factor 2^{} is too large.",
+                self.stark.log_n_cosets
+            )));
+        }
+        let options = ProofOptions {
+            blowup_factor: 1 << self.stark.log_n_cosets,
+            fri_number_of_queries: fri.n_queries,
+            coset_offset: 3,
+            validate_trace: false,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(log_n_cosets: u32, proof_of_work_bits: usize) -> StoneParameters {
+        StoneParameters {
+            field: "PrimeField0".to_string(),
+            stark: StoneStarkParameters {
+                fri: StoneFriParameters {
+                    fri_step_list: vec![0, 4, 4, 3],
+                    last_layer_degree_bound: 64,
+                    n_queries: 18,
+                    proof_of_work_bits,
+                },
+                log_n_cosets,
+            },
+            use_extension_field: false,
+        }
+    }
+
+    #[test]
+    fn keccak256_matches_known_digests() {
+        assert_eq!(
+            keccak256(b""),
+            bytes32("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            keccak256(b"abc"),
+            bytes32("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+    }
+
+    fn bytes32(digest: &str) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(digest.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(core::str::from_utf8(pair).unwrap(), 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn parameters_give_the_proof_options() {
+        let options = parameters(2, 0).proof_options().unwrap();
+        assert_eq!(options.blowup_factor, 4);
+        assert_eq!(options.fri_number_of_queries, 18);
+        assert!(parameters(2, 24).proof_options().is_err());
+        assert!(parameters(8, 0).proof_options().is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn parameters_are_read_from_stone_json() {
+        let json = r#"{
+            "field": "PrimeField0",
+            "stark": {
+                "fri": {
+                    "fri_step_list": [0, 4, 4, 3],
+                    "last_layer_degree_bound": 64,
+                    "n_queries": 18,
+                    "proof_of_work_bits": 0
+                },
+                "log_n_cosets": 2
+            },
+            "use_extension_field": false,
+            "verifier_friendly_channel_updates": true
+        }"#;
+        assert_eq!(StoneParameters::from_json(json).unwrap(), parameters(2, 0));
+    }
+}
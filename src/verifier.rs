@@ -1,18 +1,33 @@
+#[cfg(feature = "annotations")]
+pub mod annotations;
+
 use super::{
     air::constraints::evaluator::ConstraintEvaluator, fri::fri_decommit::FriDecommitment,
     sample_z_ood,
 };
+#[cfg(feature = "std")]
+use crate::air::cairo_air::air::{CairoAIR, CairoPublicOutput, PublicInputs};
 use crate::{
-    air::traits::AIR, batch_sample_challenges, fri::HASHER, proof::StarkProof, transcript_to_field,
-    transcript_to_usize, Domain,
+    air::{frame::FrameLayout, periodic, traits::AIR},
+    batch_sample_challenges,
+    fri::{ChallengeHasher, HASHER},
+    proof::{encoding::ProofDecodingError, StarkProof},
+    prover::{check_domain_parameters, commit_preprocessed_columns, replay_round_1, ProvingError},
+    transcript_to_index, Domain, IsChallengeField, MaybeSync,
 };
+use alloc::{format, string::String, vec::Vec};
 #[cfg(not(feature = "test_fiat_shamir"))]
 use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
 use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
+#[cfg(feature = "std")]
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 
 #[cfg(feature = "test_fiat_shamir")]
 use lambdaworks_crypto::fiat_shamir::test_transcript::TestTranscript;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use lambdaworks_math::{
     field::{
         element::FieldElement,
@@ -22,6 +37,71 @@ use lambdaworks_math::{
     traits::ByteConversion,
 };
 
+/// Reason why a proof was rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The proof doesn't have the number of commitments, evaluations or queries
+    /// that the AIR requires.
+    InvalidProofShape(String),
+    /// ∑ zⁱHᵢ(zᴺ) doesn't match the composition polynomial evaluation computed
+    /// from the out of domain frame.
+    OodConsistency,
+    /// The Merkle proof of the trace column with this index is invalid.
+    TraceOpening(usize),
+    /// The Merkle proof of the composition polynomial part with this index is
+    /// invalid.
+    CompositionPolyOpening(usize),
+    /// The evaluation of the DEEP composition polynomial at 𝜐₀ doesn't match the
+    /// first layer of FRI.
+    DeepConsistency,
+    /// The Merkle proof of the given FRI layer is invalid in the given query.
+    FriLayerOpening { query: usize, layer: usize },
+    /// The folded value of the given query doesn't match the last FRI value.
+    FriLastValue { query: usize },
+    /// The encoded proof couldn't be decoded.
+    MalformedProof(ProofDecodingError),
+    /// The options or the context of the AIR are invalid.
+    InvalidParameters(String),
+    /// The proof doesn't commit to the preprocessed columns of the AIR.
+    PreprocessedCommitment,
+    /// The segment with this index of a chain doesn't start from the final
+    /// state of the previous one.
+    BrokenChain(usize),
+}
+
+/// Reports an AIR that the prover would reject, keeping the message of a
+/// `WrongParameter`.
+fn invalid_parameters(error: ProvingError) -> VerificationError {
+    match error {
+        ProvingError::WrongParameter(message) => VerificationError::InvalidParameters(message),
+        error => VerificationError::InvalidParameters(format!("{error:?}")),
+    }
+}
+
+/// Checks that the first trace commitments of the proof are the roots of the
+/// preprocessed columns of the AIR.
+fn check_preprocessed_roots<F: IsFFTField, E: IsField, A: AIR<Field = F>>(
+    air: &A,
+    proof: &StarkProof<F, E>,
+) -> Result<(), VerificationError>
+where
+    FieldElement<F>: ByteConversion,
+{
+    let roots = match air.preprocessed_roots() {
+        Some(roots) => roots,
+        None if air.preprocessed_columns().is_empty() => return Ok(()),
+        None => {
+            commit_preprocessed_columns(air, &Domain::new(air))
+                .map_err(invalid_parameters)?
+                .lde_trace_merkle_roots
+        }
+    };
+    if !proof.lde_trace_merkle_roots.starts_with(&roots) {
+        return Err(VerificationError::PreprocessedCommitment);
+    }
+    Ok(())
+}
+
 #[cfg(feature = "test_fiat_shamir")]
 fn step_1_transcript_initialization() -> TestTranscript {
     TestTranscript::new()
@@ -33,28 +113,131 @@ fn step_1_transcript_initialization() -> DefaultTranscript {
     DefaultTranscript::new()
 }
 
-struct Challenges<F: IsFFTField, A: AIR<Field = F>> {
-    z: FieldElement<F>,
-    boundary_coeffs: Vec<(FieldElement<F>, FieldElement<F>)>,
-    transition_coeffs: Vec<(FieldElement<F>, FieldElement<F>)>,
-    trace_term_coeffs: Vec<Vec<FieldElement<F>>>,
-    gamma_even: FieldElement<F>,
-    gamma_odd: FieldElement<F>,
-    zetas: Vec<FieldElement<F>>,
+struct Challenges<E: IsField, A: AIR<ChallengeField = E>> {
+    z: FieldElement<E>,
+    boundary_coeffs: Vec<(FieldElement<E>, FieldElement<E>)>,
+    transition_coeffs: Vec<(FieldElement<E>, FieldElement<E>)>,
+    trace_term_coeffs: Vec<FieldElement<E>>,
+    composition_poly_coeffs: Vec<FieldElement<E>>,
+    zetas: Vec<FieldElement<E>>,
     iotas: Vec<usize>,
     rap_challenges: A::RAPChallenges,
 }
 
-fn step_1_replay_rounds_and_recover_challenges<F, A, T>(
+/// Checks that the sizes of the proof match what the AIR expects, so that the
+/// following steps can index into it without panicking on a malformed proof.
+fn step_0_check_proof_shape<F: IsFFTField, E: IsField, A: AIR<Field = F>>(
     air: &A,
-    proof: &StarkProof<F>,
+    proof: &StarkProof<F, E>,
     domain: &Domain<F>,
+) -> Result<(), VerificationError> {
+    let extension_columns = air.number_extension_columns();
+    let trace_columns = air.context().trace_columns - extension_columns;
+    let frame_entries = FrameLayout::of(air).num_entries();
+    let fri_layers = domain.root_order as usize;
+    let shape_error = |what: &str, expected: usize, found: usize| {
+        Err(VerificationError::InvalidProofShape(format!(
+            "expected {expected} {what}, found {found}"
+        )))
+    };
+
+    if proof.lde_trace_merkle_roots.len() != trace_columns {
+        return shape_error(
+            "trace commitments",
+            trace_columns,
+            proof.lde_trace_merkle_roots.len(),
+        );
+    }
+    if proof.trace_ood_evaluations.len() != frame_entries {
+        return shape_error(
+            "out of domain evaluations",
+            frame_entries,
+            proof.trace_ood_evaluations.len(),
+        );
+    }
+    let openings = &proof.deep_poly_openings;
+    if openings.lde_trace_merkle_proofs.len() != trace_columns
+        || openings.lde_trace_evaluations.len() != trace_columns
+    {
+        return shape_error(
+            "trace openings",
+            trace_columns,
+            openings.lde_trace_evaluations.len(),
+        );
+    }
+    if proof.lde_extension_merkle_roots.len() != extension_columns {
+        return shape_error(
+            "commitments of columns over the challenge field",
+            extension_columns,
+            proof.lde_extension_merkle_roots.len(),
+        );
+    }
+    if openings.lde_extension_merkle_proofs.len() != extension_columns
+        || openings.lde_extension_evaluations.len() != extension_columns
+    {
+        return shape_error(
+            "openings of columns over the challenge field",
+            extension_columns,
+            openings.lde_extension_evaluations.len(),
+        );
+    }
+    let parts = air.composition_poly_parts();
+    if proof.composition_poly_roots.len() != parts
+        || proof.composition_poly_ood_evaluations.len() != parts
+    {
+        return shape_error(
+            "composition polynomial parts",
+            parts,
+            proof.composition_poly_roots.len(),
+        );
+    }
+    if openings.lde_composition_poly_proofs.len() != parts
+        || openings.lde_composition_poly_evaluations.len() != parts
+    {
+        return shape_error(
+            "composition polynomial openings",
+            parts,
+            openings.lde_composition_poly_evaluations.len(),
+        );
+    }
+    if proof.fri_layers_merkle_roots.len() != fri_layers {
+        return shape_error(
+            "FRI layers",
+            fri_layers,
+            proof.fri_layers_merkle_roots.len(),
+        );
+    }
+    let queries = air.options().fri_number_of_queries;
+    if queries == 0 || proof.query_list.len() != queries {
+        return shape_error("FRI queries", queries, proof.query_list.len());
+    }
+    for query in proof.query_list.iter() {
+        if query.layers_auth_paths_sym.len() != fri_layers
+            || query.layers_evaluations_sym.len() != fri_layers
+        {
+            return shape_error(
+                "FRI layer openings per query",
+                fri_layers,
+                query.layers_evaluations_sym.len(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn step_1_replay_rounds_and_recover_challenges<F, E, A, T>(
+    air: &A,
+    proof: &StarkProof<F, E>,
+    domain: &Domain<F>,
+    public_input: &A::PublicInput,
     transcript: &mut T,
-) -> Challenges<F, A>
+) -> Challenges<E, A>
 where
     F: IsFFTField,
+    E: IsChallengeField<F>,
     FieldElement<F>: ByteConversion,
-    A: AIR<Field = F>,
+    A: AIR<Field = F, ChallengeField = E>,
     T: Transcript,
 {
     // ===================================
@@ -68,15 +251,14 @@ where
     let aux_columns = air.number_auxiliary_rap_columns();
     let main_columns = total_columns - aux_columns;
 
-    for root in proof.lde_trace_merkle_roots.iter().take(main_columns) {
-        transcript.append(&root.to_bytes_be());
-    }
-
-    let rap_challenges = air.build_rap_challenges(transcript);
-
-    for root in proof.lde_trace_merkle_roots.iter().skip(main_columns) {
-        transcript.append(&root.to_bytes_be());
-    }
+    let rap_challenges = replay_round_1(
+        air,
+        public_input,
+        &proof.lde_trace_merkle_roots,
+        &proof.lde_extension_merkle_roots,
+        main_columns,
+        transcript,
+    );
 
     // ===================================
     // ==========|   Round 2   |==========
@@ -84,15 +266,15 @@ where
 
     // These are the challenges alpha^B_j and beta^B_j
     // >>>> Send challenges: 𝛼_j^B
-    let boundary_coeffs_alphas = batch_sample_challenges(n_trace_cols, transcript);
+    let boundary_coeffs_alphas = batch_sample_challenges::<F, E, T>(n_trace_cols, transcript);
     // >>>> Send  challenges: 𝛽_j^B
-    let boundary_coeffs_betas = batch_sample_challenges(n_trace_cols, transcript);
+    let boundary_coeffs_betas = batch_sample_challenges::<F, E, T>(n_trace_cols, transcript);
     // >>>> Send challenges: 𝛼_j^T
     let transition_coeffs_alphas =
-        batch_sample_challenges(air.context().num_transition_constraints, transcript);
+        batch_sample_challenges::<F, E, T>(air.num_transition_constraints(), transcript);
     // >>>> Send challenges: 𝛽_j^T
     let transition_coeffs_betas =
-        batch_sample_challenges(air.context().num_transition_constraints, transcript);
+        batch_sample_challenges::<F, E, T>(air.num_transition_constraints(), transcript);
     let boundary_coeffs: Vec<_> = boundary_coeffs_alphas
         .into_iter()
         .zip(boundary_coeffs_betas)
@@ -103,72 +285,61 @@ where
         .zip(transition_coeffs_betas)
         .collect();
 
-    // <<<< Receive commitments: [H₁], [H₂]
-    transcript.append(&proof.composition_poly_even_root.to_bytes_be());
-    transcript.append(&proof.composition_poly_odd_root.to_bytes_be());
+    // <<<< Receive commitments: [Hᵢ]
+    for root in proof.composition_poly_roots.iter() {
+        transcript.append(&E::to_bytes_be(root));
+    }
 
     // ===================================
     // ==========|   Round 3   |==========
     // ===================================
 
     // >>>> Send challenge: z
-    let z = sample_z_ood(
-        &domain.lde_roots_of_unity_coset,
-        &domain.trace_roots_of_unity,
-        transcript,
-    );
+    let z = sample_z_ood::<F, E, T>(domain, transcript);
 
-    // <<<< Receive value: H₁(z²)
-    transcript.append(&proof.composition_poly_even_ood_evaluation.to_bytes_be());
-    // <<<< Receive value: H₂(z²)
-    transcript.append(&proof.composition_poly_odd_ood_evaluation.to_bytes_be());
+    // <<<< Receive values: Hᵢ(zᴺ)
+    for evaluation in proof.composition_poly_ood_evaluations.iter() {
+        transcript.append(&E::to_bytes_be(evaluation));
+    }
     // <<<< Receive values: tⱼ(zgᵏ)
-    for i in 0..proof.trace_ood_frame_evaluations.num_rows() {
-        for element in proof.trace_ood_frame_evaluations.get_row(i).iter() {
-            transcript.append(&element.to_bytes_be());
-        }
+    for element in proof.trace_ood_evaluations.iter() {
+        transcript.append(&E::to_bytes_be(element));
     }
 
     // ===================================
     // ==========|   Round 4   |==========
     // ===================================
 
-    // >>>> Send challenges: 𝛾, 𝛾'
-    let gamma_even = transcript_to_field(transcript);
-    let gamma_odd = transcript_to_field(transcript);
-
-    // >>>> Send challenges: 𝛾ⱼ, 𝛾ⱼ'
-    // Get the number of trace terms the DEEP composition poly will have.
-    // One coefficient will be sampled for each of them.
-    // TODO: try remove this, call transcript inside for and move gamma declarations
-    let trace_term_coeffs = (0..n_trace_cols)
-        .map(|_| {
-            (0..air.context().transition_offsets.len())
-                .map(|_| transcript_to_field(transcript))
-                .collect()
-        })
-        .collect::<Vec<Vec<FieldElement<F>>>>();
+    // >>>> Send challenges: 𝛾ᵢ
+    let composition_poly_coeffs =
+        batch_sample_challenges::<F, E, T>(proof.composition_poly_roots.len(), transcript);
+
+    // >>>> Send challenges: 𝛾ⱼₖ
+    // One coefficient for each trace term of the DEEP composition poly, that
+    // is, for each value of the frame layout.
+    let trace_term_coeffs =
+        batch_sample_challenges::<F, E, T>(FrameLayout::of(air).num_entries(), transcript);
 
     // FRI commit phase
-    let mut zetas: Vec<FieldElement<F>> = Vec::new();
+    let mut zetas: Vec<FieldElement<E>> = Vec::new();
     let merkle_roots = &proof.fri_layers_merkle_roots;
     for root in merkle_roots.iter() {
-        let root_bytes = root.to_bytes_be();
+        let root_bytes = E::to_bytes_be(root);
         // <<<< Receive commitment: [pₖ] (the first one is [p₀])
         transcript.append(&root_bytes);
 
         // >>>> Send challenge 𝜁ₖ
-        let zeta = transcript_to_field(transcript);
+        let zeta = E::sample(transcript);
         zetas.push(zeta);
     }
 
     // <<<< Receive value: pₙ
-    transcript.append(&proof.fri_last_value.to_bytes_be());
+    transcript.append(&E::to_bytes_be(&proof.fri_last_value));
 
     // FRI query phase
     // <<<< Send challenges 𝜄ₛ (iota_s)
     let iotas = (0..air.options().fri_number_of_queries)
-        .map(|_| transcript_to_usize(transcript) % (2_usize.pow(domain.lde_root_order)))
+        .map(|_| transcript_to_index(transcript, 2_usize.pow(domain.lde_root_order)))
         .collect();
 
     Challenges {
@@ -176,32 +347,36 @@ where
         boundary_coeffs,
         transition_coeffs,
         trace_term_coeffs,
-        gamma_even,
-        gamma_odd,
+        composition_poly_coeffs,
         zetas,
         iotas,
         rap_challenges,
     }
 }
 
-fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>>(
+fn step_2_verify_claimed_composition_polynomial<F, E, A>(
     air: &A,
-    proof: &StarkProof<F>,
+    proof: &StarkProof<F, E>,
     domain: &Domain<F>,
     public_input: &A::PublicInput,
-    challenges: &Challenges<F, A>,
-) -> bool {
+    challenges: &Challenges<E, A>,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+{
     // BEGIN TRACE <-> Composition poly consistency evaluation check
-    // These are H_1(z^2) and H_2(z^2)
-    let composition_poly_even_ood_evaluation = &proof.composition_poly_even_ood_evaluation;
-    let composition_poly_odd_ood_evaluation = &proof.composition_poly_odd_ood_evaluation;
-
     let boundary_constraints = air.boundary_constraints(&challenges.rap_challenges, public_input);
 
     let n_trace_cols = air.context().trace_columns;
+    boundary_constraints
+        .check(air.context())
+        .map_err(invalid_parameters)?;
+    let trace_primitive_root = E::embed(&domain.trace_primitive_root);
 
     let boundary_constraint_domains =
-        boundary_constraints.generate_roots_of_unity(&domain.trace_primitive_root, n_trace_cols);
+        boundary_constraints.generate_roots_of_unity(&trace_primitive_root, n_trace_cols);
     let values = boundary_constraints.values(n_trace_cols);
 
     // Following naming conventions from https://www.notamonadtutorial.com/diving-deep-fri/
@@ -209,14 +384,15 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
     let mut boundary_quotient_degrees = Vec::with_capacity(n_trace_cols);
 
     for trace_idx in 0..n_trace_cols {
-        let trace_evaluation = &proof.trace_ood_frame_evaluations.get_row(0)[trace_idx];
+        // Every column is read at the first row, so these are the first values.
+        let trace_evaluation = &proof.trace_ood_evaluations[trace_idx];
         let boundary_constraints_domain = &boundary_constraint_domains[trace_idx];
         let boundary_interpolating_polynomial =
             &Polynomial::interpolate(boundary_constraints_domain, &values[trace_idx])
                 .expect("xs and ys have equal length and xs are unique");
 
         let boundary_zerofier =
-            boundary_constraints.compute_zerofier(&domain.trace_primitive_root, trace_idx);
+            boundary_constraints.compute_zerofier(&trace_primitive_root, trace_idx);
 
         let boundary_quotient_ood_evaluation = (trace_evaluation
             - boundary_interpolating_polynomial.evaluate(&challenges.z))
@@ -234,7 +410,7 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
 
     let boundary_term_degree_adjustment = air.composition_poly_degree_bound() - trace_length;
 
-    let boundary_quotient_ood_evaluations: Vec<FieldElement<F>> = boundary_c_i_evaluations
+    let boundary_quotient_ood_evaluations: Vec<FieldElement<E>> = boundary_c_i_evaluations
         .iter()
         .zip(&challenges.boundary_coeffs)
         .map(|(poly_eval, (alpha, beta))| {
@@ -244,37 +420,42 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
 
     let boundary_quotient_ood_evaluation = boundary_quotient_ood_evaluations
         .iter()
-        .fold(FieldElement::<F>::zero(), |acc, x| acc + x);
-
-    let transition_ood_frame_evaluations = air.compute_transition(
-        &proof.trace_ood_frame_evaluations,
-        &challenges.rap_challenges,
-    );
-
-    let transition_exemptions = air.transition_exemptions();
-
-    let x_n = Polynomial::new_monomial(FieldElement::<F>::one(), trace_length);
-    let x_n_1 = x_n - FieldElement::<F>::one();
-
-    let divisors = transition_exemptions
-        .into_iter()
-        .map(|exemption| x_n_1.clone() / exemption)
-        .collect::<Vec<Polynomial<FieldElement<F>>>>();
-
-    let mut denominators = Vec::with_capacity(divisors.len());
-    for divisor in divisors.iter() {
-        denominators.push(divisor.evaluate(&challenges.z));
-    }
-    FieldElement::inplace_batch_inverse(&mut denominators);
+        .fold(FieldElement::<E>::zero(), |acc, x| acc + x);
+
+    let ood_frame = FrameLayout::of(air)
+        .frame(&proof.trace_ood_evaluations)
+        .with_periodic_values(periodic::evaluate_at(
+            &air.periodic_columns(),
+            trace_length,
+            &challenges.z,
+        ))
+        .with_virtual_columns(|row| air.evaluate_virtual_columns(row));
+    let transition_ood_frame_evaluations =
+        air.compute_transition(&ood_frame, &challenges.rap_challenges);
+
+    let zerofiers = air.transition_zerofiers();
+
+    // The zerofier of each transition constraint is V(x) / E(x), where V vanishes on
+    // the rows of its subset (xⁿ - 1 for every row) and its included rows, and E on
+    // the rows it excludes. Since z is not in the trace domain, its inverse at z is
+    // E(z) / V(z), and there is no need to build the quotient.
+    let denominators: Vec<_> = zerofiers
+        .iter()
+        .map(|zerofier| {
+            let vanishing = zerofier.vanishing_polynomial(trace_length, &trace_primitive_root);
+            let exemptions = zerofier.exemptions_polynomial(trace_length, &trace_primitive_root);
+            exemptions.evaluate(&challenges.z) * vanishing.evaluate(&challenges.z).inv()
+        })
+        .collect();
 
-    let mut degree_adjustments = Vec::with_capacity(divisors.len());
-    for transition_degree in air.context().transition_degrees().iter() {
+    let mut degree_adjustments = Vec::with_capacity(zerofiers.len());
+    for (transition_degree, zerofier) in air.context().transition_degrees().iter().zip(&zerofiers) {
         let degree_adjustment = air.composition_poly_degree_bound()
-            - (air.context().trace_length * (transition_degree - 1));
+            - zerofier.quotient_degree_bound(trace_length, *transition_degree);
         degree_adjustments.push(challenges.z.pow(degree_adjustment));
     }
     let transition_c_i_evaluations_sum =
-        ConstraintEvaluator::<F, A>::compute_constraint_composition_poly_evaluations_sum(
+        ConstraintEvaluator::<F, E, A>::compute_constraint_composition_poly_evaluations_sum(
             &transition_ood_frame_evaluations,
             &denominators,
             &degree_adjustments,
@@ -284,123 +465,180 @@ fn step_2_verify_claimed_composition_polynomial<F: IsFFTField, A: AIR<Field = F>
     let composition_poly_ood_evaluation =
         &boundary_quotient_ood_evaluation + transition_c_i_evaluations_sum;
 
-    let composition_poly_claimed_ood_evaluation =
-        composition_poly_even_ood_evaluation + &challenges.z * composition_poly_odd_ood_evaluation;
+    // H(z) = ∑ zⁱHᵢ(zᴺ)
+    let composition_poly_claimed_ood_evaluation = proof
+        .composition_poly_ood_evaluations
+        .iter()
+        .rev()
+        .fold(FieldElement::<E>::zero(), |acc, evaluation| {
+            acc * &challenges.z + evaluation
+        });
 
-    composition_poly_claimed_ood_evaluation == composition_poly_ood_evaluation
+    if composition_poly_claimed_ood_evaluation != composition_poly_ood_evaluation {
+        return Err(VerificationError::OodConsistency);
+    }
+    Ok(())
 }
 
-fn step_3_verify_fri<F, A>(
+fn step_3_verify_fri<F, E, A>(
     air: &A,
-    proof: &StarkProof<F>,
+    proof: &StarkProof<F, E>,
     domain: &Domain<F>,
-    challenges: &Challenges<F, A>,
-) -> bool
+    challenges: &Challenges<E, A>,
+) -> Result<(), VerificationError>
 where
     F: IsFFTField,
-    FieldElement<F>: ByteConversion,
-    A: AIR<Field = F>,
+    E: IsChallengeField<F>,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<E>: MaybeSync,
+    A: AIR<Field = F, ChallengeField = E> + MaybeSync,
 {
-    let mut result = true;
+    let zetas = &challenges.zetas;
+
+    // Each query is checked independently of the others, so with the `parallel`
+    // feature they are spread over the available threads.
+    #[cfg(not(feature = "parallel"))]
+    let queries = proof
+        .query_list
+        .iter()
+        .zip(challenges.iotas.iter())
+        .enumerate();
+    #[cfg(feature = "parallel")]
+    let queries = proof
+        .query_list
+        .par_iter()
+        .zip(challenges.iotas.par_iter())
+        .enumerate();
+
     // Verify FRI
-    for (proof_s, iota_s) in proof.query_list.iter().zip(challenges.iotas.iter()) {
-        // this is done in constant time
-        result &= verify_query_and_sym_openings(
-            air,
-            &proof.fri_layers_merkle_roots,
-            &proof.fri_last_value,
-            &challenges.zetas,
-            *iota_s,
-            proof_s,
-            domain,
-        );
-    }
+    let query_results: Vec<Result<(), VerificationError>> = queries
+        .map(|(query, (proof_s, iota_s))| {
+            verify_query_and_sym_openings(
+                air,
+                &proof.fri_layers_merkle_roots,
+                &proof.fri_last_value,
+                zetas,
+                *iota_s,
+                proof_s,
+                domain,
+            )
+            .map_err(|error| match error {
+                QueryError::LayerOpening(layer) => {
+                    VerificationError::FriLayerOpening { query, layer }
+                }
+                QueryError::LastValue => VerificationError::FriLastValue { query },
+            })
+        })
+        .collect();
 
-    result
+    // All the queries are checked before reporting the first failure, so the
+    // running time doesn't depend on which query failed.
+    query_results.into_iter().collect()
 }
 
-fn step_4_verify_deep_composition_polynomial<F: IsFFTField, A: AIR<Field = F>>(
-    proof: &StarkProof<F>,
+fn step_4_verify_deep_composition_polynomial<F, E, A>(
+    air: &A,
+    proof: &StarkProof<F, E>,
     domain: &Domain<F>,
-    challenges: &Challenges<F, A>,
-) -> bool
+    challenges: &Challenges<E, A>,
+) -> Result<(), VerificationError>
 where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
     FieldElement<F>: ByteConversion,
 {
-    let mut result = true;
-
     let iota_0 = challenges.iotas[0];
+    let challenge_hasher = ChallengeHasher::new::<F>();
 
-    // Verify opening Open(H₁(D_LDE, 𝜐₀)
-    result &= proof
-        .deep_poly_openings
-        .lde_composition_poly_even_proof
-        .verify(
-            &proof.composition_poly_even_root,
-            iota_0,
-            &proof
-                .deep_poly_openings
-                .lde_composition_poly_even_evaluation,
-            &HASHER,
-        );
-
-    // Verify opening Open(H₂(D_LDE, 𝜐₀),
-    result &= proof
-        .deep_poly_openings
-        .lde_composition_poly_odd_proof
-        .verify(
-            &proof.composition_poly_odd_root,
-            iota_0,
-            &proof.deep_poly_openings.lde_composition_poly_odd_evaluation,
-            &HASHER,
-        );
+    // Verify openings Open(Hᵢ(D_LDE), 𝜐₀)
+    for (part, ((merkle_root, merkle_proof), evaluation)) in proof
+        .composition_poly_roots
+        .iter()
+        .zip(&proof.deep_poly_openings.lde_composition_poly_proofs)
+        .zip(&proof.deep_poly_openings.lde_composition_poly_evaluations)
+        .enumerate()
+    {
+        if !merkle_proof.verify(merkle_root, iota_0, evaluation, &challenge_hasher) {
+            return Err(VerificationError::CompositionPolyOpening(part));
+        }
+    }
 
     // Verify openings Open(tⱼ(D_LDE), 𝜐₀)
-    for ((merkle_root, merkle_proof), evaluation) in proof
+    for (column, ((merkle_root, merkle_proof), evaluation)) in proof
         .lde_trace_merkle_roots
         .iter()
         .zip(&proof.deep_poly_openings.lde_trace_merkle_proofs)
         .zip(&proof.deep_poly_openings.lde_trace_evaluations)
+        .enumerate()
     {
-        result &= merkle_proof.verify(merkle_root, iota_0, evaluation, &HASHER);
+        if !merkle_proof.verify(merkle_root, iota_0, evaluation, &HASHER) {
+            return Err(VerificationError::TraceOpening(column));
+        }
+    }
+    // The columns over the challenge field follow the others.
+    let first_extension_column = proof.lde_trace_merkle_roots.len();
+    for (column, ((merkle_root, merkle_proof), evaluation)) in proof
+        .lde_extension_merkle_roots
+        .iter()
+        .zip(&proof.deep_poly_openings.lde_extension_merkle_proofs)
+        .zip(&proof.deep_poly_openings.lde_extension_evaluations)
+        .enumerate()
+    {
+        if !merkle_proof.verify(merkle_root, iota_0, evaluation, &challenge_hasher) {
+            return Err(VerificationError::TraceOpening(
+                first_extension_column + column,
+            ));
+        }
     }
 
     // DEEP consistency check
     // Verify that Deep(x) is constructed correctly
     let deep_poly_evaluation =
-        reconstruct_deep_composition_poly_evaluation(proof, domain, challenges);
+        reconstruct_deep_composition_poly_evaluation(air, proof, domain, challenges);
     let deep_poly_claimed_evaluation = &proof.query_list[0].first_layer_evaluation;
 
-    result &= deep_poly_claimed_evaluation == &deep_poly_evaluation;
-    result
+    if deep_poly_claimed_evaluation != &deep_poly_evaluation {
+        return Err(VerificationError::DeepConsistency);
+    }
+    Ok(())
+}
+
+/// Failure of a single FRI query, before it is tagged with the query index.
+enum QueryError {
+    LayerOpening(usize),
+    LastValue,
 }
 
-fn verify_query_and_sym_openings<F: IsField + IsFFTField, A: AIR<Field = F>>(
+fn verify_query_and_sym_openings<F, E, A>(
     air: &A,
-    fri_layers_merkle_roots: &[FieldElement<F>],
-    fri_last_value: &FieldElement<F>,
-    zetas: &[FieldElement<F>],
+    fri_layers_merkle_roots: &[FieldElement<E>],
+    fri_last_value: &FieldElement<E>,
+    zetas: &[FieldElement<E>],
     iota: usize,
-    fri_decommitment: &FriDecommitment<F>,
+    fri_decommitment: &FriDecommitment<E>,
     domain: &Domain<F>,
-) -> bool
+) -> Result<(), QueryError>
 where
-    FieldElement<F>: ByteConversion,
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F>,
 {
+    let hasher = ChallengeHasher::new::<F>();
     // Verify opening Open(p₀(D₀), 𝜐ₛ)
     if !fri_decommitment.first_layer_auth_path.verify(
         &fri_layers_merkle_roots[0],
         iota,
         &fri_decommitment.first_layer_evaluation,
-        &HASHER,
+        &hasher,
     ) {
-        return false;
+        return Err(QueryError::LayerOpening(0));
     }
 
     let lde_primitive_root = F::get_primitive_root_of_unity(domain.lde_root_order as u64).unwrap();
-    let offset = FieldElement::from(air.options().coset_offset);
+    let offset = FieldElement::<F>::from(air.options().coset_offset);
     // evaluation point = offset * w ^ i in the Stark literature
-    let mut evaluation_point = offset * lde_primitive_root.pow(iota);
+    let mut evaluation_point = E::embed(&(offset * lde_primitive_root.pow(iota)));
 
     let mut v = fri_decommitment.first_layer_evaluation.clone();
     // For each fri layer merkle proof check:
@@ -439,79 +677,214 @@ where
             merkle_root,
             layer_evaluation_index_sym,
             evaluation_sym,
-            &HASHER,
+            &hasher,
         ) {
-            return false;
+            return Err(QueryError::LayerOpening(k));
         }
 
         let beta = &zetas[k];
         // v is the calculated element for the co linearity check
-        let two = &FieldElement::from(2);
+        let two = &FieldElement::<E>::from(2);
         v = (&v + evaluation_sym) / two + beta * (&v - evaluation_sym) / (two * &evaluation_point);
         evaluation_point = evaluation_point.pow(2_u64);
     }
 
     // Check that last value is the given by the prover
-    v == *fri_last_value
+    if v != *fri_last_value {
+        return Err(QueryError::LastValue);
+    }
+    Ok(())
 }
 
 // Reconstruct Deep(\upsilon_0) off the values in the proof
-fn reconstruct_deep_composition_poly_evaluation<F: IsFFTField, A: AIR<Field = F>>(
-    proof: &StarkProof<F>,
+fn reconstruct_deep_composition_poly_evaluation<F, E, A>(
+    air: &A,
+    proof: &StarkProof<F, E>,
     domain: &Domain<F>,
-    challenges: &Challenges<F, A>,
-) -> FieldElement<F> {
-    let primitive_root = &F::get_primitive_root_of_unity(domain.root_order as u64).unwrap();
-    let upsilon_0 = &domain.lde_roots_of_unity_coset[challenges.iotas[0]];
+    challenges: &Challenges<E, A>,
+) -> FieldElement<E>
+where
+    F: IsFFTField,
+    E: IsChallengeField<F>,
+    A: AIR<Field = F, ChallengeField = E>,
+{
+    let primitive_root = &E::embed(&domain.trace_primitive_root);
+    let lde_primitive_root = F::get_primitive_root_of_unity(domain.lde_root_order as u64).unwrap();
+    let upsilon_0 =
+        &E::embed(&(&domain.coset_offset * lde_primitive_root.pow(challenges.iotas[0])));
+
+    let transition_offsets = &air.context().transition_offsets;
+    let layout = FrameLayout::of(air);
+    let ood_frame = layout.frame(&proof.trace_ood_evaluations);
 
+    let openings = &proof.deep_poly_openings;
+    let extension_column = openings.lde_trace_evaluations.len();
     let mut trace_terms = FieldElement::zero();
+    for ((row, column), coeff) in layout
+        .entries_by_column()
+        .zip(&challenges.trace_term_coeffs)
+    {
+        let opened_value = if column < extension_column {
+            E::embed(&openings.lde_trace_evaluations[column])
+        } else {
+            openings.lde_extension_evaluations[column - extension_column].clone()
+        };
+        let poly_evaluation = (opened_value - ood_frame.get_row(row)[column].clone())
+            / (upsilon_0 - &challenges.z * primitive_root.pow(transition_offsets[row]));
+
+        trace_terms += poly_evaluation * coeff.clone();
+    }
 
-    for (col_idx, coeff_row) in
-        (0..proof.trace_ood_frame_evaluations.num_columns()).zip(&challenges.trace_term_coeffs)
+    let z_power = &challenges.z.pow(proof.composition_poly_roots.len());
+    let mut h_terms = FieldElement::zero();
+    for ((h_i_upsilon_0, h_i_z_power), gamma) in proof
+        .deep_poly_openings
+        .lde_composition_poly_evaluations
+        .iter()
+        .zip(&proof.composition_poly_ood_evaluations)
+        .zip(&challenges.composition_poly_coeffs)
     {
-        for (row_idx, coeff) in (0..proof.trace_ood_frame_evaluations.num_rows()).zip(coeff_row) {
-            let poly_evaluation = (proof.deep_poly_openings.lde_trace_evaluations[col_idx].clone()
-                - proof.trace_ood_frame_evaluations.get_row(row_idx)[col_idx].clone())
-                / (upsilon_0 - &challenges.z * primitive_root.pow(row_idx as u64));
+        h_terms += (h_i_upsilon_0 - h_i_z_power) / (upsilon_0 - z_power) * gamma;
+    }
 
-            trace_terms += poly_evaluation * coeff.clone();
+    trace_terms + h_terms
+}
+
+pub fn verify<F, A>(
+    proof: &StarkProof<F, A::ChallengeField>,
+    air: &A,
+    public_input: &A::PublicInput,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    verify_with_transcript(
+        proof,
+        air,
+        public_input,
+        &mut step_1_transcript_initialization(),
+    )
+}
+
+/// Verifies the proofs of an execution split in segments, built by
+/// `prover::prove_chain`, each with the public input of its segment. Besides
+/// each proof, it checks that every segment starts from the final state of
+/// the previous one, so the AIR has to have a state.
+pub fn verify_chain<F, A>(
+    segments: &[(StarkProof<F, A::ChallengeField>, A::PublicInput)],
+    air: &A,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+{
+    if segments.is_empty() {
+        return Err(VerificationError::InvalidParameters(String::from(
+            "the chain has no segments",
+        )));
+    }
+    if air.initial_state(&segments[0].1).is_empty() {
+        return Err(VerificationError::InvalidParameters(String::from(
+            "the AIR has no state to link the segments with",
+        )));
+    }
+    for (index, pair) in segments.windows(2).enumerate() {
+        if air.final_state(&pair[0].1) != air.initial_state(&pair[1].1) {
+            return Err(VerificationError::BrokenChain(index + 1));
         }
     }
+    segments
+        .iter()
+        .try_for_each(|(proof, public_input)| verify(proof, air, public_input))
+}
 
-    let z_squared = &(&challenges.z * &challenges.z);
-    let h_1_upsilon_0 = &proof
-        .deep_poly_openings
-        .lde_composition_poly_even_evaluation;
-    let h_1_zsquared = &proof.composition_poly_even_ood_evaluation;
-    let h_2_upsilon_0 = &proof.deep_poly_openings.lde_composition_poly_odd_evaluation;
-    let h_2_zsquared = &proof.composition_poly_odd_ood_evaluation;
+/// Verifies the proof of a Cairo program, and returns the output of the
+/// program, which the proof binds to its public memory.
+#[cfg(feature = "std")]
+pub fn verify_cairo(
+    proof: &StarkProof<Stark252PrimeField, Stark252PrimeField>,
+    air: &CairoAIR,
+    public_input: &PublicInputs,
+) -> Result<CairoPublicOutput, VerificationError> {
+    verify(proof, air, public_input)?;
+    public_input
+        .public_output()
+        .map_err(|error| VerificationError::InvalidParameters(format!("{error:?}")))
+}
 
-    let h_1_term = (h_1_upsilon_0 - h_1_zsquared) / (upsilon_0 - z_squared);
-    let h_2_term = (h_2_upsilon_0 - h_2_zsquared) / (upsilon_0 - z_squared);
+/// Same as `verify`, for a proof encoded with `StarkProof::to_bytes` by this or
+/// any earlier release of the crate. The proof is decoded with the decoder of
+/// the layout version in its header.
+pub fn verify_bytes<F, A>(
+    proof: &[u8],
+    air: &A,
+    public_input: &A::PublicInput,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: ByteConversion + MaybeSync,
+{
+    let proof = StarkProof::<F, A::ChallengeField>::from_bytes(proof)
+        .map_err(VerificationError::MalformedProof)?;
+    verify(&proof, air, public_input)
+}
 
-    trace_terms + h_1_term * &challenges.gamma_even + h_2_term * &challenges.gamma_odd
+/// Same as `verify`, for a proof encoded with `StarkProof::to_compressed_bytes`.
+#[cfg(feature = "compression")]
+pub fn verify_compressed<F, A>(
+    proof: &[u8],
+    air: &A,
+    public_input: &A::PublicInput,
+) -> Result<(), VerificationError>
+where
+    F: IsFFTField,
+    A: AIR<Field = F> + MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: ByteConversion + MaybeSync,
+{
+    let proof = StarkProof::<F, A::ChallengeField>::from_compressed_bytes(proof)
+        .map_err(VerificationError::MalformedProof)?;
+    verify(&proof, air, public_input)
 }
 
-pub fn verify<F, A>(proof: &StarkProof<F>, air: &A, public_input: &A::PublicInput) -> bool
+/// Same as `verify`, but replays the proof on `transcript` instead of a fresh
+/// one. It checks proofs built with `prove_with_transcript` or `finish`, and the
+/// transcript must be in the same state the prover's was in when it started.
+pub fn verify_with_transcript<F, A, T>(
+    proof: &StarkProof<F, A::ChallengeField>,
+    air: &A,
+    public_input: &A::PublicInput,
+    transcript: &mut T,
+) -> Result<(), VerificationError>
 where
     F: IsFFTField,
-    A: AIR<Field = F>,
-    FieldElement<F>: ByteConversion,
+    A: AIR<Field = F> + MaybeSync,
+    FieldElement<F>: ByteConversion + MaybeSync,
+    FieldElement<A::ChallengeField>: MaybeSync,
+    T: Transcript,
 {
-    let mut transcript = step_1_transcript_initialization();
-    let domain = Domain::new(air);
+    let air = &air
+        .with_public_input(public_input)
+        .map_err(invalid_parameters)?;
+    check_domain_parameters(air).map_err(invalid_parameters)?;
+    let domain = Domain::new_succinct(air);
+
+    step_0_check_proof_shape(air, proof, &domain)?;
+    check_preprocessed_roots(air, proof)?;
 
     let challenges =
-        step_1_replay_rounds_and_recover_challenges(air, proof, &domain, &mut transcript);
+        step_1_replay_rounds_and_recover_challenges(air, proof, &domain, public_input, transcript);
 
-    if !step_2_verify_claimed_composition_polynomial(air, proof, &domain, public_input, &challenges)
-    {
-        return false;
-    }
+    step_2_verify_claimed_composition_polynomial(air, proof, &domain, public_input, &challenges)?;
 
-    if !step_3_verify_fri(air, proof, &domain, &challenges) {
-        return false;
-    }
+    step_3_verify_fri(air, proof, &domain, &challenges)?;
 
-    step_4_verify_deep_composition_polynomial(proof, &domain, &challenges)
+    step_4_verify_deep_composition_polynomial(air, proof, &domain, &challenges)
 }
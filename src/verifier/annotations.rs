@@ -0,0 +1,269 @@
+//! Intermediate values of the verification of a Cairo proof, in the form of
+//! the annotations Stone's verifier writes with `--annotation_file`.
+//!
+//! Each annotation is a value the prover sends (`P->V`) or the verifier draws
+//! (`V->P`), in the order of the transcript, under the scope of its round:
+//! the commitments of the trace, the interaction elements, the constraint
+//! coefficients, the composition commitments, the out of domain point and
+//! values, and the FRI layers. Comparing them with Stone's annotations of the
+//! same statement shows the first round where the two provers disagree.
+//!
+//! The lines have no byte ranges, since proofs of this crate aren't
+//! serialized as Stone's, and each column has a commitment of its own where
+//! Stone commits to all the columns of a trace at once. Field elements are
+//! written in standard form, as 32 big endian bytes in hex.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+use lambdaworks_math::{
+    field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField, traits::ByteConversion,
+};
+
+use super::{
+    check_preprocessed_roots, step_0_check_proof_shape,
+    step_1_replay_rounds_and_recover_challenges, step_1_transcript_initialization,
+    VerificationError,
+};
+use crate::{
+    air::{
+        cairo_air::air::{CairoAIR, PublicInputs},
+        traits::AIR,
+    },
+    proof::StarkProof,
+    prover::ProvingError,
+    Domain, FE,
+};
+
+const STARK: &str = "/cpu air/STARK";
+
+/// Who sends the value of an annotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ProverToVerifier,
+    VerifierToProver,
+}
+
+/// A value of the verification, as a line of Stone's annotations:
+/// `P->V: /cpu air/STARK/FRI/Commitment/Layer 1: Commitment: Hash(0x...)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    pub direction: Direction,
+    /// Scope of the value, the round it belongs to.
+    pub scope: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::ProverToVerifier => "P->V",
+            Direction::VerifierToProver => "V->P",
+        };
+        write!(
+            f,
+            "{direction}: {}: {}: {}",
+            self.scope, self.name, self.value
+        )
+    }
+}
+
+#[derive(Default)]
+struct Annotations {
+    annotations: Vec<Annotation>,
+}
+
+impl Annotations {
+    fn push(&mut self, direction: Direction, scope: &str, name: &str, value: String) {
+        self.annotations.push(Annotation {
+            direction,
+            scope: format!("{STARK}{scope}"),
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    fn hashes(&mut self, scope: &str, roots: &[FE]) {
+        for (column, root) in roots.iter().enumerate() {
+            self.push(
+                Direction::ProverToVerifier,
+                scope,
+                &format!("Commitment #{column}"),
+                format!("Hash({})", hex(root)),
+            );
+        }
+    }
+
+    fn element(&mut self, direction: Direction, scope: &str, name: &str, element: &FE) {
+        self.push(
+            direction,
+            scope,
+            name,
+            format!("Field Element({})", hex(element)),
+        );
+    }
+
+    fn elements<'a, I>(&mut self, direction: Direction, scope: &str, name: &str, elements: I)
+    where
+        I: IntoIterator<Item = &'a FE>,
+    {
+        let elements: Vec<_> = elements.into_iter().map(hex).collect();
+        self.push(
+            direction,
+            scope,
+            name,
+            format!("Field Elements({})", elements.join(", ")),
+        );
+    }
+}
+
+/// Annotations of the verification of `proof`, in the order of the
+/// transcript. It's an error if the public input doesn't fit the AIR or the
+/// proof doesn't have its shape, but the proof isn't checked any further, so
+/// the annotations of invalid proofs can be compared too.
+pub fn annotate_cairo(
+    proof: &StarkProof<Stark252PrimeField, Stark252PrimeField>,
+    air: &CairoAIR,
+    public_input: &PublicInputs,
+) -> Result<Vec<Annotation>, VerificationError> {
+    use Direction::*;
+
+    let air = &match air.with_public_input(public_input) {
+        Ok(air) => air,
+        Err(ProvingError::WrongParameter(message)) => {
+            return Err(VerificationError::InvalidParameters(message))
+        }
+        Err(error) => return Err(VerificationError::InvalidParameters(format!("{error:?}"))),
+    };
+    let domain = Domain::new_succinct(air);
+    step_0_check_proof_shape(air, proof, &domain)?;
+    check_preprocessed_roots(air, proof)?;
+    let challenges = step_1_replay_rounds_and_recover_challenges(
+        air,
+        proof,
+        &domain,
+        public_input,
+        &mut step_1_transcript_initialization(),
+    );
+
+    let mut annotations = Annotations::default();
+    let main_columns = proof.lde_trace_merkle_roots.len() - air.number_auxiliary_rap_columns();
+    let (main_roots, aux_roots) = proof.lde_trace_merkle_roots.split_at(main_columns);
+    annotations.hashes("/Original/Commit on Trace", main_roots);
+    let rap_challenges = &challenges.rap_challenges;
+    for (index, element) in [
+        &rap_challenges.alpha_memory,
+        &rap_challenges.z_memory,
+        &rap_challenges.z_range_check,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        annotations.element(
+            VerifierToProver,
+            "/Interaction",
+            &format!("Interaction element #{index}"),
+            element,
+        );
+    }
+    annotations.hashes("/Interaction/Commit on Trace", aux_roots);
+    annotations.hashes(
+        "/Interaction/Commit on Extension",
+        &proof.lde_extension_merkle_roots,
+    );
+
+    let (boundary_alphas, boundary_betas): (Vec<_>, Vec<_>) =
+        challenges.boundary_coeffs.iter().cloned().unzip();
+    let (transition_alphas, transition_betas): (Vec<_>, Vec<_>) =
+        challenges.transition_coeffs.iter().cloned().unzip();
+    annotations.elements(
+        VerifierToProver,
+        "/Out Of Domain Sampling",
+        "Constraint polynomial random elements",
+        boundary_alphas
+            .iter()
+            .chain(&boundary_betas)
+            .chain(&transition_alphas)
+            .chain(&transition_betas),
+    );
+    annotations.hashes(
+        "/Out Of Domain Sampling/Commit on Trace",
+        &proof.composition_poly_roots,
+    );
+    annotations.element(
+        VerifierToProver,
+        "/Out Of Domain Sampling",
+        "Evaluation point",
+        &challenges.z,
+    );
+    annotations.elements(
+        ProverToVerifier,
+        "/Out Of Domain Sampling",
+        "OODS values",
+        proof
+            .composition_poly_ood_evaluations
+            .iter()
+            .chain(&proof.trace_ood_evaluations),
+    );
+    annotations.elements(
+        VerifierToProver,
+        "/Out Of Domain Sampling",
+        "OODS coefficients",
+        challenges
+            .composition_poly_coeffs
+            .iter()
+            .chain(&challenges.trace_term_coeffs),
+    );
+
+    for (layer, (root, zeta)) in proof
+        .fri_layers_merkle_roots
+        .iter()
+        .zip(&challenges.zetas)
+        .enumerate()
+    {
+        let scope = format!("/FRI/Commitment/Layer {layer}");
+        annotations.push(
+            ProverToVerifier,
+            &scope,
+            "Commitment",
+            format!("Hash({})", hex(root)),
+        );
+        annotations.element(VerifierToProver, &scope, "Evaluation point", zeta);
+    }
+    annotations.elements(
+        ProverToVerifier,
+        "/FRI/Commitment/Last Layer",
+        "Coefficients",
+        [&proof.fri_last_value],
+    );
+    let queries: Vec<_> = challenges.iotas.iter().map(usize::to_string).collect();
+    annotations.push(
+        VerifierToProver,
+        "/FRI/QueryIndices",
+        "Query indices",
+        format!("Indices({})", queries.join(", ")),
+    );
+    Ok(annotations.annotations)
+}
+
+/// The annotations as the lines of an annotation file.
+pub fn to_annotation_file(annotations: &[Annotation]) -> String {
+    let mut file = String::new();
+    for annotation in annotations {
+        writeln!(file, "{annotation}").expect("writing to a string can't fail");
+    }
+    file
+}
+
+fn hex(element: &FE) -> String {
+    let mut hex = String::from("0x");
+    for byte in element.to_bytes_be() {
+        write!(hex, "{byte:02x}").expect("writing to a string can't fail");
+    }
+    hex
+}
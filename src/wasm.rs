@@ -0,0 +1,93 @@
+//! JavaScript bindings of the Cairo prover and verifier, enabled by the `wasm`
+//! feature and built with `make build_wasm_bindings`.
+//!
+//! Proofs are the bytes of `StarkProof::to_bytes`, while the public input and
+//! the proof options are JSON strings, in the format written and read by the
+//! `cairo-stark` command line tool:
+//!
+//! ```js
+//! const output = prove(traceBytes, memoryBytes, programSize, options);
+//! const proof = output.takeProof();
+//! const valid = verify(proof, output.publicInput, options);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    air::{
+        cairo_air::air::{CairoAIR, PublicInputs},
+        context::ProofOptions,
+    },
+    cairo_vm::{cairo_mem::CairoMemory, cairo_trace::CairoTrace},
+    prover, verifier,
+};
+
+/// Proof of a Cairo execution and the public input it has to be verified against.
+#[wasm_bindgen]
+pub struct ProofOutput {
+    proof: Vec<u8>,
+    public_input: String,
+}
+
+#[wasm_bindgen]
+impl ProofOutput {
+    /// Moves the proof bytes out to JavaScript, leaving this output without
+    /// them, so that the proof isn't kept twice in the WASM memory.
+    #[wasm_bindgen(js_name = takeProof)]
+    pub fn take_proof(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.proof)
+    }
+
+    /// The public input as JSON.
+    #[wasm_bindgen(getter, js_name = publicInput)]
+    pub fn public_input(&self) -> String {
+        self.public_input.clone()
+    }
+}
+
+fn parse_options(options: &str) -> Result<ProofOptions, JsError> {
+    serde_json::from_str(options).map_err(|e| JsError::new(&format!("invalid options: {e}")))
+}
+
+/// Proves the execution given by the trace and memory files of the Cairo
+/// runner. The program bytecode is in the `program_size` memory cells from
+/// address 1.
+#[wasm_bindgen]
+pub fn prove(
+    trace: &[u8],
+    memory: &[u8],
+    program_size: usize,
+    options: &str,
+) -> Result<ProofOutput, JsError> {
+    let options = parse_options(options)?;
+    let register_states = CairoTrace::from_bytes_le(trace)
+        .map_err(|e| JsError::new(&format!("invalid trace: {e}")))?;
+    let memory = CairoMemory::from_bytes_le(memory)
+        .map_err(|e| JsError::new(&format!("invalid memory: {e}")))?;
+
+    let public_input = PublicInputs::try_from_regs_and_mem(&register_states, &memory, program_size)
+        .map_err(|e| JsError::new(&format!("invalid execution: {e:?}")))?;
+    let air = CairoAIR::for_public_input(options, &public_input)
+        .map_err(|e| JsError::new(&format!("invalid public input: {e:?}")))?;
+    let output = prover::prove(&(register_states, memory), &air, &public_input)
+        .map_err(|e| JsError::new(&format!("proving failed: {e:?}")))?;
+
+    Ok(ProofOutput {
+        proof: output.proof.to_bytes(),
+        public_input: serde_json::to_string(&output.public_values)
+            .map_err(|e| JsError::new(&format!("couldn't encode the public input: {e}")))?,
+    })
+}
+
+/// Verifies a proof returned by `prove`. Returns an error only when the
+/// public input or the options can't be read.
+#[wasm_bindgen]
+pub fn verify(proof: &[u8], public_input: &str, options: &str) -> Result<bool, JsError> {
+    let options = parse_options(options)?;
+    let public_input: PublicInputs = serde_json::from_str(public_input)
+        .map_err(|e| JsError::new(&format!("invalid public input: {e}")))?;
+
+    let air = CairoAIR::for_public_input(options, &public_input)
+        .map_err(|e| JsError::new(&format!("invalid public input: {e:?}")))?;
+    Ok(verifier::verify_bytes(proof, &air, &public_input).is_ok())
+}
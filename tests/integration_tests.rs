@@ -1,21 +1,57 @@
+use std::collections::HashMap;
+use std::sync::{atomic::AtomicBool, Arc};
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+use lambdaworks_crypto::fiat_shamir::default_transcript::DefaultTranscript;
+#[cfg(not(feature = "test_fiat_shamir"))]
+use lambdaworks_crypto::fiat_shamir::transcript::Transcript;
 use lambdaworks_math::field::fields::{
-    fft_friendly::stark_252_prime_field::Stark252PrimeField, u64_prime_field::FE17,
+    fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    u64_prime_field::{F17, FE17},
 };
 use lambdaworks_math::helpers::resize_to_next_power_of_two;
-use lambdaworks_stark::air::cairo_air::air::{CairoAIR, PublicInputs};
+use lambdaworks_stark::air::cairo_air::air::{
+    CairoAIR, MemorySegment, PublicInputs, EC_OP_BASE, EC_OP_BIT, EC_OP_CELLS, EC_OP_Q_Y,
+    EC_OP_SELECTOR, EC_OP_SLOPE, EC_OP_VALUE, EC_OP_X, EC_OP_X_DIFF_INV, FRAME_SELECTOR,
+    KECCAK_BASE, KECCAK_CELLS, KECCAK_SELECTOR, KECCAK_VALUE, MAIN_COLUMNS, PEDERSEN_BASE,
+    PEDERSEN_BIT, PEDERSEN_SELECTOR, PEDERSEN_SUFFIX, PEDERSEN_UNPACKING, PEDERSEN_VALUE,
+    PEDERSEN_X, POSEIDON_BASE, POSEIDON_CARRIES, POSEIDON_CUBES, POSEIDON_SELECTOR, POSEIDON_STATE,
+    POSEIDON_VALUE,
+};
+use lambdaworks_stark::air::cairo_air::ec_op as cairo_ec_op;
+use lambdaworks_stark::air::cairo_air::keccak as cairo_keccak;
+use lambdaworks_stark::air::cairo_air::layout::{Builtin, Layout};
+use lambdaworks_stark::air::cairo_air::pedersen::{self, PEDERSEN_ROWS};
+use lambdaworks_stark::air::cairo_air::poseidon as cairo_poseidon;
+use lambdaworks_stark::air::composition::{AirColumn, AirComposition, Link};
+use lambdaworks_stark::air::debug::validate_trace;
+use lambdaworks_stark::air::dsl::{AirBuilder, Expr};
+use lambdaworks_stark::air::dyn_air::{DynAir, DynPublicInput, DynRawTrace};
 use lambdaworks_stark::air::example::fibonacci_rap::{fibonacci_rap_trace, FibonacciRAP};
 use lambdaworks_stark::air::example::{
-    dummy_air, fibonacci_2_columns, fibonacci_f17, quadratic_air, simple_fibonacci,
+    block_counter, countdown, dummy_air, extension_permutation, f17_squared::F17Squared,
+    fibonacci_2_columns, fibonacci_f17, fibonacci_segment, keccak, multi_round_permutation,
+    parallel_fibonacci, periodic_lookup, periodic_sum, poseidon, preprocessed_sum, quadratic_air,
+    range_check, rescue, segment_sum, simple_fibonacci, vm, zero_count,
 };
+use lambdaworks_stark::air::gadgets::public_memory::MemoryPage;
+use lambdaworks_stark::air::lookup::multiplicities;
+use lambdaworks_stark::air::testing::{assert_air_sound, find_unconstrained_cells};
+use lambdaworks_stark::air::traits::AIR;
 use lambdaworks_stark::cairo_run::cairo_layout::CairoLayout;
 use lambdaworks_stark::cairo_run::run::run_program;
 use lambdaworks_stark::cairo_vm::cairo_mem::CairoMemory;
 use lambdaworks_stark::cairo_vm::cairo_trace::CairoTrace;
+use lambdaworks_stark::sharp::{fact_hash, fact_of_proof, GpsStatement};
 use lambdaworks_stark::{
     air::context::{AirContext, ProofOptions},
     fri::FieldElement,
-    prover::prove,
-    verifier::verify,
+    prover::{
+        commit, commit_preprocessed_columns, finish, prove, prove_chain, prove_with_cancellation,
+        prove_with_checkpoints, prove_with_progress, prove_with_transcript, resume_proof, Prover,
+        ProvingError, ProvingStage,
+    },
+    verifier::{verify, verify_cairo, verify_chain, verify_with_transcript, VerificationError},
 };
 
 pub type FE = FieldElement<Stark252PrimeField>;
@@ -44,6 +80,7 @@ fn test_prove_fib() {
             blowup_factor: 2,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         },
         trace_length,
         trace_columns: 1,
@@ -51,85 +88,1431 @@ fn test_prove_fib() {
         transition_exemptions: vec![2],
         transition_offsets: vec![0, 1, 2],
         num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
     let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
 
-    let result = prove(&trace, &fibonacci_air, &mut ()).unwrap();
-    assert!(verify(&result, &fibonacci_air, &()));
+    let result = prove(&trace, &fibonacci_air, &()).unwrap().proof;
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
 }
 
 #[test_log::test]
-fn test_prove_fib17() {
-    let trace = simple_fibonacci::fibonacci_trace([FE17::from(1), FE17::from(1)], 4);
+fn test_prove_with_progress_reports_every_stage() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let trace_length = trace[0].len();
 
     let context = AirContext {
         options: ProofOptions {
             blowup_factor: 2,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         },
-        trace_length: trace[0].len(),
+        trace_length,
         trace_columns: 1,
         transition_degrees: vec![1],
         transition_exemptions: vec![2],
         transition_offsets: vec![0, 1, 2],
         num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
-    let fibonacci_air = fibonacci_f17::Fibonacci17AIR::from(context);
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let mut reports = Vec::new();
+    let result = prove_with_progress(&trace, &fibonacci_air, &(), |stage, done| {
+        reports.push((stage, done))
+    })
+    .unwrap()
+    .proof;
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
 
-    let result = prove(&trace, &fibonacci_air, &mut ()).unwrap();
-    assert!(verify(&result, &fibonacci_air, &()));
+    assert_eq!(reports.first(), Some(&(ProvingStage::MainTrace, 0.0)));
+    assert_eq!(reports.last(), Some(&(ProvingStage::Finished, 1.0)));
+    assert!(reports.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    let fri_layers = reports
+        .iter()
+        .filter(|(stage, _)| matches!(stage, ProvingStage::FriLayer(_)))
+        .count();
+    assert_eq!(fri_layers, trace_length.trailing_zeros() as usize);
 }
 
+#[cfg(feature = "serde")]
 #[test_log::test]
-fn test_prove_fib_2_cols() {
-    let trace_columns =
-        fibonacci_2_columns::fibonacci_trace_2_columns([FE::from(1), FE::from(1)], 16);
+fn test_proof_round_trips_through_json_and_bincode() {
+    use lambdaworks_stark::proof::StarkProof;
+
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let trace_length = trace[0].len();
 
     let context = AirContext {
         options: ProofOptions {
             blowup_factor: 2,
-            fri_number_of_queries: 7,
+            fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         },
-        trace_length: trace_columns[0].len(),
-        transition_degrees: vec![1, 1],
-        transition_exemptions: vec![1, 1],
-        transition_offsets: vec![0, 1],
-        num_transition_constraints: 2,
-        trace_columns: 2,
+        trace_length,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
-    let fibonacci_air = fibonacci_2_columns::Fibonacci2ColsAIR::from(context);
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+    let proof = prove(&trace, &fibonacci_air, &()).unwrap().proof;
 
-    let result = prove(&trace_columns, &fibonacci_air, &mut ()).unwrap();
-    assert!(verify(&result, &fibonacci_air, &()));
+    let json = serde_json::to_string(&proof).unwrap();
+    let from_json: StarkProof<Stark252PrimeField> = serde_json::from_str(&json).unwrap();
+    assert!(verify(&from_json, &fibonacci_air, &()).is_ok());
+
+    let config = bincode::config::standard();
+    let bytes = bincode::serde::encode_to_vec(&proof, config).unwrap();
+    let (from_bincode, _): (StarkProof<Stark252PrimeField>, usize) =
+        bincode::serde::decode_from_slice(&bytes, config).unwrap();
+    assert!(verify(&from_bincode, &fibonacci_air, &()).is_ok());
 }
 
 #[test_log::test]
-fn test_prove_quadratic() {
-    let trace = quadratic_air::quadratic_trace(FE::from(3), 4);
+fn test_prove_with_cancellation_stops_once_the_flag_is_set() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let trace_length = trace[0].len();
 
     let context = AirContext {
         options: ProofOptions {
             blowup_factor: 2,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         },
-        trace_length: trace.len(),
+        trace_length,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let cancellation = AtomicBool::new(false);
+    let result = prove_with_cancellation(&trace, &fibonacci_air, &(), &cancellation)
+        .unwrap()
+        .proof;
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
+
+    let cancellation = AtomicBool::new(true);
+    assert!(matches!(
+        prove_with_cancellation(&trace, &fibonacci_air, &(), &cancellation),
+        Err(ProvingError::Cancelled)
+    ));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_proof_with_missing_queries() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let trace_length = trace[0].len();
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let mut result = prove(&trace, &fibonacci_air, &()).unwrap().proof;
+    result.query_list.clear();
+    assert!(matches!(
+        verify(&result, &fibonacci_air, &()),
+        Err(VerificationError::InvalidProofShape(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_periodic_sum() {
+    let period = vec![FE::from(1), FE::from(0), FE::from(5), FE::from(2)];
+    let trace = periodic_sum::periodic_sum_trace(&period, 16);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 3,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 16,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = periodic_sum::PeriodicSumAIR::new(context.clone(), period);
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let other_period = vec![FE::from(1), FE::from(0), FE::from(5), FE::from(3)];
+    let other_air = periodic_sum::PeriodicSumAIR::new(context, other_period);
+    assert!(verify(&proof, &other_air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_periodic_lookup() {
+    let values: Vec<FE> = [3, 7, 0, 3, 5, 5, 5, 1, 2, 7, 6, 3, 0, 4, 4, 1]
+        .into_iter()
+        .map(FE::from)
+        .collect();
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 3,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 16,
+        trace_columns: 3,
+        transition_degrees: vec![3],
+        transition_exemptions: vec![0],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = periodic_lookup::PeriodicLookupAIR::new(context.clone(), 8);
+    let proof = prove(&values, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let mut out_of_range = values;
+    out_of_range[4] = FE::from(8);
+    assert!(prove(&out_of_range, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_range_check_gadget_with_limbs() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // 10 bits in a trace of 16 rows are checked in limbs of 4, 4 and 2 bits.
+    let air = range_check::RangeCheckAIR::new(options, 16, 10).unwrap();
+    let values: Vec<FE> = (0..16).map(|row| FE::from(row * 68 + 3)).collect();
+
+    let proof = prove(&values, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let mut out_of_range = values;
+    out_of_range[9] = FE::from(1024);
+    assert!(prove(&out_of_range, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_segment_with_a_row_every_few_rows() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // The segment has a row every 4 rows: the sum of 4², 8² and 12².
+    let air = segment_sum::SegmentSumAIR::new(options, 16, 4).unwrap();
+    let output = prove(&(), &air, &FE::zero()).unwrap();
+    assert_eq!(output.public_values, FE::from(224));
+    assert!(verify(&output.proof, &air, &FE::from(224)).is_ok());
+    assert!(verify(&output.proof, &air, &FE::from(225)).is_err());
+
+    assert!(segment_sum::SegmentSumAIR::new(air.options().clone(), 16, 16).is_err());
+}
+
+#[test_log::test]
+fn test_prove_air_with_registered_constraints() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = countdown::CountdownAIR::new(options, 16);
+    assert_eq!(air.context().num_transition_constraints, 3);
+    assert_eq!(air.context().transition_exemptions, vec![1, 0, 0]);
+
+    let output = prove(&(), &air, &FE::from(9)).unwrap();
+    assert!(verify(&output.proof, &air, &FE::from(9)).is_ok());
+    assert!(verify(&output.proof, &air, &FE::from(8)).is_err());
+}
+
+#[test_log::test]
+fn test_prove_air_with_a_column_over_the_challenge_field() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: true,
+    };
+    let air = extension_permutation::ExtensionPermutationAIR::new(options, 16);
+    let values: Vec<FE> = (0..15u64).map(|i| FE::from(i * i + 3)).collect();
+    let trace = extension_permutation::extension_permutation_trace(&values);
+
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert_eq!(proof.lde_trace_merkle_roots.len(), 2);
+    assert_eq!(proof.lde_extension_merkle_roots.len(), 1);
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let decoded =
+        lambdaworks_stark::proof::StarkProof::<Stark252PrimeField>::from_bytes(&proof.to_bytes())
+            .unwrap();
+    assert!(verify(&decoded, &air, &()).is_ok());
+
+    let mut tampered = decoded;
+    tampered.lde_extension_merkle_roots.clear();
+    assert!(matches!(
+        verify(&tampered, &air, &()),
+        Err(VerificationError::InvalidProofShape(_))
+    ));
+
+    // A column that isn't a permutation of the other breaks the product.
+    let mut trace = trace;
+    trace[1][0] = FE::from(1);
+    assert!(matches!(
+        prove(&trace, &air, &()),
+        Err(ProvingError::InvalidTrace(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_over_a_quadratic_extension_of_the_trace_field() {
+    // Two-adicity of F17 is 4, so the LDE domain of the 4 rows is 8 points
+    // and the offset 3, which isn't a square, keeps it off the trace domain.
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: true,
+    };
+    let air =
+        extension_permutation::ExtensionPermutationAIR::<F17, F17Squared>::with_fields(options, 4);
+    let values: Vec<FE17> = [1u64, 2, 5].into_iter().map(FE17::from).collect();
+    let trace = extension_permutation::extension_permutation_trace(&values);
+
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert_eq!(proof.lde_extension_merkle_roots.len(), 1);
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let mut trace = trace;
+    trace[1][0] = FE17::from(3);
+    assert!(matches!(
+        prove(&trace, &air, &()),
+        Err(ProvingError::InvalidTrace(_))
+    ));
+}
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+#[test_log::test]
+fn test_challenges_use_the_whole_challenge_field() {
+    use lambdaworks_stark::IsChallengeField;
+
+    let mut transcript = DefaultTranscript::new();
+    transcript.append(b"challenges");
+
+    // A challenge over Stark252 takes more than the 64 lowest bits.
+    let challenge =
+        <Stark252PrimeField as IsChallengeField<Stark252PrimeField>>::sample(&mut transcript);
+    let bytes =
+        <Stark252PrimeField as IsChallengeField<Stark252PrimeField>>::to_bytes_be(&challenge);
+    assert!(bytes[..24].iter().any(|byte| *byte != 0));
+
+    // Challenges over F17² aren't embedded from F17.
+    let challenges: Vec<_> = (0..8)
+        .map(|_| <F17Squared as IsChallengeField<F17>>::sample(&mut transcript))
+        .collect();
+    assert!(challenges
+        .iter()
+        .any(|challenge| challenge.value()[1] != FE17::zero()));
+}
+
+#[test_log::test]
+fn test_prove_with_preprocessed_columns_committed_once() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let constants: Vec<FE> = (0..16u64).map(|i| FE::from(i * i + 1)).collect();
+    let air = preprocessed_sum::PreprocessedSumAIR::new(options.clone(), constants.clone());
+
+    // The prover commits to the constants in its first proof only.
+    let mut prover = Prover::new(air.clone());
+    let first = prover.prove(&(), &FE::zero()).unwrap();
+    let second = prover.prove(&(), &FE::zero()).unwrap();
+    assert_eq!(first.public_values, FE::from(1030));
+    assert!(verify(&first.proof, &air, &first.public_values).is_ok());
+
+    // The verifier can be given the roots computed offline.
+    let domain = lambdaworks_stark::Domain::new(&air);
+    let roots = commit_preprocessed_columns(&air, &domain)
+        .unwrap()
+        .lde_trace_merkle_roots;
+    assert_eq!(second.proof.lde_trace_merkle_roots[..1], roots[..]);
+    let pinned = air.with_roots(roots);
+    assert!(verify(&second.proof, &pinned, &second.public_values).is_ok());
+
+    // A proof with other constants doesn't verify against these.
+    let mut other_constants = constants;
+    other_constants[3] = FE::zero();
+    let other = preprocessed_sum::PreprocessedSumAIR::new(options, other_constants);
+    let output = prove(&(), &other, &FE::zero()).unwrap();
+    assert!(matches!(
+        verify(&output.proof, &pinned, &output.public_values),
+        Err(VerificationError::PreprocessedCommitment)
+    ));
+}
+
+#[test_log::test]
+fn test_prove_poseidon_chain() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // Two permutations, of 128 rows each.
+    let air = poseidon::PoseidonAIR::new(options, 256).unwrap();
+    let input = [FE::from(1), FE::from(2), FE::from(3)];
+    let trace = poseidon::poseidon_chain_trace(&input, 256, air.round_constants());
+
+    let output = poseidon::permutation(
+        &poseidon::permutation(&input, air.round_constants()),
+        air.round_constants(),
+    );
+    let last_row: Vec<_> = trace.iter().map(|column| column[255].clone()).collect();
+    assert_eq!(last_row, output);
+
+    let public_input = poseidon::PoseidonPublicInput {
+        input,
+        output: [FE::zero(), FE::zero(), FE::zero()],
+    };
+    let proof = prove(&trace, &air, &public_input).unwrap().proof;
+    let public_input = poseidon::PoseidonPublicInput {
+        input: public_input.input,
+        output: [output[0].clone(), output[1].clone(), output[2].clone()],
+    };
+    assert!(verify(&proof, &air, &public_input).is_ok());
+
+    let mut wrong_output = public_input;
+    wrong_output.output[0] = &wrong_output.output[0] + FE::one();
+    assert!(verify(&proof, &air, &wrong_output).is_err());
+}
+
+#[test_log::test]
+fn test_prove_rescue_chain() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // Four permutations, of 8 rows each.
+    let air = rescue::RescueAIR::new(options, 32).unwrap();
+    let input = [FE::from(7), FE::from(8), FE::from(9)];
+    let trace = rescue::rescue_chain_trace(&input, 32, air.round_constants());
+
+    let output = (0..4).fold(input.to_vec(), |state, _| {
+        rescue::permutation(&state, air.round_constants())
+    });
+    let public_input = rescue::RescuePublicInput {
+        input,
+        output: [output[0].clone(), output[1].clone(), output[2].clone()],
+    };
+    let proof = prove(&trace, &air, &public_input).unwrap().proof;
+    assert!(verify(&proof, &air, &public_input).is_ok());
+
+    let mut wrong_input = public_input;
+    wrong_input.input[2] = FE::zero();
+    assert!(verify(&proof, &air, &wrong_input).is_err());
+}
+
+#[test_log::test]
+fn test_prove_keccak_permutation() {
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = keccak::KeccakAIR::new(options, 32).unwrap();
+    let input = [0; keccak::LANES];
+    let output = keccak::permutation(&input);
+    // The first lane of Keccak-f[1600] of the zero state.
+    assert_eq!(output[0], 0xf1258f7940e1dde7);
+
+    let trace = keccak::keccak_chain_trace(&input, 32);
+    let public_input = keccak::KeccakPublicInput { input, output };
+    let proof = prove(&trace, &air, &public_input).unwrap().proof;
+    assert!(verify(&proof, &air, &public_input).is_ok());
+
+    let mut wrong_output = public_input;
+    wrong_output.output[24] ^= 1 << 63;
+    assert!(verify(&proof, &air, &wrong_output).is_err());
+}
+
+#[test_log::test]
+fn test_prove_vm_program() {
+    use vm::Instruction::*;
+
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // Stores 5 at address 1, multiplies it by 3, loads it back, jumps over an
+    // addition since it isn't zero and doubles it.
+    let program = vec![
+        Set(5),
+        Add,
+        Set(1),
+        Store,
+        Set(3),
+        Mul,
+        Set(1),
+        Load,
+        Jnz(10),
+        Add,
+        Set(2),
+        Mul,
+        Halt,
+    ];
+    let air = vm::VmAIR::new(options.clone(), 16, program).unwrap();
+    let trace = air.execute().unwrap();
+
+    let public_input = vm::VmPublicInput {
+        output: FE::from(10),
+    };
+    let proof = prove(&trace, &air, &public_input).unwrap().proof;
+    assert!(verify(&proof, &air, &public_input).is_ok());
+
+    let wrong_output = vm::VmPublicInput {
+        output: FE::from(11),
+    };
+    assert!(verify(&proof, &air, &wrong_output).is_err());
+
+    let loads_first = vm::VmAIR::new(options, 16, vec![Set(1), Load, Halt]).unwrap();
+    assert!(loads_first.execute().is_err());
+}
+
+#[test_log::test]
+fn test_prove_block_counter() {
+    let trace = block_counter::block_counter_trace(4, 16);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 3,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 16,
+        trace_columns: 1,
+        transition_degrees: vec![1; 4],
+        transition_exemptions: vec![0; 4],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 4,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = block_counter::BlockCounterAIR::new(context, 4);
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    // A counter that doesn't restart satisfies every constraint but the first.
+    let counter = vec![(0..16).map(FE::from).collect()];
+    let proof = prove(&counter, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_block_counter_with_custom_zerofiers() {
+    let trace = block_counter::block_counter_trace(4, 16);
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+
+    let air = block_counter::CompactBlockCounterAIR::new(options, 16, 4);
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    let counter = vec![(0..16).map(FE::from).collect()];
+    let proof = prove(&counter, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_zero_count_with_a_virtual_column() {
+    let values: Vec<FE> = [0, 3, 0, 0, 7, 1, 0, 2].into_iter().map(FE::from).collect();
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 4,
+            fri_number_of_queries: 3,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 8,
+        trace_columns: 3,
+        transition_degrees: vec![3, 2],
+        transition_exemptions: vec![0, 1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = zero_count::ZeroCountAIR::new(context);
+    let output = prove(&values, &air, &FE::zero()).unwrap();
+    assert_eq!(output.public_values, FE::from(4));
+    assert!(verify(&output.proof, &air, &output.public_values).is_ok());
+    assert!(verify(&output.proof, &air, &FE::from(3)).is_err());
+}
+
+#[test_log::test]
+fn test_prove_fibonacci_as_a_chain_of_segments() {
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = fibonacci_segment::FibonacciSegmentAIR::new(options, 8);
+    let segments = fibonacci_segment::fibonacci_segments([FE::one(), FE::one()], 3, 8);
+
+    let outputs = prove_chain(&segments, &air).unwrap();
+    let mut chain: Vec<_> = outputs
+        .into_iter()
+        .map(|output| (output.proof, output.public_values))
+        .collect();
+    assert!(verify_chain(&chain, &air).is_ok());
+
+    // Each proof is valid, but the segments aren't in order.
+    chain.swap(1, 2);
+    assert_eq!(
+        verify_chain(&chain, &air),
+        Err(VerificationError::BrokenChain(1))
+    );
+    chain.swap(1, 2);
+
+    // A segment whose statement doesn't start where the previous one ends.
+    chain[1].1.first[0] = FE::zero();
+    assert!(verify_chain(&chain, &air).is_err());
+
+    let mut unlinked = segments;
+    unlinked.swap(0, 1);
+    assert!(matches!(
+        prove_chain(&unlinked, &air),
+        Err(ProvingError::WrongParameter(_))
+    ));
+}
+
+#[test_log::test]
+fn test_chain_of_an_air_without_state_is_rejected() {
+    let air = simple_fibonacci::FibonacciAIR::from(AirContext::new(
+        ProofOptions::new(2, 3, 3),
+        8,
+        1,
+        vec![1],
+        vec![0, 1, 2],
+        vec![2],
+    ));
+    let trace = simple_fibonacci::fibonacci_trace([FE::one(), FE::one()], 8);
+    let chain: Vec<_> = (0..2)
+        .map(|_| (prove(&trace, &air, &()).unwrap().proof, ()))
+        .collect();
+
+    assert!(matches!(
+        prove_chain(&[(trace.clone(), ()), (trace, ())], &air),
+        Err(ProvingError::WrongParameter(_))
+    ));
+    assert!(matches!(
+        verify_chain(&chain, &air),
+        Err(VerificationError::InvalidParameters(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_air_described_with_the_dsl() {
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next() - a - b, b.next() - a.next() - b];
+        boundaries: [a[0] = 1, b[0] = 1];
+    };
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 16);
+    let trace = fibonacci_2_columns::fibonacci_trace_2_columns([FE::from(1), FE::from(1)], 16);
+
+    let output = prove(&trace, &air, &()).unwrap();
+    assert!(verify(&output.proof, &air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_changing_any_cell_of_a_fibonacci_trace_is_caught() {
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next() - a - b, b.next() - a.next() - b];
+        boundaries: [a[0] = 1, b[0] = 1];
+    };
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 8);
+    let trace = fibonacci_2_columns::fibonacci_trace_2_columns([FE::from(1), FE::from(1)], 8);
+
+    assert_air_sound(&air, &trace, &());
+}
+
+#[test_log::test]
+fn test_unconstrained_cells_of_an_air_are_found() {
+    // `b` is only read through `a.next()`, so its last value is free.
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next() - a - b];
+        boundaries: [a[0] = 1, b[0] = 1];
+    };
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 8);
+    let trace = fibonacci_2_columns::fibonacci_trace_2_columns([FE::from(1), FE::from(1)], 8);
+
+    let cells = (0..8).flat_map(|row| [(row, 0), (row, 1)]);
+    assert_eq!(
+        find_unconstrained_cells(&air, &trace, &(), cells),
+        vec![(7, 1)]
+    );
+}
+
+#[test_log::test]
+fn test_prove_dsl_air_with_a_cyclic_transition() {
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    // `b` is `a` shifted by a row, its first value being the last one of `a`.
+    let cyclic: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next() - a];
+        cyclic: [b.next() - a];
+        boundaries: [a[0] = 3];
+    };
+    let air = cyclic.build(options.clone(), 16);
+    let trace = vec![vec![FE::from(3); 16], vec![FE::from(3); 16]];
+    let output = prove(&trace, &air, &()).unwrap();
+    assert!(verify(&output.proof, &air, &()).is_ok());
+
+    // Only the first row of `b` is wrong, which the last row checks.
+    let mut b = vec![FE::from(3); 16];
+    b[0] = FE::zero();
+    let trace = vec![vec![FE::from(3); 16], b];
+    let output = prove(&trace, &air, &()).unwrap();
+    assert!(verify(&output.proof, &air, &()).is_err());
+
+    let acyclic: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next() - a, b.next() - a];
+        boundaries: [a[0] = 3];
+    };
+    let air = acyclic.build(options, 16);
+    let output = prove(&trace, &air, &()).unwrap();
+    assert!(verify(&output.proof, &air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_dsl_air_with_a_periodic_column() {
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [sum];
+        periodic: [k = vec![FE::from(1), FE::from(2), FE::from(3), FE::from(4)]];
+        transitions: [sum.next() - sum - k];
+        boundaries: [sum[0] = 0];
+    };
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 16);
+    let mut sum = vec![FE::zero()];
+    for step in 0..15u64 {
+        sum.push(&sum[step as usize] + FE::from(1 + step % 4));
+    }
+
+    let output = prove(&vec![sum], &air, &()).unwrap();
+    assert!(verify(&output.proof, &air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_air_with_a_composition_polynomial_split_in_four_parts() {
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a];
+        transitions: [a.next() - a * a * a * a];
+        boundaries: [a[0] = 2];
+    };
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 16);
+    let mut column = vec![FE::from(2)];
+    for _ in 1..16 {
+        let last = column.last().unwrap();
+        column.push(last.square().square());
+    }
+
+    let output = prove(&vec![column], &air, &()).unwrap();
+    assert_eq!(air.composition_poly_parts(), 4);
+    assert_eq!(output.proof.composition_poly_roots.len(), 4);
+    assert!(verify(&output.proof, &air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_air_whose_columns_are_read_at_different_offsets() {
+    let builder: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [a.next().next() - a.next() - a, b - a * a];
+        boundaries: [a[0] = 1, a[1] = 1];
+    };
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = builder.build(options, 16);
+    let mut a = vec![FE::one(), FE::one()];
+    for step in 2..16 {
+        a.push(&a[step - 1] + &a[step - 2]);
+    }
+    let b = a.iter().map(|value| value.square()).collect();
+
+    // `a` is read at the three rows of the frame, `b` only at the first one.
+    let output = prove(&vec![a, b], &air, &()).unwrap();
+    assert_eq!(output.proof.trace_ood_evaluations.len(), 4);
+    assert!(verify(&output.proof, &air, &()).is_ok());
+
+    let mut proof = output.proof;
+    proof.trace_ood_evaluations.extend([FE::zero(), FE::zero()]);
+    assert!(verify(&proof, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_prove_composition_of_airs() {
+    let trace_length = 16;
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+
+    let fibonacci = simple_fibonacci::FibonacciAIR::from(AirContext {
+        options: options.clone(),
+        trace_length,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    });
+    let fibonacci_trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 16);
+
+    let zero_count = zero_count::ZeroCountAIR::new(AirContext {
+        options: options.clone(),
+        trace_length,
+        trace_columns: 3,
+        transition_degrees: vec![3, 2],
+        transition_exemptions: vec![0, 1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    });
+    let values: Vec<FE> = (0..16u64).map(|i| FE::from(i % 3)).collect();
+
+    let fibonacci_rap = FibonacciRAP::new(AirContext {
+        options: options.clone(),
+        trace_length,
+        trace_columns: 3,
+        transition_degrees: vec![1, 2],
+        transition_exemptions: vec![3, 1],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    });
+    // The trace has an extra row after the sequence.
+    let rap_trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], trace_length - 1);
+
+    let air = AirComposition::new(
+        options,
+        vec![
+            DynAir::new(fibonacci),
+            DynAir::new(zero_count),
+            DynAir::new(fibonacci_rap),
+        ],
+    )
+    .unwrap();
+    assert_eq!(air.context().trace_columns, 7);
+    assert_eq!(air.columns(2), &[4, 5, 6]);
+
+    let raw_trace: Vec<DynRawTrace> = vec![
+        Box::new(fibonacci_trace),
+        Box::new(values),
+        Box::new(rap_trace),
+    ];
+    let public_input: Vec<DynPublicInput> = vec![Arc::new(()), Arc::new(FE::zero()), Arc::new(())];
+
+    let output = prove(&raw_trace, &air, &public_input).unwrap();
+    let zeros = output.public_values[1].downcast_ref::<FE>().unwrap();
+    assert_eq!(zeros, &FE::from(5));
+    assert!(verify(&output.proof, &air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_composition_with_links_between_airs() {
+    let trace_length = 16;
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+
+    // Requests of values `b = 2a`, looked up in the table of the second AIR,
+    // whose shuffled column is a permutation of `a`.
+    let requests: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [a, b];
+        transitions: [b - a - a];
+        boundaries: [];
+    };
+    let table: AirBuilder<Stark252PrimeField> = lambdaworks_stark::air! {
+        columns: [value, multiplicity, shuffled];
+        transitions: [value.next() - value - Expr::constant(FE::one())];
+        boundaries: [value[0] = 0];
+    };
+
+    let a: Vec<FE> = (0..16u64).map(|i| FE::from(i % 8)).collect();
+    let b: Vec<FE> = a.iter().map(|a| a + a).collect();
+    let value: Vec<FE> = (0..16u64).map(FE::from).collect();
+    let multiplicity = multiplicities(&b, &value).unwrap();
+    let shuffled: Vec<FE> = a.iter().rev().cloned().collect();
+
+    let air = AirComposition::with_links(
+        options.clone(),
+        vec![
+            DynAir::new(requests.build(options.clone(), trace_length)),
+            DynAir::new(table.build(options, trace_length)),
+        ],
+        vec![
+            Link::Lookup {
+                values: AirColumn::new(0, 1),
+                table: AirColumn::new(1, 0),
+                multiplicities: AirColumn::new(1, 1),
+            },
+            Link::Permutation {
+                left: vec![AirColumn::new(0, 0)],
+                right: vec![AirColumn::new(1, 2)],
+            },
+        ],
+    )
+    .unwrap();
+    assert_eq!(air.context().trace_columns, 7);
+
+    let raw_trace: Vec<DynRawTrace> = vec![
+        Box::new(vec![a, b]),
+        Box::new(vec![value, multiplicity, shuffled]),
+    ];
+    let public_input: Vec<DynPublicInput> = vec![Arc::new(()), Arc::new(())];
+
+    let output = prove(&raw_trace, &air, &public_input).unwrap();
+    assert!(verify(&output.proof, &air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_composition_rejects_airs_with_preprocessed_columns_or_segments() {
+    let options = ProofOptions::new(4, 3, 3);
+    let fibonacci = || {
+        DynAir::new(simple_fibonacci::FibonacciAIR::from(AirContext::new(
+            options.clone(),
+            16,
+            1,
+            vec![1],
+            vec![0, 1, 2],
+            vec![2],
+        )))
+    };
+    let constants: Vec<FE> = (0..16u64).map(FE::from).collect();
+    let preprocessed = preprocessed_sum::PreprocessedSumAIR::new(options.clone(), constants);
+    let segments = segment_sum::SegmentSumAIR::new(options.clone(), 16, 4).unwrap();
+
+    for air in [DynAir::new(preprocessed), DynAir::new(segments)] {
+        assert!(matches!(
+            AirComposition::new(options.clone(), vec![fibonacci(), air]),
+            Err(ProvingError::WrongParameter(_))
+        ));
+    }
+}
+
+#[test_log::test]
+fn test_prove_air_with_columns_set_by_the_public_input() {
+    let options = ProofOptions {
+        blowup_factor: 2,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let air = parallel_fibonacci::ParallelFibonacciAIR::new(options, 16);
+    let sequence = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 16).remove(0);
+    let trace = vec![sequence; 3];
+
+    let output = prove(&trace, &air, &0).unwrap();
+    assert_eq!(output.public_values, 3);
+    assert!(verify(&output.proof, &air, &3).is_ok());
+    assert!(verify(&output.proof, &air, &2).is_err());
+    assert!(matches!(
+        verify(&output.proof, &air, &0),
+        Err(VerificationError::InvalidParameters(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_fails_with_trace_length_not_matching_the_context() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 16,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let result = prove(&trace, &fibonacci_air, &());
+    assert!(matches!(result, Err(ProvingError::InvalidTraceLength(8))));
+}
+
+#[test_log::test]
+fn test_prover_builder_proves_several_traces() {
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 8,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+    let options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 2,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+
+    let mut prover = Prover::new(simple_fibonacci::FibonacciAIR::from(context))
+        .with_options(options)
+        .unwrap();
+
+    for _ in 0..2 {
+        let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+        let result = prover.prove(&trace, &()).unwrap().proof;
+        assert!(verify(&result, prover.air(), &()).is_ok());
+    }
+}
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+#[test_log::test]
+fn test_two_phase_prove_matches_prove() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace[0].len(),
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let (commitments, state) = commit(&trace, &fibonacci_air, &()).unwrap();
+    let result = finish(state, &fibonacci_air, &mut DefaultTranscript::new())
+        .unwrap()
+        .proof;
+
+    assert_eq!(
+        commitments.lde_trace_merkle_roots,
+        result.lde_trace_merkle_roots
+    );
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
+}
+
+#[cfg(not(feature = "test_fiat_shamir"))]
+#[test_log::test]
+fn test_prove_with_transcript_binds_the_proof_to_the_transcript_state() {
+    let trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace[0].len(),
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+
+    let mut prover_transcript = DefaultTranscript::new();
+    prover_transcript.append(b"outer statement");
+    let result = prove_with_transcript(&trace, &fibonacci_air, &(), &mut prover_transcript)
+        .unwrap()
+        .proof;
+
+    let mut verifier_transcript = DefaultTranscript::new();
+    verifier_transcript.append(b"outer statement");
+    assert!(verify_with_transcript(&result, &fibonacci_air, &(), &mut verifier_transcript).is_ok());
+
+    let mut other_transcript = DefaultTranscript::new();
+    other_transcript.append(b"another statement");
+    assert!(verify_with_transcript(&result, &fibonacci_air, &(), &mut other_transcript).is_err());
+}
+
+#[test_log::test]
+fn test_prove_fib17() {
+    let trace = simple_fibonacci::fibonacci_trace([FE17::from(1), FE17::from(1)], 4);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace[0].len(),
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = fibonacci_f17::Fibonacci17AIR::from(context.clone());
+
+    let result = prove(&trace, &fibonacci_air, &()).unwrap().proof;
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
+
+    // F17 has no LDE domain of 2^5 points, the verifier rejects the AIR
+    // instead of building its domain.
+    let too_long = fibonacci_f17::Fibonacci17AIR::from(AirContext {
+        trace_length: 16,
+        ..context
+    });
+    assert!(matches!(
+        verify(&result, &too_long, &()),
+        Err(VerificationError::InvalidParameters(_))
+    ));
+}
+
+#[test_log::test]
+fn test_prove_fib_2_cols() {
+    let trace_columns =
+        fibonacci_2_columns::fibonacci_trace_2_columns([FE::from(1), FE::from(1)], 16);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 7,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace_columns[0].len(),
+        transition_degrees: vec![1, 1],
+        transition_exemptions: vec![1, 1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 2,
+        trace_columns: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_air = fibonacci_2_columns::Fibonacci2ColsAIR::from(context);
+
+    let result = prove(&trace_columns, &fibonacci_air, &()).unwrap().proof;
+    assert!(verify(&result, &fibonacci_air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_quadratic() {
+    let trace = quadratic_air::quadratic_trace(FE::from(3), 4);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace.len(),
+        trace_columns: 1,
+        transition_degrees: vec![2],
+        transition_exemptions: vec![1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let quadratic_air = quadratic_air::QuadraticAIR::from(context);
+
+    let result = prove(&trace, &quadratic_air, &()).unwrap().proof;
+    assert!(verify(&result, &quadratic_air, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_degree_check_reports_the_constraint_with_a_wrong_degree() {
+    let trace = quadratic_air::quadratic_trace(FE::from(3), 16);
+    let mut context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace.len(),
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let mut prover =
+        Prover::new(quadratic_air::QuadraticAIR::from(context.clone())).with_degree_check();
+    assert!(matches!(
+        prover.prove(&trace, &()),
+        Err(ProvingError::TransitionDegree {
+            constraint: 0,
+            label: None,
+            declared: 1,
+            found: 2
+        })
+    ));
+
+    context.transition_degrees = vec![2];
+    let mut prover = Prover::new(quadratic_air::QuadraticAIR::from(context)).with_degree_check();
+    assert!(prover.prove(&trace, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_rejects_a_wrong_number_of_transition_evaluations() {
+    let trace = quadratic_air::quadratic_trace(FE::from(3), 16);
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: trace.len(),
+        trace_columns: 1,
+        transition_degrees: vec![2, 2],
+        transition_exemptions: vec![1, 1],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = quadratic_air::QuadraticAIR::from(context);
+    assert!(matches!(
+        prove(&trace, &air, &()),
+        Err(ProvingError::TransitionCount {
+            declared: 2,
+            found: 1
+        })
+    ));
+}
+
+#[test_log::test]
+fn test_validate_trace_reports_the_failing_constraint_and_row() {
+    let mut trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 8,
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: vec!["fib".to_string()],
+        constraint_labels: vec!["fibonacci step".to_string()],
+    };
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+    let domain = lambdaworks_stark::Domain::new(&fibonacci_air);
+    let validate = |trace: &Vec<Vec<FE>>| {
+        let (main_trace, _) = fibonacci_air.build_main_trace(trace, &()).unwrap();
+        validate_trace(
+            &fibonacci_air,
+            &main_trace.compute_trace_polys().unwrap(),
+            &domain,
+            &(),
+            &(),
+        )
+    };
+    assert!(validate(&trace).is_valid());
+
+    // Row 4 is read by the constraint on rows 2, 3 and 4.
+    trace[0][4] = &trace[0][4] + FE::one();
+    let report = validate(&trace);
+    assert!(report.boundary.is_empty());
+    assert_eq!(report.first_failing_row(), Some(2));
+    let rows: Vec<_> = report.transition.iter().map(|v| v.row).collect();
+    assert_eq!(rows, vec![2, 3, 4]);
+    let violation = &report.transition[0];
+    assert_eq!(violation.label.as_deref(), Some("fibonacci step"));
+    assert_eq!(violation.frame[2], vec![trace[0][4].clone()]);
+    assert_eq!(violation.residue, FE::one());
+
+    trace[0][0] = FE::from(2);
+    let report = validate(&trace);
+    assert_eq!(report.boundary.len(), 1);
+    let violation = &report.boundary[0];
+    assert_eq!((violation.column, violation.row), (0, 0));
+    assert_eq!(violation.column_name.as_deref(), Some("fib"));
+    assert_eq!(
+        (&violation.expected, &violation.actual),
+        (&FE::one(), &FE::from(2))
+    );
+}
+
+#[test_log::test]
+fn test_prove_with_trace_validation_rejects_an_invalid_trace() {
+    let mut trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    trace[0][5] = FE::zero();
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: true,
+        },
+        trace_length: 8,
         trace_columns: 1,
-        transition_degrees: vec![2],
-        transition_exemptions: vec![1],
-        transition_offsets: vec![0, 1],
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
         num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
-    let quadratic_air = quadratic_air::QuadraticAIR::from(context);
-
-    let result = prove(&trace, &quadratic_air, &mut ()).unwrap();
-    assert!(verify(&result, &quadratic_air, &()));
+    let fibonacci_air = simple_fibonacci::FibonacciAIR::from(context);
+    match prove(&trace, &fibonacci_air, &()) {
+        Err(ProvingError::InvalidTrace(message)) => {
+            assert!(message.starts_with("transition constraint 0 at row 3"))
+        }
+        _ => panic!("the invalid trace isn't rejected"),
+    }
 }
 
 #[ignore = "metal"]
@@ -142,9 +1525,10 @@ fn test_prove_cairo_program(file_path: &str) {
         blowup_factor: 4,
         fri_number_of_queries: 3,
         coset_offset: 3,
+        validate_trace: false,
     };
 
-    let mut pub_inputs = PublicInputs::from_regs_and_mem(&register_states, &memory, program_size);
+    let pub_inputs = PublicInputs::from_regs_and_mem(&register_states, &memory, program_size);
 
     // This should be auto calculated
     // First pad is needed by the prover to validate the program bytecode
@@ -152,11 +1536,11 @@ fn test_prove_cairo_program(file_path: &str) {
     // Then we need to pad it to next power of two
     let padded_trace_length = (register_states.steps() + first_pad).next_power_of_two();
 
-    let cairo_air = CairoAIR::new(proof_options, padded_trace_length, register_states.steps());
+    let cairo_air = CairoAIR::new(proof_options, padded_trace_length);
 
-    let result = prove(&(register_states, memory), &cairo_air, &mut pub_inputs).unwrap();
+    let output = prove(&(register_states, memory), &cairo_air, &pub_inputs).unwrap();
 
-    assert!(verify(&result, &cairo_air, &pub_inputs));
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
 }
 
 fn program_path(program_name: &str) -> String {
@@ -190,6 +1574,70 @@ fn test_prove_rap_fib() {
             blowup_factor: 2,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_columns: 3,
+        trace_length: trace_cols[0].len(),
+        transition_degrees: vec![1, 2],
+        transition_offsets: vec![0, 1, 2],
+        transition_exemptions: vec![exemptions, 1],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let fibonacci_rap = FibonacciRAP::new(context);
+
+    let result = prove(&trace_cols, &fibonacci_rap, &()).unwrap().proof;
+    assert!(verify(&result, &fibonacci_rap, &()).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_multi_round_permutation() {
+    let mut trace = multi_round_permutation::reversed_pairs_trace(8);
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 3,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: 8,
+        trace_columns: 7,
+        transition_degrees: vec![1, 1, 2],
+        transition_exemptions: vec![0, 0, 0],
+        transition_offsets: vec![0, 1],
+        num_transition_constraints: 3,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let air = multi_round_permutation::MultiRoundPermutationAIR::new(context);
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_ok());
+
+    // Swapping two values of y breaks the pairs, but not the values of each column.
+    trace[3].swap(1, 2);
+    let proof = prove(&trace, &air, &()).unwrap().proof;
+    assert!(verify(&proof, &air, &()).is_err());
+}
+
+#[test_log::test]
+fn test_resumed_proof_from_checkpoint_verifies() {
+    let trace_length = 16;
+    let trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], trace_length);
+    let mut trace_cols = vec![trace[0].clone(), trace[1].clone()];
+    resize_to_next_power_of_two(&mut trace_cols);
+    let power_of_two_len = trace_cols[0].len();
+    let exemptions = 3 + power_of_two_len - trace_length - 1;
+
+    let context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
         },
         trace_columns: 3,
         trace_length: trace_cols[0].len(),
@@ -197,12 +1645,104 @@ fn test_prove_rap_fib() {
         transition_offsets: vec![0, 1, 2],
         transition_exemptions: vec![exemptions, 1],
         num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
     let fibonacci_rap = FibonacciRAP::new(context);
+    let checkpoint = std::env::temp_dir().join(format!(
+        "lambdaworks-stark-resume-{}.checkpoint",
+        std::process::id()
+    ));
+
+    let result = prove_with_checkpoints(&trace_cols, &fibonacci_rap, &(), &checkpoint)
+        .unwrap()
+        .proof;
+    assert!(verify(&result, &fibonacci_rap, &()).is_ok());
+
+    let resumed = resume_proof(&trace_cols, &fibonacci_rap, &(), &checkpoint);
+
+    // The digest of the public input and the main trace roots follows the
+    // magic, the version and the five parameters of the header.
+    let mut bytes = std::fs::read(&checkpoint).unwrap();
+    bytes[8 + 4 + 5 * 8] ^= 1;
+    std::fs::write(&checkpoint, bytes).unwrap();
+    let corrupted = resume_proof(&trace_cols, &fibonacci_rap, &(), &checkpoint);
+    std::fs::remove_file(&checkpoint).unwrap();
+    assert!(matches!(corrupted, Err(ProvingError::Checkpoint(_))));
+
+    let resumed = resumed.unwrap().proof;
+    assert!(verify(&resumed, &fibonacci_rap, &()).is_ok());
+    assert_eq!(
+        resumed.composition_poly_roots,
+        result.composition_poly_roots
+    );
+}
+
+#[test_log::test]
+fn test_prove_air_selected_at_runtime() {
+    let fibonacci_trace = simple_fibonacci::fibonacci_trace([FE::from(1), FE::from(1)], 8);
+    let fibonacci_context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_length: fibonacci_trace[0].len(),
+        trace_columns: 1,
+        transition_degrees: vec![1],
+        transition_exemptions: vec![2],
+        transition_offsets: vec![0, 1, 2],
+        num_transition_constraints: 1,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
+
+    let rap_trace = fibonacci_rap_trace([FE::from(1), FE::from(1)], 16);
+    let mut rap_trace_cols = vec![rap_trace[0].clone(), rap_trace[1].clone()];
+    resize_to_next_power_of_two(&mut rap_trace_cols);
+    let exemptions = 3 + rap_trace_cols[0].len() - 16 - 1;
+    let rap_context = AirContext {
+        options: ProofOptions {
+            blowup_factor: 2,
+            fri_number_of_queries: 1,
+            coset_offset: 3,
+            validate_trace: false,
+        },
+        trace_columns: 3,
+        trace_length: rap_trace_cols[0].len(),
+        transition_degrees: vec![1, 2],
+        transition_offsets: vec![0, 1, 2],
+        transition_exemptions: vec![exemptions, 1],
+        num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
+    };
 
-    let result = prove(&trace_cols, &fibonacci_rap, &mut ()).unwrap();
-    assert!(verify(&result, &fibonacci_rap, &()));
+    let registry: Vec<(DynAir<Stark252PrimeField>, DynRawTrace)> = vec![
+        (
+            DynAir::new(simple_fibonacci::FibonacciAIR::from(fibonacci_context)),
+            Box::new(fibonacci_trace),
+        ),
+        (
+            DynAir::new(FibonacciRAP::new(rap_context)),
+            Box::new(rap_trace_cols),
+        ),
+    ];
+
+    let public_input: DynPublicInput = Arc::new(());
+    for (air, raw_trace) in &registry {
+        let result = prove(raw_trace, air, &public_input).unwrap().proof;
+        assert!(verify(&result, air, &public_input).is_ok());
+    }
+
+    let (air, _) = &registry[0];
+    let wrong_trace: DynRawTrace = Box::new(0u64);
+    assert!(matches!(
+        prove(&wrong_trace, air, &public_input),
+        Err(ProvingError::WrongParameter(_))
+    ));
 }
 
 #[test_log::test]
@@ -215,6 +1755,7 @@ fn test_prove_dummy() {
             blowup_factor: 2,
             fri_number_of_queries: 1,
             coset_offset: 3,
+            validate_trace: false,
         },
         trace_length,
         trace_columns: 2,
@@ -222,12 +1763,14 @@ fn test_prove_dummy() {
         transition_exemptions: vec![0, 2],
         transition_offsets: vec![0, 1, 2],
         num_transition_constraints: 2,
+        column_names: Vec::new(),
+        constraint_labels: Vec::new(),
     };
 
     let dummy_air = dummy_air::DummyAIR::from(context);
 
-    let result = prove(&trace, &dummy_air, &mut ()).unwrap();
-    assert!(verify(&result, &dummy_air, &()));
+    let result = prove(&trace, &dummy_air, &()).unwrap().proof;
+    assert!(verify(&result, &dummy_air, &()).is_ok());
 }
 
 #[test_log::test]
@@ -240,6 +1783,7 @@ fn test_verifier_rejects_proof_of_a_slightly_different_program() {
         blowup_factor: 4,
         fri_number_of_queries: 1,
         coset_offset: 3,
+        validate_trace: false,
     };
 
     let program_size = 5;
@@ -252,33 +1796,48 @@ fn test_verifier_rejects_proof_of_a_slightly_different_program() {
     program_2[1] = FieldElement::from(5);
     program_2[3] = FieldElement::from(5);
 
-    let cairo_air = CairoAIR::new(proof_options, 16, program_1_raw_trace.steps());
+    let cairo_air = CairoAIR::new(proof_options, 16);
 
     let first_step = &program_1_raw_trace.rows[0];
     let last_step = &program_1_raw_trace.rows[program_1_raw_trace.steps() - 1];
 
-    let mut public_input = PublicInputs {
+    let public_input = PublicInputs {
         pc_init: FE::from(first_step.pc),
         ap_init: FE::from(first_step.ap),
         fp_init: FE::from(first_step.fp),
         pc_final: FE::from(last_step.pc),
         ap_final: FE::from(last_step.ap),
+        fp_final: FE::from(last_step.fp),
         program: program_1,
         range_check_min: None,
         range_check_max: None,
+        memory_holes: None,
+        program_segment: None,
+        execution_segment: None,
+        range_check_builtin: None,
+        range_check96_builtin: None,
+        pedersen_builtin: None,
+        poseidon_builtin: None,
+        keccak_builtin: None,
+        ec_op_builtin: None,
+        output_builtin: None,
+        builtin_ratios: None,
+        public_memory: Vec::new(),
         num_steps: program_1_raw_trace.steps(),
     };
 
-    let result = prove(
+    let output = prove(
         &(program_1_raw_trace, program_1_memory),
         &cairo_air,
-        &mut public_input,
+        &public_input,
     )
     .unwrap();
+    let result = output.proof;
+    let mut public_input = output.public_values;
 
     // Here we change program 1 to program 2 in the public inputs.
     public_input.program = program_2;
-    assert!(!verify(&result, &cairo_air, &public_input));
+    assert!(verify(&result, &cairo_air, &public_input).is_err());
 }
 
 #[test_log::test]
@@ -291,6 +1850,7 @@ fn test_verifier_rejects_proof_with_different_range_bounds() {
         blowup_factor: 4,
         fri_number_of_queries: 1,
         coset_offset: 3,
+        validate_trace: false,
     };
 
     let program_size = 5;
@@ -299,29 +1859,646 @@ fn test_verifier_rejects_proof_with_different_range_bounds() {
         program.push(memory.get(&i).unwrap().clone());
     }
 
-    let cairo_air = CairoAIR::new(proof_options, 16, raw_trace.steps());
+    let cairo_air = CairoAIR::new(proof_options, 16);
 
     let first_step = &raw_trace.rows[0];
     let last_step = &raw_trace.rows[raw_trace.steps() - 1];
 
-    let mut public_input = PublicInputs {
+    let public_input = PublicInputs {
         pc_init: FE::from(first_step.pc),
         ap_init: FE::from(first_step.ap),
         fp_init: FE::from(first_step.fp),
         pc_final: FE::from(last_step.pc),
         ap_final: FE::from(last_step.ap),
+        fp_final: FE::from(last_step.fp),
         program,
         range_check_min: None,
         range_check_max: None,
+        memory_holes: None,
+        program_segment: None,
+        execution_segment: None,
+        range_check_builtin: None,
+        range_check96_builtin: None,
+        pedersen_builtin: None,
+        poseidon_builtin: None,
+        keccak_builtin: None,
+        ec_op_builtin: None,
+        output_builtin: None,
+        builtin_ratios: None,
+        public_memory: Vec::new(),
         num_steps: raw_trace.steps(),
     };
 
-    let result = prove(&(raw_trace, memory), &cairo_air, &mut public_input).unwrap();
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+    let result = output.proof;
+    let mut public_input = output.public_values;
 
     public_input.range_check_min = Some(public_input.range_check_min.unwrap() + 1);
-    assert!(!verify(&result, &cairo_air, &public_input));
+    assert!(verify(&result, &cairo_air, &public_input).is_err());
 
     public_input.range_check_min = Some(public_input.range_check_min.unwrap() - 1);
     public_input.range_check_max = Some(public_input.range_check_max.unwrap() - 1);
-    assert!(!verify(&result, &cairo_air, &public_input));
+    assert!(verify(&result, &cairo_air, &public_input).is_err());
+
+    // Bounds that are missing or swapped are rejected before the proof is
+    // read.
+    public_input.range_check_max = Some(public_input.range_check_max.unwrap() + 1);
+    assert!(verify(&result, &cairo_air, &public_input).is_ok());
+    let (min, max) = (public_input.range_check_min, public_input.range_check_max);
+    public_input.range_check_min = max;
+    public_input.range_check_max = min;
+    assert!(matches!(
+        verify(&result, &cairo_air, &public_input),
+        Err(VerificationError::InvalidParameters(_))
+    ));
+    public_input.range_check_min = None;
+    assert!(verify(&result, &cairo_air, &public_input).is_err());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_proof_with_a_different_number_of_steps() {
+    // The final pc and ap are checked at the last step given by the public
+    // input, which has to be the one of the execution and inside the trace.
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+
+    let proof_options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 1,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let public_input = PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5);
+    let cairo_air = CairoAIR::new(proof_options, 16);
+
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+    let result = output.proof;
+    let mut public_input = output.public_values;
+    assert!(verify(&result, &cairo_air, &public_input).is_ok());
+
+    public_input.num_steps -= 1;
+    assert!(verify(&result, &cairo_air, &public_input).is_err());
+
+    public_input.num_steps = 17;
+    assert!(matches!(
+        verify(&result, &cairo_air, &public_input),
+        Err(VerificationError::InvalidParameters(_))
+    ));
+}
+
+#[test_log::test]
+fn test_selector_of_the_cairo_steps_is_constrained() {
+    // Turning the selector off at a step would disable the constraints of
+    // the instruction it executes.
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+
+    let proof_options = ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    };
+    let public_input = PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5);
+    let cairo_air = CairoAIR::new(proof_options, 16);
+
+    let cells = (0..16).map(|row| (row, FRAME_SELECTOR));
+    let unconstrained =
+        find_unconstrained_cells(&cairo_air, &(raw_trace, memory), &public_input, cells);
+    assert!(unconstrained.is_empty());
+}
+
+/// Execution of `simple_program` with the cells `values` of a builtin
+/// segment right after its memory, and the public input of the execution
+/// without builtins.
+fn simple_program_with_builtin_cells(
+    values: Vec<FE>,
+) -> ((CairoTrace, CairoMemory), PublicInputs, MemorySegment) {
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+
+    let mut cells: HashMap<u64, FE> = (1..=memory.len() as u64)
+        .map(|address| (address, memory.get(&address).unwrap().clone()))
+        .collect();
+    let segment = MemorySegment {
+        begin_addr: memory.len() as u64 + 1,
+        stop_ptr: (memory.len() + values.len()) as u64 + 1,
+    };
+    for (address, value) in (segment.begin_addr..).zip(values) {
+        cells.insert(address, value);
+    }
+    let memory = CairoMemory::new(cells);
+
+    let public_input = PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5);
+    ((raw_trace, memory), public_input, segment)
+}
+
+fn builtin_proof_options() -> ProofOptions {
+    ProofOptions {
+        blowup_factor: 4,
+        fri_number_of_queries: 3,
+        coset_offset: 3,
+        validate_trace: false,
+    }
+}
+
+/// Execution of `simple_program` with a builtin segment holding `cells`
+/// right after its memory, which `segment` sets in the public input.
+fn simple_program_with_builtin(
+    cells: Vec<FE>,
+    segment: fn(&mut PublicInputs) -> &mut Option<MemorySegment>,
+) -> (CairoAIR, (CairoTrace, CairoMemory), PublicInputs) {
+    let (raw_trace, mut public_input, builtin_segment) = simple_program_with_builtin_cells(cells);
+    *segment(&mut public_input) = Some(builtin_segment);
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+    (cairo_air, raw_trace, public_input)
+}
+
+/// Cells of a Pedersen instance hashing `a` and `b`.
+fn pedersen_instance(a: FE, b: FE) -> Vec<FE> {
+    let hash = pedersen::hash(&a, &b);
+    vec![a, b, hash]
+}
+
+/// Cells of a Poseidon instance permuting `input`.
+fn poseidon_instance(input: [FE; 3]) -> Vec<FE> {
+    let mut cells = input.to_vec();
+    cells.extend(cairo_poseidon::permutation(&input));
+    cells
+}
+
+/// Cells of a Keccak instance permuting `input`.
+fn keccak_instance(input: &[u64; cairo_keccak::LANES]) -> Vec<FE> {
+    let mut cells = cairo_keccak::cells_from_state(input).to_vec();
+    cells.extend(cairo_keccak::cells_from_state(&cairo_keccak::permutation(
+        input,
+    )));
+    cells
+}
+
+/// Cells of an EC op instance computing `p + m·q`.
+fn ec_op_instance(p: (FE, FE), q: (FE, FE), m: FE) -> Vec<FE> {
+    let r = cairo_ec_op::ec_op(&p, &q, &m).unwrap();
+    vec![p.0, p.1, q.0, q.1, m, r.0, r.1]
+}
+
+/// Proves the execution of `simple_program` with the cells of a range check
+/// builtin segment, holding `values`, right after its memory.
+fn prove_simple_program_with_range_checks(values: &[&str]) -> bool {
+    let cells = values
+        .iter()
+        .map(|value| FE::from_hex(value).unwrap())
+        .collect();
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(cells, |input| &mut input.range_check_builtin);
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    verify(&output.proof, &cairo_air, &output.public_values).is_ok()
+}
+
+#[test_log::test]
+fn test_prove_cairo_range_check_builtin() {
+    // The parts of the values are in the range of the offsets of the program.
+    assert!(prove_simple_program_with_range_checks(&[
+        "80008000800080008000800080008000",
+        "7fff80017fff80017fff80017fff8001",
+        "7ffe7ffe7ffe7ffe7ffe7ffe7ffe7ffe",
+    ]));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_range_checks_of_values_out_of_range() {
+    assert!(!prove_simple_program_with_range_checks(&[
+        "80008000800080008000800080008000",
+        "180008000800080008000800080008000",
+    ]));
+}
+
+/// Proves the execution of `simple_program` with a range check builtin
+/// segment holding `values` right after its memory, followed by a range check
+/// 96 builtin segment holding `values96`.
+fn prove_simple_program_with_range_checks96(values: &[&str], values96: &[&str]) -> bool {
+    let cells = values
+        .iter()
+        .chain(values96)
+        .map(|value| FE::from_hex(value).unwrap())
+        .collect();
+    let (raw_trace, public_input, segment) = simple_program_with_builtin_cells(cells);
+    let split = segment.begin_addr + values.len() as u64;
+    let public_input = PublicInputs {
+        range_check_builtin: Some(MemorySegment {
+            begin_addr: segment.begin_addr,
+            stop_ptr: split,
+        }),
+        range_check96_builtin: Some(MemorySegment {
+            begin_addr: split,
+            stop_ptr: segment.stop_ptr,
+        }),
+        ..public_input
+    };
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    verify(&output.proof, &cairo_air, &output.public_values).is_ok()
+}
+
+#[test_log::test]
+fn test_prove_cairo_range_check96_builtin() {
+    assert!(prove_simple_program_with_range_checks96(
+        &["80008000800080008000800080008000"],
+        &["800080008000800080008000", "7fff80017fff80017fff8001"],
+    ));
+}
+
+#[test_log::test]
+fn test_verifier_rejects_range_checks96_of_values_out_of_range() {
+    // The value is a valid range check, but it's 97 bits long.
+    assert!(!prove_simple_program_with_range_checks96(
+        &["80008000800080008000800080008000"],
+        &["1800080008000800080008000"],
+    ));
+}
+
+#[test_log::test]
+fn test_prove_cairo_pedersen_builtin() {
+    // The largest input has the bits 251, 196 and 192 set.
+    let (cairo_air, raw_trace, public_input) = simple_program_with_builtin(
+        pedersen_instance(
+            -FE::one(),
+            FE::from_hex("208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a")
+                .unwrap(),
+        ),
+        |input| &mut input.pedersen_builtin,
+    );
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_changes_of_the_pedersen_builtin() {
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(pedersen_instance(FE::from(1234), FE::from(5678)), |input| {
+            &mut input.pedersen_builtin
+        });
+
+    let cells = [
+        (0, PEDERSEN_SUFFIX),
+        (3, PEDERSEN_BIT),
+        (0, PEDERSEN_UNPACKING[0]),
+        (100, PEDERSEN_SELECTOR),
+        (300, PEDERSEN_BASE),
+        (PEDERSEN_ROWS - 1, PEDERSEN_X),
+        (PEDERSEN_ROWS - 1, PEDERSEN_VALUE),
+    ];
+    let unconstrained = find_unconstrained_cells(&cairo_air, &raw_trace, &public_input, cells);
+    assert!(unconstrained.is_empty());
+}
+
+#[test_log::test]
+fn test_prove_cairo_poseidon_builtin() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_builtin(
+        poseidon_instance([FE::from(1), -FE::one(), FE::from(2)]),
+        |input| &mut input.poseidon_builtin,
+    );
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_changes_of_the_poseidon_builtin() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_builtin(
+        poseidon_instance([FE::from(1), FE::from(2), FE::from(3)]),
+        |input| &mut input.poseidon_builtin,
+    );
+
+    let output_row = cairo_poseidon::ROUNDS;
+    let cells = [
+        (0, POSEIDON_STATE[0]),
+        (1, POSEIDON_CARRIES[0]),
+        (50, POSEIDON_STATE[1]),
+        (50, POSEIDON_CUBES[0]),
+        (10, POSEIDON_SELECTOR),
+        (100, POSEIDON_BASE),
+        (output_row, POSEIDON_VALUE),
+        (output_row + 2, POSEIDON_CARRIES[1]),
+    ];
+    let unconstrained = find_unconstrained_cells(&cairo_air, &raw_trace, &public_input, cells);
+    assert!(unconstrained.is_empty());
+}
+
+#[test_log::test]
+fn test_prove_cairo_keccak_builtin() {
+    let input = core::array::from_fn(|i| u64::MAX - i as u64);
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(keccak_instance(&input), |input| &mut input.keccak_builtin);
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_changes_of_the_keccak_builtin() {
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(keccak_instance(&[1; cairo_keccak::LANES]), |input| {
+            &mut input.keccak_builtin
+        });
+
+    let output_row = cairo_keccak::ROUNDS;
+    let cells = [
+        (0, KECCAK_CELLS[0]),
+        (2, KECCAK_CELLS[3]),
+        (10, KECCAK_SELECTOR),
+        (20, KECCAK_BASE),
+        (output_row + 1, KECCAK_VALUE),
+        (5, MAIN_COLUMNS + cairo_keccak::theta_column(1, 2, 3)),
+        (
+            output_row,
+            MAIN_COLUMNS + cairo_keccak::state_column(4, 4, 63),
+        ),
+    ];
+    let unconstrained = find_unconstrained_cells(&cairo_air, &raw_trace, &public_input, cells);
+    assert!(unconstrained.is_empty());
+}
+
+#[test_log::test]
+fn test_prove_cairo_ec_op_builtin() {
+    // The largest m has the bits 251, 196 and 192 set.
+    let (cairo_air, raw_trace, public_input) = simple_program_with_builtin(
+        ec_op_instance(
+            pedersen::shift_point(),
+            cairo_ec_op::generator(),
+            -FE::one(),
+        ),
+        |input| &mut input.ec_op_builtin,
+    );
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_changes_of_the_ec_op_builtin() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_builtin(
+        ec_op_instance(
+            pedersen::shift_point(),
+            cairo_ec_op::generator(),
+            FE::from(1234),
+        ),
+        |input| &mut input.ec_op_builtin,
+    );
+
+    // 1234 has its second bit set.
+    let output_row = cairo_ec_op::OUTPUT_ROW;
+    let cells = [
+        (0, EC_OP_CELLS[0]),
+        (3, EC_OP_CELLS[1]),
+        (1, EC_OP_BIT),
+        (1, EC_OP_SLOPE),
+        (1, EC_OP_X_DIFF_INV),
+        (20, EC_OP_Q_Y),
+        (100, EC_OP_SELECTOR),
+        (200, EC_OP_BASE),
+        (output_row, EC_OP_X),
+        (output_row + 1, EC_OP_VALUE),
+    ];
+    let unconstrained = find_unconstrained_cells(&cairo_air, &raw_trace, &public_input, cells);
+    assert!(unconstrained.is_empty());
+}
+
+#[test_log::test]
+fn test_prove_cairo_program_of_a_layout_with_unused_builtins() {
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+    // An empty segment is a builtin the execution doesn't use.
+    let begin_addr = memory.len() as u64 + 1;
+    let public_input = PublicInputs {
+        keccak_builtin: Some(MemorySegment {
+            begin_addr,
+            stop_ptr: begin_addr,
+        }),
+        ..PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5)
+    };
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input)
+        .unwrap()
+        .with_layout(Layout::StarknetWithKeccak);
+    assert!(!public_input.uses_builtin(Builtin::Keccak));
+    assert!(
+        !cairo_air
+            .with_public_input(&public_input)
+            .unwrap()
+            .keccak_builtin
+    );
+
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_layout_rejects_builtins_it_does_not_have() {
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(pedersen_instance(FE::from(1), FE::from(2)), |input| {
+            &mut input.pedersen_builtin
+        });
+    let plain_air = cairo_air.clone().with_layout(Layout::Plain);
+    assert!(prove(&raw_trace, &plain_air, &public_input).is_err());
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &plain_air, &output.public_values).is_err());
+}
+
+#[test_log::test]
+fn test_prove_cairo_program_of_a_dynamic_layout() {
+    let (cairo_air, raw_trace, public_input) =
+        simple_program_with_builtin(pedersen_instance(FE::from(1), FE::from(2)), |input| {
+            &mut input.pedersen_builtin
+        });
+    let dynamic_air = cairo_air.with_layout(Layout::Dynamic);
+    assert!(prove(&raw_trace, &dynamic_air, &public_input).is_err());
+
+    let public_input = PublicInputs {
+        builtin_ratios: Some(public_input.dynamic_ratios()),
+        ..public_input
+    };
+    let output = prove(&raw_trace, &dynamic_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &dynamic_air, &output.public_values).is_ok());
+
+    // The proof is bound to its ratios, even if others have room for the
+    // instance.
+    let mut public_values = output.public_values.clone();
+    public_values.builtin_ratios = Some(vec![(Builtin::Pedersen, 1)]);
+    assert!(verify(&output.proof, &dynamic_air, &public_values).is_err());
+    let too_large = public_input.num_steps + 1;
+    let public_input = PublicInputs {
+        builtin_ratios: Some(vec![(Builtin::Pedersen, too_large)]),
+        ..public_input
+    };
+    assert!(prove(&raw_trace, &dynamic_air, &public_input).is_err());
+}
+
+/// Execution of `simple_program` with its last `cells` memory cells in a
+/// public page.
+fn simple_program_with_public_page(
+    cells: usize,
+) -> (CairoAIR, (CairoTrace, CairoMemory), PublicInputs) {
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+    let begin = (memory.len() - cells) as u64 + 1;
+    let values = (begin..=memory.len() as u64)
+        .map(|address| memory.get(&address).unwrap().clone())
+        .collect();
+    let public_input = PublicInputs {
+        public_memory: vec![MemoryPage::new(FE::from(begin), values)],
+        ..PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5)
+    };
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+    (cairo_air, (raw_trace, memory), public_input)
+}
+
+#[test_log::test]
+fn test_prove_cairo_with_public_memory() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_public_page(3);
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+}
+
+#[test_log::test]
+fn test_prove_cairo_with_memory_holes() {
+    // A page two cells after the end of the memory leaves two addresses
+    // that nothing accesses.
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+    let begin = memory.len() as u64 + 3;
+    let public_input = PublicInputs {
+        public_memory: vec![MemoryPage::new(FE::from(begin), vec![FE::from(7)])],
+        memory_holes: Some(2),
+        ..PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5)
+    };
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+    assert_eq!(output.public_values.memory_holes, Some(2));
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+
+    let mut public_values = output.public_values;
+    public_values.memory_holes = Some(1);
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_segments_that_do_not_match_the_registers() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_public_page(3);
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    assert!(verify(&output.proof, &cairo_air, &output.public_values).is_ok());
+
+    // Moving the end of the execution moves the final ap, which the trace
+    // doesn't have.
+    let mut public_values = output.public_values.clone();
+    let execution = public_values.execution_segment.as_mut().unwrap();
+    execution.stop_ptr += 1;
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+    public_values.ap_final = &public_values.ap_final + FE::one();
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+
+    let mut public_values = output.public_values;
+    public_values.program_segment = None;
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+}
+
+#[test_log::test]
+fn test_verifier_rejects_changes_of_the_public_memory() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_public_page(3);
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+
+    let mut public_values = output.public_values.clone();
+    let value = &mut public_values.public_memory[0].values[1];
+    *value = &*value + FE::one();
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+
+    let mut public_values = output.public_values;
+    public_values.program[0] = &public_values.program[0] + FE::one();
+    assert!(verify(&output.proof, &cairo_air, &public_values).is_err());
+}
+
+#[test_log::test]
+fn test_verify_cairo_returns_the_output_of_the_program() {
+    // The output is after the execution segment, as the runner lays it out.
+    let values = vec![FE::from(10), FE::from(20), FE::from(30)];
+    let (raw_trace, public_input, segment) = simple_program_with_builtin_cells(values.clone());
+    let public_input = PublicInputs {
+        public_memory: vec![MemoryPage::new(
+            FE::from(segment.begin_addr),
+            values.clone(),
+        )],
+        output_builtin: Some(segment),
+        ..public_input
+    };
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    let public_output = verify_cairo(&output.proof, &cairo_air, &output.public_values).unwrap();
+    assert_eq!(public_output.values, values);
+
+    // The output segment has to be a public page.
+    let mut public_values = output.public_values;
+    public_values.public_memory.clear();
+    assert!(verify_cairo(&output.proof, &cairo_air, &public_values).is_err());
+}
+
+#[test_log::test]
+fn test_fact_of_a_proof_is_the_one_of_its_program_and_output() {
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+    let public_input = PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5);
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+
+    // The program has no output.
+    let fact = fact_of_proof(&output.proof, &cairo_air, &output.public_values).unwrap();
+    assert_eq!(fact, fact_hash(&public_input.program_hash(), &[]));
+}
+
+#[test_log::test]
+fn test_gps_statement_of_a_batch_has_the_facts_of_its_proofs() {
+    let (cairo_air, raw_trace, public_input) = simple_program_with_public_page(3);
+    let output = prove(&raw_trace, &cairo_air, &public_input).unwrap();
+    let proof = (&output.proof, &cairo_air, &output.public_values);
+
+    let statement = GpsStatement::from_proofs([proof, proof]).unwrap();
+    assert_eq!(statement.tasks.len(), 2);
+    let fact = fact_of_proof(&output.proof, &cairo_air, &output.public_values).unwrap();
+    assert!(statement.tasks.iter().all(|task| task.fact == fact));
+    assert_eq!(statement.bootloader_output()[0], FE::from(2));
+
+    let mut public_values = output.public_values.clone();
+    public_values.program[0] = &public_values.program[0] + FE::one();
+    assert!(
+        GpsStatement::from_proofs([proof, (&output.proof, &cairo_air, &public_values)]).is_err()
+    );
+}
+
+#[cfg(feature = "annotations")]
+#[test_log::test]
+fn test_annotations_of_a_cairo_proof_differ_from_the_round_that_changed() {
+    use lambdaworks_stark::verifier::annotations::{annotate_cairo, to_annotation_file};
+
+    let (raw_trace, memory) = load_cairo_trace_and_memory("simple_program");
+    let public_input = PublicInputs::from_regs_and_mem(&raw_trace, &memory, 5);
+    let cairo_air = CairoAIR::for_public_input(builtin_proof_options(), &public_input).unwrap();
+    let output = prove(&(raw_trace, memory), &cairo_air, &public_input).unwrap();
+
+    let annotations = annotate_cairo(&output.proof, &cairo_air, &output.public_values).unwrap();
+    let file = to_annotation_file(&annotations);
+    assert!(
+        file.starts_with("P->V: /cpu air/STARK/Original/Commit on Trace: Commitment #0: Hash(0x")
+    );
+    assert_eq!(file.lines().count(), annotations.len());
+
+    let mut proof = output.proof.clone();
+    proof.fri_last_value = &proof.fri_last_value + FE::one();
+    let changed = annotate_cairo(&proof, &cairo_air, &output.public_values).unwrap();
+    let first_difference = annotations
+        .iter()
+        .zip(&changed)
+        .position(|(annotation, other)| annotation != other)
+        .unwrap();
+    assert_eq!(
+        changed[first_difference].scope,
+        "/cpu air/STARK/FRI/Commitment/Last Layer"
+    );
 }
@@ -0,0 +1,23 @@
+//! Tests of the JavaScript bindings, run in Node with `make test_wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use lambdaworks_stark::wasm::{prove, verify};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const OPTIONS: &str = r#"{"blowup_factor": 4, "fri_number_of_queries": 3, "coset_offset": 3}"#;
+
+#[wasm_bindgen_test]
+fn proof_of_a_cairo_program_verifies() {
+    let trace = include_bytes!("../src/cairo_vm/test_data/simple_program.trace");
+    let memory = include_bytes!("../src/cairo_vm/test_data/simple_program.memory");
+
+    let mut output = prove(trace, memory, 5, OPTIONS)
+        .ok()
+        .expect("the execution is proved");
+    let public_input = output.public_input();
+    let proof = output.take_proof();
+    assert!(output.take_proof().is_empty());
+
+    assert!(verify(&proof, &public_input, OPTIONS).ok().unwrap());
+    assert!(!verify(&proof[1..], &public_input, OPTIONS).ok().unwrap());
+}